@@ -0,0 +1,157 @@
+use std::{
+    any::Any,
+    backtrace::Backtrace,
+    collections::VecDeque,
+    fmt::Write as _,
+    fs,
+    panic::{self, PanicHookInfo},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::error;
+
+// Number of drawn frames that are kept for the crash report
+const RENDER_HISTORY: usize = 16;
+
+/// Name of the thread that runs the update loop
+pub(crate) const UPDATE_THREAD: &str = "helium-update";
+
+// The threads that crash reports are written for, the renderer runs on the main thread.
+// Panics on other threads, like background tasks and model loading, are left to the
+// default hook since the report would describe what the update thread is doing
+const REPORTED_THREADS: [&str; 2] = [UPDATE_THREAD, "main"];
+
+// What the engine was doing, kept up to date so the panic hook can report it
+struct CrashState {
+    frame: u64,
+    system: Option<&'static str>,
+    gpu: String,
+    renders: VecDeque<String>,
+}
+
+static CRASH_STATE: Mutex<CrashState> = Mutex::new(CrashState {
+    frame: 0,
+    system: None,
+    gpu: String::new(),
+    renders: VecDeque::new(),
+});
+
+// Runs a change on the state, a poisoned state is still used since it only holds text
+fn with_state<F>(change: F)
+where
+    F: FnOnce(&mut CrashState),
+{
+    let mut state = CRASH_STATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    change(&mut state);
+}
+
+/// Counts a new update so crash reports have the frame the panic happened in
+pub(crate) fn begin_frame() {
+    with_state(|state| {
+        state.frame += 1;
+        state.system = None;
+    });
+}
+
+/// Sets the system or stage of the update that is running
+pub(crate) fn set_system(system: Option<&'static str>) {
+    with_state(|state| state.system = system);
+}
+
+/// Sets the description of the gpu that is added to crash reports
+pub(crate) fn set_gpu(gpu: String) {
+    with_state(|state| state.gpu = gpu);
+}
+
+/// Adds a summary of a drawn frame, the latest ones are added to crash reports
+pub(crate) fn record_render(summary: String) {
+    with_state(|state| {
+        if state.renders.len() == RENDER_HISTORY {
+            state.renders.pop_front();
+        }
+        state.renders.push_back(summary);
+    });
+}
+
+// Writes the report of a panic from the state and the panic
+fn write_report(info: &PanicHookInfo, directory: &Path) -> Result<PathBuf, std::io::Error> {
+    let mut report = String::new();
+    let thread = thread::current();
+
+    _ = writeln!(report, "Helium crash report");
+    _ = writeln!(report, "Thread: {}", thread.name().unwrap_or("unnamed"));
+    _ = writeln!(report, "Panic: {info}");
+
+    with_state(|state| {
+        _ = writeln!(report, "Frame: {}", state.frame);
+        _ = writeln!(report, "System: {}", state.system.unwrap_or("none"));
+        _ = writeln!(report, "Gpu: {}", state.gpu);
+        _ = writeln!(report, "\nRecent frames:");
+        for render in state.renders.iter() {
+            _ = writeln!(report, "  {render}");
+        }
+    });
+
+    _ = writeln!(report, "\nBacktrace:\n{}", Backtrace::force_capture());
+
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default();
+
+    fs::create_dir_all(directory)?;
+    let path = directory.join(format!("crash-{time}.txt"));
+    fs::write(&path, report)?;
+
+    Ok(path)
+}
+
+/// Writes a crash report into a directory whenever the update thread or the renderer
+/// panics, the report has the frame and system that were running, the gpu, and the
+/// latest drawn frames
+pub(crate) fn install_panic_hook(directory: PathBuf) {
+    let default_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+
+        if !thread::current()
+            .name()
+            .is_some_and(|name| REPORTED_THREADS.contains(&name))
+        {
+            return;
+        }
+
+        match write_report(info, &directory) {
+            Ok(path) => error!("Wrote the crash report to {path:?}"),
+            Err(e) => error!("Failed to write the crash report: {e}"),
+        }
+    }));
+}
+
+/// What the engine does when the update thread panics
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UpdatePanicPolicy {
+    /// Closes the window and stops the engine
+    #[default]
+    Exit,
+    /// Keeps the window open with the panic message drawn over the last frame
+    ShowError,
+    /// Clears the scene and starts the update thread again, which runs the startup
+    /// functions again. The error is shown after a few restarts that keep panicking
+    Restart,
+}
+
+/// Gives the message of a panic from what the thread panicked with
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("unknown panic"))
+}
@@ -0,0 +1,242 @@
+use cgmath::InnerSpace;
+use helium_renderer::BoundingSphere;
+use winit::{
+    event::{DeviceEvent, ElementState, MouseScrollDelta, RawKeyEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::helium_compatibility::Camera3d;
+
+// Distance of one line of the mouse wheel for touchpads that scroll in pixels
+const PIXELS_PER_LINE: f32 = 40.0;
+
+/// Settings of the fly camera for looking around the scene while editing it. The editor
+/// camera flies on its own and leaves the cameras of the game where they are
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EditorCamera {
+    toggle_key: KeyCode,
+    focus_key: KeyCode,
+    speed: f32,
+    speed_range: (f32, f32),
+    scroll_factor: f32,
+}
+
+impl Default for EditorCamera {
+    /// Toggled with F1, moves at 10 units a second, and focuses the selection with F
+    fn default() -> Self {
+        Self {
+            toggle_key: KeyCode::F1,
+            focus_key: KeyCode::KeyF,
+            speed: 10.0,
+            speed_range: (0.1, 1000.0),
+            scroll_factor: 1.25,
+        }
+    }
+}
+
+impl EditorCamera {
+    /// Sets the key that turns the editor camera on and off
+    pub fn with_toggle_key(mut self, toggle_key: KeyCode) -> Self {
+        self.toggle_key = toggle_key;
+        self
+    }
+
+    /// Sets the key that moves the camera to the selected entity
+    pub fn with_focus_key(mut self, focus_key: KeyCode) -> Self {
+        self.focus_key = focus_key;
+        self
+    }
+
+    /// Sets how many units a second the camera moves when it is turned on
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed.max(f32::EPSILON);
+        self
+    }
+
+    /// Sets the slowest and fastest speed the mouse wheel can change the speed to
+    pub fn with_speed_range(mut self, min_speed: f32, max_speed: f32) -> Self {
+        let min_speed = min_speed.max(f32::EPSILON);
+        self.speed_range = (min_speed, max_speed.max(min_speed));
+        self
+    }
+
+    /// Sets what the speed is multiplied by for every line the mouse wheel scrolls up
+    pub fn with_scroll_factor(mut self, scroll_factor: f32) -> Self {
+        self.scroll_factor = scroll_factor.max(1.0);
+        self
+    }
+
+    pub fn get_toggle_key(&self) -> KeyCode {
+        self.toggle_key
+    }
+
+    pub fn get_focus_key(&self) -> KeyCode {
+        self.focus_key
+    }
+
+    pub fn get_speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn get_speed_range(&self) -> (f32, f32) {
+        self.speed_range
+    }
+
+    pub fn get_scroll_factor(&self) -> f32 {
+        self.scroll_factor
+    }
+}
+
+// Keys that move the camera, in the order of the directions of `EditorCameraState::movement`
+const MOVEMENT_KEYS: [KeyCode; 6] = [
+    KeyCode::KeyW,
+    KeyCode::KeyS,
+    KeyCode::KeyA,
+    KeyCode::KeyD,
+    KeyCode::KeyQ,
+    KeyCode::KeyE,
+];
+
+/// The editor camera with the input it has been given since the last update
+pub(crate) struct EditorCameraState {
+    settings: EditorCamera,
+    // The camera that is flown, `None` while the editor camera is off
+    camera: Option<Camera3d>,
+    speed: f32,
+    // Forward, backward, left, right, down, and up
+    movement: [bool; 6],
+    scroll: f32,
+    toggle: bool,
+    focus: bool,
+}
+
+impl EditorCameraState {
+    pub fn new(settings: EditorCamera) -> Self {
+        Self {
+            settings,
+            camera: None,
+            speed: settings.speed,
+            movement: [false; 6],
+            scroll: 0.0,
+            toggle: false,
+            focus: false,
+        }
+    }
+
+    pub fn get_settings(&self) -> EditorCamera {
+        self.settings
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.camera.is_some()
+    }
+
+    pub fn get_camera(&self) -> Option<&Camera3d> {
+        self.camera.as_ref()
+    }
+
+    /// Starts flying from where a camera of the game is
+    pub fn activate(&mut self, camera: Camera3d) {
+        self.camera = Some(camera);
+        self.movement = [false; 6];
+        self.scroll = 0.0;
+        self.focus = false;
+    }
+
+    pub fn deactivate(&mut self) {
+        self.camera = None;
+    }
+
+    /// Keeps the keys and scrolling of an event for the next update
+    pub fn process_event(&mut self, event: &DeviceEvent) {
+        match event {
+            DeviceEvent::Key(RawKeyEvent {
+                physical_key: PhysicalKey::Code(keycode),
+                state,
+            }) => {
+                let is_pressed = *state == ElementState::Pressed;
+
+                if *keycode == self.settings.toggle_key {
+                    self.toggle |= is_pressed;
+                } else if *keycode == self.settings.focus_key {
+                    self.focus |= is_pressed && self.is_active();
+                }
+
+                if let Some(direction) = MOVEMENT_KEYS.iter().position(|key| key == keycode) {
+                    self.movement[direction] = is_pressed;
+                }
+            }
+            DeviceEvent::MouseWheel { delta } if self.is_active() => {
+                self.scroll += match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// Takes if the toggle key was pressed since the last update
+    pub fn take_toggle(&mut self) -> bool {
+        std::mem::take(&mut self.toggle)
+    }
+
+    /// Takes if the focus key was pressed since the last update
+    pub fn take_focus(&mut self) -> bool {
+        std::mem::take(&mut self.focus)
+    }
+
+    /// Moves the camera back so a sphere fills the view
+    pub fn focus(&mut self, sphere: BoundingSphere) {
+        if let Some(camera) = self.camera.as_mut() {
+            camera.frame(sphere);
+        }
+    }
+
+    /// Moves and turns the camera for an update
+    ///
+    /// # Arguments
+    ///
+    /// * `look` - The mouse motion to turn the camera by, `None` while it is not looking
+    /// * `delta_time` - The seconds since the last update
+    /// * `aspect` - The aspect ratio of the window
+    ///
+    /// # Returns
+    ///
+    /// The camera to draw the scene with
+    pub fn update(
+        &mut self,
+        look: Option<(f32, f32)>,
+        delta_time: f32,
+        aspect: f32,
+    ) -> Option<Camera3d> {
+        let camera = self.camera.as_mut()?;
+        let (min_speed, max_speed) = self.settings.speed_range;
+        self.speed = (self.speed * self.settings.scroll_factor.powf(self.scroll))
+            .clamp(min_speed, max_speed);
+        self.scroll = 0.0;
+
+        if let Some((x, y)) = look {
+            camera.add_yaw(-x);
+            camera.add_pitch(-y);
+        }
+
+        let forward = camera.target.normalize();
+        let right = forward.cross(camera.up).normalize();
+        let up = camera.up.normalize();
+        let directions = [forward, -forward, -right, right, -up, up];
+
+        let step = self.speed * delta_time;
+        for (direction, moving) in directions.iter().zip(self.movement) {
+            if moving {
+                camera.eye += direction * step;
+            }
+        }
+
+        if camera.aspect != aspect {
+            camera.set_aspect(aspect);
+        }
+
+        Some(*camera)
+    }
+}
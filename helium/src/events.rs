@@ -0,0 +1,76 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+// The events of one type, events that are sent during a frame are read during the next
+trait EventQueue {
+    fn swap(&mut self);
+
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct Queue<E> {
+    // Events that can be read this frame
+    current: Vec<E>,
+    // Events sent this frame
+    pending: Vec<E>,
+}
+
+impl<E: 'static> EventQueue for Queue<E> {
+    fn swap(&mut self) {
+        self.current = std::mem::take(&mut self.pending);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self as &dyn Any
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self as &mut dyn Any
+    }
+}
+
+/// Typed events that systems send to each other, every event is readable for the whole
+/// frame after it was sent so every reader sees it once
+#[derive(Default)]
+pub struct Events {
+    queues: HashMap<TypeId, Box<dyn EventQueue>>,
+}
+
+impl Events {
+    /// Sends an event that can be read during the next frame
+    pub fn send<E: 'static>(&mut self, event: E) {
+        self.queues
+            .entry(TypeId::of::<E>())
+            .or_insert_with(|| {
+                Box::new(Queue::<E> {
+                    current: Vec::new(),
+                    pending: Vec::new(),
+                })
+            })
+            .as_any_mut()
+            .downcast_mut::<Queue<E>>()
+            .unwrap()
+            .pending
+            .push(event);
+    }
+
+    /// Gives the events of a type that were sent during the last frame
+    pub fn read<E: 'static>(&self) -> &[E] {
+        self.queues
+            .get(&TypeId::of::<E>())
+            .and_then(|queue| queue.as_any().downcast_ref::<Queue<E>>())
+            .map(|queue| queue.current.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Makes the events sent during the last frame readable and drops the older ones,
+    /// called once at the start of every frame
+    pub fn update(&mut self) {
+        for queue in self.queues.values_mut() {
+            queue.swap();
+        }
+    }
+}
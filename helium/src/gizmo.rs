@@ -0,0 +1,513 @@
+use std::f32::consts::{PI, TAU};
+
+use cgmath::{Deg, Quaternion, Rad, Rotation, Rotation3, Vector3};
+use helium_renderer::{DebugLine, HeliumState, LinearRgba, ScreenPoint, Srgba};
+
+use crate::helium_compatibility::Transform3d;
+
+// Distance in pixels the cursor can be from a handle and still grab it
+const GRAB_DISTANCE: f32 = 8.0;
+
+// Number of line segments in each rotation ring
+const RING_SEGMENTS: usize = 32;
+
+// Smallest scale the scale handles can shrink an axis to
+const MIN_SCALE: f32 = 0.001;
+
+// Size of the boxes at the ends of the scale handles as a fraction of the handle length
+const SCALE_BOX_SIZE: f32 = 0.08;
+
+/// What dragging the handles of the gizmo changes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GizmoMode {
+    /// Arrows along the world axes that move the entity
+    #[default]
+    Translate,
+    /// Rings around the world axes that turn the entity
+    Rotate,
+    /// Handles along the axes of the entity that stretch it
+    Scale,
+}
+
+/// Settings of the handles that are drawn on the selected entity to move, turn, and
+/// scale it with the mouse
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gizmo {
+    mode: GizmoMode,
+    size: f32,
+    translate_snap: Option<f32>,
+    rotate_snap: Option<f32>,
+    scale_snap: Option<f32>,
+    click_select: bool,
+}
+
+impl Default for Gizmo {
+    /// Moves the entity without snapping and selects what is clicked
+    fn default() -> Self {
+        Self {
+            mode: GizmoMode::Translate,
+            size: 0.15,
+            translate_snap: None,
+            rotate_snap: None,
+            scale_snap: None,
+            click_select: true,
+        }
+    }
+}
+
+impl Gizmo {
+    pub fn with_mode(mut self, mode: GizmoMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the length of the handles as a fraction of their distance to the camera, so
+    /// the gizmo stays the same size on the screen
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = size.max(f32::EPSILON);
+        self
+    }
+
+    /// Moves the entity in steps of a distance, `None` moves it freely
+    pub fn with_translate_snap(mut self, snap: Option<f32>) -> Self {
+        self.translate_snap = snap.filter(|snap| *snap > 0.0);
+        self
+    }
+
+    /// Turns the entity in steps of an angle in degrees, `None` turns it freely
+    pub fn with_rotate_snap(mut self, snap: Option<f32>) -> Self {
+        self.rotate_snap = snap.filter(|snap| *snap > 0.0);
+        self
+    }
+
+    /// Scales the entity in steps of a size, `None` scales it freely
+    pub fn with_scale_snap(mut self, snap: Option<f32>) -> Self {
+        self.scale_snap = snap.filter(|snap| *snap > 0.0);
+        self
+    }
+
+    /// Sets if clicking away from the handles selects the model under the cursor with
+    /// gpu picking, clicking nothing clears the selection
+    pub fn with_click_select(mut self, click_select: bool) -> Self {
+        self.click_select = click_select;
+        self
+    }
+
+    pub fn get_mode(&self) -> GizmoMode {
+        self.mode
+    }
+
+    pub fn get_size(&self) -> f32 {
+        self.size
+    }
+
+    pub fn get_translate_snap(&self) -> Option<f32> {
+        self.translate_snap
+    }
+
+    pub fn get_rotate_snap(&self) -> Option<f32> {
+        self.rotate_snap
+    }
+
+    pub fn get_scale_snap(&self) -> Option<f32> {
+        self.scale_snap
+    }
+
+    pub fn get_click_select(&self) -> bool {
+        self.click_select
+    }
+}
+
+// Rounds a value to the nearest step when there is one
+fn snap(value: f32, step: Option<f32>) -> f32 {
+    match step {
+        Some(step) => (value / step).round() * step,
+        None => value,
+    }
+}
+
+fn sub(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn dot(a: (f32, f32), b: (f32, f32)) -> f32 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+// Distance from a point to a line segment on the screen
+fn segment_distance(point: (f32, f32), start: (f32, f32), end: (f32, f32)) -> f32 {
+    let segment = sub(end, start);
+    let length = dot(segment, segment);
+    let amount = if length > 0.0 {
+        (dot(sub(point, start), segment) / length).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    let offset = sub(
+        point,
+        (start.0 + segment.0 * amount, start.1 + segment.1 * amount),
+    );
+    dot(offset, offset).sqrt()
+}
+
+// Turns an angle into the range from -PI to PI
+fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
+// Angle of a point around a center on the screen
+fn screen_angle(point: (f32, f32), center: (f32, f32)) -> f32 {
+    let offset = sub(point, center);
+    offset.1.atan2(offset.0)
+}
+
+/// A handle of the gizmo in the world
+struct Handle {
+    axis: Vector3<f32>,
+    // The points of the handle in the world, two for a line and a loop for a ring
+    points: Vec<Vector3<f32>>,
+}
+
+// What a drag started from
+struct GizmoDrag {
+    handle: usize,
+    cursor: (f32, f32),
+    start: Transform3d,
+    axis: Vector3<f32>,
+    // The world length of the handle and the handle on the screen when the drag started
+    length: f32,
+    screen_axis: (f32, f32),
+    // The center on the screen, the angle of the cursor last update, and the angle turned
+    center: (f32, f32),
+    angle: f32,
+    turned: f32,
+    // If the axis pointed towards the camera, the rings turn the other way on the screen
+    facing: bool,
+}
+
+/// The gizmo with the handle that is grabbed
+pub(crate) struct GizmoState {
+    settings: Gizmo,
+    hovered: Option<usize>,
+    drag: Option<GizmoDrag>,
+    was_pressed: bool,
+}
+
+/// What the manager does after the gizmo has seen the cursor
+pub(crate) enum GizmoAction {
+    None,
+    /// The transform of the selected entity was dragged
+    Transform(Transform3d),
+    /// The handle was let go, with the transform from before the drag
+    Release(Transform3d),
+    /// The cursor clicked away from the handles at a pixel
+    Select(u32, u32),
+}
+
+impl GizmoState {
+    pub fn new(settings: Gizmo) -> Self {
+        Self {
+            settings,
+            hovered: None,
+            drag: None,
+            was_pressed: false,
+        }
+    }
+
+    pub fn get_settings(&self) -> Gizmo {
+        self.settings
+    }
+
+    /// Changes the mode, a drag that is going on is dropped
+    pub fn set_mode(&mut self, mode: GizmoMode) {
+        self.settings.mode = mode;
+        self.drag = None;
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    // The handles of the mode around a transform
+    fn handles(&self, transform: &Transform3d, length: f32) -> Vec<Handle> {
+        let center = *transform.get_position();
+        let axes = match self.settings.mode {
+            GizmoMode::Translate | GizmoMode::Rotate => {
+                [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()]
+            }
+            GizmoMode::Scale => [transform.right(), transform.up(), -transform.forward()],
+        };
+
+        (0..3)
+            .map(|index| {
+                let axis = axes[index];
+                let points = match self.settings.mode {
+                    GizmoMode::Rotate => {
+                        // The ring is in the plane of the other two axes
+                        let (u, v) = (axes[(index + 1) % 3], axes[(index + 2) % 3]);
+                        (0..=RING_SEGMENTS)
+                            .map(|segment| {
+                                let angle = segment as f32 / RING_SEGMENTS as f32 * TAU;
+                                center + (u * angle.cos() + v * angle.sin()) * length
+                            })
+                            .collect()
+                    }
+                    _ => vec![center, center + axis * length],
+                };
+
+                Handle { axis, points }
+            })
+            .collect()
+    }
+
+    /// Grabs, drags, and lets go of the handles with the cursor and draws the gizmo
+    ///
+    /// # Arguments
+    ///
+    /// * `transform` - The transform of the selected entity, `None` when nothing is selected
+    /// * `cursor` - The position of the cursor in pixels and if the left button is held
+    /// * `renderer` - The renderer to project the handles with and draw them into
+    ///
+    /// # Returns
+    ///
+    /// What the manager has to do with the selected entity
+    pub fn update(
+        &mut self,
+        transform: Option<Transform3d>,
+        cursor: ((f32, f32), bool),
+        renderer: &mut HeliumState,
+    ) -> GizmoAction {
+        let (position, pressed) = cursor;
+        let clicked = pressed && !self.was_pressed;
+        self.was_pressed = pressed;
+
+        let Some(transform) = transform else {
+            self.drag = None;
+            self.hovered = None;
+            if clicked && self.settings.click_select {
+                return GizmoAction::Select(position.0 as u32, position.1 as u32);
+            }
+            return GizmoAction::None;
+        };
+
+        let Some(center) = renderer.world_to_screen(*transform.get_position()) else {
+            self.drag = None;
+            return GizmoAction::None;
+        };
+
+        let action = match self.drag.as_mut() {
+            Some(_) if !pressed => {
+                let drag = self.drag.take().unwrap();
+                GizmoAction::Release(drag.start)
+            }
+            Some(drag) => GizmoAction::Transform(Self::dragged(&self.settings, drag, position)),
+            None => {
+                let length = self.settings.size * center.distance;
+                let handles = self.handles(&transform, length);
+                self.hovered = Self::closest_handle(&handles, position, renderer);
+
+                match (clicked, self.hovered) {
+                    (true, Some(handle)) => {
+                        self.drag = Some(Self::start_drag(
+                            &handles[handle],
+                            handle,
+                            transform,
+                            length,
+                            position,
+                            &center,
+                            renderer,
+                        ));
+                        GizmoAction::None
+                    }
+                    (true, None) if self.settings.click_select => {
+                        GizmoAction::Select(position.0 as u32, position.1 as u32)
+                    }
+                    _ => GizmoAction::None,
+                }
+            }
+        };
+
+        let drawn = match &action {
+            GizmoAction::Transform(transform) => *transform,
+            _ => transform,
+        };
+        self.draw(&drawn, self.settings.size * center.distance, renderer);
+
+        action
+    }
+
+    // The handle nearest the cursor that is close enough to grab
+    fn closest_handle(
+        handles: &[Handle],
+        cursor: (f32, f32),
+        renderer: &HeliumState,
+    ) -> Option<usize> {
+        handles
+            .iter()
+            .enumerate()
+            .filter_map(|(index, handle)| {
+                let points: Vec<ScreenPoint> = handle
+                    .points
+                    .iter()
+                    .filter_map(|point| renderer.world_to_screen(*point))
+                    .collect();
+
+                points
+                    .windows(2)
+                    .map(|pair| segment_distance(cursor, pair[0].position, pair[1].position))
+                    .reduce(f32::min)
+                    .map(|distance| (index, distance))
+            })
+            .filter(|(_, distance)| *distance <= GRAB_DISTANCE)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    }
+
+    fn start_drag(
+        handle: &Handle,
+        index: usize,
+        start: Transform3d,
+        length: f32,
+        cursor: (f32, f32),
+        center: &ScreenPoint,
+        renderer: &HeliumState,
+    ) -> GizmoDrag {
+        let tip = renderer.world_to_screen(start.get_position() + handle.axis * length);
+
+        GizmoDrag {
+            handle: index,
+            cursor,
+            start,
+            axis: handle.axis,
+            length,
+            screen_axis: tip.map_or((0.0, 0.0), |tip| sub(tip.position, center.position)),
+            center: center.position,
+            angle: screen_angle(cursor, center.position),
+            turned: 0.0,
+            facing: tip.is_some_and(|tip| tip.depth < center.depth),
+        }
+    }
+
+    // The transform after the handle is dragged to the cursor
+    fn dragged(settings: &Gizmo, drag: &mut GizmoDrag, cursor: (f32, f32)) -> Transform3d {
+        let mut transform = drag.start;
+
+        // How far along the handle on the screen the cursor moved, 1 is the handle length
+        let along = || {
+            let length = dot(drag.screen_axis, drag.screen_axis);
+            if length > 0.0 {
+                dot(sub(cursor, drag.cursor), drag.screen_axis) / length
+            } else {
+                0.0
+            }
+        };
+
+        match settings.mode {
+            GizmoMode::Translate => {
+                let distance = snap(along() * drag.length, settings.translate_snap);
+                transform.update_position(drag.start.get_position() + drag.axis * distance);
+            }
+            GizmoMode::Scale => {
+                let factor = 1.0 + along();
+                let mut scale = *drag.start.get_scale();
+                scale[drag.handle] =
+                    snap(scale[drag.handle] * factor, settings.scale_snap).max(MIN_SCALE);
+                transform.set_scale(scale);
+            }
+            GizmoMode::Rotate => {
+                // The angle is added up every update so the ring can be turned past half a turn
+                let angle = screen_angle(cursor, drag.center);
+                drag.turned += wrap_angle(angle - drag.angle);
+                drag.angle = angle;
+
+                // Angles on the screen go clockwise since y points down
+                let turned = if drag.facing {
+                    -drag.turned
+                } else {
+                    drag.turned
+                };
+                let degrees = snap(Deg::from(Rad(turned)).0, settings.rotate_snap);
+                let rotation = Quaternion::from_axis_angle(drag.axis, Deg(degrees));
+                transform.update_rotation(rotation * drag.start.get_rotation());
+            }
+        }
+
+        transform
+    }
+
+    // Draws the handles in the colors of their axes, the grabbed handle is highlighted
+    fn draw(&self, transform: &Transform3d, length: f32, renderer: &mut HeliumState) {
+        let colors = [
+            Srgba::rgb(0.9, 0.2, 0.2),
+            Srgba::rgb(0.2, 0.8, 0.2),
+            Srgba::rgb(0.2, 0.4, 0.9),
+        ];
+        let active = self.drag.as_ref().map(|drag| drag.handle).or(self.hovered);
+
+        for (index, handle) in self.handles(transform, length).into_iter().enumerate() {
+            let color = if active == Some(index) {
+                LinearRgba::from(Srgba::rgb(1.0, 0.85, 0.1))
+            } else {
+                LinearRgba::from(colors[index])
+            }
+            .to_array();
+
+            match self.settings.mode {
+                GizmoMode::Translate => {
+                    renderer.debug_arrow(handle.points[0], handle.points[1], color)
+                }
+                GizmoMode::Rotate => {
+                    for pair in handle.points.windows(2) {
+                        renderer.debug_line(DebugLine {
+                            start: pair[0],
+                            end: pair[1],
+                            color,
+                        });
+                    }
+                }
+                GizmoMode::Scale => {
+                    renderer.debug_line(DebugLine {
+                        start: handle.points[0],
+                        end: handle.points[1],
+                        color,
+                    });
+                    let rotation = *transform.get_rotation();
+                    let half = length * SCALE_BOX_SIZE / 2.0;
+                    Self::draw_box(handle.points[1], rotation, half, color, renderer);
+                }
+            }
+        }
+    }
+
+    // Draws the edges of a small box turned with the entity
+    fn draw_box(
+        center: Vector3<f32>,
+        rotation: Quaternion<f32>,
+        half: f32,
+        color: [f32; 4],
+        renderer: &mut HeliumState,
+    ) {
+        let corner = |index: usize| {
+            let signs = Vector3::new(
+                if index & 1 == 0 { -1.0 } else { 1.0 },
+                if index & 2 == 0 { -1.0 } else { 1.0 },
+                if index & 4 == 0 { -1.0 } else { 1.0 },
+            );
+            center + rotation.rotate_vector(signs * half)
+        };
+
+        // Every pair of corners that differ in one axis is an edge
+        for a in 0..8 {
+            for bit in [1, 2, 4] {
+                if a & bit == 0 {
+                    renderer.debug_line(DebugLine {
+                        start: corner(a),
+                        end: corner(a | bit),
+                        color,
+                    });
+                }
+            }
+        }
+    }
+}
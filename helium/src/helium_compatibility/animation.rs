@@ -0,0 +1,377 @@
+use cgmath::{InnerSpace, Quaternion, Vector3, VectorSpace};
+use helium_ecs::Entity;
+
+/// Sent when the playback of an `AnimationPlayer` crosses an event of its clip
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationEvent {
+    pub entity: Entity,
+    /// The name the event was added to the clip with
+    pub name: String,
+    /// The time of the event in the clip in seconds
+    pub time: f32,
+}
+
+// Inserts a key keeping the keys in order of time
+fn insert_key<T>(keys: &mut Vec<(f32, T)>, time: f32, value: T) {
+    let index = keys.partition_point(|(key_time, _)| *key_time <= time);
+    keys.insert(index, (time, value));
+}
+
+// Finds the keys on either side of a time and how far the time is between them
+fn find_keys<T: Copy>(keys: &[(f32, T)], time: f32) -> Option<(T, T, f32)> {
+    let (first, last) = (keys.first()?, keys.last()?);
+    if time <= first.0 {
+        return Some((first.1, first.1, 0.0));
+    }
+    if time >= last.0 {
+        return Some((last.1, last.1, 0.0));
+    }
+
+    let next = keys.partition_point(|(key_time, _)| *key_time <= time);
+    let (start, end) = (keys[next - 1], keys[next]);
+    let span = end.0 - start.0;
+    let amount = if span > 0.0 {
+        (time - start.0) / span
+    } else {
+        0.0
+    };
+
+    Some((start.1, end.1, amount))
+}
+
+/// A keyframed animation of the transform of an entity with named events at points in
+/// its timeline, the keys are blended linearly and the events are sent when the playback
+/// crosses them
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationClip {
+    duration: f32,
+    looping: bool,
+    position_keys: Vec<(f32, Vector3<f32>)>,
+    rotation_keys: Vec<(f32, Quaternion<f32>)>,
+    scale_keys: Vec<(f32, Vector3<f32>)>,
+    events: Vec<(f32, String)>,
+}
+
+impl AnimationClip {
+    /// Creates an empty clip that plays once
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - The length of the clip in seconds
+    pub fn new(duration: f32) -> Self {
+        Self {
+            duration: duration.max(0.0),
+            looping: false,
+            position_keys: Vec::new(),
+            rotation_keys: Vec::new(),
+            scale_keys: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Starts the clip over from the beginning when it reaches the end
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Adds a key for the position of the entity
+    pub fn with_position_key(mut self, time: f32, position: Vector3<f32>) -> Self {
+        let time = self.clamp_time(time);
+        insert_key(&mut self.position_keys, time, position);
+        self
+    }
+
+    /// Adds a key for the rotation of the entity
+    pub fn with_rotation_key(mut self, time: f32, rotation: Quaternion<f32>) -> Self {
+        let time = self.clamp_time(time);
+        insert_key(&mut self.rotation_keys, time, rotation.normalize());
+        self
+    }
+
+    /// Adds a key for the scale of the entity
+    pub fn with_scale_key(mut self, time: f32, scale: Vector3<f32>) -> Self {
+        let time = self.clamp_time(time);
+        insert_key(&mut self.scale_keys, time, scale);
+        self
+    }
+
+    /// Adds an event that is sent as an `AnimationEvent` when the playback crosses it
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - When the event happens in seconds from the start of the clip
+    /// * `name` - The name of the event, like "footstep" or "attack_hit"
+    pub fn with_event(mut self, time: f32, name: &str) -> Self {
+        let time = self.clamp_time(time);
+        insert_key(&mut self.events, time, name.to_string());
+        self
+    }
+
+    fn clamp_time(&self, time: f32) -> f32 {
+        time.clamp(0.0, self.duration)
+    }
+
+    pub fn get_duration(&self) -> f32 {
+        self.duration
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    /// The events of the clip with their times in order
+    pub fn get_events(&self) -> &[(f32, String)] {
+        &self.events
+    }
+
+    /// The position at a time of the clip, none when the clip has no position keys
+    pub fn sample_position(&self, time: f32) -> Option<Vector3<f32>> {
+        find_keys(&self.position_keys, time).map(|(start, end, amount)| start.lerp(end, amount))
+    }
+
+    /// The rotation at a time of the clip, none when the clip has no rotation keys
+    pub fn sample_rotation(&self, time: f32) -> Option<Quaternion<f32>> {
+        find_keys(&self.rotation_keys, time).map(|(start, end, amount)| {
+            // The shorter way around is taken
+            let end = if start.dot(end) < 0.0 { -end } else { end };
+            start.nlerp(end, amount)
+        })
+    }
+
+    /// The scale at a time of the clip, none when the clip has no scale keys
+    pub fn sample_scale(&self, time: f32) -> Option<Vector3<f32>> {
+        find_keys(&self.scale_keys, time).map(|(start, end, amount)| start.lerp(end, amount))
+    }
+}
+
+/// Plays an animation clip on the transform of its entity
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationPlayer {
+    clip: AnimationClip,
+    time: f32,
+    speed: f32,
+    playing: bool,
+}
+
+impl AnimationPlayer {
+    /// Creates a player that starts playing the clip from the beginning
+    pub fn new(clip: AnimationClip) -> Self {
+        Self {
+            clip,
+            time: 0.0,
+            speed: 1.0,
+            playing: true,
+        }
+    }
+
+    /// Sets how fast the clip plays, 1 is the speed it was made at
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.set_speed(speed);
+        self
+    }
+
+    /// Switches to another clip and plays it from the beginning
+    pub fn play_clip(&mut self, clip: AnimationClip) {
+        self.clip = clip;
+        self.time = 0.0;
+        self.playing = true;
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Jumps to a time of the clip without sending the events in between
+    pub fn seek(&mut self, time: f32) {
+        self.time = self.clip.clamp_time(time);
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.0);
+    }
+
+    pub fn get_speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn get_time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn get_clip(&self) -> &AnimationClip {
+        &self.clip
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Whether a clip that plays once has reached its end
+    pub fn is_finished(&self) -> bool {
+        !self.clip.looping && self.time >= self.clip.duration
+    }
+
+    /// Moves the playback forward
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - The seconds that passed
+    ///
+    /// # Returns
+    ///
+    /// The events that were crossed in the order they happened with their times
+    pub fn advance(&mut self, delta_time: f32) -> Vec<(f32, String)> {
+        let mut crossed = Vec::new();
+        let mut remaining = delta_time * self.speed;
+        if !self.playing || self.is_finished() || remaining <= 0.0 {
+            return crossed;
+        }
+
+        let duration = self.clip.duration;
+        let mut start = self.time;
+        // Events at the start of the clip are sent as soon as it starts
+        let mut include_start = start == 0.0;
+
+        loop {
+            let end = start + remaining;
+            let reaches_end = end >= duration;
+            let end = end.min(duration);
+
+            crossed.extend(
+                self.clip
+                    .events
+                    .iter()
+                    // An event right where the playback stops is sent now, so it is
+                    // skipped by the next advance that starts there
+                    .filter(|(time, _)| {
+                        (*time > start || (include_start && *time == start)) && *time <= end
+                    })
+                    .cloned(),
+            );
+
+            if !reaches_end || !self.clip.looping || duration <= 0.0 {
+                self.time = end;
+                break;
+            }
+
+            // Long frames can run through a looping clip more than once
+            remaining = start + remaining - duration;
+            if remaining <= 0.0 {
+                // The events at the start of the next lap are sent by the next advance
+                self.time = 0.0;
+                break;
+            }
+            start = 0.0;
+            include_start = true;
+        }
+
+        crossed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(events: Vec<(f32, String)>) -> Vec<String> {
+        events.into_iter().map(|(_, name)| name).collect()
+    }
+
+    #[test]
+    fn test_start_event() {
+        let clip = AnimationClip::new(1.0).with_event(0.0, "start");
+        let mut player = AnimationPlayer::new(clip);
+
+        assert!(player.advance(0.0).is_empty());
+        assert_eq!(names(player.advance(0.25)), ["start"]);
+        assert!(player.advance(0.25).is_empty());
+    }
+
+    #[test]
+    fn test_end_event() {
+        let clip = AnimationClip::new(1.0)
+            .with_event(0.5, "middle")
+            .with_event(1.0, "end");
+        let mut player = AnimationPlayer::new(clip);
+
+        assert_eq!(names(player.advance(0.5)), ["middle"]);
+        assert_eq!(names(player.advance(2.0)), ["end"]);
+        assert!(player.is_finished());
+        assert_eq!(player.get_time(), 1.0);
+        assert!(player.advance(1.0).is_empty());
+
+        // Landing right on the end sends it too
+        player.seek(0.75);
+        assert!(!player.is_finished());
+        assert_eq!(names(player.advance(0.25)), ["end"]);
+    }
+
+    #[test]
+    fn test_looping_laps() {
+        let clip = AnimationClip::new(1.0)
+            .with_looping(true)
+            .with_event(0.0, "lap")
+            .with_event(0.75, "b")
+            .with_event(0.25, "a");
+        let mut player = AnimationPlayer::new(clip);
+
+        let events = player.advance(2.5);
+        assert_eq!(
+            events.iter().map(|(time, _)| *time).collect::<Vec<_>>(),
+            [0.0, 0.25, 0.75, 0.0, 0.25, 0.75, 0.0, 0.25]
+        );
+        assert_eq!(
+            names(events),
+            ["lap", "a", "b", "lap", "a", "b", "lap", "a"]
+        );
+        assert_eq!(player.get_time(), 0.5);
+        assert!(!player.is_finished());
+    }
+
+    #[test]
+    fn test_loop_ends_on_lap() {
+        let clip = AnimationClip::new(1.0)
+            .with_looping(true)
+            .with_event(0.0, "lap");
+        let mut player = AnimationPlayer::new(clip);
+
+        // A lap that ends right on the end of the clip sends the next start only once
+        assert_eq!(names(player.advance(0.5)), ["lap"]);
+        assert!(player.advance(0.5).is_empty());
+        assert_eq!(player.get_time(), 0.0);
+        assert_eq!(names(player.advance(0.5)), ["lap"]);
+    }
+
+    #[test]
+    fn test_seek_skips_events() {
+        let clip = AnimationClip::new(1.0)
+            .with_event(0.0, "start")
+            .with_event(0.25, "a")
+            .with_event(0.5, "b")
+            .with_event(0.75, "c");
+        let mut player = AnimationPlayer::new(clip);
+
+        player.seek(0.5);
+        assert_eq!(names(player.advance(0.5)), ["c"]);
+
+        player.seek(0.1);
+        assert_eq!(names(player.advance(0.2)), ["a"]);
+    }
+
+    #[test]
+    fn test_paused() {
+        let clip = AnimationClip::new(1.0).with_event(0.5, "a");
+        let mut player = AnimationPlayer::new(clip).with_speed(2.0);
+
+        player.pause();
+        assert!(player.advance(1.0).is_empty());
+        player.play();
+        assert_eq!(names(player.advance(0.25)), ["a"]);
+        assert_eq!(player.get_time(), 0.5);
+    }
+}
@@ -0,0 +1,22 @@
+/// Gives an entity a `RectangleCollider` the size of the bounds of its model, the collider
+/// is added once the model has finished loading and this component is removed
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AutoCollider {
+    padding: f32,
+}
+
+impl AutoCollider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Grows the collider by an amount on every side
+    pub fn with_padding(mut self, padding: f32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn get_padding(&self) -> f32 {
+        self.padding
+    }
+}
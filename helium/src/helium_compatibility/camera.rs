@@ -1,6 +1,7 @@
 use std::time::Instant;
 
-use cgmath::{InnerSpace, Point3, Quaternion, Rotation, Vector3};
+use cgmath::{EuclideanSpace, InnerSpace, Point3, Quaternion, Rotation, Vector3};
+use helium_renderer::BoundingSphere;
 use winit::{
     event::{DeviceEvent, ElementState, RawKeyEvent},
     keyboard::{KeyCode, PhysicalKey},
@@ -114,6 +115,28 @@ impl Camera3d {
         }
     }
 
+    /// Moves the camera back along the direction it is looking so a sphere fills the view
+    ///
+    /// # Arguments
+    ///
+    /// * `sphere` - The sphere in world space to fit in the view, like the bounds of a model
+    pub fn frame(&mut self, sphere: BoundingSphere) {
+        let half_fovy = self.fovy.to_radians() / 2.0;
+        let half_fovx = (half_fovy.tan() * self.aspect).atan();
+        let half_fov = half_fovy.min(half_fovx);
+
+        let distance = sphere.radius.max(f32::EPSILON) / half_fov.sin();
+        self.eye = Point3::from_vec(sphere.center - self.target.normalize() * distance);
+        self.update_flag = true;
+    }
+
+    /// Changes the width of the view divided by the height, the engine keeps this at the
+    /// aspect ratio of the window
+    pub fn set_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
+        self.update_flag = true;
+    }
+
     pub fn get_update_flag(&self) -> bool {
         self.update_flag
     }
@@ -123,6 +146,8 @@ impl Camera3d {
     }
 }
 
+/// Moves a camera with the keyboard and turns it with the mouse. The engine reads the
+/// mouse motion once every update, `delta` is extra turning that is added to it
 #[derive(Default, Debug)]
 pub struct CameraController {
     pub forward: bool,
@@ -133,33 +158,29 @@ pub struct CameraController {
 }
 
 impl CameraController {
+    /// Moves the camera with the keys in the event, mouse motion is added by the engine
     pub fn process_events(&mut self, event: &DeviceEvent) {
-        match event {
-            DeviceEvent::Key(RawKeyEvent {
-                physical_key: PhysicalKey::Code(keycode),
-                state,
-            }) => {
-                let is_pressed = *state == ElementState::Pressed;
-                match keycode {
-                    KeyCode::KeyW => {
-                        self.forward = is_pressed;
-                    }
-                    KeyCode::KeyS => {
-                        self.backward = is_pressed;
-                    }
-                    KeyCode::KeyA => {
-                        self.left = is_pressed;
-                    }
-                    KeyCode::KeyD => {
-                        self.right = is_pressed;
-                    }
-                    _ => {}
+        if let DeviceEvent::Key(RawKeyEvent {
+            physical_key: PhysicalKey::Code(keycode),
+            state,
+        }) = event
+        {
+            let is_pressed = *state == ElementState::Pressed;
+            match keycode {
+                KeyCode::KeyW => {
+                    self.forward = is_pressed;
                 }
+                KeyCode::KeyS => {
+                    self.backward = is_pressed;
+                }
+                KeyCode::KeyA => {
+                    self.left = is_pressed;
+                }
+                KeyCode::KeyD => {
+                    self.right = is_pressed;
+                }
+                _ => {}
             }
-            DeviceEvent::MouseMotion { delta } => {
-                self.delta = (delta.0 as f32, delta.1 as f32);
-            }
-            _ => {}
         }
     }
 }
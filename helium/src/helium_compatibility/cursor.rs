@@ -0,0 +1,10 @@
+/// The state of the mouse cursor in the window
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Cursor {
+    /// Position of the cursor in pixels from the top left of the window
+    pub position: (f32, f32),
+    /// If the left mouse button is held down
+    pub left_pressed: bool,
+    /// If the right mouse button is held down
+    pub right_pressed: bool,
+}
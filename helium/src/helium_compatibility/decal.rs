@@ -0,0 +1,104 @@
+use cgmath::{One, Quaternion, Vector3};
+use helium_renderer::{DecalProjector, DecalTexture};
+
+/// A texture projected onto the scene around the transform of an entity, like a bullet
+/// hole, a blood splat, or a road marking
+pub struct Decal {
+    texture: DecalTexture,
+    size: Vector3<f32>,
+    color: [f32; 4],
+    position: Vector3<f32>,
+    rotation: Quaternion<f32>,
+    renderer_index: Option<usize>,
+    update_flag: bool,
+}
+
+impl Decal {
+    /// Creates a new decal
+    ///
+    /// # Arguments
+    ///
+    /// * `texture` - The texture loaded with `HeliumManager::load_decal_texture`
+    /// * `size` - Size of the box the texture is projected through, the texture is
+    ///   projected down the local y axis so the y size is how deep the decal reaches
+    pub fn new(texture: DecalTexture, size: Vector3<f32>) -> Self {
+        Self {
+            texture,
+            size,
+            color: [1.0, 1.0, 1.0, 1.0],
+            position: Vector3::new(0.0, 0.0, 0.0),
+            rotation: Quaternion::one(),
+            renderer_index: None,
+            update_flag: false,
+        }
+    }
+
+    /// Sets the RGBA color multiplied into the texture
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    // Setters
+    pub fn set_texture(&mut self, texture: DecalTexture) {
+        self.texture = texture;
+        self.update_flag = true;
+    }
+
+    pub fn set_size(&mut self, size: Vector3<f32>) {
+        self.size = size;
+        self.update_flag = true;
+    }
+
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        self.color = color;
+        self.update_flag = true;
+    }
+
+    pub fn set_transform(&mut self, position: Vector3<f32>, rotation: Quaternion<f32>) {
+        self.position = position;
+        self.rotation = rotation;
+        self.update_flag = true;
+    }
+
+    pub fn set_renderer_index(&mut self, index: usize) {
+        self.renderer_index = Some(index);
+    }
+
+    pub fn update(&mut self) {
+        self.update_flag = false;
+    }
+
+    // Getters
+    pub fn get_texture(&self) -> DecalTexture {
+        self.texture
+    }
+
+    pub fn get_size(&self) -> Vector3<f32> {
+        self.size
+    }
+
+    pub fn get_color(&self) -> [f32; 4] {
+        self.color
+    }
+
+    pub fn get_renderer_index(&self) -> Option<&usize> {
+        self.renderer_index.as_ref()
+    }
+
+    pub fn get_update_flag(&self) -> bool {
+        self.update_flag
+    }
+}
+
+impl From<&Decal> for DecalProjector {
+    fn from(value: &Decal) -> Self {
+        Self {
+            texture: value.texture,
+            position: value.position,
+            rotation: value.rotation,
+            size: value.size,
+            color: value.color,
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::f32::consts::TAU;
+
+/// Makes the emission of an `Emissive` rise and fall over time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EmissivePulse {
+    /// Pulses per second
+    pub speed: f32,
+    /// The intensity at the dimmest point of the pulse
+    pub min_intensity: f32,
+}
+
+/// Scales how brightly the emissive material of an entity glows, used for pulsing lights,
+/// screens, and lava
+pub struct Emissive {
+    intensity: f32,
+    pulse: Option<EmissivePulse>,
+    // Seconds into the pulse
+    time: f32,
+    update_flag: bool,
+}
+
+impl Default for Emissive {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl Emissive {
+    /// Creates an emission for the model of an entity
+    ///
+    /// # Arguments
+    ///
+    /// * `intensity` - Multiplies the emissive color of the material, values above 1 bloom
+    pub fn new(intensity: f32) -> Self {
+        Self {
+            intensity,
+            pulse: None,
+            time: 0.0,
+            update_flag: true,
+        }
+    }
+
+    /// Makes the emission pulse between `min_intensity` and the intensity
+    ///
+    /// # Arguments
+    ///
+    /// * `speed` - Pulses per second
+    /// * `min_intensity` - The intensity at the dimmest point of the pulse
+    pub fn with_pulse(mut self, speed: f32, min_intensity: f32) -> Self {
+        self.pulse = Some(EmissivePulse {
+            speed,
+            min_intensity,
+        });
+        self
+    }
+
+    // Setters
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity;
+        self.update_flag = true;
+    }
+
+    pub fn set_pulse(&mut self, pulse: Option<EmissivePulse>) {
+        self.pulse = pulse;
+        self.time = 0.0;
+        self.update_flag = true;
+    }
+
+    /// Moves the pulse forward, this is called every frame by the engine
+    pub fn advance(&mut self, delta_time: f32) {
+        if let Some(pulse) = self.pulse.as_ref() {
+            // Wrapped to a whole number of pulses so the time keeps its precision
+            self.time = (self.time + delta_time) % (1.0 / pulse.speed.abs().max(1e-4));
+            self.update_flag = true;
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.update_flag = false;
+    }
+
+    // Getters
+    pub fn get_intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn get_pulse(&self) -> Option<EmissivePulse> {
+        self.pulse
+    }
+
+    /// The intensity at the current point of the pulse
+    pub fn get_current_intensity(&self) -> f32 {
+        match self.pulse.as_ref() {
+            Some(pulse) => {
+                let wave = 0.5 - 0.5 * (TAU * pulse.speed * self.time).cos();
+                pulse.min_intensity + (self.intensity - pulse.min_intensity) * wave
+            }
+            None => self.intensity,
+        }
+    }
+
+    pub fn get_update_flag(&self) -> bool {
+        self.update_flag
+    }
+}
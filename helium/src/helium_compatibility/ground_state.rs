@@ -0,0 +1,51 @@
+use cgmath::Vector3;
+use helium_ecs::Entity;
+
+// Surfaces with a normal that points up less than this are too steep to stand on
+pub const GROUND_MIN_NORMAL_Y: f32 = 0.7;
+
+/// Whether an entity is standing on something, kept up to date by the collision system
+/// for every entity with `Gravity` and a `RectangleCollider`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GroundState {
+    grounded: bool,
+    ground_normal: Option<Vector3<f32>>,
+    ground_entity: Option<Entity>,
+}
+
+impl GroundState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks the entity as standing on a surface
+    ///
+    /// # Arguments
+    ///
+    /// * `normal` - Normal of the surface the entity is standing on
+    /// * `entity` - The entity with the collider that is stood on
+    pub fn set_ground(&mut self, normal: Vector3<f32>, entity: Entity) {
+        self.grounded = true;
+        self.ground_normal = Some(normal);
+        self.ground_entity = Some(entity);
+    }
+
+    /// Marks the entity as being in the air
+    pub fn clear_ground(&mut self) {
+        self.grounded = false;
+        self.ground_normal = None;
+        self.ground_entity = None;
+    }
+
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    pub fn get_ground_normal(&self) -> Option<Vector3<f32>> {
+        self.ground_normal
+    }
+
+    pub fn get_ground_entity(&self) -> Option<Entity> {
+        self.ground_entity
+    }
+}
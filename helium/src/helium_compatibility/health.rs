@@ -0,0 +1,162 @@
+use helium_ecs::Entity;
+
+/// Sent when an entity with `Health` takes damage
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub source: Option<Entity>,
+    pub amount: f32,
+    /// Health left after the damage
+    pub remaining: f32,
+}
+
+/// Sent when the health of an entity reaches zero
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DeathEvent {
+    pub entity: Entity,
+    /// The entity that dealt the final damage
+    pub source: Option<Entity>,
+}
+
+/// The health of an entity, damage is ignored for a short time after each hit when it
+/// has an invulnerability window
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Health {
+    current: f32,
+    max: f32,
+    // Seconds of invulnerability after taking damage
+    invulnerability: f32,
+    invulnerable_for: f32,
+    despawn_on_death: bool,
+}
+
+impl Health {
+    /// Creates health that starts full
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - The most health the entity can have
+    pub fn new(max: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            invulnerability: 0.0,
+            invulnerable_for: 0.0,
+            despawn_on_death: false,
+        }
+    }
+
+    /// Ignores damage for a time after each hit
+    ///
+    /// # Arguments
+    ///
+    /// * `seconds` - How long the entity is invulnerable after being damaged
+    pub fn with_invulnerability(mut self, seconds: f32) -> Self {
+        self.invulnerability = seconds;
+        self
+    }
+
+    /// Despawns the entity when it dies
+    pub fn with_despawn_on_death(mut self, despawn_on_death: bool) -> Self {
+        self.despawn_on_death = despawn_on_death;
+        self
+    }
+
+    pub fn get_current(&self) -> f32 {
+        self.current
+    }
+
+    pub fn get_max(&self) -> f32 {
+        self.max
+    }
+
+    pub fn set_max(&mut self, max: f32) {
+        self.max = max;
+        self.current = self.current.min(max);
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable_for > 0.0
+    }
+
+    pub fn get_despawn_on_death(&self) -> bool {
+        self.despawn_on_death
+    }
+
+    /// Makes the entity ignore damage for a time
+    pub fn set_invulnerable_for(&mut self, seconds: f32) {
+        self.invulnerable_for = seconds;
+    }
+
+    /// Restores health up to the max, dead entities can not be healed
+    pub fn heal(&mut self, amount: f32) {
+        if !self.is_dead() {
+            self.current = (self.current + amount).min(self.max);
+        }
+    }
+
+    /// Brings a dead entity back with an amount of health
+    pub fn revive(&mut self, amount: f32) {
+        self.current = amount.clamp(0.0, self.max);
+        self.invulnerable_for = 0.0;
+    }
+
+    /// Takes damage unless the entity is invulnerable or already dead
+    ///
+    /// # Returns
+    ///
+    /// Whether the damage was taken
+    pub fn damage(&mut self, amount: f32) -> bool {
+        if self.is_dead() || self.is_invulnerable() || amount <= 0.0 {
+            return false;
+        }
+
+        self.current = (self.current - amount).max(0.0);
+        self.invulnerable_for = self.invulnerability;
+        true
+    }
+
+    /// Counts down the invulnerability window
+    pub fn advance(&mut self, delta_time: f32) {
+        self.invulnerable_for = (self.invulnerable_for - delta_time).max(0.0);
+    }
+}
+
+/// Damages the entities with `Health` that its `RectangleCollider` touches, like a
+/// projectile or a spike trap
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Damage {
+    amount: f32,
+    despawn_on_hit: bool,
+}
+
+impl Damage {
+    pub fn new(amount: f32) -> Self {
+        Self {
+            amount,
+            despawn_on_hit: false,
+        }
+    }
+
+    /// Despawns the entity after it damages something, used for projectiles
+    pub fn with_despawn_on_hit(mut self, despawn_on_hit: bool) -> Self {
+        self.despawn_on_hit = despawn_on_hit;
+        self
+    }
+
+    pub fn get_amount(&self) -> f32 {
+        self.amount
+    }
+
+    pub fn set_amount(&mut self, amount: f32) {
+        self.amount = amount;
+    }
+
+    pub fn get_despawn_on_hit(&self) -> bool {
+        self.despawn_on_hit
+    }
+}
@@ -0,0 +1,68 @@
+use helium_renderer::Outline;
+
+/// Draws a colored outline around the model of an entity, like a selected object
+pub struct Highlighted {
+    color: [f32; 4],
+    width: f32,
+    update_flag: bool,
+}
+
+impl Default for Highlighted {
+    fn default() -> Self {
+        let outline = Outline::default();
+        Self::new(outline.color, outline.width)
+    }
+}
+
+impl Highlighted {
+    /// Creates a highlight for the model of an entity
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The RGBA color of the outline
+    /// * `width` - The thickness of the outline in world units
+    pub fn new(color: [f32; 4], width: f32) -> Self {
+        Self {
+            color,
+            width,
+            update_flag: true,
+        }
+    }
+
+    // Setters
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        self.color = color;
+        self.update_flag = true;
+    }
+
+    pub fn set_width(&mut self, width: f32) {
+        self.width = width;
+        self.update_flag = true;
+    }
+
+    pub fn update(&mut self) {
+        self.update_flag = false;
+    }
+
+    // Getters
+    pub fn get_color(&self) -> [f32; 4] {
+        self.color
+    }
+
+    pub fn get_width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn get_update_flag(&self) -> bool {
+        self.update_flag
+    }
+}
+
+impl From<&Highlighted> for Outline {
+    fn from(value: &Highlighted) -> Self {
+        Self {
+            color: value.color,
+            width: value.width,
+        }
+    }
+}
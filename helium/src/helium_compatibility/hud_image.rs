@@ -0,0 +1,135 @@
+use helium_renderer::{OverlaySprite, SpriteHandle, UiLayout};
+
+/// An image drawn on top of the scene in screen space, like a crosshair or an icon
+pub struct HudImage {
+    texture: SpriteHandle,
+    layout: UiLayout,
+    size: (f32, f32),
+    scale: f32,
+    rotation: f32,
+    opacity: f32,
+    renderer_index: Option<usize>,
+    update_flag: bool,
+}
+
+impl HudImage {
+    /// Creates a new hud image
+    ///
+    /// # Arguments
+    ///
+    /// * `texture` - The texture loaded with `HeliumManager::load_sprite_texture`
+    /// * `layout` - Where to place the image on the screen
+    /// * `size` - The width and height of the image in pixels
+    pub fn new(texture: SpriteHandle, layout: UiLayout, size: (f32, f32)) -> Self {
+        Self {
+            texture,
+            layout,
+            size,
+            scale: 1.0,
+            rotation: 0.0,
+            opacity: 1.0,
+            renderer_index: None,
+            update_flag: false,
+        }
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the rotation around the center of the image in radians
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    // Setters
+    pub fn set_texture(&mut self, texture: SpriteHandle) {
+        self.texture = texture;
+        self.update_flag = true;
+    }
+
+    pub fn set_layout(&mut self, layout: UiLayout) {
+        self.layout = layout;
+        self.update_flag = true;
+    }
+
+    pub fn set_size(&mut self, size: (f32, f32)) {
+        self.size = size;
+        self.update_flag = true;
+    }
+
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+        self.update_flag = true;
+    }
+
+    pub fn set_rotation(&mut self, rotation: f32) {
+        self.rotation = rotation;
+        self.update_flag = true;
+    }
+
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity;
+        self.update_flag = true;
+    }
+
+    pub fn update(&mut self) {
+        self.update_flag = false;
+    }
+
+    // Getters
+    pub fn get_texture(&self) -> SpriteHandle {
+        self.texture
+    }
+
+    pub fn get_layout(&self) -> &UiLayout {
+        &self.layout
+    }
+
+    pub fn get_size(&self) -> (f32, f32) {
+        self.size
+    }
+
+    pub fn get_scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn get_rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    pub fn get_opacity(&self) -> f32 {
+        self.opacity
+    }
+
+    pub fn get_update_flag(&self) -> bool {
+        self.update_flag
+    }
+
+    /// Used internally to link the component to the renderer
+    pub fn set_renderer_index(&mut self, index: usize) {
+        self.renderer_index = Some(index);
+    }
+
+    /// Used internally to get information about the image from the renderer
+    pub fn get_renderer_index(&self) -> Option<&usize> {
+        self.renderer_index.as_ref()
+    }
+}
+
+impl From<&HudImage> for OverlaySprite {
+    fn from(value: &HudImage) -> Self {
+        OverlaySprite::new(value.texture, (0.0, 0.0), value.size)
+            .with_layout(Some(value.layout))
+            .with_scale(value.scale)
+            .with_rotation(value.rotation)
+            .with_opacity(value.opacity)
+    }
+}
@@ -0,0 +1,133 @@
+use cgmath::{InnerSpace, Vector3};
+use helium_ecs::Entity;
+
+/// Sent when the player presses the use key while an `Interactable` is focused
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InteractionEvent {
+    /// The entity that was used
+    pub entity: Entity,
+    /// The camera the player was looking through
+    pub camera: Option<Entity>,
+}
+
+/// What the player has to do for an interactable to be focused
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InteractionTrigger {
+    /// Being within the radius
+    Proximity,
+    /// Being within the radius and looking at the entity, the view has to hit the
+    /// collider of the entity or pass within an angle of its position when it has none
+    LookAt {
+        /// The widest angle in radians between the view and the entity
+        max_angle: f32,
+    },
+}
+
+/// Something the player can use, like a door or a lever. A prompt is shown while it is
+/// focused and an `InteractionEvent` is sent when the use key is pressed
+#[derive(Clone, Debug, PartialEq)]
+pub struct Interactable {
+    prompt: String,
+    radius: f32,
+    trigger: InteractionTrigger,
+    enabled: bool,
+}
+
+impl Interactable {
+    /// Creates an interactable that is focused when the camera is near it
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - The text shown while it is focused, like "Open", it can be a key of the
+    ///   string table
+    /// * `radius` - How close the camera has to be in world units
+    pub fn new(prompt: String, radius: f32) -> Self {
+        Self {
+            prompt,
+            radius: radius.max(0.0),
+            trigger: InteractionTrigger::Proximity,
+            enabled: true,
+        }
+    }
+
+    /// Only focuses the interactable while the camera looks at it
+    ///
+    /// # Arguments
+    ///
+    /// * `max_angle` - The widest angle in radians between the view and the entity when it
+    ///   has no collider to look at
+    pub fn with_look_at(mut self, max_angle: f32) -> Self {
+        self.trigger = InteractionTrigger::LookAt {
+            max_angle: max_angle.abs(),
+        };
+        self
+    }
+
+    // Setters
+    pub fn set_prompt(&mut self, prompt: String) {
+        self.prompt = prompt;
+    }
+
+    pub fn set_radius(&mut self, radius: f32) {
+        self.radius = radius.max(0.0);
+    }
+
+    /// Disabled interactables are never focused, like a door that is locked
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    // Getters
+    pub fn get_prompt(&self) -> &str {
+        &self.prompt
+    }
+
+    pub fn get_radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub fn get_trigger(&self) -> InteractionTrigger {
+        self.trigger
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Used internally to find if the player can use the interactable
+    ///
+    /// # Arguments
+    ///
+    /// * `eye` - The position of the camera
+    /// * `forward` - The normalized direction the camera looks in
+    /// * `position` - The position of the entity
+    /// * `looked_at` - Whether the view hits the collider of the entity, `None` when it has
+    ///   no collider
+    ///
+    /// # Returns
+    ///
+    /// The distance from the camera or `None` if the interactable is not focused
+    pub fn focus_distance(
+        &self,
+        eye: Vector3<f32>,
+        forward: Vector3<f32>,
+        position: Vector3<f32>,
+        looked_at: Option<bool>,
+    ) -> Option<f32> {
+        let offset = position - eye;
+        let distance = offset.magnitude();
+        if !self.enabled || distance > self.radius {
+            return None;
+        }
+
+        let focused = match (self.trigger, looked_at) {
+            (InteractionTrigger::Proximity, _) => true,
+            (InteractionTrigger::LookAt { .. }, Some(looked_at)) => looked_at,
+            (InteractionTrigger::LookAt { max_angle }, None) => {
+                distance <= f32::EPSILON || forward.angle(offset / distance).0 <= max_angle
+            }
+        };
+
+        focused.then_some(distance)
+    }
+}
@@ -1,2 +1,2 @@
-#[derive(PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct Label(pub String);
@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+/// Despawns an entity and everything it draws once the time left runs out, used for
+/// particles, projectiles and other temporary effects
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lifetime(pub Duration);
+
+impl Lifetime {
+    /// Creates a lifetime
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - How long the entity lives
+    pub fn new(duration: Duration) -> Self {
+        Self(duration)
+    }
+
+    pub fn from_secs_f32(seconds: f32) -> Self {
+        Self(Duration::from_secs_f32(seconds.max(0.0)))
+    }
+
+    /// Counts down the time left
+    pub fn advance(&mut self, delta_time: Duration) {
+        self.0 = self.0.saturating_sub(delta_time);
+    }
+
+    pub fn get_remaining(&self) -> Duration {
+        self.0
+    }
+
+    /// Adds more time before the entity is despawned
+    pub fn extend(&mut self, duration: Duration) {
+        self.0 += duration;
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.0.is_zero()
+    }
+}
@@ -1,9 +1,51 @@
+pub mod animation;
+pub mod auto_collider;
 pub mod camera;
+pub mod cursor;
+pub mod decal;
+pub mod emissive;
+pub mod ground_state;
+pub mod health;
+pub mod highlight;
+pub mod hud_image;
+pub mod interactable;
 pub mod label;
+pub mod lifetime;
 pub mod model;
+pub mod reflective;
+pub mod spawner;
+pub mod spring_follow;
+pub mod state_machine;
+pub mod static_batch;
+pub mod text;
+pub mod trail;
 pub mod transform;
+pub mod visible;
+pub mod widget;
+pub mod world_ui;
 
+pub use animation::*;
+pub use auto_collider::*;
 pub use camera::*;
+pub use cursor::*;
+pub use decal::*;
+pub use emissive::*;
+pub use ground_state::*;
+pub use health::*;
+pub use highlight::*;
+pub use hud_image::*;
+pub use interactable::*;
 pub use label::*;
+pub use lifetime::*;
 pub use model::*;
+pub use reflective::*;
+pub use spawner::*;
+pub use spring_follow::*;
+pub use state_machine::*;
+pub use static_batch::*;
+pub use text::*;
+pub use trail::*;
 pub use transform::*;
+pub use visible::*;
+pub use widget::*;
+pub use world_ui::*;
@@ -1,20 +1,83 @@
+use helium_renderer::{ColorMaterial, Topology};
+
+#[derive(Clone)]
 pub struct Model3d {
     model_path: String,
+    color_material: Option<ColorMaterial>,
+    lines: Option<(Vec<[f32; 3]>, Topology)>,
     renderer_index: Option<usize>,
 }
 
 impl Model3d {
+    /// Creates a model from a file, the renderer picks the loader from the extension so
+    /// .gltf and .glb files, like the assets exported from Blender, are loaded with the
+    /// materials and textures in the file and every other file is read as an obj
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - Filepath of the obj, gltf, or glb file
+    ///
+    /// # Returns
+    ///
+    /// The model that is loaded when it is added to the world
     pub fn from_obj(file_path: String) -> Self {
         Self {
             model_path: file_path,
+            color_material: None,
+            lines: None,
+            renderer_index: None,
+        }
+    }
+
+    /// Creates a model of lines or points instead of loading one from a file, it can not
+    /// be scattered or batched
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The points in object space in the order they are joined
+    /// * `topology` - How the points are joined into lines
+    ///
+    /// # Returns
+    ///
+    /// The model that is drawn unlit in white unless it is given a color material
+    pub fn from_lines(points: Vec<[f32; 3]>, topology: Topology) -> Self {
+        Self {
+            model_path: String::new(),
+            color_material: None,
+            lines: Some((points, topology)),
             renderer_index: None,
         }
     }
 
+    /// Draws every mesh of the model with a flat color instead of the materials in the file
+    ///
+    /// # Arguments
+    ///
+    /// * `color_material` - The color material to draw the model with
+    ///
+    /// # Returns
+    ///
+    /// The model with the color material
+    pub fn with_color_material(mut self, color_material: ColorMaterial) -> Self {
+        self.color_material = Some(color_material);
+        self
+    }
+
     pub fn get_path(&self) -> &str {
         &self.model_path
     }
 
+    pub fn get_color_material(&self) -> Option<&ColorMaterial> {
+        self.color_material.as_ref()
+    }
+
+    /// The points and topology of a model made of lines
+    pub fn get_lines(&self) -> Option<(&[[f32; 3]], Topology)> {
+        self.lines
+            .as_ref()
+            .map(|(points, topology)| (points.as_slice(), *topology))
+    }
+
     /// Used internally to link the component to the renderer
     pub fn set_renderer_index(&mut self, index: usize) {
         self.renderer_index = Some(index);
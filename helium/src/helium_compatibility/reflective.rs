@@ -0,0 +1,33 @@
+/// Makes the model of an entity a flat reflective surface like water or a mirror,
+/// the surface is the local xz plane of the transform of the entity
+pub struct Reflective {
+    reflectivity: f32,
+}
+
+impl Default for Reflective {
+    fn default() -> Self {
+        Self::new(0.8)
+    }
+}
+
+impl Reflective {
+    /// Creates a reflective surface for the model of an entity
+    ///
+    /// # Arguments
+    ///
+    /// * `reflectivity` - How much of the reflection is mixed into the color of the
+    ///   surface from 0 to 1
+    pub fn new(reflectivity: f32) -> Self {
+        Self { reflectivity }
+    }
+
+    // Setters
+    pub fn set_reflectivity(&mut self, reflectivity: f32) {
+        self.reflectivity = reflectivity;
+    }
+
+    // Getters
+    pub fn get_reflectivity(&self) -> f32 {
+        self.reflectivity
+    }
+}
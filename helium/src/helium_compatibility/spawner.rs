@@ -0,0 +1,137 @@
+use cgmath::{Vector3, Zero};
+use helium_ecs::Entity;
+
+use crate::rng::Rng;
+
+/// Spawns a registered prefab on a timer at random points inside a region around the
+/// entity, the points come from `manager.rng()` and the spawned entities are tracked so
+/// no more than the max alive exist at once
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spawner {
+    prefab: String,
+    // Seconds between spawns
+    interval: f32,
+    max_alive: usize,
+    // Corners of the box the prefabs are spawned in relative to the entity
+    region_min: Vector3<f32>,
+    region_max: Vector3<f32>,
+    enabled: bool,
+
+    timer: f32,
+    children: Vec<Entity>,
+}
+
+impl Spawner {
+    /// Creates a spawner
+    ///
+    /// # Arguments
+    ///
+    /// * `prefab` - Name of the prefab registered with `register_prefab`
+    /// * `interval` - Seconds between spawns
+    pub fn new(prefab: &str, interval: f32) -> Self {
+        Self {
+            prefab: prefab.to_string(),
+            interval,
+            max_alive: usize::MAX,
+            region_min: Vector3::zero(),
+            region_max: Vector3::zero(),
+            enabled: true,
+            timer: 0.0,
+            children: Vec::new(),
+        }
+    }
+
+    /// Limits how many spawned entities can exist at once
+    pub fn with_max_alive(mut self, max_alive: usize) -> Self {
+        self.max_alive = max_alive;
+        self
+    }
+
+    /// Spawns the prefabs at random points in a box around the entity
+    ///
+    /// # Arguments
+    ///
+    /// * `min` - Corner of the box with the smallest coordinates relative to the entity
+    /// * `max` - Corner of the box with the largest coordinates relative to the entity
+    pub fn with_region(mut self, min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        self.region_min = min;
+        self.region_max = max;
+        self
+    }
+
+    pub fn get_prefab(&self) -> &str {
+        &self.prefab
+    }
+
+    pub fn set_interval(&mut self, interval: f32) {
+        self.interval = interval;
+    }
+
+    pub fn get_interval(&self) -> f32 {
+        self.interval
+    }
+
+    pub fn set_max_alive(&mut self, max_alive: usize) {
+        self.max_alive = max_alive;
+    }
+
+    pub fn get_max_alive(&self) -> usize {
+        self.max_alive
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// The spawned entities that are still alive
+    pub fn get_children(&self) -> &[Entity] {
+        &self.children
+    }
+
+    /// Used internally to forget the spawned entities that were despawned
+    pub fn retain_children<F>(&mut self, alive: F)
+    where
+        F: FnMut(&Entity) -> bool,
+    {
+        self.children.retain(alive);
+    }
+
+    /// Used internally to track a spawned entity
+    pub fn add_child(&mut self, entity: Entity) {
+        self.children.push(entity);
+    }
+
+    /// Counts down to the next spawn
+    ///
+    /// # Returns
+    ///
+    /// Whether a prefab should be spawned this update
+    pub fn advance(&mut self, delta_time: f32) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        self.timer += delta_time;
+        if self.timer < self.interval || self.children.len() >= self.max_alive {
+            return false;
+        }
+
+        // Leftover time is kept so the spawns stay on the interval
+        self.timer = (self.timer - self.interval).min(self.interval);
+        true
+    }
+
+    /// Picks a random point in the spawn region
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - Position of the spawner
+    /// * `rng` - The random number generator to pick the point with
+    pub fn next_position(&self, origin: Vector3<f32>, rng: &mut Rng) -> Vector3<f32> {
+        origin + rng.point_in_box(self.region_min, self.region_max)
+    }
+}
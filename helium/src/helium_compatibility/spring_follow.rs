@@ -0,0 +1,165 @@
+use cgmath::{InnerSpace, Quaternion, Rotation, Vector3, Zero};
+use helium_ecs::Entity;
+
+/// Pulls the transform of an entity towards another entity with a damped spring so it
+/// trails behind it smoothly, used for cameras, floating ui, and loose attachments
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpringFollow {
+    target: Entity,
+    offset: Vector3<f32>,
+    // Whether the offset turns with the target
+    local_offset: bool,
+    stiffness: f32,
+    // 1 is critically damped, less overshoots and more is sluggish
+    damping: f32,
+    follow_rotation: bool,
+
+    velocity: Vector3<f32>,
+}
+
+impl SpringFollow {
+    /// Creates a critically damped spring to a target
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The entity to follow
+    pub fn new(target: Entity) -> Self {
+        Self {
+            target,
+            offset: Vector3::zero(),
+            local_offset: false,
+            stiffness: 50.0,
+            damping: 1.0,
+            follow_rotation: false,
+            velocity: Vector3::zero(),
+        }
+    }
+
+    /// Keeps the entity away from the target instead of on top of it
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Where the entity rests relative to the target
+    /// * `local` - Whether the offset turns with the target, like a camera behind a car
+    pub fn with_offset(mut self, offset: Vector3<f32>, local: bool) -> Self {
+        self.offset = offset;
+        self.local_offset = local;
+        self
+    }
+
+    /// Sets how hard the spring pulls for every unit the entity is away from its rest
+    /// position, stiffer springs catch up faster
+    pub fn with_stiffness(mut self, stiffness: f32) -> Self {
+        self.stiffness = stiffness.max(0.0);
+        self
+    }
+
+    /// Sets the damping as a ratio of critical damping, 1 settles as fast as possible
+    /// without overshooting, less bounces and more lags behind
+    pub fn with_damping(mut self, damping: f32) -> Self {
+        self.damping = damping.max(0.0);
+        self
+    }
+
+    /// Turns the entity towards the rotation of the target with the same smoothing
+    pub fn with_rotation(mut self, follow_rotation: bool) -> Self {
+        self.follow_rotation = follow_rotation;
+        self
+    }
+
+    pub fn set_target(&mut self, target: Entity) {
+        self.target = target;
+    }
+
+    pub fn get_target(&self) -> Entity {
+        self.target
+    }
+
+    pub fn get_offset(&self) -> &Vector3<f32> {
+        &self.offset
+    }
+
+    pub fn get_stiffness(&self) -> f32 {
+        self.stiffness
+    }
+
+    pub fn get_damping(&self) -> f32 {
+        self.damping
+    }
+
+    pub fn is_following_rotation(&self) -> bool {
+        self.follow_rotation
+    }
+
+    pub fn get_velocity(&self) -> &Vector3<f32> {
+        &self.velocity
+    }
+
+    /// Stops the spring from moving, used after teleporting the entity
+    pub fn reset_velocity(&mut self) {
+        self.velocity = Vector3::zero();
+    }
+
+    /// Where the entity rests for a target transform
+    pub fn rest_position(
+        &self,
+        target_position: Vector3<f32>,
+        target_rotation: Quaternion<f32>,
+    ) -> Vector3<f32> {
+        if self.local_offset {
+            target_position + target_rotation.rotate_vector(self.offset)
+        } else {
+            target_position + self.offset
+        }
+    }
+
+    /// Moves the entity along the spring towards where it rests
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position of the entity
+    /// * `rest` - Where the spring pulls the entity to
+    /// * `delta_time` - The seconds that passed
+    ///
+    /// # Returns
+    ///
+    /// The new position of the entity
+    pub fn step(
+        &mut self,
+        position: Vector3<f32>,
+        rest: Vector3<f32>,
+        delta_time: f32,
+    ) -> Vector3<f32> {
+        if delta_time <= 0.0 {
+            return position;
+        }
+
+        // Implicit integration keeps stiff springs stable with long frames
+        let stretch = position - rest;
+        let damping = self.damping * 2.0 * self.stiffness.sqrt();
+        self.velocity = (self.velocity - stretch * self.stiffness * delta_time)
+            / (1.0 + damping * delta_time + self.stiffness * delta_time * delta_time);
+
+        position + self.velocity * delta_time
+    }
+
+    /// Turns the entity towards the rotation of the target, it closes the same fraction
+    /// of the angle every second as a critically damped spring of the same stiffness
+    pub fn step_rotation(
+        &self,
+        rotation: Quaternion<f32>,
+        target_rotation: Quaternion<f32>,
+        delta_time: f32,
+    ) -> Quaternion<f32> {
+        let amount = 1.0 - (-self.stiffness.sqrt() * delta_time.max(0.0)).exp();
+
+        // The shorter way around is taken
+        let target_rotation = if rotation.dot(target_rotation) < 0.0 {
+            -target_rotation
+        } else {
+            target_rotation
+        };
+
+        rotation.nlerp(target_rotation, amount)
+    }
+}
@@ -0,0 +1,475 @@
+use std::{collections::HashMap, fmt::Debug, hash::Hash};
+
+use helium_ecs::Entity;
+
+use crate::HeliumManager;
+
+/// Called with the entity of the state machine when it enters, leaves or stays in a state
+pub type StateFunction = fn(&mut HeliumManager, Entity);
+
+/// Checked with the entity of the state machine to find if a transition can be taken
+pub type GuardFunction = fn(&HeliumManager, Entity) -> bool;
+
+/// Sent when a state machine changes state
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StateChanged<T> {
+    pub entity: Entity,
+    pub from: T,
+    pub to: T,
+}
+
+/// What has to be true for a transition to be taken
+#[derive(Clone, Copy, Debug)]
+enum Condition {
+    Guard(GuardFunction),
+    // Seconds in the state the transition leaves from
+    After(f32),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Transition<T> {
+    from: T,
+    to: T,
+    condition: Condition,
+}
+
+#[derive(Clone, Debug)]
+struct StateNode<T> {
+    parent: Option<T>,
+    // The substate that is entered with the state
+    initial: Option<T>,
+    on_enter: Option<StateFunction>,
+    on_exit: Option<StateFunction>,
+    on_update: Option<StateFunction>,
+}
+
+impl<T> Default for StateNode<T> {
+    fn default() -> Self {
+        Self {
+            parent: None,
+            initial: None,
+            on_enter: None,
+            on_exit: None,
+            on_update: None,
+        }
+    }
+}
+
+/// A hierarchical state machine for AI, player states or animation logic. States can have
+/// substates, a transition from a state is also taken from any of its substates and the
+/// callbacks of the parent states run around the callbacks of their substates. The
+/// machine is run by a system that is registered with `HeliumManager::add_state_machine`.
+/// The states are usually a fieldless enum, like an enemy that patrols until it sees the
+/// player and then chases and attacks it in substates of an alert state
+#[derive(Clone, Debug)]
+pub struct StateMachine<T> {
+    states: HashMap<T, StateNode<T>>,
+    transitions: Vec<Transition<T>>,
+    initial: T,
+    current: T,
+    started: bool,
+    time_in_state: f32,
+    requested: Option<T>,
+}
+
+impl<T> StateMachine<T>
+where
+    T: Copy + Eq + Hash + Debug + 'static,
+{
+    /// Creates a state machine that starts in a state, the state is entered on the first
+    /// update after the machine is added
+    ///
+    /// # Arguments
+    ///
+    /// * `initial` - The state to start in
+    pub fn new(initial: T) -> Self {
+        Self {
+            states: HashMap::new(),
+            transitions: Vec::new(),
+            initial,
+            current: initial,
+            started: false,
+            time_in_state: 0.0,
+            requested: None,
+        }
+    }
+
+    /// Makes a state a substate of another state, the first substate added to a state is
+    /// entered whenever that state is entered
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The substate
+    /// * `parent` - The state it is part of
+    pub fn with_substate(mut self, state: T, parent: T) -> Self {
+        if self.path(parent).contains(&state) {
+            log::warn!("{state:?} can not be a substate of its own substate {parent:?}");
+            return self;
+        }
+
+        self.states.entry(state).or_default().parent = Some(parent);
+        self.states
+            .entry(parent)
+            .or_default()
+            .initial
+            .get_or_insert(state);
+        self.initial = self.resolve(self.initial);
+        if !self.started {
+            self.current = self.initial;
+        }
+        self
+    }
+
+    /// Adds a transition that is taken when its guard returns true, the transitions of a
+    /// state are checked in the order they were added before the transitions of its parent
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The state the transition leaves, or any of its substates
+    /// * `to` - The state the transition enters
+    /// * `guard` - Returns whether the transition should be taken this update
+    pub fn with_transition(mut self, from: T, to: T, guard: GuardFunction) -> Self {
+        self.transitions.push(Transition {
+            from,
+            to,
+            condition: Condition::Guard(guard),
+        });
+        self
+    }
+
+    /// Adds a transition that is taken after some time in the current state
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The state the transition leaves, or any of its substates
+    /// * `to` - The state the transition enters
+    /// * `seconds` - How long the machine has been in its current state
+    pub fn with_transition_after(mut self, from: T, to: T, seconds: f32) -> Self {
+        self.transitions.push(Transition {
+            from,
+            to,
+            condition: Condition::After(seconds),
+        });
+        self
+    }
+
+    /// Calls a function when the state is entered, parent states are entered first
+    pub fn with_on_enter(mut self, state: T, on_enter: StateFunction) -> Self {
+        self.states.entry(state).or_default().on_enter = Some(on_enter);
+        self
+    }
+
+    /// Calls a function when the state is left, substates are left first
+    pub fn with_on_exit(mut self, state: T, on_exit: StateFunction) -> Self {
+        self.states.entry(state).or_default().on_exit = Some(on_exit);
+        self
+    }
+
+    /// Calls a function every update the machine stays in the state or one of its
+    /// substates
+    pub fn with_on_update(mut self, state: T, on_update: StateFunction) -> Self {
+        self.states.entry(state).or_default().on_update = Some(on_update);
+        self
+    }
+
+    /// Changes to a state on the next update without checking the transitions, the exit
+    /// and enter callbacks still run
+    pub fn request_state(&mut self, state: T) {
+        self.requested = Some(state);
+    }
+
+    /// The innermost state the machine is in
+    pub fn get_state(&self) -> T {
+        self.current
+    }
+
+    /// Checks if the machine is in a state or any of its substates
+    pub fn is_in(&self, state: T) -> bool {
+        self.path(self.current).contains(&state)
+    }
+
+    pub fn get_parent(&self, state: T) -> Option<T> {
+        self.states.get(&state).and_then(|node| node.parent)
+    }
+
+    /// Seconds since the machine last changed state
+    pub fn get_time_in_state(&self) -> f32 {
+        self.time_in_state
+    }
+
+    // The states from the outermost parent down to the state
+    fn path(&self, state: T) -> Vec<T> {
+        let mut path = vec![state];
+        while let Some(parent) = self.get_parent(path[path.len() - 1]) {
+            if path.contains(&parent) {
+                break;
+            }
+            path.push(parent);
+        }
+
+        path.reverse();
+        path
+    }
+
+    // The innermost state that is entered with a state
+    fn resolve(&self, state: T) -> T {
+        let mut resolved = state;
+        for _ in 0..self.states.len() {
+            match self.states.get(&resolved).and_then(|node| node.initial) {
+                Some(initial) => resolved = initial,
+                None => break,
+            }
+        }
+
+        resolved
+    }
+
+    fn callbacks<F>(&self, states: &[T], callback: F) -> Vec<StateFunction>
+    where
+        F: Fn(&StateNode<T>) -> Option<StateFunction>,
+    {
+        states
+            .iter()
+            .filter_map(|state| self.states.get(state).and_then(&callback))
+            .collect()
+    }
+
+    /// Used internally to enter the initial state on the first update
+    ///
+    /// # Returns
+    ///
+    /// The enter callbacks to run or `None` if the machine has already started
+    pub(crate) fn start(&mut self) -> Option<Vec<StateFunction>> {
+        if self.started {
+            return None;
+        }
+
+        self.started = true;
+        self.current = self.initial;
+        self.time_in_state = 0.0;
+        Some(self.callbacks(&self.path(self.current), |node| node.on_enter))
+    }
+
+    /// Used internally to count the time in the state and take the requested state
+    ///
+    /// # Returns
+    ///
+    /// The requested state or the first timed transition that is ready, and the guarded
+    /// transitions before it that are checked first, innermost first
+    pub(crate) fn advance(&mut self, delta_time: f32) -> (Option<T>, Vec<(T, GuardFunction)>) {
+        self.time_in_state += delta_time;
+        if let Some(requested) = self.requested.take() {
+            return (Some(requested), Vec::new());
+        }
+
+        let mut guarded = Vec::new();
+        for state in self.path(self.current).into_iter().rev() {
+            for transition in self.transitions.iter().filter(|t| t.from == state) {
+                match transition.condition {
+                    Condition::Guard(guard) => guarded.push((transition.to, guard)),
+                    // Timed transitions are ready now or never this update
+                    Condition::After(seconds) if self.time_in_state >= seconds => {
+                        return (Some(transition.to), guarded);
+                    }
+                    Condition::After(_) => {}
+                }
+            }
+        }
+
+        (None, guarded)
+    }
+
+    /// Used internally to find the callbacks of a change of state
+    ///
+    /// # Returns
+    ///
+    /// The innermost state that is entered with the exit callbacks, innermost first, and
+    /// the enter callbacks, outermost first
+    pub(crate) fn change(&self, to: T) -> (T, Vec<StateFunction>, Vec<StateFunction>) {
+        let to = self.resolve(to);
+        let from_path = self.path(self.current);
+        let to_path = self.path(to);
+
+        // The shared parents are not left, changing to the same state leaves and enters it
+        let shared = from_path
+            .iter()
+            .zip(&to_path)
+            .take_while(|(from, to)| from == to)
+            .count()
+            .min(from_path.len() - 1)
+            .min(to_path.len() - 1);
+
+        let exited = from_path[shared..]
+            .iter()
+            .rev()
+            .copied()
+            .collect::<Vec<_>>();
+        (
+            to,
+            self.callbacks(&exited, |node| node.on_exit),
+            self.callbacks(&to_path[shared..], |node| node.on_enter),
+        )
+    }
+
+    /// Used internally to move into a state after its exit callbacks ran
+    pub(crate) fn set_state(&mut self, state: T) {
+        self.current = state;
+        self.time_in_state = 0.0;
+    }
+
+    /// Used internally to get the update callbacks of the state, outermost first
+    pub(crate) fn update_callbacks(&self) -> Vec<StateFunction> {
+        self.callbacks(&self.path(self.current), |node| node.on_update)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    enum Enemy {
+        Idle,
+        Alert,
+        Chase,
+        Attack,
+    }
+
+    // The callbacks that ran, in order
+    struct Log(Vec<&'static str>);
+
+    // Marks the enemy as having seen the player
+    struct Alerted;
+
+    fn log(manager: &mut HeliumManager, entity: Entity, entry: &'static str) {
+        if let Some(log) = manager.query_mut::<Log>().unwrap().get_mut(&entity) {
+            log.0.push(entry);
+        }
+    }
+
+    fn take_log(manager: &mut HeliumManager, entity: Entity) -> Vec<&'static str> {
+        std::mem::take(
+            &mut manager
+                .query_mut::<Log>()
+                .unwrap()
+                .get_mut(&entity)
+                .unwrap()
+                .0,
+        )
+    }
+
+    fn alerted(manager: &HeliumManager, entity: Entity) -> bool {
+        manager
+            .query::<Alerted>()
+            .is_some_and(|alerted| alerted.contains_key(&entity))
+    }
+
+    fn calm(manager: &HeliumManager, entity: Entity) -> bool {
+        !alerted(manager, entity)
+    }
+
+    fn always(_: &HeliumManager, _: Entity) -> bool {
+        true
+    }
+
+    fn enemy() -> StateMachine<Enemy> {
+        StateMachine::new(Enemy::Idle)
+            .with_substate(Enemy::Chase, Enemy::Alert)
+            .with_substate(Enemy::Attack, Enemy::Alert)
+            .with_transition(Enemy::Idle, Enemy::Alert, alerted)
+            .with_transition(Enemy::Alert, Enemy::Idle, calm)
+            .with_transition_after(Enemy::Chase, Enemy::Attack, 1.0)
+            .with_on_enter(Enemy::Idle, |m, e| log(m, e, "enter idle"))
+            .with_on_exit(Enemy::Idle, |m, e| log(m, e, "exit idle"))
+            .with_on_enter(Enemy::Alert, |m, e| log(m, e, "enter alert"))
+            .with_on_exit(Enemy::Alert, |m, e| log(m, e, "exit alert"))
+            .with_on_enter(Enemy::Chase, |m, e| log(m, e, "enter chase"))
+            .with_on_exit(Enemy::Chase, |m, e| log(m, e, "exit chase"))
+            .with_on_enter(Enemy::Attack, |m, e| log(m, e, "enter attack"))
+            .with_on_exit(Enemy::Attack, |m, e| log(m, e, "exit attack"))
+    }
+
+    fn spawn(state_machine: StateMachine<Enemy>) -> (HeliumManager, Entity) {
+        let mut manager = HeliumManager::without_renderer();
+        let entity = manager.create_entity();
+        manager.add_component(entity, Log(Vec::new()));
+        manager.add_state_machine(entity, state_machine);
+
+        manager.step(Duration::from_millis(100), &[]);
+        (manager, entity)
+    }
+
+    #[test]
+    fn test_initial_substate() {
+        let machine = StateMachine::new(Enemy::Alert).with_substate(Enemy::Chase, Enemy::Alert);
+        assert_eq!(machine.get_state(), Enemy::Chase);
+        assert!(machine.is_in(Enemy::Alert));
+
+        let (mut manager, entity) = spawn(enemy());
+        assert_eq!(take_log(&mut manager, entity), ["enter idle"]);
+
+        // Entering a parent state enters its first substate
+        manager.add_component(entity, Alerted);
+        manager.step(Duration::from_millis(100), &[]);
+        assert_eq!(manager.get_state(entity), Some(Enemy::Chase));
+        assert_eq!(
+            take_log(&mut manager, entity),
+            ["exit idle", "enter alert", "enter chase"]
+        );
+    }
+
+    #[test]
+    fn test_exit_and_enter_order() {
+        let (mut manager, entity) = spawn(enemy());
+        manager.add_component(entity, Alerted);
+        manager.step(Duration::from_millis(100), &[]);
+        take_log(&mut manager, entity);
+
+        // The shared parent is neither left nor entered again
+        manager.step(Duration::from_secs(1), &[]);
+        assert_eq!(manager.get_state(entity), Some(Enemy::Attack));
+        assert_eq!(
+            take_log(&mut manager, entity),
+            ["exit chase", "enter attack"]
+        );
+
+        // The transition of the parent is taken from the substate, innermost first
+        manager.ecs_instance.remove_component::<Alerted>(entity);
+        manager.step(Duration::from_millis(100), &[]);
+        assert_eq!(manager.get_state(entity), Some(Enemy::Idle));
+        assert_eq!(
+            take_log(&mut manager, entity),
+            ["exit attack", "exit alert", "enter idle"]
+        );
+    }
+
+    #[test]
+    fn test_self_transition() {
+        let (mut manager, entity) = spawn(enemy());
+        manager.add_component(entity, Alerted);
+        manager.step(Duration::from_millis(100), &[]);
+        take_log(&mut manager, entity);
+
+        // Changing to the current state leaves it and enters it again but not its parent
+        assert!(manager.request_state(entity, Enemy::Chase));
+        manager.step(Duration::from_millis(100), &[]);
+        assert_eq!(manager.get_state(entity), Some(Enemy::Chase));
+        assert_eq!(
+            take_log(&mut manager, entity),
+            ["exit chase", "enter chase"]
+        );
+    }
+
+    #[test]
+    fn test_guard_before_timer() {
+        let machine = StateMachine::new(Enemy::Idle)
+            .with_transition(Enemy::Idle, Enemy::Alert, always)
+            .with_transition_after(Enemy::Idle, Enemy::Attack, 0.0);
+        let (mut manager, entity) = spawn(machine);
+
+        manager.step(Duration::from_millis(100), &[]);
+        assert_eq!(manager.get_state(entity), Some(Enemy::Alert));
+    }
+}
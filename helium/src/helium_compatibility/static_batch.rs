@@ -0,0 +1,36 @@
+use crate::helium_compatibility::{Model3d, Transform3d};
+
+/// Non-moving objects that are merged into one model in the renderer so they are drawn
+/// with one draw call per material
+pub struct StaticBatch {
+    objects: Vec<(Model3d, Transform3d)>,
+    renderer_index: Option<usize>,
+}
+
+impl StaticBatch {
+    /// Creates a static batch from the objects to merge
+    ///
+    /// # Arguments
+    ///
+    /// * `objects` - The models to merge with the transform of each model
+    pub fn new(objects: Vec<(Model3d, Transform3d)>) -> Self {
+        Self {
+            objects,
+            renderer_index: None,
+        }
+    }
+
+    pub fn get_objects(&self) -> &[(Model3d, Transform3d)] {
+        &self.objects
+    }
+
+    /// Used internally to link the component to the renderer
+    pub fn set_renderer_index(&mut self, index: usize) {
+        self.renderer_index = Some(index);
+    }
+
+    /// Used internally to get information about the batch from the renderer
+    pub fn get_renderer_index(&self) -> Option<&usize> {
+        self.renderer_index.as_ref()
+    }
+}
@@ -0,0 +1,183 @@
+use helium_renderer::{OverlayText, TextBounds, TextStyle, UiLayout};
+
+/// Text drawn on top of the scene in screen space
+pub struct TextLabel {
+    text: String,
+    position: (f32, f32),
+    style: TextStyle,
+    layout: Option<UiLayout>,
+    bounds: Option<TextBounds>,
+    // The key of the translated string that is shown instead of the text
+    translation_key: Option<String>,
+    // Whether tags in the text draw colors, bold text, and icons
+    markup: bool,
+    renderer_index: Option<usize>,
+    update_flag: bool,
+}
+
+impl TextLabel {
+    /// Creates a new text label
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to draw
+    /// * `position` - Position of the top left of the text in pixels
+    pub fn new(text: String, position: (f32, f32)) -> Self {
+        Self {
+            text,
+            position,
+            style: TextStyle::default(),
+            layout: None,
+            bounds: None,
+            translation_key: None,
+            markup: false,
+            renderer_index: None,
+            update_flag: false,
+        }
+    }
+
+    /// Creates a text label that shows a translated string and changes with the language
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the string in the string tables
+    /// * `position` - Position of the top left of the text in pixels
+    pub fn localized(key: &str, position: (f32, f32)) -> Self {
+        let mut text_label = Self::new(key.to_string(), position);
+        text_label.translation_key = Some(key.to_string());
+        text_label
+    }
+
+    pub fn with_style(mut self, style: TextStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Wraps and aligns the text inside of a box, a layout places the box instead of the
+    /// start of the text
+    pub fn with_bounds(mut self, bounds: TextBounds) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Reads `[b]`, `[color=#hex]`, and `[icon=name]` tags in the text for dialogue and chat
+    /// with colored names, bold words, and button icons. Bold text uses the bold font of the
+    /// style and icons are named with `set_text_icon`
+    pub fn with_markup(mut self) -> Self {
+        self.markup = true;
+        self
+    }
+
+    /// Anchors the text to the screen, the position is ignored while a layout is set
+    pub fn with_layout(mut self, layout: UiLayout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    // Setters
+    pub fn set_text(&mut self, text: String) {
+        self.text = text;
+        self.update_flag = true;
+    }
+
+    pub fn set_position(&mut self, position: (f32, f32)) {
+        self.position = position;
+        self.update_flag = true;
+    }
+
+    pub fn set_style(&mut self, style: TextStyle) {
+        self.style = style;
+        self.update_flag = true;
+    }
+
+    pub fn set_bounds(&mut self, bounds: Option<TextBounds>) {
+        self.bounds = bounds;
+        self.update_flag = true;
+    }
+
+    /// Sets the first line that is drawn when the bounds scroll, `usize::MAX` scrolls to
+    /// the last lines like a chat log
+    pub fn set_scroll(&mut self, scroll: usize) {
+        if let Some(bounds) = self.bounds.as_mut() {
+            bounds.scroll = scroll;
+            self.update_flag = true;
+        }
+    }
+
+    pub fn set_markup(&mut self, markup: bool) {
+        self.markup = markup;
+        self.update_flag = true;
+    }
+
+    pub fn set_layout(&mut self, layout: Option<UiLayout>) {
+        self.layout = layout;
+        self.update_flag = true;
+    }
+
+    /// Shows a translated string, `None` shows the text again
+    pub fn set_translation_key(&mut self, key: Option<String>) {
+        self.translation_key = key;
+        self.update_flag = true;
+    }
+
+    /// Draws the label again, used when the language changes
+    pub fn refresh(&mut self) {
+        self.update_flag = true;
+    }
+
+    pub fn update(&mut self) {
+        self.update_flag = false;
+    }
+
+    // Getters
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn get_position(&self) -> (f32, f32) {
+        self.position
+    }
+
+    pub fn get_style(&self) -> &TextStyle {
+        &self.style
+    }
+
+    pub fn get_layout(&self) -> Option<&UiLayout> {
+        self.layout.as_ref()
+    }
+
+    pub fn get_bounds(&self) -> Option<&TextBounds> {
+        self.bounds.as_ref()
+    }
+
+    pub fn has_markup(&self) -> bool {
+        self.markup
+    }
+
+    pub fn get_translation_key(&self) -> Option<&str> {
+        self.translation_key.as_deref()
+    }
+
+    pub fn get_update_flag(&self) -> bool {
+        self.update_flag
+    }
+
+    /// Used internally to link the component to the renderer
+    pub fn set_renderer_index(&mut self, index: usize) {
+        self.renderer_index = Some(index);
+    }
+
+    /// Used internally to get information about the text from the renderer
+    pub fn get_renderer_index(&self) -> Option<&usize> {
+        self.renderer_index.as_ref()
+    }
+}
+
+impl From<&TextLabel> for OverlayText {
+    fn from(value: &TextLabel) -> Self {
+        OverlayText::new(value.text.clone(), value.position, value.style)
+            .with_layout(value.layout)
+            .with_bounds(value.bounds)
+            .with_markup(value.markup)
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::VecDeque;
+
+use cgmath::{InnerSpace, Quaternion, Rotation, Vector3, Zero};
+use helium_renderer::{DynamicMesh, DynamicVertex};
+
+// A point of the trail and how long ago it was left behind
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TrailPoint {
+    position: Vector3<f32>,
+    age: f32,
+}
+
+/// Leaves a ribbon behind an entity that always faces the camera and fades out over the
+/// lifetime of its points, used for sword swipes, projectiles, and tire marks
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trail {
+    lifetime: f32,
+    start_width: f32,
+    end_width: f32,
+    start_color: [f32; 4],
+    end_color: [f32; 4],
+    // How far the entity moves before a new point is left behind
+    min_distance: f32,
+    max_points: usize,
+    // Where the trail is emitted from relative to the entity, turns with it
+    offset: Vector3<f32>,
+    emitting: bool,
+
+    // The newest point is at the front
+    points: VecDeque<TrailPoint>,
+}
+
+impl Trail {
+    /// Creates a white trail that narrows to nothing at its end
+    ///
+    /// # Arguments
+    ///
+    /// * `lifetime` - How many seconds a point of the trail lasts
+    /// * `width` - The width of the trail at the entity in world units
+    pub fn new(lifetime: f32, width: f32) -> Self {
+        Self {
+            lifetime: lifetime.max(f32::EPSILON),
+            start_width: width,
+            end_width: 0.0,
+            start_color: [1.0, 1.0, 1.0, 1.0],
+            end_color: [1.0, 1.0, 1.0, 0.0],
+            min_distance: 0.1,
+            max_points: 256,
+            offset: Vector3::zero(),
+            emitting: true,
+            points: VecDeque::new(),
+        }
+    }
+
+    /// Sets the width of the trail at the entity and at its end, the width is blended
+    /// between them over the lifetime of the points
+    pub fn with_width(mut self, start: f32, end: f32) -> Self {
+        self.start_width = start;
+        self.end_width = end;
+        self
+    }
+
+    /// Sets the RGBA color of the trail at the entity and at its end, a transparent end
+    /// fades the trail out
+    pub fn with_color(mut self, start: [f32; 4], end: [f32; 4]) -> Self {
+        self.start_color = start;
+        self.end_color = end;
+        self
+    }
+
+    /// Sets how far the entity moves before a new point is left behind, shorter distances
+    /// make smoother curves with more triangles
+    pub fn with_min_distance(mut self, min_distance: f32) -> Self {
+        self.min_distance = min_distance.max(0.0);
+        self
+    }
+
+    /// Limits how many points the trail keeps, the oldest are dropped first
+    pub fn with_max_points(mut self, max_points: usize) -> Self {
+        self.max_points = max_points.max(2);
+        self
+    }
+
+    /// Emits the trail from a point relative to the entity, like the tip of a sword
+    pub fn with_offset(mut self, offset: Vector3<f32>) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Stops leaving new points behind, the trail that is already there fades out
+    pub fn set_emitting(&mut self, emitting: bool) {
+        self.emitting = emitting;
+    }
+
+    pub fn is_emitting(&self) -> bool {
+        self.emitting
+    }
+
+    /// Removes the whole trail, used after teleporting the entity
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    pub fn get_lifetime(&self) -> f32 {
+        self.lifetime
+    }
+
+    pub fn get_num_points(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Ages the points of the trail and leaves a new one behind when the entity has moved
+    /// far enough
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position of the entity
+    /// * `rotation` - The rotation of the entity, it turns the offset
+    /// * `delta_time` - The seconds that passed
+    pub fn update(&mut self, position: Vector3<f32>, rotation: Quaternion<f32>, delta_time: f32) {
+        for point in self.points.iter_mut() {
+            point.age += delta_time;
+        }
+        while self
+            .points
+            .back()
+            .is_some_and(|point| point.age >= self.lifetime)
+        {
+            self.points.pop_back();
+        }
+
+        if !self.emitting {
+            return;
+        }
+
+        let position = position + rotation.rotate_vector(self.offset);
+        // The newest point follows the entity until it is far enough from the one behind it
+        let settled = match (self.points.front(), self.points.get(1)) {
+            (Some(_), Some(previous)) => {
+                (position - previous.position).magnitude() < self.min_distance
+            }
+            _ => false,
+        };
+        if settled {
+            self.points[0] = TrailPoint { position, age: 0.0 };
+        } else {
+            self.points.push_front(TrailPoint { position, age: 0.0 });
+        }
+
+        self.points.truncate(self.max_points);
+    }
+
+    /// Builds the ribbon of the trail facing a camera
+    ///
+    /// # Arguments
+    ///
+    /// * `eye` - The position of the camera
+    ///
+    /// # Returns
+    ///
+    /// The mesh of the ribbon, it is empty when the trail has less than two points
+    pub fn build_mesh(&self, eye: Vector3<f32>) -> DynamicMesh {
+        let mut mesh = DynamicMesh::new();
+        if self.points.len() < 2 {
+            return mesh;
+        }
+
+        let last = self.points.len() - 1;
+        for (index, point) in self.points.iter().enumerate() {
+            // The direction along the trail is averaged with both neighbours
+            let ahead = self.points[index.saturating_sub(1)].position;
+            let behind = self.points[(index + 1).min(last)].position;
+            let along = ahead - behind;
+
+            let side = along.cross(eye - point.position);
+            let side = if side.magnitude2() > f32::EPSILON {
+                side.normalize()
+            } else {
+                Vector3::zero()
+            };
+
+            let amount = (point.age / self.lifetime).clamp(0.0, 1.0);
+            let width = self.start_width + (self.end_width - self.start_width) * amount;
+            let mut color = self.start_color;
+            for (channel, end) in color.iter_mut().zip(self.end_color) {
+                *channel += (end - *channel) * amount;
+            }
+
+            let half = side * width * 0.5;
+            mesh.push_vertex(DynamicVertex::new(point.position + half, color));
+            mesh.push_vertex(DynamicVertex::new(point.position - half, color));
+        }
+
+        for segment in 0..last as u32 {
+            let first = segment * 2;
+            mesh.push_triangle(first, first + 1, first + 2);
+            mesh.push_triangle(first + 1, first + 3, first + 2);
+        }
+
+        mesh
+    }
+}
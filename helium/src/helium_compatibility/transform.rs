@@ -1,10 +1,13 @@
-use cgmath::{One, Quaternion, Vector3, Zero};
+use cgmath::{
+    Deg, Euler, InnerSpace, Matrix3, Matrix4, One, Quaternion, Rotation, Vector3, VectorSpace, Zero,
+};
 use helium_renderer::instance::Instance;
 
 #[derive(Clone, Copy, Debug)]
 pub struct Transform3d {
     position: Vector3<f32>,
     rotation: Quaternion<f32>,
+    scale: Vector3<f32>,
     update_flag: bool,
 }
 
@@ -13,6 +16,7 @@ impl Default for Transform3d {
         Self {
             position: Vector3::zero(),
             rotation: Quaternion::one(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
             update_flag: false,
         }
     }
@@ -23,6 +27,7 @@ impl Transform3d {
         Self {
             position,
             rotation,
+            scale: Vector3::new(1.0, 1.0, 1.0),
             update_flag: false,
         }
     }
@@ -71,10 +76,139 @@ impl Transform3d {
         self.update_flag = true;
     }
 
+    /// The size of the model along each of its axes, colliders keep their own size
+    pub fn get_scale(&self) -> &Vector3<f32> {
+        &self.scale
+    }
+
+    pub fn set_scale(&mut self, new_scale: Vector3<f32>) {
+        self.scale = new_scale;
+        self.update_flag = true;
+    }
+
     pub fn get_transform(&self) -> (&Vector3<f32>, &Quaternion<f32>) {
         (&self.position, &self.rotation)
     }
 
+    /// The direction the transform faces, models face down the negative z axis
+    pub fn forward(&self) -> Vector3<f32> {
+        self.rotation.rotate_vector(-Vector3::unit_z())
+    }
+
+    pub fn right(&self) -> Vector3<f32> {
+        self.rotation.rotate_vector(Vector3::unit_x())
+    }
+
+    pub fn up(&self) -> Vector3<f32> {
+        self.rotation.rotate_vector(Vector3::unit_y())
+    }
+
+    /// Turns the transform so it faces a point with its up staying as close to the world
+    /// up as possible
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The point to face
+    pub fn look_at(&mut self, target: Vector3<f32>) {
+        let forward = target - self.position;
+        if forward.magnitude2() <= f32::EPSILON {
+            return;
+        }
+        let forward = forward.normalize();
+
+        // Looking straight up or down uses the z axis as up instead
+        let world_up = if forward.y.abs() < 0.999 {
+            Vector3::unit_y()
+        } else {
+            Vector3::unit_z()
+        };
+        let right = forward.cross(world_up).normalize();
+        let up = right.cross(forward);
+
+        self.rotation = Quaternion::from(Matrix3::from_cols(right, up, -forward));
+        self.update_flag = true;
+    }
+
+    /// The rotation as angles around the x, y and z axes in degrees
+    pub fn get_euler_angles(&self) -> Vector3<f32> {
+        let euler = Euler::from(self.rotation);
+        Vector3::new(
+            Deg::from(euler.x).0,
+            Deg::from(euler.y).0,
+            Deg::from(euler.z).0,
+        )
+    }
+
+    /// Sets the rotation from angles around the x, y and z axes in degrees
+    pub fn set_euler_angles(&mut self, angles: Vector3<f32>) {
+        self.rotation = Quaternion::from(Euler::new(Deg(angles.x), Deg(angles.y), Deg(angles.z)));
+        self.update_flag = true;
+    }
+
+    /// Blends towards another transform, the rotation is blended linearly which is fast
+    /// and close to `slerp` for small differences
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The transform at `amount` 1
+    /// * `amount` - How far to blend from 0 to 1
+    pub fn lerp(&self, other: &Transform3d, amount: f32) -> Self {
+        let mut target = other.rotation;
+        // Blends the short way around
+        if self.rotation.dot(target) < 0.0 {
+            target = -target;
+        }
+
+        let mut transform = Self::new(
+            self.position.lerp(other.position, amount),
+            self.rotation.nlerp(target, amount),
+        );
+        transform.scale = self.scale.lerp(other.scale, amount);
+        transform
+    }
+
+    /// Blends towards another transform, the rotation turns at a constant speed
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The transform at `amount` 1
+    /// * `amount` - How far to blend from 0 to 1
+    pub fn slerp(&self, other: &Transform3d, amount: f32) -> Self {
+        let mut target = other.rotation;
+        if self.rotation.dot(target) < 0.0 {
+            target = -target;
+        }
+
+        let mut transform = Self::new(
+            self.position.lerp(other.position, amount),
+            self.rotation.slerp(target, amount),
+        );
+        transform.scale = self.scale.lerp(other.scale, amount);
+        transform
+    }
+
+    /// The matrix that moves points from the space of the transform to the world
+    pub fn to_matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.position)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+
+    /// Creates a transform from a matrix with a translation, rotation, and scale, the
+    /// matrix can not be sheared
+    pub fn from_matrix(matrix: &Matrix4<f32>) -> Self {
+        let (x, y, z) = (
+            matrix.x.truncate(),
+            matrix.y.truncate(),
+            matrix.z.truncate(),
+        );
+        let rotation = Matrix3::from_cols(x.normalize(), y.normalize(), z.normalize());
+
+        let mut transform = Self::new(matrix.w.truncate(), Quaternion::from(rotation));
+        transform.scale = Vector3::new(x.magnitude(), y.magnitude(), z.magnitude());
+        transform
+    }
+
     // Static functions
     pub fn translate(transform: &mut Self, translation: Vector3<f32>) {
         transform.position += translation;
@@ -92,11 +226,26 @@ impl Transform3d {
     // }
 }
 
+impl From<Transform3d> for Matrix4<f32> {
+    fn from(value: Transform3d) -> Self {
+        value.to_matrix()
+    }
+}
+
 impl From<Transform3d> for Instance {
     fn from(value: Transform3d) -> Self {
-        Instance {
-            position: value.position,
-            rotation: value.rotation,
-        }
+        Instance::new(value.position, value.rotation).with_scale(value.scale)
+    }
+}
+
+impl From<Transform3d> for (Vector3<f32>, Quaternion<f32>) {
+    fn from(value: Transform3d) -> Self {
+        (value.position, value.rotation)
+    }
+}
+
+impl From<Transform3d> for (Vector3<f32>, Quaternion<f32>, Vector3<f32>) {
+    fn from(value: Transform3d) -> Self {
+        (value.position, value.rotation, value.scale)
     }
 }
@@ -0,0 +1,20 @@
+/// Shows or hides the model of an entity without despawning it, the model keeps its place
+/// on the gpu so showing it again is cheap
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Visible(pub bool);
+
+impl Default for Visible {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+impl Visible {
+    pub fn is_visible(&self) -> bool {
+        self.0
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.0 = visible;
+    }
+}
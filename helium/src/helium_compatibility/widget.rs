@@ -0,0 +1,471 @@
+use helium_renderer::{OverlayQuad, OverlayText, TextStyle, UiLayout};
+
+use super::Cursor;
+
+/// A colored background drawn in the overlay to group other widgets
+pub struct Panel {
+    layout: UiLayout,
+    size: (f32, f32),
+    color: [f32; 4],
+    quad_index: Option<usize>,
+    update_flag: bool,
+}
+
+impl Panel {
+    /// Creates a new panel
+    ///
+    /// # Arguments
+    ///
+    /// * `layout` - Where to place the panel on the screen
+    /// * `size` - The width and height of the panel in pixels
+    /// * `color` - The RGBA color of the background
+    pub fn new(layout: UiLayout, size: (f32, f32), color: [f32; 4]) -> Self {
+        Self {
+            layout,
+            size,
+            color,
+            quad_index: None,
+            update_flag: false,
+        }
+    }
+
+    // Setters
+    pub fn set_layout(&mut self, layout: UiLayout) {
+        self.layout = layout;
+        self.update_flag = true;
+    }
+
+    pub fn set_size(&mut self, size: (f32, f32)) {
+        self.size = size;
+        self.update_flag = true;
+    }
+
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        self.color = color;
+        self.update_flag = true;
+    }
+
+    pub fn update(&mut self) {
+        self.update_flag = false;
+    }
+
+    // Getters
+    pub fn get_layout(&self) -> &UiLayout {
+        &self.layout
+    }
+
+    pub fn get_size(&self) -> (f32, f32) {
+        self.size
+    }
+
+    pub fn get_color(&self) -> [f32; 4] {
+        self.color
+    }
+
+    pub fn get_update_flag(&self) -> bool {
+        self.update_flag
+    }
+
+    /// Used internally to create the quad drawn in the overlay
+    pub fn quad(&self) -> OverlayQuad {
+        OverlayQuad::new((0.0, 0.0), self.size, self.color).with_layout(Some(self.layout))
+    }
+
+    /// Used internally to link the component to the renderer
+    pub fn set_quad_index(&mut self, index: usize) {
+        self.quad_index = Some(index);
+    }
+
+    /// Used internally to get information about the panel from the renderer
+    pub fn get_quad_index(&self) -> Option<&usize> {
+        self.quad_index.as_ref()
+    }
+}
+
+/// The interaction state of a button
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ButtonState {
+    #[default]
+    Normal,
+    Hovered,
+    Pressed,
+}
+
+/// The background colors of a button for each state
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ButtonColors {
+    pub normal: [f32; 4],
+    pub hovered: [f32; 4],
+    pub pressed: [f32; 4],
+}
+
+impl Default for ButtonColors {
+    fn default() -> Self {
+        Self {
+            normal: [0.2, 0.2, 0.2, 1.0],
+            hovered: [0.3, 0.3, 0.3, 1.0],
+            pressed: [0.1, 0.1, 0.1, 1.0],
+        }
+    }
+}
+
+/// A clickable button with a text label drawn in the overlay
+pub struct Button {
+    layout: UiLayout,
+    size: (f32, f32),
+    text: String,
+    text_style: TextStyle,
+    colors: ButtonColors,
+    state: ButtonState,
+    // If the press started on this button
+    pressed_inside: bool,
+    clicked: bool,
+    quad_index: Option<usize>,
+    text_index: Option<usize>,
+    update_flag: bool,
+}
+
+impl Button {
+    /// Creates a new button
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text drawn in the center of the button
+    /// * `layout` - Where to place the button on the screen
+    /// * `size` - The width and height of the button in pixels
+    pub fn new(text: String, layout: UiLayout, size: (f32, f32)) -> Self {
+        Self {
+            layout,
+            size,
+            text,
+            text_style: TextStyle::default(),
+            colors: ButtonColors::default(),
+            state: ButtonState::Normal,
+            pressed_inside: false,
+            clicked: false,
+            quad_index: None,
+            text_index: None,
+            update_flag: false,
+        }
+    }
+
+    pub fn with_text_style(mut self, text_style: TextStyle) -> Self {
+        self.text_style = text_style;
+        self
+    }
+
+    pub fn with_colors(mut self, colors: ButtonColors) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Used internally to update the state of the button from the cursor
+    ///
+    /// # Arguments
+    ///
+    /// * `cursor` - The current state of the cursor
+    /// * `screen_size` - The width and height of the screen in pixels
+    pub fn handle_cursor(&mut self, cursor: &Cursor, screen_size: (f32, f32)) {
+        let hovered = self
+            .layout
+            .contains(screen_size, self.size, cursor.position);
+
+        // A click is a press and release that both happen on the button
+        self.clicked = hovered && self.pressed_inside && !cursor.left_pressed;
+
+        if cursor.left_pressed {
+            if self.state == ButtonState::Hovered {
+                self.pressed_inside = true;
+            }
+        } else {
+            self.pressed_inside = false;
+        }
+
+        let state = match (hovered, self.pressed_inside) {
+            (true, true) => ButtonState::Pressed,
+            (true, false) if !cursor.left_pressed => ButtonState::Hovered,
+            _ => ButtonState::Normal,
+        };
+
+        if state != self.state {
+            self.state = state;
+            self.update_flag = true;
+        }
+    }
+
+    // Setters
+    pub fn set_text(&mut self, text: String) {
+        self.text = text;
+        self.update_flag = true;
+    }
+
+    pub fn set_layout(&mut self, layout: UiLayout) {
+        self.layout = layout;
+        self.update_flag = true;
+    }
+
+    pub fn set_size(&mut self, size: (f32, f32)) {
+        self.size = size;
+        self.update_flag = true;
+    }
+
+    pub fn set_text_style(&mut self, text_style: TextStyle) {
+        self.text_style = text_style;
+        self.update_flag = true;
+    }
+
+    pub fn set_colors(&mut self, colors: ButtonColors) {
+        self.colors = colors;
+        self.update_flag = true;
+    }
+
+    pub fn update(&mut self) {
+        self.update_flag = false;
+    }
+
+    // Getters
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn get_layout(&self) -> &UiLayout {
+        &self.layout
+    }
+
+    pub fn get_size(&self) -> (f32, f32) {
+        self.size
+    }
+
+    pub fn get_state(&self) -> ButtonState {
+        self.state
+    }
+
+    /// Checks if the button was clicked during the last update
+    pub fn was_clicked(&self) -> bool {
+        self.clicked
+    }
+
+    pub fn get_update_flag(&self) -> bool {
+        self.update_flag
+    }
+
+    /// Used internally to create the background quad drawn in the overlay
+    pub fn quad(&self) -> OverlayQuad {
+        let color = match self.state {
+            ButtonState::Normal => self.colors.normal,
+            ButtonState::Hovered => self.colors.hovered,
+            ButtonState::Pressed => self.colors.pressed,
+        };
+
+        OverlayQuad::new((0.0, 0.0), self.size, color).with_layout(Some(self.layout))
+    }
+
+    /// Used internally to create the text drawn in the overlay
+    pub fn overlay_text(&self) -> OverlayText {
+        OverlayText::new(self.text.clone(), (0.0, 0.0), self.text_style)
+            .with_layout(Some(self.layout.centered_in(self.size)))
+    }
+
+    /// Used internally to link the component to the renderer
+    pub fn set_renderer_indices(&mut self, quad_index: usize, text_index: usize) {
+        self.quad_index = Some(quad_index);
+        self.text_index = Some(text_index);
+    }
+
+    /// Used internally to get the quad index of the button in the renderer
+    pub fn get_quad_index(&self) -> Option<&usize> {
+        self.quad_index.as_ref()
+    }
+
+    /// Used internally to get the text index of the button in the renderer
+    pub fn get_text_index(&self) -> Option<&usize> {
+        self.text_index.as_ref()
+    }
+}
+
+/// A horizontal slider that can be dragged to pick a value in a range
+pub struct Slider {
+    layout: UiLayout,
+    size: (f32, f32),
+    value: f32,
+    min: f32,
+    max: f32,
+    track_color: [f32; 4],
+    handle_color: [f32; 4],
+    dragging: bool,
+    changed: bool,
+    track_index: Option<usize>,
+    handle_index: Option<usize>,
+    update_flag: bool,
+}
+
+impl Slider {
+    /// Creates a new slider with a range of 0 to 1
+    ///
+    /// # Arguments
+    ///
+    /// * `layout` - Where to place the slider on the screen
+    /// * `size` - The width and height of the slider in pixels
+    /// * `value` - The starting value of the slider
+    pub fn new(layout: UiLayout, size: (f32, f32), value: f32) -> Self {
+        Self {
+            layout,
+            size,
+            value: value.clamp(0.0, 1.0),
+            min: 0.0,
+            max: 1.0,
+            track_color: [0.2, 0.2, 0.2, 1.0],
+            handle_color: [0.8, 0.8, 0.8, 1.0],
+            dragging: false,
+            changed: false,
+            track_index: None,
+            handle_index: None,
+            update_flag: false,
+        }
+    }
+
+    /// Sets the range of values the slider can pick from
+    pub fn with_range(mut self, min: f32, max: f32) -> Self {
+        self.min = min;
+        self.max = max;
+        self.value = self.value.clamp(min, max);
+        self
+    }
+
+    pub fn with_colors(mut self, track_color: [f32; 4], handle_color: [f32; 4]) -> Self {
+        self.track_color = track_color;
+        self.handle_color = handle_color;
+        self
+    }
+
+    // The handle is a square with the height of the slider
+    fn handle_size(&self) -> (f32, f32) {
+        (self.size.1, self.size.1)
+    }
+
+    /// Used internally to update the value of the slider from the cursor
+    ///
+    /// # Arguments
+    ///
+    /// * `cursor` - The current state of the cursor
+    /// * `screen_size` - The width and height of the screen in pixels
+    pub fn handle_cursor(&mut self, cursor: &Cursor, screen_size: (f32, f32)) {
+        self.changed = false;
+
+        if !cursor.left_pressed {
+            self.dragging = false;
+            return;
+        }
+
+        if !self.dragging {
+            // Dragging can only start when the press begins on the slider
+            if self
+                .layout
+                .contains(screen_size, self.size, cursor.position)
+            {
+                self.dragging = true;
+            } else {
+                return;
+            }
+        }
+
+        let (x, _) = self.layout.resolve_rect(screen_size, self.size);
+        let handle_width = self.handle_size().0;
+        let travel = (self.size.0 - handle_width).max(f32::EPSILON);
+        let t = ((cursor.position.0 - x - handle_width / 2.0) / travel).clamp(0.0, 1.0);
+
+        let value = self.min + t * (self.max - self.min);
+        if value != self.value {
+            self.value = value;
+            self.changed = true;
+            self.update_flag = true;
+        }
+    }
+
+    // Setters
+    pub fn set_value(&mut self, value: f32) {
+        self.value = value.clamp(self.min, self.max);
+        self.update_flag = true;
+    }
+
+    pub fn set_layout(&mut self, layout: UiLayout) {
+        self.layout = layout;
+        self.update_flag = true;
+    }
+
+    pub fn set_size(&mut self, size: (f32, f32)) {
+        self.size = size;
+        self.update_flag = true;
+    }
+
+    pub fn update(&mut self) {
+        self.update_flag = false;
+    }
+
+    // Getters
+    pub fn get_value(&self) -> f32 {
+        self.value
+    }
+
+    pub fn get_range(&self) -> (f32, f32) {
+        (self.min, self.max)
+    }
+
+    pub fn get_layout(&self) -> &UiLayout {
+        &self.layout
+    }
+
+    pub fn get_size(&self) -> (f32, f32) {
+        self.size
+    }
+
+    /// Checks if the value was changed by the cursor during the last update
+    pub fn was_changed(&self) -> bool {
+        self.changed
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.dragging
+    }
+
+    pub fn get_update_flag(&self) -> bool {
+        self.update_flag
+    }
+
+    /// Used internally to create the track quad drawn in the overlay
+    pub fn track_quad(&self) -> OverlayQuad {
+        OverlayQuad::new((0.0, 0.0), self.size, self.track_color).with_layout(Some(self.layout))
+    }
+
+    /// Used internally to create the handle quad drawn in the overlay
+    pub fn handle_quad(&self) -> OverlayQuad {
+        let handle_size = self.handle_size();
+        let range = self.max - self.min;
+        let t = if range == 0.0 {
+            0.0
+        } else {
+            (self.value - self.min) / range
+        };
+
+        let offset = (t * (self.size.0 - handle_size.0), 0.0);
+
+        OverlayQuad::new((0.0, 0.0), handle_size, self.handle_color)
+            .with_layout(Some(self.layout.inner(self.size, offset, handle_size)))
+    }
+
+    /// Used internally to link the component to the renderer
+    pub fn set_renderer_indices(&mut self, track_index: usize, handle_index: usize) {
+        self.track_index = Some(track_index);
+        self.handle_index = Some(handle_index);
+    }
+
+    /// Used internally to get the track index of the slider in the renderer
+    pub fn get_track_index(&self) -> Option<&usize> {
+        self.track_index.as_ref()
+    }
+
+    /// Used internally to get the handle index of the slider in the renderer
+    pub fn get_handle_index(&self) -> Option<&usize> {
+        self.handle_index.as_ref()
+    }
+}
@@ -0,0 +1,399 @@
+use cgmath::{Quaternion, Vector3, Zero};
+use helium_renderer::{
+    FontHandle, LinearRgba, OverlayQuad, OverlayText, ScreenPoint, SdfPlacement, SdfText,
+    TextStyle, UiLayout,
+};
+
+// Limits of the scale of world ui that shrinks with distance
+const MIN_WORLD_UI_SCALE: f32 = 0.1;
+const MAX_WORLD_UI_SCALE: f32 = 4.0;
+
+/// How world ui is drawn on the screen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldUiOptions {
+    /// Offset from the position of the entity in world units
+    pub offset: Vector3<f32>,
+    /// Distance from the camera that the ui is drawn at its normal size,
+    /// the ui does not scale with distance if this is not set
+    pub reference_distance: Option<f32>,
+    /// Hides the ui behind objects in the scene
+    pub occlude: bool,
+}
+
+impl Default for WorldUiOptions {
+    fn default() -> Self {
+        Self {
+            offset: Vector3::zero(),
+            reference_distance: None,
+            occlude: false,
+        }
+    }
+}
+
+impl WorldUiOptions {
+    // The scale of the ui at a distance from the camera
+    fn scale(&self, distance: f32) -> f32 {
+        match self.reference_distance {
+            Some(reference_distance) => (reference_distance / distance.max(f32::EPSILON))
+                .clamp(MIN_WORLD_UI_SCALE, MAX_WORLD_UI_SCALE),
+            None => 1.0,
+        }
+    }
+
+    // The depth to draw the ui at if it is occluded
+    fn depth(&self, screen_point: &ScreenPoint) -> Option<f32> {
+        self.occlude.then_some(screen_point.depth)
+    }
+}
+
+/// Text that follows the `Transform3d` of its entity, like a nameplate
+pub struct WorldText {
+    text: String,
+    style: TextStyle,
+    options: WorldUiOptions,
+    renderer_index: Option<usize>,
+}
+
+impl WorldText {
+    /// Creates text centered on the position of the entity
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to draw
+    pub fn new(text: String) -> Self {
+        Self {
+            text,
+            style: TextStyle::default(),
+            options: WorldUiOptions::default(),
+            renderer_index: None,
+        }
+    }
+
+    pub fn with_style(mut self, style: TextStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_offset(mut self, offset: Vector3<f32>) -> Self {
+        self.options.offset = offset;
+        self
+    }
+
+    /// Scales the text down as it moves away from the camera
+    pub fn with_distance_scaling(mut self, reference_distance: f32) -> Self {
+        self.options.reference_distance = Some(reference_distance);
+        self
+    }
+
+    /// Hides the text behind objects in the scene
+    pub fn with_occlusion(mut self, occlude: bool) -> Self {
+        self.options.occlude = occlude;
+        self
+    }
+
+    // Setters
+    pub fn set_text(&mut self, text: String) {
+        self.text = text;
+    }
+
+    pub fn set_style(&mut self, style: TextStyle) {
+        self.style = style;
+    }
+
+    pub fn set_options(&mut self, options: WorldUiOptions) {
+        self.options = options;
+    }
+
+    // Getters
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn get_style(&self) -> &TextStyle {
+        &self.style
+    }
+
+    pub fn get_options(&self) -> &WorldUiOptions {
+        &self.options
+    }
+
+    /// Used internally to find where the text is in the world
+    pub fn world_position(&self, entity_position: Vector3<f32>) -> Vector3<f32> {
+        entity_position + self.options.offset
+    }
+
+    /// Used internally to create the text drawn on the screen
+    pub fn overlay_text(&self, screen_point: &ScreenPoint) -> OverlayText {
+        let scale = self.options.scale(screen_point.distance);
+
+        let mut style = self.style.with_size(self.style.size * scale);
+        if let Some(outline) = style.outline.as_mut() {
+            outline.thickness *= scale;
+        }
+
+        OverlayText::new(self.text.clone(), screen_point.position, style)
+            .with_layout(Some(UiLayout::centered_at(screen_point.position)))
+            .with_depth(self.options.depth(screen_point))
+    }
+
+    /// Used internally to link the component to the renderer
+    pub fn set_renderer_index(&mut self, index: usize) {
+        self.renderer_index = Some(index);
+    }
+
+    /// Used internally to get information about the text from the renderer
+    pub fn get_renderer_index(&self) -> Option<&usize> {
+        self.renderer_index.as_ref()
+    }
+}
+
+/// Text drawn in the world at its entity from a signed distance field, unlike
+/// `WorldText` it has a size in world units and stays sharp up close, like a sign
+pub struct WorldLabel {
+    text: SdfText,
+    offset: Vector3<f32>,
+    billboard: bool,
+    renderer_index: Option<usize>,
+}
+
+impl WorldLabel {
+    /// Creates a label centered on the position of the entity that faces the camera
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to draw
+    /// * `size` - The height of a line of the text in world units
+    pub fn new(text: String, size: f32) -> Self {
+        Self {
+            text: SdfText::billboard(text, Vector3::zero(), size),
+            offset: Vector3::zero(),
+            billboard: true,
+            renderer_index: None,
+        }
+    }
+
+    pub fn with_font(mut self, font: FontHandle) -> Self {
+        self.text = self.text.with_font(font);
+        self
+    }
+
+    pub fn with_color<C>(mut self, color: C) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        self.text = self.text.with_color(color);
+        self
+    }
+
+    /// Draws an outline around the text
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The color of the outline
+    /// * `width` - The width of the outline as a fraction of the size of the text
+    pub fn with_outline<C>(mut self, color: C, width: f32) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        self.text = self.text.with_outline(color, width);
+        self
+    }
+
+    /// Draws a blurred shadow behind the text
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The color of the shadow
+    /// * `offset` - The offset of the shadow as a fraction of the size of the text
+    /// * `softness` - How blurred the shadow is from 0 to 1
+    pub fn with_shadow<C>(mut self, color: C, offset: (f32, f32), softness: f32) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        self.text = self.text.with_shadow(color, offset, softness);
+        self
+    }
+
+    pub fn with_offset(mut self, offset: Vector3<f32>) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Turns the text with the rotation of the entity instead of facing the camera, the
+    /// text is written along the x axis of the entity
+    pub fn with_entity_rotation(mut self) -> Self {
+        self.billboard = false;
+        self
+    }
+
+    // Setters
+    pub fn set_text(&mut self, text: String) {
+        self.text.text = text;
+    }
+
+    pub fn set_size(&mut self, size: f32) {
+        self.text.size = size;
+    }
+
+    pub fn set_offset(&mut self, offset: Vector3<f32>) {
+        self.offset = offset;
+    }
+
+    // Getters
+    pub fn get_text(&self) -> &str {
+        &self.text.text
+    }
+
+    pub fn get_size(&self) -> f32 {
+        self.text.size
+    }
+
+    pub fn get_offset(&self) -> &Vector3<f32> {
+        &self.offset
+    }
+
+    /// Used internally to create the text drawn at the entity
+    pub fn sdf_text(&self, position: Vector3<f32>, rotation: Quaternion<f32>) -> SdfText {
+        let position = position + rotation * self.offset;
+        let placement = if self.billboard {
+            SdfPlacement::Billboard { position }
+        } else {
+            SdfPlacement::World { position, rotation }
+        };
+
+        SdfText {
+            placement,
+            ..self.text.clone()
+        }
+    }
+
+    /// Used internally to link the component to the renderer
+    pub fn set_renderer_index(&mut self, index: usize) {
+        self.renderer_index = Some(index);
+    }
+
+    /// Used internally to get information about the text from the renderer
+    pub fn get_renderer_index(&self) -> Option<&usize> {
+        self.renderer_index.as_ref()
+    }
+}
+
+/// A bar that follows the `Transform3d` of its entity, like a health bar
+pub struct WorldBar {
+    value: f32,
+    size: (f32, f32),
+    fill_color: [f32; 4],
+    background_color: [f32; 4],
+    options: WorldUiOptions,
+    background_index: Option<usize>,
+    fill_index: Option<usize>,
+}
+
+impl WorldBar {
+    /// Creates a full bar centered on the position of the entity
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The width and height of the bar in pixels
+    pub fn new(size: (f32, f32)) -> Self {
+        Self {
+            value: 1.0,
+            size,
+            fill_color: [0.8, 0.1, 0.1, 1.0],
+            background_color: [0.1, 0.1, 0.1, 0.8],
+            options: WorldUiOptions::default(),
+            background_index: None,
+            fill_index: None,
+        }
+    }
+
+    pub fn with_colors(mut self, fill_color: [f32; 4], background_color: [f32; 4]) -> Self {
+        self.fill_color = fill_color;
+        self.background_color = background_color;
+        self
+    }
+
+    pub fn with_offset(mut self, offset: Vector3<f32>) -> Self {
+        self.options.offset = offset;
+        self
+    }
+
+    /// Scales the bar down as it moves away from the camera
+    pub fn with_distance_scaling(mut self, reference_distance: f32) -> Self {
+        self.options.reference_distance = Some(reference_distance);
+        self
+    }
+
+    /// Hides the bar behind objects in the scene
+    pub fn with_occlusion(mut self, occlude: bool) -> Self {
+        self.options.occlude = occlude;
+        self
+    }
+
+    // Setters
+    /// Sets how full the bar is from 0 to 1
+    pub fn set_value(&mut self, value: f32) {
+        self.value = value.clamp(0.0, 1.0);
+    }
+
+    pub fn set_size(&mut self, size: (f32, f32)) {
+        self.size = size;
+    }
+
+    pub fn set_options(&mut self, options: WorldUiOptions) {
+        self.options = options;
+    }
+
+    // Getters
+    pub fn get_value(&self) -> f32 {
+        self.value
+    }
+
+    pub fn get_size(&self) -> (f32, f32) {
+        self.size
+    }
+
+    pub fn get_options(&self) -> &WorldUiOptions {
+        &self.options
+    }
+
+    /// Used internally to find where the bar is in the world
+    pub fn world_position(&self, entity_position: Vector3<f32>) -> Vector3<f32> {
+        entity_position + self.options.offset
+    }
+
+    /// Used internally to create the background and fill quads drawn on the screen
+    pub fn quads(&self, screen_point: &ScreenPoint) -> (OverlayQuad, OverlayQuad) {
+        let scale = self.options.scale(screen_point.distance);
+        let size = (self.size.0 * scale, self.size.1 * scale);
+        let depth = self.options.depth(screen_point);
+        let layout = UiLayout::centered_at(screen_point.position);
+
+        let fill_size = (size.0 * self.value, size.1);
+
+        (
+            OverlayQuad::new(screen_point.position, size, self.background_color)
+                .with_layout(Some(layout))
+                .with_depth(depth),
+            OverlayQuad::new(screen_point.position, fill_size, self.fill_color)
+                .with_layout(Some(layout.inner(size, (0.0, 0.0), fill_size)))
+                .with_depth(depth),
+        )
+    }
+
+    /// Used internally to link the component to the renderer
+    pub fn set_renderer_indices(&mut self, background_index: usize, fill_index: usize) {
+        self.background_index = Some(background_index);
+        self.fill_index = Some(fill_index);
+    }
+
+    /// Used internally to get the background index of the bar in the renderer
+    pub fn get_background_index(&self) -> Option<&usize> {
+        self.background_index.as_ref()
+    }
+
+    /// Used internally to get the fill index of the bar in the renderer
+    pub fn get_fill_index(&self) -> Option<&usize> {
+        self.fill_index.as_ref()
+    }
+}
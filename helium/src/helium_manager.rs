@@ -1,49 +1,513 @@
-use crate::helium_compatibility::{Camera3d, Model3d, Transform3d};
+use crate::editor_camera::{EditorCamera, EditorCameraState};
+use crate::events::Events;
+use crate::gizmo::{Gizmo, GizmoAction, GizmoMode, GizmoState};
+use crate::helium_compatibility::{
+    Button, Camera3d, Cursor, DamageEvent, DeathEvent, Decal, GroundState, Health, Highlighted,
+    HudImage, Interactable, InteractionEvent, Label, Model3d, Panel, Reflective, Slider,
+    StateChanged, StateMachine, StaticBatch, TextLabel, Transform3d, Visible, WorldBar, WorldLabel,
+    WorldText,
+};
+use crate::interaction::{Interaction, InteractionState};
+use crate::localization::Localization;
+use crate::logging::LogConsole;
+use crate::pacing::{FixedClock, UpdatePacing};
+use crate::profiling::{ProfilerOverlay, SystemTimings};
+use crate::rng::Rng;
+use crate::scene_file::{self, SceneEntity};
+use crate::scene_graph::{self, RendererHandle, SceneGraphNode};
+use crate::scenes::{LoadingScreen, SceneLoad, SceneLoadProgress, SceneLoaded};
+use crate::streaming::{ChunkAction, SceneChunk, WorldStreamer};
+use crate::tasks::{TaskComplete, TaskHandle, TaskPool};
+use crate::time_of_day::TimeOfDay;
+use crate::undo::{ComponentEdit, EditCommand, SpawnEdit, TransformEdit, UndoHistory};
+use crate::{
+    PickFunction, PrefabFunction, SceneFunction, StartupFunction, TaskFunction, UpdateFunction,
+};
+use cgmath::{EuclideanSpace, InnerSpace, Zero};
 pub use cgmath::{Quaternion, Vector3};
-pub use helium_ecs::{Entity, HeliumECS};
-use helium_renderer::{HeliumState, Light};
+use helium_collisions::{
+    collider::{RectangleCollider, StationaryPlaneCollider},
+    spatial::SpatialGrid,
+};
+pub use helium_ecs::{ComponentMap, Entity, HeliumECS, WorldId};
+use helium_nav::{agent::NavAgent, navmesh::NavMesh};
+use helium_renderer::{
+    exposure_from_ev100, profile_frame, profile_span, set_profiling, Aabb, AmbientOcclusionBake,
+    BoundingSphere, CustomRenderPass, DebugLine, DebugText, DecalTexture, DynamicResolution,
+    FontHandle, HeliumState, LensFlare, Light, LinearRgba, OverlayQuad, OverlayText, PickRequest,
+    PostSettings, RenderPassHandle, RenderStage, RenderStats, RendererCapabilities, ScatterRegion,
+    ScatterSettings, ScreenOverlay, ScreenOverlayHandle, SdfText, ShadowSettings, SpriteHandle,
+    StaticBatchObject, TextureAnimation,
+};
+use log::{error, info, warn};
+use std::any::TypeId;
 pub use std::cell::{Ref, RefMut};
 pub use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::fs;
+use std::future::Future;
+use std::hash::Hash;
+use std::io;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use wgpu::SurfaceConfiguration;
+use winit::event::DeviceEvent;
+
+// Width of the cells of the proximity grid until the game sets its own
+const DEFAULT_SPATIAL_CELL_SIZE: f32 = 8.0;
 
 pub struct HeliumManager {
     pub ecs_instance: HeliumECS,
-    pub renderer_instance: Arc<Mutex<HeliumState>>,
+    // `None` for managers that run without a gpu, nothing is drawn for them
+    pub renderer_instance: Option<Arc<Mutex<HeliumState>>>,
 
     // For easy access to the camera
     pub camera_id: Option<Entity>,
 
     pub time: Instant,
     pub delta_time: Instant,
+
+    // The time the current update covers, the systems read it instead of measuring the
+    // time themselves so every system of an update moves by the same amount
+    pub(crate) frame_time: Duration,
+    update_start: Instant,
+
+    // State of the mouse cursor for the ui
+    pub cursor: Cursor,
+
+    // Mouse motion since the last update that turns the cameras with a controller
+    pub look_delta: (f32, f32),
+
+    // Chunks of the world that are streamed in around the camera
+    pub streamer: WorldStreamer,
+
+    // Picks waiting to be read back from the gpu with the function to call with the result
+    picks: Vec<(PickRequest, PickFunction)>,
+
+    // Walkable surfaces that nav agents find their paths over
+    navmesh: Option<NavMesh>,
+
+    // Functions that create the entities of each prefab by name
+    prefabs: HashMap<String, PrefabFunction>,
+
+    // Events that systems and update functions send to each other
+    events: Events,
+
+    // Work running on background threads
+    tasks: TaskPool,
+
+    // Shared random numbers so a scene can be replayed from its seed
+    rng: Rng,
+
+    // How long the update functions and engine systems took last frame
+    pub(crate) system_timings: SystemTimings,
+    stats_overlay: bool,
+    // The flamegraph of the last frames when it is shown
+    profiler_overlay: Option<ProfilerOverlay>,
+
+    // How often the updates and the fixed physics ticks run
+    pacing: UpdatePacing,
+    fixed_clock: FixedClock,
+
+    // The latest lines of the log when the engine logger keeps them
+    pub(crate) log_console: Option<LogConsole>,
+
+    // The functions that create the world, run again when the world restarts
+    pub(crate) startup_functions: Vec<StartupFunction>,
+
+    // Functions that create the entities of each scene by name
+    scenes: HashMap<String, SceneFunction>,
+    current_scene: Option<String>,
+    loading_screen: LoadingScreen,
+    // The scene that is loading with its loading screen
+    scene_load: Option<SceneLoad>,
+
+    // Update functions that run with their world active
+    world_systems: Vec<(WorldId, UpdateFunction)>,
+    // The systems that run the state machines of each type of state
+    state_machine_systems: Vec<(TypeId, UpdateFunction)>,
+    // Worlds whose models are not drawn
+    hidden_worlds: HashSet<WorldId>,
+
+    // The fly camera for editing the scene when it is turned on
+    editor_camera: Option<EditorCameraState>,
+    // The entity that is being edited
+    selected: Option<Entity>,
+    // The handles that edit the transform of the selected entity
+    gizmo: Option<GizmoState>,
+
+    // Edits that can be undone and redone
+    undo_history: UndoHistory,
+    // The hidden world despawned entities wait in until their edit is forgotten
+    removed_world: Option<WorldId>,
+
+    // The clock that moves the sun of the procedural sky with the light it drives
+    time_of_day: Option<TimeOfDay>,
+    sun: Option<Entity>,
+
+    // The translated strings that localized text labels show
+    pub(crate) localization: Localization,
+
+    // The positions of the entities with a transform, rebuilt every update for proximity
+    // queries
+    spatial_index: SpatialGrid<Entity>,
+
+    // The interactable the player is looking at and the prompt shown for it
+    interaction: InteractionState,
 }
 
 impl HeliumManager {
-    pub fn new(ecs: HeliumECS, renderer: Arc<Mutex<HeliumState>>) -> Self {
+    pub fn new(ecs: HeliumECS, renderer: Option<Arc<Mutex<HeliumState>>>) -> Self {
         Self {
             ecs_instance: ecs,
-            renderer_instance: renderer.clone(),
+            renderer_instance: renderer,
             camera_id: None,
             time: Instant::now(),
             delta_time: Instant::now(),
+            frame_time: Duration::ZERO,
+            update_start: Instant::now(),
+            cursor: Cursor::default(),
+            look_delta: (0.0, 0.0),
+            streamer: WorldStreamer::default(),
+            picks: Vec::new(),
+            navmesh: None,
+            prefabs: HashMap::new(),
+            events: Events::default(),
+            tasks: TaskPool::default(),
+            rng: Rng::default(),
+            system_timings: SystemTimings::default(),
+            stats_overlay: false,
+            profiler_overlay: None,
+            pacing: UpdatePacing::default(),
+            fixed_clock: FixedClock::default(),
+            log_console: None,
+            startup_functions: Vec::new(),
+            scenes: HashMap::new(),
+            current_scene: None,
+            loading_screen: LoadingScreen::default(),
+            scene_load: None,
+            world_systems: Vec::new(),
+            state_machine_systems: Vec::new(),
+            hidden_worlds: HashSet::new(),
+            editor_camera: None,
+            selected: None,
+            gizmo: None,
+            undo_history: UndoHistory::default(),
+            removed_world: None,
+            time_of_day: None,
+            sun: None,
+            localization: Localization::default(),
+            spatial_index: SpatialGrid::new(DEFAULT_SPATIAL_CELL_SIZE),
+            interaction: InteractionState::default(),
+        }
+    }
+
+    /// Creates a manager without a window, used to run gameplay systems and physics in
+    /// tests with `step`. It renders offscreen when there is a gpu adapter, including a
+    /// software one, and runs without a renderer otherwise so the tests also run on
+    /// machines without a gpu
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width of the offscreen render target in pixels
+    /// * `height` - Height of the offscreen render target in pixels
+    pub fn headless(width: u32, height: u32) -> Self {
+        match HeliumState::new_headless(width, height) {
+            Ok(renderer) => Self::new(HeliumECS::default(), Some(Arc::new(Mutex::new(renderer)))),
+            Err(e) => {
+                warn!("Running without a renderer: {e}");
+                Self::without_renderer()
+            }
+        }
+    }
+
+    /// Creates a manager that runs the engine systems without drawing anything, the
+    /// renderer functions do nothing and give empty handles
+    pub fn without_renderer() -> Self {
+        Self::new(HeliumECS::default(), None)
+    }
+
+    /// Checks if the manager draws its scene, managers made with `without_renderer` or
+    /// by `headless` on a machine without a gpu do not
+    pub fn has_renderer(&self) -> bool {
+        self.renderer_instance.is_some()
+    }
+
+    /// Runs a function with the renderer locked, it is skipped when there is no renderer
+    ///
+    /// # Returns
+    ///
+    /// What the function returned or `None` if there is no renderer
+    pub fn with_renderer<R, F>(&self, function: F) -> Option<R>
+    where
+        F: FnOnce(&mut HeliumState) -> R,
+    {
+        self.renderer_instance
+            .as_ref()
+            .map(|renderer| function(&mut renderer.lock().unwrap()))
+    }
+
+    /// Runs one frame of the engine with a fixed delta time, the same as the update
+    /// thread does without input
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - How much time the frame takes
+    /// * `update_functions` - The update functions to run before the world and engine
+    ///   systems
+    pub fn step(&mut self, delta_time: Duration, update_functions: &[UpdateFunction]) {
+        let _span = profile_span("update");
+        self.frame_time = delta_time;
+        // Update functions that measure the frame from the delta time instant see about
+        // the same time
+        let now = Instant::now();
+        self.delta_time = now.checked_sub(delta_time).unwrap_or(now);
+
+        self.update_events();
+        let start = Instant::now();
+        for update_function in update_functions {
+            update_function(self);
         }
+        self.system_timings
+            .record("update_functions", start.elapsed());
+
+        let start = Instant::now();
+        self.run_world_systems();
+        self.system_timings.record("world_systems", start.elapsed());
+
+        crate::run_engine_systems(self);
+        self.delta_time = Instant::now();
+        profile_frame("update");
+    }
+
+    /// Used internally to measure the time since the last update started when a new one
+    /// starts
+    pub(crate) fn begin_update(&mut self) {
+        let now = Instant::now();
+        self.frame_time = now - self.update_start;
+        self.update_start = now;
+    }
+
+    /// Gives the seconds the current update covers, the delta time of a tick while the
+    /// fixed rate systems run. It stays the same for the whole update however long the
+    /// update functions take
+    pub fn get_delta_time(&self) -> f32 {
+        self.frame_time.as_secs_f32()
+    }
+
+    /// Renders a frame, used by headless managers since there is no window to redraw
+    pub fn render(&mut self) {
+        self.with_renderer(|renderer| {
+            if renderer.is_device_lost() {
+                if let Err(e) = renderer.recover_device() {
+                    error!("Failed to recover the renderer: {e}");
+                    return;
+                }
+            }
+
+            _ = renderer.render();
+        });
+    }
+
+    /// The width of the window divided by the height, 1 without a renderer
+    pub fn get_aspect_ratio(&self) -> f32 {
+        self.with_renderer(|renderer| renderer.get_aspect_ratio())
+            .unwrap_or(1.0)
     }
 
-    pub fn get_render_config(&self) -> SurfaceConfiguration {
-        self.renderer_instance.lock().unwrap().config.clone()
+    /// The configuration of the surface, `None` without a renderer
+    pub fn get_render_config(&self) -> Option<SurfaceConfiguration> {
+        self.with_renderer(|renderer| renderer.config.clone())
     }
 
     pub fn add_light(&mut self, mut light: Light) -> Entity {
-        self.renderer_instance.lock().unwrap().add_light(&mut light);
+        self.with_renderer(|renderer| renderer.add_light(&mut light));
 
         let light_entity = self.ecs_instance.new_entity();
         self.ecs_instance.add_component(light_entity, light);
         light_entity
     }
 
+    /// Points a light at the position of another entity, call this again when either of
+    /// them moves to keep following it
+    ///
+    /// # Arguments
+    ///
+    /// * `light_entity` - The entity with the `Light` to aim
+    /// * `target` - The entity with the `Transform3d` to aim the light at
+    pub fn light_look_at(&mut self, light_entity: Entity, target: Entity) {
+        let Some(target_position) = self
+            .ecs_instance
+            .query::<Transform3d>()
+            .and_then(|transforms| transforms.get(&target).map(|t| *t.get_position()))
+        else {
+            return;
+        };
+
+        let Some(mut lights) = self.ecs_instance.query_mut::<Light>() else {
+            return;
+        };
+
+        if let Some(light) = lights.get_mut(&light_entity) {
+            light.look_at(&target_position);
+            self.with_renderer(|renderer| renderer.update_light(light));
+        }
+    }
+
+    /// Draws a procedural sky behind the scene with a directional sun light that follows
+    /// the time of day, the time moves on its own with the speed of the `TimeOfDay`
+    ///
+    /// # Arguments
+    ///
+    /// * `time_of_day` - The time and the sky, `None` removes the sky and turns off the sun
+    pub fn set_time_of_day(&mut self, time_of_day: Option<TimeOfDay>) {
+        self.time_of_day = time_of_day;
+
+        if self.time_of_day.is_some() {
+            self.apply_time_of_day();
+            return;
+        }
+
+        self.with_renderer(|renderer| renderer.set_sky(None));
+        let Some(mut lights) = self.ecs_instance.query_mut::<Light>() else {
+            return;
+        };
+        if let Some(light) = self.sun.and_then(|sun| lights.get_mut(&sun)) {
+            light.update_intensity(0.0);
+            self.with_renderer(|renderer| renderer.update_light(light));
+        }
+    }
+
+    pub fn get_time_of_day(&self) -> Option<&TimeOfDay> {
+        self.time_of_day.as_ref()
+    }
+
+    /// The time of day to animate, the sky and the sun follow it on the next update
+    pub fn get_time_of_day_mut(&mut self) -> Option<&mut TimeOfDay> {
+        self.time_of_day.as_mut()
+    }
+
+    /// The entity with the directional `Light` of the sun, it can be given shadows like
+    /// any other light
+    pub fn get_sun(&self) -> Option<Entity> {
+        self.sun
+    }
+
+    /// Moves the clock of the time of day forward and the sky and the sun with it
+    pub(crate) fn update_time_of_day(&mut self) {
+        let delta_time = self.get_delta_time();
+        let Some(time_of_day) = self.time_of_day.as_mut() else {
+            return;
+        };
+
+        time_of_day.advance(delta_time);
+        self.apply_time_of_day();
+    }
+
+    // Sends the sky to the renderer and points the sun light along it
+    fn apply_time_of_day(&mut self) {
+        let Some(time_of_day) = self.time_of_day else {
+            return;
+        };
+
+        let sky = time_of_day.get_sky();
+        self.with_renderer(|renderer| renderer.set_sky(Some(sky)));
+
+        let has_sun = self.sun.is_some_and(|sun| {
+            self.ecs_instance
+                .query::<Light>()
+                .is_some_and(|lights| lights.contains_key(&sun))
+        });
+        if !has_sun {
+            self.sun = Some(self.add_light(Light::directional(
+                sky.get_sun_color(),
+                -sky.get_sun_direction(),
+            )));
+        }
+
+        let Some(mut lights) = self.ecs_instance.query_mut::<Light>() else {
+            return;
+        };
+        if let Some(light) = self.sun.and_then(|sun| lights.get_mut(&sun)) {
+            light
+                .update_direction(&-sky.get_sun_direction())
+                .update_color(sky.get_sun_color())
+                .update_intensity(time_of_day.get_sun_intensity() * sky.get_daylight());
+            self.with_renderer(|renderer| renderer.update_light(light));
+        }
+    }
+
+    /// Shows the range of every light in candela and the direction of directional lights
+    pub fn set_light_gizmos(&mut self, enabled: bool) {
+        self.with_renderer(|renderer| renderer.set_light_gizmos(enabled));
+    }
+
+    /// Draws a line in the world for this update, the lines are drawn until the next
+    /// update ends
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Where the line starts in world space
+    /// * `end` - Where the line ends in world space
+    /// * `color` - The color of the line
+    pub fn debug_line<C>(&mut self, start: Vector3<f32>, end: Vector3<f32>, color: C)
+    where
+        C: Into<LinearRgba>,
+    {
+        self.with_renderer(|renderer| {
+            renderer.debug_line(DebugLine {
+                start,
+                end,
+                color: color.into().to_array(),
+            })
+        });
+    }
+
+    /// Draws the outline of a sphere in the world for this update
+    pub fn debug_sphere<C>(&mut self, center: Vector3<f32>, radius: f32, color: C)
+    where
+        C: Into<LinearRgba>,
+    {
+        self.with_renderer(|renderer| {
+            renderer.debug_sphere(center, radius, color.into().to_array())
+        });
+    }
+
+    /// Draws an arrow from `start` to `end` in the world for this update
+    pub fn debug_arrow<C>(&mut self, start: Vector3<f32>, end: Vector3<f32>, color: C)
+    where
+        C: Into<LinearRgba>,
+    {
+        self.with_renderer(|renderer| renderer.debug_arrow(start, end, color.into().to_array()));
+    }
+
+    /// Draws a label above a point in the world for this update, the label faces the
+    /// screen and keeps its size at any distance, used to annotate entities, waypoints,
+    /// and contacts
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The point in world space the label is drawn above
+    /// * `text` - The text of the label
+    /// * `color` - The color of the text
+    pub fn debug_text_3d<C>(&mut self, position: Vector3<f32>, text: &str, color: C)
+    where
+        C: Into<LinearRgba>,
+    {
+        self.with_renderer(|renderer| {
+            renderer.debug_text(DebugText {
+                position,
+                text: text.to_string(),
+                color: color.into().to_array(),
+            })
+        });
+    }
+
     /// Creates a 3d camera to view the scene with. The rendering will be skipped if
-    /// No cameara is present
+    /// No cameara is present. The aspect ratio of the camera is set to the aspect ratio
+    /// of the window and follows it when the window is resized
     ///
     /// # Arguments
     ///
@@ -52,16 +516,19 @@ impl HeliumManager {
     /// # Returns
     ///
     /// The entity id
-    pub fn create_camera(&mut self, camera: Camera3d) -> Entity {
-        self.renderer_instance.lock().unwrap().add_camera(
-            camera.eye,
-            camera.target,
-            camera.up,
-            camera.aspect,
-            camera.fovy,
-            camera.znear,
-            camera.zfar,
-        );
+    pub fn create_camera(&mut self, mut camera: Camera3d) -> Entity {
+        camera.set_aspect(self.get_aspect_ratio());
+        self.with_renderer(|renderer| {
+            renderer.add_camera(
+                camera.eye,
+                camera.target,
+                camera.up,
+                camera.aspect,
+                camera.fovy,
+                camera.znear,
+                camera.zfar,
+            )
+        });
 
         let camera_entity = self.ecs_instance.new_entity();
         self.ecs_instance.add_component(camera_entity, camera);
@@ -73,32 +540,47 @@ impl HeliumManager {
     ///
     /// # Arguments
     ///
-    /// * `camera` - the new camera
-    pub fn update_camera(&mut self, camera: Camera3d) {
-        self.renderer_instance.lock().unwrap().update_camera(
-            camera.eye,
-            camera.target,
-            camera.up,
-            camera.aspect,
-            camera.fovy,
-            camera.znear,
-            camera.zfar,
-        );
+    /// * `camera` - the new camera, it keeps the aspect ratio of the window
+    pub fn update_camera(&mut self, mut camera: Camera3d) {
+        camera.set_aspect(self.get_aspect_ratio());
+        self.with_renderer(|renderer| {
+            renderer.update_camera(
+                camera.eye,
+                camera.target,
+                camera.up,
+                camera.aspect,
+                camera.fovy,
+                camera.znear,
+                camera.zfar,
+            )
+        });
         self.ecs_instance
             .add_component(*self.camera_id.as_ref().unwrap(), camera);
     }
 
-    /// Used internally to update the camera position
+    /// Used internally to update the camera position, the cameras of the game are not
+    /// drawn while the editor camera is active
     pub fn move_camera_to_render(&self, camera: &Camera3d) {
-        self.renderer_instance.lock().unwrap().update_camera(
-            camera.eye,
-            camera.target,
-            camera.up,
-            camera.aspect,
-            camera.fovy,
-            camera.znear,
-            camera.zfar,
-        );
+        if self.is_editor_camera_active() {
+            return;
+        }
+
+        self.render_camera(camera);
+    }
+
+    // Sends a camera to the renderer to draw the scene with
+    fn render_camera(&self, camera: &Camera3d) {
+        self.with_renderer(|renderer| {
+            renderer.update_camera(
+                camera.eye,
+                camera.target,
+                camera.up,
+                camera.aspect,
+                camera.fovy,
+                camera.znear,
+                camera.zfar,
+            )
+        });
     }
 
     /// Creates a new entity in the ECS
@@ -121,13 +603,25 @@ impl HeliumManager {
     ///
     /// The entity id
     pub fn create_object(&mut self, mut model: Model3d, transform: Transform3d) -> Entity {
-        let renderer_index = self
-            .renderer_instance
-            .lock()
-            .unwrap()
-            .create_object(model.get_path(), vec![transform.into()]);
+        let renderer_index = self.with_renderer(|renderer| {
+            let renderer_index = match model.get_lines() {
+                Some((points, topology)) => {
+                    renderer.create_lines(points, topology, vec![transform.into()])
+                }
+                None => renderer.create_object(model.get_path(), vec![transform.into()]),
+            };
+
+            if let Some(color_material) = model.get_color_material() {
+                renderer.set_object_color_material(renderer_index, *color_material);
+            }
+
+            renderer_index
+        });
 
-        model.set_renderer_index(renderer_index);
+        if let Some(renderer_index) = renderer_index {
+            model.set_renderer_index(renderer_index);
+            self.hide_in_hidden_world(renderer_index);
+        }
 
         // let mut ecs = self.ecs_instance;
         let entity = self.ecs_instance.new_entity();
@@ -137,159 +631,2899 @@ impl HeliumManager {
         entity
     }
 
-    /// Sets the transform for a specified entity to a new transform
+    /// Creates a 3d model component that is loaded on another thread so the update loop
+    /// is not blocked, the model is drawn once it finishes loading
     ///
     /// # Arguments
     ///
-    /// * `entity` - The entity to change the transform of
-    /// * `transform` - The new Transform to set for the entity
+    /// * `model` - The 3d model to import into the engine
+    /// * `transform` - The transformation to apply to the model
     ///
     /// # Returns
     ///
     /// The entity id
-    #[deprecated]
-    pub fn update_transform(&mut self, entity: Entity, transform: Transform3d) -> Entity {
-        self.ecs_instance.add_component(entity, transform);
+    pub fn create_object_async(&mut self, mut model: Model3d, transform: Transform3d) -> Entity {
+        let renderer_index = self.with_renderer(|renderer| {
+            // Lines are quick to create so they are not loaded on another thread
+            let renderer_index = match model.get_lines() {
+                Some((points, topology)) => {
+                    renderer.create_lines(points, topology, vec![transform.into()])
+                }
+                None => renderer.create_object_async(model.get_path(), vec![transform.into()]),
+            };
 
-        // Get the renderer index from the model
-        let object_index = *self
-            .ecs_instance
-            .query::<Model3d>()
-            .unwrap()
-            .get(&entity)
-            .unwrap()
-            .get_renderer_index()
-            .unwrap();
+            if let Some(color_material) = model.get_color_material() {
+                renderer.set_object_color_material(renderer_index, *color_material);
+            }
+
+            renderer_index
+        });
+
+        if let Some(renderer_index) = renderer_index {
+            model.set_renderer_index(renderer_index);
+            self.hide_in_hidden_world(renderer_index);
+        }
 
-        let mut renderer = self.renderer_instance.lock().unwrap();
-        renderer.update_instances(object_index, vec![transform.into()]);
+        let entity = self.ecs_instance.new_entity();
+        self.ecs_instance.add_component(entity, model);
+        self.ecs_instance.add_component(entity, transform);
 
         entity
     }
 
-    #[deprecated]
-    pub fn set_position(&mut self, entity: Entity, position: Vector3<f32>) {
-        let object_index = *self
-            .ecs_instance
-            .query::<Model3d>()
-            .unwrap()
-            .get(&entity)
-            .unwrap()
-            .get_renderer_index()
-            .unwrap();
-
-        if let Some(transform) = self
-            .ecs_instance
-            .query_mut::<Transform3d>()
-            .unwrap()
-            .get_mut(&entity)
-        {
-            Transform3d::set_position(transform, position);
-            self.renderer_instance
-                .lock()
-                .unwrap()
-                .update_instances(object_index, vec![(*transform).into()]);
+    // Hides a new object when the active world is not rendered
+    fn hide_in_hidden_world(&mut self, object_index: usize) {
+        if !self.is_world_rendered(self.ecs_instance.get_active_world()) {
+            self.with_renderer(|renderer| renderer.set_object_visible(object_index, false));
         }
     }
 
-    // #[deprecated]
-    pub fn set_rotation(&mut self, entity: Entity, rotation: Quaternion<f32>) {
-        let object_index = *self
-            .ecs_instance
-            .query::<Model3d>()
-            .unwrap()
-            .get(&entity)
-            .unwrap()
-            .get_renderer_index()
-            .unwrap();
-
-        if let Some(transform) = self
-            .ecs_instance
-            .query_mut::<Transform3d>()
-            .unwrap()
-            .get_mut(&entity)
-        {
-            Transform3d::set_rotation(transform, rotation);
-            self.renderer_instance
-                .lock()
-                .unwrap()
-                .update_instances(object_index, vec![(*transform).into()]);
-        }
+    /// Creates a new empty world next to the main world, like a ui world or an inventory
+    /// that is kept apart from the game world
+    ///
+    /// # Returns
+    ///
+    /// The id of the world
+    pub fn create_world(&mut self) -> WorldId {
+        self.ecs_instance.create_world()
     }
 
-    #[deprecated]
-    pub fn move_transform_to_renderer(&self, entity: Entity) {
-        let object_index = *self
-            .ecs_instance
-            .query::<Model3d>()
-            .unwrap()
-            .get(&entity)
-            .unwrap()
-            .get_renderer_index()
-            .unwrap();
+    /// Removes a world with every entity in it, its systems, and everything it draws,
+    /// the main world can not be removed
+    ///
+    /// # Arguments
+    ///
+    /// * `world` - The id of the world to remove
+    ///
+    /// # Returns
+    ///
+    /// Whether the world was removed
+    pub fn remove_world(&mut self, world: WorldId) -> bool {
+        if world == helium_ecs::MAIN_WORLD || !self.ecs_instance.contains_world(world) {
+            return false;
+        }
 
-        let transforms = self.ecs_instance.query::<Transform3d>();
-        if let Some(transform) = transforms.unwrap().get(&entity) {
-            self.renderer_instance
-                .lock()
-                .unwrap()
-                .update_instances(object_index, vec![(*transform).into()]);
+        let active = self.ecs_instance.get_active_world();
+        self.ecs_instance.set_active_world(world);
+        for entity in self.ecs_instance.entities() {
+            self.despawn(entity);
         }
+        self.ecs_instance.set_active_world(active);
+
+        self.world_systems
+            .retain(|(system_world, _)| *system_world != world);
+        self.hidden_worlds.remove(&world);
+        self.ecs_instance.remove_world(world)
     }
 
-    /// Adds a component to the specified entity
+    /// Makes the entity and component functions of the manager work on another world
     ///
     /// # Arguments
     ///
-    /// * `entity` - Entity to add componenets to
-    /// * `component` - Component to add to the entity
+    /// * `world` - The id of the world
     ///
     /// # Returns
     ///
-    /// The entity id
-    pub fn add_component<ComponentType: 'static>(
-        &mut self,
-        entity: Entity,
-        component: ComponentType,
-    ) -> Entity {
-        self.ecs_instance.add_component(entity, component);
-        entity
+    /// Whether the world exists and is now active
+    pub fn set_active_world(&mut self, world: WorldId) -> bool {
+        self.ecs_instance.set_active_world(world)
     }
 
-    /// Querys the ECS for the component type specified and gives the corresponding information
+    pub fn get_active_world(&self) -> WorldId {
+        self.ecs_instance.get_active_world()
+    }
+
+    /// Adds an update function that runs every update with its world active, after the
+    /// update functions of the main world
     ///
     /// # Arguments
     ///
-    /// * `ComponentType` - The type for the ECS to query for
+    /// * `world` - The id of the world the function updates
+    /// * `update_function` - The function to run
+    pub fn add_world_update(&mut self, world: WorldId, update_function: UpdateFunction) {
+        self.world_systems.push((world, update_function));
+    }
+
+    /// Used internally to run the update functions of every world
+    pub(crate) fn run_world_systems(&mut self) {
+        let active = self.ecs_instance.get_active_world();
+        for index in 0..self.world_systems.len() {
+            let Some((world, system)) = self.world_systems.get(index).copied() else {
+                break;
+            };
+
+            if self.ecs_instance.set_active_world(world) {
+                system(self);
+            }
+        }
+        self.ecs_instance.set_active_world(active);
+    }
+
+    /// Moves an entity to another world, its models are shown or hidden for whether the
+    /// world is rendered
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity to move
+    /// * `world` - The id of the world to move it to
     ///
     /// # Returns
     ///
-    /// A `Ref` to the `HashMap` of the specified `ComponentType`
-    pub fn query<ComponentType: 'static>(&self) -> Option<Ref<'_, HashMap<Entity, ComponentType>>> {
-        self.ecs_instance.query::<ComponentType>()
+    /// Whether the entity was moved
+    pub fn move_entity_to_world(&mut self, entity: Entity, world: WorldId) -> bool {
+        if !self.ecs_instance.move_entity(entity, world) {
+            return false;
+        }
+
+        let active = self.ecs_instance.get_active_world();
+        self.ecs_instance.set_active_world(world);
+        if let Some(object_index) = self.get_renderer_index(entity) {
+            let visible = self.is_world_rendered(world) && self.is_visible(entity);
+            self.with_renderer(|renderer| renderer.set_object_visible(object_index, visible));
+        }
+        self.ecs_instance.set_active_world(active);
+
+        true
     }
 
-    /// Querys the ECS for the component type specified and gives the corresponding information
+    /// Chooses if the models of a world are drawn, worlds are rendered when they are created
     ///
     /// # Arguments
     ///
-    /// * `ComponentType` - The type for the ECS to query for
+    /// * `world` - The id of the world
+    /// * `rendered` - Whether the models of the world are drawn
+    pub fn set_world_rendered(&mut self, world: WorldId, rendered: bool) {
+        if !self.ecs_instance.contains_world(world) || self.is_world_rendered(world) == rendered {
+            return;
+        }
+
+        if rendered {
+            self.hidden_worlds.remove(&world);
+        } else {
+            self.hidden_worlds.insert(world);
+        }
+
+        let active = self.ecs_instance.get_active_world();
+        self.ecs_instance.set_active_world(world);
+        if let Some(models) = self.ecs_instance.query::<Model3d>() {
+            self.with_renderer(|renderer| {
+                for (entity, model) in models.iter() {
+                    if let Some(object_index) = model.get_renderer_index() {
+                        renderer.set_object_visible(
+                            *object_index,
+                            rendered && self.is_visible(*entity),
+                        );
+                    }
+                }
+            });
+        }
+        self.ecs_instance.set_active_world(active);
+    }
+
+    pub fn is_world_rendered(&self, world: WorldId) -> bool {
+        !self.hidden_worlds.contains(&world)
+    }
+
+    /// Scatters thousands of instances of a model in a region, like grass or rocks,
+    /// the instances are drawn in one instance range and culled on the gpu
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The 3d model to scatter
+    /// * `region` - The region to place the instances in
+    /// * `density` - The number of instances per square unit of the region
+    /// * `seed` - The seed of the random placement, the same seed gives the same instances
     ///
     /// # Returns
     ///
-    /// A `RefMut` to the `HashMap` of the specified `ComponentType`
-    pub fn query_mut<ComponentType: 'static>(
-        &self,
-    ) -> Option<RefMut<'_, HashMap<Entity, ComponentType>>> {
-        self.ecs_instance.query_mut::<ComponentType>()
+    /// The entity id
+    pub fn scatter(
+        &mut self,
+        model: Model3d,
+        region: ScatterRegion,
+        density: f32,
+        seed: u64,
+    ) -> Entity {
+        self.scatter_with_settings(model, region, density, seed, ScatterSettings::default())
     }
 
-    /// Gives a list of entities that have a component with a specific comparator operator
+    /// Scatters instances of a model like `scatter` with custom culling settings
     ///
     /// # Arguments
     ///
-    /// * `ComponentType` - The type for the component map to seach
-    /// * `comparator` - A fucntion pointer to compare the component value given
+    /// * `model` - The 3d model to scatter
+    /// * `region` - The region to place the instances in
+    /// * `density` - The number of instances per square unit of the region
+    /// * `seed` - The seed of the random placement, the same seed gives the same instances
+    /// * `settings` - The bounding radius of the model and the distances the density falls off at
+    ///
+    /// # Returns
+    ///
+    /// The entity id
+    pub fn scatter_with_settings(
+        &mut self,
+        mut model: Model3d,
+        region: ScatterRegion,
+        density: f32,
+        seed: u64,
+        settings: ScatterSettings,
+    ) -> Entity {
+        let renderer_index = self.with_renderer(|renderer| {
+            let renderer_index =
+                renderer.create_scatter(model.get_path(), region, density, seed, settings);
+
+            if let Some(color_material) = model.get_color_material() {
+                renderer.set_object_color_material(renderer_index, *color_material);
+            }
+
+            renderer_index
+        });
+
+        if let Some(renderer_index) = renderer_index {
+            model.set_renderer_index(renderer_index);
+        }
+
+        let entity = self.ecs_instance.new_entity();
+        self.ecs_instance.add_component(entity, model);
+
+        entity
+    }
+
+    /// Merges models that never move into a single batch so they are drawn with one
+    /// draw call per material instead of one per mesh, models with the same file and
+    /// color material share their meshes
+    ///
+    /// # Arguments
+    ///
+    /// * `objects` - The models to merge with the transform of each model
+    ///
+    /// # Returns
+    ///
+    /// The entity id of the batch
+    pub fn create_static_batch(&mut self, objects: Vec<(Model3d, Transform3d)>) -> Entity {
+        let mut batch_objects: Vec<StaticBatchObject> = Vec::new();
+        for (model, transform) in objects.iter() {
+            let path = Path::new(model.get_path());
+            let color_material = model.get_color_material().copied();
+
+            match batch_objects
+                .iter_mut()
+                .find(|object| object.path == path && object.color_material == color_material)
+            {
+                Some(object) => object.instances.push((*transform).into()),
+                None => batch_objects.push(StaticBatchObject {
+                    path: path.to_path_buf(),
+                    color_material,
+                    instances: vec![(*transform).into()],
+                }),
+            }
+        }
+
+        let renderer_index =
+            self.with_renderer(|renderer| renderer.create_static_batch(&batch_objects));
+
+        let mut batch = StaticBatch::new(objects);
+        if let Some(renderer_index) = renderer_index {
+            batch.set_renderer_index(renderer_index);
+        }
+
+        let entity = self.ecs_instance.new_entity();
+        self.ecs_instance.add_component(entity, batch);
+
+        entity
+    }
+
+    /// Removes the outline from the model of an entity
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity with the `Highlighted` component to remove
+    pub fn remove_highlight(&mut self, entity: Entity) {
+        if let Some(object_index) = self.get_renderer_index(entity) {
+            self.with_renderer(|renderer| renderer.set_object_outline(object_index, None));
+        }
+
+        self.ecs_instance.remove_component::<Highlighted>(entity);
+    }
+
+    /// Shows the model of an entity again and removes its visibility
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity with the `Visible` component to remove
+    pub fn remove_visibility(&mut self, entity: Entity) {
+        if let Some(object_index) = self.get_renderer_index(entity) {
+            let visible = self.is_world_rendered(self.get_active_world());
+            self.with_renderer(|renderer| renderer.set_object_visible(object_index, visible));
+        }
+
+        self.ecs_instance.remove_component::<Visible>(entity);
+    }
+
+    // Whether the `Visible` component of an entity in the active world lets it be drawn
+    fn is_visible(&self, entity: Entity) -> bool {
+        self.ecs_instance
+            .query::<Visible>()
+            .and_then(|visibles| visibles.get(&entity).map(Visible::is_visible))
+            .unwrap_or(true)
+    }
+
+    /// Stops the model of an entity from reflecting the scene
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity with the `Reflective` component to remove
+    pub fn remove_reflection(&mut self, entity: Entity) {
+        if let Some(object_index) = self.get_renderer_index(entity) {
+            self.with_renderer(|renderer| renderer.set_object_reflection(object_index, None));
+        }
+
+        self.ecs_instance.remove_component::<Reflective>(entity);
+    }
+
+    /// Removes an entity with all of its components and everything it draws, nothing
+    /// happens if the entity was already removed
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity to remove
+    pub fn despawn(&mut self, entity: Entity) {
+        if !self.ecs_instance.contains_entity(entity) {
+            return;
+        }
+
+        let handles = self.get_renderer_handles(entity);
+
+        self.with_renderer(|renderer| {
+            for handle in handles {
+                match handle {
+                    RendererHandle::Model(index) => renderer.remove_object(index),
+                    RendererHandle::Text(index) => renderer.remove_text(index),
+                    RendererHandle::Sprite(index) => renderer.remove_sprite(index),
+                    RendererHandle::Quad(index) => renderer.remove_quad(index),
+                    RendererHandle::Decal(index) => renderer.remove_decal(index),
+                    RendererHandle::SdfText(index) => renderer.remove_sdf_text(index),
+                }
+            }
+        });
+
+        self.ecs_instance.remove_entity(entity);
+    }
+
+    /// The random number generator shared by the engine and the game, seeding it makes
+    /// everything that uses it happen the same way every run
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    /// Sends an event that can be read with `read_events` during the next frame
+    pub fn send_event<E: 'static>(&mut self, event: E) {
+        self.events.send(event);
+    }
+
+    /// Gives the events of a type that were sent during the last frame
+    pub fn read_events<E: 'static>(&self) -> &[E] {
+        self.events.read::<E>()
+    }
+
+    /// Runs a future on a background thread, the output is sent as a `TaskComplete` event
+    /// on the update after the task finishes
+    ///
+    /// # Arguments
+    ///
+    /// * `future` - The work to do, like pathfinding, generation, or loading files
+    ///
+    /// # Returns
+    ///
+    /// The `TaskHandle` that the `TaskComplete` event is sent with
+    pub fn spawn_task<F>(&mut self, future: F) -> TaskHandle
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.tasks.spawn(future, |manager, handle, output| {
+            manager.send_event(TaskComplete { handle, output })
+        })
+    }
+
+    /// Runs a future on a background thread and calls a function with the output on the
+    /// update thread once it finishes, the function can change the world like any system
+    ///
+    /// # Arguments
+    ///
+    /// * `future` - The work to do
+    /// * `on_complete` - The function to call with the output
+    ///
+    /// # Returns
+    ///
+    /// The `TaskHandle` of the task
+    pub fn spawn_task_with<F>(
+        &mut self,
+        future: F,
+        on_complete: TaskFunction<F::Output>,
+    ) -> TaskHandle
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.tasks.spawn(future, move |manager, _, output| {
+            on_complete(manager, output)
+        })
+    }
+
+    /// Checks if a background task has not finished yet
+    pub fn is_task_running(&self, handle: TaskHandle) -> bool {
+        self.tasks.is_running(handle)
+    }
+
+    /// Number of background tasks that have not finished yet
+    pub fn get_running_tasks(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Used internally to hand the outputs of the finished tasks to the game
+    pub fn update_tasks(&mut self) {
+        for completion in self.tasks.take_completed() {
+            completion(self);
+        }
+    }
+
+    /// Used internally to run the startup functions that create the world
+    pub(crate) fn run_startup_functions(&mut self) {
+        for startup_function in self.startup_functions.clone() {
+            startup_function(self);
+        }
+    }
+
+    /// Removes every entity and everything in the renderer and creates the world again,
+    /// like restarting a level after a game over. The current scene is loaded again when
+    /// one was loaded, otherwise the startup functions run again. The registered scenes
+    /// and prefabs are kept. The random numbers start again from the seed so the
+    /// restarted world is the same as the first one. Background tasks keep running and
+    /// finish into the new world
+    pub fn restart_world(&mut self) {
+        info!("Restarting the world");
+
+        self.time = Instant::now();
+        self.events = Events::default();
+        self.rng.set_seed(self.rng.get_seed());
+
+        match self.current_scene.clone() {
+            Some(scene) => {
+                self.load_scene_async(&scene);
+            }
+            None => {
+                self.clear_world();
+                self.run_startup_functions();
+            }
+        }
+    }
+
+    // Removes every world with its entities and systems and everything in the renderer
+    fn clear_world(&mut self) {
+        self.ecs_instance = HeliumECS::with_storage_order(self.ecs_instance.get_storage_order());
+        self.with_renderer(|renderer| renderer.clear_scene());
+
+        self.camera_id = None;
+        self.delta_time = Instant::now();
+        self.streamer = WorldStreamer::default();
+        self.picks.clear();
+        self.navmesh = None;
+        self.scene_load = None;
+        self.world_systems.clear();
+        self.hidden_worlds.clear();
+        self.selected = None;
+        if let Some(editor_camera) = self.editor_camera.as_mut() {
+            editor_camera.deactivate();
+        }
+        // The renderer was cleared with the quads and text of the flamegraph
+        if self.profiler_overlay.is_some() {
+            self.profiler_overlay = Some(ProfilerOverlay::default());
+        }
+        // The edits point at entities of the old worlds, which are already gone
+        self.undo_history.clear();
+        self.removed_world = None;
+        // The sun light was removed with the renderer, it is added again on the next update
+        self.sun = None;
+        // The prompt text went with the renderer
+        self.interaction.clear();
+    }
+
+    /// Registers a scene that can be loaded by name with `load_scene_async`
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the scene is loaded with
+    /// * `scene` - The function that creates the entities of the scene, models created
+    ///   with `create_object_async` are waited for before the loading screen is removed
+    pub fn register_scene(&mut self, name: &str, scene: SceneFunction) {
+        self.scenes.insert(name.to_string(), scene);
+    }
+
+    /// Changes what is drawn over the screen while a scene loads
+    pub fn set_loading_screen(&mut self, loading_screen: LoadingScreen) {
+        self.loading_screen = loading_screen;
+    }
+
+    /// Unloads the current scene and loads a registered scene with the loading screen
+    /// drawn over it until every object of the scene has loaded. A `SceneLoadProgress`
+    /// event is sent every update while it loads and a `SceneLoaded` event once it has
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the scene was registered with
+    ///
+    /// # Returns
+    ///
+    /// Whether the scene started loading, it does not when no scene has the name
+    pub fn load_scene_async(&mut self, name: &str) -> bool {
+        let Some(scene) = self.scenes.get(name).copied() else {
+            warn!("No scene is registered with the name {}", name);
+            return false;
+        };
+
+        info!("Loading scene {}", name);
+        self.clear_world();
+        scene(self);
+
+        let scene_load = self
+            .with_renderer(|renderer| SceneLoad::new(name, self.loading_screen.clone(), renderer));
+        self.current_scene = Some(name.to_string());
+        match scene_load {
+            Some(scene_load) => {
+                self.scene_load = Some(scene_load);
+                self.update_scene_load();
+            }
+            // Nothing is loaded on another thread without a renderer
+            None => self.events.send(SceneLoaded {
+                scene: name.to_string(),
+            }),
+        }
+
+        true
+    }
+
+    /// The name of the last scene that was loaded
+    pub fn get_current_scene(&self) -> Option<&str> {
+        self.current_scene.as_deref()
+    }
+
+    /// Checks if the loading screen of a scene is being drawn
+    pub fn is_loading_scene(&self) -> bool {
+        self.scene_load.is_some()
+    }
+
+    /// Used internally to show the progress of the loading scene
+    pub fn update_scene_load(&mut self) {
+        let Some(scene_load) = self.scene_load.as_ref() else {
+            return;
+        };
+
+        let Some(renderer) = self.renderer_instance.clone() else {
+            return;
+        };
+        let mut renderer = renderer.lock().unwrap();
+        let total = renderer.get_num_objects();
+        let progress = SceneLoadProgress {
+            scene: scene_load.get_scene().to_string(),
+            loaded: total - renderer.get_num_pending_objects(),
+            total,
+        };
+        scene_load.update(&progress, &mut renderer);
+
+        let loaded = progress.loaded == progress.total;
+        let scene = progress.scene.clone();
+        self.events.send(progress);
+
+        if loaded {
+            self.scene_load.take().unwrap().finish(&mut renderer);
+            self.events.send(SceneLoaded { scene });
+        }
+    }
+
+    /// Used internally to make the events sent last frame readable
+    pub fn update_events(&mut self) {
+        self.events.update();
+    }
+
+    /// Damages an entity with `Health`, a `DamageEvent` is sent when the damage is taken
+    /// and a `DeathEvent` when the entity dies
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The entity to damage
+    /// * `amount` - How much health to take away
+    /// * `source` - The entity that dealt the damage
+    ///
+    /// # Returns
+    ///
+    /// Whether the damage was taken, it is not when the entity has no health, is
+    /// invulnerable or is already dead
+    pub fn apply_damage(&mut self, target: Entity, amount: f32, source: Option<Entity>) -> bool {
+        let Some((taken, health)) = self
+            .ecs_instance
+            .query_mut::<Health>()
+            .as_mut()
+            .and_then(|healths| healths.get_mut(&target))
+            .map(|health| (health.damage(amount), *health))
+        else {
+            return false;
+        };
+
+        if !taken {
+            return false;
+        }
+
+        self.events.send(DamageEvent {
+            target,
+            source,
+            amount,
+            remaining: health.get_current(),
+        });
+
+        if health.is_dead() {
+            self.events.send(DeathEvent {
+                entity: target,
+                source,
+            });
+
+            if health.get_despawn_on_death() {
+                self.despawn(target);
+            }
+        }
+
+        true
+    }
+
+    /// Registers a prefab that can be spawned by name, used by `Spawner` components
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the prefab is spawned with
+    /// * `prefab` - The function that creates the entity of the prefab at a position
+    pub fn register_prefab(&mut self, name: &str, prefab: PrefabFunction) {
+        self.prefabs.insert(name.to_string(), prefab);
+    }
+
+    /// Spawns a registered prefab
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the prefab was registered with
+    /// * `position` - Where the prefab is spawned
+    ///
+    /// # Returns
+    ///
+    /// The entity of the prefab or `None` if no prefab has the name
+    pub fn spawn_prefab(&mut self, name: &str, position: Vector3<f32>) -> Option<Entity> {
+        let Some(prefab) = self.prefabs.get(name).copied() else {
+            warn!("No prefab is registered with the name {}", name);
+            return None;
+        };
+
+        Some(prefab(self, position))
+    }
+
+    /// Adds a chunk of the world that is loaded when the camera gets close to it
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - The region and objects of the chunk
+    ///
+    /// # Returns
+    ///
+    /// The index of the chunk
+    pub fn add_scene_chunk(&mut self, chunk: SceneChunk) -> usize {
+        self.streamer.add_chunk(chunk)
+    }
+
+    /// Finds the entity drawn at a pixel on the screen, the pick is read back from the gpu
+    /// after the next frame so the result is passed to a function once it is ready
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The horizontal position of the pixel from the left of the screen
+    /// * `y` - The vertical position of the pixel from the top of the screen
+    /// * `on_pick` - The function called with the picked entity, or `None` if nothing was hit
+    pub fn pick(&mut self, x: u32, y: u32, on_pick: PickFunction) {
+        match self.with_renderer(|renderer| renderer.pick(x, y)) {
+            Some(request) => self.picks.push((request, on_pick)),
+            // Nothing can be hit without a renderer
+            None => on_pick(self, None),
+        }
+    }
+
+    /// Used internally to call the pick functions of the picks that have been read back
+    pub fn update_picks(&mut self) {
+        let mut index = 0;
+        while index < self.picks.len() {
+            let instance_index = match self.picks[index].0.try_receive() {
+                Some(instance_index) => instance_index,
+                None => {
+                    index += 1;
+                    continue;
+                }
+            };
+
+            let (_, on_pick) = self.picks.remove(index);
+
+            let object_index = instance_index.and_then(|instance_index| {
+                self.with_renderer(|renderer| renderer.get_object_from_instance(instance_index))
+                    .flatten()
+            });
+            let entity = object_index.and_then(|object_index| self.get_object_entity(object_index));
+
+            on_pick(self, entity);
+        }
+    }
+
+    // Finds the entity that owns an object in the renderer
+    fn get_object_entity(&self, object_index: usize) -> Option<Entity> {
+        if let Some(models) = self.query::<Model3d>() {
+            if let Some((entity, _)) = models
+                .iter()
+                .find(|(_, model)| model.get_renderer_index() == Some(&object_index))
+            {
+                return Some(*entity);
+            }
+        }
+
+        self.query::<StaticBatch>()?
+            .iter()
+            .find(|(_, batch)| batch.get_renderer_index() == Some(&object_index))
+            .map(|(entity, _)| *entity)
+    }
+
+    /// Used internally to load and unload the chunks around the camera
+    pub fn update_streaming(&mut self) {
+        let camera_position = match self.camera_id.and_then(|camera_id| {
+            self.query::<Camera3d>()
+                .and_then(|cameras| cameras.get(&camera_id).map(|camera| camera.eye))
+        }) {
+            Some(eye) => Vector3::new(eye.x, eye.y, eye.z),
+            None => return,
+        };
+
+        for chunk_index in 0..self.streamer.get_num_chunks() {
+            match self.streamer.action(chunk_index, camera_position) {
+                ChunkAction::Load => {
+                    let objects = self.streamer.get_chunk(chunk_index).objects.clone();
+
+                    let entities = objects
+                        .into_iter()
+                        .map(|object| {
+                            let entity = self.create_object_async(object.model, object.transform);
+
+                            if let Some(label) = object.label {
+                                self.ecs_instance.add_component(entity, label);
+                            }
+
+                            if let Some(collider) = object.rectangle_collider {
+                                self.ecs_instance.add_component(entity, collider);
+                            }
+
+                            if let Some(collider) = object.plane_collider {
+                                self.ecs_instance.add_component(entity, collider);
+                            }
+
+                            entity
+                        })
+                        .collect();
+
+                    self.streamer.set_loaded(chunk_index, entities);
+                }
+                ChunkAction::Unload => {
+                    for entity in self.streamer.take_entities(chunk_index) {
+                        self.despawn(entity);
+                    }
+                }
+                ChunkAction::Keep => {}
+            }
+        }
+    }
+
+    /// Creates a text label that is drawn on top of the scene
+    ///
+    /// # Arguments
+    ///
+    /// * `text_label` - The text label to draw
+    ///
+    /// # Returns
+    ///
+    /// The entity id
+    pub fn create_text_label(&mut self, mut text_label: TextLabel) -> Entity {
+        let mut overlay_text: OverlayText = (&text_label).into();
+        if let Some(key) = text_label.get_translation_key() {
+            overlay_text.text = self.localization.tr(key);
+        }
+
+        let renderer_index = self.with_renderer(|renderer| renderer.create_text(overlay_text));
+
+        if let Some(renderer_index) = renderer_index {
+            text_label.set_renderer_index(renderer_index);
+        }
+
+        let entity = self.ecs_instance.new_entity();
+        self.ecs_instance.add_component(entity, text_label);
+
+        entity
+    }
+
+    /// Creates a panel that is drawn on top of the scene below any text
+    ///
+    /// # Arguments
+    ///
+    /// * `panel` - The panel to draw
+    ///
+    /// # Returns
+    ///
+    /// The entity id
+    pub fn create_panel(&mut self, mut panel: Panel) -> Entity {
+        let quad_index = self.with_renderer(|renderer| renderer.create_quad(panel.quad()));
+
+        if let Some(quad_index) = quad_index {
+            panel.set_quad_index(quad_index);
+        }
+
+        let entity = self.ecs_instance.new_entity();
+        self.ecs_instance.add_component(entity, panel);
+
+        entity
+    }
+
+    /// Creates a button that can be clicked with the cursor
+    ///
+    /// # Arguments
+    ///
+    /// * `button` - The button to draw
+    ///
+    /// # Returns
+    ///
+    /// The entity id
+    pub fn create_button(&mut self, mut button: Button) -> Entity {
+        if let Some((quad_index, text_index)) = self.with_renderer(|renderer| {
+            (
+                renderer.create_quad(button.quad()),
+                renderer.create_text(button.overlay_text()),
+            )
+        }) {
+            button.set_renderer_indices(quad_index, text_index);
+        }
+
+        let entity = self.ecs_instance.new_entity();
+        self.ecs_instance.add_component(entity, button);
+
+        entity
+    }
+
+    /// Creates a slider that can be dragged with the cursor
+    ///
+    /// # Arguments
+    ///
+    /// * `slider` - The slider to draw
+    ///
+    /// # Returns
+    ///
+    /// The entity id
+    pub fn create_slider(&mut self, mut slider: Slider) -> Entity {
+        if let Some((track_index, handle_index)) = self.with_renderer(|renderer| {
+            (
+                renderer.create_quad(slider.track_quad()),
+                renderer.create_quad(slider.handle_quad()),
+            )
+        }) {
+            slider.set_renderer_indices(track_index, handle_index);
+        }
+
+        let entity = self.ecs_instance.new_entity();
+        self.ecs_instance.add_component(entity, slider);
+
+        entity
+    }
+
+    /// Creates an image that is drawn on top of the scene in screen space
+    ///
+    /// # Arguments
+    ///
+    /// * `hud_image` - The image to draw
+    ///
+    /// # Returns
+    ///
+    /// The entity id
+    pub fn create_hud_image(&mut self, mut hud_image: HudImage) -> Entity {
+        let renderer_index =
+            self.with_renderer(|renderer| renderer.create_sprite((&hud_image).into()));
+
+        if let Some(renderer_index) = renderer_index {
+            hud_image.set_renderer_index(renderer_index);
+        }
+
+        let entity = self.ecs_instance.new_entity();
+        self.ecs_instance.add_component(entity, hud_image);
+
+        entity
+    }
+
+    /// Projects a texture onto the scene, the decal follows the transform of the entity
+    ///
+    /// # Arguments
+    ///
+    /// * `decal` - The texture and size of the decal
+    /// * `transform` - The center and orientation of the projection box
+    ///
+    /// # Returns
+    ///
+    /// The entity id
+    pub fn create_decal(&mut self, mut decal: Decal, transform: Transform3d) -> Entity {
+        decal.set_transform(*transform.get_position(), *transform.get_rotation());
+        decal.update();
+
+        let renderer_index = self.with_renderer(|renderer| renderer.create_decal((&decal).into()));
+
+        if let Some(renderer_index) = renderer_index {
+            decal.set_renderer_index(renderer_index);
+        }
+
+        let entity = self.ecs_instance.new_entity();
+        self.ecs_instance.add_component(entity, decal);
+        self.ecs_instance.add_component(entity, transform);
+
+        entity
+    }
+
+    /// Loads an image from a file so it can be projected with a `Decal`
+    ///
+    /// # Arguments
+    ///
+    /// * `texture_path` - Filepath to the image
+    ///
+    /// # Returns
+    ///
+    /// A `DecalTexture` to use in a `Decal`
+    pub fn load_decal_texture<P>(&mut self, texture_path: P) -> Result<DecalTexture, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.with_renderer(|renderer| renderer.load_decal_texture(texture_path))
+            .unwrap_or(Ok(Default::default()))
+    }
+
+    /// Lights the scene with an environment map so metallic materials reflect it
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Filepath to an HDR image in the equirectangular format
+    /// * `intensity` - How bright the lighting from the environment is
+    pub fn set_environment<P>(&mut self, path: P, intensity: f32) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.with_renderer(|renderer| renderer.set_environment(path, intensity))
+            .unwrap_or(Ok(()))
+    }
+
+    /// Changes how bright the lighting from the environment map is
+    pub fn set_environment_intensity(&mut self, intensity: f32) {
+        self.with_renderer(|renderer| renderer.set_environment_intensity(intensity));
+    }
+
+    /// Changes the tonemapper and exposure that the scene is drawn to the screen with
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The settings of the post effects
+    pub fn set_post_settings(&mut self, settings: PostSettings) {
+        self.with_renderer(|renderer| renderer.set_post_settings(settings));
+    }
+
+    pub fn get_post_settings(&self) -> PostSettings {
+        self.with_renderer(|renderer| renderer.get_post_settings())
+            .unwrap_or_default()
+    }
+
+    /// Draws lens flares over the sun and bright lights that fade behind the scene
+    ///
+    /// # Arguments
+    ///
+    /// * `lens_flare` - The chain of flare elements, `None` turns the flares off
+    pub fn set_lens_flare(&mut self, lens_flare: Option<LensFlare>) {
+        self.with_renderer(|renderer| renderer.set_lens_flare(lens_flare));
+    }
+
+    /// Adds a full screen effect over the scene that fades in, like a red vignette when
+    /// the player takes damage or a blue tint and distortion underwater. Give it a
+    /// duration to flash it from a gameplay event
+    ///
+    /// # Arguments
+    ///
+    /// * `overlay` - The kind, color, and fading of the overlay
+    ///
+    /// # Returns
+    ///
+    /// The handle to change or remove the overlay with
+    pub fn add_screen_overlay(&mut self, overlay: ScreenOverlay) -> ScreenOverlayHandle {
+        self.with_renderer(|renderer| renderer.add_screen_overlay(overlay))
+            .unwrap_or_default()
+    }
+
+    /// Changes an overlay without fading it in again, like raising the strength of a
+    /// poison tint
+    pub fn update_screen_overlay(&mut self, handle: ScreenOverlayHandle, overlay: ScreenOverlay) {
+        self.with_renderer(|renderer| renderer.update_screen_overlay(handle, overlay));
+    }
+
+    /// Fades an overlay out and removes it from the screen
+    pub fn remove_screen_overlay(&mut self, handle: ScreenOverlayHandle) {
+        self.with_renderer(|renderer| renderer.remove_screen_overlay(handle));
+    }
+
+    pub fn has_screen_overlay(&self, handle: ScreenOverlayHandle) -> bool {
+        self.with_renderer(|renderer| renderer.has_screen_overlay(handle))
+            .unwrap_or_default()
+    }
+
+    /// Changes the quality and number of the shadows of the lights
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The settings of the shadows, lights only cast shadows when they were
+    ///   created with `Light::with_shadows`
+    pub fn set_shadow_settings(&mut self, settings: ShadowSettings) {
+        self.with_renderer(|renderer| renderer.set_shadow_settings(settings));
+    }
+
+    pub fn get_shadow_settings(&self) -> ShadowSettings {
+        self.with_renderer(|renderer| renderer.get_shadow_settings())
+            .unwrap_or_default()
+    }
+
+    /// Inserts a custom pass into the frame
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The point in the frame where the pass runs
+    /// * `pass` - The pass to run every frame
+    ///
+    /// # Returns
+    ///
+    /// A `RenderPassHandle` to remove the pass with or an error if the pass can not run at
+    /// the stage
+    pub fn add_render_pass<P>(
+        &mut self,
+        stage: RenderStage,
+        pass: P,
+    ) -> Result<RenderPassHandle, io::Error>
+    where
+        P: CustomRenderPass + 'static,
+    {
+        self.with_renderer(|renderer| renderer.add_render_pass(stage, pass))
+            .unwrap_or(Ok(Default::default()))
+    }
+
+    pub fn remove_render_pass(&mut self, handle: RenderPassHandle) {
+        self.with_renderer(|renderer| renderer.remove_render_pass(handle));
+    }
+
+    /// Grades the colors of the screen with a 3d lookup table
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Filepath to a `.cube` lookup table, `None` removes the color grading
+    pub fn set_color_grading<P>(&mut self, path: Option<P>) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.with_renderer(|renderer| renderer.set_color_grading(path))
+            .unwrap_or(Ok(()))
+    }
+
+    /// Loads an image from a file so it can be drawn with a `HudImage`
+    ///
+    /// # Arguments
+    ///
+    /// * `texture_path` - Filepath to the image
+    ///
+    /// # Returns
+    ///
+    /// A `SpriteHandle` to use in a `HudImage`
+    pub fn load_sprite_texture<P>(&mut self, texture_path: P) -> Result<SpriteHandle, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.with_renderer(|renderer| renderer.load_sprite_texture(texture_path))
+            .unwrap_or(Ok(Default::default()))
+    }
+
+    /// Packs images from files into one texture so the `HudImage`s drawn with them share a
+    /// draw call
+    ///
+    /// # Arguments
+    ///
+    /// * `texture_paths` - Filepaths to the images
+    ///
+    /// # Returns
+    ///
+    /// A `SpriteHandle` for each image in the same order as the paths
+    pub fn load_sprite_atlas<P>(
+        &mut self,
+        texture_paths: &[P],
+    ) -> Result<Vec<SpriteHandle>, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.with_renderer(|renderer| renderer.load_sprite_atlas(texture_paths))
+            .unwrap_or(Ok(Default::default()))
+    }
+
+    /// Names a sprite so `TextLabel`s with markup can draw it inline with `[icon=name]`
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name used in the markup
+    /// * `sprite` - The sprite to draw, `None` removes the icon
+    pub fn set_text_icon(&mut self, name: &str, sprite: Option<SpriteHandle>) {
+        self.with_renderer(|renderer| renderer.set_text_icon(name, sprite));
+    }
+
+    /// Adds text that follows the transform of an entity
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity with a `Transform3d` to place the text at
+    /// * `world_text` - The text to draw
+    ///
+    /// # Returns
+    ///
+    /// The entity id
+    pub fn add_world_text(&mut self, entity: Entity, mut world_text: WorldText) -> Entity {
+        let renderer_index = self.with_renderer(|renderer| {
+            // The text is hidden until it is projected onto the screen
+            let renderer_index = renderer.create_text(OverlayText::new(
+                String::new(),
+                (0.0, 0.0),
+                Default::default(),
+            ));
+            renderer.remove_text(renderer_index);
+            renderer_index
+        });
+
+        if let Some(renderer_index) = renderer_index {
+            world_text.set_renderer_index(renderer_index);
+        }
+        self.ecs_instance.add_component(entity, world_text);
+
+        entity
+    }
+
+    /// Adds sharp text that is drawn in the world at an entity
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity with a `Transform3d` to place the label at
+    /// * `world_label` - The label to draw
+    ///
+    /// # Returns
+    ///
+    /// The entity id
+    pub fn add_world_label(&mut self, entity: Entity, mut world_label: WorldLabel) -> Entity {
+        let renderer_index = self.with_renderer(|renderer| {
+            // The label is hidden until the transform of the entity is known
+            let renderer_index =
+                renderer.create_sdf_text(SdfText::billboard(String::new(), Vector3::zero(), 0.0));
+            renderer.remove_sdf_text(renderer_index);
+            renderer_index
+        });
+
+        if let Some(renderer_index) = renderer_index {
+            world_label.set_renderer_index(renderer_index);
+        }
+        self.ecs_instance.add_component(entity, world_label);
+
+        entity
+    }
+
+    /// Draws signed distance field text that is not attached to an entity, like a large
+    /// title on the screen
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text and where it is drawn
+    ///
+    /// # Returns
+    ///
+    /// The index of the text used to update or remove it
+    pub fn create_sdf_text(&mut self, text: SdfText) -> usize {
+        self.with_renderer(|renderer| renderer.create_sdf_text(text))
+            .unwrap_or_default()
+    }
+
+    pub fn update_sdf_text(&mut self, text_index: usize, text: SdfText) {
+        self.with_renderer(|renderer| renderer.update_sdf_text(text_index, text));
+    }
+
+    pub fn remove_sdf_text(&mut self, text_index: usize) {
+        self.with_renderer(|renderer| renderer.remove_sdf_text(text_index));
+    }
+
+    /// Adds a bar that follows the transform of an entity
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity with a `Transform3d` to place the bar at
+    /// * `world_bar` - The bar to draw
+    ///
+    /// # Returns
+    ///
+    /// The entity id
+    pub fn add_world_bar(&mut self, entity: Entity, mut world_bar: WorldBar) -> Entity {
+        let renderer_indices = self.with_renderer(|renderer| {
+            // The bar is hidden until it is projected onto the screen
+            let empty_quad = OverlayQuad::new((0.0, 0.0), (0.0, 0.0), [0.0; 4]);
+            let background_index = renderer.create_quad(empty_quad);
+            let fill_index = renderer.create_quad(empty_quad);
+            renderer.remove_quad(background_index);
+            renderer.remove_quad(fill_index);
+            (background_index, fill_index)
+        });
+
+        if let Some((background_index, fill_index)) = renderer_indices {
+            world_bar.set_renderer_indices(background_index, fill_index);
+        }
+        self.ecs_instance.add_component(entity, world_bar);
+
+        entity
+    }
+
+    /// Loads a font from a file so text can be drawn with it
+    ///
+    /// # Arguments
+    ///
+    /// * `font_path` - Filepath to the ttf or otf font
+    ///
+    /// # Returns
+    ///
+    /// A `FontHandle` to use in a `TextStyle`
+    pub fn load_font<P>(&mut self, font_path: P) -> Result<FontHandle, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.with_renderer(|renderer| renderer.load_font(font_path))
+            .unwrap_or(Ok(Default::default()))
+    }
+
+    /// Draws the characters a font is missing with other fonts, needed when a translation
+    /// uses an alphabet the font of the text does not have
+    ///
+    /// # Arguments
+    ///
+    /// * `font` - The font of the text
+    /// * `fallbacks` - The fonts that are tried in order, empty removes the fallbacks
+    pub fn set_font_fallbacks(&mut self, font: FontHandle, fallbacks: &[FontHandle]) {
+        self.with_renderer(|renderer| renderer.set_font_fallbacks(font, fallbacks));
+    }
+
+    /// Loads the strings of a language from a `.ftl` or `.json` string table
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - The name of the language, like "en" or "ja"
+    /// * `path` - Filepath to the string table
+    pub fn load_string_table<P>(&mut self, language: &str, path: P) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.localization.load_table(language, path)
+    }
+
+    /// Switches the language of every localized text label at runtime
+    pub fn set_language(&mut self, language: &str) {
+        self.localization.set_language(language);
+
+        if let Some(mut text_labels) = self.query_mut::<TextLabel>() {
+            for (_, text_label) in text_labels.iter_mut() {
+                if text_label.get_translation_key().is_some() {
+                    text_label.refresh();
+                }
+            }
+        }
+    }
+
+    pub fn get_language(&self) -> &str {
+        self.localization.get_language()
+    }
+
+    /// The string of a key in the current language, the key itself when it is missing
+    pub fn tr(&self, key: &str) -> String {
+        self.localization.tr(key)
+    }
+
+    /// The string of a key with its `{ $name }` variables filled in
+    pub fn tr_with(&self, key: &str, args: &[(&str, &str)]) -> String {
+        self.localization.tr_with(key, args)
+    }
+
+    pub fn get_localization(&self) -> &Localization {
+        &self.localization
+    }
+
+    pub fn get_localization_mut(&mut self) -> &mut Localization {
+        &mut self.localization
+    }
+
+    /// Sets the transform for a specified entity to a new transform
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity to change the transform of
+    /// * `transform` - The new Transform to set for the entity
+    ///
+    /// # Returns
+    ///
+    /// The entity id
+    #[deprecated]
+    pub fn update_transform(&mut self, entity: Entity, transform: Transform3d) -> Entity {
+        self.ecs_instance.add_component(entity, transform);
+
+        // Get the renderer index from the model
+        let object_index = *self
+            .ecs_instance
+            .query::<Model3d>()
+            .unwrap()
+            .get(&entity)
+            .unwrap()
+            .get_renderer_index()
+            .unwrap();
+
+        self.with_renderer(|renderer| {
+            renderer.update_instance_transforms(object_index, vec![transform.into()])
+        });
+
+        entity
+    }
+
+    #[deprecated]
+    pub fn set_position(&mut self, entity: Entity, position: Vector3<f32>) {
+        let object_index = *self
+            .ecs_instance
+            .query::<Model3d>()
+            .unwrap()
+            .get(&entity)
+            .unwrap()
+            .get_renderer_index()
+            .unwrap();
+
+        if let Some(transform) = self
+            .ecs_instance
+            .query_mut::<Transform3d>()
+            .unwrap()
+            .get_mut(&entity)
+        {
+            Transform3d::set_position(transform, position);
+            self.with_renderer(|renderer| {
+                renderer.update_instance_transforms(object_index, vec![(*transform).into()])
+            });
+        }
+    }
+
+    // #[deprecated]
+    pub fn set_rotation(&mut self, entity: Entity, rotation: Quaternion<f32>) {
+        let object_index = *self
+            .ecs_instance
+            .query::<Model3d>()
+            .unwrap()
+            .get(&entity)
+            .unwrap()
+            .get_renderer_index()
+            .unwrap();
+
+        if let Some(transform) = self
+            .ecs_instance
+            .query_mut::<Transform3d>()
+            .unwrap()
+            .get_mut(&entity)
+        {
+            Transform3d::set_rotation(transform, rotation);
+            self.with_renderer(|renderer| {
+                renderer.update_instance_transforms(object_index, vec![(*transform).into()])
+            });
+        }
+    }
+
+    #[deprecated]
+    pub fn move_transform_to_renderer(&self, entity: Entity) {
+        let object_index = *self
+            .ecs_instance
+            .query::<Model3d>()
+            .unwrap()
+            .get(&entity)
+            .unwrap()
+            .get_renderer_index()
+            .unwrap();
+
+        let transforms = self.ecs_instance.query::<Transform3d>();
+        if let Some(transform) = transforms.unwrap().get(&entity) {
+            self.with_renderer(|renderer| {
+                renderer.update_instance_transforms(object_index, vec![(*transform).into()])
+            });
+        }
+    }
+
+    /// Sets the tint color of the model of an entity without changing its transform
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity with the model to tint
+    /// * `color` - The RGBA color to multiply into the material color
+    pub fn set_instance_color<C>(&mut self, entity: Entity, color: C)
+    where
+        C: Into<LinearRgba>,
+    {
+        if let Some(object_index) = self.get_renderer_index(entity) {
+            self.with_renderer(|renderer| renderer.set_instance_color(object_index, color));
+        }
+    }
+
+    /// Sets the custom shader data of the model of an entity without changing its transform
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity with the model to modify
+    /// * `custom_data` - The data passed to the shaders
+    pub fn set_instance_custom_data(&mut self, entity: Entity, custom_data: [f32; 4]) {
+        if let Some(object_index) = self.get_renderer_index(entity) {
+            self.with_renderer(|renderer| {
+                renderer.set_instance_custom_data(object_index, custom_data)
+            });
+        }
+    }
+
+    /// Sets the layer of the texture array that the model of an entity is drawn with so
+    /// instances of a layered material can look different without rebinding it
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity with the model to modify
+    /// * `layer` - The layer of the diffuse texture array of the material
+    pub fn set_instance_layer(&mut self, entity: Entity, layer: u32) {
+        if let Some(object_index) = self.get_renderer_index(entity) {
+            self.with_renderer(|renderer| renderer.set_instance_layer(object_index, layer));
+        }
+    }
+
+    /// Scales the emissive color of the model of an entity, use the `Emissive` component
+    /// to animate it
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity with the model to modify
+    /// * `emission` - Multiplies the emissive color of the material, 0 turns the glow off
+    pub fn set_instance_emission(&mut self, entity: Entity, emission: f32) {
+        if let Some(object_index) = self.get_renderer_index(entity) {
+            self.with_renderer(|renderer| renderer.set_instance_emission(object_index, emission));
+        }
+    }
+
+    /// Moves the texture coordinates of the model of an entity over time, for scrolling
+    /// surfaces and sprite sheet flipbooks
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity with the model to modify
+    /// * `texture_animation` - How the texture coordinates of every material move
+    pub fn set_texture_animation(&mut self, entity: Entity, texture_animation: TextureAnimation) {
+        if let Some(object_index) = self.get_renderer_index(entity) {
+            self.with_renderer(|renderer| {
+                renderer.set_object_texture_animation(object_index, texture_animation)
+            });
+        }
+    }
+
+    /// Loads the shapes the model of an entity can be blended towards, every file is the
+    /// obj file of the model in another shape with the same vertices. The model has to be
+    /// loaded from an obj file and finished loading
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity with the model
+    /// * `targets` - The name and file of every target, in the order of their weights
+    pub fn load_morph_targets<P>(
+        &mut self,
+        entity: Entity,
+        targets: &[(&str, P)],
+    ) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let Some(object_index) = self.get_renderer_index(entity) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "The entity does not have a model",
+            ));
+        };
+
+        self.with_renderer(|renderer| renderer.load_morph_targets(object_index, targets))
+            .unwrap_or(Ok(()))
+    }
+
+    /// Blends the model of an entity towards its morph targets
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity with the model to modify
+    /// * `weights` - How far to blend towards each target, in the order they were loaded
+    pub fn set_morph_weights(&mut self, entity: Entity, weights: &[f32]) {
+        if let Some(object_index) = self.get_renderer_index(entity) {
+            self.with_renderer(|renderer| renderer.set_morph_weights(object_index, weights));
+        }
+    }
+
+    /// Sets how much lights in candela are scaled before they are added to the scene
+    ///
+    /// # Arguments
+    ///
+    /// * `ev100` - The exposure value of the camera, around 15 for a sunny day and around 5
+    ///   for a lit room
+    pub fn set_light_exposure(&mut self, ev100: f32) {
+        self.with_renderer(|renderer| renderer.set_light_exposure(exposure_from_ev100(ev100)));
+    }
+
+    /// Logs warnings for textures and surfaces that are in the wrong color space
+    pub fn set_color_audit(&mut self, enabled: bool) {
+        self.with_renderer(|renderer| renderer.set_color_audit(enabled));
+    }
+
+    /// Gets the number of draw calls and state changes used to draw the last frame
+    pub fn get_render_stats(&self) -> RenderStats {
+        self.with_renderer(|renderer| renderer.get_render_stats())
+            .unwrap_or_default()
+    }
+
+    /// Gets how long the update functions and each engine system took last frame
+    pub fn get_system_timings(&self) -> &SystemTimings {
+        &self.system_timings
+    }
+
+    /// Gives the console of the latest log lines, there is one when the log settings
+    /// keep lines for a console
+    pub fn get_log_console(&self) -> Option<&LogConsole> {
+        self.log_console.as_ref()
+    }
+
+    /// Changes how often the update thread runs and how often the physics systems run
+    pub fn set_update_pacing(&mut self, pacing: UpdatePacing) {
+        self.pacing = pacing;
+    }
+
+    pub fn get_update_pacing(&self) -> UpdatePacing {
+        self.pacing
+    }
+
+    /// Used internally to count the fixed ticks that fit in the time this update covers
+    ///
+    /// # Returns
+    ///
+    /// The delta time of a tick and the number of ticks to run, `None` when the physics
+    /// systems run once every update
+    pub(crate) fn take_fixed_ticks(&mut self) -> Option<(Duration, u32)> {
+        let interval = self.pacing.get_fixed_interval()?;
+        let ticks =
+            self.fixed_clock
+                .advance(self.frame_time, interval, self.pacing.get_max_fixed_ticks());
+
+        Some((interval, ticks))
+    }
+
+    /// Sets up the editor fly camera that is toggled with a key. While it is active the
+    /// right mouse button turns it, WASD moves it, Q and E move it down and up, the mouse
+    /// wheel changes its speed, and the focus key moves it to the selected entity. The
+    /// cameras of the game keep their place and are drawn again when it is turned off
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The keys and speeds of the camera, `None` removes the editor camera
+    pub fn set_editor_camera(&mut self, settings: Option<EditorCamera>) {
+        self.set_editor_camera_active(false);
+        self.editor_camera = settings.map(EditorCameraState::new);
+    }
+
+    pub fn get_editor_camera_settings(&self) -> Option<EditorCamera> {
+        self.editor_camera
+            .as_ref()
+            .map(EditorCameraState::get_settings)
+    }
+
+    /// Turns the editor camera on or off like its toggle key
+    ///
+    /// # Arguments
+    ///
+    /// * `active` - If the scene is drawn from the editor camera
+    ///
+    /// # Returns
+    ///
+    /// If the editor camera is active, it can not start without an editor camera set up
+    /// or a camera of the game to start from
+    pub fn set_editor_camera_active(&mut self, active: bool) -> bool {
+        let game_camera = self.camera_id.and_then(|camera_id| {
+            self.ecs_instance
+                .query::<Camera3d>()
+                .and_then(|cameras| cameras.get(&camera_id).copied())
+        });
+
+        let Some(editor_camera) = self.editor_camera.as_mut() else {
+            return false;
+        };
+
+        if active == editor_camera.is_active() {
+            return active;
+        }
+
+        if active {
+            match game_camera {
+                Some(camera) => editor_camera.activate(camera),
+                None => {
+                    warn!("The editor camera needs a camera to start from");
+                    return false;
+                }
+            }
+        } else {
+            editor_camera.deactivate();
+            if let Some(camera) = game_camera {
+                self.render_camera(&camera);
+            }
+        }
+
+        active
+    }
+
+    pub fn is_editor_camera_active(&self) -> bool {
+        self.editor_camera
+            .as_ref()
+            .is_some_and(EditorCameraState::is_active)
+    }
+
+    /// Gets the camera the scene is drawn from while the editor camera is active
+    pub fn get_editor_camera(&self) -> Option<&Camera3d> {
+        self.editor_camera.as_ref()?.get_camera()
+    }
+
+    /// Selects the entity that is edited, the editor camera focuses on it
+    pub fn set_selected(&mut self, entity: Option<Entity>) {
+        self.selected = entity;
+    }
+
+    pub fn get_selected(&self) -> Option<Entity> {
+        self.selected
+    }
+
+    /// Draws handles on the selected entity that move, turn, and scale its transform
+    /// when they are dragged with the left mouse button
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The mode, size, and snapping of the handles, `None` removes them
+    pub fn set_gizmo(&mut self, settings: Option<Gizmo>) {
+        self.gizmo = settings.map(GizmoState::new);
+    }
+
+    pub fn get_gizmo(&self) -> Option<Gizmo> {
+        self.gizmo.as_ref().map(GizmoState::get_settings)
+    }
+
+    /// Switches the handles between moving, turning, and scaling
+    pub fn set_gizmo_mode(&mut self, mode: GizmoMode) {
+        if let Some(gizmo) = self.gizmo.as_mut() {
+            gizmo.set_mode(mode);
+        }
+    }
+
+    /// If a handle of the gizmo is being dragged, clicks in the game can be ignored
+    /// while it is
+    pub fn is_gizmo_dragging(&self) -> bool {
+        self.gizmo.as_ref().is_some_and(GizmoState::is_dragging)
+    }
+
+    /// Used internally to drag the handles of the gizmo with the cursor
+    pub(crate) fn update_gizmo(&mut self) {
+        let Some(gizmo) = self.gizmo.as_mut() else {
+            return;
+        };
+
+        let transform = self.selected.and_then(|selected| {
+            self.ecs_instance
+                .query::<Transform3d>()
+                .and_then(|transforms| transforms.get(&selected).copied())
+        });
+
+        // The handles are picked from what is drawn
+        let Some(renderer) = &self.renderer_instance else {
+            return;
+        };
+        let action = gizmo.update(
+            transform,
+            (self.cursor.position, self.cursor.left_pressed),
+            &mut renderer.lock().unwrap(),
+        );
+
+        match action {
+            GizmoAction::None => {}
+            GizmoAction::Transform(transform) => {
+                let selected = self.selected.unwrap();
+                if let Some(current) = self
+                    .ecs_instance
+                    .query_mut::<Transform3d>()
+                    .as_mut()
+                    .and_then(|transforms| transforms.get_mut(&selected))
+                {
+                    *current = transform;
+                }
+            }
+            GizmoAction::Release(start) => {
+                let selected = self.selected.unwrap();
+                let after = self
+                    .ecs_instance
+                    .query::<Transform3d>()
+                    .and_then(|transforms| transforms.get(&selected).copied());
+                if let Some(after) = after {
+                    self.record_edit(Box::new(TransformEdit {
+                        entity: selected,
+                        before: start,
+                        after,
+                    }));
+                }
+            }
+            GizmoAction::Select(x, y) => {
+                self.pick(x, y, |manager, entity| manager.set_selected(entity));
+            }
+        }
+    }
+
+    /// Makes an edit that can be undone with `undo`, the edits that could be redone are
+    /// forgotten
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The edit to make
+    pub fn apply_edit(&mut self, mut command: Box<dyn EditCommand>) {
+        command.apply(self);
+        self.record_edit(command);
+    }
+
+    // Adds an edit that was already made to the history
+    fn record_edit(&mut self, command: Box<dyn EditCommand>) {
+        let discarded = self.undo_history.push(command);
+        self.discard_edits(discarded);
+    }
+
+    fn discard_edits(&mut self, discarded: Vec<Box<dyn EditCommand>>) {
+        for mut command in discarded {
+            command.discard(self);
+        }
+    }
+
+    /// Sets the transform of an entity as an edit that can be undone
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity to move
+    /// * `transform` - The new transform of the entity
+    ///
+    /// # Returns
+    ///
+    /// Whether the entity has a transform to edit
+    pub fn edit_transform(&mut self, entity: Entity, transform: Transform3d) -> bool {
+        let Some(before) = self
+            .query::<Transform3d>()
+            .and_then(|transforms| transforms.get(&entity).copied())
+        else {
+            return false;
+        };
+
+        self.apply_edit(Box::new(TransformEdit {
+            entity,
+            before,
+            after: transform,
+        }));
+        true
+    }
+
+    /// Sets, adds, or removes a component of an entity as an edit that can be undone
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity to edit
+    /// * `component` - The new value of the component, `None` removes it
+    pub fn edit_component<ComponentType: Clone + 'static>(
+        &mut self,
+        entity: Entity,
+        component: Option<ComponentType>,
+    ) {
+        let before = self
+            .query::<ComponentType>()
+            .and_then(|components| components.get(&entity).cloned());
+
+        self.apply_edit(Box::new(ComponentEdit {
+            entity,
+            before,
+            after: component,
+        }));
+    }
+
+    /// Records that an entity was just created so undoing removes it again
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity that was created in the active world
+    ///
+    /// # Returns
+    ///
+    /// Whether the entity is in a world and was recorded
+    pub fn edit_spawn(&mut self, entity: Entity) -> bool {
+        let Some(world) = self.ecs_instance.world_of(entity) else {
+            return false;
+        };
+
+        self.apply_edit(Box::new(SpawnEdit {
+            entity,
+            world,
+            spawn: true,
+        }));
+        true
+    }
+
+    /// Despawns an entity as an edit that can be undone, the entity keeps its id and
+    /// components until the edit leaves the history
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity to despawn
+    ///
+    /// # Returns
+    ///
+    /// Whether the entity is in a world and was despawned
+    pub fn edit_despawn(&mut self, entity: Entity) -> bool {
+        let Some(world) = self.ecs_instance.world_of(entity) else {
+            return false;
+        };
+        if Some(world) == self.removed_world {
+            return false;
+        }
+
+        self.apply_edit(Box::new(SpawnEdit {
+            entity,
+            world,
+            spawn: false,
+        }));
+        true
+    }
+
+    /// Undoes the last edit
+    ///
+    /// # Returns
+    ///
+    /// Whether there was an edit to undo
+    pub fn undo(&mut self) -> bool {
+        let Some(mut command) = self.undo_history.take_undo() else {
+            return false;
+        };
+
+        command.revert(self);
+        self.undo_history.push_undone(command);
+        true
+    }
+
+    /// Makes the last undone edit again
+    ///
+    /// # Returns
+    ///
+    /// Whether there was an edit to redo
+    pub fn redo(&mut self) -> bool {
+        let Some(mut command) = self.undo_history.take_redo() else {
+            return false;
+        };
+
+        command.apply(self);
+        let discarded = self.undo_history.push_redone(command);
+        self.discard_edits(discarded);
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.undo_history.get_undo_name().is_some()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.undo_history.get_redo_name().is_some()
+    }
+
+    /// The name of the edit `undo` would undo, like "Transform" or "Despawn"
+    pub fn get_undo_name(&self) -> Option<&str> {
+        self.undo_history.get_undo_name()
+    }
+
+    /// The name of the edit `redo` would make again
+    pub fn get_redo_name(&self) -> Option<&str> {
+        self.undo_history.get_redo_name()
+    }
+
+    /// Sets how many edits can be undone, the oldest edits are forgotten past it
+    ///
+    /// # Arguments
+    ///
+    /// * `depth` - The number of edits to keep, 100 by default
+    pub fn set_undo_depth(&mut self, depth: usize) {
+        let discarded = self.undo_history.set_depth(depth);
+        self.discard_edits(discarded);
+    }
+
+    pub fn get_undo_depth(&self) -> usize {
+        self.undo_history.get_depth()
+    }
+
+    /// Forgets every edit, entities despawned by edits are removed for good
+    pub fn clear_undo_history(&mut self) {
+        let discarded = self.undo_history.clear();
+        self.discard_edits(discarded);
+    }
+
+    /// Used internally to get the hidden world despawned entities are kept in
+    pub(crate) fn get_removed_world(&mut self) -> WorldId {
+        if let Some(world) = self.removed_world {
+            return world;
+        }
+
+        let world = self.create_world();
+        self.set_world_rendered(world, false);
+        self.removed_world = Some(world);
+        world
+    }
+
+    /// Used internally to remove an entity for good once its despawn can not be undone
+    pub(crate) fn despawn_removed(&mut self, entity: Entity) {
+        let Some(world) = self.removed_world else {
+            return;
+        };
+        if self.ecs_instance.world_of(entity) != Some(world) {
+            return;
+        }
+
+        let active = self.ecs_instance.get_active_world();
+        self.ecs_instance.set_active_world(world);
+        self.despawn(entity);
+        self.ecs_instance.set_active_world(active);
+    }
+
+    /// Used internally to give the editor camera the keys and scrolling of an event
+    pub(crate) fn process_editor_input(&mut self, event: &DeviceEvent) {
+        if let Some(editor_camera) = self.editor_camera.as_mut() {
+            editor_camera.process_event(event);
+        }
+    }
+
+    /// Used internally to fly the editor camera and draw the scene from it
+    pub(crate) fn update_editor_camera(&mut self) {
+        let Some(editor_camera) = self.editor_camera.as_mut() else {
+            return;
+        };
+
+        let toggle = editor_camera.take_toggle();
+        let focus = editor_camera.take_focus();
+
+        if toggle {
+            let active = !self.is_editor_camera_active();
+            self.set_editor_camera_active(active);
+        }
+
+        let focus = self
+            .selected
+            .filter(|_| focus)
+            .and_then(|selected| self.get_world_bounding_sphere(selected));
+        let look_delta = std::mem::take(&mut self.look_delta);
+        let look = self.cursor.right_pressed.then_some(look_delta);
+        let delta_time = self.get_delta_time();
+        let aspect = self.get_aspect_ratio();
+
+        let Some(editor_camera) = self.editor_camera.as_mut() else {
+            return;
+        };
+        if let Some(sphere) = focus {
+            editor_camera.focus(sphere);
+        }
+
+        if let Some(camera) = editor_camera.update(look, delta_time, aspect) {
+            self.render_camera(&camera);
+        }
+    }
+
+    /// Draws the render stats and the system timings under the fps
+    pub fn set_stats_overlay(&mut self, enabled: bool) {
+        self.stats_overlay = enabled;
+        if !enabled {
+            self.with_renderer(|renderer| renderer.stats.clear());
+        }
+    }
+
+    /// Draws the profile spans of the last update and the last render as a flamegraph at
+    /// the bottom of the screen, showing it turns profiling on
+    pub fn set_profiler_overlay(&mut self, enabled: bool) {
+        if enabled {
+            set_profiling(true);
+            self.profiler_overlay
+                .get_or_insert_with(ProfilerOverlay::default);
+        } else if let Some(mut overlay) = self.profiler_overlay.take() {
+            self.with_renderer(|renderer| overlay.clear(renderer));
+        }
+    }
+
+    /// Used internally to draw the flamegraph of the last frames
+    pub(crate) fn update_profiler_overlay(&mut self) {
+        if let (Some(overlay), Some(renderer)) =
+            (self.profiler_overlay.as_mut(), &self.renderer_instance)
+        {
+            overlay.update(&mut renderer.lock().unwrap());
+        }
+    }
+
+    /// Used internally to send the stats of the last frame to the overlay
+    pub fn update_stats_overlay(&mut self) {
+        if !self.stats_overlay {
+            return;
+        }
+
+        self.with_renderer(|renderer| {
+            let render_stats = renderer.get_render_stats();
+            renderer.stats = format!(
+                "{} draws {} triangles {} culled\n{}\n{:>8.3} ms total",
+                render_stats.draw_calls,
+                render_stats.triangles,
+                render_stats.culled,
+                self.system_timings.to_overlay_text(),
+                self.system_timings.total().as_secs_f64() * 1000.0,
+            );
+        });
+    }
+
+    /// Sets the nav mesh that nav agents find their paths over
+    pub fn set_navmesh(&mut self, navmesh: NavMesh) {
+        self.navmesh = Some(navmesh);
+    }
+
+    pub fn get_navmesh(&self) -> Option<&NavMesh> {
+        self.navmesh.as_ref()
+    }
+
+    /// Loads the nav mesh that nav agents find their paths over from an obj file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Filepath of the obj file
+    pub fn load_navmesh<P>(&mut self, path: P) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.navmesh = Some(NavMesh::load(path)?);
+        Ok(())
+    }
+
+    /// Bakes the nav mesh from the stationary plane colliders in the scene, planes that
+    /// share an edge are connected
+    ///
+    /// # Arguments
+    ///
+    /// * `max_slope` - The steepest walkable slope in degrees
+    pub fn bake_navmesh(&mut self, max_slope: f32) {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        if let Some(planes) = self.ecs_instance.query::<StationaryPlaneCollider>() {
+            for plane in planes.values() {
+                let base = vertices.len();
+                vertices.extend_from_slice(plane.get_corners());
+                indices.push([base, base + 1, base + 2]);
+                indices.push([base, base + 2, base + 3]);
+            }
+        }
+
+        self.navmesh = Some(NavMesh::bake(&vertices, &indices, max_slope.to_radians()));
+    }
+
+    /// Finds a path over the nav mesh
+    ///
+    /// # Returns
+    ///
+    /// The corners of the path or `None` if there is no nav mesh or no path
+    pub fn find_path(&self, start: Vector3<f32>, end: Vector3<f32>) -> Option<Vec<Vector3<f32>>> {
+        self.navmesh.as_ref()?.find_path(start, end)
+    }
+
+    /// Sends the nav agent of an entity to a point
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity with the `NavAgent`
+    /// * `destination` - The point to move to
+    pub fn set_nav_destination(&mut self, entity: Entity, destination: Vector3<f32>) {
+        if let Some(agent) = self
+            .ecs_instance
+            .query_mut::<NavAgent>()
+            .as_mut()
+            .and_then(|agents| agents.get_mut(&entity))
+        {
+            agent.set_destination(destination);
+        }
+    }
+
+    /// Checks if an entity is standing on a surface, only entities with `Gravity` and a
+    /// `RectangleCollider` have their ground tracked
+    pub fn is_grounded(&self, entity: Entity) -> bool {
+        self.ecs_instance
+            .query::<GroundState>()
+            .and_then(|ground_states| ground_states.get(&entity).map(GroundState::is_grounded))
+            .unwrap_or(false)
+    }
+
+    /// Gets the box around the model of an entity in world space
+    ///
+    /// # Returns
+    ///
+    /// The box or `None` if the entity has no model or the model has not finished loading
+    pub fn get_world_aabb(&self, entity: Entity) -> Option<Aabb> {
+        let aabb = self.get_local_aabb(entity)?;
+
+        Some(match self.get_transform_parts(entity) {
+            Some((position, rotation, scale)) => aabb.scaled(scale).transformed(position, rotation),
+            None => aabb,
+        })
+    }
+
+    /// Gets the box around the model of an entity in model space, before the transform
+    /// of the entity is applied
+    ///
+    /// # Returns
+    ///
+    /// The box or `None` if the entity has no model or the model has not finished loading
+    pub fn get_local_aabb(&self, entity: Entity) -> Option<Aabb> {
+        let object_index = self.get_renderer_index(entity)?;
+        self.with_renderer(|renderer| renderer.get_object_aabb(object_index))
+            .flatten()
+    }
+
+    /// Gets the sphere around the model of an entity in world space
+    ///
+    /// # Returns
+    ///
+    /// The sphere or `None` if the entity has no model or the model has not finished loading
+    pub fn get_world_bounding_sphere(&self, entity: Entity) -> Option<BoundingSphere> {
+        let object_index = self.get_renderer_index(entity)?;
+        let sphere = self
+            .with_renderer(|renderer| renderer.get_object_bounding_sphere(object_index))
+            .flatten()?;
+
+        Some(match self.get_transform_parts(entity) {
+            Some((position, rotation, scale)) => {
+                sphere.scaled(scale).transformed(position, rotation)
+            }
+            None => sphere,
+        })
+    }
+
+    /// Moves a camera back along the direction it is looking until the model of an entity
+    /// fills the view
+    ///
+    /// # Arguments
+    ///
+    /// * `camera_entity` - The entity with the `Camera3d` to move
+    /// * `target` - The entity with the model to frame, nothing happens if it has not
+    ///   finished loading
+    pub fn frame_camera(&mut self, camera_entity: Entity, target: Entity) {
+        let Some(sphere) = self.get_world_bounding_sphere(target) else {
+            return;
+        };
+
+        let camera = {
+            let Some(mut cameras) = self.ecs_instance.query_mut::<Camera3d>() else {
+                return;
+            };
+            let Some(camera) = cameras.get_mut(&camera_entity) else {
+                return;
+            };
+
+            camera.frame(sphere);
+            *camera
+        };
+
+        // Cameras with a transform follow it so the transform is moved too
+        if let Some(transform) = self
+            .ecs_instance
+            .query_mut::<Transform3d>()
+            .as_mut()
+            .and_then(|transforms| transforms.get_mut(&camera_entity))
+        {
+            transform.update_position(camera.eye.to_vec());
+        }
+
+        self.move_camera_to_render(&camera);
+    }
+
+    /// Skips drawing models that are outside of the view of the camera, it is on by default
+    pub fn set_frustum_culling(&mut self, enabled: bool) {
+        self.with_renderer(|renderer| renderer.set_frustum_culling(enabled));
+    }
+
+    /// Draws the scene at a different resolution than the screen, lower is faster
+    ///
+    /// # Arguments
+    ///
+    /// * `scale` - The resolution of the scene compared to the screen, from 0.25 to 2
+    pub fn set_render_scale(&mut self, scale: f32) {
+        self.with_renderer(|renderer| renderer.set_render_scale(scale));
+    }
+
+    pub fn get_render_scale(&self) -> f32 {
+        self.with_renderer(|renderer| renderer.get_render_scale())
+            .unwrap_or(1.0)
+    }
+
+    /// Bakes ambient occlusion into the vertices of models loaded after it is set, which
+    /// darkens crevices for free on hardware that can not afford screen space occlusion
+    ///
+    /// # Arguments
+    ///
+    /// * `occlusion` - How the occlusion is baked, `None` to stop baking
+    pub fn set_vertex_occlusion(&mut self, occlusion: Option<AmbientOcclusionBake>) {
+        self.with_renderer(|renderer| renderer.set_vertex_occlusion(occlusion));
+    }
+
+    /// Changes the render scale automatically to keep the frame time near a target
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The target and limits of the scale, `None` turns it off
+    pub fn set_dynamic_resolution(&mut self, settings: Option<DynamicResolution>) {
+        self.with_renderer(|renderer| renderer.set_dynamic_resolution(settings));
+    }
+
+    /// Gets the optional gpu features and the limits the renderer was set up with, `None`
+    /// when the manager runs without a renderer
+    pub fn get_renderer_capabilities(&self) -> Option<RendererCapabilities> {
+        self.with_renderer(|renderer| renderer.get_capabilities().clone())
+    }
+
+    /// Draws the meshes with indirect draws, useful for heavily instanced scenes
+    ///
+    /// # Returns
+    ///
+    /// Whether indirect drawing is on, it stays off when the gpu does not support it
+    pub fn set_indirect_drawing(&mut self, enabled: bool) -> bool {
+        self.with_renderer(|renderer| renderer.set_indirect_drawing(enabled))
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn get_transform_parts(
+        &self,
+        entity: Entity,
+    ) -> Option<(Vector3<f32>, Quaternion<f32>, Vector3<f32>)> {
+        let transforms = self.ecs_instance.query::<Transform3d>()?;
+        Some((*transforms.get(&entity)?).into())
+    }
+
+    // Finds the renderer index of the model attached to an entity
+    fn get_renderer_index(&self, entity: Entity) -> Option<usize> {
+        self.ecs_instance
+            .query::<Model3d>()?
+            .get(&entity)?
+            .get_renderer_index()
+            .copied()
+    }
+
+    /// Writes every entity with its label, components and renderer handles to a file
+    /// for inspecting the scene. Files ending in `.dot` or `.gv` are written as a graphviz
+    /// graph and any other file is written as JSON
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Filepath to write the scene graph to
+    pub fn dump_scene_graph<P>(&self, path: P) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let nodes = self
+            .ecs_instance
+            .entities()
+            .into_iter()
+            .map(|entity| SceneGraphNode {
+                entity,
+                label: self
+                    .query::<Label>()
+                    .and_then(|labels| labels.get(&entity).map(|label| label.0.clone())),
+                components: self
+                    .ecs_instance
+                    .component_names(entity)
+                    .into_iter()
+                    .map(scene_graph::short_type_name)
+                    .collect(),
+                renderer_handles: self.get_renderer_handles(entity),
+            })
+            .collect::<Vec<_>>();
+
+        let contents = match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("dot") | Some("gv") => scene_graph::to_dot(&nodes),
+            _ => scene_graph::to_json(&nodes),
+        };
+
+        fs::write(path, contents)
+    }
+
+    /// Saves the entities of the active world to a scene file with their labels, models,
+    /// transforms, lights, and colliders as they are now, so edits made while the game
+    /// runs can be kept. Other components are not saved
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Filepath to write the scene to
+    pub fn save_scene<P>(&self, path: P) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let component = |entity: &Entity| {
+            let labels = self.query::<Label>();
+            let models = self.query::<Model3d>();
+            let transforms = self.query::<Transform3d>();
+            let lights = self.query::<Light>();
+            let rectangle_colliders = self.query::<RectangleCollider>();
+            let plane_colliders = self.query::<StationaryPlaneCollider>();
+
+            SceneEntity {
+                label: labels.and_then(|labels| labels.get(entity).cloned()),
+                model: models.and_then(|models| models.get(entity).cloned()),
+                transform: transforms.and_then(|transforms| transforms.get(entity).copied()),
+                light: lights.and_then(|lights| lights.get(entity).copied()),
+                rectangle_collider: rectangle_colliders
+                    .and_then(|colliders| colliders.get(entity).cloned()),
+                plane_collider: plane_colliders
+                    .and_then(|colliders| colliders.get(entity).cloned()),
+            }
+        };
+
+        let mut entities = self.ecs_instance.entities();
+        entities.sort_unstable();
+        let scene_entities = entities
+            .iter()
+            .map(component)
+            .filter(|scene_entity| !scene_entity.is_empty())
+            .collect::<Vec<_>>();
+
+        fs::write(path, scene_file::write_scene(&scene_entities))
+    }
+
+    /// Loads the entities of a scene file saved with `save_scene` into the active world,
+    /// the entities that are already in the world are kept
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Filepath of the scene file
+    ///
+    /// # Returns
+    ///
+    /// The new entities in the order they are in the file or an error if the file could
+    /// not be read
+    pub fn load_scene<P>(&mut self, path: P) -> Result<Vec<Entity>, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let scene_entities = scene_file::read_scene(&fs::read_to_string(path)?)?;
+
+        let entities = scene_entities
+            .into_iter()
+            .map(|scene_entity| {
+                let entity = match scene_entity.model {
+                    Some(model) => {
+                        let transform = scene_entity.transform.unwrap_or_default();
+                        self.create_object(model, transform)
+                    }
+                    None => {
+                        let entity = self.create_entity();
+                        if let Some(transform) = scene_entity.transform {
+                            self.add_component(entity, transform);
+                        }
+                        entity
+                    }
+                };
+
+                if let Some(label) = scene_entity.label {
+                    self.add_component(entity, label);
+                }
+                if let Some(mut light) = scene_entity.light {
+                    self.with_renderer(|renderer| renderer.add_light(&mut light));
+                    self.add_component(entity, light);
+                }
+                if let Some(collider) = scene_entity.rectangle_collider {
+                    self.add_component(entity, collider);
+                }
+                if let Some(collider) = scene_entity.plane_collider {
+                    self.add_component(entity, collider);
+                }
+
+                entity
+            })
+            .collect();
+
+        Ok(entities)
+    }
+
+    // Finds every renderer resource that the components of an entity are linked to
+    fn get_renderer_handles(&self, entity: Entity) -> Vec<RendererHandle> {
+        let mut handles = Vec::new();
+
+        if let Some(index) = self.get_renderer_index(entity) {
+            handles.push(RendererHandle::Model(index));
+        }
+
+        if let Some(batches) = self.query::<StaticBatch>() {
+            if let Some(index) = batches.get(&entity).and_then(|b| b.get_renderer_index()) {
+                handles.push(RendererHandle::Model(*index));
+            }
+        }
+
+        if let Some(text_labels) = self.query::<TextLabel>() {
+            if let Some(index) = text_labels
+                .get(&entity)
+                .and_then(|t| t.get_renderer_index())
+            {
+                handles.push(RendererHandle::Text(*index));
+            }
+        }
+
+        if let Some(world_texts) = self.query::<WorldText>() {
+            if let Some(index) = world_texts
+                .get(&entity)
+                .and_then(|t| t.get_renderer_index())
+            {
+                handles.push(RendererHandle::Text(*index));
+            }
+        }
+
+        if let Some(world_labels) = self.query::<WorldLabel>() {
+            if let Some(index) = world_labels
+                .get(&entity)
+                .and_then(|l| l.get_renderer_index())
+            {
+                handles.push(RendererHandle::SdfText(*index));
+            }
+        }
+
+        if let Some(hud_images) = self.query::<HudImage>() {
+            if let Some(index) = hud_images.get(&entity).and_then(|i| i.get_renderer_index()) {
+                handles.push(RendererHandle::Sprite(*index));
+            }
+        }
+
+        if let Some(decals) = self.query::<Decal>() {
+            if let Some(index) = decals.get(&entity).and_then(|d| d.get_renderer_index()) {
+                handles.push(RendererHandle::Decal(*index));
+            }
+        }
+
+        if let Some(panels) = self.query::<Panel>() {
+            if let Some(index) = panels.get(&entity).and_then(|p| p.get_quad_index()) {
+                handles.push(RendererHandle::Quad(*index));
+            }
+        }
+
+        if let Some(buttons) = self.query::<Button>() {
+            if let Some(button) = buttons.get(&entity) {
+                handles.extend(button.get_quad_index().copied().map(RendererHandle::Quad));
+                handles.extend(button.get_text_index().copied().map(RendererHandle::Text));
+            }
+        }
+
+        if let Some(sliders) = self.query::<Slider>() {
+            if let Some(slider) = sliders.get(&entity) {
+                handles.extend(slider.get_track_index().copied().map(RendererHandle::Quad));
+                handles.extend(slider.get_handle_index().copied().map(RendererHandle::Quad));
+            }
+        }
+
+        if let Some(world_bars) = self.query::<WorldBar>() {
+            if let Some(world_bar) = world_bars.get(&entity) {
+                handles.extend(
+                    world_bar
+                        .get_background_index()
+                        .copied()
+                        .map(RendererHandle::Quad),
+                );
+                handles.extend(
+                    world_bar
+                        .get_fill_index()
+                        .copied()
+                        .map(RendererHandle::Quad),
+                );
+            }
+        }
+
+        handles
+    }
+
+    /// The closest entity to a position
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position in world space to search around
+    /// * `filter` - Skips the entities it returns false for, like the entity searching
+    ///
+    /// # Returns
+    ///
+    /// The entity or `None` if no entity passed the filter
+    pub fn nearest_entity<F>(&self, position: Vector3<f32>, filter: F) -> Option<Entity>
+    where
+        F: FnMut(Entity) -> bool,
+    {
+        self.nearest_entities(position, 1, filter).first().copied()
+    }
+
+    /// The closest entities to a position, closest first
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position in world space to search around
+    /// * `count` - How many entities to find at most
+    /// * `filter` - Skips the entities it returns false for
+    pub fn nearest_entities<F>(
+        &self,
+        position: Vector3<f32>,
+        count: usize,
+        filter: F,
+    ) -> Vec<Entity>
+    where
+        F: FnMut(Entity) -> bool,
+    {
+        self.spatial_index
+            .nearest(position, count, filter)
+            .into_iter()
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+
+    /// The entities within a distance of a position, closest first
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position in world space to search around
+    /// * `radius` - The distance to search in world units
+    /// * `filter` - Skips the entities it returns false for
+    pub fn entities_within_radius<F>(
+        &self,
+        position: Vector3<f32>,
+        radius: f32,
+        filter: F,
+    ) -> Vec<Entity>
+    where
+        F: FnMut(Entity) -> bool,
+    {
+        self.spatial_index
+            .within_radius(position, radius, filter)
+            .into_iter()
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+
+    /// Sets the width of the cells the entities are sorted into for proximity queries,
+    /// about the radius most queries search is fastest
+    pub fn set_spatial_cell_size(&mut self, cell_size: f32) {
+        self.spatial_index.set_cell_size(cell_size);
+    }
+
+    /// Puts the entities with a transform back into the grid of proximity queries, the
+    /// queries see the positions of the last update
+    pub(crate) fn update_spatial_index(&mut self) {
+        self.spatial_index.clear();
+
+        let Some(transforms) = self.ecs_instance.query::<Transform3d>() else {
+            return;
+        };
+        for (entity, transform) in transforms.iter() {
+            self.spatial_index
+                .insert(*entity, *transform.get_position());
+        }
+    }
+
+    /// Adds a state machine to an entity and starts running the state machines with its
+    /// type of state
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - Entity to add the state machine to
+    /// * `state_machine` - The state machine, its initial state is entered on the next
+    ///   update
+    ///
+    /// # Returns
+    ///
+    /// The entity id
+    pub fn add_state_machine<T>(&mut self, entity: Entity, state_machine: StateMachine<T>) -> Entity
+    where
+        T: Copy + Eq + Hash + Debug + 'static,
+    {
+        let state_type = TypeId::of::<T>();
+        if !self
+            .state_machine_systems
+            .iter()
+            .any(|(system_type, _)| *system_type == state_type)
+        {
+            self.state_machine_systems
+                .push((state_type, Self::run_state_machines::<T>));
+        }
+
+        self.add_component(entity, state_machine)
+    }
+
+    /// The innermost state of the state machine of an entity
+    pub fn get_state<T>(&self, entity: Entity) -> Option<T>
+    where
+        T: Copy + Eq + Hash + Debug + 'static,
+    {
+        self.query::<StateMachine<T>>()
+            .and_then(|machines| machines.get(&entity).map(StateMachine::get_state))
+    }
+
+    /// Changes the state machine of an entity to a state on the next update, without
+    /// checking its transitions
+    ///
+    /// # Returns
+    ///
+    /// Whether the entity has a state machine with this type of state
+    pub fn request_state<T>(&mut self, entity: Entity, state: T) -> bool
+    where
+        T: Copy + Eq + Hash + Debug + 'static,
+    {
+        self.with_state_machine(entity, |machine| machine.request_state(state))
+            .is_some()
+    }
+
+    fn with_state_machine<T, R, F>(&self, entity: Entity, function: F) -> Option<R>
+    where
+        T: Copy + Eq + Hash + Debug + 'static,
+        F: FnOnce(&mut StateMachine<T>) -> R,
+    {
+        self.query_mut::<StateMachine<T>>()
+            .and_then(|mut machines| machines.get_mut(&entity).map(function))
+    }
+
+    /// Used internally to run the state machines of every type of state
+    pub(crate) fn update_state_machines(&mut self) {
+        // The callbacks can add state machines with new types of state
+        let mut index = 0;
+        while let Some((_, system)) = self.state_machine_systems.get(index).copied() {
+            system(self);
+            index += 1;
+        }
+    }
+
+    // Takes a transition of each state machine and calls its callbacks, the machines are
+    // not borrowed while a callback runs so the callbacks can use them
+    fn run_state_machines<T>(&mut self)
+    where
+        T: Copy + Eq + Hash + Debug + 'static,
+    {
+        let delta_time = self.get_delta_time();
+        let mut entities = match self.query::<StateMachine<T>>() {
+            Some(machines) => machines.keys().copied().collect::<Vec<_>>(),
+            None => return,
+        };
+        entities.sort_unstable();
+
+        for entity in entities {
+            let Some(start) = self.with_state_machine(entity, StateMachine::<T>::start) else {
+                continue;
+            };
+            if let Some(on_enters) = start {
+                for on_enter in on_enters {
+                    on_enter(self, entity);
+                }
+                continue;
+            }
+
+            let Some((ready, guarded)) = self
+                .with_state_machine(entity, |machine: &mut StateMachine<T>| {
+                    machine.advance(delta_time)
+                })
+            else {
+                continue;
+            };
+            let target = guarded
+                .into_iter()
+                .find(|(_, guard)| guard(self, entity))
+                .map(|(to, _)| to)
+                .or(ready);
+
+            if let Some(target) = target {
+                let Some((from, (to, on_exits, on_enters))) = self
+                    .with_state_machine(entity, |machine: &mut StateMachine<T>| {
+                        (machine.get_state(), machine.change(target))
+                    })
+                else {
+                    continue;
+                };
+
+                for on_exit in on_exits {
+                    on_exit(self, entity);
+                }
+                // An exit callback may have despawned the entity
+                if self
+                    .with_state_machine(entity, |machine: &mut StateMachine<T>| {
+                        machine.set_state(to)
+                    })
+                    .is_none()
+                {
+                    continue;
+                }
+                for on_enter in on_enters {
+                    on_enter(self, entity);
+                }
+
+                self.send_event(StateChanged { entity, from, to });
+            }
+
+            let on_updates = self
+                .with_state_machine(entity, |machine: &mut StateMachine<T>| {
+                    machine.update_callbacks()
+                })
+                .unwrap_or_default();
+            for on_update in on_updates {
+                on_update(self, entity);
+            }
+        }
+    }
+
+    /// Sets the use key and how the prompt of the focused interactable looks
+    pub fn set_interaction(&mut self, interaction: Interaction) {
+        self.interaction.set_settings(interaction);
+    }
+
+    pub fn get_interaction(&self) -> &Interaction {
+        self.interaction.get_settings()
+    }
+
+    /// The interactable the player would use by pressing the use key, it is found again
+    /// every update
+    pub fn get_focused_interactable(&self) -> Option<Entity> {
+        self.interaction.get_focused()
+    }
+
+    /// Used internally to keep the presses of the use key for the next update
+    pub(crate) fn process_interaction_input(&mut self, event: &DeviceEvent) {
+        self.interaction.process_event(event);
+    }
+
+    /// Used internally to focus the closest interactable in front of the camera, show its
+    /// prompt and send an `InteractionEvent` when the use key was pressed
+    pub(crate) fn update_interactions(&mut self) {
+        let used = self.interaction.take_used();
+
+        // Flying the editor camera should not open doors around the player
+        let camera = self
+            .camera_id
+            .filter(|_| !self.is_editor_camera_active())
+            .and_then(|camera_id| {
+                self.query::<Camera3d>()
+                    .and_then(|cameras| cameras.get(&camera_id).copied())
+            });
+
+        let focused = camera.and_then(|camera| {
+            let eye = camera.eye.to_vec();
+            let forward = camera.target.normalize();
+
+            let interactables = self.query::<Interactable>()?;
+            let transforms = self.query::<Transform3d>()?;
+            let colliders = self.query::<RectangleCollider>();
+
+            interactables
+                .iter()
+                .filter_map(|(entity, interactable)| {
+                    let position = *transforms.get(entity)?.get_position();
+                    let looked_at = colliders
+                        .as_ref()
+                        .and_then(|colliders| colliders.get(entity))
+                        .map(|collider| {
+                            collider
+                                .raycast(eye, forward, interactable.get_radius())
+                                .is_some()
+                        });
+
+                    interactable
+                        .focus_distance(eye, forward, position, looked_at)
+                        .map(|distance| (*entity, interactable.get_prompt().to_string(), distance))
+                })
+                .min_by(|a, b| a.2.total_cmp(&b.2))
+        });
+
+        self.interaction
+            .set_focused(focused.as_ref().map(|(entity, ..)| *entity));
+
+        let prompt = focused.as_ref().map(|(_, prompt, _)| self.tr(prompt));
+        if let Some(renderer) = &self.renderer_instance {
+            self.interaction
+                .show_prompt(prompt.as_deref(), &mut renderer.lock().unwrap());
+        }
+
+        if let (true, Some((entity, ..))) = (used, focused) {
+            self.send_event(InteractionEvent {
+                entity,
+                camera: self.camera_id,
+            });
+        }
+    }
+
+    /// Adds a component to the specified entity
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - Entity to add componenets to
+    /// * `component` - Component to add to the entity
+    ///
+    /// # Returns
+    ///
+    /// The entity id
+    pub fn add_component<ComponentType: 'static>(
+        &mut self,
+        entity: Entity,
+        component: ComponentType,
+    ) -> Entity {
+        self.ecs_instance.add_component(entity, component);
+        entity
+    }
+
+    /// Querys the ECS for the component type specified and gives the corresponding information
+    ///
+    /// # Arguments
+    ///
+    /// * `ComponentType` - The type for the ECS to query for
+    ///
+    /// # Returns
+    ///
+    /// A `Ref` to the `HashMap` of the specified `ComponentType`
+    pub fn query<ComponentType: 'static>(&self) -> Option<Ref<'_, ComponentMap<ComponentType>>> {
+        self.ecs_instance.query::<ComponentType>()
+    }
+
+    /// Querys the ECS for the component type specified and gives the corresponding information
+    ///
+    /// # Arguments
+    ///
+    /// * `ComponentType` - The type for the ECS to query for
+    ///
+    /// # Returns
+    ///
+    /// A `RefMut` to the `HashMap` of the specified `ComponentType`
+    pub fn query_mut<ComponentType: 'static>(
+        &self,
+    ) -> Option<RefMut<'_, ComponentMap<ComponentType>>> {
+        self.ecs_instance.query_mut::<ComponentType>()
+    }
+
+    /// Gives a list of entities that have a component with a specific comparator operator
+    ///
+    /// # Arguments
+    ///
+    /// * `ComponentType` - The type for the component map to seach
+    /// * `comparator` - A fucntion pointer to compare the component value given
     ///
     /// # Returns
     ///
@@ -300,4 +3534,223 @@ impl HeliumManager {
     ) -> Vec<Entity> {
         self.ecs_instance.entities_with::<ComponentType>(comparator)
     }
+
+    /// Tags an entity with a marker type, like `manager.tag::<Enemy>(entity)`, tags have
+    /// no value and are faster to look up than a `Label`
+    ///
+    /// # Arguments
+    ///
+    /// * `TagType` - The marker type to tag the entity with
+    /// * `entity` - Entity to tag
+    ///
+    /// # Returns
+    ///
+    /// The entity id
+    pub fn tag<TagType: 'static>(&mut self, entity: Entity) -> Entity {
+        self.ecs_instance.tag::<TagType>(entity);
+        entity
+    }
+
+    pub fn untag<TagType: 'static>(&mut self, entity: Entity) {
+        self.ecs_instance.untag::<TagType>(entity);
+    }
+
+    pub fn has_tag<TagType: 'static>(&self, entity: Entity) -> bool {
+        self.ecs_instance.has_tag::<TagType>(entity)
+    }
+
+    /// Gives the entities tagged with a marker type in ascending order
+    pub fn entities_tagged<TagType: 'static>(&self) -> Vec<Entity> {
+        self.ecs_instance.entities_tagged::<TagType>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Damage, Lifetime};
+
+    fn extend_lifetimes(manager: &mut HeliumManager) {
+        if let Some(mut lifetimes) = manager.query_mut::<Lifetime>() {
+            for (_, lifetime) in lifetimes.iter_mut() {
+                lifetime.extend(Duration::from_millis(100));
+            }
+        }
+    }
+
+    #[test]
+    fn test_step_delta_time() {
+        let mut manager = HeliumManager::headless(64, 64);
+        let entity = manager.create_entity();
+        manager.add_component(entity, Lifetime::new(Duration::from_millis(500)));
+
+        manager.step(Duration::from_millis(300), &[]);
+        assert_eq!(manager.get_delta_time(), 0.3);
+        assert!(manager.ecs_instance.contains_entity(entity));
+
+        manager.step(Duration::from_millis(300), &[]);
+        assert!(!manager.ecs_instance.contains_entity(entity));
+    }
+
+    #[test]
+    fn test_step_update_functions() {
+        let mut manager = HeliumManager::headless(64, 64);
+        let entity = manager.create_entity();
+        manager.add_component(entity, Lifetime::new(Duration::from_millis(250)));
+
+        // Each step extends the lifetime by more than it advances
+        for _ in 0..5 {
+            manager.step(Duration::from_millis(50), &[extend_lifetimes]);
+        }
+
+        let lifetimes = manager.query::<Lifetime>().unwrap();
+        assert_eq!(
+            lifetimes.get(&entity).map(Lifetime::get_remaining),
+            Some(Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn test_step_world_systems() {
+        let mut manager = HeliumManager::headless(64, 64);
+        let main_world = manager.get_active_world();
+        let world = manager.create_world();
+
+        manager.set_active_world(world);
+        let entity = manager.create_entity();
+        manager.add_component(entity, Lifetime::new(Duration::from_millis(250)));
+        manager.set_active_world(main_world);
+
+        manager.add_world_update(world, extend_lifetimes);
+        manager.step(Duration::from_millis(50), &[]);
+        assert_eq!(manager.get_active_world(), main_world);
+
+        // The engine systems only advance the lifetimes of the active world
+        manager.set_active_world(world);
+        let lifetimes = manager.query::<Lifetime>().unwrap();
+        assert_eq!(
+            lifetimes.get(&entity).map(Lifetime::get_remaining),
+            Some(Duration::from_millis(350))
+        );
+    }
+
+    #[test]
+    fn test_despawn_twice() {
+        let mut manager = HeliumManager::headless(64, 64);
+        let entity = manager.create_entity();
+        manager.add_component(entity, Lifetime::new(Duration::from_millis(250)));
+        let other = manager.create_entity();
+        manager.add_component(other, Lifetime::new(Duration::from_millis(250)));
+
+        manager.despawn(entity);
+        manager.despawn(entity);
+        assert!(!manager.ecs_instance.contains_entity(entity));
+        assert!(manager.ecs_instance.contains_entity(other));
+        assert_eq!(manager.ecs_instance.entities(), vec![other]);
+    }
+
+    fn fighter(manager: &mut HeliumManager, health: Health, damage: Option<Damage>) -> Entity {
+        let entity = manager.create_entity();
+        manager.add_component(entity, health);
+        manager.add_component(
+            entity,
+            RectangleCollider::new(1.0, 1.0, 1.0, Vector3::zero()),
+        );
+        if let Some(damage) = damage {
+            manager.add_component(entity, damage);
+        }
+        entity
+    }
+
+    #[test]
+    fn test_dead_sources_deal_no_damage() {
+        let mut manager = HeliumManager::headless(64, 64);
+        let dying = Health::new(10.0).with_despawn_on_death(true);
+        let first = fighter(&mut manager, dying, Some(Damage::new(10.0)));
+        let second = fighter(&mut manager, dying, Some(Damage::new(10.0)));
+        let target = fighter(
+            &mut manager,
+            Health::new(100.0).with_invulnerability(1.0),
+            None,
+        );
+
+        manager.step(Duration::from_millis(10), &[]);
+
+        // Whichever source hits first kills the other before it can hit back
+        let alive = [first, second]
+            .into_iter()
+            .filter(|entity| manager.ecs_instance.contains_entity(*entity))
+            .collect::<Vec<_>>();
+        assert_eq!(alive.len(), 1);
+        assert_eq!(
+            manager
+                .query::<Health>()
+                .unwrap()
+                .get(&target)
+                .map(Health::get_current),
+            Some(90.0)
+        );
+
+        // The events of the first step are read during the next one
+        manager.step(Duration::from_millis(10), &[]);
+        let damage_sources = manager
+            .read_events::<DamageEvent>()
+            .iter()
+            .map(|event| event.source)
+            .collect::<Vec<_>>();
+        assert_eq!(damage_sources, vec![Some(alive[0]); 2]);
+        let deaths = manager.read_events::<DeathEvent>();
+        assert_eq!(deaths.len(), 1);
+        assert_eq!(deaths[0].source, Some(alive[0]));
+    }
+
+    fn arena(manager: &mut HeliumManager) {
+        let entity = manager.create_entity();
+        manager.add_component(entity, Lifetime::new(Duration::from_secs(1)));
+    }
+
+    fn pickup(manager: &mut HeliumManager, _position: Vector3<f32>) -> Entity {
+        let entity = manager.create_entity();
+        manager.add_component(entity, Lifetime::new(Duration::from_secs(5)));
+        entity
+    }
+
+    #[test]
+    fn test_restart_scene() {
+        let mut manager = HeliumManager::headless(64, 64);
+        manager.register_scene("arena", arena);
+        manager.register_prefab("pickup", pickup);
+
+        assert!(manager.load_scene_async("arena"));
+        manager.step(Duration::from_millis(500), &[]);
+        assert!(manager.spawn_prefab("pickup", Vector3::zero()).is_some());
+
+        manager.restart_world();
+        assert_eq!(manager.get_current_scene(), Some("arena"));
+
+        // Only the entity of the scene is created again, with its whole lifetime
+        let remaining = manager
+            .query::<Lifetime>()
+            .unwrap()
+            .values()
+            .map(Lifetime::get_remaining)
+            .collect::<Vec<_>>();
+        assert_eq!(remaining, vec![Duration::from_secs(1)]);
+
+        assert!(manager.spawn_prefab("pickup", Vector3::zero()).is_some());
+        assert!(manager.load_scene_async("arena"));
+    }
+
+    #[test]
+    fn test_fixed_ticks() {
+        let mut manager = HeliumManager::headless(64, 64);
+        manager.set_update_pacing(UpdatePacing::default().with_fixed_tick_rate(Some(4.0)));
+
+        // However long the update took to get here, it covers one second
+        manager.frame_time = Duration::from_secs(1);
+        assert_eq!(
+            manager.take_fixed_ticks(),
+            Some((Duration::from_millis(250), 4))
+        );
+    }
 }
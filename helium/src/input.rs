@@ -0,0 +1,38 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Packs the x and y motion into the bits of a single number so both change together
+fn pack(delta: (f32, f32)) -> u64 {
+    ((delta.0.to_bits() as u64) << 32) | delta.1.to_bits() as u64
+}
+
+fn unpack(bits: u64) -> (f32, f32) {
+    (
+        f32::from_bits((bits >> 32) as u32),
+        f32::from_bits(bits as u32),
+    )
+}
+
+/// Mouse motion for looking around, the window thread adds the motion as it arrives and
+/// the update thread takes everything that was added once every update. Nothing is
+/// locked so the window thread never waits on the update thread and no motion is lost
+#[derive(Debug, Default)]
+pub(crate) struct LookInput {
+    delta: AtomicU64,
+}
+
+impl LookInput {
+    /// Adds mouse motion to the motion since the last update
+    pub fn add(&self, delta: (f32, f32)) {
+        _ = self
+            .delta
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |bits| {
+                let (x, y) = unpack(bits);
+                Some(pack((x + delta.0, y + delta.1)))
+            });
+    }
+
+    /// Takes the mouse motion since the last update
+    pub fn take(&self) -> (f32, f32) {
+        unpack(self.delta.swap(pack((0.0, 0.0)), Ordering::AcqRel))
+    }
+}
@@ -0,0 +1,144 @@
+use helium_ecs::Entity;
+use helium_renderer::{Anchor, HeliumState, OverlayText, TextStyle, UiLayout};
+use winit::{
+    event::{DeviceEvent, ElementState, RawKeyEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+/// Settings of how the player uses `Interactable`s
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Interaction {
+    use_key: KeyCode,
+    prompt_style: TextStyle,
+    prompt_layout: UiLayout,
+}
+
+impl Default for Interaction {
+    /// Used with E, the prompt is outlined text above the bottom of the screen
+    fn default() -> Self {
+        Self {
+            use_key: KeyCode::KeyE,
+            prompt_style: TextStyle::default().with_outline([0.0, 0.0, 0.0, 1.0], 2.0),
+            prompt_layout: UiLayout::new(Anchor::BottomCenter).with_offset_percent((0.0, -0.2)),
+        }
+    }
+}
+
+impl Interaction {
+    /// Sets the key that uses the focused interactable
+    pub fn with_use_key(mut self, use_key: KeyCode) -> Self {
+        self.use_key = use_key;
+        self
+    }
+
+    /// Sets how the prompt text looks
+    pub fn with_prompt_style(mut self, prompt_style: TextStyle) -> Self {
+        self.prompt_style = prompt_style;
+        self
+    }
+
+    /// Sets where the prompt is placed on the screen
+    pub fn with_prompt_layout(mut self, prompt_layout: UiLayout) -> Self {
+        self.prompt_layout = prompt_layout;
+        self
+    }
+
+    pub fn get_use_key(&self) -> KeyCode {
+        self.use_key
+    }
+
+    pub fn get_prompt_style(&self) -> &TextStyle {
+        &self.prompt_style
+    }
+
+    pub fn get_prompt_layout(&self) -> &UiLayout {
+        &self.prompt_layout
+    }
+
+    /// The name of the use key shown in the prompt, like "E" or "Space"
+    pub fn use_key_name(&self) -> String {
+        let name = format!("{:?}", self.use_key);
+        match name
+            .strip_prefix("Key")
+            .or_else(|| name.strip_prefix("Digit"))
+        {
+            Some(short) => short.to_string(),
+            None => name,
+        }
+    }
+
+    /// The text shown while an interactable is focused
+    pub fn prompt_text(&self, prompt: &str) -> OverlayText {
+        OverlayText::new(
+            format!("[{}] {prompt}", self.use_key_name()),
+            (0.0, 0.0),
+            self.prompt_style,
+        )
+        .with_layout(Some(self.prompt_layout))
+    }
+}
+
+/// The interaction settings with the focused interactable and the input since the last
+/// update
+#[derive(Debug, Default)]
+pub(crate) struct InteractionState {
+    settings: Interaction,
+    focused: Option<Entity>,
+    used: bool,
+    // The text of the prompt in the renderer, it is kept while hidden so it can be shown again
+    prompt_index: Option<usize>,
+}
+
+impl InteractionState {
+    pub fn get_settings(&self) -> &Interaction {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: Interaction) {
+        self.settings = settings;
+    }
+
+    pub fn get_focused(&self) -> Option<Entity> {
+        self.focused
+    }
+
+    pub fn set_focused(&mut self, focused: Option<Entity>) {
+        self.focused = focused;
+    }
+
+    /// Keeps if the use key was pressed for the next update
+    pub fn process_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::Key(RawKeyEvent {
+            physical_key: PhysicalKey::Code(keycode),
+            state: ElementState::Pressed,
+        }) = event
+        {
+            self.used |= *keycode == self.settings.use_key;
+        }
+    }
+
+    /// Takes if the use key was pressed since the last update
+    pub fn take_used(&mut self) -> bool {
+        std::mem::take(&mut self.used)
+    }
+
+    /// Shows the prompt of the focused interactable or hides it when there is none
+    pub fn show_prompt(&mut self, prompt: Option<&str>, renderer: &mut HeliumState) {
+        match (prompt, self.prompt_index) {
+            (Some(prompt), Some(index)) => {
+                renderer.update_text(index, self.settings.prompt_text(prompt))
+            }
+            (Some(prompt), None) => {
+                self.prompt_index = Some(renderer.create_text(self.settings.prompt_text(prompt)))
+            }
+            (None, Some(index)) => renderer.remove_text(index),
+            (None, None) => {}
+        }
+    }
+
+    /// Forgets the prompt and the focused interactable after the scene was cleared
+    pub fn clear(&mut self) {
+        self.focused = None;
+        self.prompt_index = None;
+    }
+}
@@ -1,14 +1,16 @@
-use cgmath::InnerSpace;
 pub use cgmath::Point3;
-use helium_compatibility::CAMERA_SPEED;
+use cgmath::{EuclideanSpace, InnerSpace};
+use helium_compatibility::{CAMERA_SPEED, GROUND_MIN_NORMAL_Y};
 // logging
 use log::*;
 
 // std imports
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::thread;
+use std::time::Duration;
 
 // std imports to be broadcast
 pub use std::cell::{Ref, RefMut};
@@ -24,189 +26,1418 @@ pub use cgmath::{One, Quaternion, Vector3, Zero};
 // Winit imports
 use winit::{
     application::ApplicationHandler,
-    event::{DeviceEvent, DeviceId, WindowEvent},
+    event::{DeviceEvent, DeviceId, MouseButton, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     window::Window,
 };
 
 // Helium compatibility imports
-pub use helium_collisions::collider::{Collider, RectangleCollider, StationaryPlaneCollider};
-pub use helium_compatibility::{Camera3d, CameraController, Label, Model3d, Transform3d};
-pub use helium_ecs::{Entity, HeliumECS};
+pub use crash::UpdatePanicPolicy;
+pub use editor_camera::EditorCamera;
+pub use events::Events;
+pub use gizmo::{Gizmo, GizmoMode};
+pub use helium_collisions::collider::{
+    Collider, RayHit, RectangleCollider, StationaryPlaneCollider,
+};
+pub use helium_compatibility::{
+    AnimationClip, AnimationEvent, AnimationPlayer, AutoCollider, Button, ButtonColors,
+    ButtonState, Camera3d, CameraController, Cursor, Damage, DamageEvent, DeathEvent, Decal,
+    Emissive, EmissivePulse, GroundState, GuardFunction, Health, Highlighted, HudImage,
+    Interactable, InteractionEvent, InteractionTrigger, Label, Lifetime, Model3d, Panel,
+    Reflective, Slider, Spawner, SpringFollow, StateChanged, StateFunction, StateMachine,
+    StaticBatch, TextLabel, Trail, Transform3d, Visible, WorldBar, WorldLabel, WorldText,
+    WorldUiOptions,
+};
+pub use helium_ecs::{ComponentMap, Entity, HeliumECS, StorageOrder, WorldId, MAIN_WORLD};
 pub use helium_manager::HeliumManager;
-pub use helium_physics::gravity::Gravity;
-pub use helium_renderer::{instance::Instance, HeliumState, Light};
+pub use helium_nav::{
+    agent::NavAgent,
+    navmesh::NavMesh,
+    steering::{Flee, FollowPath, Seek, Separation, Steering, Wander},
+};
+pub use helium_physics::{
+    buoyancy::{Buoyancy, WaterVolume},
+    gravity::Gravity,
+    vehicle::{Vehicle, Wheel},
+};
+pub use helium_renderer::{
+    clear_profile, export_chrome_trace, exposure_from_ev100, get_profile_spans,
+    get_profile_threads, instance::Instance, is_profiling, profile_frame, profile_span,
+    set_profile_capacity, set_profiling, Aabb, AmbientOcclusionBake, Anchor, AntiAliasing, Bloom,
+    BoundingSphere, ColorMaterial, CustomRenderPass, DebugLine, DebugText, DecalTexture, DepthBias,
+    DepthOfField, DepthOfFieldFocus, DynamicMesh, DynamicResolution, DynamicVertex, Exposure,
+    FlareElement, FlareShape, Flipbook, FontHandle, HeliumState, LensEffects, LensFlare, Light,
+    LightKind, LightShadow, LightUnits, LinearRgba, MotionBlur, Outline, PassContext, PostSettings,
+    ProceduralSky, ProfileRecord, ProfileSpan, Reflection, RenderPassHandle, RenderResource,
+    RenderStage, RenderStats, RendererCapabilities, ScatterRegion, ScatterSettings, ScreenOverlay,
+    ScreenOverlayHandle, ScreenOverlayKind, SdfOutline, SdfPlacement, SdfShadow, SdfText,
+    ShadowSettings, SpriteHandle, Srgba, TextAlign, TextBounds, TextOutline, TextOverflow,
+    TextStyle, TextureAnimation, TextureAtlasBuilder, Tonemapper, Topology, UiLayout,
+    MAX_MORPH_TARGETS,
+};
+use input::LookInput;
+pub use interaction::Interaction;
+pub use localization::Localization;
+pub use logging::{LogConsole, LogSettings};
+pub use pacing::UpdatePacing;
+pub use profiling::SystemTimings;
+pub use rng::Rng;
+pub use scenes::{LoadingScreen, SceneLoadProgress, SceneLoaded};
+pub use streaming::{ChunkAction, ChunkObject, SceneChunk, WorldStreamer};
+pub use tasks::{TaskComplete, TaskHandle};
+pub use time_of_day::TimeOfDay;
+pub use undo::EditCommand;
+
+mod crash;
+mod editor_camera;
+mod events;
+mod gizmo;
+mod helium_compatibility;
+mod helium_manager;
+mod input;
+mod interaction;
+mod localization;
+mod logging;
+mod pacing;
+mod profiling;
+mod rng;
+mod scene_file;
+mod scene_graph;
+mod scenes;
+mod streaming;
+mod tasks;
+mod time_of_day;
+mod undo;
+// Number of times the update thread is restarted after panics before the error is shown
+const MAX_UPDATE_RESTARTS: u32 = 3;
+
+// Custom type aliases for simplicity
+pub type InputEvent = DeviceEvent;
+pub type StartupFunction = fn(&mut HeliumManager);
+pub type UpdateFunction = fn(&mut HeliumManager);
+pub type InputFunction = fn(&mut HeliumManager, &InputEvent);
+pub type PickFunction = fn(&mut HeliumManager, Option<Entity>);
+pub type PrefabFunction = fn(&mut HeliumManager, Vector3<f32>) -> Entity;
+pub type TaskFunction<T> = fn(&mut HeliumManager, T);
+pub type SceneFunction = fn(&mut HeliumManager);
+
+// Internal function for handling collisions if they are turned on
+fn handle_gravity_collisions(manager: &mut HeliumManager) {
+    let stationary_plane_colliders = match manager.query::<StationaryPlaneCollider>() {
+        Some(plane_colliders) => plane_colliders,
+        None => return,
+    };
+
+    let mut rectangle_colliders = match manager.query_mut::<RectangleCollider>() {
+        Some(rectangle_colliders) => rectangle_colliders,
+        None => return,
+    };
+
+    let mut transforms = match manager.query_mut::<Transform3d>() {
+        Some(transforms) => transforms,
+        None => return,
+    };
+
+    let mut gravities = match manager.query_mut::<Gravity>() {
+        Some(gravities) => gravities,
+        None => return,
+    };
+
+    // The ground each falling entity is standing on this frame
+    let mut grounds = Vec::new();
+
+    for (entity, rectangle_colider) in rectangle_colliders.iter_mut() {
+        if let Some(gravity) = gravities.get_mut(entity) {
+            gravity.accelerate(manager.get_delta_time());
+            let mut ground = None;
+
+            if let Some(transform) = transforms.get_mut(entity) {
+                for (plane_entity, plane_collider) in stationary_plane_colliders.iter() {
+                    // Colliders moving away from a one way plane pass through it
+                    if plane_collider.is_one_way()
+                        && gravity.velocity.dot(*plane_collider.normal()) > 0.0
+                    {
+                        continue;
+                    }
+
+                    if rectangle_colider.is_colliding(plane_collider) {
+                        // Moves the entity out of the plane with the collider so the snap
+                        // is not undone when the transform is synced
+                        let origin = *rectangle_colider.origin();
+                        let normal = rectangle_colider.snap_to_plane(plane_collider);
+                        transform.add_position(rectangle_colider.origin() - origin);
+
+                        // Only the motion into the plane is stopped so sliding is kept
+                        gravity.kill_velocity_along(normal);
+
+                        if normal.y >= GROUND_MIN_NORMAL_Y {
+                            ground = Some((normal, *plane_entity));
+                        }
+                    }
+                }
+
+                transform.add_position(gravity.velocity * manager.get_delta_time());
+            }
+
+            grounds.push((*entity, ground));
+        }
+    }
+
+    // The queries have to be released before ground states can be added
+    drop(stationary_plane_colliders);
+    drop(rectangle_colliders);
+    drop(transforms);
+    drop(gravities);
+    update_ground_states(manager, grounds);
+}
+
+// Pushes the entities with a buoyancy up by how deep they are in the water volumes
+fn update_buoyancy(manager: &mut HeliumManager) {
+    let waters = match manager.query::<WaterVolume>() {
+        Some(waters) => waters,
+        None => return,
+    };
+
+    let buoyancies = match manager.query::<Buoyancy>() {
+        Some(buoyancies) => buoyancies,
+        None => return,
+    };
+
+    let rectangle_colliders = match manager.query::<RectangleCollider>() {
+        Some(rectangle_colliders) => rectangle_colliders,
+        None => return,
+    };
+
+    let mut gravities = match manager.query_mut::<Gravity>() {
+        Some(gravities) => gravities,
+        None => return,
+    };
+
+    let delta_time = manager.get_delta_time();
+    for (entity, buoyancy) in buoyancies.iter() {
+        let (Some(gravity), Some(collider)) =
+            (gravities.get_mut(entity), rectangle_colliders.get(entity))
+        else {
+            continue;
+        };
+
+        // The collider is the shape of the entity that displaces the water
+        let origin = collider.origin();
+        let bottom = origin.y - collider.height() / 2.0;
+        let top = origin.y + collider.height() / 2.0;
+        let center = cgmath::Vector2::new(origin.x, origin.z);
+
+        // Overlapping volumes do not push more than being fully under the water
+        let submerged = waters
+            .values()
+            .map(|water| water.submerged_fraction(center, bottom, top))
+            .sum::<f32>()
+            .min(1.0);
+
+        buoyancy.apply(gravity, submerged, delta_time);
+    }
+}
+
+// Drives the vehicles on the colliders of the world
+fn update_vehicles(manager: &mut HeliumManager) {
+    let mut vehicles = match manager.query_mut::<Vehicle>() {
+        Some(vehicles) => vehicles,
+        None => return,
+    };
+
+    let mut transforms = match manager.query_mut::<Transform3d>() {
+        Some(transforms) => transforms,
+        None => return,
+    };
+
+    let planes = manager.query::<StationaryPlaneCollider>();
+    let mut rectangle_colliders = manager.query_mut::<RectangleCollider>();
+
+    let delta_time = manager.get_delta_time();
+    for (entity, vehicle) in vehicles.iter_mut() {
+        let Some(transform) = transforms.get_mut(entity) else {
+            continue;
+        };
+
+        let (mut position, mut rotation) = (*transform.get_position(), *transform.get_rotation());
+
+        // The wheels stand on the planes and on the boxes of everything but the vehicle
+        let raycast = |origin, direction, distance| {
+            let plane_hits = planes
+                .iter()
+                .flat_map(|planes| planes.values())
+                .filter_map(|plane| plane.raycast(origin, direction, distance));
+            let box_hits = rectangle_colliders
+                .iter()
+                .flat_map(|colliders| colliders.iter())
+                .filter(|(other, _)| *other != entity)
+                .filter_map(|(_, collider)| collider.raycast(origin, direction, distance));
+
+            plane_hits
+                .chain(box_hits)
+                .min_by(|a, b| a.distance.total_cmp(&b.distance))
+                .map(|hit| (hit.distance, hit.normal))
+        };
+
+        vehicle.step(&mut position, &mut rotation, delta_time, raycast);
+        transform.update_transform(position, rotation);
+
+        // The collider of the vehicle follows it so other bodies hit it where it is
+        if let Some(collider) = rectangle_colliders
+            .as_mut()
+            .and_then(|colliders| colliders.get_mut(entity))
+        {
+            collider.set_transform(&position, &rotation);
+        }
+    }
+}
+
+// Normal and entity of the surface an entity is standing on
+type Ground = Option<(Vector3<f32>, Entity)>;
+
+// Stores the ground each entity touched this frame in its ground state
+fn update_ground_states(manager: &mut HeliumManager, grounds: Vec<(Entity, Ground)>) {
+    let mut new_states = Vec::new();
+
+    if let Some(mut ground_states) = manager.query_mut::<GroundState>() {
+        for (entity, ground) in grounds {
+            let ground_state = match ground_states.get_mut(&entity) {
+                Some(ground_state) => ground_state,
+                None => {
+                    new_states.push((entity, ground));
+                    continue;
+                }
+            };
+
+            match ground {
+                Some((normal, ground_entity)) => ground_state.set_ground(normal, ground_entity),
+                None => ground_state.clear_ground(),
+            }
+        }
+    } else {
+        new_states = grounds;
+    }
+
+    for (entity, ground) in new_states {
+        let mut ground_state = GroundState::new();
+        if let Some((normal, ground_entity)) = ground {
+            ground_state.set_ground(normal, ground_entity);
+        }
+
+        manager.add_component(entity, ground_state);
+    }
+}
+
+fn update_camera_aspects(manager: &mut HeliumManager) {
+    let aspect = manager.get_aspect_ratio();
+
+    let mut cameras = match manager.query_mut::<Camera3d>() {
+        Some(cameras) => cameras,
+        None => return,
+    };
+
+    for (entity, camera) in cameras.iter_mut() {
+        if camera.aspect == aspect {
+            continue;
+        }
+
+        camera.set_aspect(aspect);
+        if manager.camera_id == Some(*entity) {
+            manager.move_camera_to_render(camera);
+        }
+    }
+}
+
+fn update_cameras(manager: &mut HeliumManager) {
+    // The controllers stay still while the editor camera flies
+    if manager.is_editor_camera_active() {
+        return;
+    }
+
+    let look_delta = std::mem::take(&mut manager.look_delta);
+
+    let mut transforms = match manager.query_mut::<Transform3d>() {
+        Some(transforms) => transforms,
+        None => return,
+    };
+
+    let mut cameras = match manager.query_mut::<Camera3d>() {
+        Some(cameras) => cameras,
+        None => return,
+    };
+
+    let mut camera_controllers = match manager.query_mut::<CameraController>() {
+        Some(controllers) => controllers,
+        None => return,
+    };
+
+    // If any of the above doesn't exist there is no point of continuing on
+
+    for (entity, controller) in camera_controllers.iter_mut() {
+        if let Some(camera) = cameras.get_mut(entity) {
+            camera.add_yaw(-(controller.delta.0 + look_delta.0));
+            camera.add_pitch(-(controller.delta.1 + look_delta.1));
+            controller.delta = (0.0, 0.0);
+
+            if let Some(transform) = transforms.get_mut(entity) {
+                let forward_norm = camera.target.normalize();
+
+                if controller.forward {
+                    transform.add_position(forward_norm * manager.get_delta_time() * CAMERA_SPEED);
+                }
+
+                if controller.backward {
+                    transform.add_position(-forward_norm * manager.get_delta_time() * CAMERA_SPEED);
+                }
+
+                let right = forward_norm.cross(camera.up);
+
+                if controller.left {
+                    transform.add_position(-right * manager.get_delta_time() * CAMERA_SPEED);
+                }
+
+                if controller.right {
+                    transform.add_position(right * manager.get_delta_time() * CAMERA_SPEED);
+                }
+            }
+
+            manager.move_camera_to_render(camera);
+        }
+    }
+}
+
+fn update_transforms_to_renderer(manager: &mut HeliumManager) {
+    // List of transforms to look through and update
+    let mut transforms = match manager.query_mut::<Transform3d>() {
+        Some(transforms) => transforms,
+        None => return,
+    };
+
+    // Models to update in the renderer
+    let models = manager.query::<Model3d>();
+
+    // Camera to update if it exists
+    let mut cameras = manager.query_mut::<Camera3d>();
+
+    // Rectangle Colliders to update if it exists
+    let mut colliders = manager.query_mut::<RectangleCollider>();
+
+    // Lights to update if exists
+    let mut lights = manager.query_mut::<Light>();
+
+    // Decals to move if they exist
+    let mut decals = manager.query_mut::<Decal>();
+
+    for (entity, transform) in transforms.iter_mut() {
+        if !transform.get_update_flag() {
+            continue;
+        }
+
+        // Update the model position
+        if let Some(models) = models.as_ref() {
+            if let Some(object_index) = models.get(entity) {
+                manager.with_renderer(|renderer| {
+                    renderer.update_instance_transforms(
+                        *object_index.get_renderer_index().unwrap(),
+                        vec![(*transform).into()],
+                    )
+                });
+            }
+        }
+
+        // Update the Camera position
+        if let Some(cameras) = cameras.as_mut() {
+            if let Some(camera) = cameras.get_mut(entity) {
+                let pos = transform.get_position();
+                camera.set_position(cgmath::point3::<f32>(pos.x, pos.y, pos.z));
+            }
+        }
+
+        // Update the colliders position
+        if let Some(colliders) = colliders.as_mut() {
+            if let Some(collider) = colliders.get_mut(entity) {
+                collider.set_transform(transform.get_position(), transform.get_rotation());
+            }
+        }
+
+        // Update the lights position
+        if let Some(lights) = lights.as_mut() {
+            if let Some(light) = lights.get_mut(entity) {
+                light.update_position(transform.get_position());
+                manager.with_renderer(|renderer| renderer.update_light(light));
+            }
+        }
+
+        // Move the decal projection box
+        if let Some(decals) = decals.as_mut() {
+            if let Some(decal) = decals.get_mut(entity) {
+                decal.set_transform(*transform.get_position(), *transform.get_rotation());
+            }
+        }
+
+        transform.update();
+    }
+}
+
+fn update_text_labels(manager: &mut HeliumManager) {
+    let mut text_labels = match manager.query_mut::<TextLabel>() {
+        Some(text_labels) => text_labels,
+        None => return,
+    };
+
+    for (_, text_label) in text_labels.iter_mut() {
+        if !text_label.get_update_flag() {
+            continue;
+        }
+
+        if let Some(text_index) = text_label.get_renderer_index() {
+            let mut overlay_text: helium_renderer::OverlayText = (&*text_label).into();
+            if let Some(key) = text_label.get_translation_key() {
+                overlay_text.text = manager.localization.tr(key);
+            }
+
+            manager.with_renderer(|renderer| renderer.update_text(*text_index, overlay_text));
+        }
+
+        text_label.update();
+    }
+}
+
+fn update_widgets(manager: &mut HeliumManager) {
+    let cursor = manager.cursor;
+    let screen_size = manager.get_render_config().map_or((1.0, 1.0), |config| {
+        (config.width as f32, config.height as f32)
+    });
+
+    if let Some(mut panels) = manager.query_mut::<Panel>() {
+        for (_, panel) in panels.iter_mut() {
+            if !panel.get_update_flag() {
+                continue;
+            }
+
+            if let Some(quad_index) = panel.get_quad_index() {
+                manager.with_renderer(|renderer| renderer.update_quad(*quad_index, panel.quad()));
+            }
+
+            panel.update();
+        }
+    }
+
+    if let Some(mut buttons) = manager.query_mut::<Button>() {
+        for (_, button) in buttons.iter_mut() {
+            button.handle_cursor(&cursor, screen_size);
+
+            if !button.get_update_flag() {
+                continue;
+            }
+
+            if let (Some(quad_index), Some(text_index), Some(renderer)) = (
+                button.get_quad_index(),
+                button.get_text_index(),
+                &manager.renderer_instance,
+            ) {
+                let mut renderer = renderer.lock().unwrap();
+                renderer.update_quad(*quad_index, button.quad());
+                renderer.update_text(*text_index, button.overlay_text());
+            }
+
+            button.update();
+        }
+    }
+
+    if let Some(mut sliders) = manager.query_mut::<Slider>() {
+        for (_, slider) in sliders.iter_mut() {
+            slider.handle_cursor(&cursor, screen_size);
+
+            if !slider.get_update_flag() {
+                continue;
+            }
+
+            if let (Some(track_index), Some(handle_index), Some(renderer)) = (
+                slider.get_track_index(),
+                slider.get_handle_index(),
+                &manager.renderer_instance,
+            ) {
+                let mut renderer = renderer.lock().unwrap();
+                renderer.update_quad(*track_index, slider.track_quad());
+                renderer.update_quad(*handle_index, slider.handle_quad());
+            }
+
+            slider.update();
+        }
+    }
+}
+
+fn update_hud_images(manager: &mut HeliumManager) {
+    let mut hud_images = match manager.query_mut::<HudImage>() {
+        Some(hud_images) => hud_images,
+        None => return,
+    };
+
+    for (_, hud_image) in hud_images.iter_mut() {
+        if !hud_image.get_update_flag() {
+            continue;
+        }
+
+        if let Some(sprite_index) = hud_image.get_renderer_index() {
+            manager.with_renderer(|renderer| {
+                renderer.update_sprite(*sprite_index, (&*hud_image).into())
+            });
+        }
+
+        hud_image.update();
+    }
+}
+
+fn update_decals(manager: &mut HeliumManager) {
+    let mut decals = match manager.query_mut::<Decal>() {
+        Some(decals) => decals,
+        None => return,
+    };
+
+    for (_, decal) in decals.iter_mut() {
+        if !decal.get_update_flag() {
+            continue;
+        }
+
+        if let Some(decal_index) = decal.get_renderer_index() {
+            manager.with_renderer(|renderer| renderer.update_decal(*decal_index, (&*decal).into()));
+        }
+
+        decal.update();
+    }
+}
+
+fn update_highlights(manager: &mut HeliumManager) {
+    let mut highlights = match manager.query_mut::<Highlighted>() {
+        Some(highlights) => highlights,
+        None => return,
+    };
+
+    let models = match manager.query::<Model3d>() {
+        Some(models) => models,
+        None => return,
+    };
+
+    for (entity, highlighted) in highlights.iter_mut() {
+        if !highlighted.get_update_flag() {
+            continue;
+        }
+
+        if let Some(object_index) = models.get(entity).and_then(|m| m.get_renderer_index()) {
+            manager.with_renderer(|renderer| {
+                renderer.set_object_outline(*object_index, Some((&*highlighted).into()))
+            });
+        }
+
+        highlighted.update();
+    }
+}
+
+fn update_auto_colliders(manager: &mut HeliumManager) {
+    let auto_colliders = match manager.query::<AutoCollider>() {
+        Some(auto_colliders) => auto_colliders
+            .iter()
+            .map(|(entity, auto_collider)| (*entity, *auto_collider))
+            .collect::<Vec<_>>(),
+        None => return,
+    };
+
+    for (entity, auto_collider) in auto_colliders {
+        // The model has not finished loading yet
+        let Some(aabb) = manager.get_local_aabb(entity) else {
+            continue;
+        };
+
+        // The box is in model space so it is offset from the entity and turns with it
+        let padding = auto_collider.get_padding() * 2.0;
+        let size = aabb.size();
+        let mut collider = RectangleCollider::new(
+            size.x + padding,
+            size.y + padding,
+            size.z + padding,
+            Vector3::zero(),
+        )
+        .with_offset(aabb.center());
+        if let Some((position, rotation, _)) = manager.get_transform_parts(entity) {
+            collider.set_transform(&position, &rotation);
+        }
+
+        manager.add_component(entity, collider);
+        manager
+            .ecs_instance
+            .remove_component::<AutoCollider>(entity);
+    }
+}
+
+fn update_nav_agents(manager: &mut HeliumManager) {
+    let delta_time = manager.get_delta_time();
+
+    let mut agents = match manager.query_mut::<NavAgent>() {
+        Some(agents) => agents,
+        None => return,
+    };
+
+    let mut transforms = match manager.query_mut::<Transform3d>() {
+        Some(transforms) => transforms,
+        None => return,
+    };
+
+    // Where every agent was at the start of the frame so they can avoid each other
+    let positions = agents
+        .iter()
+        .filter_map(|(entity, agent)| {
+            let transform = transforms.get(entity)?;
+            Some((*entity, *transform.get_position(), agent.get_radius()))
+        })
+        .collect::<Vec<_>>();
+
+    for (entity, agent) in agents.iter_mut() {
+        let Some(transform) = transforms.get_mut(entity) else {
+            continue;
+        };
+        let position = *transform.get_position();
+
+        if agent.needs_path() {
+            let destination = *agent.get_destination().unwrap();
+            agent.set_path(manager.find_path(position, destination));
+        }
+
+        let neighbors = positions
+            .iter()
+            .filter(|(other, ..)| other != entity)
+            .map(|(_, position, radius)| (*position, *radius))
+            .collect::<Vec<_>>();
+
+        let new_position = agent.steer(position, &neighbors, delta_time);
+        if new_position != position {
+            transform.update_position(new_position);
+        }
+    }
+}
+
+fn update_steering(manager: &mut HeliumManager) {
+    let delta_time = manager.get_delta_time();
+
+    let mut steerings = match manager.query_mut::<Steering>() {
+        Some(steerings) => steerings,
+        None => return,
+    };
+
+    let mut transforms = match manager.query_mut::<Transform3d>() {
+        Some(transforms) => transforms,
+        None => return,
+    };
+
+    let seeks = manager.query::<Seek>();
+    let flees = manager.query::<Flee>();
+    let mut wanders = manager.query_mut::<Wander>();
+    let mut follow_paths = manager.query_mut::<FollowPath>();
+    let separations = manager.query::<Separation>();
+
+    // Where every steering entity was at the start of the frame for separation
+    let positions = steerings
+        .keys()
+        .filter_map(|entity| Some((*entity, *transforms.get(entity)?.get_position())))
+        .collect::<Vec<_>>();
+
+    for (entity, steering) in steerings.iter_mut() {
+        let Some(transform) = transforms.get_mut(entity) else {
+            continue;
+        };
+        let position = *transform.get_position();
+
+        // The forces of all the behaviors on the entity are added together
+        let mut force = Vector3::zero();
+
+        if let Some(seek) = seeks.as_ref().and_then(|seeks| seeks.get(entity)) {
+            force += seek.force(position, steering);
+        }
+
+        if let Some(flee) = flees.as_ref().and_then(|flees| flees.get(entity)) {
+            force += flee.force(position, steering);
+        }
+
+        if let Some(wander) = wanders.as_mut().and_then(|wanders| wanders.get_mut(entity)) {
+            force += wander.force(steering, delta_time);
+        }
+
+        if let Some(follow_path) = follow_paths
+            .as_mut()
+            .and_then(|follow_paths| follow_paths.get_mut(entity))
+        {
+            force += follow_path.force(position, steering);
+        }
+
+        if let Some(separation) = separations
+            .as_ref()
+            .and_then(|separations| separations.get(entity))
+        {
+            let neighbors = positions
+                .iter()
+                .filter(|(other, _)| other != entity)
+                .map(|(_, position)| *position)
+                .collect::<Vec<_>>();
+            force += separation.force(position, &neighbors, steering);
+        }
+
+        let step = steering.apply(force, delta_time);
+        if step != Vector3::zero() {
+            transform.add_position(step);
+        }
+    }
+}
+
+// How often an engine system runs
+#[derive(Clone, Copy, PartialEq)]
+enum SystemRate {
+    // Once every update with the time since the last update
+    Variable,
+    // At the fixed tick rate of the pacing when it has one
+    Fixed,
+}
+
+// The engine systems of a frame in the order they run after the update and input functions
+const ENGINE_SYSTEMS: &[(&str, SystemRate, UpdateFunction)] = &[
+    // Hand the outputs of the finished background tasks to the game
+    (
+        "update_tasks",
+        SystemRate::Variable,
+        HeliumManager::update_tasks,
+    ),
+    // Show the progress of the loading scene and remove the loading screen once it loaded
+    (
+        "update_scene_load",
+        SystemRate::Variable,
+        HeliumManager::update_scene_load,
+    ),
+    // Despawn the entities whose lifetime ran out
+    ("update_lifetimes", SystemRate::Variable, update_lifetimes),
+    // Spawn the prefabs of the spawners that are ready
+    ("update_spawners", SystemRate::Variable, update_spawners),
+    // Apply the damage of colliders touching entities with health
+    ("update_health", SystemRate::Variable, update_health),
+    // Give the models that finished loading their colliders
+    (
+        "update_auto_colliders",
+        SystemRate::Variable,
+        update_auto_colliders,
+    ),
+    // Float the entities in water before they are moved
+    ("update_buoyancy", SystemRate::Fixed, update_buoyancy),
+    // Drive the vehicles on their wheels
+    ("update_vehicles", SystemRate::Fixed, update_vehicles),
+    // Handle collisions
+    (
+        "handle_gravity_collisions",
+        SystemRate::Fixed,
+        handle_gravity_collisions,
+    ),
+    // Move the nav agents along their paths
+    ("update_nav_agents", SystemRate::Fixed, update_nav_agents),
+    // Move the entities with steering behaviors
+    ("update_steering", SystemRate::Fixed, update_steering),
+    // Play the animation clips on the transforms and send their events
+    ("update_animations", SystemRate::Variable, update_animations),
+    // Pull the spring followers after their targets moved
+    (
+        "update_spring_follows",
+        SystemRate::Variable,
+        update_spring_follows,
+    ),
+    // Sort the moved entities into the grid of proximity queries
+    (
+        "update_spatial_index",
+        SystemRate::Variable,
+        HeliumManager::update_spatial_index,
+    ),
+    // Change the states of the state machines and call their callbacks
+    (
+        "update_state_machines",
+        SystemRate::Variable,
+        HeliumManager::update_state_machines,
+    ),
+    // Focus the interactable in front of the camera and use it
+    (
+        "update_interactions",
+        SystemRate::Variable,
+        HeliumManager::update_interactions,
+    ),
+    // Drag the gizmo handles of the selected entity
+    (
+        "update_gizmo",
+        SystemRate::Variable,
+        HeliumManager::update_gizmo,
+    ),
+    // Update all the changed transforms
+    (
+        "update_transforms_to_renderer",
+        SystemRate::Variable,
+        update_transforms_to_renderer,
+    ),
+    // Show and hide the models of the entities with a visibility
+    ("update_visibility", SystemRate::Variable, update_visibility),
+    // Keep the cameras at the aspect ratio of the window
+    (
+        "update_camera_aspects",
+        SystemRate::Variable,
+        update_camera_aspects,
+    ),
+    // Handle cameras
+    ("update_cameras", SystemRate::Variable, update_cameras),
+    // Fly the editor camera when it is active
+    (
+        "update_editor_camera",
+        SystemRate::Variable,
+        HeliumManager::update_editor_camera,
+    ),
+    // Update all the changed text
+    (
+        "update_text_labels",
+        SystemRate::Variable,
+        update_text_labels,
+    ),
+    // Update the ui widgets with the cursor
+    ("update_widgets", SystemRate::Variable, update_widgets),
+    // Resolve the picks that have been read back from the gpu
+    (
+        "update_picks",
+        SystemRate::Variable,
+        HeliumManager::update_picks,
+    ),
+    // Stream the chunks of the world around the camera
+    (
+        "update_streaming",
+        SystemRate::Variable,
+        HeliumManager::update_streaming,
+    ),
+    // Update the changed hud images
+    ("update_hud_images", SystemRate::Variable, update_hud_images),
+    // Update the moved and changed decals
+    ("update_decals", SystemRate::Variable, update_decals),
+    // Update the outlines of highlighted models
+    ("update_highlights", SystemRate::Variable, update_highlights),
+    // Animate the glow of emissive models
+    ("update_emission", SystemRate::Variable, update_emission),
+    // Move the sun across the sky with the time of day
+    (
+        "update_time_of_day",
+        SystemRate::Variable,
+        HeliumManager::update_time_of_day,
+    ),
+    // Move the reflective surfaces with their transforms
+    (
+        "update_reflections",
+        SystemRate::Variable,
+        update_reflections,
+    ),
+    // Project the world ui onto the screen
+    ("update_world_ui", SystemRate::Variable, update_world_ui),
+    // Leave the trails behind the moved entities
+    ("update_trails", SystemRate::Variable, update_trails),
+    // Draw the debug lines of this update until the next one
+    ("flush_debug_draw", SystemRate::Variable, |manager| {
+        manager.with_renderer(|renderer| renderer.flush_debug_draw());
+    }),
+    // Draw the dynamic meshes of this update until the next one
+    ("flush_dynamic_meshes", SystemRate::Variable, |manager| {
+        manager.with_renderer(|renderer| renderer.flush_dynamic_meshes());
+    }),
+];
+
+// Runs the engine systems of a frame and records how long each of them took
+pub(crate) fn run_engine_systems(manager: &mut HeliumManager) {
+    let fixed_ticks = manager.take_fixed_ticks();
+    let mut fixed_systems_ran = false;
+
+    for (name, rate, system) in ENGINE_SYSTEMS {
+        match (rate, fixed_ticks) {
+            // Every fixed rate system already ran in the ticks with the first of them
+            (SystemRate::Fixed, Some(_)) if fixed_systems_ran => {}
+            (SystemRate::Fixed, Some((interval, ticks))) => {
+                run_fixed_systems(manager, interval, ticks);
+                fixed_systems_ran = true;
+            }
+            _ => {
+                crash::set_system(Some(name));
+                let _span = profile_span(name);
+                let start = Instant::now();
+                system(manager);
+                manager.system_timings.record(name, start.elapsed());
+            }
+        }
+    }
+
+    crash::set_system(None);
+    manager.update_stats_overlay();
+    manager.update_profiler_overlay();
+}
+
+// Runs a tick of every fixed rate system in table order before the next tick starts, so
+// the physics systems of a tick see each other's results of the same tick
+fn run_fixed_systems(manager: &mut HeliumManager, interval: Duration, ticks: u32) {
+    let fixed_systems = ENGINE_SYSTEMS
+        .iter()
+        .filter(|(_, rate, _)| *rate == SystemRate::Fixed)
+        .collect::<Vec<_>>();
+    let mut timings = vec![Duration::ZERO; fixed_systems.len()];
+
+    let frame_time = manager.frame_time;
+    // The systems read the time of one tick as their delta time
+    manager.frame_time = interval;
+    for _ in 0..ticks {
+        for ((name, _, system), timing) in fixed_systems.iter().zip(&mut timings) {
+            crash::set_system(Some(name));
+            let _span = profile_span(name);
+            let start = Instant::now();
+            system(manager);
+            *timing += start.elapsed();
+        }
+    }
+    manager.frame_time = frame_time;
+
+    for ((name, _, _), timing) in fixed_systems.iter().zip(timings) {
+        manager.system_timings.record(name, timing);
+    }
+}
+
+fn update_animations(manager: &mut HeliumManager) {
+    let delta_time = manager.get_delta_time();
+
+    let mut players = match manager.query_mut::<AnimationPlayer>() {
+        Some(players) => players,
+        None => return,
+    };
+
+    let mut transforms = manager.query_mut::<Transform3d>();
+
+    let mut events = Vec::new();
+    for (entity, player) in players.iter_mut() {
+        if !player.is_playing() || player.is_finished() {
+            continue;
+        }
+
+        for (time, name) in player.advance(delta_time) {
+            events.push(AnimationEvent {
+                entity: *entity,
+                name,
+                time,
+            });
+        }
+
+        let Some(transform) = transforms
+            .as_mut()
+            .and_then(|transforms| transforms.get_mut(entity))
+        else {
+            continue;
+        };
+
+        // Only the parts of the transform the clip has keys for are animated
+        let (clip, time) = (player.get_clip(), player.get_time());
+        if let Some(position) = clip.sample_position(time) {
+            transform.update_position(position);
+        }
+        if let Some(rotation) = clip.sample_rotation(time) {
+            transform.update_rotation(rotation);
+        }
+        if let Some(scale) = clip.sample_scale(time) {
+            transform.set_scale(scale);
+        }
+    }
+
+    drop(players);
+    drop(transforms);
+    for event in events {
+        manager.send_event(event);
+    }
+}
+
+fn update_spring_follows(manager: &mut HeliumManager) {
+    let mut follows = match manager.query_mut::<SpringFollow>() {
+        Some(follows) => follows,
+        None => return,
+    };
+
+    let mut transforms = match manager.query_mut::<Transform3d>() {
+        Some(transforms) => transforms,
+        None => return,
+    };
+
+    let delta_time = manager.get_delta_time();
+    for (entity, follow) in follows.iter_mut() {
+        // Followers of despawned targets stay where they are
+        let Some(target) = transforms.get(&follow.get_target()) else {
+            continue;
+        };
+        let (target_position, target_rotation) = (*target.get_position(), *target.get_rotation());
+
+        let Some(transform) = transforms.get_mut(entity) else {
+            continue;
+        };
+
+        let rest = follow.rest_position(target_position, target_rotation);
+        let position = follow.step(*transform.get_position(), rest, delta_time);
+        if follow.is_following_rotation() {
+            let rotation =
+                follow.step_rotation(*transform.get_rotation(), target_rotation, delta_time);
+            transform.update_transform(position, rotation);
+        } else {
+            transform.update_position(position);
+        }
+    }
+}
+
+fn update_trails(manager: &mut HeliumManager) {
+    let mut trails = match manager.query_mut::<Trail>() {
+        Some(trails) => trails,
+        None => return,
+    };
+
+    let transforms = match manager.query::<Transform3d>() {
+        Some(transforms) => transforms,
+        None => return,
+    };
+
+    // The ribbons are turned towards the active camera
+    let eye = manager.camera_id.and_then(|camera_id| {
+        manager
+            .query::<Camera3d>()
+            .and_then(|cameras| cameras.get(&camera_id).map(|camera| camera.eye))
+    });
+
+    let delta_time = manager.get_delta_time();
+    // The trails keep moving without a renderer, there is just nothing to draw them with
+    let mut renderer = manager
+        .renderer_instance
+        .as_ref()
+        .map(|renderer| renderer.lock().unwrap());
+    for (entity, trail) in trails.iter_mut() {
+        let Some(transform) = transforms.get(entity) else {
+            continue;
+        };
+        trail.update(
+            *transform.get_position(),
+            *transform.get_rotation(),
+            delta_time,
+        );
+
+        if let (Some(eye), Some(renderer)) = (eye, renderer.as_mut()) {
+            let mesh = trail.build_mesh(eye.to_vec());
+            if !mesh.is_empty() {
+                renderer.add_dynamic_mesh(&mesh);
+            }
+        }
+    }
+}
+
+fn update_lifetimes(manager: &mut HeliumManager) {
+    let delta_time = manager.frame_time;
+
+    let expired = match manager.query_mut::<Lifetime>() {
+        Some(mut lifetimes) => lifetimes
+            .iter_mut()
+            .filter_map(|(entity, lifetime)| {
+                lifetime.advance(delta_time);
+                lifetime.is_expired().then_some(*entity)
+            })
+            .collect::<Vec<_>>(),
+        None => return,
+    };
+
+    for entity in expired {
+        manager.despawn(entity);
+    }
+}
+
+fn update_spawners(manager: &mut HeliumManager) {
+    let delta_time = manager.get_delta_time();
+
+    // The children that were despawned are found before the spawners are borrowed
+    // mutably because checking an entity borrows every component map
+    let despawned = match manager.query::<Spawner>() {
+        Some(spawners) => spawners
+            .values()
+            .flat_map(|spawner| spawner.get_children().iter().copied())
+            .collect::<Vec<_>>(),
+        None => return,
+    }
+    .into_iter()
+    .filter(|child| !manager.ecs_instance.contains_entity(*child))
+    .collect::<HashSet<_>>();
+
+    let spawns = match manager.query_mut::<Spawner>() {
+        Some(mut spawners) => {
+            let transforms = manager.query::<Transform3d>();
+
+            spawners
+                .iter_mut()
+                .filter_map(|(entity, spawner)| {
+                    spawner.retain_children(|child| !despawned.contains(child));
+
+                    if !spawner.advance(delta_time) {
+                        return None;
+                    }
+
+                    let origin = transforms
+                        .as_ref()
+                        .and_then(|transforms| transforms.get(entity))
+                        .map(|transform| *transform.get_position())
+                        .unwrap_or_else(Vector3::zero);
+
+                    Some((*entity, spawner.clone(), origin))
+                })
+                .collect::<Vec<_>>()
+        }
+        None => return,
+    };
+
+    for (spawner_entity, spawner, origin) in spawns {
+        let position = spawner.next_position(origin, manager.rng());
+        let prefab = spawner.get_prefab();
+        let Some(child) = manager.spawn_prefab(prefab, position) else {
+            continue;
+        };
+
+        if let Some(spawner) = manager
+            .query_mut::<Spawner>()
+            .as_mut()
+            .and_then(|spawners| spawners.get_mut(&spawner_entity))
+        {
+            spawner.add_child(child);
+        }
+    }
+}
+
+fn update_health(manager: &mut HeliumManager) {
+    let delta_time = manager.get_delta_time();
+
+    let Some(mut healths) = manager.query_mut::<Health>() else {
+        return;
+    };
+
+    for health in healths.values_mut() {
+        health.advance(delta_time);
+    }
+
+    // Every damage collider that touches a collider with health
+    let hits = match (
+        manager.query::<Damage>(),
+        manager.query::<RectangleCollider>(),
+    ) {
+        (Some(damages), Some(colliders)) => damages
+            .iter()
+            .filter_map(|(source, damage)| Some((source, damage, colliders.get(source)?)))
+            .flat_map(|(source, damage, source_collider)| {
+                healths
+                    .keys()
+                    .filter(|target| *target != source)
+                    .filter(|target| {
+                        colliders
+                            .get(target)
+                            .is_some_and(|collider| collider.overlaps(source_collider))
+                    })
+                    .map(|target| (*source, *target, *damage))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>(),
+        _ => return,
+    };
+    drop(healths);
+
+    let mut spent = HashSet::new();
+    for (source, target, damage) in hits {
+        // A source that died from an earlier hit deals no more damage
+        if spent.contains(&source) || !manager.ecs_instance.contains_entity(source) {
+            continue;
+        }
+
+        if manager.apply_damage(target, damage.get_amount(), Some(source))
+            && damage.get_despawn_on_hit()
+        {
+            spent.insert(source);
+        }
+    }
+
+    // A source that was hit by another source can have died and been despawned already
+    for source in spent {
+        if manager.ecs_instance.contains_entity(source) {
+            manager.despawn(source);
+        }
+    }
+}
 
-mod helium_compatibility;
-mod helium_manager;
-// Custom type aliases for simplicity
-pub type InputEvent = DeviceEvent;
-pub type StartupFunction = fn(&mut HeliumManager);
-pub type UpdateFunction = fn(&mut HeliumManager);
-pub type InputFunction = fn(&mut HeliumManager, &InputEvent);
+fn update_emission(manager: &mut HeliumManager) {
+    let delta_time = manager.get_delta_time();
 
-// Internal function for handling collisions if they are turned on
-fn handle_gravity_collisions(manager: &mut HeliumManager) {
-    let stationary_plane_colliders = match manager.query::<StationaryPlaneCollider>() {
-        Some(plane_colliders) => plane_colliders,
+    let mut emissives = match manager.query_mut::<Emissive>() {
+        Some(emissives) => emissives,
         None => return,
     };
 
-    let mut rectangle_colliders = match manager.query_mut::<RectangleCollider>() {
-        Some(rectangle_colliders) => rectangle_colliders,
+    let models = match manager.query::<Model3d>() {
+        Some(models) => models,
         None => return,
     };
 
-    let mut transforms = match manager.query_mut::<Transform3d>() {
-        Some(transforms) => transforms,
+    for (entity, emissive) in emissives.iter_mut() {
+        emissive.advance(delta_time);
+        if !emissive.get_update_flag() {
+            continue;
+        }
+
+        if let Some(object_index) = models.get(entity).and_then(|m| m.get_renderer_index()) {
+            manager.with_renderer(|renderer| {
+                renderer.set_instance_emission(*object_index, emissive.get_current_intensity())
+            });
+        }
+
+        emissive.update();
+    }
+}
+
+fn update_visibility(manager: &mut HeliumManager) {
+    let visibles = match manager.query::<Visible>() {
+        Some(visibles) => visibles,
         None => return,
     };
 
-    let mut gravities = match manager.query_mut::<Gravity>() {
-        Some(gravities) => gravities,
+    let models = match manager.query::<Model3d>() {
+        Some(models) => models,
         None => return,
     };
 
-    for (entity, rectangle_colider) in rectangle_colliders.iter_mut() {
-        if let Some(gravity) = gravities.get_mut(entity) {
-            gravity.update_gravity(&manager.delta_time);
-
-            if let Some(transform) = transforms.get_mut(entity) {
-                for (_, plane_collider) in stationary_plane_colliders.iter() {
-                    if rectangle_colider.is_colliding(plane_collider) {
-                        rectangle_colider.snap_y(plane_collider);
-                        gravity.kill_velocity();
-                    }
-                }
-
-                transform
-                    .add_position(gravity.velocity * manager.delta_time.elapsed().as_secs_f32());
+    let world_rendered = manager.is_world_rendered(manager.get_active_world());
+    let Some(renderer) = &manager.renderer_instance else {
+        return;
+    };
+    let mut renderer = renderer.lock().unwrap();
+    for (entity, visible) in visibles.iter() {
+        if let Some(object_index) = models.get(entity).and_then(|m| m.get_renderer_index()) {
+            // Only the objects whose visibility changed are written to the instance buffer
+            let visible = visible.is_visible() && world_rendered;
+            if renderer.is_object_visible(*object_index) != visible {
+                renderer.set_object_visible(*object_index, visible);
             }
         }
     }
 }
 
-fn update_cameras(manager: &mut HeliumManager) {
-    let mut transforms = match manager.query_mut::<Transform3d>() {
-        Some(transforms) => transforms,
+fn update_reflections(manager: &mut HeliumManager) {
+    let reflectives = match manager.query::<Reflective>() {
+        Some(reflectives) => reflectives,
         None => return,
     };
 
-    let mut cameras = match manager.query_mut::<Camera3d>() {
-        Some(cameras) => cameras,
+    let models = match manager.query::<Model3d>() {
+        Some(models) => models,
         None => return,
     };
 
-    let mut camera_controllers = match manager.query_mut::<CameraController>() {
-        Some(controllers) => controllers,
+    let transforms = match manager.query::<Transform3d>() {
+        Some(transforms) => transforms,
         None => return,
     };
 
-    // If any of the above doesn't exist there is no point of continuing on
-
-    for (entity, controller) in camera_controllers.iter_mut() {
-        if let Some(camera) = cameras.get_mut(entity) {
-            camera.add_yaw(-controller.delta.0);
-            camera.add_pitch(-controller.delta.1);
-            controller.delta = (0.0, 0.0);
-
-            if let Some(transform) = transforms.get_mut(entity) {
-                let forward_norm = camera.target.normalize();
-
-                if controller.forward {
-                    transform.add_position(
-                        forward_norm * manager.delta_time.elapsed().as_secs_f32() * CAMERA_SPEED,
-                    );
-                }
-
-                if controller.backward {
-                    transform.add_position(
-                        -forward_norm * manager.delta_time.elapsed().as_secs_f32() * CAMERA_SPEED,
-                    );
-                }
-
-                let right = forward_norm.cross(camera.up);
-
-                if controller.left {
-                    transform.add_position(
-                        -right * manager.delta_time.elapsed().as_secs_f32() * CAMERA_SPEED,
-                    );
-                }
-
-                if controller.right {
-                    transform.add_position(
-                        right * manager.delta_time.elapsed().as_secs_f32() * CAMERA_SPEED,
-                    );
-                }
-            }
+    let Some(renderer) = &manager.renderer_instance else {
+        return;
+    };
+    let mut renderer = renderer.lock().unwrap();
+    for (entity, reflective) in reflectives.iter() {
+        let (Some(object_index), Some(transform)) = (
+            models.get(entity).and_then(|m| m.get_renderer_index()),
+            transforms.get(entity),
+        ) else {
+            continue;
+        };
 
-            manager.move_camera_to_render(camera);
-        }
+        // The renderer only rewrites the reflection when it changes
+        renderer.set_object_reflection(
+            *object_index,
+            Some(Reflection {
+                point: *transform.get_position(),
+                normal: transform.get_rotation() * Vector3::unit_y(),
+                reflectivity: reflective.get_reflectivity(),
+            }),
+        );
     }
 }
 
-fn update_transforms_to_renderer(manager: &mut HeliumManager) {
-    // List of transforms to look through and update
-    let mut transforms = match manager.query_mut::<Transform3d>() {
+fn update_world_ui(manager: &mut HeliumManager) {
+    let transforms = match manager.query::<Transform3d>() {
         Some(transforms) => transforms,
         None => return,
     };
 
-    // Models to update in the renderer
-    let models = manager.query::<Model3d>();
-
-    // Camera to update if it exists
-    let mut cameras = manager.query_mut::<Camera3d>();
+    let world_texts = manager.query::<WorldText>();
+    let world_bars = manager.query::<WorldBar>();
+    let world_labels = manager.query::<WorldLabel>();
 
-    // Rectangle Colliders to update if it exists
-    let mut colliders = manager.query_mut::<RectangleCollider>();
+    // World ui is projected every update so it follows the camera
+    let Some(renderer) = &manager.renderer_instance else {
+        return;
+    };
+    let mut renderer = renderer.lock().unwrap();
 
-    // Lights to update if exists
-    let mut lights = manager.query_mut::<Light>();
+    if let Some(world_texts) = world_texts.as_ref() {
+        for (entity, world_text) in world_texts.iter() {
+            let text_index = match world_text.get_renderer_index() {
+                Some(text_index) => *text_index,
+                None => continue,
+            };
 
-    for (entity, transform) in transforms.iter_mut() {
-        if !transform.get_update_flag() {
-            continue;
-        }
+            let screen_point = transforms.get(entity).and_then(|transform| {
+                renderer.world_to_screen(world_text.world_position(*transform.get_position()))
+            });
 
-        // Update the model position
-        if let Some(models) = models.as_ref() {
-            if let Some(object_index) = models.get(entity) {
-                manager.renderer_instance.lock().unwrap().update_instances(
-                    *object_index.get_renderer_index().unwrap(),
-                    vec![(*transform).into()],
-                );
+            match screen_point {
+                Some(screen_point) => {
+                    renderer.update_text(text_index, world_text.overlay_text(&screen_point))
+                }
+                None => renderer.remove_text(text_index),
             }
         }
+    }
 
-        // Update the Camera position
-        if let Some(cameras) = cameras.as_mut() {
-            if let Some(camera) = cameras.get_mut(entity) {
-                let pos = transform.get_position();
-                camera.set_position(cgmath::point3::<f32>(pos.x, pos.y, pos.z));
-            }
-        }
+    if let Some(world_bars) = world_bars.as_ref() {
+        for (entity, world_bar) in world_bars.iter() {
+            let (background_index, fill_index) =
+                match (world_bar.get_background_index(), world_bar.get_fill_index()) {
+                    (Some(background_index), Some(fill_index)) => (*background_index, *fill_index),
+                    _ => continue,
+                };
 
-        // Update the colliders position
-        if let Some(colliders) = colliders.as_mut() {
-            if let Some(collider) = colliders.get_mut(entity) {
-                collider.set_origin(transform.get_position());
-            }
-        }
+            let screen_point = transforms.get(entity).and_then(|transform| {
+                renderer.world_to_screen(world_bar.world_position(*transform.get_position()))
+            });
 
-        // Update the lights position
-        if let Some(lights) = lights.as_mut() {
-            if let Some(light) = lights.get_mut(entity) {
-                light.update_position(transform.get_position());
-                manager
-                    .renderer_instance
-                    .lock()
-                    .unwrap()
-                    .update_light(light);
+            match screen_point {
+                Some(screen_point) => {
+                    let (background, fill) = world_bar.quads(&screen_point);
+                    renderer.update_quad(background_index, background);
+                    renderer.update_quad(fill_index, fill);
+                }
+                None => {
+                    renderer.remove_quad(background_index);
+                    renderer.remove_quad(fill_index);
+                }
             }
         }
+    }
 
-        transform.update();
+    // Labels are drawn in the world so they only need to follow their entity
+    if let Some(world_labels) = world_labels.as_ref() {
+        for (entity, world_label) in world_labels.iter() {
+            let (Some(text_index), Some(transform)) =
+                (world_label.get_renderer_index(), transforms.get(entity))
+            else {
+                continue;
+            };
+
+            renderer.update_sdf_text(
+                *text_index,
+                world_label.sdf_text(*transform.get_position(), *transform.get_rotation()),
+            );
+        }
     }
 }
 
@@ -225,6 +1456,10 @@ pub struct Helium {
     window: Option<Arc<Window>>,
     /// Event handling for the window
     event_handler: Arc<Mutex<VecDeque<InputEvent>>>,
+    /// Mouse motion for the cameras that the update thread reads once every update
+    look_input: Arc<LookInput>,
+    /// State of the cursor in the window for the ui
+    cursor: Arc<Mutex<Cursor>>,
     /// Renderer for the window
     renderer: Option<Arc<Mutex<HeliumState>>>,
     /// Thread that runs continuously to call update functions from the user
@@ -233,6 +1468,22 @@ pub struct Helium {
     event_loop_working: Arc<Mutex<bool>>,
     /// Time to keep track of fps
     fps: Instant,
+    /// Seed for the random number generator of the manager, seeded from the time if unset
+    seed: Option<u64>,
+    /// Order the component maps of the ecs iterate in
+    storage_order: StorageOrder,
+    /// How often the update thread runs
+    pacing: UpdatePacing,
+    /// How the logger is installed
+    log_settings: LogSettings,
+    /// The latest lines of the log when the logger keeps them
+    log_console: Option<LogConsole>,
+    /// Directory that crash reports are written to when a thread panics
+    crash_reports: Option<PathBuf>,
+    /// What happens when the update thread panics
+    panic_policy: UpdatePanicPolicy,
+    /// Number of times the update thread was restarted after a panic
+    restarts: u32,
 }
 
 impl Default for Helium {
@@ -246,10 +1497,20 @@ impl Default for Helium {
             input_functions: Arc::new(Mutex::new(Vec::new())),
             window: None,
             event_handler: Arc::new(Mutex::new(VecDeque::new())),
+            look_input: Arc::new(LookInput::default()),
+            cursor: Arc::new(Mutex::new(Cursor::default())),
             renderer: None,
             update_thread: None,
             event_loop_working: Arc::new(Mutex::new(false)),
             fps: Instant::now(),
+            seed: None,
+            storage_order: StorageOrder::default(),
+            pacing: UpdatePacing::default(),
+            log_settings: LogSettings::default(),
+            log_console: None,
+            crash_reports: Some(PathBuf::from("crash_reports")),
+            panic_policy: UpdatePanicPolicy::default(),
+            restarts: 0,
         }
     }
 }
@@ -309,8 +1570,273 @@ impl Helium {
         self
     }
 
+    /// Seeds the random number generator of the manager so runs can be reproduced
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The seed for `manager.rng()`
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self
+    pub fn set_seed(&mut self, seed: u64) -> &mut Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets the order the ecs iterates components in, `StorageOrder::Deterministic` makes
+    /// systems behave the same every run for tests, networking and replays
+    ///
+    /// # Arguments
+    ///
+    /// * `storage_order` - The order of the component maps
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self
+    pub fn set_storage_order(&mut self, storage_order: StorageOrder) -> &mut Self {
+        self.storage_order = storage_order;
+        self
+    }
+
+    /// Sets how often the update thread runs and how often the physics systems run
+    ///
+    /// # Arguments
+    ///
+    /// * `pacing` - The update rate and fixed tick rate
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self
+    pub fn set_update_pacing(&mut self, pacing: UpdatePacing) -> &mut Self {
+        self.pacing = pacing;
+        self
+    }
+
+    /// Sets how the logger is installed when the engine runs, like the levels of each
+    /// module, a log file, and a console of the latest lines
+    ///
+    /// # Arguments
+    ///
+    /// * `log_settings` - The settings of the logger
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self
+    pub fn set_log_settings(&mut self, log_settings: LogSettings) -> &mut Self {
+        self.log_settings = log_settings;
+        self
+    }
+
+    /// Sets the directory that a crash report is written to when the update thread or
+    /// the renderer panics, the report has the frame and system that were running, the
+    /// gpu, and the latest drawn frames. It is `crash_reports` by default
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - The directory of the reports, `None` writes no reports
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self
+    pub fn set_crash_reports<P>(&mut self, directory: Option<P>) -> &mut Self
+    where
+        P: AsRef<Path>,
+    {
+        self.crash_reports = directory.map(|directory| directory.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets what happens when an update function or engine system panics, the window is
+    /// closed by default
+    ///
+    /// # Arguments
+    ///
+    /// * `panic_policy` - Whether to exit, show the error, or restart the update thread
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to self
+    pub fn set_update_panic_policy(&mut self, panic_policy: UpdatePanicPolicy) -> &mut Self {
+        self.panic_policy = panic_policy;
+        self
+    }
+
+    // The shared state that the update thread may have been holding when it panicked
+    // is still used after the panic
+    fn clear_poison(&self) {
+        self.renderer.as_ref().unwrap().clear_poison();
+        self.update_functions.clear_poison();
+        self.input_functions.clear_poison();
+        self.startup_functions.clear_poison();
+        self.event_handler.clear_poison();
+        self.cursor.clear_poison();
+    }
+
+    // Handles the panic of the update thread with the panic policy
+    //
+    // Returns true when the window stays open
+    fn handle_update_panic(&mut self, message: &str) -> bool {
+        self.clear_poison();
+
+        let policy = match self.panic_policy {
+            UpdatePanicPolicy::Restart if self.restarts >= MAX_UPDATE_RESTARTS => {
+                error!("The update thread kept panicking after {MAX_UPDATE_RESTARTS} restarts");
+                UpdatePanicPolicy::ShowError
+            }
+            policy => policy,
+        };
+
+        match policy {
+            UpdatePanicPolicy::Exit => false,
+            UpdatePanicPolicy::ShowError => {
+                self.renderer.as_ref().unwrap().lock().unwrap().create_text(
+                    helium_renderer::OverlayText::new(
+                        format!("The update thread panicked: {message}"),
+                        (10.0, 40.0),
+                        TextStyle::default().with_color(LinearRgba::new(1.0, 0.2, 0.2, 1.0)),
+                    ),
+                );
+                true
+            }
+            UpdatePanicPolicy::Restart => {
+                warn!("Restarting the update thread");
+                self.restarts += 1;
+
+                // The scene of the old ecs is removed so the startup functions create it again
+                self.renderer
+                    .as_ref()
+                    .unwrap()
+                    .lock()
+                    .unwrap()
+                    .clear_scene();
+                self.event_handler.lock().unwrap().clear();
+                self.spawn_update_thread();
+                true
+            }
+        }
+    }
+
+    // Starts the update thread with a new ecs that runs the startup functions
+    fn spawn_update_thread(&mut self) {
+        // Create arc clones to pass to the ecs
+        let startup_functions_clone = self.startup_functions.clone();
+        let update_functions_clone = self.update_functions.clone();
+        let input_functions_clone = self.input_functions.clone();
+        let renderer_clone = self.renderer.as_ref().unwrap().clone();
+        let event_handler_clone = self.event_handler.clone();
+        let look_input_clone = self.look_input.clone();
+        let cursor_clone = self.cursor.clone();
+        let seed = self.seed;
+        let storage_order = self.storage_order;
+        let pacing = self.pacing;
+        let log_console = self.log_console.clone();
+
+        // For making sure this thread ends as soon as the main thread ends
+        let event_loop_working_clone = self.event_loop_working.clone();
+
+        // This is the continuously running update thread
+        let update_thread = thread::Builder::new().name(String::from(crash::UPDATE_THREAD));
+        self.update_thread = Some(
+            update_thread
+                .spawn(move || {
+                    let new_ecs = HeliumECS::with_storage_order(storage_order);
+                    let mut manager = HeliumManager::new(new_ecs, Some(renderer_clone));
+                    if let Some(seed) = seed {
+                        manager.rng().set_seed(seed);
+                    }
+                    manager.set_update_pacing(pacing);
+                    manager.log_console = log_console;
+                    info!("Starting Helium ECS");
+
+                    // Run all the starup functions when starting the update thread
+                    manager.startup_functions = startup_functions_clone.lock().unwrap().clone();
+                    manager.run_startup_functions();
+                    info!("Starup functions complete, Running Updates");
+
+                    loop {
+                        crash::begin_frame();
+                        let update_span = profile_span("update");
+                        let update_start = Instant::now();
+                        manager.begin_update();
+                        manager.cursor = *cursor_clone.lock().unwrap();
+                        manager.look_delta = look_input_clone.take();
+                        // Make the events sent last frame readable
+                        manager.update_events();
+
+                        // Handle all updates
+                        crash::set_system(Some("update_functions"));
+                        let span = profile_span("update_functions");
+                        let start = Instant::now();
+                        for update_function in
+                            update_functions_clone.lock().as_ref().unwrap().iter()
+                        {
+                            update_function(&mut manager);
+                        }
+                        manager
+                            .system_timings
+                            .record("update_functions", start.elapsed());
+                        drop(span);
+
+                        // Run the systems of the other worlds with their world active
+                        crash::set_system(Some("world_systems"));
+                        let span = profile_span("world_systems");
+                        let start = Instant::now();
+                        manager.run_world_systems();
+                        manager
+                            .system_timings
+                            .record("world_systems", start.elapsed());
+                        drop(span);
+
+                        // Handle any necessary window events here
+                        crash::set_system(Some("input_functions"));
+                        let span = profile_span("input_functions");
+                        while let Some(event) = event_handler_clone.lock().unwrap().pop_front() {
+                            manager.process_editor_input(&event);
+                            manager.process_interaction_input(&event);
+                            for input_function in input_functions_clone.lock().unwrap().iter() {
+                                input_function(&mut manager, &event);
+                            }
+                        }
+                        drop(span);
+
+                        run_engine_systems(&mut manager);
+                        // Handle lights
+                        manager.delta_time = Instant::now();
+                        // The update ends before the thread sleeps until the next one
+                        drop(update_span);
+                        profile_frame("update");
+
+                        if !(*event_loop_working_clone.lock().unwrap()) {
+                            break;
+                        }
+
+                        // Sleep until the next update, input that arrives wakes the thread early
+                        if let Some(interval) = manager.get_update_pacing().get_update_interval() {
+                            let next_update = update_start + interval;
+                            let now = Instant::now();
+                            if now < next_update {
+                                thread::park_timeout(next_update - now);
+                            }
+                        }
+                    }
+                })
+                .unwrap(),
+        );
+    }
+
+    // Wakes the update thread from its sleep between updates
+    fn wake_update_thread(&self) {
+        if let Some(update_thread) = self.update_thread.as_ref() {
+            update_thread.thread().unpark();
+        }
+    }
+
     pub fn run(&mut self) {
-        pretty_env_logger::init();
+        self.log_console = logging::install(&self.log_settings);
+        if let Some(directory) = self.crash_reports.clone() {
+            crash::install_panic_hook(directory);
+        }
         info!("Starting Helium Window");
 
         *self.event_loop_working.lock().unwrap() = true;
@@ -333,55 +1859,19 @@ impl ApplicationHandler for Helium {
             self.window.as_ref().unwrap().clone(),
         ))));
 
-        // Create arc clones to pass to the ecs
-        let startup_functions_clone = self.startup_functions.clone();
-        let update_functions_clone = self.update_functions.clone();
-        let input_functions_clone = self.input_functions.clone();
-        let renderer_clone = self.renderer.as_ref().unwrap().clone();
-        let event_handler_clone = self.event_handler.clone();
-
-        // For making sure this thread ends as soon as the main thread ends
-        let event_loop_working_clone = self.event_loop_working.clone();
-
-        // This is the continuously running update thread
-        self.update_thread = Some(thread::spawn(move || {
-            let new_ecs = HeliumECS::default();
-            let mut manager = HeliumManager::new(new_ecs, renderer_clone);
-            info!("Starting Helium ECS");
-
-            // Run all the starup functions when starting the update thread
-            for startup_function in startup_functions_clone.lock().as_ref().unwrap().iter() {
-                startup_function(&mut manager);
-            }
-            info!("Starup functions complete, Running Updates");
-
-            loop {
-                // Handle all updates
-                for update_function in update_functions_clone.lock().as_ref().unwrap().iter() {
-                    update_function(&mut manager);
-                }
-
-                // Handle any necessary window events here
-                while let Some(event) = event_handler_clone.lock().unwrap().pop_front() {
-                    for input_function in input_functions_clone.lock().unwrap().iter() {
-                        input_function(&mut manager, &event);
-                    }
-                }
+        {
+            let renderer = self.renderer.as_ref().unwrap().lock().unwrap();
+            let adapter_info = renderer.get_capabilities().get_adapter_info();
+            crash::set_gpu(format!(
+                "{} ({:?}, {} {})",
+                adapter_info.name,
+                adapter_info.backend,
+                adapter_info.driver,
+                adapter_info.driver_info
+            ));
+        }
 
-                // Handle collisions
-                handle_gravity_collisions(&mut manager);
-                // Update all the changed transforms
-                update_transforms_to_renderer(&mut manager);
-                // Handle cameras
-                update_cameras(&mut manager);
-                // Handle lights
-                manager.delta_time = Instant::now();
-
-                if !(*event_loop_working_clone.lock().unwrap()) {
-                    break;
-                }
-            }
-        }));
+        self.spawn_update_thread();
     }
 
     fn window_event(
@@ -395,26 +1885,61 @@ impl ApplicationHandler for Helium {
                 WindowEvent::CloseRequested => {
                     info!("Window close requested; stopping");
                     *self.event_loop_working.lock().unwrap() = false;
-                    self.update_thread.take().unwrap().join().unwrap();
+                    self.wake_update_thread();
+                    if let Some(update_thread) = self.update_thread.take() {
+                        _ = update_thread.join();
+                    }
                     event_loop.exit();
                 }
                 WindowEvent::RedrawRequested => {
                     // Redraw the application
                     if let Ok(renderer) = self.renderer.as_ref().unwrap().clone().lock().as_mut() {
+                        // Everything is loaded onto a new device after a driver reset
+                        if renderer.is_device_lost() {
+                            if let Err(e) = renderer.recover_device() {
+                                error!("Failed to recover the renderer: {e}");
+                                return;
+                            }
+                        }
+
                         renderer.fps =
                             format!("{:>7.2} FPS", 1.0 / self.fps.elapsed().as_secs_f32());
-                        _ = renderer.render();
+                        let result = renderer.render();
+                        crash::record_render(format!(
+                            "{:?} {:?}",
+                            result,
+                            renderer.get_render_stats()
+                        ));
                         self.fps = Instant::now();
                     }
                 }
+                WindowEvent::CursorMoved { position, .. } => {
+                    self.cursor.lock().unwrap().position = (position.x as f32, position.y as f32);
+                }
+                WindowEvent::MouseInput {
+                    state,
+                    button: MouseButton::Left,
+                    ..
+                } => {
+                    self.cursor.lock().unwrap().left_pressed = state.is_pressed();
+                }
+                WindowEvent::MouseInput {
+                    state,
+                    button: MouseButton::Right,
+                    ..
+                } => {
+                    self.cursor.lock().unwrap().right_pressed = state.is_pressed();
+                }
                 WindowEvent::Resized(new_size) => {
                     if let Ok(renderer) = self.renderer.as_ref().unwrap().clone().lock().as_mut() {
                         renderer.resize(new_size);
-                        renderer.brush.resize_view(
-                            renderer.config.width as f32,
-                            renderer.config.height as f32,
-                            renderer.get_queue(),
-                        )
+                    }
+                }
+                WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                    let new_size = self.window.as_ref().unwrap().inner_size();
+                    if let Ok(renderer) = self.renderer.as_ref().unwrap().clone().lock().as_mut() {
+                        renderer.set_scale_factor(scale_factor);
+                        renderer.resize(new_size);
                     }
                 }
                 _ => {}
@@ -428,10 +1953,36 @@ impl ApplicationHandler for Helium {
         _device_id: DeviceId,
         event: DeviceEvent,
     ) {
+        if let DeviceEvent::MouseMotion { delta } = event {
+            self.look_input.add((delta.0 as f32, delta.1 as f32));
+        }
+
         self.event_handler.lock().unwrap().push_back(event);
+        self.wake_update_thread();
     }
 
-    fn about_to_wait(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
+    fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        // Closes the window instead of leaving it open when the update thread panicked
+        if self
+            .update_thread
+            .as_ref()
+            .is_some_and(|update_thread| update_thread.is_finished())
+        {
+            if let Err(payload) = self.update_thread.take().unwrap().join() {
+                let message = crash::panic_message(&*payload);
+                error!("The update thread panicked: {message}");
+
+                if self.handle_update_panic(&message) {
+                    return;
+                }
+            }
+
+            info!("The update thread stopped; stopping");
+            *self.event_loop_working.lock().unwrap() = false;
+            event_loop.exit();
+            return;
+        }
+
         self.window.as_ref().unwrap().request_redraw();
     }
 }
@@ -0,0 +1,475 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use log::warn;
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// The translated strings of every loaded language, looked up by key like "menu.start".
+/// Keys that are missing from the current language are looked up in the fallback
+/// language and then shown as the key itself so they are easy to spot
+#[derive(Clone, Debug, Default)]
+pub struct Localization {
+    tables: HashMap<String, HashMap<String, String>>,
+    language: String,
+    fallback_language: Option<String>,
+}
+
+impl Localization {
+    /// Loads the strings of a language from a file, strings that were already loaded for
+    /// the language are replaced
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - The name of the language, like "en" or "ja"
+    /// * `path` - A `.ftl` file of `key = value` messages or a `.json` file of an object of
+    ///   strings, nested objects add their keys after a dot
+    pub fn load_table<P>(&mut self, language: &str, path: P) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let contents = fs::read_to_string(path.as_ref())?;
+        let strings = match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("json") => parse_json(&contents)?,
+            Some("ftl") => parse_ftl(&contents),
+            extension => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unknown string table format {extension:?}, expected ftl or json"),
+                ))
+            }
+        };
+
+        self.add_strings(language, strings);
+        Ok(())
+    }
+
+    /// Adds strings to a language without a file
+    pub fn add_strings<I>(&mut self, language: &str, strings: I)
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        // The first language that is loaded is used until another one is chosen
+        if self.language.is_empty() {
+            self.language = language.to_string();
+        }
+
+        self.tables
+            .entry(language.to_string())
+            .or_default()
+            .extend(strings);
+    }
+
+    pub fn set_language(&mut self, language: &str) {
+        if !self.tables.contains_key(language) {
+            warn!("No strings are loaded for the language {language}");
+        }
+        self.language = language.to_string();
+    }
+
+    pub fn get_language(&self) -> &str {
+        &self.language
+    }
+
+    /// Sets the language that strings missing from the current language are taken from
+    pub fn set_fallback_language(&mut self, language: Option<&str>) {
+        self.fallback_language = language.map(str::to_string);
+    }
+
+    /// The languages that have strings loaded
+    pub fn get_languages(&self) -> Vec<&str> {
+        let mut languages = self.tables.keys().map(String::as_str).collect::<Vec<_>>();
+        languages.sort();
+        languages
+    }
+
+    fn lookup(&self, key: &str) -> Option<&str> {
+        [Some(&self.language), self.fallback_language.as_ref()]
+            .into_iter()
+            .flatten()
+            .find_map(|language| self.tables.get(language)?.get(key))
+            .map(String::as_str)
+    }
+
+    /// The string of a key in the current language, or the key when it is missing
+    pub fn tr(&self, key: &str) -> String {
+        self.tr_with(key, &[])
+    }
+
+    /// The string of a key with its variables filled in, the variables are written as
+    /// `{ $name }` in the string
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the string
+    /// * `args` - The name and value of every variable
+    pub fn tr_with(&self, key: &str, args: &[(&str, &str)]) -> String {
+        let Some(string) = self.lookup(key) else {
+            return key.to_string();
+        };
+
+        self.fill_placeables(string, args)
+    }
+
+    // Replaces the `{ $variable }` and `{ -term }` placeables of a string
+    fn fill_placeables(&self, string: &str, args: &[(&str, &str)]) -> String {
+        let mut filled = String::with_capacity(string.len());
+        let mut rest = string;
+        while let Some(open) = rest.find('{') {
+            let Some(close) = rest[open..].find('}').map(|close| open + close) else {
+                break;
+            };
+
+            filled.push_str(&rest[..open]);
+            let placeable = rest[open + 1..close].trim();
+            let value = if let Some(name) = placeable.strip_prefix('$') {
+                args.iter()
+                    .find(|(arg, _)| *arg == name)
+                    .map(|(_, value)| value.to_string())
+            } else if placeable.starts_with('-') {
+                // Terms are shared pieces of text like the name of the game
+                self.lookup(placeable).map(str::to_string)
+            } else {
+                // Quoted literals escape braces
+                placeable
+                    .strip_prefix('"')
+                    .and_then(|literal| literal.strip_suffix('"'))
+                    .map(str::to_string)
+            };
+
+            match value {
+                Some(value) => filled.push_str(&value),
+                None => filled.push_str(&rest[open..=close]),
+            }
+            rest = &rest[close + 1..];
+        }
+        filled.push_str(rest);
+
+        filled
+    }
+}
+
+// Reads the messages of a Fluent file, only plain messages, terms, attributes, and
+// multiline values are supported
+fn parse_ftl(contents: &str) -> Vec<(String, String)> {
+    let mut strings: Vec<(String, String)> = Vec::new();
+    // The message that attributes and indented lines belong to
+    let mut message: Option<String> = None;
+
+    for line in contents.lines() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // Indented lines continue the last value or add an attribute to the message
+        if line.starts_with(char::is_whitespace) {
+            let trimmed = line.trim();
+            if let Some(attribute) = trimmed.strip_prefix('.') {
+                if let (Some(message), Some((name, value))) =
+                    (message.as_ref(), attribute.split_once('='))
+                {
+                    strings.push((
+                        format!("{message}.{}", name.trim()),
+                        value.trim().to_string(),
+                    ));
+                }
+            } else if let Some((_, value)) = strings.last_mut() {
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(trimmed);
+            }
+            continue;
+        }
+
+        match line.split_once('=') {
+            Some((key, value)) => {
+                let key = key.trim().to_string();
+                strings.push((key.clone(), value.trim().to_string()));
+                message = Some(key);
+            }
+            None => warn!("Skipping the line {line:?} of a string table"),
+        }
+    }
+
+    strings
+}
+
+// Reads a json object of strings, the keys of nested objects are joined with dots
+fn parse_json(contents: &str) -> Result<Vec<(String, String)>, io::Error> {
+    let mut parser = JsonParser {
+        chars: contents.chars().collect(),
+        position: 0,
+    };
+    let mut strings = Vec::new();
+
+    parser.skip_whitespace();
+    parser.parse_object("", &mut strings)?;
+    parser.skip_whitespace();
+    if parser.position < parser.chars.len() {
+        return Err(invalid_data(format!(
+            "Unexpected text after the string table at {}",
+            parser.position
+        )));
+    }
+
+    Ok(strings)
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl JsonParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), io::Error> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(found) if found == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            found => Err(invalid_data(format!(
+                "Expected {expected:?} at {} but found {found:?}",
+                self.position
+            ))),
+        }
+    }
+
+    fn parse_object(
+        &mut self,
+        prefix: &str,
+        strings: &mut Vec<(String, String)>,
+    ) -> Result<(), io::Error> {
+        self.expect('{')?;
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(());
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            let key = if prefix.is_empty() {
+                key
+            } else {
+                format!("{prefix}.{key}")
+            };
+            self.expect(':')?;
+            self.skip_whitespace();
+
+            match self.peek() {
+                Some('{') => self.parse_object(&key, strings)?,
+                Some('"') => {
+                    let value = self.parse_string()?;
+                    strings.push((key, value));
+                }
+                // Numbers, booleans, and null are kept as they are written
+                _ => {
+                    let start = self.position;
+                    while self
+                        .peek()
+                        .is_some_and(|c| !matches!(c, ',' | '}') && !c.is_whitespace())
+                    {
+                        self.position += 1;
+                    }
+                    if start == self.position {
+                        return Err(invalid_data(format!("Expected a value at {start}")));
+                    }
+                    strings.push((key, self.chars[start..self.position].iter().collect()));
+                }
+            }
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some('}') => {
+                    self.position += 1;
+                    return Ok(());
+                }
+                found => {
+                    return Err(invalid_data(format!(
+                        "Expected ',' or '}}' at {} but found {found:?}",
+                        self.position
+                    )))
+                }
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, io::Error> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(invalid_data("Unterminated string".to_string()));
+            };
+            self.position += 1;
+
+            match c {
+                '"' => return Ok(string),
+                '\\' => {
+                    let escaped = self.peek();
+                    self.position += 1;
+                    match escaped {
+                        Some('n') => string.push('\n'),
+                        Some('t') => string.push('\t'),
+                        Some('r') => string.push('\r'),
+                        Some('b') => string.push('\u{8}'),
+                        Some('f') => string.push('\u{c}'),
+                        Some('u') => string.push(self.parse_unicode_escape()?),
+                        Some(other) => string.push(other),
+                        None => return Err(invalid_data("Unterminated string".to_string())),
+                    }
+                }
+                c => string.push(c),
+            }
+        }
+    }
+
+    // Reads the four hex digits of a unicode escape
+    fn parse_hex_code(&mut self) -> Result<u32, io::Error> {
+        let digits = self
+            .chars
+            .get(self.position..self.position + 4)
+            .map(|digits| digits.iter().collect::<String>())
+            .ok_or_else(|| invalid_data("Unterminated unicode escape".to_string()))?;
+        self.position += 4;
+        u32::from_str_radix(&digits, 16)
+            .map_err(|_| invalid_data(format!("Invalid unicode escape {digits}")))
+    }
+
+    // Reads the code after \u, pairs of surrogates make one character
+    fn parse_unicode_escape(&mut self) -> Result<char, io::Error> {
+        let high = self.parse_hex_code()?;
+        let code = if (0xD800..0xDC00).contains(&high)
+            && self.chars.get(self.position..self.position + 2) == Some(&['\\', 'u'])
+        {
+            self.position += 2;
+            match self.parse_hex_code()? {
+                low @ 0xDC00..0xE000 => 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00),
+                // A high surrogate without its low half is not a character, the escape
+                // after it is read on its own
+                _ => {
+                    self.position -= 6;
+                    return Ok(char::REPLACEMENT_CHARACTER);
+                }
+            }
+        } else {
+            high
+        };
+
+        Ok(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get<'a>(strings: &'a [(String, String)], key: &str) -> Option<&'a str> {
+        strings
+            .iter()
+            .rev()
+            .find(|(found, _)| found == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn test_ftl_messages() {
+        let strings = parse_ftl(
+            "# Menu\n\
+             menu-start = Start game\n\
+             -game-name = Helium\n\
+             intro =\n    Welcome to\n    { -game-name }\n\
+             login-input = Predefined value\n    .placeholder = email@example.com\n    .aria-label = Login\n\
+             \x20   input\n\
+             not a message\n",
+        );
+
+        assert_eq!(get(&strings, "menu-start"), Some("Start game"));
+        assert_eq!(get(&strings, "-game-name"), Some("Helium"));
+        assert_eq!(get(&strings, "intro"), Some("Welcome to\n{ -game-name }"));
+        assert_eq!(get(&strings, "login-input"), Some("Predefined value"));
+        assert_eq!(
+            get(&strings, "login-input.placeholder"),
+            Some("email@example.com")
+        );
+        // Indented lines after an attribute continue the attribute
+        assert_eq!(
+            get(&strings, "login-input.aria-label"),
+            Some("Login\ninput")
+        );
+        assert_eq!(strings.len(), 6);
+    }
+
+    #[test]
+    fn test_json_nested_keys() {
+        let strings = parse_json(
+            r#"{
+                "menu": { "start": "Start", "options": { "volume": "Volume" } },
+                "lives": 3,
+                "empty": {},
+                "quote": "Say \"hi\"\n"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(get(&strings, "menu.start"), Some("Start"));
+        assert_eq!(get(&strings, "menu.options.volume"), Some("Volume"));
+        assert_eq!(get(&strings, "lives"), Some("3"));
+        assert_eq!(get(&strings, "quote"), Some("Say \"hi\"\n"));
+        assert_eq!(strings.len(), 4);
+
+        assert!(parse_json(r#"{ "menu": "Start" } extra"#).is_err());
+        assert!(parse_json(r#"{ "menu": "Start" "#).is_err());
+    }
+
+    #[test]
+    fn test_json_unicode_escapes() {
+        let strings = parse_json(
+            r#"{
+                "smile": "\uD83D\uDE00",
+                "e": "caf\u00e9",
+                "broken": "\uD83DA\uD83D\u0042"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(get(&strings, "smile"), Some("\u{1F600}"));
+        assert_eq!(get(&strings, "e"), Some("café"));
+        assert_eq!(get(&strings, "broken"), Some("\u{FFFD}A\u{FFFD}B"));
+        assert!(parse_json(r#"{ "short": "\u12" }"#).is_err());
+    }
+
+    #[test]
+    fn test_fallback_language() {
+        let mut localization = Localization::default();
+        localization.add_strings("en", parse_ftl("greeting = Hello { $name }\nquit = Quit\n"));
+        localization.add_strings("fr", parse_ftl("greeting = Bonjour { $name }\n"));
+        localization.set_language("fr");
+
+        assert_eq!(
+            localization.tr_with("greeting", &[("name", "Ada")]),
+            "Bonjour Ada"
+        );
+        // Missing keys are shown as the key until there is a fallback language
+        assert_eq!(localization.tr("quit"), "quit");
+
+        localization.set_fallback_language(Some("en"));
+        assert_eq!(localization.tr("quit"), "Quit");
+        assert_eq!(localization.tr("missing"), "missing");
+    }
+}
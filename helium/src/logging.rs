@@ -0,0 +1,213 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// How the engine sets up logging when it starts
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogSettings {
+    install: bool,
+    level: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+    file: Option<PathBuf>,
+    console_lines: usize,
+}
+
+impl Default for LogSettings {
+    /// Installs a logger that writes errors to the terminal, `RUST_LOG` changes the levels
+    fn default() -> Self {
+        Self {
+            install: true,
+            level: LevelFilter::Error,
+            modules: Vec::new(),
+            file: None,
+            console_lines: 0,
+        }
+    }
+}
+
+impl LogSettings {
+    /// Sets if the engine installs its logger, turn it off when the app installs its own
+    pub fn with_install(mut self, install: bool) -> Self {
+        self.install = install;
+        self
+    }
+
+    /// Sets the level of everything that has no level of its own
+    pub fn with_level(mut self, level: LevelFilter) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Sets the level of a module and the modules inside it
+    ///
+    /// # Arguments
+    ///
+    /// * `module` - The path of the module, like `helium_renderer` or `helium::streaming`
+    /// * `level` - The most detailed level that is logged from the module
+    pub fn with_module_level(mut self, module: &str, level: LevelFilter) -> Self {
+        self.modules.push((module.to_string(), level));
+        self
+    }
+
+    /// Writes the log to a file as well as the terminal
+    pub fn with_file<P>(mut self, path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        self.file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Keeps the latest lines of the log so they can be shown in a console in the game
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - How many lines to keep, 0 keeps none
+    pub fn with_console(mut self, lines: usize) -> Self {
+        self.console_lines = lines;
+        self
+    }
+
+    pub fn get_level(&self) -> LevelFilter {
+        self.level
+    }
+}
+
+/// The latest lines of the log for showing in the game
+#[derive(Clone, Debug, Default)]
+pub struct LogConsole {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize,
+}
+
+impl LogConsole {
+    fn new(capacity: usize) -> Self {
+        Self {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    fn push(&self, line: String) {
+        let mut lines = self.lines.lock().unwrap();
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// Gives the kept lines from the oldest to the newest
+    pub fn get_lines(&self) -> Vec<String> {
+        self.lines.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.lines.lock().unwrap().clear();
+    }
+}
+
+// Writes to the terminal and to the file and console when there are any, the terminal
+// logger decides what is logged so every output logs the same records
+struct HeliumLogger {
+    terminal: Box<dyn Log>,
+    file: Option<Mutex<File>>,
+    console: Option<LogConsole>,
+}
+
+impl Log for HeliumLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.terminal.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        self.terminal.log(record);
+
+        if self.file.is_none() && self.console.is_none() {
+            return;
+        }
+
+        let line = format!(
+            "{:<5} {} > {}",
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        if let Some(file) = self.file.as_ref() {
+            _ = writeln!(file.lock().unwrap(), "{line}");
+        }
+
+        if let Some(console) = self.console.as_ref() {
+            console.push(line);
+        }
+    }
+
+    fn flush(&self) {
+        self.terminal.flush();
+        if let Some(file) = self.file.as_ref() {
+            _ = file.lock().unwrap().flush();
+        }
+    }
+}
+
+/// Installs the engine logger unless the settings turn it off or another logger is
+/// already installed
+///
+/// # Returns
+///
+/// The console that keeps the latest lines when the settings have one
+pub(crate) fn install(settings: &LogSettings) -> Option<LogConsole> {
+    if !settings.install {
+        return None;
+    }
+
+    let mut builder = pretty_env_logger::formatted_builder();
+    builder.filter_level(settings.level);
+    for (module, level) in settings.modules.iter() {
+        builder.filter_module(module, *level);
+    }
+
+    // The environment overrides the levels of the settings
+    if let Ok(filters) = std::env::var("RUST_LOG") {
+        builder.parse_filters(&filters);
+    }
+
+    let terminal = builder.build();
+    let max_level = terminal.filter();
+
+    let file = settings
+        .file
+        .as_ref()
+        .and_then(|path| match File::create(path) {
+            Ok(file) => Some(Mutex::new(file)),
+            Err(e) => {
+                eprintln!("Failed to create the log file {path:?}: {e}");
+                None
+            }
+        });
+    let console = (settings.console_lines > 0).then(|| LogConsole::new(settings.console_lines));
+
+    let logger = HeliumLogger {
+        terminal: Box::new(terminal),
+        file,
+        console: console.clone(),
+    };
+
+    match log::set_boxed_logger(Box::new(logger)) {
+        Ok(()) => {
+            log::set_max_level(max_level);
+            console
+        }
+        // The app installed its own logger, the records go to it instead
+        Err(_) => None,
+    }
+}
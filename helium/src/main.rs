@@ -112,12 +112,11 @@ fn add_model(manager: &mut HeliumManager) {
 }
 
 fn add_camera(manager: &mut HeliumManager) {
-    let config = manager.get_render_config();
     let camera = manager.create_camera(Camera3d::new(
         (5.0, 5.0, 5.0).into(),
         (-5.0, -5.0, -5.0).into(),
         Vector3::unit_y(),
-        config.width as f32 / config.height as f32,
+        manager.get_aspect_ratio(),
         45.0,
         0.1,
         100.0,
@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+/// How often the update thread runs. The update functions and most engine systems run
+/// once every update, while the physics systems can run at a fixed rate of their own
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UpdatePacing {
+    max_update_rate: Option<f32>,
+    fixed_tick_rate: Option<f32>,
+    max_fixed_ticks: u32,
+}
+
+impl Default for UpdatePacing {
+    /// Updates at most 240 times a second with the physics running once every update
+    fn default() -> Self {
+        Self {
+            max_update_rate: Some(240.0),
+            fixed_tick_rate: None,
+            max_fixed_ticks: 8,
+        }
+    }
+}
+
+impl UpdatePacing {
+    /// Sets the most updates a second, the update thread sleeps until the next update or
+    /// until input arrives. `None` updates as fast as possible
+    pub fn with_max_update_rate(mut self, max_update_rate: Option<f32>) -> Self {
+        self.max_update_rate = max_update_rate.filter(|rate| *rate > 0.0);
+        self
+    }
+
+    /// Sets how many times a second the physics systems run with a fixed delta time.
+    /// `None` runs them once every update with the time since the last update
+    pub fn with_fixed_tick_rate(mut self, fixed_tick_rate: Option<f32>) -> Self {
+        self.fixed_tick_rate = fixed_tick_rate.filter(|rate| *rate > 0.0);
+        self
+    }
+
+    /// Sets the most fixed ticks run in one update, the ticks past it are dropped so a
+    /// slow update does not make the next one even slower
+    pub fn with_max_fixed_ticks(mut self, max_fixed_ticks: u32) -> Self {
+        self.max_fixed_ticks = max_fixed_ticks.max(1);
+        self
+    }
+
+    pub fn get_max_update_rate(&self) -> Option<f32> {
+        self.max_update_rate
+    }
+
+    pub fn get_fixed_tick_rate(&self) -> Option<f32> {
+        self.fixed_tick_rate
+    }
+
+    pub fn get_max_fixed_ticks(&self) -> u32 {
+        self.max_fixed_ticks
+    }
+
+    /// The shortest time between two updates
+    pub fn get_update_interval(&self) -> Option<Duration> {
+        self.max_update_rate
+            .map(|rate| Duration::from_secs_f32(1.0 / rate))
+    }
+
+    /// The delta time of every fixed tick
+    pub fn get_fixed_interval(&self) -> Option<Duration> {
+        self.fixed_tick_rate
+            .map(|rate| Duration::from_secs_f32(1.0 / rate))
+    }
+}
+
+/// Keeps the time that has not been used by a fixed tick yet
+#[derive(Debug, Default)]
+pub(crate) struct FixedClock {
+    accumulated: Duration,
+}
+
+impl FixedClock {
+    /// Adds the time of an update
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed` - The time since the last update
+    /// * `interval` - The delta time of a fixed tick
+    /// * `max_ticks` - The most ticks to run for the update
+    ///
+    /// # Returns
+    ///
+    /// The number of fixed ticks to run
+    pub fn advance(&mut self, elapsed: Duration, interval: Duration, max_ticks: u32) -> u32 {
+        if interval.is_zero() {
+            return 0;
+        }
+
+        self.accumulated += elapsed;
+        let due = (self.accumulated.as_nanos() / interval.as_nanos()) as u32;
+        self.accumulated -= interval * due;
+
+        due.min(max_ticks)
+    }
+}
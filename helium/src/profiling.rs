@@ -0,0 +1,206 @@
+use std::time::Duration;
+
+use helium_renderer::{
+    get_last_frame_spans, get_profile_threads, HeliumState, OverlayQuad, OverlayText, Srgba,
+    TextStyle,
+};
+
+/// How long each engine system took during the last frame
+#[derive(Clone, Debug, Default)]
+pub struct SystemTimings {
+    // Timings in the order the systems ran
+    timings: Vec<(&'static str, Duration)>,
+}
+
+impl SystemTimings {
+    /// Used internally to record the time a system took this frame
+    pub(crate) fn record(&mut self, name: &'static str, duration: Duration) {
+        match self
+            .timings
+            .iter_mut()
+            .find(|(timing_name, _)| *timing_name == name)
+        {
+            Some((_, timing)) => *timing = duration,
+            None => self.timings.push((name, duration)),
+        }
+    }
+
+    /// Gives the time a system took during the last frame
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the system, like `"update_steering"`
+    pub fn get(&self, name: &str) -> Option<Duration> {
+        self.timings
+            .iter()
+            .find(|(timing_name, _)| *timing_name == name)
+            .map(|(_, timing)| *timing)
+    }
+
+    /// Iterates the systems and their times in the order they ran
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, Duration)> + '_ {
+        self.timings.iter().copied()
+    }
+
+    /// The time all the systems took together
+    pub fn total(&self) -> Duration {
+        self.timings.iter().map(|(_, timing)| *timing).sum()
+    }
+
+    /// Formats the timings as lines of text for the stats overlay
+    pub fn to_overlay_text(&self) -> String {
+        self.timings
+            .iter()
+            .map(|(name, timing)| format!("{:>8.3} ms {name}", timing.as_secs_f64() * 1000.0))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// Height of a row of spans in the flamegraph in pixels
+const FLAMEGRAPH_ROW: f32 = 16.0;
+
+// Space around the flamegraph and between the rows of the threads in pixels
+const FLAMEGRAPH_MARGIN: f32 = 8.0;
+
+// Width of a character of the span names, names that do not fit in their span are hidden
+const FLAMEGRAPH_CHARACTER: f32 = 7.0;
+
+// Colors the spans are drawn in, a span keeps its color from frame to frame
+const FLAMEGRAPH_COLORS: [(f32, f32, f32); 6] = [
+    (0.85, 0.35, 0.25),
+    (0.9, 0.6, 0.2),
+    (0.75, 0.75, 0.25),
+    (0.35, 0.7, 0.35),
+    (0.3, 0.55, 0.85),
+    (0.6, 0.4, 0.8),
+];
+
+// Picks the color of a span from its name
+fn span_color(name: &str) -> Srgba {
+    let hash = name.bytes().fold(2166136261u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(16777619)
+    });
+    let (r, g, b) = FLAMEGRAPH_COLORS[hash as usize % FLAMEGRAPH_COLORS.len()];
+    Srgba::rgb(r, g, b)
+}
+
+/// Draws the spans of the last frame of each thread as a flamegraph at the bottom of the
+/// screen, the quads and text are reused from frame to frame
+#[derive(Default)]
+pub(crate) struct ProfilerOverlay {
+    quads: Vec<usize>,
+    texts: Vec<usize>,
+}
+
+impl ProfilerOverlay {
+    pub fn update(&mut self, renderer: &mut HeliumState) {
+        let spans = get_last_frame_spans();
+        let threads = get_profile_threads();
+        let (width, height) = (renderer.config.width as f32, renderer.config.height as f32);
+        let bar_width = width - FLAMEGRAPH_MARGIN * 2.0;
+        let text_style = TextStyle::default().with_size(FLAMEGRAPH_ROW - 4.0);
+
+        // Each thread gets as many rows as its deepest span, the last thread is at the bottom
+        let frames: Vec<_> = spans.iter().filter(|span| span.depth == 0).collect();
+        let mut quads = Vec::new();
+        let mut texts = Vec::new();
+        let mut bottom = height - FLAMEGRAPH_MARGIN;
+        for frame in frames.iter().rev() {
+            let thread_spans: Vec<_> = spans
+                .iter()
+                .filter(|span| span.thread == frame.thread)
+                .collect();
+            let rows = thread_spans
+                .iter()
+                .map(|span| span.depth + 1)
+                .max()
+                .unwrap_or(1);
+            let top = bottom - rows as f32 * FLAMEGRAPH_ROW;
+            let frame_time = frame.duration.as_secs_f32().max(f32::EPSILON);
+
+            for span in thread_spans {
+                let offset = (span.start - frame.start).as_secs_f32() / frame_time;
+                let x = FLAMEGRAPH_MARGIN + offset * bar_width;
+                let y = top + span.depth as f32 * FLAMEGRAPH_ROW;
+                let span_width = (span.duration.as_secs_f32() / frame_time * bar_width).max(1.0);
+                quads.push(OverlayQuad::new(
+                    (x, y),
+                    (span_width, FLAMEGRAPH_ROW - 1.0),
+                    span_color(span.name),
+                ));
+
+                // The frame is labeled with its thread and how long it took
+                let label = if span.depth == 0 {
+                    let thread_name = threads
+                        .iter()
+                        .find(|(thread, _)| *thread == span.thread)
+                        .map_or("", |(_, name)| name.as_str());
+                    format!(
+                        "{} {} {:.3} ms",
+                        thread_name,
+                        span.name,
+                        span.duration.as_secs_f64() * 1000.0
+                    )
+                } else {
+                    span.name.to_string()
+                };
+                if label.len() as f32 * FLAMEGRAPH_CHARACTER <= span_width {
+                    texts.push(OverlayText::new(label, (x + 2.0, y + 1.0), text_style));
+                }
+            }
+
+            bottom = top - FLAMEGRAPH_MARGIN;
+        }
+
+        Self::sync(
+            &mut self.quads,
+            quads,
+            renderer,
+            HeliumState::create_quad,
+            HeliumState::update_quad,
+            HeliumState::remove_quad,
+        );
+        Self::sync(
+            &mut self.texts,
+            texts,
+            renderer,
+            HeliumState::create_text,
+            HeliumState::update_text,
+            HeliumState::remove_text,
+        );
+    }
+
+    // Puts the new items in the renderer in place of the items of the last frame
+    fn sync<T>(
+        indices: &mut Vec<usize>,
+        items: Vec<T>,
+        renderer: &mut HeliumState,
+        create: fn(&mut HeliumState, T) -> usize,
+        update: fn(&mut HeliumState, usize, T),
+        remove: fn(&mut HeliumState, usize),
+    ) {
+        let count = items.len();
+        for (position, item) in items.into_iter().enumerate() {
+            match indices.get(position) {
+                Some(index) => update(renderer, *index, item),
+                None => indices.push(create(renderer, item)),
+            }
+        }
+
+        // Unused items are hidden and kept for the next frames
+        for index in indices.iter().skip(count) {
+            remove(renderer, *index);
+        }
+    }
+
+    /// Removes the flamegraph from the screen
+    pub fn clear(&mut self, renderer: &mut HeliumState) {
+        for index in self.quads.drain(..) {
+            renderer.remove_quad(index);
+        }
+        for index in self.texts.drain(..) {
+            renderer.remove_text(index);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    ops::Range,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use cgmath::{InnerSpace, Vector3};
+
+// Spreads the bits of a seed so seeds that are close give unrelated states
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// A seedable random number generator, the same seed always gives the same numbers so
+/// procedural placement, ai and particles can be replayed exactly
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rng {
+    seed: u64,
+    // State of the xoshiro256** generator
+    state: [u64; 4],
+}
+
+impl Default for Rng {
+    /// Seeds the generator from the current time
+    fn default() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or_default();
+
+        Self::new(seed)
+    }
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut split_state = seed;
+        Self {
+            seed,
+            state: [(); 4].map(|_| split_mix(&mut split_state)),
+        }
+    }
+
+    /// Restarts the generator from a seed
+    pub fn set_seed(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+
+    /// The seed the generator was started from
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Creates a separate generator from the next number, used to give a system its own
+    /// numbers that do not change when other systems use more
+    pub fn fork(&mut self) -> Self {
+        Self::new(self.next_u64())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let shifted = self.state[1] << 17;
+
+        self.state[2] ^= self.state[0];
+        self.state[3] ^= self.state[1];
+        self.state[1] ^= self.state[2];
+        self.state[0] ^= self.state[3];
+        self.state[2] ^= shifted;
+        self.state[3] = self.state[3].rotate_left(45);
+
+        result
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// A number from 0 up to but not including 1
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// A number in a range
+    pub fn range_f32(&mut self, range: Range<f32>) -> f32 {
+        range.start + (range.end - range.start) * self.next_f32()
+    }
+
+    /// A whole number in a range, the start is returned for empty ranges
+    pub fn range_usize(&mut self, range: Range<usize>) -> usize {
+        if range.is_empty() {
+            return range.start;
+        }
+
+        range.start + (self.next_u64() % (range.end - range.start) as u64) as usize
+    }
+
+    /// Returns true with a probability from 0 to 1
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+
+    /// Picks a random item of a slice
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+
+        items.get(self.range_usize(0..items.len()))
+    }
+
+    /// A random point inside a box
+    pub fn point_in_box(&mut self, min: Vector3<f32>, max: Vector3<f32>) -> Vector3<f32> {
+        Vector3::new(
+            self.range_f32(min.x..max.x),
+            self.range_f32(min.y..max.y),
+            self.range_f32(min.z..max.z),
+        )
+    }
+
+    /// A random direction with a length of 1
+    pub fn unit_vector(&mut self) -> Vector3<f32> {
+        loop {
+            let point =
+                self.point_in_box(Vector3::new(-1.0, -1.0, -1.0), Vector3::new(1.0, 1.0, 1.0));
+            let length = point.magnitude2();
+            if length > 1e-6 && length <= 1.0 {
+                return point.normalize();
+            }
+        }
+    }
+}
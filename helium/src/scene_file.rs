@@ -0,0 +1,465 @@
+use std::{fmt::Write, io};
+
+use cgmath::{Quaternion, Vector3};
+use helium_collisions::collider::{RectangleCollider, StationaryPlaneCollider};
+use helium_renderer::{ColorMaterial, DepthBias, Light, LightKind, LightUnits, Topology};
+
+use crate::helium_compatibility::{Label, Model3d, Transform3d};
+
+// First line of every scene file
+const SCENE_HEADER: &str = "# Helium scene";
+
+/// The components of an entity that are written to a scene file
+#[derive(Default)]
+pub(crate) struct SceneEntity {
+    pub label: Option<Label>,
+    pub model: Option<Model3d>,
+    pub transform: Option<Transform3d>,
+    pub light: Option<Light>,
+    pub rectangle_collider: Option<RectangleCollider>,
+    pub plane_collider: Option<StationaryPlaneCollider>,
+}
+
+impl SceneEntity {
+    // Entities without any saved component are left out of the file
+    pub fn is_empty(&self) -> bool {
+        self.label.is_none()
+            && self.model.is_none()
+            && self.transform.is_none()
+            && self.light.is_none()
+            && self.rectangle_collider.is_none()
+            && self.plane_collider.is_none()
+    }
+}
+
+fn topology_name(topology: Topology) -> &'static str {
+    match topology {
+        Topology::Triangles => "triangles",
+        Topology::Lines => "lines",
+        Topology::LineStrip => "line_strip",
+        Topology::Points => "points",
+    }
+}
+
+fn topology_from_name(name: &str) -> Option<Topology> {
+    match name {
+        "triangles" => Some(Topology::Triangles),
+        "lines" => Some(Topology::Lines),
+        "line_strip" => Some(Topology::LineStrip),
+        "points" => Some(Topology::Points),
+        _ => None,
+    }
+}
+
+// Joins values with spaces, floats are written in their shortest form that reads back
+// the same
+fn join<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Writes text in quotes so line breaks and spaces at the ends are kept
+fn quote(text: &str) -> String {
+    let mut quoted = String::from('"');
+    for character in text.chars() {
+        match character {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            character => quoted.push(character),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Reads text written by `quote`, text without quotes is read as it is like in older files
+//
+// Returns `None` if the quotes are not closed or an escape is unknown
+fn unquote(text: &str) -> Option<String> {
+    let Some(quoted) = text.strip_prefix('"') else {
+        return Some(text.to_string());
+    };
+    let quoted = quoted.strip_suffix('"')?;
+
+    let mut unquoted = String::with_capacity(quoted.len());
+    let mut characters = quoted.chars();
+    while let Some(character) = characters.next() {
+        if character != '\\' {
+            unquoted.push(character);
+            continue;
+        }
+
+        unquoted.push(match characters.next()? {
+            '"' => '"',
+            '\\' => '\\',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            _ => return None,
+        });
+    }
+
+    Some(unquoted)
+}
+
+fn quaternion_values(quaternion: &Quaternion<f32>) -> [f32; 4] {
+    [quaternion.s, quaternion.v.x, quaternion.v.y, quaternion.v.z]
+}
+
+/// Writes entities in the scene format, one statement a line under an `entity` line
+///
+/// # Arguments
+///
+/// * `entities` - The entities to write in the order they are loaded
+///
+/// # Returns
+///
+/// The text of the scene file
+pub(crate) fn write_scene(entities: &[SceneEntity]) -> String {
+    let mut text = String::new();
+    _ = writeln!(text, "{}", SCENE_HEADER);
+
+    for entity in entities {
+        _ = writeln!(text, "\nentity");
+
+        if let Some(Label(label)) = &entity.label {
+            _ = writeln!(text, "label {}", quote(label));
+        }
+
+        if let Some(model) = &entity.model {
+            match model.get_lines() {
+                Some((points, topology)) => {
+                    let values: Vec<f32> = points.iter().flatten().copied().collect();
+                    _ = writeln!(text, "lines {} {}", topology_name(topology), join(&values));
+                }
+                None => _ = writeln!(text, "model {}", quote(model.get_path())),
+            }
+
+            if let Some(material) = model.get_color_material() {
+                let bias = material.depth_bias;
+                _ = writeln!(
+                    text,
+                    "color_material {} {} {} {} {} {} {} {}",
+                    join(&material.albedo),
+                    join(&material.emissive),
+                    material.emissive_intensity,
+                    material.unlit as u8,
+                    material.double_sided as u8,
+                    bias.constant,
+                    bias.slope_scale,
+                    bias.clamp,
+                );
+            }
+        }
+
+        if let Some(transform) = &entity.transform {
+            let position = transform.get_position();
+            let scale = transform.get_scale();
+            _ = writeln!(
+                text,
+                "position {}",
+                join(&[position.x, position.y, position.z])
+            );
+            _ = writeln!(
+                text,
+                "rotation {}",
+                join(&quaternion_values(transform.get_rotation()))
+            );
+            _ = writeln!(text, "scale {}", join(&[scale.x, scale.y, scale.z]));
+        }
+
+        if let Some(light) = &entity.light {
+            let (r, g, b) = light.get_color();
+            let position = light.get_position();
+            let direction = light.get_direction();
+            let kind = match light.get_kind() {
+                LightKind::Point => "point",
+                LightKind::Directional => "directional",
+            };
+            let units = match light.get_units() {
+                LightUnits::Unitless => "unitless",
+                LightUnits::Candela => "candela",
+            };
+            _ = writeln!(
+                text,
+                "light {} {} {} {} {} {}",
+                kind,
+                join(&[r, g, b]),
+                light.get_intensity(),
+                units,
+                join(&[position.x, position.y, position.z]),
+                join(&[direction.x, direction.y, direction.z]),
+            );
+        }
+
+        if let Some(collider) = &entity.rectangle_collider {
+            let size = collider.get_size();
+            let offset = collider.get_offset();
+            _ = writeln!(
+                text,
+                "rectangle_collider {} {} {}",
+                join(&[size.x, size.y, size.z]),
+                join(&[offset.x, offset.y, offset.z]),
+                join(&quaternion_values(collider.get_local_rotation())),
+            );
+        }
+
+        if let Some(collider) = &entity.plane_collider {
+            let origin = collider.origin;
+            _ = writeln!(
+                text,
+                "plane_collider {} {} {} {} {} {}",
+                collider.width,
+                collider.length,
+                join(&[origin.x, origin.y, origin.z]),
+                join(&quaternion_values(collider.get_orientation())),
+                collider.get_thickness(),
+                collider.is_one_way() as u8,
+            );
+        }
+    }
+
+    text
+}
+
+/// Reads the entities of a scene file written by `write_scene`, unknown statements are
+/// skipped so newer files still load
+///
+/// # Arguments
+///
+/// * `text` - The text of the scene file
+///
+/// # Returns
+///
+/// The entities in the file or an error if a statement is invalid
+pub(crate) fn read_scene(text: &str) -> Result<Vec<SceneEntity>, io::Error> {
+    let invalid = |line: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid scene line: {}", line),
+        )
+    };
+
+    let mut entities: Vec<SceneEntity> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (statement, rest) = line.split_once(' ').unwrap_or((line, ""));
+        if statement == "entity" {
+            entities.push(SceneEntity::default());
+            continue;
+        }
+
+        let entity = entities.last_mut().ok_or_else(|| invalid(line))?;
+
+        // The statements that are only numbers
+        let floats = |count: usize| {
+            let values = rest
+                .split_whitespace()
+                .map(|value| value.parse::<f32>().map_err(|_| invalid(line)))
+                .collect::<Result<Vec<_>, _>>()?;
+            if values.len() != count {
+                return Err(invalid(line));
+            }
+            Ok(values)
+        };
+        let transform = || entity.transform.unwrap_or_default();
+
+        match statement {
+            "label" => entity.label = Some(Label(unquote(rest).ok_or_else(|| invalid(line))?)),
+            "model" => {
+                let path = unquote(rest).ok_or_else(|| invalid(line))?;
+                entity.model = Some(Model3d::from_obj(path));
+            }
+            "lines" => {
+                let (topology, points) = rest.split_once(' ').unwrap_or((rest, ""));
+                let topology = topology_from_name(topology).ok_or_else(|| invalid(line))?;
+                let values = points
+                    .split_whitespace()
+                    .map(|value| value.parse::<f32>().map_err(|_| invalid(line)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if values.len() % 3 != 0 {
+                    return Err(invalid(line));
+                }
+
+                let points = values
+                    .chunks(3)
+                    .map(|point| [point[0], point[1], point[2]])
+                    .collect();
+                entity.model = Some(Model3d::from_lines(points, topology));
+            }
+            "color_material" => {
+                let values = floats(13)?;
+                let material = ColorMaterial {
+                    albedo: [values[0], values[1], values[2], values[3]],
+                    emissive: [values[4], values[5], values[6]],
+                    emissive_intensity: values[7],
+                    unlit: values[8] != 0.0,
+                    double_sided: values[9] != 0.0,
+                    depth_bias: DepthBias::new(values[10] as i32, values[11], values[12]),
+                };
+                let model = entity.model.take().ok_or_else(|| invalid(line))?;
+                entity.model = Some(model.with_color_material(material));
+            }
+            "position" => {
+                let values = floats(3)?;
+                let mut new_transform = transform();
+                new_transform.update_position(Vector3::new(values[0], values[1], values[2]));
+                entity.transform = Some(new_transform);
+            }
+            "rotation" => {
+                let values = floats(4)?;
+                let mut new_transform = transform();
+                new_transform
+                    .update_rotation(Quaternion::new(values[0], values[1], values[2], values[3]));
+                entity.transform = Some(new_transform);
+            }
+            "scale" => {
+                let values = floats(3)?;
+                let mut new_transform = transform();
+                new_transform.set_scale(Vector3::new(values[0], values[1], values[2]));
+                entity.transform = Some(new_transform);
+            }
+            "light" => {
+                let parts: Vec<&str> = rest.split_whitespace().collect();
+                if parts.len() != 12 {
+                    return Err(invalid(line));
+                }
+                // The color and intensity, then the position and direction
+                let values = parts[1..5]
+                    .iter()
+                    .chain(&parts[6..12])
+                    .map(|value| value.parse::<f32>().map_err(|_| invalid(line)))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let color = (values[0], values[1], values[2]);
+                let direction = Vector3::new(values[7], values[8], values[9]);
+                let mut light = match parts[0] {
+                    "point" => Light::new(color),
+                    "directional" => Light::directional(color, direction),
+                    _ => return Err(invalid(line)),
+                };
+                let units = match parts[5] {
+                    "unitless" => LightUnits::Unitless,
+                    "candela" => LightUnits::Candela,
+                    _ => return Err(invalid(line)),
+                };
+                light = light.with_intensity(values[3], units);
+                light.update_position(&Vector3::new(values[4], values[5], values[6]));
+                entity.light = Some(light);
+            }
+            "rectangle_collider" => {
+                let values = floats(10)?;
+                let position = entity
+                    .transform
+                    .map_or(Vector3::new(0.0, 0.0, 0.0), |transform| {
+                        *transform.get_position()
+                    });
+                let collider = RectangleCollider::new(values[0], values[1], values[2], position)
+                    .with_offset(Vector3::new(values[3], values[4], values[5]))
+                    .with_rotation(Quaternion::new(values[6], values[7], values[8], values[9]));
+                entity.rectangle_collider = Some(collider);
+            }
+            "plane_collider" => {
+                let values = floats(11)?;
+                let collider = StationaryPlaneCollider::new(
+                    values[0],
+                    values[1],
+                    Vector3::new(values[2], values[3], values[4]),
+                    Quaternion::new(values[5], values[6], values[7], values[8]),
+                )
+                .with_thickness(values[9])
+                .with_one_way(values[10] != 0.0);
+                entity.plane_collider = Some(collider);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(entities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entities() -> Vec<SceneEntity> {
+        let mut transform = Transform3d::new(
+            Vector3::new(1.5, -2.0, 0.25),
+            Quaternion::new(0.5, 0.5, -0.5, 0.5),
+        );
+        transform.set_scale(Vector3::new(2.0, 1.0, 0.5));
+
+        vec![
+            SceneEntity {
+                label: Some(Label(String::from(" Door \"north\"\nline two\\ "))),
+                model: Some(Model3d::from_obj(String::from("assets/big door.obj"))),
+                transform: Some(transform),
+                light: Some(Light::new((1.0, 0.5, 0.25)).with_intensity(3.0, LightUnits::Candela)),
+                rectangle_collider: Some(
+                    RectangleCollider::new(1.0, 2.0, 3.0, Vector3::new(1.5, -2.0, 0.25))
+                        .with_offset(Vector3::new(0.0, 1.0, 0.0)),
+                ),
+                plane_collider: None,
+            },
+            SceneEntity {
+                label: Some(Label(String::from("path"))),
+                model: Some(
+                    Model3d::from_lines(vec![[0.0, 0.0, 0.0], [1.0, 2.0, 3.0]], Topology::Lines)
+                        .with_color_material(ColorMaterial::unlit([1.0, 0.0, 0.0, 0.5])),
+                ),
+                transform: None,
+                light: None,
+                rectangle_collider: None,
+                plane_collider: Some(
+                    StationaryPlaneCollider::new(
+                        4.0,
+                        8.0,
+                        Vector3::new(0.0, -1.0, 0.0),
+                        Quaternion::new(1.0, 0.0, 0.0, 0.0),
+                    )
+                    .with_thickness(0.5)
+                    .with_one_way(true),
+                ),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_scene_round_trip() {
+        let text = write_scene(&entities());
+        let entities = read_scene(&text).unwrap();
+
+        assert_eq!(entities.len(), 2);
+        assert_eq!(
+            entities[0].label.as_ref().map(|label| label.0.as_str()),
+            Some(" Door \"north\"\nline two\\ ")
+        );
+        assert_eq!(
+            entities[0].model.as_ref().map(Model3d::get_path),
+            Some("assets/big door.obj")
+        );
+        assert_eq!(write_scene(&entities), text);
+    }
+
+    #[test]
+    fn test_unquoted_labels() {
+        let entities = read_scene("# Helium scene\n\nentity\nlabel Old door\n").unwrap();
+        assert_eq!(
+            entities[0].label.as_ref().map(|label| label.0.as_str()),
+            Some("Old door")
+        );
+
+        assert!(read_scene("entity\nlabel \"not closed\n").is_err());
+        assert!(read_scene("entity\nlabel \"bad \\q escape\"\n").is_err());
+    }
+}
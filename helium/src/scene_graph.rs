@@ -0,0 +1,231 @@
+use std::fmt::Write;
+
+use helium_ecs::Entity;
+
+/// A renderer resource that a component of an entity is linked to, with its index in the
+/// renderer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RendererHandle {
+    Model(usize),
+    Text(usize),
+    Sprite(usize),
+    Quad(usize),
+    Decal(usize),
+    SdfText(usize),
+}
+
+impl RendererHandle {
+    /// The name of the kind of resource used in the scene graph dumps
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Model(_) => "model",
+            Self::Text(_) => "text",
+            Self::Sprite(_) => "sprite",
+            Self::Quad(_) => "quad",
+            Self::Decal(_) => "decal",
+            Self::SdfText(_) => "sdf_text",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        match *self {
+            Self::Model(index)
+            | Self::Text(index)
+            | Self::Sprite(index)
+            | Self::Quad(index)
+            | Self::Decal(index)
+            | Self::SdfText(index) => index,
+        }
+    }
+}
+
+/// A snapshot of an entity used to inspect the scene
+pub struct SceneGraphNode {
+    pub entity: Entity,
+    pub label: Option<String>,
+    /// Short type names of the components of the entity
+    pub components: Vec<String>,
+    /// The renderer resources the entity uses
+    pub renderer_handles: Vec<RendererHandle>,
+}
+
+// Removes the module path from a type name while keeping the generics
+// e.g. `helium::helium_compatibility::model::Model3d` becomes `Model3d`
+pub fn short_type_name(type_name: &str) -> String {
+    let (path, generics) = match type_name.find('<') {
+        Some(index) => type_name.split_at(index),
+        None => (type_name, ""),
+    };
+
+    let name = path.rsplit("::").next().unwrap_or(path);
+
+    name.to_string() + generics
+}
+
+// Escapes text for a field of a DOT record label, where braces, bars, and angle brackets
+// lay out the fields
+fn escape_dot(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '\\' | '"' | '{' | '}' | '|' | '<' | '>' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            '\n' => escaped.push_str("\\n"),
+            character if character.is_control() => escaped.push(' '),
+            character => escaped.push(character),
+        }
+    }
+
+    escaped
+}
+
+// Escapes text for a JSON string, control characters are written as escapes
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            character if (character as u32) < 0x20 => {
+                _ = write!(escaped, "\\u{:04x}", character as u32);
+            }
+            character => escaped.push(character),
+        }
+    }
+
+    escaped
+}
+
+/// Writes the scene graph in the graphviz DOT format
+/// Every entity is a node connected to the renderer resources it uses
+pub fn to_dot(nodes: &[SceneGraphNode]) -> String {
+    let mut dot = String::from("digraph scene {\n    node [shape=record];\n");
+
+    for node in nodes {
+        let title = match node.label.as_ref() {
+            Some(label) => format!("{} ({})", node.entity, escape_dot(label)),
+            None => node.entity.to_string(),
+        };
+
+        _ = writeln!(
+            dot,
+            "    entity_{} [label=\"{{{}|{}}}\"];",
+            node.entity,
+            title,
+            node.components
+                .iter()
+                .map(|component| escape_dot(component))
+                .collect::<Vec<_>>()
+                .join("\\n"),
+        );
+
+        for handle in node.renderer_handles.iter() {
+            let (kind, index) = (handle.kind(), handle.index());
+            _ = writeln!(
+                dot,
+                "    {kind}_{index} [shape=ellipse, label=\"{kind} {index}\"];\n    entity_{} -> {kind}_{index};",
+                node.entity,
+            );
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// Writes the scene graph as a JSON array of entities
+pub fn to_json(nodes: &[SceneGraphNode]) -> String {
+    let entities = nodes
+        .iter()
+        .map(|node| {
+            let label = match node.label.as_ref() {
+                Some(label) => format!("\"{}\"", escape_json(label)),
+                None => String::from("null"),
+            };
+
+            let components = node
+                .components
+                .iter()
+                .map(|component| format!("\"{}\"", escape_json(component)))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let renderer_handles = node
+                .renderer_handles
+                .iter()
+                .map(|handle| {
+                    format!(
+                        "{{ \"kind\": \"{}\", \"index\": {} }}",
+                        handle.kind(),
+                        handle.index()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            format!(
+                "  {{\n    \"entity\": {},\n    \"label\": {label},\n    \"components\": [{components}],\n    \"renderer_handles\": [{renderer_handles}]\n  }}",
+                node.entity,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",\n");
+
+    format!("[\n{entities}\n]\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(label: &str) -> SceneGraphNode {
+        SceneGraphNode {
+            entity: 3,
+            label: Some(label.to_string()),
+            components: vec![String::from("Option<Transform3d>")],
+            renderer_handles: vec![RendererHandle::Model(1)],
+        }
+    }
+
+    #[test]
+    fn test_short_type_name() {
+        assert_eq!(
+            short_type_name("helium::helium_compatibility::model::Model3d"),
+            "Model3d"
+        );
+        assert_eq!(
+            short_type_name("core::option::Option<helium::Transform3d>"),
+            "Option<helium::Transform3d>"
+        );
+    }
+
+    #[test]
+    fn test_dot_escapes_record_fields() {
+        let dot = to_dot(&[node("a|b {c} \"d\"\nline")]);
+
+        assert!(dot.contains(
+            "entity_3 [label=\"{3 (a\\|b \\{c\\} \\\"d\\\"\\nline)|Option\\<Transform3d\\>}\"];"
+        ));
+        assert!(dot.contains("model_1 [shape=ellipse, label=\"model 1\"];"));
+        assert!(dot.contains("entity_3 -> model_1;"));
+        // Every line is a statement, the label did not break one
+        assert_eq!(dot.lines().count(), 6);
+    }
+
+    #[test]
+    fn test_json_escapes_control_characters() {
+        let json = to_json(&[node("tab\there \"quoted\"\nback\\slash\u{1}")]);
+
+        assert!(json.contains("\"label\": \"tab\\there \\\"quoted\\\"\\nback\\\\slash\\u0001\","));
+        assert!(json.contains("\"components\": [\"Option<Transform3d>\"],"));
+        assert!(json.contains("\"renderer_handles\": [{ \"kind\": \"model\", \"index\": 1 }]"));
+        assert!(!json
+            .chars()
+            .any(|character| character.is_control() && character != '\n'));
+    }
+}
@@ -0,0 +1,168 @@
+use helium_renderer::{
+    Anchor, HeliumState, LinearRgba, OverlayQuad, OverlayText, Srgba, TextStyle, UiLayout,
+};
+
+// Size of the progress bar as a fraction of the screen width and in pixels high
+const BAR_WIDTH: f32 = 0.5;
+const BAR_HEIGHT: f32 = 12.0;
+
+// The color of the part of the progress bar that is not filled yet
+fn faded(color: [f32; 4]) -> [f32; 4] {
+    [color[0], color[1], color[2], color[3] * 0.25]
+}
+
+/// What is drawn over the screen while a scene loads
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadingScreen {
+    pub text: String,
+    pub style: TextStyle,
+    /// Color of the quad that covers the screen
+    pub background: [f32; 4],
+    /// Color of the progress bar, `None` draws no progress bar
+    pub progress_bar: Option<[f32; 4]>,
+}
+
+impl Default for LoadingScreen {
+    fn default() -> Self {
+        Self {
+            text: String::from("Loading"),
+            style: TextStyle::default().with_size(32.0),
+            background: LinearRgba::from(Srgba::rgb(0.05, 0.05, 0.05)).to_array(),
+            progress_bar: Some(LinearRgba::from(Srgba::WHITE).to_array()),
+        }
+    }
+}
+
+impl LoadingScreen {
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = text.to_string();
+        self
+    }
+
+    pub fn with_style(mut self, style: TextStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_background<C>(mut self, color: C) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        self.background = color.into().to_array();
+        self
+    }
+
+    pub fn with_progress_bar<C>(mut self, color: Option<C>) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        self.progress_bar = color.map(|color| color.into().to_array());
+        self
+    }
+}
+
+/// The event sent every update while a scene loads
+#[derive(Clone, Debug, PartialEq)]
+pub struct SceneLoadProgress {
+    pub scene: String,
+    /// Number of objects of the scene that finished loading
+    pub loaded: usize,
+    pub total: usize,
+}
+
+impl SceneLoadProgress {
+    /// How much of the scene has loaded from 0 to 1
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+
+        self.loaded as f32 / self.total as f32
+    }
+}
+
+/// The event sent when every object of a scene has loaded and the loading screen is gone
+#[derive(Clone, Debug, PartialEq)]
+pub struct SceneLoaded {
+    pub scene: String,
+}
+
+// A scene that is loading with the overlay drawn over it
+pub(crate) struct SceneLoad {
+    scene: String,
+    screen: LoadingScreen,
+    text: usize,
+    background: usize,
+    // The empty and the filled part of the progress bar
+    bar: Option<(usize, usize)>,
+}
+
+impl SceneLoad {
+    /// Draws the loading screen over the scene, it is fit to the screen by `update`
+    pub fn new(scene: &str, screen: LoadingScreen, renderer: &mut HeliumState) -> Self {
+        let size = (1.0, 1.0);
+        let background =
+            renderer.create_quad(OverlayQuad::new((0.0, 0.0), size, screen.background));
+        let bar = screen.progress_bar.map(|color| {
+            (
+                renderer.create_quad(OverlayQuad::new((0.0, 0.0), size, faded(color))),
+                renderer.create_quad(OverlayQuad::new((0.0, 0.0), size, color)),
+            )
+        });
+        let text = renderer.create_text(
+            OverlayText::new(screen.text.clone(), (0.0, 0.0), screen.style).with_layout(Some(
+                UiLayout::new(Anchor::Center).with_offset_pixels((0.0, -2.0 * BAR_HEIGHT)),
+            )),
+        );
+
+        Self {
+            scene: scene.to_string(),
+            screen,
+            text,
+            background,
+            bar,
+        }
+    }
+
+    pub fn get_scene(&self) -> &str {
+        &self.scene
+    }
+
+    /// Fits the loading screen to the screen and fills the progress bar
+    pub fn update(&self, progress: &SceneLoadProgress, renderer: &mut HeliumState) {
+        let (width, height) = (renderer.config.width as f32, renderer.config.height as f32);
+
+        renderer.update_quad(
+            self.background,
+            OverlayQuad::new((0.0, 0.0), (width, height), self.screen.background),
+        );
+
+        if let (Some((empty, filled)), Some(color)) = (self.bar, self.screen.progress_bar) {
+            let bar_width = width * BAR_WIDTH;
+            let position = ((width - bar_width) / 2.0, height / 2.0);
+
+            renderer.update_quad(
+                empty,
+                OverlayQuad::new(position, (bar_width, BAR_HEIGHT), faded(color)),
+            );
+            renderer.update_quad(
+                filled,
+                OverlayQuad::new(
+                    position,
+                    (bar_width * progress.fraction(), BAR_HEIGHT),
+                    color,
+                ),
+            );
+        }
+    }
+
+    /// Removes the loading screen
+    pub fn finish(self, renderer: &mut HeliumState) {
+        renderer.remove_text(self.text);
+        renderer.remove_quad(self.background);
+        if let Some((empty, filled)) = self.bar {
+            renderer.remove_quad(empty);
+            renderer.remove_quad(filled);
+        }
+    }
+}
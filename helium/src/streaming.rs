@@ -0,0 +1,183 @@
+use cgmath::{InnerSpace, Vector3};
+use helium_collisions::collider::{RectangleCollider, StationaryPlaneCollider};
+use helium_ecs::Entity;
+
+use crate::helium_compatibility::{Label, Model3d, Transform3d};
+
+// Default distances used for streaming chunks in and out
+const DEFAULT_LOAD_DISTANCE: f32 = 100.0;
+const DEFAULT_HYSTERESIS_MARGIN: f32 = 20.0;
+
+/// An object that is spawned when its chunk is loaded
+#[derive(Clone)]
+pub struct ChunkObject {
+    pub model: Model3d,
+    pub transform: Transform3d,
+    pub label: Option<Label>,
+    pub rectangle_collider: Option<RectangleCollider>,
+    pub plane_collider: Option<StationaryPlaneCollider>,
+}
+
+impl ChunkObject {
+    pub fn new(model: Model3d, transform: Transform3d) -> Self {
+        Self {
+            model,
+            transform,
+            label: None,
+            rectangle_collider: None,
+            plane_collider: None,
+        }
+    }
+
+    pub fn with_label(mut self, label: Label) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    pub fn with_rectangle_collider(mut self, collider: RectangleCollider) -> Self {
+        self.rectangle_collider = Some(collider);
+        self
+    }
+
+    pub fn with_plane_collider(mut self, collider: StationaryPlaneCollider) -> Self {
+        self.plane_collider = Some(collider);
+        self
+    }
+}
+
+/// A group of objects in a region of the world that are loaded and unloaded together
+#[derive(Clone)]
+pub struct SceneChunk {
+    /// Corner of the region with the smallest coordinates
+    pub min: Vector3<f32>,
+    /// Corner of the region with the largest coordinates
+    pub max: Vector3<f32>,
+    pub objects: Vec<ChunkObject>,
+}
+
+impl SceneChunk {
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        Self {
+            min,
+            max,
+            objects: Vec::new(),
+        }
+    }
+
+    pub fn with_object(mut self, object: ChunkObject) -> Self {
+        self.objects.push(object);
+        self
+    }
+
+    /// Distance from a point to the region of the chunk, 0 if the point is inside of it
+    pub fn distance_to(&self, point: Vector3<f32>) -> f32 {
+        let closest = Vector3::new(
+            point.x.clamp(self.min.x, self.max.x),
+            point.y.clamp(self.min.y, self.max.y),
+            point.z.clamp(self.min.z, self.max.z),
+        );
+
+        (point - closest).magnitude()
+    }
+}
+
+/// What the streamer should do with a chunk
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChunkAction {
+    Load,
+    Unload,
+    Keep,
+}
+
+// A chunk with the entities that were spawned for it while it is loaded
+struct StreamedChunk {
+    chunk: SceneChunk,
+    entities: Option<Vec<Entity>>,
+}
+
+/// Loads and unloads scene chunks around the camera
+pub struct WorldStreamer {
+    chunks: Vec<StreamedChunk>,
+    /// Chunks closer than this to the camera are loaded
+    load_distance: f32,
+    /// Extra distance past the load distance before a chunk is unloaded so chunks on the
+    /// border do not load and unload every frame
+    hysteresis_margin: f32,
+}
+
+impl Default for WorldStreamer {
+    fn default() -> Self {
+        Self {
+            chunks: Vec::new(),
+            load_distance: DEFAULT_LOAD_DISTANCE,
+            hysteresis_margin: DEFAULT_HYSTERESIS_MARGIN,
+        }
+    }
+}
+
+impl WorldStreamer {
+    /// Adds a chunk to be streamed in when the camera gets close
+    ///
+    /// # Returns
+    ///
+    /// The index of the chunk
+    pub fn add_chunk(&mut self, chunk: SceneChunk) -> usize {
+        self.chunks.push(StreamedChunk {
+            chunk,
+            entities: None,
+        });
+        self.chunks.len() - 1
+    }
+
+    pub fn set_distances(&mut self, load_distance: f32, hysteresis_margin: f32) {
+        self.load_distance = load_distance;
+        self.hysteresis_margin = hysteresis_margin;
+    }
+
+    pub fn get_distances(&self) -> (f32, f32) {
+        (self.load_distance, self.hysteresis_margin)
+    }
+
+    pub fn is_chunk_loaded(&self, chunk_index: usize) -> bool {
+        self.chunks
+            .get(chunk_index)
+            .is_some_and(|chunk| chunk.entities.is_some())
+    }
+
+    pub fn get_num_chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Decides what to do with a chunk for the current camera position
+    pub fn action(&self, chunk_index: usize, camera_position: Vector3<f32>) -> ChunkAction {
+        let streamed = &self.chunks[chunk_index];
+        let distance = streamed.chunk.distance_to(camera_position);
+
+        match streamed.entities {
+            None if distance <= self.load_distance => ChunkAction::Load,
+            Some(_) if distance > self.load_distance + self.hysteresis_margin => {
+                ChunkAction::Unload
+            }
+            _ => ChunkAction::Keep,
+        }
+    }
+
+    /// Used internally to get the objects to spawn for a chunk
+    pub fn get_chunk(&self, chunk_index: usize) -> &SceneChunk {
+        &self.chunks[chunk_index].chunk
+    }
+
+    /// Used internally to mark a chunk as loaded with the entities spawned for it
+    pub fn set_loaded(&mut self, chunk_index: usize, entities: Vec<Entity>) {
+        self.chunks[chunk_index].entities = Some(entities);
+    }
+
+    /// Used internally to mark a chunk as unloaded
+    ///
+    /// # Returns
+    ///
+    /// The entities that were spawned for the chunk
+    pub fn take_entities(&mut self, chunk_index: usize) -> Vec<Entity> {
+        self.chunks[chunk_index].entities.take().unwrap_or_default()
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    collections::HashSet,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::mpsc::{self, Receiver, Sender},
+};
+
+use log::error;
+use smol::future::FutureExt;
+
+use crate::HeliumManager;
+
+/// A task running in the background, the handle is given back with its output
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TaskHandle(u64);
+
+/// The event sent when a task spawned with `spawn_task` finishes
+#[derive(Debug)]
+pub struct TaskComplete<T> {
+    pub handle: TaskHandle,
+    pub output: T,
+}
+
+// Work to do on the update thread when a task finishes
+type Completion = Box<dyn FnOnce(&mut HeliumManager) + Send>;
+
+/// Runs futures on the background threads of the executor and hands their outputs back
+/// to the update thread, so pathfinding, generation, and io do not block the updates
+pub(crate) struct TaskPool {
+    next_handle: u64,
+    running: HashSet<TaskHandle>,
+    sender: Sender<(TaskHandle, Option<Completion>)>,
+    receiver: Receiver<(TaskHandle, Option<Completion>)>,
+}
+
+impl Default for TaskPool {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            next_handle: 0,
+            running: HashSet::new(),
+            sender,
+            receiver,
+        }
+    }
+}
+
+impl TaskPool {
+    /// Starts a future in the background
+    ///
+    /// # Arguments
+    ///
+    /// * `future` - The work to do
+    /// * `on_complete` - Called on the update thread with the output of the future
+    ///
+    /// # Returns
+    ///
+    /// The `TaskHandle` of the task
+    pub fn spawn<F, C>(&mut self, future: F, on_complete: C) -> TaskHandle
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+        C: FnOnce(&mut HeliumManager, TaskHandle, F::Output) + Send + 'static,
+    {
+        let handle = TaskHandle(self.next_handle);
+        self.next_handle += 1;
+        self.running.insert(handle);
+
+        let sender = self.sender.clone();
+        smol::spawn(async move {
+            // A panicking task is reported instead of staying in the running tasks forever
+            let completion = match AssertUnwindSafe(future).catch_unwind().await {
+                Ok(output) => Some(Box::new(move |manager: &mut HeliumManager| {
+                    on_complete(manager, handle, output)
+                }) as Completion),
+                Err(_) => None,
+            };
+
+            _ = sender.send((handle, completion));
+        })
+        .detach();
+
+        handle
+    }
+
+    pub fn is_running(&self, handle: TaskHandle) -> bool {
+        self.running.contains(&handle)
+    }
+
+    /// Number of tasks that have not finished
+    pub fn len(&self) -> usize {
+        self.running.len()
+    }
+
+    /// Takes the work of the tasks that finished since the last update
+    pub fn take_completed(&mut self) -> Vec<Completion> {
+        let mut completed = Vec::new();
+        while let Ok((handle, completion)) = self.receiver.try_recv() {
+            self.running.remove(&handle);
+            match completion {
+                Some(completion) => completed.push(completion),
+                None => error!("Task {} panicked", handle.0),
+            }
+        }
+
+        completed
+    }
+}
@@ -0,0 +1,139 @@
+use std::f32::consts::PI;
+
+use cgmath::Vector3;
+use helium_renderer::ProceduralSky;
+
+// Hours in a day, the time wraps around past it
+const HOURS_PER_DAY: f32 = 24.0;
+
+/// The time of day that moves the sun across the procedural sky and the directional sun
+/// light with it. The sun rises in the east along +x and stands in the south along +z at
+/// noon north of the equator
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeOfDay {
+    hours: f32,
+    speed: f32,
+    latitude: f32,
+    declination: f32,
+    sun_intensity: f32,
+    sky: ProceduralSky,
+}
+
+impl Default for TimeOfDay {
+    /// Noon on an equinox at 45 degrees north with the clock stopped
+    fn default() -> Self {
+        Self {
+            hours: 12.0,
+            speed: 0.0,
+            latitude: 45.0,
+            declination: 0.0,
+            sun_intensity: 1.0,
+            sky: ProceduralSky::default(),
+        }
+    }
+}
+
+impl TimeOfDay {
+    /// Sets the hour of the day from 0 to 24, 12 is noon
+    pub fn with_hours(mut self, hours: f32) -> Self {
+        self.set_hours(hours);
+        self
+    }
+
+    /// Sets how many hours of the day pass every second, 0 stops the clock
+    pub fn with_speed(mut self, hours_per_second: f32) -> Self {
+        self.speed = hours_per_second;
+        self
+    }
+
+    /// Sets the latitude in degrees, which sets how high the sun gets at noon
+    pub fn with_latitude(mut self, degrees: f32) -> Self {
+        self.latitude = degrees.clamp(-90.0, 90.0);
+        self
+    }
+
+    /// Sets the season as the angle of the sun above the equator in degrees, from -23.44 in
+    /// the northern winter to 23.44 in the northern summer
+    pub fn with_declination(mut self, degrees: f32) -> Self {
+        self.declination = degrees.clamp(-90.0, 90.0);
+        self
+    }
+
+    /// Sets the intensity of the sun light at noon, it fades out as the sun sets
+    pub fn with_sun_intensity(mut self, intensity: f32) -> Self {
+        self.sun_intensity = intensity.max(0.0);
+        self
+    }
+
+    /// Sets how the sky looks, the direction of its sun is set from the time
+    pub fn with_sky(mut self, sky: ProceduralSky) -> Self {
+        self.sky = sky;
+        self
+    }
+
+    pub fn set_hours(&mut self, hours: f32) {
+        self.hours = hours.rem_euclid(HOURS_PER_DAY);
+    }
+
+    pub fn get_hours(&self) -> f32 {
+        self.hours
+    }
+
+    pub fn set_speed(&mut self, hours_per_second: f32) {
+        self.speed = hours_per_second;
+    }
+
+    pub fn get_speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn get_latitude(&self) -> f32 {
+        self.latitude
+    }
+
+    pub fn get_declination(&self) -> f32 {
+        self.declination
+    }
+
+    pub fn get_sun_intensity(&self) -> f32 {
+        self.sun_intensity
+    }
+
+    /// Moves the clock forward by the speed
+    ///
+    /// # Arguments
+    ///
+    /// * `delta_time` - The seconds that passed
+    pub fn advance(&mut self, delta_time: f32) {
+        if self.speed != 0.0 {
+            self.set_hours(self.hours + self.speed * delta_time);
+        }
+    }
+
+    /// The direction from the ground towards the sun, below the horizon at night
+    pub fn get_sun_direction(&self) -> Vector3<f32> {
+        // The angle of the sun past noon around the axis of the earth
+        let hour_angle = (self.hours - HOURS_PER_DAY / 2.0) / HOURS_PER_DAY * 2.0 * PI;
+        let latitude = self.latitude.to_radians();
+        let declination = self.declination.to_radians();
+
+        // East, north, and up from the ground
+        let east = -declination.cos() * hour_angle.sin();
+        let north = -declination.cos() * hour_angle.cos() * latitude.sin()
+            + declination.sin() * latitude.cos();
+        let up = declination.cos() * hour_angle.cos() * latitude.cos()
+            + declination.sin() * latitude.sin();
+
+        Vector3::new(east, up, -north)
+    }
+
+    /// The sky with its sun at the position of the time
+    pub fn get_sky(&self) -> ProceduralSky {
+        self.sky.with_sun_direction(self.get_sun_direction())
+    }
+
+    /// Whether the sun is below the horizon
+    pub fn is_night(&self) -> bool {
+        self.get_sun_direction().y < 0.0
+    }
+}
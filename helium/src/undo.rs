@@ -0,0 +1,225 @@
+use std::collections::VecDeque;
+
+use helium_ecs::{Entity, WorldId};
+
+use crate::{helium_compatibility::Transform3d, helium_manager::HeliumManager};
+
+// Number of edits that are kept when the depth is not set
+const DEFAULT_UNDO_DEPTH: usize = 100;
+
+/// An edit of the scene that can be undone and done again, like moving an entity or
+/// changing a component
+pub trait EditCommand {
+    /// Makes the edit, called when it is first made and when it is redone
+    fn apply(&mut self, manager: &mut HeliumManager);
+
+    /// Puts the scene back to how it was before the edit
+    fn revert(&mut self, manager: &mut HeliumManager);
+
+    /// Called when the edit leaves the history and can not be undone or redone again
+    fn discard(&mut self, _manager: &mut HeliumManager) {}
+
+    /// A short name of the edit for menus, like "Transform" or "Despawn"
+    fn name(&self) -> &str {
+        "Edit"
+    }
+}
+
+/// Moves, turns, or scales an entity
+pub(crate) struct TransformEdit {
+    pub entity: Entity,
+    pub before: Transform3d,
+    pub after: Transform3d,
+}
+
+impl TransformEdit {
+    fn set(manager: &mut HeliumManager, entity: Entity, transform: Transform3d) {
+        if let Some(current) = manager
+            .query_mut::<Transform3d>()
+            .as_mut()
+            .and_then(|transforms| transforms.get_mut(&entity))
+        {
+            *current = transform;
+            // Marks the transform so the renderer is sent the change
+            current.set_scale(*transform.get_scale());
+        }
+    }
+}
+
+impl EditCommand for TransformEdit {
+    fn apply(&mut self, manager: &mut HeliumManager) {
+        Self::set(manager, self.entity, self.after);
+    }
+
+    fn revert(&mut self, manager: &mut HeliumManager) {
+        Self::set(manager, self.entity, self.before);
+    }
+
+    fn name(&self) -> &str {
+        "Transform"
+    }
+}
+
+/// Sets, adds, or removes a component of an entity
+pub(crate) struct ComponentEdit<T> {
+    pub entity: Entity,
+    pub before: Option<T>,
+    pub after: Option<T>,
+}
+
+impl<T: Clone + 'static> ComponentEdit<T> {
+    fn set(manager: &mut HeliumManager, entity: Entity, component: Option<T>) {
+        match component {
+            Some(component) => {
+                manager.add_component(entity, component);
+            }
+            None => {
+                let has_component = manager
+                    .query::<T>()
+                    .is_some_and(|components| components.contains_key(&entity));
+                if has_component {
+                    manager.ecs_instance.remove_component::<T>(entity);
+                }
+            }
+        }
+    }
+}
+
+impl<T: Clone + 'static> EditCommand for ComponentEdit<T> {
+    fn apply(&mut self, manager: &mut HeliumManager) {
+        Self::set(manager, self.entity, self.after.clone());
+    }
+
+    fn revert(&mut self, manager: &mut HeliumManager) {
+        Self::set(manager, self.entity, self.before.clone());
+    }
+
+    fn name(&self) -> &str {
+        "Component"
+    }
+}
+
+/// Spawns or despawns an entity. Despawned entities are kept in a hidden world so they
+/// can come back with every component, they are removed for good once the edit can not
+/// be undone anymore
+pub(crate) struct SpawnEdit {
+    pub entity: Entity,
+    // The world the entity lives in while it is spawned
+    pub world: WorldId,
+    // Whether the edit spawns the entity or despawns it
+    pub spawn: bool,
+}
+
+impl SpawnEdit {
+    fn set_spawned(&self, manager: &mut HeliumManager, spawned: bool) {
+        if spawned {
+            manager.move_entity_to_world(self.entity, self.world);
+        } else {
+            let removed = manager.get_removed_world();
+            manager.move_entity_to_world(self.entity, removed);
+            if manager.get_selected() == Some(self.entity) {
+                manager.set_selected(None);
+            }
+        }
+    }
+}
+
+impl EditCommand for SpawnEdit {
+    fn apply(&mut self, manager: &mut HeliumManager) {
+        self.set_spawned(manager, self.spawn);
+    }
+
+    fn revert(&mut self, manager: &mut HeliumManager) {
+        self.set_spawned(manager, !self.spawn);
+    }
+
+    fn discard(&mut self, manager: &mut HeliumManager) {
+        manager.despawn_removed(self.entity);
+    }
+
+    fn name(&self) -> &str {
+        if self.spawn {
+            "Spawn"
+        } else {
+            "Despawn"
+        }
+    }
+}
+
+/// The edits that can be undone and the undone edits that can be redone
+pub(crate) struct UndoHistory {
+    undo: VecDeque<Box<dyn EditCommand>>,
+    redo: Vec<Box<dyn EditCommand>>,
+    depth: usize,
+}
+
+impl Default for UndoHistory {
+    fn default() -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            depth: DEFAULT_UNDO_DEPTH,
+        }
+    }
+}
+
+impl UndoHistory {
+    /// Adds an edit that was made
+    ///
+    /// # Returns
+    ///
+    /// The edits that left the history and have to be discarded
+    pub fn push(&mut self, command: Box<dyn EditCommand>) -> Vec<Box<dyn EditCommand>> {
+        let mut discarded: Vec<_> = self.redo.drain(..).collect();
+        self.undo.push_back(command);
+        discarded.extend(self.trim());
+        discarded
+    }
+
+    pub fn take_undo(&mut self) -> Option<Box<dyn EditCommand>> {
+        self.undo.pop_back()
+    }
+
+    pub fn take_redo(&mut self) -> Option<Box<dyn EditCommand>> {
+        self.redo.pop()
+    }
+
+    /// Puts back an edit that was undone
+    pub fn push_undone(&mut self, command: Box<dyn EditCommand>) {
+        self.redo.push(command);
+    }
+
+    /// Puts back an edit that was redone, it does not clear the edits left to redo
+    pub fn push_redone(&mut self, command: Box<dyn EditCommand>) -> Vec<Box<dyn EditCommand>> {
+        self.undo.push_back(command);
+        self.trim()
+    }
+
+    pub fn get_undo_name(&self) -> Option<&str> {
+        self.undo.back().map(|command| command.name())
+    }
+
+    pub fn get_redo_name(&self) -> Option<&str> {
+        self.redo.last().map(|command| command.name())
+    }
+
+    pub fn set_depth(&mut self, depth: usize) -> Vec<Box<dyn EditCommand>> {
+        self.depth = depth;
+        self.trim()
+    }
+
+    pub fn get_depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Takes every edit out of the history
+    pub fn clear(&mut self) -> Vec<Box<dyn EditCommand>> {
+        self.undo.drain(..).chain(self.redo.drain(..)).collect()
+    }
+
+    // Takes the oldest edits past the depth
+    fn trim(&mut self) -> Vec<Box<dyn EditCommand>> {
+        let excess = self.undo.len().saturating_sub(self.depth);
+        self.undo.drain(..excess).collect()
+    }
+}
@@ -1,4 +1,4 @@
-use cgmath::{InnerSpace, Quaternion, Rotation, Vector3, Zero};
+use cgmath::{InnerSpace, Matrix3, One, Quaternion, Rotation, Vector3, Zero};
 use std::{any::Any, ops::Range};
 
 const PLANE_LOCAL_NORMAL: Vector3<f32> = Vector3 {
@@ -33,7 +33,17 @@ pub trait Collider {
     fn as_any(&self) -> &dyn Any;
 }
 
-#[derive(PartialEq, Debug)]
+/// Where a ray cast against a collider hit it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    /// Distance along the ray from its origin
+    pub distance: f32,
+    pub point: Vector3<f32>,
+    /// Normal of the surface that was hit, facing back towards the ray
+    pub normal: Vector3<f32>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
 pub struct RectangleCollider {
     // x
     width: f32,
@@ -44,6 +54,15 @@ pub struct RectangleCollider {
     // origin
     origin: Vector3<f32>,
 
+    // Position and rotation of the entity the collider is attached to
+    position: Vector3<f32>,
+    rotation: Quaternion<f32>,
+    // Placement of the collider relative to the entity
+    offset: Vector3<f32>,
+    local_rotation: Quaternion<f32>,
+
+    // Half of the size of the rotated collider along each world axis
+    extents: Vector3<f32>,
     vertices: [Vector3<f32>; 8],
 }
 
@@ -53,63 +72,245 @@ impl RectangleCollider {
         height: f32,
         length: f32,
         origin: &Vector3<f32>,
+        rotation: &Quaternion<f32>,
     ) -> [Vector3<f32>; 8] {
         let (width_2, height_2, length_2) = (width / 2.0, height / 2.0, length / 2.0);
 
         [
-            Vector3 {
-                x: origin.x + width_2,
-                y: origin.y + height_2,
-                z: origin.z + length_2,
-            },
-            Vector3 {
-                x: origin.x + width_2,
-                y: origin.y + height_2,
-                z: origin.z - length_2,
-            },
-            Vector3 {
-                x: origin.x - width_2,
-                y: origin.y + height_2,
-                z: origin.z + length_2,
-            },
-            Vector3 {
-                x: origin.x - width_2,
-                y: origin.y + height_2,
-                z: origin.z - length_2,
-            },
-            Vector3 {
-                x: origin.x + width_2,
-                y: origin.y - height_2,
-                z: origin.z + length_2,
-            },
-            Vector3 {
-                x: origin.x + width_2,
-                y: origin.y - height_2,
-                z: origin.z - length_2,
-            },
-            Vector3 {
-                x: origin.x - width_2,
-                y: origin.y - height_2,
-                z: origin.z + length_2,
-            },
-            Vector3 {
-                x: origin.x - width_2,
-                y: origin.y - height_2,
-                z: origin.z - length_2,
-            },
+            (width_2, height_2, length_2),
+            (width_2, height_2, -length_2),
+            (-width_2, height_2, length_2),
+            (-width_2, height_2, -length_2),
+            (width_2, -height_2, length_2),
+            (width_2, -height_2, -length_2),
+            (-width_2, -height_2, length_2),
+            (-width_2, -height_2, -length_2),
         ]
+        .map(|(x, y, z)| origin + rotation.rotate_vector(Vector3 { x, y, z }))
+    }
+
+    // Each world axis of the rotated collider is the sum of the rotated half sizes on it
+    fn compute_extents(
+        width: f32,
+        height: f32,
+        length: f32,
+        rotation: &Quaternion<f32>,
+    ) -> Vector3<f32> {
+        let rotation = Matrix3::from(*rotation);
+        let half = Vector3::new(width, height, length) / 2.0;
+        let extent = |row: usize| {
+            rotation.x[row].abs() * half.x
+                + rotation.y[row].abs() * half.y
+                + rotation.z[row].abs() * half.z
+        };
+
+        Vector3::new(extent(0), extent(1), extent(2))
+    }
+
+    // Recomputes the world placement from the entity and the local offset
+    fn update_placement(&mut self) {
+        let rotation = self.rotation * self.local_rotation;
+        self.origin = self.position + self.rotation.rotate_vector(self.offset);
+        self.extents = Self::compute_extents(self.width, self.height, self.length, &rotation);
+        self.vertices = Self::compute_vertices(
+            self.width,
+            self.height,
+            self.length,
+            &self.origin,
+            &rotation,
+        );
+    }
+
+    // Moves the entity position to match the origin after the origin was moved directly
+    // so the offset from the entity is kept
+    fn sync_position(&mut self) {
+        self.position = self.origin - self.rotation.rotate_vector(self.offset);
+        self.update_placement();
     }
 
     pub fn new(width: f32, height: f32, length: f32, origin: Vector3<f32>) -> Self {
-        let vertices = Self::compute_vertices(width, height, length, &origin);
-        Self {
+        let mut collider = Self {
             width,
             height,
             length,
             origin,
-            vertices,
+            position: origin,
+            rotation: Quaternion::one(),
+            offset: Vector3::zero(),
+            local_rotation: Quaternion::one(),
+            extents: Vector3::zero(),
+            vertices: [Vector3::zero(); 8],
+        };
+
+        collider.update_placement();
+        collider
+    }
+
+    /// Places the collider relative to the entity it is attached to, the offset is
+    /// rotated with the entity
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Position of the center of the collider relative to the entity
+    pub fn with_offset(mut self, offset: Vector3<f32>) -> Self {
+        self.set_offset(offset);
+        self
+    }
+
+    /// Rotates the collider relative to the entity it is attached to
+    ///
+    /// # Arguments
+    ///
+    /// * `rotation` - Rotation of the collider relative to the entity
+    pub fn with_rotation(mut self, rotation: Quaternion<f32>) -> Self {
+        self.set_local_rotation(rotation);
+        self
+    }
+
+    pub fn set_offset(&mut self, offset: Vector3<f32>) {
+        self.offset = offset;
+        self.update_placement();
+    }
+
+    pub fn get_offset(&self) -> &Vector3<f32> {
+        &self.offset
+    }
+
+    /// The width, height, and length of the collider along its x, y, and z axes
+    pub fn get_size(&self) -> Vector3<f32> {
+        Vector3::new(self.width, self.height, self.length)
+    }
+
+    pub fn set_local_rotation(&mut self, rotation: Quaternion<f32>) {
+        self.local_rotation = rotation.normalize();
+        self.update_placement();
+    }
+
+    pub fn get_local_rotation(&self) -> &Quaternion<f32> {
+        &self.local_rotation
+    }
+
+    /// Moves the collider with the entity it is attached to, the local offset and
+    /// rotation are applied on top of the transform
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - Position of the entity
+    /// * `rotation` - Rotation of the entity
+    pub fn set_transform(&mut self, position: &Vector3<f32>, rotation: &Quaternion<f32>) {
+        self.position = *position;
+        self.rotation = rotation.normalize();
+        self.update_placement();
+    }
+
+    /// Position of the entity the collider is attached to, this is the origin without
+    /// the offset
+    pub fn get_position(&self) -> &Vector3<f32> {
+        &self.position
+    }
+
+    /// The rotation of the collider in the world
+    pub fn get_rotation(&self) -> Quaternion<f32> {
+        self.rotation * self.local_rotation
+    }
+
+    /// Checks if the boxes around two colliders on the world axes overlap
+    pub fn overlaps(&self, other: &RectangleCollider) -> bool {
+        let distance = self.origin - other.origin;
+        let reach = self.extents + other.extents;
+
+        distance.x.abs() < reach.x && distance.y.abs() < reach.y && distance.z.abs() < reach.z
+    }
+
+    /// Pushes the collider out of a plane along its normal to the closest side, one way
+    /// planes always push to the side they face
+    ///
+    /// # Returns
+    ///
+    /// The direction the collider was pushed in
+    pub fn snap_to_plane(&mut self, plane: &StationaryPlaneCollider) -> Vector3<f32> {
+        let normal = plane.local_normal;
+        let distances = self
+            .vertices
+            .map(|vertex| normal.dot(vertex - plane.origin));
+        let min_distance = distances.iter().copied().fold(f32::INFINITY, f32::min);
+        let max_distance = distances.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+
+        let center_distance = normal.dot(self.origin - plane.origin);
+        if plane.one_way || center_distance >= -plane.thickness / 2.0 {
+            self.origin += normal * (-min_distance).max(0.0);
+            self.sync_position();
+            normal
+        } else {
+            self.origin -= normal * (max_distance + plane.thickness).max(0.0);
+            self.sync_position();
+            -normal
         }
     }
+
+    /// Casts a ray against the rotated box of the collider, rays starting inside of the
+    /// box do not hit it
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - Where the ray starts in world space
+    /// * `direction` - The direction of the ray, it does not have to be normalized
+    /// * `max_distance` - How far the ray reaches
+    ///
+    /// # Returns
+    ///
+    /// The closest hit on the surface of the box if there is one
+    pub fn raycast(
+        &self,
+        origin: Vector3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<RayHit> {
+        if direction.magnitude2() <= f32::EPSILON {
+            return None;
+        }
+        let direction = direction.normalize();
+
+        // The ray is tested against the slabs of the box in its own space
+        let rotation = self.get_rotation();
+        let inverse = rotation.invert();
+        let local_origin = inverse.rotate_vector(origin - self.origin);
+        let local_direction = inverse.rotate_vector(direction);
+        let half = self.get_size() / 2.0;
+
+        let mut enter = f32::NEG_INFINITY;
+        let mut exit = f32::INFINITY;
+        let mut enter_normal = Vector3::zero();
+        for axis in 0..3 {
+            if local_direction[axis].abs() <= f32::EPSILON {
+                // Parallel rays miss unless they are between the two sides
+                if local_origin[axis].abs() > half[axis] {
+                    return None;
+                }
+                continue;
+            }
+
+            let near = (-half[axis] - local_origin[axis]) / local_direction[axis];
+            let far = (half[axis] - local_origin[axis]) / local_direction[axis];
+            let (near, far) = (near.min(far), near.max(far));
+            if near > enter {
+                enter = near;
+                enter_normal = Vector3::zero();
+                enter_normal[axis] = -local_direction[axis].signum();
+            }
+            exit = exit.min(far);
+        }
+
+        if enter > exit || enter < 0.0 || enter > max_distance {
+            return None;
+        }
+
+        Some(RayHit {
+            distance: enter,
+            point: origin + direction * enter,
+            normal: rotation.rotate_vector(enter_normal),
+        })
+    }
 }
 
 impl Collider for RectangleCollider {
@@ -125,16 +326,16 @@ impl Collider for RectangleCollider {
                 distances.push(plane.local_normal.dot(verticie - plane.origin));
             }
 
-            // This calculates if the rectangular collider is intersecting the plane
-            let mut sum: f32 = 0.0;
-            let mut abs_sum: f32 = 0.0;
-            for distance in distances {
-                sum += distance;
-                abs_sum += f32::abs(distance);
-            }
-            sum = f32::abs(sum);
+            // This calculates if the rectangular collider is intersecting the plane, thick
+            // planes are a slab below the surface so fast colliders do not pass through
+            let min_distance = distances.iter().copied().fold(f32::INFINITY, f32::min);
+            let max_distance = distances.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            let intersecting = min_distance < 0.0 && max_distance > -plane.thickness;
 
-            if abs_sum != sum {
+            // One way planes only stop colliders that are on the side the plane faces
+            let above = plane.local_normal.dot(self.origin - plane.origin) >= 0.0;
+
+            if intersecting && (above || !plane.one_way) {
                 // Now we need to calculate if the point is in the range of the plane
 
                 // Project all the points onto the plane
@@ -189,39 +390,39 @@ impl Collider for RectangleCollider {
     }
 
     fn is_colliding_x(&self, other: &dyn Collider) -> bool {
-        let width_2 = self.width / 2.0;
+        let width_2 = self.extents.x;
 
         other.contains_x(&((self.origin.x - width_2)..(self.origin.x + width_2)))
     }
 
     fn is_colliding_y(&self, other: &dyn Collider) -> bool {
-        let height_2 = self.height / 2.0;
+        let height_2 = self.extents.y;
 
         other.contains_y(&((self.origin.y - height_2)..(self.origin.y + height_2)))
     }
 
     fn is_colliding_z(&self, other: &dyn Collider) -> bool {
-        let length_2 = self.length / 2.0;
+        let length_2 = self.extents.z;
 
         other.contains_z(&((self.origin.z - length_2)..(self.origin.z + length_2)))
     }
 
     fn contains_x(&self, range: &Range<f32>) -> bool {
-        let width_2 = self.width / 2.0;
+        let width_2 = self.extents.x;
         let x_range = (self.origin.x - width_2)..(self.origin.x + width_2);
 
         x_range.contains(&range.start) || x_range.contains(&range.end)
     }
 
     fn contains_y(&self, range: &Range<f32>) -> bool {
-        let height_2 = self.height / 2.0;
+        let height_2 = self.extents.y;
         let y_range = (self.origin.y - height_2)..(self.origin.y + height_2);
 
         y_range.contains(&range.start) || y_range.contains(&range.end)
     }
 
     fn contains_z(&self, range: &Range<f32>) -> bool {
-        let length_2 = self.length / 2.0;
+        let length_2 = self.extents.z;
         let z_range = (self.origin.z - length_2)..(self.origin.z + length_2);
 
         z_range.contains(&range.start) || z_range.contains(&range.end)
@@ -229,7 +430,7 @@ impl Collider for RectangleCollider {
 
     fn snap(&mut self, other: &dyn Collider) {
         let (self_width_2, self_height_2, self_length_2) =
-            (self.width / 2.0, self.height / 2.0, self.length / 2.0);
+            (self.extents.x, self.extents.y, self.extents.z);
 
         let (other_width_2, other_height_2, other_length_2) = (
             other.width() / 2.0,
@@ -258,11 +459,11 @@ impl Collider for RectangleCollider {
             self.origin.z = other.origin().z + other_length_2 + self_length_2;
         }
 
-        self.vertices = Self::compute_vertices(self.width, self.height, self.length, &self.origin);
+        self.sync_position();
     }
 
     fn snap_x(&mut self, other: &dyn Collider) {
-        let self_width_2 = self.width / 2.0;
+        let self_width_2 = self.extents.x;
 
         let other_width_2 = other.width() / 2.0;
 
@@ -273,11 +474,11 @@ impl Collider for RectangleCollider {
             self.origin.x = other.origin().x + other_width_2 + self_width_2;
         }
 
-        self.vertices = Self::compute_vertices(self.width, self.height, self.length, &self.origin);
+        self.sync_position();
     }
 
     fn snap_y(&mut self, other: &dyn Collider) {
-        let self_height_2 = self.height / 2.0;
+        let self_height_2 = self.extents.y;
 
         let other_height_2 = other.height() / 2.0;
 
@@ -288,11 +489,11 @@ impl Collider for RectangleCollider {
             self.origin.y = other.origin().y + other_height_2 + self_height_2;
         }
 
-        self.vertices = Self::compute_vertices(self.width, self.height, self.length, &self.origin);
+        self.sync_position();
     }
 
     fn snap_z(&mut self, other: &dyn Collider) {
-        let self_length_2 = self.length / 2.0;
+        let self_length_2 = self.extents.z;
 
         let other_length_2 = other.length() / 2.0;
 
@@ -303,28 +504,29 @@ impl Collider for RectangleCollider {
             self.origin.z = other.origin().z + other_length_2 + self_length_2;
         }
 
-        self.vertices = Self::compute_vertices(self.width, self.height, self.length, &self.origin);
+        self.sync_position();
     }
 
     fn set_origin(&mut self, new_origin: &Vector3<f32>) {
         self.origin = *new_origin;
-        self.vertices = Self::compute_vertices(self.width, self.height, self.length, &self.origin);
+        self.sync_position();
     }
 
     fn origin(&self) -> &Vector3<f32> {
         &self.origin
     }
 
+    // The sizes are along the world axes so they include the rotation of the collider
     fn width(&self) -> f32 {
-        self.width
+        self.extents.x * 2.0
     }
 
     fn height(&self) -> f32 {
-        self.height
+        self.extents.y * 2.0
     }
 
     fn length(&self) -> f32 {
-        self.length
+        self.extents.z * 2.0
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -332,7 +534,7 @@ impl Collider for RectangleCollider {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct StationaryPlaneCollider {
     pub width: f32,
     pub length: f32,
@@ -340,6 +542,12 @@ pub struct StationaryPlaneCollider {
 
     plane_points: [Vector3<f32>; 4],
     local_normal: Vector3<f32>,
+    orientation: Quaternion<f32>,
+
+    // Depth of the plane below its surface
+    thickness: f32,
+    // Only collide with colliders coming from the side the plane faces
+    one_way: bool,
 }
 
 impl StationaryPlaneCollider {
@@ -387,7 +595,98 @@ impl StationaryPlaneCollider {
             origin,
             plane_points,
             local_normal: orientation.rotate_vector(PLANE_LOCAL_NORMAL).normalize(),
+            orientation,
+            thickness: 0.0,
+            one_way: false,
+        }
+    }
+
+    /// Gives the plane a depth below its surface, colliders that move far in one frame
+    /// are still pushed out instead of passing through
+    ///
+    /// # Arguments
+    ///
+    /// * `thickness` - Depth of the plane opposite to its normal
+    pub fn with_thickness(mut self, thickness: f32) -> Self {
+        self.thickness = thickness.max(0.0);
+        self
+    }
+
+    /// Makes the plane only collide with colliders coming from the side it faces so they
+    /// can pass through it from below, used for platforms that can be jumped through
+    pub fn with_one_way(mut self, one_way: bool) -> Self {
+        self.one_way = one_way;
+        self
+    }
+
+    pub fn get_thickness(&self) -> f32 {
+        self.thickness
+    }
+
+    pub fn is_one_way(&self) -> bool {
+        self.one_way
+    }
+
+    /// The rotation the plane was created with
+    pub fn get_orientation(&self) -> &Quaternion<f32> {
+        &self.orientation
+    }
+
+    /// The direction the plane faces
+    pub fn normal(&self) -> &Vector3<f32> {
+        &self.local_normal
+    }
+
+    /// The four corners of the plane in order around its edge
+    pub fn get_corners(&self) -> &[Vector3<f32>; 4] {
+        &self.plane_points
+    }
+
+    /// Casts a ray against the surface of the plane, one way planes are only hit from the
+    /// side they face
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - Where the ray starts in world space
+    /// * `direction` - The direction of the ray, it does not have to be normalized
+    /// * `max_distance` - How far the ray reaches
+    ///
+    /// # Returns
+    ///
+    /// Where the ray hit the plane if it did
+    pub fn raycast(
+        &self,
+        origin: Vector3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<RayHit> {
+        if direction.magnitude2() <= f32::EPSILON {
+            return None;
         }
+        let direction = direction.normalize();
+
+        let facing = direction.dot(self.local_normal);
+        if facing.abs() <= f32::EPSILON || (self.one_way && facing > 0.0) {
+            return None;
+        }
+
+        let distance = (self.origin - origin).dot(self.local_normal) / facing;
+        if distance < 0.0 || distance > max_distance {
+            return None;
+        }
+
+        // The hit has to be inside of the edges of the plane
+        let point = origin + direction * distance;
+        let local = self.orientation.invert().rotate_vector(point - self.origin);
+        if local.x.abs() > self.width / 2.0 || local.z.abs() > self.length / 2.0 {
+            return None;
+        }
+
+        Some(RayHit {
+            distance,
+            point,
+            normal: self.local_normal * -facing.signum(),
+        })
     }
 }
 
@@ -526,7 +825,7 @@ impl Collider for StationaryPlaneCollider {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use cgmath::{One, Zero};
+    use cgmath::{Deg, One, Rotation3, Zero};
 
     #[test]
     fn test_rectangle_colliders_x() {
@@ -871,4 +1170,161 @@ mod tests {
 
         assert!(!rectangle_collider.is_colliding(&plane_collider));
     }
+
+    #[test]
+    fn test_rectangle_offset() {
+        let mut collider =
+            RectangleCollider::new(2.0, 2.0, 2.0, Vector3::zero()).with_offset(Vector3 {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            });
+
+        collider.set_transform(
+            &Vector3 {
+                x: 3.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            &Quaternion::one(),
+        );
+        assert_eq!(
+            *collider.origin(),
+            Vector3 {
+                x: 3.0,
+                y: 1.0,
+                z: 0.0,
+            }
+        );
+
+        // The offset turns with the entity
+        collider.set_transform(&Vector3::zero(), &Quaternion::from_angle_z(Deg(90.0)));
+        assert!((collider.origin() - Vector3::new(-1.0, 0.0, 0.0)).magnitude() < 1e-5);
+
+        // Snapping moves the entity position and keeps the offset
+        collider.set_origin(&Vector3::new(-1.0, 5.0, 0.0));
+        assert!((collider.get_position() - Vector3::new(0.0, 5.0, 0.0)).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn test_rectangle_rotation() {
+        let collider = RectangleCollider::new(4.0, 2.0, 2.0, Vector3::zero())
+            .with_rotation(Quaternion::from_angle_y(Deg(90.0)));
+
+        assert!((collider.width() - 2.0).abs() < 1e-5);
+        assert!((collider.length() - 4.0).abs() < 1e-5);
+        assert!((collider.height() - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_plane_thickness() {
+        let plane = StationaryPlaneCollider::new(10.0, 10.0, Vector3::zero(), Quaternion::one());
+        let thick_plane = plane.clone().with_thickness(1.0);
+
+        // Fully below the surface but inside the thick plane
+        let collider = RectangleCollider::new(2.0, 2.0, 2.0, Vector3::new(0.0, -1.5, 0.0));
+        assert!(!collider.is_colliding(&plane));
+        assert_eq!(thick_plane.get_thickness(), 1.0);
+        assert!(collider.is_colliding(&thick_plane));
+
+        // The thick plane pushes back up to the surface when closer to it
+        let mut collider = RectangleCollider::new(2.0, 2.0, 2.0, Vector3::new(0.0, -0.25, 0.0));
+        assert_eq!(collider.snap_to_plane(&thick_plane), Vector3::unit_y());
+        assert!((collider.origin().y - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_plane_one_way() {
+        let plane = StationaryPlaneCollider::new(10.0, 10.0, Vector3::zero(), Quaternion::one())
+            .with_one_way(true);
+        assert!(plane.is_one_way());
+
+        // Coming from below passes through
+        let collider = RectangleCollider::new(2.0, 2.0, 2.0, Vector3::new(0.0, -0.5, 0.0));
+        assert!(!collider.is_colliding(&plane));
+
+        // Landing from above collides
+        let mut collider = RectangleCollider::new(2.0, 2.0, 2.0, Vector3::new(0.0, 0.5, 0.0));
+        assert!(collider.is_colliding(&plane));
+        assert_eq!(collider.snap_to_plane(&plane), Vector3::unit_y());
+        assert!((collider.origin().y - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_rectangle_overlaps() {
+        let collider_1 = RectangleCollider::new(2.0, 2.0, 2.0, Vector3::zero());
+
+        assert!(collider_1.overlaps(&RectangleCollider::new(
+            2.0,
+            2.0,
+            2.0,
+            Vector3::new(1.5, 1.5, 0.0)
+        )));
+        assert!(collider_1.overlaps(&RectangleCollider::new(0.5, 0.5, 0.5, Vector3::zero())));
+        assert!(!collider_1.overlaps(&RectangleCollider::new(
+            2.0,
+            2.0,
+            2.0,
+            Vector3::new(0.0, 0.0, 2.5)
+        )));
+    }
+
+    #[test]
+    fn test_rectangle_raycast() {
+        let collider = RectangleCollider::new(2.0, 2.0, 2.0, Vector3::zero());
+
+        let hit = collider
+            .raycast(Vector3::new(0.0, 5.0, 0.0), -Vector3::unit_y(), 10.0)
+            .unwrap();
+        assert!((hit.distance - 4.0).abs() < 1e-5);
+        assert!((hit.normal - Vector3::unit_y()).magnitude() < 1e-5);
+
+        // Too short, pointing away, and passing beside the box
+        assert!(collider
+            .raycast(Vector3::new(0.0, 5.0, 0.0), -Vector3::unit_y(), 3.0)
+            .is_none());
+        assert!(collider
+            .raycast(Vector3::new(0.0, 5.0, 0.0), Vector3::unit_y(), 10.0)
+            .is_none());
+        assert!(collider
+            .raycast(Vector3::new(2.0, 5.0, 0.0), -Vector3::unit_y(), 10.0)
+            .is_none());
+
+        // A box turned 45 degrees reaches further along the diagonal
+        let turned = RectangleCollider::new(2.0, 2.0, 2.0, Vector3::zero())
+            .with_rotation(Quaternion::from_angle_y(Deg(45.0)));
+        let hit = turned
+            .raycast(Vector3::new(5.0, 0.0, 0.0), -Vector3::unit_x(), 10.0)
+            .unwrap();
+        assert!((hit.distance - (5.0 - 2.0f32.sqrt())).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_plane_raycast() {
+        let plane = StationaryPlaneCollider::new(4.0, 4.0, Vector3::zero(), Quaternion::one());
+
+        let hit = plane
+            .raycast(Vector3::new(1.0, 2.0, 1.0), -Vector3::unit_y(), 5.0)
+            .unwrap();
+        assert!((hit.distance - 2.0).abs() < 1e-5);
+        assert!((hit.point - Vector3::new(1.0, 0.0, 1.0)).magnitude() < 1e-5);
+        assert_eq!(hit.normal, Vector3::unit_y());
+
+        // From below the normal faces down
+        let hit = plane
+            .raycast(Vector3::new(0.0, -1.0, 0.0), Vector3::unit_y(), 5.0)
+            .unwrap();
+        assert_eq!(hit.normal, -Vector3::unit_y());
+
+        // Past the edge of the plane
+        assert!(plane
+            .raycast(Vector3::new(3.0, 2.0, 0.0), -Vector3::unit_y(), 5.0)
+            .is_none());
+
+        // One way planes are only hit from above
+        let one_way = plane.clone().with_one_way(true);
+        assert!(one_way
+            .raycast(Vector3::new(0.0, -1.0, 0.0), Vector3::unit_y(), 5.0)
+            .is_none());
+    }
 }
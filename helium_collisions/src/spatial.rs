@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+
+use cgmath::{InnerSpace, Vector3};
+
+type Cell = (i32, i32, i32);
+
+/// A uniform grid of points used to find what is near a position without checking every
+/// point, the grid is rebuilt when the points move
+#[derive(Clone, Debug)]
+pub struct SpatialGrid<T> {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<usize>>,
+    points: Vec<(T, Vector3<f32>)>,
+    // The lowest and highest occupied cells
+    bounds: Option<(Cell, Cell)>,
+}
+
+impl<T> SpatialGrid<T>
+where
+    T: Copy,
+{
+    /// Creates an empty grid
+    ///
+    /// # Arguments
+    ///
+    /// * `cell_size` - The width of a cell in world units, about the distance most queries
+    ///   search is a good size
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(f32::EPSILON),
+            cells: HashMap::new(),
+            points: Vec::new(),
+            bounds: None,
+        }
+    }
+
+    fn cell(&self, position: Vector3<f32>) -> Cell {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.y / self.cell_size).floor() as i32,
+            (position.z / self.cell_size).floor() as i32,
+        )
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.points.clear();
+        self.bounds = None;
+    }
+
+    pub fn insert(&mut self, item: T, position: Vector3<f32>) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push(self.points.len());
+        self.points.push((item, position));
+
+        self.bounds = Some(match self.bounds {
+            Some((min, max)) => (
+                (min.0.min(cell.0), min.1.min(cell.1), min.2.min(cell.2)),
+                (max.0.max(cell.0), max.1.max(cell.1), max.2.max(cell.2)),
+            ),
+            None => (cell, cell),
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Changes the width of the cells and puts the points back into the grid
+    pub fn set_cell_size(&mut self, cell_size: f32) {
+        let points = std::mem::take(&mut self.points);
+        self.clear();
+        self.cell_size = cell_size.max(f32::EPSILON);
+        for (item, position) in points {
+            self.insert(item, position);
+        }
+    }
+
+    pub fn get_cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    // The points of the cells from `min` to `max`, or `None` when the range holds more
+    // cells than there are points and checking every point is faster
+    fn points_in_cells(&self, min: Cell, max: Cell) -> Option<Vec<usize>> {
+        let count = |low: i32, high: i32| (high as i64 - low as i64 + 1).max(0) as usize;
+        let cell_count = count(min.0, max.0)
+            .saturating_mul(count(min.1, max.1))
+            .saturating_mul(count(min.2, max.2));
+        if cell_count > self.points.len() {
+            return None;
+        }
+
+        let mut points = Vec::new();
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                for z in min.2..=max.2 {
+                    if let Some(cell) = self.cells.get(&(x, y, z)) {
+                        points.extend_from_slice(cell);
+                    }
+                }
+            }
+        }
+
+        Some(points)
+    }
+
+    /// The points within a distance of a position, closest first
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position to search around
+    /// * `radius` - The distance to search
+    /// * `filter` - Skips the points it returns false for
+    ///
+    /// # Returns
+    ///
+    /// The items of the points and their distances from the position
+    pub fn within_radius<F>(
+        &self,
+        position: Vector3<f32>,
+        radius: f32,
+        mut filter: F,
+    ) -> Vec<(T, f32)>
+    where
+        F: FnMut(T) -> bool,
+    {
+        let Some((bounds_min, bounds_max)) = self.bounds else {
+            return Vec::new();
+        };
+
+        let low = self.cell(position - Vector3::new(radius, radius, radius));
+        let high = self.cell(position + Vector3::new(radius, radius, radius));
+        let min = (
+            low.0.max(bounds_min.0),
+            low.1.max(bounds_min.1),
+            low.2.max(bounds_min.2),
+        );
+        let max = (
+            high.0.min(bounds_max.0),
+            high.1.min(bounds_max.1),
+            high.2.min(bounds_max.2),
+        );
+
+        let candidates = self
+            .points_in_cells(min, max)
+            .unwrap_or_else(|| (0..self.points.len()).collect());
+
+        let mut found = candidates
+            .into_iter()
+            .filter_map(|index| {
+                let (item, point) = self.points[index];
+                let distance = (point - position).magnitude();
+                (distance <= radius && filter(item)).then_some((item, distance))
+            })
+            .collect::<Vec<_>>();
+        found.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        found
+    }
+
+    /// The closest points to a position, the search grows one ring of cells at a time
+    /// until no unchecked point can be closer
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position to search around
+    /// * `count` - How many points to find at most
+    /// * `filter` - Skips the points it returns false for
+    ///
+    /// # Returns
+    ///
+    /// The items of the points and their distances from the position, closest first
+    pub fn nearest<F>(&self, position: Vector3<f32>, count: usize, mut filter: F) -> Vec<(T, f32)>
+    where
+        F: FnMut(T) -> bool,
+    {
+        let Some((bounds_min, bounds_max)) = self.bounds else {
+            return Vec::new();
+        };
+        if count == 0 {
+            return Vec::new();
+        }
+
+        let center = self.cell(position);
+        // The ring that reaches every occupied cell
+        let last_ring = [
+            center.0 - bounds_min.0,
+            bounds_max.0 - center.0,
+            center.1 - bounds_min.1,
+            bounds_max.1 - center.1,
+            center.2 - bounds_min.2,
+            bounds_max.2 - center.2,
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or(0)
+        .max(0);
+
+        let mut found: Vec<(T, f32)> = Vec::new();
+        let mut consider = |index: usize, found: &mut Vec<(T, f32)>| {
+            let (item, point) = self.points[index];
+            if filter(item) {
+                found.push((item, (point - position).magnitude()));
+            }
+        };
+
+        for ring in 0..=last_ring {
+            let ring_min = (center.0 - ring, center.1 - ring, center.2 - ring);
+            let ring_max = (center.0 + ring, center.1 + ring, center.2 + ring);
+            let Some(points) = self.points_in_cells(ring_min, ring_max) else {
+                // The rings have grown past the points so every point is checked instead
+                found.clear();
+                for index in 0..self.points.len() {
+                    consider(index, &mut found);
+                }
+                break;
+            };
+
+            // Only the cells on the edge of the ring are new
+            let inner = ring - 1;
+            for index in points {
+                let cell = self.cell(self.points[index].1);
+                let ring_distance = (cell.0 - center.0)
+                    .abs()
+                    .max((cell.1 - center.1).abs())
+                    .max((cell.2 - center.2).abs());
+                if ring_distance > inner {
+                    consider(index, &mut found);
+                }
+            }
+
+            // Points outside the ring are at least this far from the position
+            let reach = ring as f32 * self.cell_size;
+            if found.len() >= count {
+                found.sort_by(|a, b| a.1.total_cmp(&b.1));
+                if found[count - 1].1 <= reach {
+                    break;
+                }
+            }
+        }
+
+        found.sort_by(|a, b| a.1.total_cmp(&b.1));
+        found.truncate(count);
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> SpatialGrid<u32> {
+        let mut grid = SpatialGrid::new(2.0);
+        grid.insert(0, Vector3::new(0.0, 0.0, 0.0));
+        grid.insert(1, Vector3::new(1.0, 0.0, 0.0));
+        grid.insert(2, Vector3::new(-3.0, 0.0, 0.0));
+        grid.insert(3, Vector3::new(0.0, 5.0, 0.0));
+        grid.insert(4, Vector3::new(0.0, 0.0, -9.5));
+        grid
+    }
+
+    #[test]
+    fn test_nearest() {
+        let grid = grid();
+
+        let nearest = grid.nearest(Vector3::new(0.9, 0.0, 0.0), 1, |_| true);
+        assert_eq!(nearest.len(), 1);
+        assert_eq!(nearest[0].0, 1);
+        assert!((nearest[0].1 - 0.1).abs() < 1e-5);
+
+        let nearest = grid.nearest(Vector3::new(0.0, 0.0, -20.0), 1, |_| true);
+        assert_eq!(nearest[0].0, 4);
+    }
+
+    #[test]
+    fn test_nearest_ordered() {
+        let grid = grid();
+
+        let items = grid
+            .nearest(Vector3::new(0.0, 0.0, 0.0), 3, |_| true)
+            .into_iter()
+            .map(|(item, _)| item)
+            .collect::<Vec<_>>();
+        assert_eq!(items, vec![0, 1, 2]);
+
+        // Asking for more points than there are returns all of them
+        assert_eq!(
+            grid.nearest(Vector3::new(0.0, 0.0, 0.0), 10, |_| true)
+                .len(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_nearest_filter() {
+        let grid = grid();
+
+        let nearest = grid.nearest(Vector3::new(0.0, 0.0, 0.0), 1, |item| item >= 2);
+        assert_eq!(nearest[0].0, 2);
+
+        assert!(grid
+            .nearest(Vector3::new(0.0, 0.0, 0.0), 1, |_| false)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_within_radius() {
+        let grid = grid();
+
+        let items = grid
+            .within_radius(Vector3::new(0.0, 0.0, 0.0), 3.0, |_| true)
+            .into_iter()
+            .map(|(item, _)| item)
+            .collect::<Vec<_>>();
+        assert_eq!(items, vec![0, 1, 2]);
+
+        let items = grid
+            .within_radius(Vector3::new(0.0, 0.0, 0.0), 100.0, |item| item % 2 == 0)
+            .into_iter()
+            .map(|(item, _)| item)
+            .collect::<Vec<_>>();
+        assert_eq!(items, vec![0, 2, 4]);
+    }
+
+    #[test]
+    fn test_matches_linear_search() {
+        let mut grid = SpatialGrid::new(1.5);
+        let points = (0..200)
+            .map(|i| {
+                let i = i as f32;
+                Vector3::new((i * 7.3) % 23.0, (i * 3.1) % 11.0, (i * 5.7) % 17.0)
+            })
+            .collect::<Vec<_>>();
+        for (index, point) in points.iter().enumerate() {
+            grid.insert(index, *point);
+        }
+
+        let position = Vector3::new(8.0, 4.0, 30.0);
+        let mut expected = points
+            .iter()
+            .map(|point| (point - position).magnitude())
+            .collect::<Vec<_>>();
+        expected.sort_by(f32::total_cmp);
+
+        let nearest = grid.nearest(position, 5, |_| true);
+        for ((_, distance), expected) in nearest.iter().zip(&expected) {
+            assert!((distance - expected).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_set_cell_size() {
+        let mut grid = grid();
+        grid.set_cell_size(0.5);
+
+        assert_eq!(grid.len(), 5);
+        assert_eq!(
+            grid.nearest(Vector3::new(0.0, 4.0, 0.0), 1, |_| true)[0].0,
+            3
+        );
+    }
+}
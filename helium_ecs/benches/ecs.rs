@@ -0,0 +1,88 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use helium_ecs::{HeliumECS, StorageOrder};
+
+const ENTITY_COUNT: u32 = 10_000;
+const ORDERS: [StorageOrder; 2] = [StorageOrder::Random, StorageOrder::Deterministic];
+
+struct Position(f32, f32, f32);
+struct Velocity(f32, f32, f32);
+
+// A world where every entity has a position and every other entity has a velocity
+fn populated(order: StorageOrder) -> HeliumECS {
+    let mut ecs = HeliumECS::with_storage_order(order);
+    for index in 0..ENTITY_COUNT {
+        let entity = ecs.new_entity();
+        ecs.add_component(entity, Position(index as f32, 0.0, 0.0));
+        if index % 2 == 0 {
+            ecs.add_component(entity, Velocity(1.0, 0.0, 0.0));
+        }
+    }
+
+    ecs
+}
+
+fn insertion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insertion");
+    for order in ORDERS {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{order:?}")),
+            &order,
+            |b, order| b.iter(|| black_box(populated(*order))),
+        );
+    }
+    group.finish();
+}
+
+fn query_iteration(c: &mut Criterion) {
+    let mut group = c.benchmark_group("query_iteration");
+    for order in ORDERS {
+        let ecs = populated(order);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{order:?}")),
+            &ecs,
+            |b, ecs| {
+                b.iter(|| {
+                    let mut positions = ecs.query_mut::<Position>().unwrap();
+                    let velocities = ecs.query::<Velocity>().unwrap();
+                    for (entity, velocity) in velocities.iter() {
+                        if let Some(position) = positions.get_mut(entity) {
+                            position.0 += velocity.0;
+                            position.1 += velocity.1;
+                            position.2 += velocity.2;
+                        }
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn structural_changes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("structural_changes");
+    for order in ORDERS {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{order:?}")),
+            &order,
+            |b, order| {
+                b.iter_batched(
+                    || populated(*order),
+                    |mut ecs| {
+                        for entity in (0..ENTITY_COUNT).step_by(3) {
+                            ecs.remove_component::<Position>(entity);
+                        }
+                        for entity in (1..ENTITY_COUNT).step_by(3) {
+                            ecs.remove_entity(entity);
+                        }
+                        black_box(ecs)
+                    },
+                    criterion::BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, insertion, query_iteration, structural_changes);
+criterion_main!(benches);
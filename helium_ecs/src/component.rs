@@ -1,19 +1,274 @@
-use crate::entity::Entity;
-use std::{any::Any, cell::RefCell, collections::HashMap};
+use crate::{entity::Entity, world::World};
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::{btree_map, hash_map, BTreeMap, BTreeSet, HashMap},
+    marker::PhantomData,
+};
+
+/// How the component maps of a world order their entities when iterated
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StorageOrder {
+    /// Hashed with a random key, the order changes every run
+    #[default]
+    Random,
+    /// Sorted by entity, so the components are always iterated in ascending entity order,
+    /// used for tests, networking and replays
+    Deterministic,
+}
+
+/// The map a component type is stored in, a hash map or a sorted map for the
+/// `StorageOrder` of its world
+#[derive(Clone, Debug)]
+pub enum ComponentMap<T> {
+    Hashed(HashMap<Entity, T>),
+    Sorted(BTreeMap<Entity, T>),
+}
+
+/// Iterates a component map with either of its storages
+#[derive(Clone, Debug)]
+pub enum StorageIter<H, S> {
+    Hashed(H),
+    Sorted(S),
+}
+
+impl<H, S> Iterator for StorageIter<H, S>
+where
+    H: Iterator,
+    S: Iterator<Item = H::Item>,
+{
+    type Item = H::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Hashed(iter) => iter.next(),
+            Self::Sorted(iter) => iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Self::Hashed(iter) => iter.size_hint(),
+            Self::Sorted(iter) => iter.size_hint(),
+        }
+    }
+}
+
+pub type Iter<'a, T> = StorageIter<hash_map::Iter<'a, Entity, T>, btree_map::Iter<'a, Entity, T>>;
+pub type IterMut<'a, T> =
+    StorageIter<hash_map::IterMut<'a, Entity, T>, btree_map::IterMut<'a, Entity, T>>;
+pub type Keys<'a, T> = StorageIter<hash_map::Keys<'a, Entity, T>, btree_map::Keys<'a, Entity, T>>;
+pub type Values<'a, T> =
+    StorageIter<hash_map::Values<'a, Entity, T>, btree_map::Values<'a, Entity, T>>;
+pub type ValuesMut<'a, T> =
+    StorageIter<hash_map::ValuesMut<'a, Entity, T>, btree_map::ValuesMut<'a, Entity, T>>;
+
+// Runs the same expression on whichever map stores the components
+macro_rules! on_storage {
+    ($map:expr, $storage:ident => $body:expr) => {
+        match $map {
+            ComponentMap::Hashed($storage) => $body,
+            ComponentMap::Sorted($storage) => $body,
+        }
+    };
+    ($map:expr, $storage:ident => iter $body:expr) => {
+        match $map {
+            ComponentMap::Hashed($storage) => StorageIter::Hashed($body),
+            ComponentMap::Sorted($storage) => StorageIter::Sorted($body),
+        }
+    };
+}
+
+impl<T> ComponentMap<T> {
+    /// Creates an empty component map
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - The order the components are iterated in
+    pub fn with_order(order: StorageOrder) -> Self {
+        match order {
+            StorageOrder::Random => Self::Hashed(HashMap::new()),
+            StorageOrder::Deterministic => Self::Sorted(BTreeMap::new()),
+        }
+    }
+
+    pub fn get_order(&self) -> StorageOrder {
+        match self {
+            Self::Hashed(_) => StorageOrder::Random,
+            Self::Sorted(_) => StorageOrder::Deterministic,
+        }
+    }
+
+    pub fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        on_storage!(self, map => map.insert(entity, component))
+    }
+
+    pub fn remove(&mut self, entity: &Entity) -> Option<T> {
+        on_storage!(self, map => map.remove(entity))
+    }
+
+    pub fn get(&self, entity: &Entity) -> Option<&T> {
+        on_storage!(self, map => map.get(entity))
+    }
+
+    pub fn get_mut(&mut self, entity: &Entity) -> Option<&mut T> {
+        on_storage!(self, map => map.get_mut(entity))
+    }
+
+    pub fn contains_key(&self, entity: &Entity) -> bool {
+        on_storage!(self, map => map.contains_key(entity))
+    }
+
+    pub fn len(&self) -> usize {
+        on_storage!(self, map => map.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        on_storage!(self, map => map.is_empty())
+    }
+
+    pub fn clear(&mut self) {
+        on_storage!(self, map => map.clear())
+    }
+
+    pub fn retain<F>(&mut self, keep: F)
+    where
+        F: FnMut(&Entity, &mut T) -> bool,
+    {
+        on_storage!(self, map => map.retain(keep))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        on_storage!(self, map => iter map.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        on_storage!(self, map => iter map.iter_mut())
+    }
+
+    pub fn keys(&self) -> Keys<'_, T> {
+        on_storage!(self, map => iter map.keys())
+    }
+
+    pub fn values(&self) -> Values<'_, T> {
+        on_storage!(self, map => iter map.values())
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, T> {
+        on_storage!(self, map => iter map.values_mut())
+    }
+}
+
+impl<T> Default for ComponentMap<T> {
+    fn default() -> Self {
+        Self::with_order(StorageOrder::default())
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ComponentMap<T> {
+    type Item = (&'a Entity, &'a T);
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut ComponentMap<T> {
+    type Item = (&'a Entity, &'a mut T);
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
 
 pub trait ComponentVec {
     #[allow(unused)]
     fn remove(&mut self, entity: Entity);
 
+    fn contains(&self, entity: Entity) -> bool;
+    fn entities(&self) -> Vec<Entity>;
+    fn type_name(&self) -> &'static str;
+
+    /// Moves the component of an entity into the same component map of another world
+    fn move_to(&mut self, entity: Entity, target: &mut World);
+
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
-impl<T: 'static> ComponentVec for RefCell<HashMap<Entity, T>> {
+impl<T: 'static> ComponentVec for RefCell<ComponentMap<T>> {
     fn remove(&mut self, entity: Entity) {
         self.borrow_mut().remove(&entity);
     }
 
+    fn contains(&self, entity: Entity) -> bool {
+        self.borrow().contains_key(&entity)
+    }
+
+    fn entities(&self) -> Vec<Entity> {
+        self.borrow().keys().copied().collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+
+    fn move_to(&mut self, entity: Entity, target: &mut World) {
+        if let Some(component) = self.get_mut().remove(&entity) {
+            target.add_component_to_entity(entity, component);
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self as &dyn Any
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self as &mut dyn Any
+    }
+}
+
+/// The entities that have a tag, tags have no value so only the entities are kept, in
+/// ascending order
+pub struct TagSet<T> {
+    pub entities: BTreeSet<Entity>,
+    tag: PhantomData<T>,
+}
+
+impl<T> Default for TagSet<T> {
+    fn default() -> Self {
+        Self {
+            entities: BTreeSet::new(),
+            tag: PhantomData,
+        }
+    }
+}
+
+impl<T: 'static> ComponentVec for TagSet<T> {
+    fn remove(&mut self, entity: Entity) {
+        self.entities.remove(&entity);
+    }
+
+    fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(&entity)
+    }
+
+    fn entities(&self) -> Vec<Entity> {
+        self.entities.iter().copied().collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+
+    fn move_to(&mut self, entity: Entity, target: &mut World) {
+        if self.entities.remove(&entity) {
+            target.add_tag_to_entity::<T>(entity);
+        }
+    }
+
     fn as_any(&self) -> &dyn Any {
         self as &dyn Any
     }
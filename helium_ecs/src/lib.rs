@@ -1,35 +1,203 @@
-use std::{
-    cell::{Ref, RefMut},
-    collections::HashMap,
-};
+use std::cell::{Ref, RefMut};
 
+pub use component::{ComponentMap, StorageOrder};
 pub use entity::Entity;
 use world::World;
 
+/// Id of a world in the ecs
+pub type WorldId = usize;
+
+/// The world that exists from the start and can not be removed
+pub const MAIN_WORLD: WorldId = 0;
+
 mod component;
 mod entity;
 mod world;
 
+/// Holds one or more worlds of entities, like a game world and a ui world. Entity ids are
+/// unique across the worlds so an entity keeps its id when it moves to another world, and
+/// everything except the world functions works on the active world
 pub struct HeliumECS {
-    world: World,
+    worlds: Vec<Option<World>>,
+    active: WorldId,
+    order: StorageOrder,
+    entity_count: Entity,
 }
 
 impl Default for HeliumECS {
     fn default() -> Self {
-        Self {
-            world: World::new(),
-        }
+        Self::with_storage_order(StorageOrder::default())
     }
 }
 
 impl HeliumECS {
-    /// Creates a new entity in the world
+    /// Creates an empty world whose component maps iterate in an order
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - `StorageOrder::Deterministic` to iterate in ascending entity order
+    pub fn with_storage_order(order: StorageOrder) -> Self {
+        Self {
+            worlds: vec![Some(World::with_order(order))],
+            active: MAIN_WORLD,
+            order,
+            entity_count: 0,
+        }
+    }
+
+    /// Gives the order the component maps of the world iterate in
+    pub fn get_storage_order(&self) -> StorageOrder {
+        self.order
+    }
+
+    /// Creates a new empty world next to the existing ones
+    ///
+    /// # Returns
+    ///
+    /// The id of the world
+    pub fn create_world(&mut self) -> WorldId {
+        self.worlds.push(Some(World::with_order(self.order)));
+        self.worlds.len() - 1
+    }
+
+    /// Removes a world and every entity in it, the main world can not be removed and the
+    /// main world becomes active when the active world is removed
+    ///
+    /// # Arguments
+    ///
+    /// * `world` - The id of the world to remove
+    ///
+    /// # Returns
+    ///
+    /// Whether the world was removed
+    pub fn remove_world(&mut self, world: WorldId) -> bool {
+        if world == MAIN_WORLD || !self.contains_world(world) {
+            return false;
+        }
+
+        self.worlds[world] = None;
+        if self.active == world {
+            self.active = MAIN_WORLD;
+        }
+
+        true
+    }
+
+    /// Checks if a world exists
+    pub fn contains_world(&self, world: WorldId) -> bool {
+        self.worlds.get(world).is_some_and(Option::is_some)
+    }
+
+    /// Gives the ids of every world in the order they were created
+    pub fn worlds(&self) -> Vec<WorldId> {
+        self.worlds
+            .iter()
+            .enumerate()
+            .filter_map(|(id, world)| world.as_ref().map(|_| id))
+            .collect()
+    }
+
+    /// Makes the entity and component functions work on another world
+    ///
+    /// # Arguments
+    ///
+    /// * `world` - The id of the world
+    ///
+    /// # Returns
+    ///
+    /// Whether the world exists and is now active
+    pub fn set_active_world(&mut self, world: WorldId) -> bool {
+        if !self.contains_world(world) {
+            return false;
+        }
+
+        self.active = world;
+        true
+    }
+
+    pub fn get_active_world(&self) -> WorldId {
+        self.active
+    }
+
+    /// Gives the world an entity is in
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity id to look for
+    ///
+    /// # Returns
+    ///
+    /// The id of the world or `None` if no world has the entity
+    pub fn world_of(&self, entity: Entity) -> Option<WorldId> {
+        self.worlds.iter().position(|world| {
+            world
+                .as_ref()
+                .is_some_and(|world| world.contains_entity(entity))
+        })
+    }
+
+    /// Moves an entity and all of its components from the world it is in to another world,
+    /// the entity keeps its id
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity id to move
+    /// * `world` - The id of the world to move it to
+    ///
+    /// # Returns
+    ///
+    /// Whether the entity was moved, it is not when either it or the world does not exist
+    pub fn move_entity(&mut self, entity: Entity, world: WorldId) -> bool {
+        let Some(from) = self.world_of(entity) else {
+            return false;
+        };
+        if !self.contains_world(world) {
+            return false;
+        }
+        if from == world {
+            return true;
+        }
+
+        let mut source = self.worlds[from].take().unwrap();
+        source.move_entity(entity, self.worlds[world].as_mut().unwrap());
+        self.worlds[from] = Some(source);
+
+        true
+    }
+
+    // The world the entity and component functions work on
+    fn world(&self) -> &World {
+        self.worlds[self.active].as_ref().unwrap()
+    }
+
+    fn world_mut(&mut self) -> &mut World {
+        self.worlds[self.active].as_mut().unwrap()
+    }
+
+    /// Creates a new entity in the active world
     ///
     /// # Returns
     ///
     /// The entity id
     pub fn new_entity(&mut self) -> Entity {
-        self.world.new_entity()
+        let entity = self.entity_count;
+        self.entity_count += 1;
+        self.world_mut().insert_entity(entity);
+        entity
+    }
+
+    /// Removes an entity and all of its components from the world, nothing happens if
+    /// the entity is not in the world
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity id to remove
+    pub fn remove_entity(&mut self, entity: Entity) {
+        if !self.contains_entity(entity) {
+            return;
+        }
+
+        self.world_mut().remove_entity(entity);
     }
 
     /// Adds the specified component to the specified entity
@@ -44,7 +212,7 @@ impl HeliumECS {
         entity: Entity,
         component: ComponentType,
     ) {
-        self.world.add_component_to_entity(entity, component);
+        self.world_mut().add_component_to_entity(entity, component);
     }
 
     /// Removes the value from the specified component from the entity
@@ -54,7 +222,7 @@ impl HeliumECS {
     /// * `ComponentType` - The type for the component to be removed
     /// * `entity` - The entity id to remove the component from
     pub fn remove_component<ComponentType: 'static>(&mut self, entity: Entity) {
-        self.world
+        self.world()
             .borrow_component_map_mut::<ComponentType>()
             .unwrap()
             .remove(&entity);
@@ -69,8 +237,8 @@ impl HeliumECS {
     /// # Returns
     ///
     /// an immutable reference to the specifed component map
-    pub fn query<ComponentType: 'static>(&self) -> Option<Ref<'_, HashMap<Entity, ComponentType>>> {
-        self.world.borrow_component_map::<ComponentType>()
+    pub fn query<ComponentType: 'static>(&self) -> Option<Ref<'_, ComponentMap<ComponentType>>> {
+        self.world().borrow_component_map::<ComponentType>()
     }
 
     /// Obtains a mutable reference to the component map specifed
@@ -84,8 +252,87 @@ impl HeliumECS {
     /// an mutable reference to the specifed component map
     pub fn query_mut<ComponentType: 'static>(
         &self,
-    ) -> Option<RefMut<'_, HashMap<Entity, ComponentType>>> {
-        self.world.borrow_component_map_mut::<ComponentType>()
+    ) -> Option<RefMut<'_, ComponentMap<ComponentType>>> {
+        self.world().borrow_component_map_mut::<ComponentType>()
+    }
+
+    /// Gives a list of every entity that has at least one component
+    ///
+    /// # Returns
+    ///
+    /// The entity ids in ascending order
+    pub fn entities(&self) -> Vec<Entity> {
+        self.world().get_entities()
+    }
+
+    /// Checks if an entity is still in the world, entities without any components are
+    /// treated as removed
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity id to check
+    pub fn contains_entity(&self, entity: Entity) -> bool {
+        self.world().contains_entity(entity)
+    }
+
+    /// Gives the type names of the components of an entity, useful for debugging
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The entity id to inspect
+    ///
+    /// # Returns
+    ///
+    /// The full type names of the components in the order they were first added to the world
+    pub fn component_names(&self, entity: Entity) -> Vec<&'static str> {
+        self.world().get_component_names(entity)
+    }
+
+    /// Gives an entity a tag, a marker type like `struct Enemy;` that has no value. Tags
+    /// are stored as a set of entities instead of a component map, so they are cheaper to
+    /// check and list than a component or a `Label` compared by name
+    ///
+    /// # Arguments
+    ///
+    /// * `TagType` - The marker type to tag the entity with
+    /// * `entity` - The entity id to tag
+    pub fn tag<TagType: 'static>(&mut self, entity: Entity) {
+        self.world_mut().add_tag_to_entity::<TagType>(entity);
+    }
+
+    /// Removes a tag from an entity
+    ///
+    /// # Arguments
+    ///
+    /// * `TagType` - The marker type to remove
+    /// * `entity` - The entity id to remove the tag from
+    pub fn untag<TagType: 'static>(&mut self, entity: Entity) {
+        if let Some(tag_set) = self.world_mut().tag_set_mut::<TagType>() {
+            tag_set.entities.remove(&entity);
+        }
+    }
+
+    /// Checks if an entity has a tag
+    pub fn has_tag<TagType: 'static>(&self, entity: Entity) -> bool {
+        self.world()
+            .tag_set::<TagType>()
+            .is_some_and(|tag_set| tag_set.entities.contains(&entity))
+    }
+
+    /// Gives the entities with a tag
+    ///
+    /// # Arguments
+    ///
+    /// * `TagType` - The marker type to look for
+    ///
+    /// # Returns
+    ///
+    /// The entity ids in ascending order
+    pub fn entities_tagged<TagType: 'static>(&self) -> Vec<Entity> {
+        self.world()
+            .tag_set::<TagType>()
+            .map(|tag_set| tag_set.entities.iter().copied().collect())
+            .unwrap_or_default()
     }
 
     /// Gives a list of entities that have a component with a specific comparator operator
@@ -104,7 +351,7 @@ impl HeliumECS {
     ) -> Vec<Entity> {
         let mut entities = Vec::new();
         for (entity, component) in self
-            .world
+            .world()
             .borrow_component_map::<ComponentType>()
             .unwrap()
             .iter()
@@ -236,5 +483,148 @@ mod tests {
         world.remove_entity(ralph);
 
         assert_eq!(world.get_num_entities(), 2);
+
+        // Removing it again or an entity that was never added changes nothing
+        world.remove_entity(ralph);
+        world.remove_entity(1000);
+        assert_eq!(world.get_num_entities(), 2);
+    }
+
+    #[test]
+    fn test_ecs_introspection() {
+        struct Health;
+        struct Player;
+
+        let mut ecs = HeliumECS::default();
+
+        let ralph = ecs.new_entity();
+        let empty = ecs.new_entity();
+        let player = ecs.new_entity();
+
+        ecs.add_component(ralph, Health);
+        ecs.add_component(player, Health);
+        ecs.add_component(player, Player);
+
+        assert_eq!(ecs.entities(), vec![ralph, player]);
+        assert!(ecs.component_names(empty).is_empty());
+        assert!(ecs.contains_entity(ralph));
+        assert!(!ecs.contains_entity(empty));
+
+        let names = ecs.component_names(player);
+        assert_eq!(names.len(), 2);
+        assert!(names[0].ends_with("Health"));
+        assert!(names[1].ends_with("Player"));
+    }
+
+    #[test]
+    fn test_multiple_worlds() {
+        struct Health(i32);
+        struct Player;
+
+        let mut ecs = HeliumECS::default();
+        let ui = ecs.create_world();
+        assert_eq!(ecs.worlds(), vec![MAIN_WORLD, ui]);
+
+        let player = ecs.new_entity();
+        ecs.add_component(player, Health(100));
+        ecs.add_component(player, Player);
+
+        assert!(ecs.set_active_world(ui));
+        let button = ecs.new_entity();
+        ecs.add_component(button, Health(1));
+        assert_ne!(player, button);
+        assert_eq!(ecs.entities(), vec![button]);
+        assert!(ecs.query::<Player>().is_none());
+
+        assert!(ecs.move_entity(player, ui));
+        assert_eq!(ecs.world_of(player), Some(ui));
+        assert_eq!(ecs.entities(), vec![player, button]);
+        assert_eq!(ecs.query::<Health>().unwrap().get(&player).unwrap().0, 100);
+        assert!(ecs.query::<Player>().unwrap().contains_key(&player));
+
+        assert!(ecs.set_active_world(MAIN_WORLD));
+        assert!(ecs.entities().is_empty());
+
+        assert!(!ecs.remove_world(MAIN_WORLD));
+        assert!(ecs.remove_world(ui));
+        assert!(!ecs.set_active_world(ui));
+        assert_eq!(ecs.world_of(player), None);
+        assert!(!ecs.move_entity(button, ui));
+    }
+
+    #[test]
+    fn test_tags() {
+        struct Enemy;
+        struct Boss;
+        struct Health;
+
+        let mut ecs = HeliumECS::default();
+        let ui = ecs.create_world();
+
+        let grunt = ecs.new_entity();
+        let boss = ecs.new_entity();
+        let player = ecs.new_entity();
+        ecs.add_component(player, Health);
+
+        ecs.tag::<Enemy>(boss);
+        ecs.tag::<Enemy>(grunt);
+        ecs.tag::<Enemy>(grunt);
+        ecs.tag::<Boss>(boss);
+
+        assert_eq!(ecs.entities_tagged::<Enemy>(), vec![grunt, boss]);
+        assert!(ecs.has_tag::<Boss>(boss));
+        assert!(!ecs.has_tag::<Boss>(player));
+        assert!(ecs.entities_tagged::<Health>().is_empty());
+        // Tags are kept apart from the components of the same type
+        assert!(ecs.query::<Enemy>().is_none());
+
+        // A tag alone keeps an entity in the world
+        assert!(ecs.contains_entity(grunt));
+        assert!(ecs
+            .component_names(boss)
+            .iter()
+            .any(|name| name.ends_with("Boss")));
+
+        ecs.untag::<Enemy>(grunt);
+        assert_eq!(ecs.entities_tagged::<Enemy>(), vec![boss]);
+
+        assert!(ecs.move_entity(boss, ui));
+        assert!(ecs.entities_tagged::<Enemy>().is_empty());
+        ecs.set_active_world(ui);
+        assert!(ecs.has_tag::<Enemy>(boss));
+        assert!(ecs.has_tag::<Boss>(boss));
+
+        ecs.remove_entity(boss);
+        assert!(ecs.entities_tagged::<Boss>().is_empty());
+    }
+
+    #[test]
+    fn test_deterministic_order() {
+        struct Position(u32);
+
+        let mut ecs = HeliumECS::with_storage_order(StorageOrder::Deterministic);
+        let mut expected = Vec::new();
+        // Added in descending order so the insertion order is not the entity order
+        let entities = (0..100).map(|_| ecs.new_entity()).collect::<Vec<_>>();
+        for (index, entity) in entities.iter().enumerate().rev() {
+            ecs.add_component(*entity, Position(index as u32));
+            if index != 42 {
+                expected.push((*entity, index as u32));
+            }
+        }
+        ecs.remove_entity(entities[42]);
+        expected.sort();
+
+        let positions = ecs.query::<Position>().unwrap();
+        let order = positions
+            .iter()
+            .map(|(entity, position)| (*entity, position.0))
+            .collect::<Vec<_>>();
+        assert_eq!(order, expected);
+        assert!(order.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(
+            HeliumECS::default().get_storage_order(),
+            StorageOrder::Random
+        );
     }
 }
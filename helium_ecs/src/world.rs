@@ -1,24 +1,32 @@
-use crate::{component::ComponentVec, entity::Entity};
-use std::{
-    cell::{Ref, RefCell, RefMut},
-    collections::HashMap,
+use crate::{
+    component::{ComponentMap, ComponentVec, StorageOrder, TagSet},
+    entity::Entity,
 };
+use std::cell::{Ref, RefCell, RefMut};
 
 pub struct World {
     entity_count: Entity,
     num_entities: Entity,
     component_maps: Vec<Box<dyn ComponentVec>>,
+    order: StorageOrder,
 }
 
 impl World {
+    #[allow(unused)]
     pub fn new() -> Self {
+        Self::with_order(StorageOrder::default())
+    }
+
+    pub fn with_order(order: StorageOrder) -> Self {
         Self {
             entity_count: 0,
             num_entities: 0,
             component_maps: Vec::new(),
+            order,
         }
     }
 
+    #[allow(unused)]
     pub fn new_entity(&mut self) -> Entity {
         let entity_id = self.entity_count;
 
@@ -27,19 +35,67 @@ impl World {
         entity_id
     }
 
+    /// Adds an entity whose id was given out by another world
+    pub fn insert_entity(&mut self, entity: Entity) {
+        self.entity_count = self.entity_count.max(entity + 1);
+        self.num_entities += 1;
+    }
+
     #[allow(unused)]
     pub fn get_num_entities(&self) -> Entity {
         self.num_entities
     }
 
-    #[allow(unused)]
     pub fn remove_entity(&mut self, entity: Entity) {
+        // Removing an entity twice would count it twice
+        if !self.contains_entity(entity) {
+            return;
+        }
+
         for component_map in self.component_maps.iter_mut() {
             component_map.remove(entity);
         }
         self.num_entities -= 1;
     }
 
+    /// Moves an entity and all of its components into another world
+    pub fn move_entity(&mut self, entity: Entity, target: &mut World) {
+        for component_map in self.component_maps.iter_mut() {
+            component_map.move_to(entity, target);
+        }
+        self.num_entities -= 1;
+        target.num_entities += 1;
+    }
+
+    /// All the entities that have at least one component, in ascending order
+    pub fn get_entities(&self) -> Vec<Entity> {
+        let mut entities = self
+            .component_maps
+            .iter()
+            .flat_map(|component_map| component_map.entities())
+            .collect::<Vec<_>>();
+
+        entities.sort_unstable();
+        entities.dedup();
+        entities
+    }
+
+    /// Whether an entity has any components
+    pub fn contains_entity(&self, entity: Entity) -> bool {
+        self.component_maps
+            .iter()
+            .any(|component_map| component_map.contains(entity))
+    }
+
+    /// The type names of all the components an entity has
+    pub fn get_component_names(&self, entity: Entity) -> Vec<&'static str> {
+        self.component_maps
+            .iter()
+            .filter(|component_map| component_map.contains(entity))
+            .map(|component_map| component_map.type_name())
+            .collect()
+    }
+
     pub fn add_component_to_entity<ComponentType: 'static>(
         &mut self,
         entity: Entity,
@@ -49,7 +105,7 @@ impl World {
         for component_map in self.component_maps.iter_mut() {
             if let Some(component_map) = component_map
                 .as_any_mut()
-                .downcast_mut::<RefCell<HashMap<Entity, ComponentType>>>()
+                .downcast_mut::<RefCell<ComponentMap<ComponentType>>>()
             {
                 component_map.borrow_mut().insert(entity, component);
                 return;
@@ -57,7 +113,8 @@ impl World {
         }
 
         // If the component doesn't exist then we create it and add it to our component maps
-        let mut new_component_map: HashMap<Entity, ComponentType> = HashMap::new();
+        let mut new_component_map: ComponentMap<ComponentType> =
+            ComponentMap::with_order(self.order);
 
         // Give the entity the component
         new_component_map.insert(entity, component);
@@ -65,6 +122,32 @@ impl World {
             .push(Box::new(RefCell::new(new_component_map)));
     }
 
+    /// Tags an entity, the tags of a type are kept apart from its component map
+    pub fn add_tag_to_entity<TagType: 'static>(&mut self, entity: Entity) {
+        match self.tag_set_mut::<TagType>() {
+            Some(tag_set) => {
+                tag_set.entities.insert(entity);
+            }
+            None => {
+                let mut tag_set = TagSet::<TagType>::default();
+                tag_set.entities.insert(entity);
+                self.component_maps.push(Box::new(tag_set));
+            }
+        }
+    }
+
+    pub fn tag_set<TagType: 'static>(&self) -> Option<&TagSet<TagType>> {
+        self.component_maps
+            .iter()
+            .find_map(|component_map| component_map.as_any().downcast_ref::<TagSet<TagType>>())
+    }
+
+    pub fn tag_set_mut<TagType: 'static>(&mut self) -> Option<&mut TagSet<TagType>> {
+        self.component_maps
+            .iter_mut()
+            .find_map(|component_map| component_map.as_any_mut().downcast_mut::<TagSet<TagType>>())
+    }
+
     // pub fn get_component_of_entity<ComponentType: 'static>(
     //     &self,
     //     entity: Entity,
@@ -72,7 +155,7 @@ impl World {
     //     for component_map in self.component_maps.iter() {
     //         if let Some(component_map) = component_map
     //             .as_any()
-    //             .downcast_ref::<RefCell<HashMap<Entity, ComponentType>>>()
+    //             .downcast_ref::<RefCell<ComponentMap<ComponentType>>>()
     //         {
     //             return component_map.borrow().get(&entity);
     //         }
@@ -82,11 +165,11 @@ impl World {
 
     pub fn borrow_component_map<ComponentType: 'static>(
         &self,
-    ) -> Option<Ref<HashMap<Entity, ComponentType>>> {
+    ) -> Option<Ref<'_, ComponentMap<ComponentType>>> {
         for component_map in self.component_maps.iter() {
             if let Some(component_map) = component_map
                 .as_any()
-                .downcast_ref::<RefCell<HashMap<Entity, ComponentType>>>()
+                .downcast_ref::<RefCell<ComponentMap<ComponentType>>>()
             {
                 return Some(component_map.borrow());
             }
@@ -96,11 +179,11 @@ impl World {
 
     pub fn borrow_component_map_mut<ComponentType: 'static>(
         &self,
-    ) -> Option<RefMut<HashMap<Entity, ComponentType>>> {
+    ) -> Option<RefMut<'_, ComponentMap<ComponentType>>> {
         for component_map in self.component_maps.iter() {
             if let Some(component_map) = component_map
                 .as_any()
-                .downcast_ref::<RefCell<HashMap<Entity, ComponentType>>>()
+                .downcast_ref::<RefCell<ComponentMap<ComponentType>>>()
             {
                 return Some(component_map.borrow_mut());
             }
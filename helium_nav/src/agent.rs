@@ -0,0 +1,208 @@
+use cgmath::{InnerSpace, Vector3, Zero};
+
+// Distance from a corner of the path where the agent moves on to the next one
+const DEFAULT_ARRIVE_DISTANCE: f32 = 0.1;
+
+/// Moves an entity along a path over the nav mesh to a destination while keeping its
+/// distance from other agents
+#[derive(Clone, Debug, PartialEq)]
+pub struct NavAgent {
+    speed: f32,
+    radius: f32,
+    arrive_distance: f32,
+
+    destination: Option<Vector3<f32>>,
+    path: Vec<Vector3<f32>>,
+    // The corner of the path that is being moved to
+    path_index: usize,
+    // The destination changed and a new path has to be found
+    needs_path: bool,
+}
+
+impl NavAgent {
+    /// Creates a nav agent
+    ///
+    /// # Arguments
+    ///
+    /// * `speed` - How far the agent moves every second
+    pub fn new(speed: f32) -> Self {
+        Self {
+            speed,
+            radius: 0.5,
+            arrive_distance: DEFAULT_ARRIVE_DISTANCE,
+            destination: None,
+            path: Vec::new(),
+            path_index: 0,
+            needs_path: false,
+        }
+    }
+
+    /// Sets how close other agents can get before they are pushed away
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// Sets how close the agent has to get to each corner of its path
+    pub fn with_arrive_distance(mut self, arrive_distance: f32) -> Self {
+        self.arrive_distance = arrive_distance;
+        self
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    pub fn get_speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn set_radius(&mut self, radius: f32) {
+        self.radius = radius;
+    }
+
+    pub fn get_radius(&self) -> f32 {
+        self.radius
+    }
+
+    /// Sends the agent to a point, the path is found on the next update
+    pub fn set_destination(&mut self, destination: Vector3<f32>) {
+        self.destination = Some(destination);
+        self.needs_path = true;
+    }
+
+    /// Stops the agent where it is
+    pub fn clear_destination(&mut self) {
+        self.destination = None;
+        self.path.clear();
+        self.path_index = 0;
+        self.needs_path = false;
+    }
+
+    pub fn get_destination(&self) -> Option<&Vector3<f32>> {
+        self.destination.as_ref()
+    }
+
+    pub fn needs_path(&self) -> bool {
+        self.needs_path
+    }
+
+    /// Gives the agent the path to its destination
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The corners of the path or `None` if the destination can not be reached,
+    ///   the agent stops in that case
+    pub fn set_path(&mut self, path: Option<Vec<Vector3<f32>>>) {
+        match path {
+            Some(path) => {
+                self.path = path;
+                self.path_index = 0;
+                self.needs_path = false;
+            }
+            None => self.clear_destination(),
+        }
+    }
+
+    pub fn get_path(&self) -> &[Vector3<f32>] {
+        &self.path
+    }
+
+    /// Checks if the agent is on its way to a destination
+    pub fn is_moving(&self) -> bool {
+        self.destination.is_some()
+    }
+
+    /// Moves the agent along its path and away from other agents
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - Where the agent is
+    /// * `neighbors` - The position and radius of the other agents
+    /// * `delta_time` - Seconds since the last update
+    ///
+    /// # Returns
+    ///
+    /// The new position of the agent
+    pub fn steer(
+        &mut self,
+        position: Vector3<f32>,
+        neighbors: &[(Vector3<f32>, f32)],
+        delta_time: f32,
+    ) -> Vector3<f32> {
+        let mut step = Vector3::zero();
+
+        if !self.needs_path {
+            // Skip past the corners that have been reached
+            while let Some(corner) = self.path.get(self.path_index) {
+                if (corner - position).magnitude() > self.arrive_distance {
+                    break;
+                }
+                self.path_index += 1;
+            }
+
+            match self.path.get(self.path_index) {
+                Some(corner) => {
+                    let offset = corner - position;
+                    let distance = offset.magnitude();
+                    step = offset / distance * (self.speed * delta_time).min(distance);
+                }
+                None if self.destination.is_some() => self.clear_destination(),
+                None => {}
+            }
+        }
+
+        // Pushes out of the other agents along the ground
+        for (neighbor, radius) in neighbors {
+            let mut away = position - neighbor;
+            away.y = 0.0;
+            let distance = away.magnitude();
+            let overlap = self.radius + radius - distance;
+            if overlap > 0.0 && distance > f32::EPSILON {
+                step += away / distance * overlap.min(self.speed * delta_time);
+            }
+        }
+
+        position + step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_follow_path() {
+        let mut agent = NavAgent::new(1.0);
+        agent.set_destination(Vector3::new(2.0, 0.0, 0.0));
+        assert!(agent.needs_path());
+
+        agent.set_path(Some(vec![Vector3::zero(), Vector3::new(2.0, 0.0, 0.0)]));
+
+        let position = agent.steer(Vector3::zero(), &[], 1.0);
+        assert!((position - Vector3::new(1.0, 0.0, 0.0)).magnitude() < 1e-5);
+
+        let position = agent.steer(position, &[], 5.0);
+        assert!((position - Vector3::new(2.0, 0.0, 0.0)).magnitude() < 1e-5);
+
+        agent.steer(position, &[], 1.0);
+        assert!(!agent.is_moving());
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let mut agent = NavAgent::new(1.0);
+        agent.set_destination(Vector3::new(2.0, 0.0, 0.0));
+        agent.set_path(None);
+
+        assert!(!agent.is_moving());
+    }
+
+    #[test]
+    fn test_avoidance() {
+        let mut agent = NavAgent::new(1.0).with_radius(0.5);
+        let position = agent.steer(Vector3::zero(), &[(Vector3::new(0.5, 0.0, 0.0), 0.5)], 1.0);
+
+        assert!(position.x < 0.0);
+    }
+}
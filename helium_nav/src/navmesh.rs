@@ -0,0 +1,506 @@
+use cgmath::{InnerSpace, MetricSpace, Vector2, Vector3};
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    fs, io,
+    path::Path,
+};
+
+// Vertices closer than this are treated as the same vertex when the mesh is built
+const WELD_DISTANCE: f32 = 1e-3;
+
+// The triangle under a point has to be within this height of it
+const MAX_POINT_HEIGHT: f32 = 2.0;
+
+#[derive(Clone, Debug)]
+struct NavTriangle {
+    indices: [usize; 3],
+    center: Vector3<f32>,
+    // Neighboring triangle with the two vertices of the shared edge
+    neighbors: Vec<(usize, [usize; 2])>,
+}
+
+// Entry in the open set of the path search, ordered so the cheapest is popped first
+#[derive(Clone, Copy, Debug)]
+struct SearchNode {
+    triangle: usize,
+    estimate: f32,
+}
+
+impl PartialEq for SearchNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.estimate == other.estimate
+    }
+}
+
+impl Eq for SearchNode {}
+
+impl PartialOrd for SearchNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SearchNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+fn flatten(point: Vector3<f32>) -> Vector2<f32> {
+    Vector2::new(point.x, point.z)
+}
+
+// Twice the signed area of the triangle seen from above, positive when c is to the
+// left of the line from a to b
+fn signed_area(a: Vector3<f32>, b: Vector3<f32>, c: Vector3<f32>) -> f32 {
+    let (u, v) = (flatten(b - a), flatten(c - a));
+    u.x * v.y - u.y * v.x
+}
+
+/// The walkable surfaces of a level as connected triangles that agents find paths over
+#[derive(Clone, Debug, Default)]
+pub struct NavMesh {
+    vertices: Vec<Vector3<f32>>,
+    triangles: Vec<NavTriangle>,
+}
+
+impl NavMesh {
+    /// Creates a nav mesh from triangles, triangles that share an edge are connected
+    ///
+    /// # Arguments
+    ///
+    /// * `vertices` - Positions of the corners of the triangles
+    /// * `indices` - The three vertices of each triangle
+    pub fn from_triangles(vertices: &[Vector3<f32>], indices: &[[usize; 3]]) -> Self {
+        // Vertices in the same place are merged so separate meshes are connected
+        let mut welded = HashMap::new();
+        let mut mesh_vertices = Vec::new();
+        let remap = vertices
+            .iter()
+            .map(|vertex| {
+                let key = (vertex / WELD_DISTANCE).map(|value| value.round() as i64);
+                *welded.entry((key.x, key.y, key.z)).or_insert_with(|| {
+                    mesh_vertices.push(*vertex);
+                    mesh_vertices.len() - 1
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut triangles = indices
+            .iter()
+            .map(|triangle| triangle.map(|index| remap[index]))
+            // Triangles that were welded down to a line can not be walked on
+            .filter(|[a, b, c]| a != b && b != c && a != c)
+            .map(|indices| NavTriangle {
+                indices,
+                center: indices
+                    .iter()
+                    .map(|index| mesh_vertices[*index])
+                    .sum::<Vector3<f32>>()
+                    / 3.0,
+                neighbors: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut edges: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (triangle_index, triangle) in triangles.iter().enumerate() {
+            for edge in 0..3 {
+                let (a, b) = (triangle.indices[edge], triangle.indices[(edge + 1) % 3]);
+                edges
+                    .entry((a.min(b), a.max(b)))
+                    .or_default()
+                    .push(triangle_index);
+            }
+        }
+
+        for ((a, b), sharing) in edges {
+            for &triangle in sharing.iter() {
+                for &neighbor in sharing.iter().filter(|neighbor| **neighbor != triangle) {
+                    triangles[triangle].neighbors.push((neighbor, [a, b]));
+                }
+            }
+        }
+
+        Self {
+            vertices: mesh_vertices,
+            triangles,
+        }
+    }
+
+    /// Bakes a nav mesh from level geometry, only triangles that are flat enough to walk
+    /// on are kept
+    ///
+    /// # Arguments
+    ///
+    /// * `vertices` - Positions of the corners of the triangles
+    /// * `indices` - The three vertices of each triangle
+    /// * `max_slope` - The steepest walkable slope in radians
+    pub fn bake(vertices: &[Vector3<f32>], indices: &[[usize; 3]], max_slope: f32) -> Self {
+        let min_normal_y = max_slope.cos();
+        let walkable = indices
+            .iter()
+            .copied()
+            .filter(|[a, b, c]| {
+                let normal = (vertices[*b] - vertices[*a]).cross(vertices[*c] - vertices[*a]);
+                normal.magnitude2() > f32::EPSILON && normal.normalize().y.abs() >= min_normal_y
+            })
+            .collect::<Vec<_>>();
+
+        Self::from_triangles(vertices, &walkable)
+    }
+
+    /// Loads a nav mesh from the vertices and faces of an obj file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Filepath of the obj file
+    pub fn load<P>(path: P) -> Result<Self, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid nav mesh line: {}", line),
+            )
+        };
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for line in fs::read_to_string(path)?.lines() {
+            let mut parts = line.split_whitespace();
+            match parts.next() {
+                Some("v") => {
+                    let position = parts
+                        .take(3)
+                        .map(|value| value.parse::<f32>().map_err(|_| invalid(line)))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if position.len() != 3 {
+                        return Err(invalid(line));
+                    }
+
+                    vertices.push(Vector3::new(position[0], position[1], position[2]));
+                }
+                Some("f") => {
+                    // Only the position of each face vertex is used, faces with more
+                    // than three vertices are split into a fan
+                    let face = parts
+                        .map(|vertex| {
+                            vertex
+                                .split('/')
+                                .next()
+                                .and_then(|index| index.parse::<usize>().ok())
+                                .filter(|index| (1..=vertices.len()).contains(index))
+                                .map(|index| index - 1)
+                                .ok_or_else(|| invalid(line))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    for corner in 1..face.len().saturating_sub(1) {
+                        indices.push([face[0], face[corner], face[corner + 1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self::from_triangles(&vertices, &indices))
+    }
+
+    /// Merges another nav mesh into this one, edges in the same place are connected
+    pub fn merge(&self, other: &NavMesh) -> Self {
+        let offset = self.vertices.len();
+        let vertices = [self.vertices.as_slice(), other.vertices.as_slice()].concat();
+        let indices = self
+            .triangles
+            .iter()
+            .map(|triangle| triangle.indices)
+            .chain(
+                other
+                    .triangles
+                    .iter()
+                    .map(|triangle| triangle.indices.map(|index| index + offset)),
+            )
+            .collect::<Vec<_>>();
+
+        Self::from_triangles(&vertices, &indices)
+    }
+
+    pub fn get_vertices(&self) -> &[Vector3<f32>] {
+        &self.vertices
+    }
+
+    /// The three vertex indices of every triangle
+    pub fn get_triangles(&self) -> Vec<[usize; 3]> {
+        self.triangles
+            .iter()
+            .map(|triangle| triangle.indices)
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    // Height of the triangle at a point seen from above
+    fn height_at(&self, triangle: &NavTriangle, point: Vector3<f32>) -> f32 {
+        let [a, b, c] = triangle.indices.map(|index| self.vertices[index]);
+        let normal = (b - a).cross(c - a);
+        if normal.y.abs() <= f32::EPSILON {
+            return triangle.center.y;
+        }
+
+        a.y - (normal.x * (point.x - a.x) + normal.z * (point.z - a.z)) / normal.y
+    }
+
+    /// Finds the triangle under a point
+    ///
+    /// # Returns
+    ///
+    /// The index of the triangle or `None` if the point is not over the nav mesh
+    pub fn find_triangle(&self, point: Vector3<f32>) -> Option<usize> {
+        self.triangles
+            .iter()
+            .enumerate()
+            .filter(|(_, triangle)| {
+                let [a, b, c] = triangle.indices.map(|index| self.vertices[index]);
+                let areas = [
+                    signed_area(a, b, point),
+                    signed_area(b, c, point),
+                    signed_area(c, a, point),
+                ];
+
+                areas.iter().all(|area| *area >= 0.0) || areas.iter().all(|area| *area <= 0.0)
+            })
+            .map(|(index, triangle)| (index, (self.height_at(triangle, point) - point.y).abs()))
+            .filter(|(_, height)| *height <= MAX_POINT_HEIGHT)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+    }
+
+    /// Finds the shortest path between two points on the nav mesh
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The point the path starts at
+    /// * `end` - The point the path goes to
+    ///
+    /// # Returns
+    ///
+    /// The corners of the path from the start to the end or `None` if either point is
+    /// not on the nav mesh or there is no way between them
+    pub fn find_path(&self, start: Vector3<f32>, end: Vector3<f32>) -> Option<Vec<Vector3<f32>>> {
+        let start_triangle = self.find_triangle(start)?;
+        let end_triangle = self.find_triangle(end)?;
+
+        let corridor = self.find_corridor(start_triangle, end_triangle, end)?;
+        Some(self.string_pull(start, end, &corridor))
+    }
+
+    // A* search over the triangles, the cost is the distance between their centers
+    fn find_corridor(&self, start: usize, end: usize, goal: Vector3<f32>) -> Option<Vec<usize>> {
+        let mut open = BinaryHeap::new();
+        let mut costs = vec![f32::INFINITY; self.triangles.len()];
+        let mut came_from = vec![None; self.triangles.len()];
+
+        costs[start] = 0.0;
+        open.push(SearchNode {
+            triangle: start,
+            estimate: self.triangles[start].center.distance(goal),
+        });
+
+        while let Some(SearchNode { triangle, .. }) = open.pop() {
+            if triangle == end {
+                let mut corridor = vec![end];
+                while let Some(previous) = came_from[*corridor.last().unwrap()] {
+                    corridor.push(previous);
+                }
+                corridor.reverse();
+                return Some(corridor);
+            }
+
+            let center = self.triangles[triangle].center;
+            for (neighbor, _) in self.triangles[triangle].neighbors.iter() {
+                let neighbor_center = self.triangles[*neighbor].center;
+                let cost = costs[triangle] + center.distance(neighbor_center);
+                if cost < costs[*neighbor] {
+                    costs[*neighbor] = cost;
+                    came_from[*neighbor] = Some(triangle);
+                    open.push(SearchNode {
+                        triangle: *neighbor,
+                        estimate: cost + neighbor_center.distance(goal),
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    // Pulls the path through the corridor tight so it only turns at corners, this is the
+    // simple stupid funnel algorithm
+    fn string_pull(
+        &self,
+        start: Vector3<f32>,
+        end: Vector3<f32>,
+        corridor: &[usize],
+    ) -> Vec<Vector3<f32>> {
+        // The left and right side of each edge crossed on the way
+        let mut portals = vec![(start, start)];
+        for pair in corridor.windows(2) {
+            let (from, to) = (&self.triangles[pair[0]], &self.triangles[pair[1]]);
+            let [a, b] = from
+                .neighbors
+                .iter()
+                .find(|(neighbor, _)| *neighbor == pair[1])
+                .map(|(_, edge)| edge.map(|index| self.vertices[index]))
+                .unwrap();
+
+            if signed_area(from.center, to.center, a) > 0.0 {
+                portals.push((a, b));
+            } else {
+                portals.push((b, a));
+            }
+        }
+        portals.push((end, end));
+
+        let mut path = vec![start];
+        let (mut apex, mut left, mut right) = (start, start, start);
+        let (mut left_index, mut right_index) = (0, 0);
+
+        let mut index = 1;
+        while index < portals.len() {
+            let (portal_left, portal_right) = portals[index];
+
+            // Tighten the right side of the funnel
+            if signed_area(apex, right, portal_right) >= 0.0 {
+                if apex == right || signed_area(apex, left, portal_right) < 0.0 {
+                    right = portal_right;
+                    right_index = index;
+                } else {
+                    // The right side crossed the left so the left corner is on the path
+                    path.push(left);
+                    apex = left;
+                    let apex_index = left_index;
+                    (left, right) = (apex, apex);
+                    (left_index, right_index) = (apex_index, apex_index);
+                    index = apex_index + 1;
+                    continue;
+                }
+            }
+
+            // Tighten the left side of the funnel
+            if signed_area(apex, left, portal_left) <= 0.0 {
+                if apex == left || signed_area(apex, right, portal_left) > 0.0 {
+                    left = portal_left;
+                    left_index = index;
+                } else {
+                    // The left side crossed the right so the right corner is on the path
+                    path.push(right);
+                    apex = right;
+                    let apex_index = right_index;
+                    (left, right) = (apex, apex);
+                    (left_index, right_index) = (apex_index, apex_index);
+                    index = apex_index + 1;
+                    continue;
+                }
+            }
+
+            index += 1;
+        }
+
+        if path.last() != Some(&end) {
+            path.push(end);
+        }
+
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An L shaped floor of three squares, the corner square is at the origin
+    fn l_shape() -> NavMesh {
+        let squares = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)];
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for (x, z) in squares {
+            let base = vertices.len();
+            vertices.extend([
+                Vector3::new(x, 0.0, z),
+                Vector3::new(x + 1.0, 0.0, z),
+                Vector3::new(x + 1.0, 0.0, z + 1.0),
+                Vector3::new(x, 0.0, z + 1.0),
+            ]);
+            indices.push([base, base + 1, base + 2]);
+            indices.push([base, base + 2, base + 3]);
+        }
+
+        NavMesh::from_triangles(&vertices, &indices)
+    }
+
+    #[test]
+    fn test_welding() {
+        let mesh = l_shape();
+
+        assert_eq!(mesh.get_vertices().len(), 8);
+        assert_eq!(mesh.get_triangles().len(), 6);
+    }
+
+    #[test]
+    fn test_find_triangle() {
+        let mesh = l_shape();
+
+        assert!(mesh.find_triangle(Vector3::new(0.5, 0.0, 0.5)).is_some());
+        assert!(mesh.find_triangle(Vector3::new(1.5, 0.0, 1.5)).is_none());
+        assert!(mesh.find_triangle(Vector3::new(0.5, 10.0, 0.5)).is_none());
+    }
+
+    #[test]
+    fn test_path_around_corner() {
+        let mesh = l_shape();
+        let path = mesh
+            .find_path(Vector3::new(1.8, 0.0, 0.5), Vector3::new(0.5, 0.0, 1.8))
+            .unwrap();
+
+        // The path bends once at the inside corner of the L
+        assert_eq!(path.len(), 3);
+        assert!(path[1].distance(Vector3::new(1.0, 0.0, 1.0)) < 1e-5);
+    }
+
+    #[test]
+    fn test_straight_path() {
+        let mesh = l_shape();
+        let path = mesh
+            .find_path(Vector3::new(0.5, 0.0, 0.5), Vector3::new(1.5, 0.0, 0.5))
+            .unwrap();
+
+        assert_eq!(path.len(), 2);
+    }
+
+    #[test]
+    fn test_no_path() {
+        let mesh = l_shape();
+
+        assert!(mesh
+            .find_path(Vector3::new(0.5, 0.0, 0.5), Vector3::new(5.0, 0.0, 5.0))
+            .is_none());
+    }
+
+    #[test]
+    fn test_bake_slope() {
+        let vertices = [
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            // A wall
+            Vector3::new(0.0, 1.0, 0.0),
+        ];
+        let mesh = NavMesh::bake(&vertices, &[[0, 2, 1], [0, 1, 3]], 45f32.to_radians());
+
+        assert_eq!(mesh.get_triangles().len(), 1);
+    }
+}
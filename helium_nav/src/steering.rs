@@ -0,0 +1,431 @@
+use cgmath::{InnerSpace, Vector3, Zero};
+
+// Clamps the length of a vector
+fn truncate(vector: Vector3<f32>, max_length: f32) -> Vector3<f32> {
+    let length = vector.magnitude();
+    if length > max_length && length > f32::EPSILON {
+        vector / length * max_length
+    } else {
+        vector
+    }
+}
+
+// The change in velocity that turns the current velocity towards a direction at full speed
+fn steer_towards(direction: Vector3<f32>, velocity: Vector3<f32>, max_speed: f32) -> Vector3<f32> {
+    if direction.magnitude2() <= f32::EPSILON {
+        return -velocity;
+    }
+
+    direction.normalize() * max_speed - velocity
+}
+
+/// Moves an entity with the forces of its steering behaviors, the behaviors are the
+/// `Seek`, `Flee`, `Wander`, `FollowPath` and `Separation` components on the same entity
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Steering {
+    max_speed: f32,
+    max_acceleration: f32,
+    velocity: Vector3<f32>,
+}
+
+impl Steering {
+    /// Creates a steering body
+    ///
+    /// # Arguments
+    ///
+    /// * `max_speed` - The fastest the entity moves every second
+    /// * `max_acceleration` - How quickly the entity can change its velocity every second
+    pub fn new(max_speed: f32, max_acceleration: f32) -> Self {
+        Self {
+            max_speed,
+            max_acceleration,
+            velocity: Vector3::zero(),
+        }
+    }
+
+    pub fn get_max_speed(&self) -> f32 {
+        self.max_speed
+    }
+
+    pub fn set_max_speed(&mut self, max_speed: f32) {
+        self.max_speed = max_speed;
+    }
+
+    pub fn get_max_acceleration(&self) -> f32 {
+        self.max_acceleration
+    }
+
+    pub fn set_max_acceleration(&mut self, max_acceleration: f32) {
+        self.max_acceleration = max_acceleration;
+    }
+
+    pub fn get_velocity(&self) -> &Vector3<f32> {
+        &self.velocity
+    }
+
+    pub fn set_velocity(&mut self, velocity: Vector3<f32>) {
+        self.velocity = truncate(velocity, self.max_speed);
+    }
+
+    /// Changes the velocity by the combined force of the behaviors
+    ///
+    /// # Arguments
+    ///
+    /// * `force` - The sum of the weighted forces of the behaviors
+    /// * `delta_time` - Seconds since the last update
+    ///
+    /// # Returns
+    ///
+    /// How far the entity moves this update
+    pub fn apply(&mut self, force: Vector3<f32>, delta_time: f32) -> Vector3<f32> {
+        let acceleration = truncate(force, self.max_acceleration);
+        self.velocity = truncate(self.velocity + acceleration * delta_time, self.max_speed);
+        self.velocity * delta_time
+    }
+}
+
+/// Steers towards a point and slows down when it gets close
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Seek {
+    target: Vector3<f32>,
+    weight: f32,
+    // Distance where slowing down starts
+    arrive_radius: f32,
+}
+
+impl Seek {
+    pub fn new(target: Vector3<f32>) -> Self {
+        Self {
+            target,
+            weight: 1.0,
+            arrive_radius: 0.0,
+        }
+    }
+
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Slows down inside a distance of the target so it stops on it instead of passing it
+    pub fn with_arrive_radius(mut self, arrive_radius: f32) -> Self {
+        self.arrive_radius = arrive_radius;
+        self
+    }
+
+    pub fn set_target(&mut self, target: Vector3<f32>) {
+        self.target = target;
+    }
+
+    pub fn get_target(&self) -> &Vector3<f32> {
+        &self.target
+    }
+
+    pub fn force(&self, position: Vector3<f32>, steering: &Steering) -> Vector3<f32> {
+        let offset = self.target - position;
+        let distance = offset.magnitude();
+
+        let speed = if distance < self.arrive_radius {
+            steering.max_speed * distance / self.arrive_radius
+        } else {
+            steering.max_speed
+        };
+
+        steer_towards(offset, steering.velocity, speed) * self.weight
+    }
+}
+
+/// Steers away from a point while it is close
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Flee {
+    target: Vector3<f32>,
+    weight: f32,
+    // Distance where fleeing stops
+    panic_distance: f32,
+}
+
+impl Flee {
+    pub fn new(target: Vector3<f32>, panic_distance: f32) -> Self {
+        Self {
+            target,
+            weight: 1.0,
+            panic_distance,
+        }
+    }
+
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn set_target(&mut self, target: Vector3<f32>) {
+        self.target = target;
+    }
+
+    pub fn get_target(&self) -> &Vector3<f32> {
+        &self.target
+    }
+
+    pub fn force(&self, position: Vector3<f32>, steering: &Steering) -> Vector3<f32> {
+        let offset = position - self.target;
+        if offset.magnitude() > self.panic_distance {
+            return Vector3::zero();
+        }
+
+        steer_towards(offset, steering.velocity, steering.max_speed) * self.weight
+    }
+}
+
+/// Wanders around by steering towards a point that moves randomly on a circle in front
+/// of the entity
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wander {
+    weight: f32,
+    // Distance of the circle in front of the entity
+    distance: f32,
+    radius: f32,
+    // How far the angle on the circle moves every second in radians
+    jitter: f32,
+    angle: f32,
+    seed: u32,
+}
+
+impl Wander {
+    pub fn new(distance: f32, radius: f32, jitter: f32) -> Self {
+        Self {
+            weight: 1.0,
+            distance,
+            radius,
+            jitter,
+            angle: 0.0,
+            seed: 0x9E37_79B9,
+        }
+    }
+
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Starts the random wandering from a seed so entities do not wander the same way
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed.max(1);
+        self
+    }
+
+    // A random value between -1 and 1 from a xorshift generator
+    fn next_random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+
+    pub fn force(&mut self, steering: &Steering, delta_time: f32) -> Vector3<f32> {
+        self.angle += self.next_random() * self.jitter * delta_time;
+
+        let forward = if steering.velocity.magnitude2() > f32::EPSILON {
+            steering.velocity.normalize()
+        } else {
+            Vector3::unit_z()
+        };
+
+        // Wandering stays on the ground
+        let offset = Vector3::new(self.angle.cos(), 0.0, self.angle.sin()) * self.radius;
+        steer_towards(
+            forward * self.distance + offset,
+            steering.velocity,
+            steering.max_speed,
+        ) * self.weight
+    }
+}
+
+/// Steers through a list of points in order
+#[derive(Clone, Debug, PartialEq)]
+pub struct FollowPath {
+    path: Vec<Vector3<f32>>,
+    index: usize,
+    weight: f32,
+    // Distance from a point where the next one is steered to
+    arrive_distance: f32,
+    looping: bool,
+}
+
+impl FollowPath {
+    pub fn new(path: Vec<Vector3<f32>>) -> Self {
+        Self {
+            path,
+            index: 0,
+            weight: 1.0,
+            arrive_distance: 0.5,
+            looping: false,
+        }
+    }
+
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn with_arrive_distance(mut self, arrive_distance: f32) -> Self {
+        self.arrive_distance = arrive_distance;
+        self
+    }
+
+    /// Starts the path again from the first point after the last one is reached
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn set_path(&mut self, path: Vec<Vector3<f32>>) {
+        self.path = path;
+        self.index = 0;
+    }
+
+    pub fn get_path(&self) -> &[Vector3<f32>] {
+        &self.path
+    }
+
+    /// Checks if the last point of the path has been reached
+    pub fn is_finished(&self) -> bool {
+        self.index >= self.path.len()
+    }
+
+    pub fn force(&mut self, position: Vector3<f32>, steering: &Steering) -> Vector3<f32> {
+        while let Some(point) = self.path.get(self.index) {
+            if (point - position).magnitude() > self.arrive_distance {
+                break;
+            }
+
+            self.index += 1;
+            if self.looping && self.index >= self.path.len() {
+                self.index = 0;
+                break;
+            }
+        }
+
+        match self.path.get(self.index) {
+            Some(point) => {
+                let seek = Seek::new(*point).with_weight(self.weight);
+                // Arrives at the last point instead of passing it
+                if !self.looping && self.index + 1 == self.path.len() {
+                    seek.with_arrive_radius(self.arrive_distance * 4.0)
+                        .force(position, steering)
+                } else {
+                    seek.force(position, steering)
+                }
+            }
+            None => -steering.velocity * self.weight,
+        }
+    }
+}
+
+/// Steers away from other steering entities that are too close
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Separation {
+    radius: f32,
+    weight: f32,
+}
+
+impl Separation {
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius,
+            weight: 1.0,
+        }
+    }
+
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn get_radius(&self) -> f32 {
+        self.radius
+    }
+
+    /// # Arguments
+    ///
+    /// * `position` - Where the entity is
+    /// * `neighbors` - Where the other steering entities are
+    /// * `steering` - The steering body of the entity
+    pub fn force(
+        &self,
+        position: Vector3<f32>,
+        neighbors: &[Vector3<f32>],
+        steering: &Steering,
+    ) -> Vector3<f32> {
+        // Closer neighbors push harder
+        let away = neighbors
+            .iter()
+            .map(|neighbor| position - neighbor)
+            .filter(|offset| {
+                let distance = offset.magnitude();
+                distance > f32::EPSILON && distance < self.radius
+            })
+            .map(|offset| offset.normalize() * (1.0 - offset.magnitude() / self.radius))
+            .sum::<Vector3<f32>>();
+
+        if away.magnitude2() <= f32::EPSILON {
+            return Vector3::zero();
+        }
+
+        steer_towards(away, steering.velocity, steering.max_speed) * self.weight
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        let mut steering = Steering::new(2.0, 1.0);
+        steering.apply(Vector3::new(100.0, 0.0, 0.0), 1.0);
+        assert!((steering.get_velocity().x - 1.0).abs() < 1e-5);
+
+        for _ in 0..10 {
+            steering.apply(Vector3::new(100.0, 0.0, 0.0), 1.0);
+        }
+        assert!((steering.get_velocity().magnitude() - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_seek_flee() {
+        let steering = Steering::new(1.0, 1.0);
+        let target = Vector3::new(5.0, 0.0, 0.0);
+
+        assert!(Seek::new(target).force(Vector3::zero(), &steering).x > 0.0);
+        assert!(Flee::new(target, 10.0).force(Vector3::zero(), &steering).x < 0.0);
+        assert_eq!(
+            Flee::new(target, 1.0).force(Vector3::zero(), &steering),
+            Vector3::zero()
+        );
+    }
+
+    #[test]
+    fn test_follow_path() {
+        let steering = Steering::new(1.0, 1.0);
+        let mut follow = FollowPath::new(vec![Vector3::zero(), Vector3::new(0.0, 0.0, 5.0)]);
+
+        // The first point is already reached so it steers to the second
+        assert!(follow.force(Vector3::zero(), &steering).z > 0.0);
+        assert!(!follow.is_finished());
+
+        follow.force(Vector3::new(0.0, 0.0, 5.0), &steering);
+        assert!(follow.is_finished());
+    }
+
+    #[test]
+    fn test_separation() {
+        let steering = Steering::new(1.0, 1.0);
+        let separation = Separation::new(2.0);
+
+        let force = separation.force(Vector3::zero(), &[Vector3::new(1.0, 0.0, 0.0)], &steering);
+        assert!(force.x < 0.0);
+
+        let force = separation.force(Vector3::zero(), &[Vector3::new(3.0, 0.0, 0.0)], &steering);
+        assert_eq!(force, Vector3::zero());
+    }
+}
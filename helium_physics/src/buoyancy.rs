@@ -0,0 +1,243 @@
+use cgmath::{Vector2, Vector3};
+
+use crate::gravity::Gravity;
+
+/// A body of water that floats the entities with a `Buoyancy` inside of it, either an
+/// ocean that covers everything below a height or a box like a pool
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WaterVolume {
+    min: Vector3<f32>,
+    max: Vector3<f32>,
+}
+
+impl WaterVolume {
+    /// Creates a box of water, the top of the box is the surface
+    ///
+    /// # Arguments
+    ///
+    /// * `min` - The corner of the box with the lowest coordinates in world space
+    /// * `max` - The corner of the box with the highest coordinates in world space
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        Self {
+            min: Vector3::new(min.x.min(max.x), min.y.min(max.y), min.z.min(max.z)),
+            max: Vector3::new(min.x.max(max.x), min.y.max(max.y), min.z.max(max.z)),
+        }
+    }
+
+    /// Creates water that fills everything below a height, like an ocean
+    pub fn plane(surface_height: f32) -> Self {
+        Self {
+            min: Vector3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+            max: Vector3::new(f32::INFINITY, surface_height, f32::INFINITY),
+        }
+    }
+
+    pub fn get_surface_height(&self) -> f32 {
+        self.max.y
+    }
+
+    pub fn get_min(&self) -> &Vector3<f32> {
+        &self.min
+    }
+
+    pub fn get_max(&self) -> &Vector3<f32> {
+        &self.max
+    }
+
+    /// How much of a body is under the water
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - The center of the body on the ground plane, x and z in world space
+    /// * `bottom` - The height of the bottom of the body
+    /// * `top` - The height of the top of the body
+    ///
+    /// # Returns
+    ///
+    /// The fraction of the height of the body that is under the water, from 0 to 1
+    pub fn submerged_fraction(&self, center: Vector2<f32>, bottom: f32, top: f32) -> f32 {
+        let inside = (self.min.x..=self.max.x).contains(&center.x)
+            && (self.min.z..=self.max.z).contains(&center.y);
+        if !inside {
+            return 0.0;
+        }
+
+        let height = top - bottom;
+        let wet = top.min(self.max.y) - bottom.max(self.min.y);
+        if height <= f32::EPSILON {
+            // A flat body is either in the water or out of it
+            return if wet >= 0.0 { 1.0 } else { 0.0 };
+        }
+
+        (wet / height).clamp(0.0, 1.0)
+    }
+}
+
+/// Makes an entity with `Gravity` float in water volumes, it is pushed up against gravity
+/// by how much of it is under the water and slowed down by the drag of the water
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Buoyancy {
+    buoyancy: f32,
+    drag: f32,
+}
+
+impl Default for Buoyancy {
+    /// Floats half under the water with enough drag to settle after a few bobs
+    fn default() -> Self {
+        Self {
+            buoyancy: 2.0,
+            drag: 1.5,
+        }
+    }
+}
+
+impl Buoyancy {
+    /// Creates a buoyancy with the default drag
+    ///
+    /// # Arguments
+    ///
+    /// * `buoyancy` - The push of the water when the entity is fully under it as a multiple
+    ///   of its gravity, it floats with `1 / buoyancy` of its height under the water and
+    ///   sinks at 1 or less
+    pub fn new(buoyancy: f32) -> Self {
+        Self {
+            buoyancy: buoyancy.max(0.0),
+            ..Default::default()
+        }
+    }
+
+    /// Sets how much of the velocity is lost every second when fully under the water
+    pub fn with_drag(mut self, drag: f32) -> Self {
+        self.drag = drag.max(0.0);
+        self
+    }
+
+    pub fn get_buoyancy(&self) -> f32 {
+        self.buoyancy
+    }
+
+    pub fn get_drag(&self) -> f32 {
+        self.drag
+    }
+
+    /// Pushes the velocity against the gravity and drags it by how much of the entity is
+    /// under the water
+    ///
+    /// # Arguments
+    ///
+    /// * `gravity` - The gravity with the velocity of the entity
+    /// * `submerged` - The fraction of the entity under the water from 0 to 1
+    /// * `delta_time` - The seconds since the last step
+    pub fn apply(&self, gravity: &mut Gravity, submerged: f32, delta_time: f32) {
+        let submerged = submerged.clamp(0.0, 1.0);
+        if submerged <= 0.0 {
+            return;
+        }
+
+        gravity.velocity -= gravity.get_gravity() * self.buoyancy * submerged * delta_time;
+        gravity.velocity *= (1.0 - self.drag * submerged * delta_time).max(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool() -> WaterVolume {
+        WaterVolume::new(
+            Vector3::new(10.0, 2.0, 10.0),
+            Vector3::new(-10.0, -3.0, -10.0),
+        )
+    }
+
+    #[test]
+    fn test_outside_volume() {
+        let pool = pool();
+
+        assert_eq!(
+            pool.submerged_fraction(Vector2::new(11.0, 0.0), -1.0, 1.0),
+            0.0
+        );
+        assert_eq!(
+            pool.submerged_fraction(Vector2::new(0.0, -11.0), -1.0, 1.0),
+            0.0
+        );
+        // Above and below the water
+        assert_eq!(
+            pool.submerged_fraction(Vector2::new(0.0, 0.0), 3.0, 4.0),
+            0.0
+        );
+        assert_eq!(
+            pool.submerged_fraction(Vector2::new(0.0, 0.0), -5.0, -4.0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_submerged_fraction() {
+        let pool = pool();
+        let center = Vector2::new(0.0, 0.0);
+
+        assert_eq!(pool.get_min(), &Vector3::new(-10.0, -3.0, -10.0));
+        assert_eq!(pool.get_surface_height(), 2.0);
+        assert_eq!(pool.submerged_fraction(center, 1.0, 3.0), 0.5);
+        assert_eq!(pool.submerged_fraction(center, -4.0, -2.0), 0.5);
+        assert_eq!(pool.submerged_fraction(center, -1.0, 1.0), 1.0);
+        // A body taller than the pool is only as wet as the pool is deep
+        assert_eq!(pool.submerged_fraction(center, -4.0, 6.0), 0.5);
+    }
+
+    #[test]
+    fn test_flat_body() {
+        let pool = pool();
+        let center = Vector2::new(0.0, 0.0);
+
+        assert_eq!(pool.submerged_fraction(center, 0.0, 0.0), 1.0);
+        assert_eq!(pool.submerged_fraction(center, 2.0, 2.0), 1.0);
+        assert_eq!(pool.submerged_fraction(center, 2.5, 2.5), 0.0);
+    }
+
+    #[test]
+    fn test_plane() {
+        let ocean = WaterVolume::plane(1.0);
+        let far = Vector2::new(1e30, -1e30);
+
+        assert_eq!(ocean.get_surface_height(), 1.0);
+        assert_eq!(ocean.submerged_fraction(far, 0.0, 2.0), 0.5);
+        assert_eq!(ocean.submerged_fraction(far, -1e30, -1e29), 1.0);
+        assert_eq!(ocean.submerged_fraction(far, 2.0, 3.0), 0.0);
+    }
+
+    #[test]
+    fn test_apply() {
+        let buoyancy = Buoyancy::new(2.0).with_drag(0.0);
+        let mut gravity = Gravity::new(Vector3::new(0.0, -10.0, 0.0));
+
+        buoyancy.apply(&mut gravity, 0.5, 0.1);
+        assert_eq!(gravity.velocity, Vector3::new(0.0, 1.0, 0.0));
+
+        // Nothing happens out of the water
+        buoyancy.apply(&mut gravity, 0.0, 0.1);
+        assert_eq!(gravity.velocity, Vector3::new(0.0, 1.0, 0.0));
+
+        // At a buoyancy of 2 half under the water the push cancels the gravity
+        gravity.accelerate(0.1);
+        buoyancy.apply(&mut gravity, 0.5, 0.1);
+        assert_eq!(gravity.velocity, Vector3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_drag_does_not_reverse() {
+        let buoyancy = Buoyancy::new(0.0).with_drag(100.0);
+        let mut gravity = Gravity::new(Vector3::new(0.0, -10.0, 0.0));
+        gravity.velocity = Vector3::new(3.0, -4.0, 5.0);
+
+        buoyancy.apply(&mut gravity, 1.0, 0.5);
+        assert_eq!(gravity.velocity, Vector3::new(0.0, 0.0, 0.0));
+
+        let buoyancy = buoyancy.with_drag(1.0);
+        gravity.velocity = Vector3::new(2.0, 0.0, 0.0);
+        buoyancy.apply(&mut gravity, 0.5, 0.5);
+        assert_eq!(gravity.velocity, Vector3::new(1.5, 0.0, 0.0));
+    }
+}
@@ -1,6 +1,6 @@
 use std::time::Instant;
 
-use cgmath::{Vector3, Zero};
+use cgmath::{InnerSpace, Vector3, Zero};
 
 pub struct Gravity {
     pub velocity: Vector3<f32>,
@@ -16,7 +16,12 @@ impl Gravity {
     }
 
     pub fn update_gravity(&mut self, delta_time: &Instant) -> &mut Self {
-        self.velocity += self.acceleration * delta_time.elapsed().as_secs_f32();
+        self.accelerate(delta_time.elapsed().as_secs_f32())
+    }
+
+    /// Speeds up the velocity by the gravity over a number of seconds
+    pub fn accelerate(&mut self, delta_time: f32) -> &mut Self {
+        self.velocity += self.acceleration * delta_time;
         self
     }
 
@@ -25,8 +30,34 @@ impl Gravity {
         self
     }
 
+    pub fn get_gravity(&self) -> Vector3<f32> {
+        self.acceleration
+    }
+
     pub fn kill_velocity(&mut self) -> &mut Self {
         self.velocity = Vector3::zero();
         self
     }
+
+    /// Removes the part of the velocity that moves into a surface so the object stops
+    /// falling through it but keeps sliding along it
+    ///
+    /// # Arguments
+    ///
+    /// * `normal` - Normal of the surface that is touched, pointing away from it
+    pub fn kill_velocity_along(&mut self, normal: Vector3<f32>) -> &mut Self {
+        if normal.magnitude2() <= f32::EPSILON {
+            return self;
+        }
+
+        let normal = normal.normalize();
+        let speed_into = self.velocity.dot(normal);
+
+        // Moving away from the surface is left alone
+        if speed_into < 0.0 {
+            self.velocity -= normal * speed_into;
+        }
+
+        self
+    }
 }
@@ -1 +1,3 @@
+pub mod buoyancy;
 pub mod gravity;
+pub mod vehicle;
@@ -0,0 +1,629 @@
+use cgmath::{InnerSpace, Quaternion, Rad, Rotation, Rotation3, Vector3, Zero};
+
+// Fraction of the spin of the body that is lost every second so it settles down
+const ANGULAR_DAMPING: f32 = 0.5;
+// How much stiffer the suspension gets once it is pushed past its travel
+const BUMP_STOP_STIFFNESS: f32 = 10.0;
+// Wheels closer than this on the length of the car are on the same axle
+const AXLE_TOLERANCE: f32 = 0.05;
+
+// The mass that a push at a point of a body along a direction moves with, it is less than
+// the mass of the body since the push also turns it. The point and direction are relative
+// to the axes of the body
+fn contact_mass(
+    mass: f32,
+    inertia: Vector3<f32>,
+    lever: Vector3<f32>,
+    direction: Vector3<f32>,
+) -> f32 {
+    let arm = lever.cross(direction);
+    1.0 / (1.0 / mass
+        + arm.x * arm.x / inertia.x
+        + arm.y * arm.y / inertia.y
+        + arm.z * arm.z / inertia.z)
+}
+
+/// A wheel of a vehicle that holds the body up with a spring along a ray cast down from
+/// where it is mounted
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wheel {
+    position: Vector3<f32>,
+    steered: bool,
+    driven: bool,
+
+    // Length of the spring from the mount to the center of the wheel
+    suspension: f32,
+    compression: f32,
+    grounded: bool,
+    steering_angle: f32,
+    spin: f32,
+}
+
+impl Wheel {
+    fn new(position: Vector3<f32>, steered: bool, driven: bool, rest_length: f32) -> Self {
+        Self {
+            position,
+            steered,
+            driven,
+            suspension: rest_length,
+            compression: 0.0,
+            grounded: false,
+            steering_angle: 0.0,
+            spin: 0.0,
+        }
+    }
+
+    /// Where the suspension is mounted relative to the vehicle
+    pub fn get_position(&self) -> &Vector3<f32> {
+        &self.position
+    }
+
+    /// Where the center of the wheel is relative to the vehicle, it moves up and down
+    /// with the suspension
+    pub fn get_center(&self) -> Vector3<f32> {
+        self.position - Vector3::unit_y() * self.suspension
+    }
+
+    /// How far the spring is pushed in from its rest length
+    pub fn get_compression(&self) -> f32 {
+        self.compression
+    }
+
+    /// The angle the wheel is turned to around the up of the vehicle in radians
+    pub fn get_steering_angle(&self) -> f32 {
+        self.steering_angle
+    }
+
+    /// The angle the wheel has rolled in radians, used to turn the model of the wheel
+    pub fn get_spin(&self) -> f32 {
+        self.spin
+    }
+
+    pub fn is_steered(&self) -> bool {
+        self.steered
+    }
+
+    pub fn is_driven(&self) -> bool {
+        self.driven
+    }
+
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+}
+
+/// An arcade vehicle that drives on its wheels, the wheels cast rays down to find the
+/// ground and push the body up with springs, and the engine, brakes, and grip of the
+/// wheels push it along the ground. The vehicle moves its own body and falls with its own
+/// gravity so it should not also have a `Gravity`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Vehicle {
+    wheels: Vec<Wheel>,
+
+    mass: f32,
+    gravity: Vector3<f32>,
+    wheel_radius: f32,
+    suspension_length: f32,
+    stiffness: f32,
+    damping: f32,
+    engine_force: f32,
+    brake_force: f32,
+    max_steering: f32,
+    friction: f32,
+    anti_roll: f32,
+    rolling_resistance: f32,
+    drag: f32,
+
+    // Inputs from -1 to 1 for the throttle and steering and from 0 to 1 for the brake
+    throttle: f32,
+    brake: f32,
+    steering: f32,
+
+    velocity: Vector3<f32>,
+    angular_velocity: Vector3<f32>,
+}
+
+impl Vehicle {
+    /// Creates a vehicle without wheels that is tuned like a small car
+    ///
+    /// # Arguments
+    ///
+    /// * `mass` - The mass of the vehicle in kilograms
+    pub fn new(mass: f32) -> Self {
+        Self {
+            wheels: Vec::new(),
+            mass: mass.max(1.0),
+            gravity: Vector3::new(0.0, -9.81, 0.0),
+            wheel_radius: 0.35,
+            suspension_length: 0.5,
+            stiffness: 30.0 * mass.max(1.0),
+            damping: 3.0 * mass.max(1.0),
+            engine_force: 8.0 * mass.max(1.0),
+            brake_force: 12.0 * mass.max(1.0),
+            max_steering: 35.0f32.to_radians(),
+            friction: 1.5,
+            anti_roll: 15.0 * mass.max(1.0),
+            rolling_resistance: 0.05 * mass.max(1.0),
+            drag: 0.4,
+            throttle: 0.0,
+            brake: 0.0,
+            steering: 0.0,
+            velocity: Vector3::zero(),
+            angular_velocity: Vector3::zero(),
+        }
+    }
+
+    /// Adds a wheel to the vehicle, wheels on either side at the same length are held
+    /// level by the anti roll bar
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - Where the suspension is mounted relative to the vehicle, the wheel
+    ///   hangs below it
+    /// * `steered` - Whether the wheel turns with the steering
+    /// * `driven` - Whether the engine pushes the vehicle with the wheel
+    pub fn with_wheel(mut self, position: Vector3<f32>, steered: bool, driven: bool) -> Self {
+        self.wheels.push(Wheel::new(
+            position,
+            steered,
+            driven,
+            self.suspension_length,
+        ));
+        self
+    }
+
+    /// Sets the acceleration the vehicle falls with
+    pub fn with_gravity(mut self, gravity: Vector3<f32>) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    pub fn with_wheel_radius(mut self, radius: f32) -> Self {
+        self.wheel_radius = radius.max(0.0);
+        self
+    }
+
+    /// Sets how far each spring reaches down to the wheel at rest and how stiff and damped
+    /// it is
+    ///
+    /// # Arguments
+    ///
+    /// * `length` - The rest length of the springs
+    /// * `stiffness` - The force of each spring for every unit it is pushed in
+    /// * `damping` - The force against the springs moving for every unit per second
+    pub fn with_suspension(mut self, length: f32, stiffness: f32, damping: f32) -> Self {
+        self.suspension_length = length.max(0.01);
+        self.stiffness = stiffness.max(0.0);
+        self.damping = damping.max(0.0);
+        for wheel in self.wheels.iter_mut() {
+            wheel.suspension = self.suspension_length;
+        }
+        self
+    }
+
+    /// Sets the force of the engine at full throttle, split between the driven wheels
+    pub fn with_engine_force(mut self, force: f32) -> Self {
+        self.engine_force = force.max(0.0);
+        self
+    }
+
+    /// Sets the force of the brakes when fully pressed, split between all the wheels
+    pub fn with_brake_force(mut self, force: f32) -> Self {
+        self.brake_force = force.max(0.0);
+        self
+    }
+
+    /// Sets how far the steered wheels turn at full steering in degrees
+    pub fn with_max_steering(mut self, degrees: f32) -> Self {
+        self.max_steering = degrees.max(0.0).to_radians();
+        self
+    }
+
+    /// Sets the grip of the wheels as how much of the weight on a wheel it can push
+    /// sideways with before it slides
+    pub fn with_friction(mut self, friction: f32) -> Self {
+        self.friction = friction.max(0.0);
+        self
+    }
+
+    /// Sets the force that keeps the wheels on an axle compressed the same amount for
+    /// every unit they differ by, which keeps the body from leaning in turns
+    pub fn with_anti_roll(mut self, anti_roll: f32) -> Self {
+        self.anti_roll = anti_roll.max(0.0);
+        self
+    }
+
+    /// Sets the force that slows down the rolling wheels for every unit per second
+    pub fn with_rolling_resistance(mut self, resistance: f32) -> Self {
+        self.rolling_resistance = resistance.max(0.0);
+        self
+    }
+
+    /// Sets the air drag on the vehicle, which grows with the square of its speed
+    pub fn with_drag(mut self, drag: f32) -> Self {
+        self.drag = drag.max(0.0);
+        self
+    }
+
+    /// Sets the throttle from -1 in reverse to 1 forward
+    pub fn set_throttle(&mut self, throttle: f32) {
+        self.throttle = throttle.clamp(-1.0, 1.0);
+    }
+
+    pub fn get_throttle(&self) -> f32 {
+        self.throttle
+    }
+
+    /// Sets how hard the brakes are pressed from 0 to 1
+    pub fn set_brake(&mut self, brake: f32) {
+        self.brake = brake.clamp(0.0, 1.0);
+    }
+
+    pub fn get_brake(&self) -> f32 {
+        self.brake
+    }
+
+    /// Sets the steering from -1 for full left to 1 for full right
+    pub fn set_steering(&mut self, steering: f32) {
+        self.steering = steering.clamp(-1.0, 1.0);
+    }
+
+    pub fn get_steering(&self) -> f32 {
+        self.steering
+    }
+
+    pub fn get_wheels(&self) -> &[Wheel] {
+        &self.wheels
+    }
+
+    pub fn get_mass(&self) -> f32 {
+        self.mass
+    }
+
+    pub fn get_velocity(&self) -> &Vector3<f32> {
+        &self.velocity
+    }
+
+    pub fn set_velocity(&mut self, velocity: Vector3<f32>) {
+        self.velocity = velocity;
+    }
+
+    /// The speed the body is turning with around each world axis in radians per second
+    pub fn get_angular_velocity(&self) -> &Vector3<f32> {
+        &self.angular_velocity
+    }
+
+    /// Stops the body from moving and turning, used when placing the vehicle
+    pub fn reset_motion(&mut self) {
+        self.velocity = Vector3::zero();
+        self.angular_velocity = Vector3::zero();
+    }
+
+    /// Whether any of the wheels are touching the ground
+    pub fn is_grounded(&self) -> bool {
+        self.wheels.iter().any(|wheel| wheel.grounded)
+    }
+
+    // The resistance of the body to turning around its own axes, approximated as a box
+    // that spans the wheels
+    fn inertia(&self) -> Vector3<f32> {
+        let (width, length) =
+            self.wheels
+                .iter()
+                .fold((0.0f32, 0.0f32), |(width, length), wheel| {
+                    (
+                        width.max(wheel.position.x.abs()),
+                        length.max(wheel.position.z.abs()),
+                    )
+                });
+        let height = self.suspension_length + self.wheel_radius;
+
+        Vector3::new(
+            height * height + length * length,
+            width * width + length * length,
+            width * width + height * height,
+        ) * (self.mass / 3.0)
+            + Vector3::new(1.0, 1.0, 1.0) * 0.1
+    }
+
+    /// Moves the vehicle by one step of the simulation
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position of the vehicle, moved by the step
+    /// * `rotation` - The rotation of the vehicle, turned by the step
+    /// * `delta_time` - The seconds of the step
+    /// * `raycast` - Casts a ray from an origin along a normalized direction up to a
+    ///   distance against the world, returning the distance to the hit and the normal of
+    ///   the surface that was hit
+    pub fn step(
+        &mut self,
+        position: &mut Vector3<f32>,
+        rotation: &mut Quaternion<f32>,
+        delta_time: f32,
+        mut raycast: impl FnMut(Vector3<f32>, Vector3<f32>, f32) -> Option<(f32, Vector3<f32>)>,
+    ) {
+        if delta_time <= 0.0 {
+            return;
+        }
+
+        let up = rotation.rotate_vector(Vector3::unit_y());
+        let forward = rotation.rotate_vector(-Vector3::unit_z());
+        let reach = self.suspension_length + self.wheel_radius;
+        let wheel_count = self.wheels.len().max(1) as f32;
+        let driven_count = self
+            .wheels
+            .iter()
+            .filter(|wheel| wheel.driven)
+            .count()
+            .max(1) as f32;
+        let wheel_mass = self.mass / wheel_count;
+        let inverse = rotation.invert();
+        let inertia = self.inertia();
+
+        let mut force = self.gravity * self.mass;
+        let mut torque = Vector3::zero();
+
+        // The force of the spring and the point it pushes on for each wheel
+        let mut loads = Vec::with_capacity(self.wheels.len());
+        for wheel in self.wheels.iter_mut() {
+            let mount = *position + rotation.rotate_vector(wheel.position);
+            let previous_compression = wheel.compression;
+
+            wheel.steering_angle = if wheel.steered {
+                -self.steering * self.max_steering
+            } else {
+                0.0
+            };
+
+            let Some((distance, normal)) = raycast(mount, -up, reach) else {
+                wheel.grounded = false;
+                wheel.compression = 0.0;
+                wheel.suspension = self.suspension_length;
+                loads.push(None);
+                continue;
+            };
+
+            wheel.grounded = true;
+            wheel.compression = reach - distance;
+            wheel.suspension = (distance - self.wheel_radius).max(0.0);
+            let contact = mount - up * distance;
+            let lever = contact - *position;
+
+            // The spring gets much stiffer once the wheel is pushed up into the body
+            let travel = wheel.compression.min(self.suspension_length);
+            let bottomed = (wheel.compression - self.suspension_length).max(0.0);
+            let compression_speed = (wheel.compression - previous_compression) / delta_time;
+            let spring = (self.stiffness * travel
+                + self.stiffness * BUMP_STOP_STIFFNESS * bottomed
+                + self.damping * compression_speed)
+                .max(0.0);
+            loads.push(Some((spring, lever)));
+
+            // The wheel rolls along the ground it stands on
+            let heading =
+                Quaternion::from_axis_angle(up, Rad(wheel.steering_angle)).rotate_vector(forward);
+            let wheel_forward = heading - normal * heading.dot(normal);
+            if wheel_forward.magnitude2() <= f32::EPSILON {
+                continue;
+            }
+            let wheel_forward = wheel_forward.normalize();
+            let wheel_side = wheel_forward.cross(normal);
+
+            let contact_velocity = self.velocity + self.angular_velocity.cross(lever);
+            let forward_speed = contact_velocity.dot(wheel_forward);
+            let side_speed = contact_velocity.dot(wheel_side);
+            wheel.spin += forward_speed / self.wheel_radius.max(0.01) * delta_time;
+
+            let mut long_force = -forward_speed * self.rolling_resistance;
+            if wheel.driven {
+                long_force += self.throttle * self.engine_force / driven_count;
+            }
+            // The brakes can only stop the body, they never push it backwards. The speed of
+            // the body is used instead of the speed of the contact so the pitching of the
+            // body does not make the brakes push back and forth once it has stopped
+            let body_speed = self.velocity.dot(wheel_forward);
+            let stopping = body_speed.abs() * wheel_mass / delta_time;
+            long_force -=
+                body_speed.signum() * (self.brake * self.brake_force / wheel_count).min(stopping);
+
+            // The grip tries to cancel the sliding of the wheel in one step. A push at the
+            // contact also turns the body, so it moves less mass than its share of the
+            // body, pushing with the whole share would overshoot and rock the body from
+            // side to side every step
+            let side_mass = contact_mass(
+                self.mass,
+                inertia,
+                inverse.rotate_vector(lever),
+                inverse.rotate_vector(wheel_side),
+            ) / wheel_count;
+            let side_force = -side_speed * side_mass / delta_time;
+
+            // Both are limited by how hard the wheel is pressed into the ground
+            let grip = spring * self.friction;
+            let mut traction = wheel_forward * long_force + wheel_side * side_force;
+            if traction.magnitude() > grip {
+                traction = traction.normalize() * grip;
+            }
+
+            force += traction;
+            torque += lever.cross(traction);
+        }
+
+        // The anti roll bar moves force from the wheel that is compressed less to the one
+        // on the other side of the axle that is compressed more
+        let mut anti_roll = vec![0.0; self.wheels.len()];
+        for a in 0..self.wheels.len() {
+            for b in (a + 1)..self.wheels.len() {
+                let (first, second) = (&self.wheels[a], &self.wheels[b]);
+                let same_axle = (first.position.z - second.position.z).abs() < AXLE_TOLERANCE
+                    && (first.position.x + second.position.x).abs() < AXLE_TOLERANCE
+                    && first.position.x != 0.0;
+                if !same_axle {
+                    continue;
+                }
+
+                let difference = first.compression.min(self.suspension_length)
+                    - second.compression.min(self.suspension_length);
+                anti_roll[a] += difference * self.anti_roll;
+                anti_roll[b] -= difference * self.anti_roll;
+            }
+        }
+
+        for (load, roll) in loads.into_iter().zip(anti_roll) {
+            let Some((spring, lever)) = load else {
+                continue;
+            };
+
+            let support = up * (spring + roll);
+            force += support;
+            torque += lever.cross(support);
+        }
+
+        let speed = self.velocity.magnitude();
+        force -= self.velocity * speed * self.drag;
+
+        self.velocity += force / self.mass * delta_time;
+
+        // The torque is turned into acceleration around the axes of the body
+        let local_torque = inverse.rotate_vector(torque);
+        let local_acceleration = Vector3::new(
+            local_torque.x / inertia.x,
+            local_torque.y / inertia.y,
+            local_torque.z / inertia.z,
+        );
+        self.angular_velocity += rotation.rotate_vector(local_acceleration) * delta_time;
+        self.angular_velocity *= (1.0 - ANGULAR_DAMPING * delta_time).max(0.0);
+
+        *position += self.velocity * delta_time;
+        let angle = self.angular_velocity.magnitude() * delta_time;
+        if angle > f32::EPSILON {
+            let turn = Quaternion::from_axis_angle(self.angular_velocity.normalize(), Rad(angle));
+            *rotation = (turn * *rotation).normalize();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::Deg;
+
+    const STEP: f32 = 1.0 / 60.0;
+
+    // The ground is the plane at a height of zero
+    fn flat_ground(
+        origin: Vector3<f32>,
+        direction: Vector3<f32>,
+        distance: f32,
+    ) -> Option<(f32, Vector3<f32>)> {
+        if direction.y >= 0.0 {
+            return None;
+        }
+
+        let hit = origin.y / -direction.y;
+        (0.0..=distance)
+            .contains(&hit)
+            .then_some((hit, Vector3::unit_y()))
+    }
+
+    fn car() -> Vehicle {
+        Vehicle::new(1000.0)
+            .with_wheel(Vector3::new(-0.8, 0.0, -1.2), true, false)
+            .with_wheel(Vector3::new(0.8, 0.0, -1.2), true, false)
+            .with_wheel(Vector3::new(-0.8, 0.0, 1.2), false, true)
+            .with_wheel(Vector3::new(0.8, 0.0, 1.2), false, true)
+    }
+
+    // Lets the car fall onto the ground and come to rest
+    fn settle(vehicle: &mut Vehicle, position: &mut Vector3<f32>, rotation: &mut Quaternion<f32>) {
+        for _ in 0..600 {
+            vehicle.step(position, rotation, STEP, flat_ground);
+        }
+    }
+
+    #[test]
+    fn test_settles_at_rest_compression() {
+        let mut vehicle = car();
+        let mut position = Vector3::new(0.0, 0.8, 0.0);
+        let mut rotation = Quaternion::from_angle_y(Deg(0.0));
+        settle(&mut vehicle, &mut position, &mut rotation);
+
+        // The four springs hold up the weight together
+        let rest = 1000.0 * 9.81 / (4.0 * vehicle.stiffness);
+        for wheel in vehicle.get_wheels() {
+            assert!(wheel.is_grounded());
+            assert!((wheel.get_compression() - rest).abs() < 1e-3);
+        }
+        assert!((position.y - (0.85 - rest)).abs() < 1e-3);
+        assert!(vehicle.get_velocity().magnitude() < 1e-3);
+    }
+
+    #[test]
+    fn test_throttle_accelerates_forward() {
+        let mut vehicle = car();
+        let mut position = Vector3::new(0.0, 0.8, 0.0);
+        // Turned so the front of the car faces along -X
+        let mut rotation = Quaternion::from_angle_y(Deg(90.0));
+        settle(&mut vehicle, &mut position, &mut rotation);
+
+        vehicle.set_throttle(1.0);
+        for _ in 0..60 {
+            vehicle.step(&mut position, &mut rotation, STEP, flat_ground);
+        }
+
+        let velocity = *vehicle.get_velocity();
+        assert!(velocity.x < -2.0);
+        assert!(velocity.z.abs() < 1e-3);
+        assert!(vehicle
+            .get_wheels()
+            .iter()
+            .all(|wheel| wheel.get_spin() > 0.0));
+    }
+
+    #[test]
+    fn test_brake_stops_without_reversing() {
+        let mut vehicle = car();
+        let mut position = Vector3::new(0.0, 0.8, 0.0);
+        let mut rotation = Quaternion::from_angle_y(Deg(0.0));
+        settle(&mut vehicle, &mut position, &mut rotation);
+
+        vehicle.set_velocity(Vector3::new(0.0, 0.0, -5.0));
+        vehicle.set_brake(1.0);
+        for _ in 0..180 {
+            vehicle.step(&mut position, &mut rotation, STEP, flat_ground);
+            assert!(vehicle.get_velocity().z <= 1e-4);
+        }
+
+        assert!(vehicle.get_velocity().z.abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_wheel_without_ground() {
+        let mut vehicle = car();
+        let mut position = Vector3::new(0.0, 0.8, 0.0);
+        let mut rotation = Quaternion::from_angle_y(Deg(0.0));
+
+        // The right side of the car hangs over a ledge
+        vehicle.step(
+            &mut position,
+            &mut rotation,
+            STEP,
+            |origin, direction, distance| {
+                flat_ground(origin, direction, distance).filter(|_| origin.x < 0.0)
+            },
+        );
+
+        for wheel in vehicle.get_wheels() {
+            assert_eq!(wheel.is_grounded(), wheel.get_position().x < 0.0);
+            if !wheel.is_grounded() {
+                assert_eq!(wheel.get_compression(), 0.0);
+                assert_eq!(
+                    wheel.get_center(),
+                    wheel.get_position() - Vector3::unit_y() * 0.5
+                );
+            }
+        }
+        assert!(vehicle.is_grounded());
+    }
+}
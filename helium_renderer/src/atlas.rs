@@ -0,0 +1,167 @@
+use std::io;
+
+use image::{load_from_memory, ImageError, RgbaImage};
+
+// Atlases start at this size and double until every image fits
+const MIN_ATLAS_SIZE: u32 = 64;
+
+/// The position and size of an image in an atlas in pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AtlasRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The images of an atlas packed into one RGBA8 image
+pub struct PackedAtlas {
+    pub rgba: RgbaImage,
+    /// The rect of each image in the order they were added
+    pub rects: Vec<AtlasRect>,
+}
+
+/// Packs many small images into one texture so they can be drawn with a single draw call
+pub struct TextureAtlasBuilder {
+    images: Vec<RgbaImage>,
+    padding: u32,
+    max_size: u32,
+}
+
+impl Default for TextureAtlasBuilder {
+    fn default() -> Self {
+        Self {
+            images: Vec::new(),
+            padding: 1,
+            max_size: 4096,
+        }
+    }
+}
+
+impl TextureAtlasBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Empty pixels between the images so they do not bleed into each other when filtered
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// The largest width and height that the atlas can grow to
+    pub fn with_max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Adds an image to the atlas
+    ///
+    /// # Returns
+    ///
+    /// The index of the image in the packed atlas
+    pub fn add_image(&mut self, image: RgbaImage) -> usize {
+        self.images.push(image);
+        self.images.len() - 1
+    }
+
+    /// Adds an encoded image to the atlas
+    ///
+    /// # Returns
+    ///
+    /// The index of the image in the packed atlas or an error if it could not be decoded
+    pub fn add_bytes(&mut self, bytes: &[u8]) -> Result<usize, ImageError> {
+        Ok(self.add_image(load_from_memory(bytes)?.to_rgba8()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    // Places the images in rows from the tallest to the shortest
+    fn pack_shelves(&self, atlas_size: (u32, u32)) -> Option<Vec<AtlasRect>> {
+        let mut order: Vec<usize> = (0..self.images.len()).collect();
+        order.sort_by_key(|&index| {
+            let image = &self.images[index];
+            (
+                std::cmp::Reverse(image.height()),
+                std::cmp::Reverse(image.width()),
+            )
+        });
+
+        let mut rects = vec![AtlasRect::default(); self.images.len()];
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+
+        for index in order {
+            let (width, height) = self.images[index].dimensions();
+            let cell = (width + self.padding, height + self.padding);
+
+            if x + cell.0 > atlas_size.0 {
+                x = 0;
+                y += shelf_height;
+                shelf_height = 0;
+            }
+
+            if x + cell.0 > atlas_size.0 || y + cell.1 > atlas_size.1 {
+                return None;
+            }
+
+            rects[index] = AtlasRect {
+                x,
+                y,
+                width,
+                height,
+            };
+
+            x += cell.0;
+            shelf_height = shelf_height.max(cell.1);
+        }
+
+        Some(rects)
+    }
+
+    /// Packs the images into the smallest power of two atlas they fit in
+    ///
+    /// # Returns
+    ///
+    /// The packed atlas or an error if the images do not fit in the max size
+    pub fn build(&self) -> Result<PackedAtlas, io::Error> {
+        let mut atlas_size = (MIN_ATLAS_SIZE, MIN_ATLAS_SIZE);
+
+        let rects = loop {
+            if atlas_size.0 > self.max_size || atlas_size.1 > self.max_size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "{} images do not fit in a {}x{} atlas",
+                        self.images.len(),
+                        self.max_size,
+                        self.max_size
+                    ),
+                ));
+            }
+
+            if let Some(rects) = self.pack_shelves(atlas_size) {
+                break rects;
+            }
+
+            // Grow the shorter side so the atlas stays close to square
+            if atlas_size.0 <= atlas_size.1 {
+                atlas_size.0 *= 2;
+            } else {
+                atlas_size.1 *= 2;
+            }
+        };
+
+        let mut rgba = RgbaImage::new(atlas_size.0, atlas_size.1);
+        for (image, rect) in self.images.iter().zip(rects.iter()) {
+            image::imageops::replace(&mut rgba, image, rect.x as i64, rect.y as i64);
+        }
+
+        Ok(PackedAtlas { rgba, rects })
+    }
+}
@@ -0,0 +1,226 @@
+use cgmath::{ElementWise, InnerSpace, Matrix3, Matrix4, Quaternion, Vector3, Vector4, Zero};
+
+/// An axis aligned bounding box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+/// A sphere that contains every point of an object
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+}
+
+// Empty objects are a point at the origin
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::new(Vector3::zero(), Vector3::zero())
+    }
+}
+
+impl Default for BoundingSphere {
+    fn default() -> Self {
+        Self::new(Vector3::zero(), 0.0)
+    }
+}
+
+impl Aabb {
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    /// Creates the smallest box that contains every point
+    ///
+    /// # Returns
+    ///
+    /// The box or `None` if there are no points
+    pub fn from_points<I>(points: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Vector3<f32>>,
+    {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+
+        Some(points.fold(Self::new(first, first), |aabb, point| Self {
+            min: aabb.min.zip(point, f32::min),
+            max: aabb.max.zip(point, f32::max),
+        }))
+    }
+
+    pub fn center(&self) -> Vector3<f32> {
+        (self.min + self.max) / 2.0
+    }
+
+    /// The width, height, and length of the box
+    pub fn size(&self) -> Vector3<f32> {
+        self.max - self.min
+    }
+
+    pub fn half_extents(&self) -> Vector3<f32> {
+        self.size() / 2.0
+    }
+
+    /// The smallest box that contains both boxes
+    pub fn union(&self, other: &Aabb) -> Self {
+        Self {
+            min: self.min.zip(other.min, f32::min),
+            max: self.max.zip(other.max, f32::max),
+        }
+    }
+
+    /// The sphere around the box
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        BoundingSphere {
+            center: self.center(),
+            radius: self.half_extents().magnitude(),
+        }
+    }
+
+    /// The box after it is scaled along each axis about the origin
+    pub fn scaled(&self, scale: Vector3<f32>) -> Self {
+        let a = self.min.mul_element_wise(scale);
+        let b = self.max.mul_element_wise(scale);
+
+        // Negative scales flip the corners
+        Self::new(a.zip(b, f32::min), a.zip(b, f32::max))
+    }
+
+    /// The box around this box after it is rotated and moved
+    pub fn transformed(&self, position: Vector3<f32>, rotation: Quaternion<f32>) -> Self {
+        let rotation = Matrix3::from(rotation);
+        let center = rotation * self.center() + position;
+
+        // Each axis of the new box is the sum of the rotated extents on that axis
+        let half = self.half_extents();
+        let extent = |row: usize| {
+            rotation.x[row].abs() * half.x
+                + rotation.y[row].abs() * half.y
+                + rotation.z[row].abs() * half.z
+        };
+        let half = Vector3::new(extent(0), extent(1), extent(2));
+
+        Self::new(center - half, center + half)
+    }
+}
+
+impl BoundingSphere {
+    pub fn new(center: Vector3<f32>, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /// Creates a sphere around the points centered on their bounding box, it is not the
+    /// smallest sphere but it is close for most models
+    ///
+    /// # Returns
+    ///
+    /// The sphere or `None` if there are no points
+    pub fn from_points<I>(points: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Vector3<f32>> + Clone,
+    {
+        let center = Aabb::from_points(points.clone())?.center();
+        let radius = points
+            .into_iter()
+            .map(|point| (point - center).magnitude2())
+            .fold(0.0, f32::max)
+            .sqrt();
+
+        Some(Self { center, radius })
+    }
+
+    /// The sphere around the sphere after it is scaled along each axis about the origin
+    pub fn scaled(&self, scale: Vector3<f32>) -> Self {
+        let largest = scale.x.abs().max(scale.y.abs()).max(scale.z.abs());
+
+        Self {
+            center: self.center.mul_element_wise(scale),
+            radius: self.radius * largest,
+        }
+    }
+
+    /// The sphere after it is rotated and moved
+    pub fn transformed(&self, position: Vector3<f32>, rotation: Quaternion<f32>) -> Self {
+        Self {
+            center: rotation * self.center + position,
+            radius: self.radius,
+        }
+    }
+
+    /// The smallest sphere that contains both spheres
+    pub fn union(&self, other: &BoundingSphere) -> Self {
+        let offset = other.center - self.center;
+        let distance = offset.magnitude();
+
+        if distance + other.radius <= self.radius {
+            return *self;
+        }
+
+        if distance + self.radius <= other.radius {
+            return *other;
+        }
+
+        let radius = (distance + self.radius + other.radius) / 2.0;
+        Self {
+            center: self.center + offset * ((radius - self.radius) / distance),
+            radius,
+        }
+    }
+}
+
+/// The six planes of the volume a camera can see, used to skip objects that are off
+/// the screen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    // The xyz of each plane points into the frustum and w is the distance from the origin
+    planes: [Vector4<f32>; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes from the view projection matrix of a camera with a depth range
+    /// of 0 to 1
+    pub fn from_view_proj(view_proj: &Matrix4<f32>) -> Self {
+        let row = |index: usize| {
+            Vector4::new(
+                view_proj.x[index],
+                view_proj.y[index],
+                view_proj.z[index],
+                view_proj.w[index],
+            )
+        };
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+
+        let planes = [w + x, w - x, w + y, w - y, z, w - z].map(|plane| {
+            let length = plane.truncate().magnitude();
+            if length > f32::EPSILON {
+                plane / length
+            } else {
+                plane
+            }
+        });
+
+        Self { planes }
+    }
+
+    /// Checks if any part of a sphere is inside the frustum
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(sphere.center) + plane.w >= -sphere.radius)
+    }
+
+    /// Checks if any part of a box is inside the frustum
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        let (center, half) = (aabb.center(), aabb.half_extents());
+
+        self.planes.iter().all(|plane| {
+            let normal = plane.truncate();
+            // Distance from the center to the corner of the box furthest along the normal
+            let radius =
+                normal.x.abs() * half.x + normal.y.abs() * half.y + normal.z.abs() * half.z;
+            normal.dot(center) + plane.w >= -radius
+        })
+    }
+}
@@ -1,5 +1,5 @@
 // cgmath imports
-use cgmath::{perspective, Deg, Matrix4, Point3, SquareMatrix, Vector3};
+use cgmath::{perspective, Deg, InnerSpace, Matrix4, Point3, SquareMatrix, Vector3};
 
 // wgpu imports
 use wgpu::{
@@ -121,18 +121,20 @@ impl Camera {
     }
 
     pub fn update_view_proj(&mut self) {
-        self.camera_uniform.update_view_proj_with_matrix(
+        self.camera_uniform
+            .update_view_proj_with_matrix(self.eye, self.build_view_projection_matrix());
+    }
+
+    pub fn build_view_projection_matrix(&self) -> Matrix4<f32> {
+        Self::build_view_projection_matrix_parts(
             self.eye,
-            Self::build_view_projection_matrix_parts(
-                self.eye,
-                self.target,
-                self.up,
-                self.aspect,
-                self.fovy,
-                self.znear,
-                self.zfar,
-            ),
-        );
+            self.target,
+            self.up,
+            self.aspect,
+            self.fovy,
+            self.znear,
+            self.zfar,
+        )
     }
 
     pub fn build_view_projection_matrix_parts(
@@ -153,6 +155,53 @@ impl Camera {
     pub fn get_layout(&self) -> &BindGroupLayout {
         &self.layout
     }
+
+    /// Projects a point in the world onto the screen
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The point in world space
+    /// * `screen_size` - The width and height of the screen in pixels
+    ///
+    /// # Returns
+    ///
+    /// The `ScreenPoint` of the position or `None` if it is behind the camera or out of range
+    pub fn world_to_screen(
+        &self,
+        position: Vector3<f32>,
+        screen_size: (f32, f32),
+    ) -> Option<ScreenPoint> {
+        let clip = self.build_view_projection_matrix() * position.extend(1.0);
+
+        if clip.w <= 0.0 {
+            return None;
+        }
+
+        let ndc = clip.truncate() / clip.w;
+        if !(0.0..=1.0).contains(&ndc.z) {
+            return None;
+        }
+
+        Some(ScreenPoint {
+            position: (
+                (ndc.x + 1.0) * 0.5 * screen_size.0,
+                (1.0 - ndc.y) * 0.5 * screen_size.1,
+            ),
+            depth: ndc.z,
+            distance: (position - Vector3::new(self.eye.x, self.eye.y, self.eye.z)).magnitude(),
+        })
+    }
+}
+
+/// A point in the world projected onto the screen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenPoint {
+    /// Position in pixels from the top left of the screen
+    pub position: (f32, f32),
+    /// Depth of the point in the depth buffer
+    pub depth: f32,
+    /// Distance from the camera to the point in world units
+    pub distance: f32,
 }
 
 #[repr(C)]
@@ -160,6 +209,10 @@ impl Camera {
 pub struct CameraUniform {
     view_position: [f32; 4],
     view_proj: [[f32; 4]; 4],
+    // Fragments behind the plane are discarded, a zero plane keeps everything
+    clip_plane: [f32; 4],
+    // x is the seconds since the renderer started, used to animate materials
+    time: [f32; 4],
 }
 
 impl Default for CameraUniform {
@@ -167,6 +220,8 @@ impl Default for CameraUniform {
         Self {
             view_position: [0.0; 4],
             view_proj: Matrix4::identity().into(),
+            clip_plane: [0.0; 4],
+            time: [0.0; 4],
         }
     }
 }
@@ -176,4 +231,19 @@ impl CameraUniform {
         self.view_position = eye.to_homogeneous().into();
         self.view_proj = matrix.into();
     }
+
+    /// Sets the plane that fragments are clipped against
+    ///
+    /// # Arguments
+    ///
+    /// * `clip_plane` - The normal of the plane in xyz and the distance in w, fragments
+    ///   where `dot(position, normal) + distance` is negative are discarded
+    pub fn set_clip_plane(&mut self, clip_plane: [f32; 4]) {
+        self.clip_plane = clip_plane;
+    }
+
+    /// Sets the time that the texture animations of the materials are played at
+    pub fn set_time(&mut self, seconds: f32) {
+        self.time[0] = seconds;
+    }
 }
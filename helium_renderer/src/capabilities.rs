@@ -0,0 +1,96 @@
+use wgpu::{Adapter, AdapterInfo, Device, Features, Limits};
+
+/// The features the renderer uses when the gpu supports them, everything that uses them
+/// has a fallback for gpus that do not
+pub const OPTIONAL_FEATURES: Features = Features::POLYGON_MODE_LINE
+    .union(Features::TIMESTAMP_QUERY)
+    .union(Features::MULTI_DRAW_INDIRECT)
+    .union(Features::INDIRECT_FIRST_INSTANCE)
+    .union(Features::TEXTURE_COMPRESSION_BC)
+    .union(Features::TEXTURE_COMPRESSION_ETC2)
+    .union(Features::TEXTURE_COMPRESSION_ASTC);
+
+/// The features and limits the device was created with, subsystems check these before
+/// using anything optional
+#[derive(Clone, Debug)]
+pub struct RendererCapabilities {
+    features: Features,
+    limits: Limits,
+    adapter_info: AdapterInfo,
+}
+
+impl RendererCapabilities {
+    /// Picks the features and limits to request from an adapter, the optional features
+    /// it supports are requested and the texture limits are raised to what it supports
+    ///
+    /// # Returns
+    ///
+    /// The features and limits to create the device with
+    pub fn negotiate(adapter: &Adapter) -> (Features, Limits) {
+        let supported = adapter.limits();
+
+        // Gpus that can not meet the default limits, like software renderers, get the
+        // lower limits that every gpu supports
+        let base = if Limits::default().check_limits(&supported) {
+            Limits::default()
+        } else {
+            Limits::downlevel_defaults()
+        };
+
+        let limits = Limits {
+            max_texture_dimension_1d: supported.max_texture_dimension_1d,
+            max_texture_dimension_2d: supported.max_texture_dimension_2d,
+            max_texture_array_layers: supported.max_texture_array_layers,
+            ..base
+        };
+
+        (adapter.features() & OPTIONAL_FEATURES, limits)
+    }
+
+    /// Reads what was granted when the device was created
+    pub fn from_device(device: &Device, adapter_info: AdapterInfo) -> Self {
+        Self {
+            features: device.features(),
+            limits: device.limits(),
+            adapter_info,
+        }
+    }
+
+    /// Checks if every feature in a set was granted
+    pub fn has(&self, features: Features) -> bool {
+        self.features.contains(features)
+    }
+
+    pub fn get_features(&self) -> Features {
+        self.features
+    }
+
+    pub fn get_limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// The name, vendor, and backend of the gpu
+    pub fn get_adapter_info(&self) -> &AdapterInfo {
+        &self.adapter_info
+    }
+
+    /// Meshes can be drawn as lines
+    pub fn supports_wireframe(&self) -> bool {
+        self.has(Features::POLYGON_MODE_LINE)
+    }
+
+    /// The time passes take on the gpu can be measured
+    pub fn supports_timestamps(&self) -> bool {
+        self.has(Features::TIMESTAMP_QUERY)
+    }
+
+    /// Several indirect draws can be issued with one call
+    pub fn supports_multi_draw(&self) -> bool {
+        self.has(Features::MULTI_DRAW_INDIRECT)
+    }
+
+    /// Block compressed textures can be sampled without decoding them on the cpu
+    pub fn supports_bc_compression(&self) -> bool {
+        self.has(Features::TEXTURE_COMPRESSION_BC)
+    }
+}
@@ -0,0 +1,194 @@
+use wgpu::TextureFormat;
+
+// Exact sRGB transfer function for a single channel
+fn srgb_to_linear(channel: f32) -> f32 {
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(channel: f32) -> f32 {
+    if channel <= 0.0031308 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// A color in linear space, this is what the shaders light and blend with
+///
+/// Colors that are passed to the renderer as plain `[f32; 4]` are treated as linear
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LinearRgba {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+/// A color in sRGB space, this is how colors are written in color pickers and image files
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Srgba {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl LinearRgba {
+    pub const WHITE: Self = Self::new(1.0, 1.0, 1.0, 1.0);
+    pub const BLACK: Self = Self::new(0.0, 0.0, 0.0, 1.0);
+    pub const TRANSPARENT: Self = Self::new(0.0, 0.0, 0.0, 0.0);
+
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self::new(r, g, b, 1.0)
+    }
+
+    /// Encodes the color into sRGB space, the alpha is not changed
+    pub fn to_srgba(self) -> Srgba {
+        Srgba::new(
+            linear_to_srgb(self.r),
+            linear_to_srgb(self.g),
+            linear_to_srgb(self.b),
+            self.a,
+        )
+    }
+
+    pub fn to_array(self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+}
+
+impl Srgba {
+    pub const WHITE: Self = Self::new(1.0, 1.0, 1.0, 1.0);
+    pub const BLACK: Self = Self::new(0.0, 0.0, 0.0, 1.0);
+    pub const TRANSPARENT: Self = Self::new(0.0, 0.0, 0.0, 0.0);
+
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self::new(r, g, b, 1.0)
+    }
+
+    /// Creates a color from 8 bit channels like the ones in an image or a color picker
+    pub fn rgba_u8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self::new(
+            r as f32 / 255.0,
+            g as f32 / 255.0,
+            b as f32 / 255.0,
+            a as f32 / 255.0,
+        )
+    }
+
+    /// Creates a color from a hex code like `#ff8800` or `ff8800cc`
+    ///
+    /// # Returns
+    ///
+    /// The color or `None` if the code is not 6 or 8 hex digits
+    pub fn hex(code: &str) -> Option<Self> {
+        let code = code.strip_prefix('#').unwrap_or(code);
+        if !matches!(code.len(), 6 | 8) {
+            return None;
+        }
+
+        let channel = |index: usize| u8::from_str_radix(code.get(index..index + 2)?, 16).ok();
+        let alpha = if code.len() == 8 { channel(6)? } else { 255 };
+
+        Some(Self::rgba_u8(channel(0)?, channel(2)?, channel(4)?, alpha))
+    }
+
+    /// Decodes the color into linear space, the alpha is not changed
+    pub fn to_linear(self) -> LinearRgba {
+        LinearRgba::new(
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+            self.a,
+        )
+    }
+
+    pub fn to_array(self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+}
+
+impl From<Srgba> for LinearRgba {
+    fn from(value: Srgba) -> Self {
+        value.to_linear()
+    }
+}
+
+impl From<LinearRgba> for Srgba {
+    fn from(value: LinearRgba) -> Self {
+        value.to_srgba()
+    }
+}
+
+impl From<[f32; 4]> for LinearRgba {
+    fn from([r, g, b, a]: [f32; 4]) -> Self {
+        Self::new(r, g, b, a)
+    }
+}
+
+impl From<[f32; 3]> for LinearRgba {
+    fn from([r, g, b]: [f32; 3]) -> Self {
+        Self::rgb(r, g, b)
+    }
+}
+
+impl From<LinearRgba> for [f32; 4] {
+    fn from(value: LinearRgba) -> Self {
+        value.to_array()
+    }
+}
+
+/// Checks that a texture is stored in the color space of what it holds
+///
+/// # Arguments
+///
+/// * `name` - The name of the texture in the warning
+/// * `format` - The format the texture was uploaded with
+/// * `holds_color` - Colors are stored in sRGB while data like normals is stored linearly
+///
+/// # Returns
+///
+/// A warning when the texture is in the wrong color space
+pub(crate) fn audit_texture(
+    name: &str,
+    format: TextureFormat,
+    holds_color: bool,
+) -> Option<String> {
+    match (holds_color, format.is_srgb()) {
+        (true, false) => Some(format!(
+            "{name} holds colors but is sampled as linear {format:?}, it will look washed out"
+        )),
+        (false, true) => Some(format!(
+            "{name} holds data but is sampled as sRGB {format:?}, its values will be curved"
+        )),
+        _ => None,
+    }
+}
+
+/// The view format the frame is drawn through, an sRGB view lets the hardware gamma
+/// encode the linear output of the shaders
+pub(crate) fn surface_view_format(format: TextureFormat) -> TextureFormat {
+    format.add_srgb_suffix()
+}
+
+/// Whether the shaders have to gamma encode their output themselves because the target
+/// has no sRGB view and is not a linear float format
+pub(crate) fn needs_shader_encode(format: TextureFormat) -> bool {
+    !format.is_srgb()
+        && !matches!(
+            format,
+            TextureFormat::Rgba16Float | TextureFormat::Rgba32Float
+        )
+}
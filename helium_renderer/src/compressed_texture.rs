@@ -0,0 +1,582 @@
+// Loading of KTX2 and DDS containers, and decoding of block compressed formats for gpus that
+// can not sample them
+use std::io::Read;
+
+use ddsfile::{D3DFormat, Dds, DxgiFormat};
+use image::{
+    error::{DecodingError, ImageFormatHint, UnsupportedError, UnsupportedErrorKind},
+    ImageError,
+};
+use ktx2::{Format, SupercompressionScheme};
+use ruzstd::decoding::StreamingDecoder;
+use wgpu::{Extent3d, TextureDimension, TextureFormat};
+
+const KTX2_MAGIC: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const DDS_MAGIC: &[u8] = b"DDS ";
+
+// Every block compressed format stores 4x4 texels in a block
+const BLOCK_TEXELS: usize = 16;
+
+/// A texture that is stored in the format the gpu samples it in
+pub(crate) struct CompressedImage {
+    pub format: TextureFormat,
+    pub dimensions: (u32, u32),
+    // The mip levels from the largest to the smallest
+    pub levels: Vec<Vec<u8>>,
+}
+
+fn decoding_error<E>(container: &str, error: E) -> ImageError
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    ImageError::Decoding(DecodingError::new(
+        ImageFormatHint::Name(String::from(container)),
+        error,
+    ))
+}
+
+fn unsupported_error(container: &str, message: String) -> ImageError {
+    ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+        ImageFormatHint::Name(String::from(container)),
+        UnsupportedErrorKind::GenericFeature(message),
+    ))
+}
+
+/// Whether the bytes are a KTX2 or DDS file instead of an image that the image crate decodes
+pub(crate) fn is_compressed_container(bytes: &[u8]) -> bool {
+    bytes.starts_with(&KTX2_MAGIC) || bytes.starts_with(DDS_MAGIC)
+}
+
+/// The width and height of a mip level in pixels
+pub(crate) fn level_dimensions(dimensions: (u32, u32), level: usize) -> (u32, u32) {
+    let size = Extent3d {
+        width: dimensions.0,
+        height: dimensions.1,
+        depth_or_array_layers: 1,
+    }
+    .mip_level_size(level as u32, TextureDimension::D2);
+
+    (size.width, size.height)
+}
+
+/// The number of bytes in a row of blocks, or of pixels for uncompressed formats
+pub(crate) fn bytes_per_row(format: TextureFormat, width: u32) -> u32 {
+    let (block_width, _) = format.block_dimensions();
+    width.div_ceil(block_width) * format.block_copy_size(None).unwrap_or(4)
+}
+
+fn level_byte_length(format: TextureFormat, dimensions: (u32, u32)) -> usize {
+    let (_, block_height) = format.block_dimensions();
+    bytes_per_row(format, dimensions.0) as usize * dimensions.1.div_ceil(block_height) as usize
+}
+
+/// Reads the format and the mip levels of a KTX2 or DDS file
+///
+/// # Arguments
+///
+/// * `bytes` - The contents of the file
+/// * `srgb` - Whether the colors are stored in srgb, like the format of png and jpeg textures
+///   this decides the color space so normal maps stay linear
+///
+/// # Returns
+///
+/// The image or an error if the container or its format is not supported
+pub(crate) fn parse(bytes: &[u8], srgb: bool) -> Result<CompressedImage, ImageError> {
+    let image = if bytes.starts_with(&KTX2_MAGIC) {
+        parse_ktx2(bytes)?
+    } else {
+        parse_dds(bytes)?
+    };
+
+    let format = if srgb {
+        image.format.add_srgb_suffix()
+    } else {
+        image.format.remove_srgb_suffix()
+    };
+
+    Ok(CompressedImage { format, ..image })
+}
+
+fn ktx2_format(format: Format) -> Option<TextureFormat> {
+    Some(match format {
+        Format::BC1_RGB_UNORM_BLOCK | Format::BC1_RGBA_UNORM_BLOCK => TextureFormat::Bc1RgbaUnorm,
+        Format::BC1_RGB_SRGB_BLOCK | Format::BC1_RGBA_SRGB_BLOCK => TextureFormat::Bc1RgbaUnormSrgb,
+        Format::BC2_UNORM_BLOCK => TextureFormat::Bc2RgbaUnorm,
+        Format::BC2_SRGB_BLOCK => TextureFormat::Bc2RgbaUnormSrgb,
+        Format::BC3_UNORM_BLOCK => TextureFormat::Bc3RgbaUnorm,
+        Format::BC3_SRGB_BLOCK => TextureFormat::Bc3RgbaUnormSrgb,
+        Format::BC4_UNORM_BLOCK => TextureFormat::Bc4RUnorm,
+        Format::BC4_SNORM_BLOCK => TextureFormat::Bc4RSnorm,
+        Format::BC5_UNORM_BLOCK => TextureFormat::Bc5RgUnorm,
+        Format::BC5_SNORM_BLOCK => TextureFormat::Bc5RgSnorm,
+        Format::BC6H_UFLOAT_BLOCK => TextureFormat::Bc6hRgbUfloat,
+        Format::BC6H_SFLOAT_BLOCK => TextureFormat::Bc6hRgbFloat,
+        Format::BC7_UNORM_BLOCK => TextureFormat::Bc7RgbaUnorm,
+        Format::BC7_SRGB_BLOCK => TextureFormat::Bc7RgbaUnormSrgb,
+        Format::R8G8B8A8_UNORM => TextureFormat::Rgba8Unorm,
+        Format::R8G8B8A8_SRGB => TextureFormat::Rgba8UnormSrgb,
+        _ => return None,
+    })
+}
+
+fn parse_ktx2(bytes: &[u8]) -> Result<CompressedImage, ImageError> {
+    let reader = ktx2::Reader::new(bytes).map_err(|e| decoding_error("KTX2", e))?;
+    let header = reader.header();
+
+    if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count > 1 {
+        return Err(unsupported_error(
+            "KTX2",
+            String::from("Only 2d textures can be loaded"),
+        ));
+    }
+
+    // Basis textures have no format because they need to be transcoded first
+    let format = header.format.and_then(ktx2_format).ok_or_else(|| {
+        unsupported_error(
+            "KTX2",
+            format!(
+                "The format {:?} with {:?} supercompression is not supported",
+                header.format, header.supercompression_scheme
+            ),
+        )
+    })?;
+
+    let levels = reader
+        .levels()
+        .map(|level| match header.supercompression_scheme {
+            None => Ok(level.data.to_vec()),
+            Some(SupercompressionScheme::Zstandard) => {
+                let mut data = Vec::with_capacity(level.uncompressed_byte_length as usize);
+                StreamingDecoder::new(level.data)
+                    .map_err(|e| decoding_error("KTX2", e))?
+                    .read_to_end(&mut data)
+                    .map_err(|e| decoding_error("KTX2", e))?;
+                Ok(data)
+            }
+            Some(scheme) => Err(unsupported_error(
+                "KTX2",
+                format!("The {scheme:?} supercompression is not supported"),
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let dimensions = (header.pixel_width, header.pixel_height.max(1));
+    validate_levels("KTX2", format, dimensions, &levels)?;
+
+    Ok(CompressedImage {
+        format,
+        dimensions,
+        levels,
+    })
+}
+
+fn dds_format(dds: &Dds) -> Option<TextureFormat> {
+    if let Some(format) = dds.get_dxgi_format() {
+        return Some(match format {
+            DxgiFormat::BC1_UNorm => TextureFormat::Bc1RgbaUnorm,
+            DxgiFormat::BC1_UNorm_sRGB => TextureFormat::Bc1RgbaUnormSrgb,
+            DxgiFormat::BC2_UNorm => TextureFormat::Bc2RgbaUnorm,
+            DxgiFormat::BC2_UNorm_sRGB => TextureFormat::Bc2RgbaUnormSrgb,
+            DxgiFormat::BC3_UNorm => TextureFormat::Bc3RgbaUnorm,
+            DxgiFormat::BC3_UNorm_sRGB => TextureFormat::Bc3RgbaUnormSrgb,
+            DxgiFormat::BC4_UNorm => TextureFormat::Bc4RUnorm,
+            DxgiFormat::BC4_SNorm => TextureFormat::Bc4RSnorm,
+            DxgiFormat::BC5_UNorm => TextureFormat::Bc5RgUnorm,
+            DxgiFormat::BC5_SNorm => TextureFormat::Bc5RgSnorm,
+            DxgiFormat::BC6H_UF16 => TextureFormat::Bc6hRgbUfloat,
+            DxgiFormat::BC6H_SF16 => TextureFormat::Bc6hRgbFloat,
+            DxgiFormat::BC7_UNorm => TextureFormat::Bc7RgbaUnorm,
+            DxgiFormat::BC7_UNorm_sRGB => TextureFormat::Bc7RgbaUnormSrgb,
+            DxgiFormat::R8G8B8A8_UNorm => TextureFormat::Rgba8Unorm,
+            DxgiFormat::R8G8B8A8_UNorm_sRGB => TextureFormat::Rgba8UnormSrgb,
+            _ => return None,
+        });
+    }
+
+    // Older files only have a four character code, premultiplied alpha is loaded as is
+    Some(match dds.get_d3d_format()? {
+        D3DFormat::DXT1 => TextureFormat::Bc1RgbaUnorm,
+        D3DFormat::DXT2 | D3DFormat::DXT3 => TextureFormat::Bc2RgbaUnorm,
+        D3DFormat::DXT4 | D3DFormat::DXT5 => TextureFormat::Bc3RgbaUnorm,
+        _ => return None,
+    })
+}
+
+fn parse_dds(bytes: &[u8]) -> Result<CompressedImage, ImageError> {
+    let dds = Dds::read(bytes).map_err(|e| decoding_error("DDS", e))?;
+
+    if dds.get_depth() > 1 || dds.get_num_array_layers() > 1 {
+        return Err(unsupported_error(
+            "DDS",
+            String::from("Only 2d textures can be loaded"),
+        ));
+    }
+
+    let format = dds_format(&dds).ok_or_else(|| {
+        unsupported_error(
+            "DDS",
+            format!(
+                "The format {:?} is not supported",
+                dds.get_dxgi_format()
+                    .map(|format| format!("{format:?}"))
+                    .or_else(|| dds.get_d3d_format().map(|format| format!("{format:?}")))
+            ),
+        )
+    })?;
+
+    let dimensions = (dds.get_width(), dds.get_height());
+    let mut data = dds.get_data(0).map_err(|e| decoding_error("DDS", e))?;
+
+    // The mip levels are stored one after the other
+    let mut levels = Vec::new();
+    for level in 0..dds.get_num_mipmap_levels().max(1) as usize {
+        let length = level_byte_length(format, level_dimensions(dimensions, level));
+        if data.len() < length {
+            break;
+        }
+
+        let (level_data, rest) = data.split_at(length);
+        levels.push(level_data.to_vec());
+        data = rest;
+    }
+
+    validate_levels("DDS", format, dimensions, &levels)?;
+
+    Ok(CompressedImage {
+        format,
+        dimensions,
+        levels,
+    })
+}
+
+// Makes sure every level has the data for all of its blocks
+fn validate_levels(
+    container: &str,
+    format: TextureFormat,
+    dimensions: (u32, u32),
+    levels: &[Vec<u8>],
+) -> Result<(), ImageError> {
+    if levels.is_empty() {
+        return Err(decoding_error(container, "The texture has no data"));
+    }
+
+    for (level, data) in levels.iter().enumerate() {
+        if data.len() < level_byte_length(format, level_dimensions(dimensions, level)) {
+            return Err(decoding_error(
+                container,
+                format!("Mip level {level} is missing data"),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Decodes a mip level into RGBA8 pixels for gpus that can not sample its format
+///
+/// # Arguments
+///
+/// * `format` - The format of the level, BC1 to BC5 and RGBA8 can be decoded
+/// * `dimensions` - The width and height of the level in pixels
+/// * `data` - The blocks of the level
+///
+/// # Returns
+///
+/// The pixels, 4 bytes per pixel, or an error if the format can not be decoded
+pub(crate) fn decode_level(
+    format: TextureFormat,
+    dimensions: (u32, u32),
+    data: &[u8],
+) -> Result<Vec<u8>, ImageError> {
+    let decode_block: fn(&[u8]) -> [[u8; 4]; BLOCK_TEXELS] = match format.remove_srgb_suffix() {
+        TextureFormat::Rgba8Unorm => return Ok(data.to_vec()),
+        TextureFormat::Bc1RgbaUnorm => decode_bc1,
+        TextureFormat::Bc2RgbaUnorm => decode_bc2,
+        TextureFormat::Bc3RgbaUnorm => decode_bc3,
+        TextureFormat::Bc4RUnorm => decode_bc4,
+        TextureFormat::Bc5RgUnorm => decode_bc5,
+        _ => {
+            return Err(unsupported_error(
+                "BCn",
+                format!("{format:?} can only be loaded on gpus that support it"),
+            ))
+        }
+    };
+
+    let (width, height) = dimensions;
+    let block_size = format.block_copy_size(None).unwrap_or(16) as usize;
+    let blocks_wide = width.div_ceil(4);
+    let mut rgba = vec![0; width as usize * height as usize * 4];
+
+    for (index, block) in data.chunks_exact(block_size).enumerate() {
+        let block_x = index as u32 % blocks_wide * 4;
+        let block_y = index as u32 / blocks_wide * 4;
+        if block_y >= height {
+            break;
+        }
+
+        for (texel, color) in decode_block(block).iter().enumerate() {
+            let x = block_x + texel as u32 % 4;
+            let y = block_y + texel as u32 / 4;
+
+            // Blocks on the edges can hang over the texture
+            if x < width && y < height {
+                let offset = (y as usize * width as usize + x as usize) * 4;
+                rgba[offset..offset + 4].copy_from_slice(color);
+            }
+        }
+    }
+
+    Ok(rgba)
+}
+
+fn rgb565(color: u16) -> [u32; 3] {
+    let r = (color >> 11) as u32 & 0x1F;
+    let g = (color >> 5) as u32 & 0x3F;
+    let b = color as u32 & 0x1F;
+
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+    ]
+}
+
+// The color half of BC1, BC2, and BC3 blocks, only BC1 can have transparent texels
+fn decode_color_block(block: &[u8], allow_transparent: bool) -> [[u8; 4]; BLOCK_TEXELS] {
+    let color_0 = u16::from_le_bytes([block[0], block[1]]);
+    let color_1 = u16::from_le_bytes([block[2], block[3]]);
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+
+    let [c0, c1] = [rgb565(color_0), rgb565(color_1)];
+    let mix = |weight_0: u32, weight_1: u32| {
+        let total = weight_0 + weight_1;
+        [0, 1, 2].map(|i| ((c0[i] * weight_0 + c1[i] * weight_1) / total) as u8)
+    };
+    let opaque = |[r, g, b]: [u8; 3]| [r, g, b, 255];
+
+    let palette = if color_0 > color_1 || !allow_transparent {
+        [
+            opaque(mix(1, 0)),
+            opaque(mix(0, 1)),
+            opaque(mix(2, 1)),
+            opaque(mix(1, 2)),
+        ]
+    } else {
+        [
+            opaque(mix(1, 0)),
+            opaque(mix(0, 1)),
+            opaque(mix(1, 1)),
+            [0, 0, 0, 0],
+        ]
+    };
+
+    std::array::from_fn(|texel| palette[(indices >> (texel * 2)) as usize & 0x3])
+}
+
+// An interpolated channel like the alpha of BC3 blocks and the channels of BC4 and BC5
+fn decode_channel_block(block: &[u8]) -> [u8; BLOCK_TEXELS] {
+    let [value_0, value_1] = [block[0] as u32, block[1] as u32];
+    let indices = u64::from_le_bytes([
+        block[2], block[3], block[4], block[5], block[6], block[7], 0, 0,
+    ]);
+
+    let mut palette = [value_0, value_1, 0, 0, 0, 0, 0, 255];
+    if value_0 > value_1 {
+        for (i, value) in palette.iter_mut().enumerate().skip(2) {
+            *value = ((8 - i as u32) * value_0 + (i as u32 - 1) * value_1) / 7;
+        }
+    } else {
+        for (i, value) in palette.iter_mut().enumerate().take(6).skip(2) {
+            *value = ((6 - i as u32) * value_0 + (i as u32 - 1) * value_1) / 5;
+        }
+    }
+
+    std::array::from_fn(|texel| palette[(indices >> (texel * 3)) as usize & 0x7] as u8)
+}
+
+fn decode_bc1(block: &[u8]) -> [[u8; 4]; BLOCK_TEXELS] {
+    decode_color_block(block, true)
+}
+
+fn decode_bc2(block: &[u8]) -> [[u8; 4]; BLOCK_TEXELS] {
+    let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+    let mut texels = decode_color_block(&block[8..], false);
+
+    for (texel, color) in texels.iter_mut().enumerate() {
+        color[3] = ((alpha >> (texel * 4)) & 0xF) as u8 * 17;
+    }
+
+    texels
+}
+
+fn decode_bc3(block: &[u8]) -> [[u8; 4]; BLOCK_TEXELS] {
+    let alpha = decode_channel_block(&block[..8]);
+    let mut texels = decode_color_block(&block[8..], false);
+
+    for (color, alpha) in texels.iter_mut().zip(alpha) {
+        color[3] = alpha;
+    }
+
+    texels
+}
+
+// Single and two channel formats are sampled with the missing channels set to 0 like on the gpu
+fn decode_bc4(block: &[u8]) -> [[u8; 4]; BLOCK_TEXELS] {
+    decode_channel_block(block).map(|r| [r, 0, 0, 255])
+}
+
+fn decode_bc5(block: &[u8]) -> [[u8; 4]; BLOCK_TEXELS] {
+    let red = decode_channel_block(&block[..8]);
+    let green = decode_channel_block(&block[8..]);
+
+    std::array::from_fn(|texel| [red[texel], green[texel], 0, 255])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: u16 = 0xF800;
+    const BLUE: u16 = 0x001F;
+
+    // Builds the color half of a block from its endpoints and the palette index of every texel
+    fn color_block(color_0: u16, color_1: u16, indices: [u8; BLOCK_TEXELS]) -> Vec<u8> {
+        let bits = indices
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (texel, index)| {
+                bits | (*index as u32) << (texel * 2)
+            });
+
+        [color_0.to_le_bytes(), color_1.to_le_bytes()]
+            .concat()
+            .into_iter()
+            .chain(bits.to_le_bytes())
+            .collect()
+    }
+
+    // Builds an interpolated channel block from its endpoints and the palette index of
+    // every texel
+    fn channel_block(value_0: u8, value_1: u8, indices: [u8; BLOCK_TEXELS]) -> Vec<u8> {
+        let bits = indices
+            .iter()
+            .enumerate()
+            .fold(0u64, |bits, (texel, index)| {
+                bits | (*index as u64) << (texel * 3)
+            });
+
+        [value_0, value_1]
+            .into_iter()
+            .chain(bits.to_le_bytes().into_iter().take(6))
+            .collect()
+    }
+
+    fn ramp(length: u8) -> [u8; BLOCK_TEXELS] {
+        std::array::from_fn(|texel| texel as u8 % length)
+    }
+
+    #[test]
+    fn test_bc1_four_colors() {
+        let texels = decode_bc1(&color_block(RED, BLUE, ramp(4)));
+
+        assert_eq!(
+            texels[..4],
+            [
+                [255, 0, 0, 255],
+                [0, 0, 255, 255],
+                [170, 0, 85, 255],
+                [85, 0, 170, 255],
+            ]
+        );
+        assert_eq!(texels[4], texels[0]);
+    }
+
+    #[test]
+    fn test_bc1_transparent() {
+        let block = color_block(BLUE, RED, ramp(4));
+
+        assert_eq!(
+            decode_bc1(&block)[..4],
+            [
+                [0, 0, 255, 255],
+                [255, 0, 0, 255],
+                [127, 0, 127, 255],
+                [0, 0, 0, 0],
+            ]
+        );
+
+        // The color of BC3 blocks always has four colors
+        let block = [channel_block(255, 255, [0; BLOCK_TEXELS]), block].concat();
+        assert_eq!(decode_bc3(&block)[3], [170, 0, 85, 255]);
+    }
+
+    #[test]
+    fn test_bc3_eight_alphas() {
+        let block = [
+            channel_block(255, 0, ramp(8)),
+            color_block(0xFFFF, 0, [0; BLOCK_TEXELS]),
+        ]
+        .concat();
+        let alphas = decode_bc3(&block).map(|[_, _, _, a]| a);
+
+        assert_eq!(alphas[..8], [255, 0, 218, 182, 145, 109, 72, 36]);
+        assert_eq!(alphas[8..], alphas[..8]);
+        assert_eq!(decode_bc3(&block)[0], [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_bc3_six_alphas() {
+        let block = [
+            channel_block(0, 255, ramp(8)),
+            color_block(0xFFFF, 0, [0; BLOCK_TEXELS]),
+        ]
+        .concat();
+        let alphas = decode_bc3(&block).map(|[_, _, _, a]| a);
+
+        assert_eq!(alphas[..8], [0, 255, 51, 102, 153, 204, 0, 255]);
+    }
+
+    #[test]
+    fn test_bc5_channels() {
+        let block = [
+            channel_block(200, 100, [1; BLOCK_TEXELS]),
+            channel_block(10, 20, [7; BLOCK_TEXELS]),
+        ]
+        .concat();
+
+        assert_eq!(decode_bc5(&block), [[100, 255, 0, 255]; BLOCK_TEXELS]);
+    }
+
+    #[test]
+    fn test_decode_overhanging_blocks() {
+        // Two blocks cover a 5x3 texture, the last column is the only part of the second
+        let data = [
+            channel_block(10, 0, [0; BLOCK_TEXELS]),
+            channel_block(20, 0, [0; BLOCK_TEXELS]),
+        ]
+        .concat();
+        let rgba = decode_level(TextureFormat::Bc4RUnorm, (5, 3), &data).unwrap();
+
+        assert_eq!(rgba.len(), 5 * 3 * 4);
+        for (pixel, color) in rgba.chunks_exact(4).enumerate() {
+            let red = if pixel % 5 == 4 { 20 } else { 10 };
+            assert_eq!(color, [red, 0, 0, 255]);
+        }
+
+        assert!(decode_level(TextureFormat::Bc7RgbaUnorm, (4, 4), &[0; 16]).is_err());
+    }
+
+    #[test]
+    fn test_validate_levels() {
+        let format = TextureFormat::Bc1RgbaUnorm;
+
+        // An 8x8 level has 2x2 blocks of 8 bytes and the 4x4 level one block
+        assert!(validate_levels("DDS", format, (8, 8), &[vec![0; 32], vec![0; 8]]).is_ok());
+        assert!(validate_levels("DDS", format, (8, 8), &[vec![0; 32], vec![0; 7]]).is_err());
+        assert!(validate_levels("DDS", format, (8, 8), &[vec![0; 31]]).is_err());
+        assert!(validate_levels("DDS", format, (8, 8), &[]).is_err());
+    }
+}
@@ -0,0 +1,365 @@
+use std::{f32::consts::TAU, mem};
+
+use cgmath::{InnerSpace, Vector3};
+use wgpu::{
+    include_wgsl, BindGroup, BlendState, Buffer, BufferAddress, BufferDescriptor, BufferUsages,
+    ColorTargetState, ColorWrites, CompareFunction, DepthBiasState, DepthStencilState, Device,
+    FragmentState, MultisampleState, PipelineCompilationOptions, PipelineLayoutDescriptor,
+    PrimitiveState, PrimitiveTopology, Queue, RenderPass, RenderPipeline, RenderPipelineDescriptor,
+    StencilState, TextureFormat, VertexAttribute, VertexBufferLayout, VertexFormat, VertexState,
+    VertexStepMode,
+};
+
+use crate::{
+    camera::Camera,
+    helium_texture,
+    layouts::{LayoutKind, LayoutRegistry},
+    light::{LightKind, LightUnits, Lights},
+    sdf_text::SdfText,
+};
+
+// Number of line segments in each circle of a sphere
+const CIRCLE_SEGMENTS: usize = 24;
+
+// Illuminance where the range gizmo of a light in candela is drawn, the light is too dim
+// to notice past it
+const LIGHT_RANGE_CUTOFF: f32 = 0.01;
+
+// Size of the gizmos of lights that do not have a range
+const LIGHT_GIZMO_SIZE: f32 = 0.25;
+
+// Length of the arrow drawn along the direction of a directional light
+const LIGHT_ARROW_LENGTH: f32 = 2.0;
+
+// The vertex buffer starts with room for this many lines and doubles when it is full
+const INITIAL_LINE_CAPACITY: usize = 256;
+
+// Height of debug labels in pixels, they keep their size at any distance so they can be
+// read across the scene
+const DEBUG_TEXT_SIZE: f32 = 16.0;
+
+/// A colored line in the world that is drawn for debugging
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DebugLine {
+    pub start: Vector3<f32>,
+    pub end: Vector3<f32>,
+    pub color: [f32; 4],
+}
+
+/// A label at a point in the world that is drawn for debugging
+#[derive(Clone, Debug, PartialEq)]
+pub struct DebugText {
+    pub position: Vector3<f32>,
+    pub text: String,
+    pub color: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct DebugVertex {
+    position: [f32; 3],
+    color: [f32; 4],
+}
+
+impl DebugVertex {
+    fn desc() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: mem::size_of::<DebugVertex>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: VertexFormat::Float32x3,
+                },
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 3]>() as BufferAddress,
+                    shader_location: 1,
+                    format: VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+// Two directions that are perpendicular to each other and to the axis
+fn perpendicular_basis(axis: Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+    let axis = axis.normalize();
+    let helper = if axis.y.abs() < 0.99 {
+        Vector3::unit_y()
+    } else {
+        Vector3::unit_x()
+    };
+    let u = axis.cross(helper).normalize();
+    (u, axis.cross(u))
+}
+
+/// The debug lines that are drawn on top of the scene, lines are collected while the
+/// scene updates and drawn every frame until the next update is flushed
+pub struct DebugDraw {
+    pipeline: RenderPipeline,
+    buffer: Buffer,
+    capacity: usize,
+    // Lines added since the last flush
+    pending: Vec<DebugLine>,
+    // Lines that are drawn until the next flush
+    lines: Vec<DebugLine>,
+    pending_texts: Vec<DebugText>,
+    texts: Vec<DebugText>,
+    light_gizmos: bool,
+}
+
+impl DebugDraw {
+    pub fn new(device: &Device, layouts: &LayoutRegistry, format: TextureFormat) -> Self {
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Debug Draw Render Pipeline Layout"),
+            bind_group_layouts: &[layouts.get(LayoutKind::Camera)],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("./shaders/debug_draw_shader.wgsl"));
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Debug Draw Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[DebugVertex::desc()],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::LineList,
+                ..Default::default()
+            },
+            // The lines are hidden behind the scene but do not hide each other
+            depth_stencil: Some(DepthStencilState {
+                format: helium_texture::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::LessEqual,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let capacity = INITIAL_LINE_CAPACITY;
+        let buffer = Self::create_buffer(device, capacity);
+
+        Self {
+            pipeline,
+            buffer,
+            capacity,
+            pending: Vec::new(),
+            lines: Vec::new(),
+            pending_texts: Vec::new(),
+            texts: Vec::new(),
+            light_gizmos: false,
+        }
+    }
+
+    fn create_buffer(device: &Device, capacity: usize) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("Debug Draw Vertex Buffer"),
+            size: (capacity * 2 * mem::size_of::<DebugVertex>()) as BufferAddress,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    pub fn add_line(&mut self, line: DebugLine) {
+        self.pending.push(line);
+    }
+
+    /// Adds three circles around the axes of a sphere
+    pub fn add_sphere(&mut self, center: Vector3<f32>, radius: f32, color: [f32; 4]) {
+        Self::push_sphere(&mut self.pending, center, radius, color);
+    }
+
+    /// Adds a line with an arrow head at the end
+    pub fn add_arrow(&mut self, start: Vector3<f32>, end: Vector3<f32>, color: [f32; 4]) {
+        Self::push_arrow(&mut self.pending, start, end, color);
+    }
+
+    pub fn add_text(&mut self, text: DebugText) {
+        self.pending_texts.push(text);
+    }
+
+    /// Replaces the lines and labels that are drawn with the ones added since the last flush
+    pub fn flush(&mut self) {
+        self.lines = mem::take(&mut self.pending);
+        self.texts = mem::take(&mut self.pending_texts);
+    }
+
+    /// The labels projected onto the screen above their points, labels behind the camera
+    /// are skipped
+    ///
+    /// # Arguments
+    ///
+    /// * `camera` - The camera the scene is viewed from
+    /// * `screen_size` - The width and height of the screen in pixels
+    pub fn labels(&self, camera: &Camera, screen_size: (f32, f32)) -> Vec<SdfText> {
+        self.texts
+            .iter()
+            .filter_map(|text| {
+                let screen_point = camera.world_to_screen(text.position, screen_size)?;
+                Some(
+                    SdfText::screen(text.text.clone(), screen_point.position, DEBUG_TEXT_SIZE)
+                        .with_color(text.color)
+                        .with_outline([0.0, 0.0, 0.0, 1.0], 0.1)
+                        .with_anchor((0.5, 1.0)),
+                )
+            })
+            .collect()
+    }
+
+    pub fn set_light_gizmos(&mut self, enabled: bool) {
+        self.light_gizmos = enabled;
+    }
+
+    pub fn get_light_gizmos(&self) -> bool {
+        self.light_gizmos
+    }
+
+    // Whether there is anything to draw this frame
+    pub fn has_lines(&self) -> bool {
+        !self.lines.is_empty() || self.light_gizmos
+    }
+
+    fn push_circle(
+        lines: &mut Vec<DebugLine>,
+        center: Vector3<f32>,
+        axis: Vector3<f32>,
+        radius: f32,
+        color: [f32; 4],
+    ) {
+        let (u, v) = perpendicular_basis(axis);
+        let point = |segment: usize| {
+            let angle = segment as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+            center + (u * angle.cos() + v * angle.sin()) * radius
+        };
+
+        lines.extend((0..CIRCLE_SEGMENTS).map(|segment| DebugLine {
+            start: point(segment),
+            end: point(segment + 1),
+            color,
+        }));
+    }
+
+    fn push_sphere(lines: &mut Vec<DebugLine>, center: Vector3<f32>, radius: f32, color: [f32; 4]) {
+        for axis in [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()] {
+            Self::push_circle(lines, center, axis, radius, color);
+        }
+    }
+
+    fn push_arrow(
+        lines: &mut Vec<DebugLine>,
+        start: Vector3<f32>,
+        end: Vector3<f32>,
+        color: [f32; 4],
+    ) {
+        lines.push(DebugLine { start, end, color });
+
+        let direction = end - start;
+        let length = direction.magnitude();
+        if length <= f32::EPSILON {
+            return;
+        }
+
+        // Four lines bent back from the tip
+        let (u, v) = perpendicular_basis(direction);
+        let back = end - direction / length * (length * 0.2);
+        let spread = length * 0.08;
+        for side in [u, -u, v, -v] {
+            lines.push(DebugLine {
+                start: end,
+                end: back + side * spread,
+                color,
+            });
+        }
+    }
+
+    // Adds the gizmos of the lights, the range of lights in candela is where they become
+    // too dim to notice and directional lights show where they shine
+    fn push_light_gizmos(lines: &mut Vec<DebugLine>, lights: &Lights) {
+        for light in lights.get_lights() {
+            let (r, g, b) = light.get_color();
+            let color = [r, g, b, 1.0];
+            let position = light.get_position();
+
+            match light.get_kind() {
+                LightKind::Point => {
+                    let radius = match light.get_units() {
+                        LightUnits::Candela => (light.get_intensity() * lights.get_exposure()
+                            / LIGHT_RANGE_CUTOFF)
+                            .max(0.0)
+                            .sqrt(),
+                        LightUnits::Unitless => LIGHT_GIZMO_SIZE,
+                    };
+                    Self::push_sphere(lines, position, radius, color);
+                }
+                LightKind::Directional => {
+                    Self::push_sphere(lines, position, LIGHT_GIZMO_SIZE, color);
+                    Self::push_arrow(
+                        lines,
+                        position,
+                        position + light.get_direction() * LIGHT_ARROW_LENGTH,
+                        color,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Draws the lines and the gizmos of the lights
+    pub(crate) fn draw(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        render_pass: &mut RenderPass,
+        camera_bind_group: &BindGroup,
+        lights: &Lights,
+    ) {
+        let mut lines = self.lines.clone();
+        if self.light_gizmos {
+            Self::push_light_gizmos(&mut lines, lights);
+        }
+
+        if lines.is_empty() {
+            return;
+        }
+
+        if lines.len() > self.capacity {
+            self.capacity = lines.len().next_power_of_two();
+            self.buffer = Self::create_buffer(device, self.capacity);
+        }
+
+        let vertices = lines
+            .iter()
+            .flat_map(|line| {
+                [line.start, line.end].map(|position| DebugVertex {
+                    position: position.into(),
+                    color: line.color,
+                })
+            })
+            .collect::<Vec<_>>();
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&vertices));
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.buffer.slice(..));
+        render_pass.draw(0..vertices.len() as u32, 0..1);
+    }
+}
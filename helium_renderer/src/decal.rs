@@ -0,0 +1,322 @@
+use cgmath::{Matrix4, Quaternion, SquareMatrix, Vector3};
+use wgpu::{
+    include_wgsl,
+    util::{BufferInitDescriptor, DeviceExt},
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, BlendState, Buffer, BufferBindingType,
+    BufferUsages, ColorTargetState, ColorWrites, Device, Face, FragmentState, FrontFace,
+    MultisampleState, PipelineCompilationOptions, PipelineLayoutDescriptor, PolygonMode,
+    PrimitiveState, PrimitiveTopology, Queue, RenderPass, RenderPipeline, RenderPipelineDescriptor,
+    ShaderStages, TextureFormat, TextureSampleType, TextureView, TextureViewDimension, VertexState,
+};
+
+use crate::{camera::Camera, helium_texture::HeliumTexture};
+
+// Number of vertices used to draw the projection box of a decal
+const DECAL_VERTICES: u32 = 36;
+
+/// Handle to a texture that has been loaded for drawing decals
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DecalTexture(pub usize);
+
+/// A texture projected onto the scene inside of a box, like a bullet hole or a road marking
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DecalProjector {
+    pub texture: DecalTexture,
+    /// Center of the projection box
+    pub position: Vector3<f32>,
+    /// The texture is projected down the local y axis of the box
+    pub rotation: Quaternion<f32>,
+    /// Width, depth, and length of the box, only surfaces inside of the box are covered
+    pub size: Vector3<f32>,
+    /// RGBA color multiplied into the texture
+    pub color: [f32; 4],
+}
+
+impl DecalProjector {
+    fn model_matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.position)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.size.x, self.size.y, self.size.z)
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct DecalGlobals {
+    view_proj: [[f32; 4]; 4],
+    inverse_view_proj: [[f32; 4]; 4],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct DecalUniform {
+    model: [[f32; 4]; 4],
+    inverse_model: [[f32; 4]; 4],
+    color: [f32; 4],
+}
+
+impl From<&DecalProjector> for DecalUniform {
+    fn from(decal: &DecalProjector) -> Self {
+        let model = decal.model_matrix();
+
+        Self {
+            model: model.into(),
+            // A box with no size covers nothing
+            inverse_model: model.invert().unwrap_or(Matrix4::from_scale(0.0)).into(),
+            color: decal.color,
+        }
+    }
+}
+
+// A decal in the renderer with its own uniform
+struct DecalInstance {
+    decal: DecalProjector,
+    buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+/// Projects decals onto the scene using the depth buffer, so decals do not need to know
+/// anything about the geometry they cover
+pub struct DecalRenderer {
+    pipeline: RenderPipeline,
+    globals_layout: BindGroupLayout,
+    decal_layout: BindGroupLayout,
+    globals_buffer: Buffer,
+    globals_bind_group: BindGroup,
+    textures: Vec<HeliumTexture>,
+    decals: Vec<Option<DecalInstance>>,
+}
+
+impl DecalRenderer {
+    pub fn new(device: &Device, format: TextureFormat, depth_view: &TextureView) -> Self {
+        let globals_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Decal Globals Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: TextureViewDimension::D2,
+                        sample_type: TextureSampleType::Depth,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let decal_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Decal Bind Group Layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Decal Render Pipeline Layout"),
+            bind_group_layouts: &[
+                &globals_layout,
+                &decal_layout,
+                &HeliumTexture::get_layout(device),
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("./shaders/decal_shader.wgsl"));
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Decal Render Pipeline"),
+            layout: Some(&layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                // The inside of the box is drawn so the decal still shows with the camera in it
+                cull_mode: Some(Face::Front),
+                polygon_mode: PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            // The depth is read in the shader instead of being tested
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let globals_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Decal globals buffer"),
+            contents: bytemuck::cast_slice(&[DecalGlobals {
+                view_proj: Matrix4::identity().into(),
+                inverse_view_proj: Matrix4::identity().into(),
+            }]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let globals_bind_group =
+            Self::create_globals_bind_group(device, &globals_layout, &globals_buffer, depth_view);
+
+        Self {
+            pipeline,
+            globals_layout,
+            decal_layout,
+            globals_buffer,
+            globals_bind_group,
+            textures: Vec::new(),
+            decals: Vec::new(),
+        }
+    }
+
+    fn create_globals_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        buffer: &Buffer,
+        depth_view: &TextureView,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Decal globals bind group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(depth_view),
+                },
+            ],
+        })
+    }
+
+    /// Call this when the depth texture is recreated
+    pub fn set_depth_view(&mut self, device: &Device, depth_view: &TextureView) {
+        self.globals_bind_group = Self::create_globals_bind_group(
+            device,
+            &self.globals_layout,
+            &self.globals_buffer,
+            depth_view,
+        );
+    }
+
+    pub fn add_texture(&mut self, texture: HeliumTexture) -> DecalTexture {
+        let handle = DecalTexture(self.textures.len());
+        self.textures.push(texture);
+        handle
+    }
+
+    pub fn has_decals(&self) -> bool {
+        self.decals.iter().any(Option::is_some)
+    }
+
+    pub fn create_decal(&mut self, device: &Device, decal: DecalProjector) -> usize {
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Decal buffer"),
+            contents: bytemuck::cast_slice(&[DecalUniform::from(&decal)]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Decal bind group"),
+            layout: &self.decal_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        let index = self.decals.len();
+        self.decals.push(Some(DecalInstance {
+            decal,
+            buffer,
+            bind_group,
+        }));
+        index
+    }
+
+    pub fn update_decal(&mut self, queue: &Queue, decal_index: usize, decal: DecalProjector) {
+        if let Some(Some(instance)) = self.decals.get_mut(decal_index) {
+            instance.decal = decal;
+            queue.write_buffer(
+                &instance.buffer,
+                0,
+                bytemuck::cast_slice(&[DecalUniform::from(&decal)]),
+            );
+        }
+    }
+
+    pub fn remove_decal(&mut self, decal_index: usize) {
+        if let Some(decal) = self.decals.get_mut(decal_index) {
+            *decal = None;
+        }
+    }
+
+    /// Draws the decals, the render pass must not have the depth texture attached
+    /// because the decals read it
+    pub fn draw(&self, render_pass: &mut RenderPass, queue: &Queue, camera: &Camera) {
+        let view_proj = camera.build_view_projection_matrix();
+        queue.write_buffer(
+            &self.globals_buffer,
+            0,
+            bytemuck::cast_slice(&[DecalGlobals {
+                view_proj: view_proj.into(),
+                inverse_view_proj: view_proj.invert().unwrap_or(view_proj).into(),
+            }]),
+        );
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.globals_bind_group, &[]);
+
+        for instance in self.decals.iter().flatten() {
+            let Some(texture_bind_group) = self
+                .textures
+                .get(instance.decal.texture.0)
+                .and_then(|texture| texture.get_bind_group())
+            else {
+                continue;
+            };
+
+            render_pass.set_bind_group(1, &instance.bind_group, &[]);
+            render_pass.set_bind_group(2, texture_bind_group, &[]);
+            render_pass.draw(0..DECAL_VERTICES, 0..1);
+        }
+    }
+}
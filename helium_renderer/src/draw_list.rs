@@ -0,0 +1,257 @@
+use std::cmp::Reverse;
+
+use cgmath::{EuclideanSpace, InnerSpace, Point3};
+
+use crate::{
+    bounds::Frustum,
+    instance::Instance,
+    model::{mesh::Mesh, Model},
+    model_pipelines::PipelineVariant,
+    reflection::ReflectionRenderer,
+    scatter::Scatter,
+};
+
+/// Counts of the work done to draw the scene in the last frame
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub draw_calls: u32,
+    pub pipeline_changes: u32,
+    /// Number of times a material bind group was bound
+    pub material_binds: u32,
+    /// Number of times the vertex and index buffers of a mesh were bound
+    pub mesh_binds: u32,
+    pub instances: u32,
+    pub triangles: u32,
+    /// Number of mesh draws that were skipped because they were off the screen
+    pub culled: u32,
+}
+
+// The pipeline a draw uses, scattered models also bind their culling settings,
+// reflective models bind their reflection, and meshes with morph targets bind their deltas
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum DrawPipeline {
+    Model,
+    Morph,
+    Scatter(usize),
+    Reflection(usize),
+}
+
+impl DrawPipeline {
+    // Draws that only differ by scatter settings or reflections share the same pipeline
+    pub(crate) fn same_pipeline(&self, other: &DrawPipeline) -> bool {
+        matches!(
+            (self, other),
+            (DrawPipeline::Model, DrawPipeline::Model)
+                | (DrawPipeline::Morph, DrawPipeline::Morph)
+                | (DrawPipeline::Scatter(_), DrawPipeline::Scatter(_))
+                | (DrawPipeline::Reflection(_), DrawPipeline::Reflection(_))
+        )
+    }
+}
+
+// Opaque draws come first, the blended draws come after them from the farthest to the
+// nearest so they blend over everything behind them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum DrawLayer {
+    Opaque,
+    // The distance to the camera as bits, they sort like the distance since it is positive
+    Blended(Reverse<u32>),
+}
+
+impl DrawLayer {
+    fn blended(distance: f32) -> Self {
+        Self::Blended(Reverse(distance.max(0.0).to_bits()))
+    }
+}
+
+// A single mesh draw in the scene, ordered so that draws sharing state are next to each other
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct DrawCommand {
+    pub layer: DrawLayer,
+    pub pipeline: DrawPipeline,
+    /// The faces and depth bias of the material, it picks the variant of the pipeline
+    pub variant: PipelineVariant,
+    /// Index of the model and the index of the material in the model
+    pub material: (usize, usize),
+    /// Index of the model and the index of the mesh in the model
+    pub mesh: (usize, usize),
+    /// The start and end of the instances that are drawn
+    pub instances: (u32, u32),
+}
+
+// Trims the instances of a mesh to the ones between the first and last instance that are
+// on the screen
+//
+// Returns `None` when every instance is off the screen or hidden
+fn visible_instances(mesh: &Mesh, instances: &[Instance], frustum: &Frustum) -> Option<(u32, u32)> {
+    let range = mesh.get_instances();
+    let sphere = mesh.bounding_sphere();
+    let visible = |index: &u32| {
+        instances.get(*index as usize).is_none_or(|instance| {
+            instance.visible && frustum.intersects_sphere(&instance.transform_sphere(&sphere))
+        })
+    };
+
+    let start = range.clone().find(visible)?;
+    let end = range.rev().find(visible)? + 1;
+    Some((start, end))
+}
+
+// Splits the instances of a mesh into the runs of instances that are on the screen, used
+// when the runs can be drawn with a single multi draw
+fn visible_runs(mesh: &Mesh, instances: &[Instance], frustum: &Frustum) -> Vec<(u32, u32)> {
+    let sphere = mesh.bounding_sphere();
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+
+    for index in mesh.get_instances() {
+        let visible = instances.get(index as usize).is_none_or(|instance| {
+            instance.visible && frustum.intersects_sphere(&instance.transform_sphere(&sphere))
+        });
+        if !visible {
+            continue;
+        }
+
+        match runs.last_mut() {
+            Some((_, end)) if *end == index => *end += 1,
+            _ => runs.push((index, index + 1)),
+        }
+    }
+
+    runs
+}
+
+/// Collects the draws of every loaded model, the opaque draws sorted by pipeline, material,
+/// and mesh and the blended draws after them sorted from back to front
+///
+/// # Arguments
+///
+/// * `models` - The models of the scene
+/// * `scatters` - The scattered models, they are culled on the gpu instead
+/// * `reflections` - The reflective surfaces
+/// * `instances` - The instances of the models
+/// * `frustum` - The frustum of the camera, `None` draws every model
+/// * `eye` - The position of the camera the blended draws are sorted by
+/// * `split_runs` - Draws every run of visible instances of a mesh separately instead of
+///   everything between the first and last visible instance
+///
+/// # Returns
+///
+/// The sorted draws and the number of mesh draws that were culled
+pub(crate) fn build_draw_list(
+    models: &[Option<Model>],
+    scatters: &[Scatter],
+    reflections: &ReflectionRenderer,
+    instances: &[Instance],
+    frustum: Option<&Frustum>,
+    eye: Point3<f32>,
+    split_runs: bool,
+) -> (Vec<DrawCommand>, u32) {
+    let mut draws = Vec::new();
+    let mut culled = 0;
+
+    for (model_index, model) in models.iter().enumerate() {
+        let Some(model) = model else {
+            continue;
+        };
+
+        let scatter_index = scatters
+            .iter()
+            .position(|scatter| scatter.get_object_index() == model_index);
+
+        let pipeline = match (scatter_index, reflections.get_reflection_index(model_index)) {
+            (Some(scatter_index), _) => DrawPipeline::Scatter(scatter_index),
+            (None, Some(reflection_index)) => DrawPipeline::Reflection(reflection_index),
+            (None, None) => DrawPipeline::Model,
+        };
+
+        for (mesh_index, mesh) in model.get_meshes().iter().enumerate() {
+            // Morph targets are only blended by the model pipeline
+            let pipeline = match pipeline {
+                DrawPipeline::Model if mesh.has_morph_targets() => DrawPipeline::Morph,
+                pipeline => pipeline,
+            };
+
+            let range = mesh.get_instances();
+            let visible = match (frustum, pipeline) {
+                (
+                    Some(frustum),
+                    DrawPipeline::Model | DrawPipeline::Morph | DrawPipeline::Reflection(_),
+                ) => {
+                    if split_runs {
+                        visible_runs(mesh, instances, frustum)
+                    } else {
+                        visible_instances(mesh, instances, frustum)
+                            .into_iter()
+                            .collect()
+                    }
+                }
+                _ => vec![(range.start, range.end)],
+            };
+
+            if visible.is_empty() {
+                culled += 1;
+                continue;
+            }
+
+            let material_index = *mesh.get_material_index().unwrap();
+            let variant = PipelineVariant::new(mesh, &model.get_materials()[material_index]);
+            let sphere = mesh.bounding_sphere();
+            draws.extend(visible.into_iter().map(|run| DrawCommand {
+                // The run is sorted by its first instance, scattered instances are not in
+                // the instances of the scene so the mesh is sorted as a whole
+                layer: if variant.blended {
+                    let center = match pipeline {
+                        DrawPipeline::Scatter(_) => sphere.center,
+                        _ => instances
+                            .get(run.0 as usize)
+                            .map_or(sphere.center, |instance| {
+                                instance.transform_sphere(&sphere).center
+                            }),
+                    };
+                    DrawLayer::blended((center - eye.to_vec()).magnitude())
+                } else {
+                    DrawLayer::Opaque
+                },
+                pipeline,
+                variant,
+                material: (model_index, material_index),
+                mesh: (model_index, mesh_index),
+                instances: run,
+            }));
+        }
+    }
+
+    draws.sort_unstable();
+    (draws, culled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw(layer: DrawLayer, model_index: usize) -> DrawCommand {
+        DrawCommand {
+            layer,
+            pipeline: DrawPipeline::Model,
+            variant: PipelineVariant::default(),
+            material: (model_index, 0),
+            mesh: (model_index, 0),
+            instances: (0, 1),
+        }
+    }
+
+    #[test]
+    fn test_blended_draws_after_opaque_draws() {
+        let mut draws = [
+            draw(DrawLayer::blended(2.0), 0),
+            draw(DrawLayer::Opaque, 1),
+            draw(DrawLayer::blended(10.0), 2),
+            draw(DrawLayer::Opaque, 3),
+            draw(DrawLayer::blended(5.0), 4),
+        ];
+        draws.sort_unstable();
+
+        let models = draws.iter().map(|draw| draw.mesh.0).collect::<Vec<_>>();
+        assert_eq!(models, vec![1, 3, 2, 4, 0]);
+    }
+}
@@ -0,0 +1,238 @@
+use std::mem;
+
+use cgmath::Vector3;
+use wgpu::{
+    include_wgsl, BindGroup, BlendState, Buffer, BufferAddress, BufferDescriptor, BufferUsages,
+    ColorTargetState, ColorWrites, CompareFunction, DepthBiasState, DepthStencilState, Device,
+    FragmentState, IndexFormat, MultisampleState, PipelineCompilationOptions,
+    PipelineLayoutDescriptor, PrimitiveState, PrimitiveTopology, Queue, RenderPass, RenderPipeline,
+    RenderPipelineDescriptor, StencilState, TextureFormat, VertexAttribute, VertexBufferLayout,
+    VertexFormat, VertexState, VertexStepMode,
+};
+
+use crate::{
+    helium_texture,
+    layouts::{LayoutKind, LayoutRegistry},
+};
+
+// The buffers start with room for this many vertices and indices and double when full
+const INITIAL_CAPACITY: usize = 1024;
+
+/// A colored vertex of a dynamic mesh in world space
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DynamicVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+}
+
+impl DynamicVertex {
+    pub fn new(position: Vector3<f32>, color: [f32; 4]) -> Self {
+        Self {
+            position: position.into(),
+            color,
+        }
+    }
+
+    fn desc() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: mem::size_of::<DynamicVertex>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: VertexFormat::Float32x3,
+                },
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 3]>() as BufferAddress,
+                    shader_location: 1,
+                    format: VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// Triangles that are built on the cpu every update, like trails and ribbons. They are
+/// drawn unlit and blended over the scene without hiding what is behind them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DynamicMesh {
+    pub vertices: Vec<DynamicVertex>,
+    pub indices: Vec<u32>,
+}
+
+impl DynamicMesh {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a vertex and returns its index in the mesh
+    pub fn push_vertex(&mut self, vertex: DynamicVertex) -> u32 {
+        self.vertices.push(vertex);
+        self.vertices.len() as u32 - 1
+    }
+
+    pub fn push_triangle(&mut self, a: u32, b: u32, c: u32) {
+        self.indices.extend([a, b, c]);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+/// The dynamic meshes that are drawn over the scene, meshes are collected while the scene
+/// updates and drawn every frame until the next update is flushed
+pub(crate) struct DynamicMeshRenderer {
+    pipeline: RenderPipeline,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    vertex_capacity: usize,
+    index_capacity: usize,
+    // Meshes added since the last flush, merged into one
+    pending: DynamicMesh,
+    // The mesh that is drawn until the next flush
+    mesh: DynamicMesh,
+    // Whether the mesh has changed since it was written to the buffers
+    dirty: bool,
+}
+
+impl DynamicMeshRenderer {
+    pub fn new(device: &Device, layouts: &LayoutRegistry, format: TextureFormat) -> Self {
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Dynamic Mesh Render Pipeline Layout"),
+            bind_group_layouts: &[layouts.get(LayoutKind::Camera)],
+            push_constant_ranges: &[],
+        });
+
+        // The vertices are colored points like the debug lines
+        let shader = device.create_shader_module(include_wgsl!("./shaders/debug_draw_shader.wgsl"));
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Dynamic Mesh Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[DynamicVertex::desc()],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            // Ribbons twist so both sides of the triangles are drawn
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: helium_texture::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::LessEqual,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            pipeline,
+            vertex_buffer: Self::create_vertex_buffer(device, INITIAL_CAPACITY),
+            index_buffer: Self::create_index_buffer(device, INITIAL_CAPACITY),
+            vertex_capacity: INITIAL_CAPACITY,
+            index_capacity: INITIAL_CAPACITY,
+            pending: DynamicMesh::default(),
+            mesh: DynamicMesh::default(),
+            dirty: false,
+        }
+    }
+
+    fn create_vertex_buffer(device: &Device, capacity: usize) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("Dynamic Mesh Vertex Buffer"),
+            size: (capacity * mem::size_of::<DynamicVertex>()) as BufferAddress,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_index_buffer(device: &Device, capacity: usize) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("Dynamic Mesh Index Buffer"),
+            size: (capacity * mem::size_of::<u32>()) as BufferAddress,
+            usage: BufferUsages::INDEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    pub fn add(&mut self, mesh: &DynamicMesh) {
+        let offset = self.pending.vertices.len() as u32;
+        self.pending.vertices.extend_from_slice(&mesh.vertices);
+        self.pending
+            .indices
+            .extend(mesh.indices.iter().map(|index| index + offset));
+    }
+
+    /// Replaces the mesh that is drawn with the meshes added since the last flush
+    pub fn flush(&mut self) {
+        self.mesh = mem::take(&mut self.pending);
+        self.dirty = true;
+    }
+
+    // Whether there is anything to draw this frame
+    pub fn has_meshes(&self) -> bool {
+        !self.mesh.is_empty()
+    }
+
+    pub fn draw(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        render_pass: &mut RenderPass,
+        camera_bind_group: &BindGroup,
+    ) {
+        if self.mesh.is_empty() {
+            return;
+        }
+
+        if self.dirty {
+            if self.mesh.vertices.len() > self.vertex_capacity {
+                self.vertex_capacity = self.mesh.vertices.len().next_power_of_two();
+                self.vertex_buffer = Self::create_vertex_buffer(device, self.vertex_capacity);
+            }
+            if self.mesh.indices.len() > self.index_capacity {
+                self.index_capacity = self.mesh.indices.len().next_power_of_two();
+                self.index_buffer = Self::create_index_buffer(device, self.index_capacity);
+            }
+
+            queue.write_buffer(
+                &self.vertex_buffer,
+                0,
+                bytemuck::cast_slice(&self.mesh.vertices),
+            );
+            queue.write_buffer(
+                &self.index_buffer,
+                0,
+                bytemuck::cast_slice(&self.mesh.indices),
+            );
+            self.dirty = false;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.mesh.indices.len() as u32, 0, 0..1);
+    }
+}
@@ -0,0 +1,414 @@
+use std::{io, path::Path};
+
+use wgpu::{
+    include_wgsl,
+    util::{BufferInitDescriptor, DeviceExt},
+    AddressMode, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBindingType, BufferUsages,
+    CommandEncoderDescriptor, ComputePassDescriptor, ComputePipeline, ComputePipelineDescriptor,
+    Device, Extent3d, FilterMode, Origin3d, PipelineCompilationOptions, PipelineLayoutDescriptor,
+    Queue, Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages, StorageTextureAccess,
+    TexelCopyBufferLayout, TexelCopyTextureInfo, Texture, TextureAspect, TextureDescriptor,
+    TextureDimension, TextureFormat, TextureSampleType, TextureUsages, TextureView,
+    TextureViewDescriptor, TextureViewDimension,
+};
+
+use crate::profile_span;
+
+// Sizes of the faces of the prefiltered cube maps
+const IRRADIANCE_SIZE: u32 = 32;
+const SPECULAR_SIZE: u32 = 128;
+const SPECULAR_MIPS: u32 = 6;
+
+// Number of samples along each axis of the hemisphere for the irradiance map
+const IRRADIANCE_STEPS: u32 = 32;
+// Number of importance samples for each texel of the specular map
+const SPECULAR_SAMPLES: u32 = 128;
+
+const CUBE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+const WORKGROUP_SIZE: u32 = 8;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct EnvironmentUniform {
+    intensity: f32,
+    specular_mips: f32,
+    enabled: u32,
+    _padding: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct PrefilterParams {
+    roughness: f32,
+    sample_count: u32,
+    _padding: [u32; 2],
+}
+
+/// Image based lighting from an environment map, the environment is prefiltered into an
+/// irradiance map for diffuse lighting and a mipmapped specular map for reflections
+pub struct Environment {
+    irradiance_view: TextureView,
+    specular_view: TextureView,
+    sampler: Sampler,
+    buffer: Buffer,
+    intensity: f32,
+    enabled: bool,
+}
+
+impl Environment {
+    /// Creates an environment that does not light anything, used when no environment is set
+    pub fn empty(device: &Device) -> Self {
+        let irradiance = Self::create_cube(device, "Empty Irradiance Map", 1, 1);
+        let specular = Self::create_cube(device, "Empty Specular Map", 1, 1);
+
+        Self::from_textures(device, &irradiance, &specular, 1, 0.0, false)
+    }
+
+    /// Loads an HDR image in the equirectangular format and prefilters it on the gpu
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Filepath to the HDR image
+    /// * `intensity` - How bright the lighting from the environment is
+    pub fn from_hdr<P>(path: P, intensity: f32, device: &Device, queue: &Queue) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let _span = profile_span("load_environment");
+        let image = image::open(path.as_ref())
+            .map_err(io::Error::other)?
+            .to_rgba32f();
+        let (width, height) = image.dimensions();
+
+        let source = device.create_texture(&TextureDescriptor {
+            label: Some("Environment Texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba32Float,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: &source,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            bytemuck::cast_slice(image.as_raw()),
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(16 * width),
+                rows_per_image: Some(height),
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        let irradiance = Self::create_cube(device, "Irradiance Map", IRRADIANCE_SIZE, 1);
+        let specular = Self::create_cube(device, "Specular Map", SPECULAR_SIZE, SPECULAR_MIPS);
+
+        Self::prefilter(device, queue, &source, &irradiance, &specular);
+
+        Ok(Self::from_textures(
+            device,
+            &irradiance,
+            &specular,
+            SPECULAR_MIPS,
+            intensity,
+            true,
+        ))
+    }
+
+    fn create_cube(device: &Device, label: &str, size: u32, mips: u32) -> Texture {
+        device.create_texture(&TextureDescriptor {
+            label: Some(label),
+            size: Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 6,
+            },
+            mip_level_count: mips,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: CUBE_FORMAT,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::STORAGE_BINDING,
+            view_formats: &[],
+        })
+    }
+
+    fn from_textures(
+        device: &Device,
+        irradiance: &Texture,
+        specular: &Texture,
+        specular_mips: u32,
+        intensity: f32,
+        enabled: bool,
+    ) -> Self {
+        let cube_view = |texture: &Texture| {
+            texture.create_view(&TextureViewDescriptor {
+                dimension: Some(TextureViewDimension::Cube),
+                ..Default::default()
+            })
+        };
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Environment Sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Environment Buffer"),
+            contents: bytemuck::cast_slice(&[EnvironmentUniform {
+                intensity,
+                specular_mips: specular_mips as f32,
+                enabled: enabled as u32,
+                _padding: 0,
+            }]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        Self {
+            irradiance_view: cube_view(irradiance),
+            specular_view: cube_view(specular),
+            sampler,
+            buffer,
+            intensity,
+            enabled,
+        }
+    }
+
+    // Runs the compute passes that fill the irradiance map and every mip of the specular map
+    fn prefilter(
+        device: &Device,
+        queue: &Queue,
+        source: &Texture,
+        irradiance: &Texture,
+        specular: &Texture,
+    ) {
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Environment Prefilter Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: TextureViewDimension::D2,
+                        sample_type: TextureSampleType::Float { filterable: false },
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::StorageTexture {
+                        access: StorageTextureAccess::WriteOnly,
+                        format: CUBE_FORMAT,
+                        view_dimension: TextureViewDimension::D2Array,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Environment Prefilter Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader =
+            device.create_shader_module(include_wgsl!("./shaders/environment_prefilter.wgsl"));
+
+        let create_pipeline = |entry_point: &str| -> ComputePipeline {
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some(&format!("Environment {} Pipeline", entry_point)),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: PipelineCompilationOptions::default(),
+                cache: None,
+            })
+        };
+
+        let irradiance_pipeline = create_pipeline("irradiance");
+        let specular_pipeline = create_pipeline("specular");
+
+        let source_view = source.create_view(&TextureViewDescriptor::default());
+
+        // Every pass writes to a single mip of one of the cube maps
+        let mut passes = vec![(
+            &irradiance_pipeline,
+            irradiance,
+            0,
+            PrefilterParams {
+                roughness: 1.0,
+                sample_count: IRRADIANCE_STEPS,
+                _padding: [0; 2],
+            },
+        )];
+
+        for mip in 0..SPECULAR_MIPS {
+            passes.push((
+                &specular_pipeline,
+                specular,
+                mip,
+                PrefilterParams {
+                    roughness: mip as f32 / (SPECULAR_MIPS - 1) as f32,
+                    sample_count: SPECULAR_SAMPLES,
+                    _padding: [0; 2],
+                },
+            ));
+        }
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Environment Prefilter Encoder"),
+        });
+
+        for (pipeline, texture, mip, params) in passes {
+            let output_view = texture.create_view(&TextureViewDescriptor {
+                dimension: Some(TextureViewDimension::D2Array),
+                base_mip_level: mip,
+                mip_level_count: Some(1),
+                ..Default::default()
+            });
+
+            let params_buffer = device.create_buffer_init(&BufferInitDescriptor {
+                label: Some("Environment Prefilter Buffer"),
+                contents: bytemuck::cast_slice(&[params]),
+                usage: BufferUsages::UNIFORM,
+            });
+
+            let bind_group = device.create_bind_group(&BindGroupDescriptor {
+                label: Some("Environment Prefilter Bind Group"),
+                layout: &layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(&source_view),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::TextureView(&output_view),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                ],
+            });
+
+            let size = (texture.width() >> mip).max(1);
+            let workgroups = size.div_ceil(WORKGROUP_SIZE);
+
+            let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("Environment Prefilter Pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(workgroups, workgroups, 6);
+        }
+
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Layout entries of the environment in the lighting bind group starting at a binding
+    pub fn layout_entries(first_binding: u32) -> [BindGroupLayoutEntry; 4] {
+        let cube_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                multisampled: false,
+                view_dimension: TextureViewDimension::Cube,
+                sample_type: TextureSampleType::Float { filterable: true },
+            },
+            count: None,
+        };
+
+        [
+            cube_entry(first_binding),
+            cube_entry(first_binding + 1),
+            BindGroupLayoutEntry {
+                binding: first_binding + 2,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: first_binding + 3,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ]
+    }
+
+    /// Bind group entries of the environment matching `layout_entries`
+    pub fn bind_group_entries(&self, first_binding: u32) -> [BindGroupEntry<'_>; 4] {
+        [
+            BindGroupEntry {
+                binding: first_binding,
+                resource: BindingResource::TextureView(&self.irradiance_view),
+            },
+            BindGroupEntry {
+                binding: first_binding + 1,
+                resource: BindingResource::TextureView(&self.specular_view),
+            },
+            BindGroupEntry {
+                binding: first_binding + 2,
+                resource: BindingResource::Sampler(&self.sampler),
+            },
+            BindGroupEntry {
+                binding: first_binding + 3,
+                resource: self.buffer.as_entire_binding(),
+            },
+        ]
+    }
+
+    pub fn get_intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Changes how bright the lighting from the environment is
+    pub fn set_intensity(&mut self, intensity: f32, queue: &Queue) {
+        self.intensity = intensity;
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[intensity]));
+    }
+}
@@ -2,20 +2,26 @@
 use wgpu::{
     AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
     BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, CompareFunction,
-    Device, Extent3d, FilterMode, Queue, Sampler, SamplerBindingType, SamplerDescriptor,
-    ShaderStages, SurfaceConfiguration, TexelCopyBufferLayout, Texture, TextureDescriptor,
-    TextureDimension, TextureFormat, TextureSampleType, TextureUsages, TextureView,
-    TextureViewDescriptor, TextureViewDimension,
+    Device, Extent3d, FilterMode, Origin3d, Queue, Sampler, SamplerBindingType, SamplerDescriptor,
+    ShaderStages, SurfaceConfiguration, TexelCopyBufferLayout, TexelCopyTextureInfo, Texture,
+    TextureAspect, TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType,
+    TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
 };
 
 // image imports
-use image::{load_from_memory, GenericImageView, ImageError};
+use image::{
+    error::{ParameterError, ParameterErrorKind},
+    load_from_memory, GenericImageView, ImageError,
+};
 
 // logging
 use log::*;
 
+use crate::compressed_texture;
+
 // Constants
-pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+// The stencil is used to draw outlines around highlighted objects
+pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth24PlusStencil8;
 
 // In the bind group, binding 0 is the texture, and binding 1 is the sampler
 // only visible in the fragment shader
@@ -42,6 +48,30 @@ const HELIUM_TEXTURE_BIND_GROUP_LAYOUT_DESCRIPTOR: BindGroupLayoutDescriptor =
         ],
     };
 
+// Same as the texture layout but the texture is an array of layers
+const HELIUM_TEXTURE_ARRAY_BIND_GROUP_LAYOUT_DESCRIPTOR: BindGroupLayoutDescriptor =
+    BindGroupLayoutDescriptor {
+        label: Some("Texture array bind group layout"),
+        entries: &[
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: TextureViewDimension::D2Array,
+                    sample_type: TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    };
+
 #[allow(unused)]
 pub struct HeliumTexture {
     texture: Texture,
@@ -56,15 +86,166 @@ impl HeliumTexture {
         device.create_bind_group_layout(&HELIUM_TEXTURE_BIND_GROUP_LAYOUT_DESCRIPTOR)
     }
 
+    pub fn get_array_layout(device: &Device) -> BindGroupLayout {
+        device.create_bind_group_layout(&HELIUM_TEXTURE_ARRAY_BIND_GROUP_LAYOUT_DESCRIPTOR)
+    }
+
     pub fn get_bind_group(&self) -> Option<&BindGroup> {
         self.bind_group.as_ref()
     }
 
     pub fn from_bytes(device: &Device, queue: &Queue, bytes: &[u8]) -> Result<Self, ImageError> {
+        Self::from_bytes_with_format(device, queue, bytes, TextureFormat::Rgba8UnormSrgb)
+    }
+
+    /// Creates a texture from encoded image bytes stored in the specified format
+    /// Use a linear format for data textures like normal maps
+    ///
+    /// KTX2 and DDS files are uploaded in their block compressed format with their mip levels,
+    /// they are decoded to RGBA8 when the gpu can not sample the format
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - The device to create the texture on
+    /// * `queue` - The queue to write the texture data with
+    /// * `bytes` - The encoded image bytes
+    /// * `format` - The RGBA8 format to store the texture as, compressed textures use its
+    ///   color space
+    pub fn from_bytes_with_format(
+        device: &Device,
+        queue: &Queue,
+        bytes: &[u8],
+        format: TextureFormat,
+    ) -> Result<Self, ImageError> {
+        if compressed_texture::is_compressed_container(bytes) {
+            return Self::from_compressed(device, queue, bytes, format.is_srgb());
+        }
+
         let img = load_from_memory(bytes)?;
         let rgba = img.to_rgba8();
-        let dimensions = img.dimensions();
 
+        Ok(Self::from_rgba_with_format(
+            device,
+            queue,
+            &rgba,
+            img.dimensions(),
+            format,
+        ))
+    }
+
+    // Uploads the blocks as they are or decodes them when the gpu does not support the format
+    fn from_compressed(
+        device: &Device,
+        queue: &Queue,
+        bytes: &[u8],
+        srgb: bool,
+    ) -> Result<Self, ImageError> {
+        let image = compressed_texture::parse(bytes, srgb)?;
+        let (width, height) = image.dimensions;
+
+        // Compressed textures have to be made of whole blocks
+        let (block_width, block_height) = image.format.block_dimensions();
+        if device.features().contains(image.format.required_features())
+            && width % block_width == 0
+            && height % block_height == 0
+        {
+            return Ok(Self::from_levels(
+                device,
+                queue,
+                &image.levels,
+                image.dimensions,
+                image.format,
+            ));
+        }
+
+        debug!("Decoding {:?} texture", image.format);
+        let levels = image
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(level, data)| {
+                compressed_texture::decode_level(
+                    image.format,
+                    compressed_texture::level_dimensions(image.dimensions, level),
+                    data,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let format = if srgb {
+            TextureFormat::Rgba8UnormSrgb
+        } else {
+            TextureFormat::Rgba8Unorm
+        };
+
+        Ok(Self::from_levels(
+            device,
+            queue,
+            &levels,
+            image.dimensions,
+            format,
+        ))
+    }
+
+    /// Creates a 1x1 texture filled with a single color
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - The device to create the texture on
+    /// * `queue` - The queue to write the texture data with
+    /// * `color` - The RGBA color of the texture
+    pub fn from_color(device: &Device, queue: &Queue, color: [u8; 4]) -> Self {
+        Self::from_rgba(device, queue, &color, (1, 1))
+    }
+
+    /// Creates a texture from raw RGBA8 pixel data
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - The device to create the texture on
+    /// * `queue` - The queue to write the texture data with
+    /// * `rgba` - The pixel data, 4 bytes per pixel
+    /// * `dimensions` - The width and height of the texture in pixels
+    pub fn from_rgba(device: &Device, queue: &Queue, rgba: &[u8], dimensions: (u32, u32)) -> Self {
+        Self::from_rgba_with_format(
+            device,
+            queue,
+            rgba,
+            dimensions,
+            TextureFormat::Rgba8UnormSrgb,
+        )
+    }
+
+    /// Creates a texture from raw RGBA8 pixel data stored in the specified format
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - The device to create the texture on
+    /// * `queue` - The queue to write the texture data with
+    /// * `rgba` - The pixel data, 4 bytes per pixel
+    /// * `dimensions` - The width and height of the texture in pixels
+    /// * `format` - The RGBA8 format to store the texture as
+    pub fn from_rgba_with_format(
+        device: &Device,
+        queue: &Queue,
+        rgba: &[u8],
+        dimensions: (u32, u32),
+        format: TextureFormat,
+    ) -> Self {
+        Self::from_levels(device, queue, &[rgba], dimensions, format)
+    }
+
+    // Creates a texture with a mip level for each of the levels, from the largest to the smallest
+    fn from_levels<L>(
+        device: &Device,
+        queue: &Queue,
+        levels: &[L],
+        dimensions: (u32, u32),
+        format: TextureFormat,
+    ) -> Self
+    where
+        L: AsRef<[u8]>,
+    {
         let size = Extent3d {
             width: dimensions.0,
             height: dimensions.1,
@@ -74,25 +255,16 @@ impl HeliumTexture {
         let texture = device.create_texture(&TextureDescriptor {
             label: Some("Texture"),
             size,
-            mip_level_count: 1,
+            mip_level_count: levels.len() as u32,
             sample_count: 1,
             dimension: TextureDimension::D2,
-            format: TextureFormat::Rgba8UnormSrgb,
+            format,
             usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
             view_formats: &[],
         });
 
         let view = texture.create_view(&TextureViewDescriptor::default());
-        // TODO: Add support for changing the texture filter modes
-        let sampler = device.create_sampler(&SamplerDescriptor {
-            address_mode_u: AddressMode::ClampToEdge,
-            address_mode_v: AddressMode::ClampToEdge,
-            address_mode_w: AddressMode::ClampToEdge,
-            mag_filter: FilterMode::Nearest,
-            min_filter: FilterMode::Nearest,
-            mipmap_filter: FilterMode::Nearest,
-            ..Default::default()
-        });
+        let sampler = Self::create_sampler(device);
 
         let layout = device.create_bind_group_layout(&HELIUM_TEXTURE_BIND_GROUP_LAYOUT_DESCRIPTOR);
 
@@ -112,24 +284,188 @@ impl HeliumTexture {
         });
 
         debug!("texture size: {:?}", size);
-        // Write the texture to the queue
-        queue.write_texture(
-            texture.as_image_copy(),
-            &rgba,
-            TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
+        // Write every mip level of the texture to the queue
+        let (_, block_height) = format.block_dimensions();
+        for (level, data) in levels.iter().enumerate() {
+            let level_size = size
+                .mip_level_size(level as u32, TextureDimension::D2)
+                .physical_size(format);
+
+            queue.write_texture(
+                TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: level as u32,
+                    origin: Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                data.as_ref(),
+                TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(compressed_texture::bytes_per_row(
+                        format,
+                        level_size.width,
+                    )),
+                    rows_per_image: Some(level_size.height / block_height),
+                },
+                level_size,
+            );
+        }
+
+        Self {
+            texture,
+            view,
+            sampler,
+            layout: Some(layout),
+            bind_group: Some(bind_group),
+        }
+    }
+
+    /// Creates a 2d texture array from encoded images that all have the same size, each image
+    /// is a layer that the shaders sample by its index
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - The device to create the texture on
+    /// * `queue` - The queue to write the texture data with
+    /// * `layers` - The encoded image bytes of each layer
+    /// * `format` - The RGBA8 format to store the texture as
+    pub fn array_from_bytes_with_format(
+        device: &Device,
+        queue: &Queue,
+        layers: &[&[u8]],
+        format: TextureFormat,
+    ) -> Result<Self, ImageError> {
+        let images = layers
+            .iter()
+            .map(|bytes| load_from_memory(bytes).map(|image| image.to_rgba8()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let dimensions = images
+            .first()
+            .map(|image| image.dimensions())
+            .ok_or_else(|| {
+                ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::Generic(
+                    String::from("A texture array needs at least one layer"),
+                )))
+            })?;
+
+        if images.iter().any(|image| image.dimensions() != dimensions) {
+            return Err(ImageError::Parameter(ParameterError::from_kind(
+                ParameterErrorKind::DimensionMismatch,
+            )));
+        }
+
+        let layers = images
+            .iter()
+            .map(|image| image.as_raw().as_slice())
+            .collect::<Vec<_>>();
+
+        Ok(Self::array_from_rgba_with_format(
+            device, queue, &layers, dimensions, format,
+        ))
+    }
+
+    /// Creates a 2d texture array from raw RGBA8 pixel data
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - The device to create the texture on
+    /// * `queue` - The queue to write the texture data with
+    /// * `layers` - The pixel data of each layer, 4 bytes per pixel
+    /// * `dimensions` - The width and height of every layer in pixels
+    /// * `format` - The RGBA8 format to store the texture as
+    pub fn array_from_rgba_with_format(
+        device: &Device,
+        queue: &Queue,
+        layers: &[&[u8]],
+        dimensions: (u32, u32),
+        format: TextureFormat,
+    ) -> Self {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Texture Array"),
+            size: Extent3d {
+                width: dimensions.0,
+                height: dimensions.1,
+                depth_or_array_layers: layers.len().max(1) as u32,
             },
-            size,
-        );
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        // A single layer would be viewed as a plain 2d texture by default
+        let view = texture.create_view(&TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let sampler = Self::create_sampler(device);
+
+        let layout =
+            device.create_bind_group_layout(&HELIUM_TEXTURE_ARRAY_BIND_GROUP_LAYOUT_DESCRIPTOR);
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Texture Array Bind Group"),
+            layout: &layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        for (layer, rgba) in layers.iter().enumerate() {
+            queue.write_texture(
+                TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: TextureAspect::All,
+                },
+                rgba,
+                TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * dimensions.0),
+                    rows_per_image: Some(dimensions.1),
+                },
+                Extent3d {
+                    width: dimensions.0,
+                    height: dimensions.1,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
 
-        Ok(Self {
+        Self {
             texture,
             view,
             sampler,
             layout: Some(layout),
             bind_group: Some(bind_group),
+        }
+    }
+
+    fn create_sampler(device: &Device) -> Sampler {
+        // TODO: Add support for changing the texture filter modes
+        device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
         })
     }
 
@@ -176,7 +512,34 @@ impl HeliumTexture {
         }
     }
 
+    /// The width and height of the texture in pixels
+    pub fn get_dimensions(&self) -> (u32, u32) {
+        (self.texture.width(), self.texture.height())
+    }
+
+    pub fn get_format(&self) -> TextureFormat {
+        self.texture.format()
+    }
+
+    /// The number of layers of a texture array, plain textures have one layer
+    pub fn get_layer_count(&self) -> u32 {
+        self.texture.depth_or_array_layers()
+    }
+
     pub fn get_view(&self) -> &TextureView {
         &self.view
     }
+
+    pub fn get_sampler(&self) -> &Sampler {
+        &self.sampler
+    }
+
+    /// Creates a view of only the depth of a depth texture so it can be read in a shader
+    pub fn create_depth_only_view(&self) -> TextureView {
+        self.texture.create_view(&TextureViewDescriptor {
+            label: Some("Depth Only Texture View"),
+            aspect: TextureAspect::DepthOnly,
+            ..Default::default()
+        })
+    }
 }
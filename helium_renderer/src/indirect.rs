@@ -0,0 +1,113 @@
+use wgpu::{
+    util::DrawIndexedIndirectArgs, Buffer, BufferAddress, BufferDescriptor, BufferUsages, Device,
+    Features, Queue,
+};
+
+use crate::{capabilities::RendererCapabilities, draw_list::DrawCommand, model::Model};
+
+// Number of draws the argument buffer has room for when it is created
+const INITIAL_CAPACITY: u64 = 256;
+
+// Size of the arguments of a single draw in the buffer
+const ARGS_SIZE: BufferAddress = std::mem::size_of::<DrawIndexedIndirectArgs>() as BufferAddress;
+
+/// The arguments of every draw of a frame in a buffer so the meshes can be drawn with
+/// indirect draws, draws of the same mesh with the same state are issued with a single
+/// multi draw when the device supports it. The buffer can also be bound as storage so a
+/// culling pass on the gpu can fill it
+pub(crate) struct IndirectDraws {
+    buffer: Buffer,
+    capacity: u64,
+    multi_draw: bool,
+    args: Vec<DrawIndexedIndirectArgs>,
+}
+
+impl IndirectDraws {
+    /// Indirect draws need to start at any instance since the meshes share the instance buffer
+    pub fn is_supported(capabilities: &RendererCapabilities) -> bool {
+        capabilities.has(Features::INDIRECT_FIRST_INSTANCE)
+    }
+
+    pub fn new(device: &Device, capabilities: &RendererCapabilities) -> Self {
+        Self {
+            buffer: Self::create_buffer(device, INITIAL_CAPACITY),
+            capacity: INITIAL_CAPACITY,
+            multi_draw: capabilities.supports_multi_draw(),
+            args: Vec::new(),
+        }
+    }
+
+    fn create_buffer(device: &Device, capacity: u64) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("Indirect Draw Buffer"),
+            size: capacity * ARGS_SIZE,
+            usage: BufferUsages::INDIRECT | BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Writes the arguments of the draws of this frame in the same order as the draws
+    ///
+    /// # Arguments
+    ///
+    /// * `draws` - The sorted draws of the frame
+    /// * `models` - The models the draws refer to
+    pub fn write(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        draws: &[DrawCommand],
+        models: &[Option<Model>],
+    ) {
+        self.args.clear();
+        self.args.extend(draws.iter().map(|draw| {
+            let (model_index, mesh_index) = draw.mesh;
+            let (start, end) = draw.instances;
+
+            // Draws of removed models still take a slot so offsets match the draw indices
+            let index_count = models[model_index]
+                .as_ref()
+                .map(|model| model.get_meshes()[mesh_index].get_num_elements())
+                .unwrap_or(0);
+
+            DrawIndexedIndirectArgs {
+                index_count,
+                instance_count: end - start,
+                first_index: 0,
+                base_vertex: 0,
+                first_instance: start,
+            }
+        }));
+
+        if self.args.is_empty() {
+            return;
+        }
+
+        let needed = self.args.len() as u64;
+        if needed > self.capacity {
+            self.capacity = needed.next_power_of_two();
+            self.buffer = Self::create_buffer(device, self.capacity);
+        }
+
+        let bytes = self
+            .args
+            .iter()
+            .flat_map(|args| args.as_bytes().iter().copied())
+            .collect::<Vec<_>>();
+        queue.write_buffer(&self.buffer, 0, &bytes);
+    }
+
+    pub fn get_buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Whether several draws can be issued with one multi draw call
+    pub fn supports_multi_draw(&self) -> bool {
+        self.multi_draw
+    }
+
+    /// Gives the offset of the arguments of a draw in the buffer
+    pub fn offset(draw_index: usize) -> BufferAddress {
+        draw_index as BufferAddress * ARGS_SIZE
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindingResource, Buffer,
+    Device, Sampler, TextureView,
+};
+
+use crate::{
+    camera::Camera,
+    helium_texture::HeliumTexture,
+    light::Lights,
+    model::{material::Material, morph::MorphTargets},
+    scatter::Scatter,
+    uniform_ring::UniformRing,
+};
+
+/// The bind group layouts that are shared by several pipelines
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LayoutKind {
+    Texture,
+    TextureArray,
+    Material,
+    Camera,
+    Lights,
+    Scatter,
+    UniformRing,
+    Morph,
+}
+
+impl LayoutKind {
+    const ALL: [LayoutKind; 8] = [
+        LayoutKind::Texture,
+        LayoutKind::TextureArray,
+        LayoutKind::Material,
+        LayoutKind::Camera,
+        LayoutKind::Lights,
+        LayoutKind::Scatter,
+        LayoutKind::UniformRing,
+        LayoutKind::Morph,
+    ];
+
+    fn create(&self, device: &Device) -> BindGroupLayout {
+        match self {
+            LayoutKind::Texture => HeliumTexture::get_layout(device),
+            LayoutKind::TextureArray => HeliumTexture::get_array_layout(device),
+            LayoutKind::Material => Material::get_layout(device),
+            LayoutKind::Camera => Camera::get_camera_layout(device),
+            LayoutKind::Lights => Lights::get_bind_group_layout(device),
+            LayoutKind::Scatter => Scatter::get_layout(device),
+            LayoutKind::UniformRing => UniformRing::get_layout(device),
+            LayoutKind::Morph => MorphTargets::get_layout(device),
+        }
+    }
+}
+
+/// Creates every shared bind group layout once when the device is created and keeps
+/// track of the layouts each pipeline was created with so bind groups can be checked
+/// against the pipeline they are bound to
+pub struct LayoutRegistry {
+    layouts: HashMap<LayoutKind, BindGroupLayout>,
+    // The layout of every bind group of each pipeline by the name of the pipeline
+    pipelines: HashMap<String, Vec<LayoutKind>>,
+}
+
+impl LayoutRegistry {
+    pub fn new(device: &Device) -> Self {
+        Self {
+            layouts: LayoutKind::ALL
+                .into_iter()
+                .map(|kind| (kind, kind.create(device)))
+                .collect(),
+            pipelines: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, kind: LayoutKind) -> &BindGroupLayout {
+        &self.layouts[&kind]
+    }
+
+    /// Gives the layouts for the bind groups of a pipeline and remembers them for checks
+    ///
+    /// # Arguments
+    ///
+    /// * `pipeline` - The name of the pipeline
+    /// * `kinds` - The layout of every bind group of the pipeline in order
+    ///
+    /// # Returns
+    ///
+    /// The layouts to create the pipeline layout with
+    pub fn register_pipeline(
+        &mut self,
+        pipeline: &str,
+        kinds: &[LayoutKind],
+    ) -> Vec<&BindGroupLayout> {
+        self.pipelines.insert(pipeline.to_string(), kinds.to_vec());
+        kinds.iter().map(|kind| self.get(*kind)).collect()
+    }
+
+    /// Checks that a bind group with a layout matches what a pipeline expects
+    ///
+    /// # Arguments
+    ///
+    /// * `pipeline` - The name the pipeline was registered with
+    /// * `group` - The index the bind group is bound at
+    /// * `kind` - The layout the bind group was created with
+    ///
+    /// # Returns
+    ///
+    /// Whether the bind group can be bound, unregistered pipelines are never matched
+    pub fn matches(&self, pipeline: &str, group: usize, kind: LayoutKind) -> bool {
+        self.pipelines
+            .get(pipeline)
+            .and_then(|kinds| kinds.get(group))
+            .is_some_and(|expected| *expected == kind)
+    }
+}
+
+/// A resource that a cached bind group is created from
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CachedResource {
+    Buffer(Buffer),
+    TextureView(TextureView),
+    Sampler(Sampler),
+}
+
+/// Bind groups keyed by their layout and resources, so the same resources are only
+/// bound into a bind group once
+#[derive(Default)]
+pub struct BindGroupCache {
+    bind_groups: HashMap<(LayoutKind, Vec<CachedResource>), BindGroup>,
+}
+
+impl BindGroupCache {
+    /// Gives the bind group for resources, it is created the first time it is asked for
+    ///
+    /// # Arguments
+    ///
+    /// * `layouts` - The registry with the layout of the bind group
+    /// * `kind` - The layout of the bind group
+    /// * `resources` - The resources at bindings 0, 1, 2 and so on
+    pub fn get_or_create(
+        &mut self,
+        device: &Device,
+        layouts: &LayoutRegistry,
+        kind: LayoutKind,
+        resources: &[CachedResource],
+    ) -> BindGroup {
+        self.bind_groups
+            .entry((kind, resources.to_vec()))
+            .or_insert_with(|| {
+                let entries = resources
+                    .iter()
+                    .enumerate()
+                    .map(|(binding, resource)| BindGroupEntry {
+                        binding: binding as u32,
+                        resource: match resource {
+                            CachedResource::Buffer(buffer) => buffer.as_entire_binding(),
+                            CachedResource::TextureView(view) => BindingResource::TextureView(view),
+                            CachedResource::Sampler(sampler) => BindingResource::Sampler(sampler),
+                        },
+                    })
+                    .collect::<Vec<_>>();
+
+                device.create_bind_group(&BindGroupDescriptor {
+                    label: Some(&format!("Cached {kind:?} bind group")),
+                    layout: layouts.get(kind),
+                    entries: &entries,
+                })
+            })
+            .clone()
+    }
+
+    /// Drops the bind groups that use a resource, called when the resource is destroyed
+    pub fn remove(&mut self, resource: &CachedResource) {
+        self.bind_groups
+            .retain(|(_, resources), _| !resources.contains(resource));
+    }
+
+    pub fn len(&self) -> usize {
+        self.bind_groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bind_groups.is_empty()
+    }
+}
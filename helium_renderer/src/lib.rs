@@ -1,5 +1,17 @@
 // std
-use std::{iter::once, path::Path, sync::Arc, time::Instant};
+use std::{
+    collections::HashMap,
+    fs, io,
+    iter::once,
+    mem,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Instant,
+};
 
 // async
 use smol::block_on;
@@ -8,7 +20,7 @@ use smol::block_on;
 use log::*;
 
 // Math
-use cgmath::{Point3, Vector3};
+use cgmath::{Point3, Quaternion, Vector3};
 
 // Wgpu imports
 use wgpu::{
@@ -16,15 +28,15 @@ use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
     Adapter, Backends, BindGroupLayout, BlendState, Buffer, BufferUsages, Color, ColorTargetState,
     ColorWrites, CommandEncoderDescriptor, CompareFunction, DepthBiasState, DepthStencilState,
-    Device, DeviceDescriptor, Face, Features, FragmentState, FrontFace, Instance,
-    InstanceDescriptor, Limits, LoadOp, MultisampleState, Operations, PipelineCompilationOptions,
-    PipelineLayoutDescriptor, PolygonMode, PowerPreference, PresentMode, PrimitiveState,
-    PrimitiveTopology, Queue, RenderPassColorAttachment, RenderPassDepthStencilAttachment,
-    RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, RequestAdapterOptionsBase,
-    StencilState, StoreOp, Surface, SurfaceCapabilities, SurfaceConfiguration, SurfaceError,
-    TextureUsages, TextureViewDescriptor, VertexState,
+    Device, DeviceDescriptor, FragmentState, IndexFormat, Instance, InstanceDescriptor, LoadOp,
+    Maintain, MultisampleState, Operations, PipelineCompilationOptions, PipelineLayoutDescriptor,
+    PolygonMode, PowerPreference, PresentMode, PrimitiveState, Queue, RenderPassColorAttachment,
+    RenderPassDepthStencilAttachment, RenderPassDescriptor, RenderPipeline,
+    RenderPipelineDescriptor, RequestAdapterOptionsBase, ShaderModuleDescriptor, StencilState,
+    StoreOp, Surface, SurfaceCapabilities, SurfaceConfiguration, SurfaceError, TextureFormat,
+    TextureUsages, TextureView, TextureViewDescriptor, VertexState,
 };
-use wgpu_text::glyph_brush::ab_glyph::FontRef;
+use wgpu_text::glyph_brush::ab_glyph::FontArc;
 pub use wgpu_text::{
     glyph_brush::{Section as TextSection, Text},
     BrushBuilder, TextBrush,
@@ -34,18 +46,123 @@ pub use wgpu_text::{
 use winit::{dpi::PhysicalSize, window::Window};
 
 // Modules
+pub mod atlas;
+pub mod bounds;
 pub mod camera;
+pub mod capabilities;
+pub mod color;
+mod compressed_texture;
+pub mod debug_draw;
+pub mod decal;
+pub mod draw_list;
+pub mod dynamic_mesh;
+pub mod environment;
 pub mod helium_texture;
+mod indirect;
+pub mod layouts;
 pub mod light;
 pub mod model;
+mod model_pipelines;
+pub mod outline;
+pub mod overlay;
+pub mod picking;
+pub mod post;
+mod profiler;
+mod recovery;
+pub mod reflection;
+pub mod render_graph;
+mod resolution;
 pub mod resources;
-
-pub use camera::Camera;
+pub mod scatter;
+mod sdf_text;
+pub mod shadow;
+pub mod sky;
+pub mod sprite;
+pub mod text;
+mod text_layout;
+pub mod ui;
+pub mod uniform_ring;
+
+pub use atlas::TextureAtlasBuilder;
+pub use bounds::{Aabb, BoundingSphere, Frustum};
+pub use camera::{Camera, ScreenPoint};
+pub use capabilities::RendererCapabilities;
+use color::{needs_shader_encode, surface_view_format};
+pub use color::{LinearRgba, Srgba};
+use debug_draw::DebugDraw;
+pub use debug_draw::{DebugLine, DebugText};
+use decal::DecalRenderer;
+pub use decal::{DecalProjector, DecalTexture};
+use draw_list::DrawPipeline;
+pub use draw_list::RenderStats;
+use dynamic_mesh::DynamicMeshRenderer;
+pub use dynamic_mesh::{DynamicMesh, DynamicVertex};
+use environment::Environment;
 use helium_texture::HeliumTexture;
+use indirect::IndirectDraws;
 use instance::InstanceRaw;
-pub use light::{Light, Lights};
+use layouts::{BindGroupCache, LayoutKind, LayoutRegistry};
+pub use light::{exposure_from_ev100, Light, LightKind, LightUnits, Lights};
 pub use model::instance;
-use model::{instance::INSTANCE_RAW_SIZE, model_vertex::ModelVertex, vertex::Vertex, Model};
+pub use model::material::{
+    AlphaMode, ColorMaterial, DepthBias, FaceMode, Flipbook, TextureAnimation, Winding,
+};
+pub use model::mesh::Topology;
+pub use model::morph::MAX_MORPH_TARGETS;
+pub use model::occlusion::AmbientOcclusionBake;
+pub use model::StaticBatchObject;
+use model::{
+    instance::INSTANCE_RAW_SIZE, model_vertex::ModelVertex, morph::MorphWeights, vertex::Vertex,
+    Model,
+};
+use model_pipelines::{ModelPipelines, PipelineVariant};
+pub use outline::Outline;
+use outline::OutlineRenderer;
+pub use overlay::OverlayQuad;
+use overlay::OverlayRenderer;
+pub use picking::PickRequest;
+use picking::PickingRenderer;
+pub use post::{
+    bloom::Bloom,
+    color_grading::ColorGradingLut,
+    depth_of_field::{DepthOfField, DepthOfFieldFocus},
+    exposure::Exposure,
+    lens::LensEffects,
+    lens_flare::{FlareElement, FlareShape, LensFlare, MAX_FLARE_ELEMENTS, MAX_FLARE_SOURCES},
+    motion_blur::MotionBlur,
+    screen_overlay::{ScreenOverlay, ScreenOverlayHandle, ScreenOverlayKind, MAX_SCREEN_OVERLAYS},
+    tonemap::Tonemapper,
+    AntiAliasing, PostSettings,
+};
+use post::{PostStack, HDR_FORMAT};
+pub use profiler::{
+    clear_profile, export_chrome_trace, get_last_frame_spans, get_profile_spans,
+    get_profile_threads, is_profiling, profile_frame, profile_span, set_profile_capacity,
+    set_profiling, ProfileRecord, ProfileSpan,
+};
+use recovery::{AssetRecords, ObjectRecord, ObjectSource};
+pub use reflection::Reflection;
+use reflection::ReflectionRenderer;
+use render_graph::FrameResources;
+pub use render_graph::{
+    CustomRenderPass, PassContext, RenderGraph, RenderPassHandle, RenderResource, RenderStage,
+};
+use resolution::{DepthUpscale, ResolutionController};
+pub use resolution::{DynamicResolution, RENDER_SCALE_RANGE};
+use scatter::Scatter;
+pub use scatter::{ScatterRegion, ScatterSettings};
+use sdf_text::SdfTextRenderer;
+pub use sdf_text::{SdfOutline, SdfPlacement, SdfShadow, SdfText};
+pub use shadow::{LightShadow, ShadowSettings};
+pub use sky::ProceduralSky;
+use sky::SkyRenderer;
+pub use sprite::{OverlaySprite, SpriteHandle};
+use sprite::{SpriteRegion, SpriteRenderer};
+use text::FontSet;
+pub use text::{FontHandle, OverlayText, TextOutline, TextStyle};
+pub use text_layout::{TextAlign, TextBounds, TextOverflow};
+pub use ui::{Anchor, UiLayout};
+use uniform_ring::UniformRing;
 
 pub type StartupFunction = fn(&mut HeliumState);
 pub type UpdateFunction = fn(&mut HeliumState, Instant);
@@ -55,9 +172,11 @@ pub type UpdateFunction = fn(&mut HeliumState, Instant);
 /// Constructs a render pipeine with a vertex shader and a fragment shader for the model vertices
 fn construct_render_pipline_from_layouts(
     layouts: Vec<&BindGroupLayout>,
+    vertex_shader: ShaderModuleDescriptor,
     device: &Device,
-    config: &SurfaceConfiguration,
+    format: TextureFormat,
     name: String,
+    variant: PipelineVariant,
 ) -> RenderPipeline {
     let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
         label: Some(&(name.clone() + " Render Pipeline Layout")),
@@ -65,7 +184,7 @@ fn construct_render_pipline_from_layouts(
         push_constant_ranges: &[],
     });
 
-    let vertex_shader = device.create_shader_module(include_wgsl!("./shaders/vertex_shader.wgsl"));
+    let vertex_shader = device.create_shader_module(vertex_shader);
 
     let fragment_shader =
         device.create_shader_module(include_wgsl!("./shaders/fragment_shader.wgsl"));
@@ -83,17 +202,22 @@ fn construct_render_pipline_from_layouts(
             module: &fragment_shader,
             entry_point: Some("main"),
             targets: &[Some(ColorTargetState {
-                format: config.format,
-                blend: Some(BlendState::REPLACE),
+                format,
+                // Opaque materials cover what is behind them whatever their alpha is
+                blend: Some(if variant.blended {
+                    BlendState::ALPHA_BLENDING
+                } else {
+                    BlendState::REPLACE
+                }),
                 write_mask: ColorWrites::ALL,
             })],
             compilation_options: PipelineCompilationOptions::default(),
         }),
         primitive: PrimitiveState {
-            topology: PrimitiveTopology::TriangleList,
-            strip_index_format: None,
-            front_face: FrontFace::Ccw,
-            cull_mode: Some(Face::Back),
+            topology: variant.topology.into(),
+            strip_index_format: variant.strip_index_format(),
+            front_face: variant.faces.front_face(),
+            cull_mode: variant.cull_mode(),
             // Change this to make a wireframe
             polygon_mode: PolygonMode::Fill,
             unclipped_depth: false,
@@ -101,10 +225,12 @@ fn construct_render_pipline_from_layouts(
         },
         depth_stencil: Some(DepthStencilState {
             format: helium_texture::DEPTH_FORMAT,
-            depth_write_enabled: true,
+            // Blended surfaces are tested against the opaque scene but do not hide each
+            // other, they are sorted from back to front instead
+            depth_write_enabled: !variant.blended,
             depth_compare: CompareFunction::Less,
             stencil: StencilState::default(),
-            bias: DepthBiasState::default(),
+            bias: variant.depth_bias.into(),
         }),
         multisample: MultisampleState {
             count: 1,
@@ -116,9 +242,24 @@ fn construct_render_pipline_from_layouts(
     })
 }
 
+// An object that has a slot in the renderer but is still loading
+struct PendingObject {
+    index: usize,
+    receiver: mpsc::Receiver<Result<Model, io::Error>>,
+    instances: Vec<instance::Instance>,
+    color_material: Option<ColorMaterial>,
+    texture_animation: Option<TextureAnimation>,
+}
+
 pub struct HeliumState {
-    surface: Surface<'static>,
+    // The window surface, headless renderers draw into an offscreen texture instead
+    surface: Option<Surface<'static>>,
+    headless_target: Option<wgpu::Texture>,
+    // The window the surface was created for, used to create it again after a device loss
+    window: Option<Arc<Window>>,
     device: Device,
+    // Set by the driver when the device is lost
+    device_lost: Arc<AtomicBool>,
     queue: Queue,
     pub config: SurfaceConfiguration,
 
@@ -131,24 +272,130 @@ pub struct HeliumState {
 
     // Depth texture for rendering the correct faces of a mesh
     depth_texture: HeliumTexture,
+    // The depth of the scene at the size of the screen for the world overlay, only used
+    // when the scene is drawn at another resolution than the screen
+    screen_depth: Option<HeliumTexture>,
+    depth_upscale: DepthUpscale,
+
+    // current pipeline for rendering, with a variant for every face mode of the materials
+    model_pipelines: ModelPipelines,
 
-    // current pipeline for rendering
-    render_pipeline: RenderPipeline,
+    // Models to render, removed models leave an empty slot so the indices stay valid
+    models: Vec<Option<Model>>,
 
-    // Models to render
-    models: Vec<Model>,
+    // Models that are being loaded on another thread
+    pending_objects: Vec<PendingObject>,
+
+    // Models that are culled on the gpu and drawn with the scatter pipeline
+    scatters: Vec<Scatter>,
 
     // Instances for all the instance
     model_instances: Vec<instance::Instance>,
 
     // Instance buffer for all the instances
     model_instance_buffer: Buffer,
+    // Morph target weights of every instance in the order of the instance buffer
+    morph_weights: MorphWeights,
+    // When the renderer started, the texture animations of the materials play from it
+    start_time: Instant,
+
+    // Textures projected onto the scene with the depth buffer
+    decal_renderer: DecalRenderer,
+
+    // The sky drawn behind the scene
+    sky_renderer: SkyRenderer,
+
+    // Outlines drawn around highlighted objects
+    outline_renderer: OutlineRenderer,
+    // Per frame uniforms that are bound with dynamic offsets
+    uniform_ring: UniformRing,
+
+    // Bind group layouts shared by the pipelines and bind groups created from them
+    layouts: LayoutRegistry,
+    bind_groups: BindGroupCache,
+
+    // The optional features and limits the device was created with
+    capabilities: RendererCapabilities,
+
+    // Lines and light gizmos drawn over the scene for debugging
+    debug_draw: DebugDraw,
+    dynamic_meshes: DynamicMeshRenderer,
+
+    // Offscreen instance ids used to find the object at a position on the screen
+    picking_renderer: PickingRenderer,
+
+    // Scene mirrored about reflective surfaces like water and mirrors
+    reflection_renderer: ReflectionRenderer,
+
+    // Effects applied to the hdr scene before it is drawn to the screen
+    post_stack: PostStack,
+
+    // Custom passes that users inserted into the frame
+    render_graph: RenderGraph,
 
     // Brush for the text ui
-    pub brush: TextBrush<FontRef<'static>>,
+    pub brush: TextBrush<FontArc>,
+
+    // Brush for text in the world that is hidden behind the scene
+    world_brush: TextBrush<FontArc>,
+
+    // Fonts that the brush can draw with, the index is the font handle
+    fonts: Vec<FontArc>,
+    // The fonts that draw the characters a font does not have, in the order they are tried
+    font_fallbacks: HashMap<FontHandle, Vec<FontHandle>>,
+
+    // Text to draw in the overlay
+    texts: Vec<Option<OverlayText>>,
+    // Text drawn from distance fields that stays sharp at any size, in the world and on
+    // the screen
+    sdf_texts: Vec<Option<SdfText>>,
+    sdf_text_renderer: SdfTextRenderer,
+
+    // Quads to draw in the overlay below the text
+    overlay_renderer: OverlayRenderer,
+    world_overlay_renderer: OverlayRenderer,
+    quads: Vec<Option<OverlayQuad>>,
+
+    // Images to draw in the overlay between the quads and the text
+    sprite_renderer: SpriteRenderer,
+    sprite_textures: Vec<HeliumTexture>,
+    // The part of a sprite texture that each sprite handle draws
+    sprite_regions: Vec<SpriteRegion>,
+    sprites: Vec<Option<OverlaySprite>>,
+    // Sprites that rich text draws inline by name
+    text_icons: HashMap<String, SpriteHandle>,
 
     // Fps to draw
     pub fps: String,
+    // Stats drawn under the fps, nothing is drawn when it is empty
+    pub stats: String,
+
+    // Statistics of the last drawn frame
+    render_stats: RenderStats,
+
+    // Physical pixels per logical pixel of the display the window is on
+    scale_factor: f64,
+
+    // Resolution of the scene compared to the screen, the scene is scaled up when it is
+    // drawn onto the screen
+    render_scale: f32,
+    // Changes the render scale with the frame time when set
+    dynamic_resolution: Option<ResolutionController>,
+
+    // Warns about textures and surfaces in the wrong color space when set
+    color_audit: bool,
+
+    // The ambient occlusion baked into the vertices of objects as they are loaded
+    vertex_occlusion: Option<AmbientOcclusionBake>,
+
+    // Skips drawing meshes that are outside of the view of the camera when set
+    frustum_culling: bool,
+
+    // Draws the meshes with indirect draws when set and supported by the device
+    indirect_draws: Option<IndirectDraws>,
+
+    // Where the objects and maps were loaded from so they can be loaded after a device loss
+    records: AssetRecords,
 }
 
 impl HeliumState {
@@ -170,31 +417,41 @@ impl HeliumState {
         self.model_instances.append(&mut instances);
         let range_end = self.model_instances.len() as u32;
 
-        self.models[object_index].set_instances(range_start..range_end);
+        if let Some(model) = self.models[object_index].as_mut() {
+            model.set_instances(range_start..range_end);
+        }
+
+        self.model_instance_buffer =
+            Self::create_instance_buffer(&self.device, &self.model_instances);
+        self.morph_weights
+            .write_all(&self.device, &self.queue, &self.model_instances);
 
-        self.model_instance_buffer = self.device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("Model instance buffer"),
-            contents: bytemuck::cast_slice(
+        self.queue.write_buffer(
+            &self.model_instance_buffer,
+            0,
+            bytemuck::cast_slice(
                 self.model_instances
                     .iter()
                     .map(|instance| instance.to_raw())
                     .collect::<Vec<_>>()
                     .as_slice(),
             ),
-            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
-        });
+        );
+    }
 
-        self.queue.write_buffer(
-            &self.model_instance_buffer,
-            0,
-            bytemuck::cast_slice(
-                self.model_instances
+    // Creates the buffer that every instance of every object is drawn from
+    fn create_instance_buffer(device: &Device, instances: &[instance::Instance]) -> Buffer {
+        device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Model instance buffer"),
+            contents: bytemuck::cast_slice(
+                instances
                     .iter()
                     .map(|instance| instance.to_raw())
                     .collect::<Vec<_>>()
                     .as_slice(),
             ),
-        );
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+        })
     }
 
     // Modify the particular instance in the instance buffer
@@ -207,6 +464,11 @@ impl HeliumState {
             (instance_index * INSTANCE_RAW_SIZE) as u64,
             bytemuck::cast_slice(&[data]),
         );
+        self.morph_weights.write(
+            &self.queue,
+            instance_index,
+            &self.model_instances[instance_index..=instance_index],
+        );
     }
 
     // Modify all the instances of a particular object
@@ -215,16 +477,25 @@ impl HeliumState {
         object_index: usize,
         mut instances: Vec<instance::Instance>,
     ) {
+        let (num_instances, offset) = match self.models[object_index].as_ref() {
+            Some(model) => (model.get_num_instances(), model.get_instances().start),
+            None => {
+                // Keep the latest instances until the object finishes loading
+                if let Some(pending) = self.get_pending_object(object_index) {
+                    pending.instances = instances;
+                }
+                return;
+            }
+        };
+
         // If the size of the new instances is greater than the range of the current instances
         // For the object, then disregard those instances and create a new set of instances
         // FIXME: find a better way to handle this
-        if instances.len() as u32 > self.models[object_index].get_num_instances() {
+        if instances.len() as u32 > num_instances {
             self.create_instances(object_index, instances);
             return;
         }
 
-        let offset = self.models[object_index].get_instances().start;
-
         // If the object is mapped to the default instance of the world origin then
         // create new instances as to not mess with the default instance
         if offset == 0 {
@@ -255,6 +526,179 @@ impl HeliumState {
             offset as u64 * INSTANCE_RAW_SIZE as u64,
             bytemuck::cast_slice(data.as_ref()),
         );
+        self.morph_weights.write(
+            &self.queue,
+            offset as usize,
+            &self.model_instances[offset as usize..offset as usize + size],
+        );
+    }
+
+    /// Updates the position, rotation, and scale of the instances of an object while keeping
+    /// the color and custom data of the instances
+    ///
+    /// # Arguments
+    ///
+    /// * `object_index` - The index of the object in the renderer
+    /// * `transforms` - The new position, rotation, and scale for each instance
+    pub fn update_instance_transforms(
+        &mut self,
+        object_index: usize,
+        transforms: Vec<(Vector3<f32>, Quaternion<f32>, Vector3<f32>)>,
+    ) {
+        let range = match self.models[object_index].as_ref() {
+            Some(model) => model.get_instances(),
+            None => {
+                if let Some(pending) = self.get_pending_object(object_index) {
+                    pending
+                        .instances
+                        .resize(transforms.len(), Default::default());
+                    for (instance, (position, rotation, scale)) in
+                        pending.instances.iter_mut().zip(transforms)
+                    {
+                        instance.position = position;
+                        instance.rotation = rotation;
+                        instance.scale = scale;
+                    }
+                }
+                return;
+            }
+        };
+
+        let instances = transforms
+            .into_iter()
+            .enumerate()
+            .map(|(index, (position, rotation, scale))| {
+                let instance_index = range.start + index as u32;
+
+                // Instances on the default instance or outside the range are created fresh
+                if range.start != 0 && instance_index < range.end {
+                    let mut instance = self.model_instances[instance_index as usize];
+                    instance.position = position;
+                    instance.rotation = rotation;
+                    instance.scale = scale;
+                    instance
+                } else {
+                    instance::Instance::new(position, rotation).with_scale(scale)
+                }
+            })
+            .collect();
+
+        self.update_instances(object_index, instances);
+    }
+
+    /// Sets the tint color of every instance of an object
+    ///
+    /// # Arguments
+    ///
+    /// * `object_index` - The index of the object in the renderer
+    /// * `color` - The RGBA color to multiply into the material color
+    pub fn set_instance_color<C>(&mut self, object_index: usize, color: C)
+    where
+        C: Into<LinearRgba>,
+    {
+        let color = color.into();
+        self.modify_instances(object_index, |instance| {
+            instance.set_color(color);
+        });
+    }
+
+    /// Sets the custom shader data of every instance of an object
+    ///
+    /// # Arguments
+    ///
+    /// * `object_index` - The index of the object in the renderer
+    /// * `custom_data` - The data passed to the shaders
+    pub fn set_instance_custom_data(&mut self, object_index: usize, custom_data: [f32; 4]) {
+        self.modify_instances(object_index, |instance| {
+            instance.set_custom_data(custom_data);
+        });
+    }
+
+    /// Sets the layer of the texture array that every instance of an object is drawn with
+    ///
+    /// # Arguments
+    ///
+    /// * `object_index` - The index of the object in the renderer
+    /// * `layer` - The layer of the diffuse texture array of a layered material
+    pub fn set_instance_layer(&mut self, object_index: usize, layer: u32) {
+        self.modify_instances(object_index, |instance| {
+            instance.set_layer(layer);
+        });
+    }
+
+    /// Scales the emissive color of every instance of an object
+    ///
+    /// # Arguments
+    ///
+    /// * `object_index` - The index of the object in the renderer
+    /// * `emission` - Multiplies the emissive color of the material, 0 turns the glow off
+    pub fn set_instance_emission(&mut self, object_index: usize, emission: f32) {
+        self.modify_instances(object_index, |instance| {
+            instance.set_emission(emission);
+        });
+    }
+
+    /// Shows or hides every instance of an object, hidden instances keep their place in
+    /// the instance buffer so showing them again is cheap
+    ///
+    /// # Arguments
+    ///
+    /// * `object_index` - The index of the object in the renderer
+    /// * `visible` - Whether the object is drawn
+    pub fn set_object_visible(&mut self, object_index: usize, visible: bool) {
+        self.modify_instances(object_index, |instance| {
+            instance.set_visible(visible);
+        });
+    }
+
+    /// Checks if an object is drawn or was hidden with `set_object_visible`
+    pub fn is_object_visible(&self, object_index: usize) -> bool {
+        match self.models.get(object_index) {
+            Some(Some(model)) => {
+                let range = model.get_instances();
+                // Objects on the shared default instance can not be hidden
+                range.start == 0
+                    || self
+                        .model_instances
+                        .get(range.start as usize)
+                        .is_none_or(|instance| instance.visible)
+            }
+            _ => self
+                .pending_objects
+                .iter()
+                .find(|pending| pending.index == object_index)
+                .and_then(|pending| pending.instances.first())
+                .is_none_or(|instance| instance.visible),
+        }
+    }
+
+    // Applies a modification to every instance of an object and writes them to the instance buffer
+    fn modify_instances<F>(&mut self, object_index: usize, modify: F)
+    where
+        F: Fn(&mut instance::Instance),
+    {
+        let range = match self.models[object_index].as_ref() {
+            Some(model) => model.get_instances(),
+            None => {
+                if let Some(pending) = self.get_pending_object(object_index) {
+                    pending.instances.iter_mut().for_each(modify);
+                }
+                return;
+            }
+        };
+
+        // Never modify the shared default instance at the world origin
+        if range.start == 0 {
+            return;
+        }
+
+        for instance_index in range.clone() {
+            self.update_instance(instance_index as usize, {
+                let mut instance = self.model_instances[instance_index as usize];
+                modify(&mut instance);
+                instance
+            });
+        }
     }
 
     /// Creates an object and adds it to the scene
@@ -272,147 +716,1607 @@ impl HeliumState {
         P: AsRef<Path>,
     {
         let index = self.models.len();
-        self.models
-            .push(Model::from_obj(model_path, &self.device, &self.queue).unwrap());
+        self.models.push(Some(
+            Model::from_file(
+                &model_path,
+                self.vertex_occlusion.as_ref(),
+                &self.device,
+                &self.queue,
+            )
+            .unwrap(),
+        ));
+        self.records.set_object(
+            index,
+            Some(ObjectRecord::new(
+                ObjectSource::File(model_path.as_ref().to_path_buf()),
+                self.vertex_occlusion,
+            )),
+        );
+        self.audit_object_colors(index);
 
         self.update_instances(index, instances);
 
         index
     }
 
-    /// Function to add a camera to the scene to be rendererd
-    #[allow(clippy::too_many_arguments)]
-    pub fn add_camera(
+    /// Creates an object of lines or points and adds it to the scene, it is drawn unlit in
+    /// white until it is given a color material
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The points in object space in the order they are joined
+    /// * `topology` - How the points are joined into lines
+    /// * `instances` - A vector of instaces with transformation data
+    ///
+    /// # Returns
+    ///
+    /// A `usize` index to the objects index in the renderers object directory
+    pub fn create_lines(
         &mut self,
-        eye: Point3<f32>,
-        target: Vector3<f32>,
-        up: Vector3<f32>,
-        aspect: f32,
-        fovy: f32,
-        znear: f32,
-        zfar: f32,
-    ) {
-        self.camera = Camera::create(&self.device, eye, target, up, aspect, fovy, znear, zfar);
-        self.queue.write_buffer(
-            self.camera.get_buffer(),
-            0,
-            bytemuck::cast_slice(&[*self.camera.get_uniform()]),
+        points: &[[f32; 3]],
+        topology: Topology,
+        instances: Vec<instance::Instance>,
+    ) -> usize {
+        let index = self.models.len();
+        self.models.push(Some(Model::from_lines(
+            points,
+            topology,
+            &self.device,
+            &self.queue,
+        )));
+        self.records.set_object(
+            index,
+            Some(ObjectRecord::new(
+                ObjectSource::Lines(points.to_vec(), topology),
+                None,
+            )),
         );
-        self.camera_active = true;
-    }
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn update_camera(
-        &mut self,
-        eye: Point3<f32>,
-        target: Vector3<f32>,
-        up: Vector3<f32>,
-        aspect: f32,
-        fovy: f32,
-        znear: f32,
-        zfar: f32,
-    ) {
-        self.camera.eye = eye;
-        self.camera.target = target;
-        self.camera.up = up;
-        self.camera.aspect = aspect;
-        self.camera.fovy = fovy;
-        self.camera.znear = znear;
-        self.camera.zfar = zfar;
+        self.update_instances(index, instances);
 
-        self.camera.update_view_proj();
+        index
+    }
 
-        self.queue.write_buffer(
-            self.camera.get_buffer(),
-            0,
-            bytemuck::cast_slice(&[*self.camera.get_uniform()]),
+    /// Merges many non-moving objects into a single object that is drawn with one draw call
+    /// for each material, the objects can not be moved after they are batched
+    ///
+    /// # Arguments
+    ///
+    /// * `objects` - The objects to merge with the transforms of each copy of the object
+    ///
+    /// # Returns
+    ///
+    /// A `usize` index to the objects index in the renderers object directory
+    pub fn create_static_batch(&mut self, objects: &[StaticBatchObject]) -> usize {
+        let index = self.models.len();
+        // The vertices are already in world space so the batch uses the default instance
+        self.models.push(Some(
+            Model::static_batch(
+                objects,
+                self.vertex_occlusion.as_ref(),
+                &self.device,
+                &self.queue,
+            )
+            .unwrap(),
+        ));
+        self.records.set_object(
+            index,
+            Some(ObjectRecord::new(
+                ObjectSource::StaticBatch(objects.to_vec()),
+                self.vertex_occlusion,
+            )),
         );
-    }
 
-    /// Function to remove the camera from the scene to stop rendering
-    pub fn remove_camera(&mut self) {
-        self.camera_active = false;
+        index
     }
 
-    pub fn add_light(&mut self, light: &mut Light) {
-        self.lights.add_light(light, &self.device);
-    }
+    /// Creates an object that is loaded on another thread and drawn once it has loaded
+    ///
+    /// # Arguments
+    ///
+    /// * `model_path` - Filepath to the model
+    /// * `instances` - A vector of instaces with transformation data
+    ///
+    /// # Returns
+    ///
+    /// A `usize` index to the objects index in the renderers object directory
+    pub fn create_object_async<P>(
+        &mut self,
+        model_path: P,
+        instances: Vec<instance::Instance>,
+    ) -> usize
+    where
+        P: AsRef<Path>,
+    {
+        let index = self.models.len();
+        self.models.push(None);
+
+        let (sender, receiver) = mpsc::channel();
+        let model_path = model_path.as_ref().to_path_buf();
+        self.records.set_object(
+            index,
+            Some(ObjectRecord::new(
+                ObjectSource::File(model_path.clone()),
+                self.vertex_occlusion,
+            )),
+        );
+        let device = self.device.clone();
+        let queue = self.queue.clone();
+        let occlusion = self.vertex_occlusion;
+
+        thread::spawn(move || {
+            _ = sender.send(Model::from_file(
+                model_path,
+                occlusion.as_ref(),
+                &device,
+                &queue,
+            ));
+        });
 
-    pub fn update_light(&mut self, light: &Light) {
-        self.lights.update_light(light, &self.queue);
-    }
+        self.pending_objects.push(PendingObject {
+            index,
+            receiver,
+            instances,
+            color_material: None,
+            texture_animation: None,
+        });
 
-    pub fn update_light_buffer(&mut self) {
-        self.lights.adjust_buffer(&self.device);
+        index
     }
 
-    pub fn new(window: Arc<Window>) -> Self {
-        let instance = Self::create_gpu_instance();
-        let surface = instance.create_surface(window.clone()).unwrap();
-        let adapter = Self::create_adapter(instance, &surface);
-        let (device, queue) = Self::create_device(&adapter);
-        let surface_capabilities = surface.get_capabilities(&adapter);
-        let size = window.inner_size();
-        let config = Self::create_surface_config(size, surface_capabilities);
-        surface.configure(&device, &config);
+    /// Adds the objects that have finished loading to the scene
+    pub fn poll_pending_objects(&mut self) {
+        let mut index = 0;
+        while index < self.pending_objects.len() {
+            let result = match self.pending_objects[index].receiver.try_recv() {
+                Ok(result) => result,
+                Err(mpsc::TryRecvError::Empty) => {
+                    index += 1;
+                    continue;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    Err(io::Error::other("Object loading thread stopped"))
+                }
+            };
 
-        let camera = Camera::create(
-            &device,
-            (5.0, 5.0, 5.0).into(),
-            (0.0, 0.0, 0.0).into(),
-            cgmath::Vector3::unit_y(),
-            config.width as f32 / config.height as f32,
-            45.0,
-            0.1,
-            100.0,
-        );
+            let pending = self.pending_objects.remove(index);
+            match result {
+                Ok(model) => {
+                    self.models[pending.index] = Some(model);
+                    self.audit_object_colors(pending.index);
 
-        let lights = Lights::default();
+                    if let Some(color_material) = pending.color_material {
+                        self.set_object_color_material(pending.index, color_material);
+                    }
 
-        let depth_texture = HeliumTexture::create_depth_texture(&device, &config);
+                    if let Some(texture_animation) = pending.texture_animation {
+                        self.set_object_texture_animation(pending.index, texture_animation);
+                    }
 
-        // The default instance for all models will be at the world origin
-        // change the location by creating instances and adding them to this vector
-        let model_instances = vec![instance::Instance::default()];
+                    self.update_instances(pending.index, pending.instances);
+                }
+                Err(e) => {
+                    error!("Failed to load object {}: {}", pending.index, e);
+                    self.records.set_object(pending.index, None);
+                }
+            }
+        }
+    }
 
-        let model_instance_buffer = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("Model instance buffer"),
-            contents: bytemuck::cast_slice(&[model_instances[0].to_raw()]),
-            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
-        });
+    /// Turns on warnings for colors that are in the wrong color space, the loaded objects
+    /// are checked when it is turned on and new objects are checked as they load
+    pub fn set_color_audit(&mut self, enabled: bool) {
+        self.color_audit = enabled;
+
+        if enabled {
+            if needs_shader_encode(surface_view_format(self.config.format)) {
+                warn!(
+                    "The surface format {:?} has no srgb view, the tonemap gamma encodes instead",
+                    self.config.format
+                );
+            }
 
-        let render_pipeline = construct_render_pipline_from_layouts(
-            vec![
-                &HeliumTexture::get_layout(&device),
-                &Camera::get_camera_layout(&device),
-                &Lights::get_bind_group_layout(&device),
-            ],
-            &device,
-            &config,
-            String::from("Model"),
-        );
+            for object_index in 0..self.models.len() {
+                self.audit_object_colors(object_index);
+            }
+        }
+    }
 
-        let obj_models = Vec::new();
+    // Logs the textures of an object that are in the wrong color space
+    fn audit_object_colors(&self, object_index: usize) {
+        if !self.color_audit {
+            return;
+        }
+
+        if let Some(Some(model)) = self.models.get(object_index) {
+            for warning in model
+                .get_materials()
+                .iter()
+                .flat_map(|material| material.audit_color_spaces())
+            {
+                warn!("Object {object_index}: {warning}");
+            }
+        }
+    }
+
+    /// Gets the optional features and the limits the gpu was set up with
+    pub fn get_capabilities(&self) -> &RendererCapabilities {
+        &self.capabilities
+    }
+
+    /// Gets the number of draw calls and state changes used to draw the last frame
+    pub fn get_render_stats(&self) -> RenderStats {
+        self.render_stats
+    }
+
+    /// Skips drawing meshes whose instances are all outside of the view of the camera, it is
+    /// on by default
+    pub fn set_frustum_culling(&mut self, enabled: bool) {
+        self.frustum_culling = enabled;
+    }
+
+    /// Draws the meshes from a buffer of draw arguments, the visible instances of a mesh
+    /// are drawn with a single multi draw when the device supports it. It is off by
+    /// default and useful for heavily instanced scenes
+    ///
+    /// # Returns
+    ///
+    /// Whether indirect drawing is on, devices that can not start indirect draws at any
+    /// instance keep drawing every mesh directly
+    pub fn set_indirect_drawing(&mut self, enabled: bool) -> bool {
+        self.indirect_draws = (enabled && IndirectDraws::is_supported(&self.capabilities))
+            .then(|| IndirectDraws::new(&self.device, &self.capabilities));
+        self.indirect_draws.is_some()
+    }
+
+    /// Gets the box around an object in object space
+    ///
+    /// # Returns
+    ///
+    /// The box or `None` if the object has not finished loading
+    pub fn get_object_aabb(&self, object_index: usize) -> Option<Aabb> {
+        self.models.get(object_index)?.as_ref().map(Model::aabb)
+    }
+
+    /// Gets the sphere around an object in object space
+    ///
+    /// # Returns
+    ///
+    /// The sphere or `None` if the object has not finished loading
+    pub fn get_object_bounding_sphere(&self, object_index: usize) -> Option<BoundingSphere> {
+        self.models
+            .get(object_index)?
+            .as_ref()
+            .map(Model::bounding_sphere)
+    }
+
+    /// Gets the number of objects that were created, including removed ones
+    pub fn get_num_objects(&self) -> usize {
+        self.models.len()
+    }
+
+    /// Gets the number of objects that are still loading on another thread
+    pub fn get_num_pending_objects(&self) -> usize {
+        self.pending_objects.len()
+    }
+
+    /// Checks if an object has finished loading and is being drawn
+    pub fn is_object_loaded(&self, object_index: usize) -> bool {
+        matches!(self.models.get(object_index), Some(Some(_)))
+    }
+
+    /// Removes an object from the scene, the index of the object will not be reused
+    pub fn remove_object(&mut self, object_index: usize) {
+        self.models[object_index] = None;
+        self.records.set_object(object_index, None);
+        self.pending_objects
+            .retain(|pending| pending.index != object_index);
+        self.scatters
+            .retain(|scatter| scatter.get_object_index() != object_index);
+        self.outline_renderer.set_outline(object_index, None);
+        self.reflection_renderer.set_reflection(
+            &self.device,
+            &self.queue,
+            &self.layouts,
+            &mut self.bind_groups,
+            object_index,
+            None,
+        );
+    }
+
+    /// Finds what is drawn at a pixel on the screen, the result is read back from the gpu
+    /// after the next frame is drawn
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The horizontal position of the pixel from the left of the screen
+    /// * `y` - The vertical position of the pixel from the top of the screen
+    ///
+    /// # Returns
+    ///
+    /// A `PickRequest` that receives the index of the picked instance
+    pub fn pick(&mut self, x: u32, y: u32) -> PickRequest {
+        self.picking_renderer.pick((x, y))
+    }
+
+    /// Finds the object that an instance in the instance buffer belongs to
+    ///
+    /// # Arguments
+    ///
+    /// * `instance_index` - The index of the instance in the instance buffer
+    ///
+    /// # Returns
+    ///
+    /// The index of the object in the renderer, if any
+    pub fn get_object_from_instance(&self, instance_index: u32) -> Option<usize> {
+        self.models.iter().position(|model| {
+            model
+                .as_ref()
+                .is_some_and(|model| model.get_instances().contains(&instance_index))
+        })
+    }
+
+    /// Loads morph targets for an object from obj files of the same model in other shapes,
+    /// the vertices of the object are blended towards them by the morph weights of its
+    /// instances. Only objects loaded from an obj file can have morph targets
+    ///
+    /// # Arguments
+    ///
+    /// * `object_index` - The index of the object in the renderer
+    /// * `targets` - The name and obj file of every target, in the order of their weights
+    ///
+    /// # Returns
+    ///
+    /// An error if the object is not loaded or a file does not match the object
+    pub fn load_morph_targets<P>(
+        &mut self,
+        object_index: usize,
+        targets: &[(&str, P)],
+    ) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let Some(ObjectSource::File(base_path)) = self
+            .records
+            .get_object_mut(object_index)
+            .map(|record| record.source.clone())
+        else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Only objects loaded from an obj file can have morph targets",
+            ));
+        };
+
+        let Some(model) = self.models.get_mut(object_index).and_then(Option::as_mut) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "The object has not finished loading",
+            ));
+        };
+
+        let targets = targets
+            .iter()
+            .map(|(name, path)| (name.to_string(), path.as_ref().to_path_buf()))
+            .collect::<Vec<_>>();
+        model.load_morph_targets(&base_path, &targets, &self.device)?;
+
+        if let Some(record) = self.records.get_object_mut(object_index) {
+            record.morph_targets = targets;
+        }
+
+        Ok(())
+    }
+
+    /// The names of the morph targets of an object in the order of their weights
+    pub fn get_morph_target_names(&self, object_index: usize) -> Vec<String> {
+        self.models
+            .get(object_index)
+            .and_then(Option::as_ref)
+            .map(Model::get_morph_target_names)
+            .unwrap_or_default()
+    }
+
+    /// Sets how far every instance of an object is blended towards each of its morph
+    /// targets
+    ///
+    /// # Arguments
+    ///
+    /// * `object_index` - The index of the object in the renderer
+    /// * `weights` - The weight of every target in the order they were loaded, usually
+    ///   from 0 for the base shape to 1 for the full target
+    pub fn set_morph_weights(&mut self, object_index: usize, weights: &[f32]) {
+        self.modify_instances(object_index, |instance| {
+            instance.set_morph_weights(weights);
+        });
+    }
+
+    /// Draws an outline around every instance of an object
+    ///
+    /// # Arguments
+    ///
+    /// * `object_index` - The index of the object in the renderer
+    /// * `outline` - The outline to draw, `None` removes the outline of the object
+    pub fn set_object_outline(&mut self, object_index: usize, outline: Option<Outline>) {
+        self.outline_renderer.set_outline(object_index, outline);
+
+        if let Some(record) = self.records.get_object_mut(object_index) {
+            record.outline = outline;
+        }
+    }
+
+    /// Makes an object a flat reflective surface like water or a mirror, the scene is
+    /// mirrored about the plane of the surface and drawn onto it
+    ///
+    /// # Arguments
+    ///
+    /// * `object_index` - The index of the object in the renderer
+    /// * `reflection` - The plane and reflectivity of the surface, `None` removes the reflection
+    pub fn set_object_reflection(&mut self, object_index: usize, reflection: Option<Reflection>) {
+        self.reflection_renderer.set_reflection(
+            &self.device,
+            &self.queue,
+            &self.layouts,
+            &mut self.bind_groups,
+            object_index,
+            reflection,
+        );
+
+        if let Some(record) = self.records.get_object_mut(object_index) {
+            record.reflection = reflection;
+        }
+    }
+
+    /// Creates an object with many randomly placed instances, like grass or rocks,
+    /// the instances are culled outside of the view of the camera and thinned out
+    /// with distance on the gpu
+    ///
+    /// # Arguments
+    ///
+    /// * `model_path` - Filepath to the model
+    /// * `region` - The region to place the instances in
+    /// * `density` - The number of instances per square unit of the region
+    /// * `seed` - The seed of the random placement
+    /// * `settings` - How the instances are culled
+    ///
+    /// # Returns
+    ///
+    /// A `usize` index to the objects index in the renderers object directory
+    pub fn create_scatter<P>(
+        &mut self,
+        model_path: P,
+        region: ScatterRegion,
+        density: f32,
+        seed: u64,
+        settings: ScatterSettings,
+    ) -> usize
+    where
+        P: AsRef<Path>,
+    {
+        let instances = scatter::generate_scatter_instances(region, density, seed);
+        let index = self.create_object(model_path, instances);
+
+        self.scatters
+            .push(Scatter::new(&self.device, index, settings));
+
+        index
+    }
+
+    /// Changes how the instances of a scattered object are culled
+    pub fn update_scatter_settings(&mut self, object_index: usize, settings: ScatterSettings) {
+        if let Some(scatter) = self
+            .scatters
+            .iter_mut()
+            .find(|scatter| scatter.get_object_index() == object_index)
+        {
+            scatter.set_settings(settings, &self.queue);
+        }
+    }
+
+    fn get_pending_object(&mut self, object_index: usize) -> Option<&mut PendingObject> {
+        self.pending_objects
+            .iter_mut()
+            .find(|pending| pending.index == object_index)
+    }
+
+    /// Replaces the materials of an object with a flat color material
+    ///
+    /// # Arguments
+    ///
+    /// * `object_index` - The index of the object in the renderer
+    /// * `color_material` - The color material to draw the object with
+    pub fn set_object_color_material(
+        &mut self,
+        object_index: usize,
+        color_material: ColorMaterial,
+    ) {
+        if let Some(record) = self.records.get_object_mut(object_index) {
+            record.color_material = Some(color_material);
+        }
+
+        match self.models[object_index].as_mut() {
+            Some(model) => model.set_color_material(color_material, &self.device, &self.queue),
+            None => {
+                if let Some(pending) = self.get_pending_object(object_index) {
+                    pending.color_material = Some(color_material);
+                }
+            }
+        }
+    }
+
+    /// Moves the texture coordinates of every material of an object over time, like a
+    /// scrolling conveyor belt or a flipbook of a sprite sheet
+    ///
+    /// # Arguments
+    ///
+    /// * `object_index` - The index of the object in the renderer
+    /// * `texture_animation` - How the texture coordinates move
+    pub fn set_object_texture_animation(
+        &mut self,
+        object_index: usize,
+        texture_animation: TextureAnimation,
+    ) {
+        if let Some(record) = self.records.get_object_mut(object_index) {
+            record.texture_animation = Some(texture_animation);
+        }
+
+        match self.models[object_index].as_mut() {
+            Some(model) => model.set_texture_animation(texture_animation, &self.queue),
+            None => {
+                if let Some(pending) = self.get_pending_object(object_index) {
+                    pending.texture_animation = Some(texture_animation);
+                }
+            }
+        }
+    }
+
+    /// Loads a font from a file so it can be used to draw text
+    ///
+    /// # Arguments
+    ///
+    /// * `font_path` - Filepath to the ttf or otf font
+    ///
+    /// # Returns
+    ///
+    /// A `FontHandle` to use in a `TextStyle`
+    pub fn load_font<P>(&mut self, font_path: P) -> Result<FontHandle, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let _span = profile_span("load_font");
+        info!("Loading Font: {:?}", font_path.as_ref());
+        self.add_font_bytes(fs::read(font_path)?)
+    }
+
+    /// Registers a font from the bytes of a ttf or otf file
+    ///
+    /// # Arguments
+    ///
+    /// * `font_bytes` - The contents of the font file
+    ///
+    /// # Returns
+    ///
+    /// A `FontHandle` to use in a `TextStyle`
+    pub fn add_font_bytes(&mut self, font_bytes: Vec<u8>) -> Result<FontHandle, io::Error> {
+        let font = FontArc::try_from_vec(font_bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let handle = FontHandle(self.fonts.len());
+        self.fonts.push(font);
+
+        // The brushes own their fonts so they have to be rebuilt to use the new one
+        (self.brush, self.world_brush) =
+            Self::create_brushes(&self.fonts, &self.device, &self.config);
+
+        Ok(handle)
+    }
+
+    /// Draws the characters a font does not have with other fonts, like the kanji of a
+    /// japanese translation with a font that only has latin letters
+    ///
+    /// # Arguments
+    ///
+    /// * `font` - The font of the text
+    /// * `fallbacks` - The fonts that are tried in order for the missing characters, empty
+    ///   removes the fallbacks
+    pub fn set_font_fallbacks(&mut self, font: FontHandle, fallbacks: &[FontHandle]) {
+        if fallbacks.is_empty() {
+            self.font_fallbacks.remove(&font);
+        } else {
+            self.font_fallbacks.insert(font, fallbacks.to_vec());
+        }
+    }
+
+    // Creates the brush for screen text and the depth tested brush for world text
+    fn create_brushes(
+        fonts: &[FontArc],
+        device: &Device,
+        config: &SurfaceConfiguration,
+    ) -> (TextBrush<FontArc>, TextBrush<FontArc>) {
+        let view_format = surface_view_format(config.format);
+        let brush = BrushBuilder::using_fonts(fonts.to_vec()).build(
+            device,
+            config.width,
+            config.height,
+            view_format,
+        );
+
+        let world_brush = BrushBuilder::using_fonts(fonts.to_vec())
+            .with_depth_stencil(Some(Self::world_overlay_depth_stencil()))
+            .build(device, config.width, config.height, view_format);
+
+        (brush, world_brush)
+    }
+
+    // Depth test that hides world overlays behind the scene without writing any depth
+    fn world_overlay_depth_stencil() -> DepthStencilState {
+        DepthStencilState {
+            format: helium_texture::DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: CompareFunction::Less,
+            stencil: StencilState::default(),
+            bias: DepthBiasState::default(),
+        }
+    }
+
+    /// Projects a point in the world onto the screen with the active camera
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The point in world space
+    ///
+    /// # Returns
+    ///
+    /// The `ScreenPoint` of the position or `None` if it is not visible to the camera
+    pub fn world_to_screen(&self, position: Vector3<f32>) -> Option<ScreenPoint> {
+        if !self.camera_active {
+            return None;
+        }
+
+        self.camera.world_to_screen(
+            position,
+            (self.config.width as f32, self.config.height as f32),
+        )
+    }
+
+    /// Adds text to be drawn in the overlay
+    ///
+    /// # Returns
+    ///
+    /// A `usize` index to the text in the renderer
+    pub fn create_text(&mut self, text: OverlayText) -> usize {
+        let index = self.texts.len();
+        self.texts.push(Some(text));
+        index
+    }
+
+    pub fn update_text(&mut self, text_index: usize, text: OverlayText) {
+        self.texts[text_index] = Some(text);
+    }
+
+    pub fn remove_text(&mut self, text_index: usize) {
+        self.texts[text_index] = None;
+    }
+
+    /// Adds text that is drawn from distance fields of its glyphs, it stays sharp however
+    /// large it is and is hidden behind the scene when it is placed in the world
+    ///
+    /// # Returns
+    ///
+    /// A `usize` index to the text in the renderer
+    pub fn create_sdf_text(&mut self, text: SdfText) -> usize {
+        let index = self.sdf_texts.len();
+        self.sdf_texts.push(Some(text));
+        index
+    }
+
+    pub fn update_sdf_text(&mut self, text_index: usize, text: SdfText) {
+        self.sdf_texts[text_index] = Some(text);
+    }
+
+    pub fn remove_sdf_text(&mut self, text_index: usize) {
+        self.sdf_texts[text_index] = None;
+    }
+
+    /// Adds a colored quad to be drawn in the overlay below the text
+    ///
+    /// # Returns
+    ///
+    /// A `usize` index to the quad in the renderer
+    pub fn create_quad(&mut self, quad: OverlayQuad) -> usize {
+        let index = self.quads.len();
+        self.quads.push(Some(quad));
+        index
+    }
+
+    pub fn update_quad(&mut self, quad_index: usize, quad: OverlayQuad) {
+        self.quads[quad_index] = Some(quad);
+    }
+
+    pub fn remove_quad(&mut self, quad_index: usize) {
+        self.quads[quad_index] = None;
+    }
+
+    /// Loads an image from a file so it can be drawn as a sprite
+    ///
+    /// # Arguments
+    ///
+    /// * `texture_path` - Filepath to the image
+    ///
+    /// # Returns
+    ///
+    /// A `SpriteHandle` to use in an `OverlaySprite`
+    pub fn load_sprite_texture<P>(&mut self, texture_path: P) -> Result<SpriteHandle, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let _span = profile_span("load_sprite_texture");
+        info!("Loading Sprite: {:?}", texture_path.as_ref());
+        let texture =
+            HeliumTexture::from_bytes(&self.device, &self.queue, &fs::read(texture_path)?)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let handle = SpriteHandle(self.sprite_regions.len());
+        self.sprite_regions.push(SpriteRegion::full(
+            self.sprite_textures.len(),
+            texture.get_dimensions(),
+        ));
+        self.sprite_textures.push(texture);
+
+        Ok(handle)
+    }
+
+    /// Packs images from files into one texture so the sprites drawn with them share a draw call
+    ///
+    /// # Arguments
+    ///
+    /// * `texture_paths` - Filepaths to the images
+    ///
+    /// # Returns
+    ///
+    /// A `SpriteHandle` for each image in the same order as the paths
+    pub fn load_sprite_atlas<P>(
+        &mut self,
+        texture_paths: &[P],
+    ) -> Result<Vec<SpriteHandle>, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut builder =
+            TextureAtlasBuilder::new().with_max_size(self.device.limits().max_texture_dimension_2d);
+
+        for texture_path in texture_paths {
+            info!("Loading Sprite: {:?}", texture_path.as_ref());
+            builder
+                .add_bytes(&fs::read(texture_path)?)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+
+        self.create_sprite_atlas(&builder)
+    }
+
+    /// Packs the images of an atlas builder into one texture for drawing sprites
+    ///
+    /// # Returns
+    ///
+    /// A `SpriteHandle` for each image in the order they were added to the builder
+    pub fn create_sprite_atlas(
+        &mut self,
+        builder: &TextureAtlasBuilder,
+    ) -> Result<Vec<SpriteHandle>, io::Error> {
+        let atlas = builder.build()?;
+        let (width, height) = atlas.rgba.dimensions();
+        info!(
+            "Packed {} sprites into a {}x{} atlas",
+            atlas.rects.len(),
+            width,
+            height
+        );
+
+        let texture_index = self.sprite_textures.len();
+        self.sprite_textures.push(HeliumTexture::from_rgba(
+            &self.device,
+            &self.queue,
+            &atlas.rgba,
+            (width, height),
+        ));
+
+        Ok(atlas
+            .rects
+            .iter()
+            .map(|rect| {
+                let handle = SpriteHandle(self.sprite_regions.len());
+                self.sprite_regions.push(SpriteRegion {
+                    texture: texture_index,
+                    uv_min: [rect.x as f32 / width as f32, rect.y as f32 / height as f32],
+                    uv_max: [
+                        (rect.x + rect.width) as f32 / width as f32,
+                        (rect.y + rect.height) as f32 / height as f32,
+                    ],
+                    dimensions: (rect.width, rect.height),
+                });
+                handle
+            })
+            .collect())
+    }
+
+    /// The width and height in pixels of a loaded sprite texture
+    pub fn get_sprite_texture_dimensions(&self, handle: SpriteHandle) -> Option<(u32, u32)> {
+        self.sprite_regions
+            .get(handle.0)
+            .map(|region| region.dimensions)
+    }
+
+    /// Adds a sprite to be drawn in the overlay between the quads and the text
+    ///
+    /// # Returns
+    ///
+    /// A `usize` index to the sprite in the renderer
+    pub fn create_sprite(&mut self, sprite: OverlaySprite) -> usize {
+        let index = self.sprites.len();
+        self.sprites.push(Some(sprite));
+        index
+    }
+
+    pub fn update_sprite(&mut self, sprite_index: usize, sprite: OverlaySprite) {
+        self.sprites[sprite_index] = Some(sprite);
+    }
+
+    pub fn remove_sprite(&mut self, sprite_index: usize) {
+        self.sprites[sprite_index] = None;
+    }
+
+    /// Lets rich text draw a sprite inline with `[icon=name]`, the icon is as tall as the
+    /// text and keeps the aspect ratio of the sprite
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name used in the markup
+    /// * `sprite` - The sprite to draw, `None` removes the icon
+    pub fn set_text_icon(&mut self, name: &str, sprite: Option<SpriteHandle>) {
+        match sprite {
+            Some(sprite) => {
+                self.text_icons.insert(name.to_string(), sprite);
+            }
+            None => {
+                self.text_icons.remove(name);
+            }
+        }
+    }
+
+    /// Loads an image from a file so it can be projected onto the scene as a decal
+    ///
+    /// # Arguments
+    ///
+    /// * `texture_path` - Filepath to the image
+    ///
+    /// # Returns
+    ///
+    /// A `DecalTexture` to use in a `DecalProjector`
+    pub fn load_decal_texture<P>(&mut self, texture_path: P) -> Result<DecalTexture, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let _span = profile_span("load_decal_texture");
+        info!("Loading Decal: {:?}", texture_path.as_ref());
+        let texture =
+            HeliumTexture::from_bytes(&self.device, &self.queue, &fs::read(texture_path)?)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(self.decal_renderer.add_texture(texture))
+    }
+
+    /// Adds a decal that is projected onto the scene
+    ///
+    /// # Returns
+    ///
+    /// A `usize` index to the decal in the renderer
+    pub fn create_decal(&mut self, decal: DecalProjector) -> usize {
+        self.decal_renderer.create_decal(&self.device, decal)
+    }
+
+    pub fn update_decal(&mut self, decal_index: usize, decal: DecalProjector) {
+        self.decal_renderer
+            .update_decal(&self.queue, decal_index, decal);
+    }
+
+    pub fn remove_decal(&mut self, decal_index: usize) {
+        self.decal_renderer.remove_decal(decal_index);
+    }
+
+    /// Function to add a camera to the scene to be rendererd
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_camera(
+        &mut self,
+        eye: Point3<f32>,
+        target: Vector3<f32>,
+        up: Vector3<f32>,
+        aspect: f32,
+        fovy: f32,
+        znear: f32,
+        zfar: f32,
+    ) {
+        self.camera = Camera::create(&self.device, eye, target, up, aspect, fovy, znear, zfar);
+        self.queue.write_buffer(
+            self.camera.get_buffer(),
+            0,
+            bytemuck::cast_slice(&[*self.camera.get_uniform()]),
+        );
+        self.camera_active = true;
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_camera(
+        &mut self,
+        eye: Point3<f32>,
+        target: Vector3<f32>,
+        up: Vector3<f32>,
+        aspect: f32,
+        fovy: f32,
+        znear: f32,
+        zfar: f32,
+    ) {
+        self.camera.eye = eye;
+        self.camera.target = target;
+        self.camera.up = up;
+        self.camera.aspect = aspect;
+        self.camera.fovy = fovy;
+        self.camera.znear = znear;
+        self.camera.zfar = zfar;
+
+        self.camera.update_view_proj();
+
+        self.queue.write_buffer(
+            self.camera.get_buffer(),
+            0,
+            bytemuck::cast_slice(&[*self.camera.get_uniform()]),
+        );
+    }
+
+    /// Function to remove the camera from the scene to stop rendering
+    pub fn remove_camera(&mut self) {
+        self.camera_active = false;
+    }
+
+    pub fn add_light(&mut self, light: &mut Light) {
+        self.lights.add_light(light, &self.device);
+    }
+
+    pub fn update_light(&mut self, light: &Light) {
+        self.lights.update_light(light, &self.queue);
+    }
+
+    /// Draws a line in the world until the debug draw is flushed again
+    pub fn debug_line(&mut self, line: DebugLine) {
+        self.debug_draw.add_line(line);
+    }
+
+    /// Draws three circles around the axes of a sphere until the debug draw is flushed again
+    pub fn debug_sphere(&mut self, center: Vector3<f32>, radius: f32, color: [f32; 4]) {
+        self.debug_draw.add_sphere(center, radius, color);
+    }
+
+    /// Draws a line with an arrow head at the end until the debug draw is flushed again
+    pub fn debug_arrow(&mut self, start: Vector3<f32>, end: Vector3<f32>, color: [f32; 4]) {
+        self.debug_draw.add_arrow(start, end, color);
+    }
+
+    /// Draws a label that faces the screen at a point in the world until the debug draw is
+    /// flushed again
+    pub fn debug_text(&mut self, text: DebugText) {
+        self.debug_draw.add_text(text);
+    }
+
+    /// Replaces the debug lines that are drawn with the ones added since the last flush,
+    /// call this once every update
+    pub fn flush_debug_draw(&mut self) {
+        self.debug_draw.flush();
+    }
+
+    /// Draws the triangles of a mesh built on the cpu until the dynamic meshes are flushed
+    /// again, used for effects that change shape every update like trails
+    pub fn add_dynamic_mesh(&mut self, mesh: &DynamicMesh) {
+        self.dynamic_meshes.add(mesh);
+    }
+
+    /// Replaces the dynamic meshes that are drawn with the ones added since the last
+    /// flush, call this once every update
+    pub fn flush_dynamic_meshes(&mut self) {
+        self.dynamic_meshes.flush();
+    }
+
+    /// Draws the range of every light in candela and the direction of directional lights
+    pub fn set_light_gizmos(&mut self, enabled: bool) {
+        self.debug_draw.set_light_gizmos(enabled);
+    }
+
+    pub fn get_light_gizmos(&self) -> bool {
+        self.debug_draw.get_light_gizmos()
+    }
+
+    pub fn update_light_buffer(&mut self) {
+        self.lights.adjust_buffer(&self.device);
+    }
+
+    /// Changes the resolution, bias, filtering, and number of the shadows of the lights
+    pub fn set_shadow_settings(&mut self, settings: ShadowSettings) {
+        self.lights.set_shadow_settings(settings, &self.device);
+    }
+
+    /// The shadow settings after they were clamped to the limits of the device
+    pub fn get_shadow_settings(&self) -> ShadowSettings {
+        self.lights.get_shadow_settings()
+    }
+
+    /// Sets how much lights in candela are scaled before they are added to the scene
+    ///
+    /// # Arguments
+    ///
+    /// * `exposure` - The multiplier of the lights, `exposure_from_ev100` gives the
+    ///   exposure of a physical camera
+    pub fn set_light_exposure(&mut self, exposure: f32) {
+        self.lights.set_exposure(exposure, &self.queue);
+    }
+
+    /// Lights the scene with an environment map, metallic materials reflect the environment
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Filepath to an HDR image in the equirectangular format
+    /// * `intensity` - How bright the lighting from the environment is
+    pub fn set_environment<P>(&mut self, path: P, intensity: f32) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let environment = Environment::from_hdr(&path, intensity, &self.device, &self.queue)?;
+        self.lights.set_environment(environment, &self.device);
+        self.records.environment = Some((path.as_ref().to_path_buf(), intensity));
+
+        Ok(())
+    }
+
+    /// Inserts a custom pass into the frame
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The point in the frame where the pass runs
+    /// * `pass` - The pass to run every frame
+    ///
+    /// # Returns
+    ///
+    /// A `RenderPassHandle` to remove the pass with or an error if the inputs and outputs
+    /// of the pass can not be used at the stage
+    pub fn add_render_pass<P>(
+        &mut self,
+        stage: RenderStage,
+        pass: P,
+    ) -> Result<RenderPassHandle, io::Error>
+    where
+        P: CustomRenderPass + 'static,
+    {
+        self.render_graph.add_pass(stage, Box::new(pass))
+    }
+
+    /// Removes a custom pass from the frame
+    pub fn remove_render_pass(&mut self, handle: RenderPassHandle) {
+        self.render_graph.remove_pass(handle);
+    }
+
+    /// The format of a resource so custom passes can create pipelines that use it
+    pub fn get_resource_format(&self, resource: RenderResource) -> TextureFormat {
+        match resource {
+            RenderResource::SceneColor => HDR_FORMAT,
+            RenderResource::SceneDepth => helium_texture::DEPTH_FORMAT,
+            RenderResource::Surface => surface_view_format(self.config.format),
+        }
+    }
+
+    /// Changes the tonemapper, exposure, and anti-aliasing that the scene is drawn to the
+    /// screen with
+    pub fn set_post_settings(&mut self, settings: PostSettings) {
+        self.post_stack.set_settings(&self.queue, settings);
+
+        // Removes the jitter from the camera when the anti-aliasing is turned off
+        self.queue.write_buffer(
+            self.camera.get_buffer(),
+            0,
+            bytemuck::cast_slice(&[*self.camera.get_uniform()]),
+        );
+    }
+
+    pub fn get_post_settings(&self) -> PostSettings {
+        self.post_stack.get_settings()
+    }
+
+    /// Draws lens flares over the sun and the bright lights on the screen, the flares fade
+    /// out while the lights are hidden behind the scene
+    ///
+    /// # Arguments
+    ///
+    /// * `lens_flare` - The chain of flare elements and which lights flare, `None` turns
+    ///   the flares off
+    pub fn set_lens_flare(&mut self, lens_flare: Option<LensFlare>) {
+        self.post_stack.set_lens_flare(lens_flare);
+    }
+
+    pub fn get_lens_flare(&self) -> Option<LensFlare> {
+        self.post_stack.get_lens_flare().cloned()
+    }
+
+    /// Adds a full screen effect like a damage vignette or an underwater distortion, it
+    /// fades in and is stacked over the overlays that are already on the screen
+    ///
+    /// # Arguments
+    ///
+    /// * `overlay` - The kind, color, and fading of the overlay
+    ///
+    /// # Returns
+    ///
+    /// The handle to change or remove the overlay with
+    pub fn add_screen_overlay(&mut self, overlay: ScreenOverlay) -> ScreenOverlayHandle {
+        self.post_stack.add_screen_overlay(overlay)
+    }
+
+    /// Changes an overlay that is on the screen without fading it in again
+    pub fn update_screen_overlay(&mut self, handle: ScreenOverlayHandle, overlay: ScreenOverlay) {
+        self.post_stack.update_screen_overlay(handle, overlay);
+    }
+
+    /// Fades an overlay out and removes it from the screen
+    pub fn remove_screen_overlay(&mut self, handle: ScreenOverlayHandle) {
+        self.post_stack.remove_screen_overlay(handle);
+    }
+
+    /// Whether an overlay is still on the screen, overlays with a duration remove
+    /// themselves
+    pub fn has_screen_overlay(&self, handle: ScreenOverlayHandle) -> bool {
+        self.post_stack.has_screen_overlay(handle)
+    }
+
+    /// Removes every overlay from the screen at once
+    pub fn clear_screen_overlays(&mut self) {
+        self.post_stack.clear_screen_overlays();
+    }
+
+    /// Grades the colors of the screen with a 3d lookup table
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Filepath to a `.cube` lookup table, `None` removes the color grading
+    pub fn set_color_grading<P>(&mut self, path: Option<P>) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let lut = path.as_ref().map(ColorGradingLut::from_cube).transpose()?;
+        self.post_stack
+            .set_color_grading(&self.device, &self.queue, lut.as_ref());
+        self.records.color_grading = path.map(|path| path.as_ref().to_path_buf());
+
+        Ok(())
+    }
 
-        let brush = BrushBuilder::using_font_bytes(include_bytes!("../../assets/font.ttf"))
-            .unwrap()
-            .build(&device, config.width, config.height, config.format);
+    /// Changes how bright the lighting from the environment map is
+    pub fn set_environment_intensity(&mut self, intensity: f32) {
+        if let Some(environment) = self.lights.get_environment_mut() {
+            environment.set_intensity(intensity, &self.queue);
+        }
+
+        if let Some((_, recorded)) = self.records.environment.as_mut() {
+            *recorded = intensity;
+        }
+    }
+
+    /// Draws a procedural sky behind the scene instead of a black background
+    ///
+    /// # Arguments
+    ///
+    /// * `sky` - The sky with the position of the sun, `None` to go back to black
+    pub fn set_sky(&mut self, sky: Option<ProceduralSky>) {
+        self.sky_renderer.set_sky(sky);
+    }
+
+    pub fn get_sky(&self) -> Option<ProceduralSky> {
+        self.sky_renderer.get_sky()
+    }
+
+    pub fn new(window: Arc<Window>) -> Self {
+        let instance = Self::create_gpu_instance();
+        let surface = instance.create_surface(window.clone()).unwrap();
+        let adapter = Self::create_adapter(instance, &surface);
+        let (device, queue, capabilities) = Self::create_device(&adapter);
+        let surface_capabilities = surface.get_capabilities(&adapter);
+        // The window can be minimized when the renderer is created
+        let size = window.inner_size();
+        let size = PhysicalSize::new(size.width.max(1), size.height.max(1));
+        let config = Self::create_surface_config(size, surface_capabilities);
+        surface.configure(&device, &config);
+
+        let mut state = Self::from_device(Some(surface), device, queue, config, capabilities);
+        state.scale_factor = window.scale_factor();
+        state.window = Some(window);
+        state
+    }
+
+    /// Creates a renderer without a window that draws into an offscreen texture, used
+    /// to run the engine in tests and tools
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width of the offscreen texture in pixels
+    /// * `height` - Height of the offscreen texture in pixels
+    ///
+    /// # Returns
+    ///
+    /// The renderer or an error if there is no gpu, not even a software one
+    pub fn new_headless(width: u32, height: u32) -> Result<Self, io::Error> {
+        let instance = Self::create_gpu_instance();
+        let adapter = [false, true]
+            .into_iter()
+            .find_map(|force_fallback_adapter| {
+                block_on(instance.request_adapter(&RequestAdapterOptionsBase {
+                    power_preference: PowerPreference::default(),
+                    compatible_surface: None,
+                    force_fallback_adapter,
+                }))
+            })
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No gpu adapter was found"))?;
+        let (device, queue, capabilities) = Self::create_device(&adapter);
+
+        let config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format: TextureFormat::Rgba8UnormSrgb,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: PresentMode::AutoNoVsync,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+
+        Ok(Self::from_device(None, device, queue, config, capabilities))
+    }
+
+    // The offscreen texture headless renderers draw the frame into
+    fn create_headless_target(device: &Device, config: &SurfaceConfiguration) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Headless Target"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
+    }
+
+    /// Removes everything from the scene, like the objects, lights, camera, text, and
+    /// custom passes, and puts the settings back to their defaults. The device, the
+    /// surface, and the fonts are kept so font handles stay valid
+    pub fn clear_scene(&mut self) {
+        let mut cleared = Self::from_device(
+            self.surface.take(),
+            self.device.clone(),
+            self.queue.clone(),
+            self.config.clone(),
+            self.capabilities.clone(),
+        );
+        cleared.window = self.window.take();
+        cleared.scale_factor = self.scale_factor;
+        cleared.fonts = mem::take(&mut self.fonts);
+        cleared.font_fallbacks = mem::take(&mut self.font_fallbacks);
+        (cleared.brush, cleared.world_brush) =
+            Self::create_brushes(&cleared.fonts, &cleared.device, &cleared.config);
+
+        *self = cleared;
+    }
+
+    /// Checks if the driver lost the gpu device, nothing can be drawn until the renderer
+    /// is recovered with `recover_device`
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::Acquire)
+    }
+
+    /// Creates the device, surface, and pipelines again after the device was lost and loads
+    /// the objects, environment map, and color grading again from their files. Objects keep
+    /// their indices, and the camera, lights, instances, fonts, text, and quads are kept.
+    /// Sprites, decals, and custom render passes have to be added again
+    ///
+    /// # Returns
+    ///
+    /// An error if no gpu could be found for the new device
+    pub fn recover_device(&mut self) -> Result<(), io::Error> {
+        warn!("Recovering the renderer from a lost gpu device");
+
+        let mut recovered = match self.window.clone() {
+            Some(window) => Self::new(window),
+            None => Self::new_headless(self.config.width, self.config.height)?,
+        };
+
+        if self.camera_active {
+            let camera = &self.camera;
+            recovered.add_camera(
+                camera.eye,
+                camera.target,
+                camera.up,
+                camera.aspect,
+                camera.fovy,
+                camera.znear,
+                camera.zfar,
+            );
+        }
+
+        for light in self.lights.get_lights() {
+            recovered.add_light(&mut light.clone());
+        }
+        recovered.set_light_exposure(self.lights.get_exposure());
+        recovered.set_shadow_settings(self.lights.get_shadow_settings());
+        recovered.set_light_gizmos(self.get_light_gizmos());
+        recovered.set_post_settings(self.get_post_settings());
+        recovered.set_lens_flare(self.get_lens_flare());
+        recovered
+            .post_stack
+            .set_screen_overlays(self.post_stack.take_screen_overlays());
+
+        if let Some((path, intensity)) = self.records.environment.take() {
+            if let Err(e) = recovered.set_environment(&path, intensity) {
+                error!("Failed to load the environment map {path:?}: {e}");
+            }
+        }
+
+        if let Some(path) = self.records.color_grading.take() {
+            if let Err(e) = recovered.set_color_grading(Some(&path)) {
+                error!("Failed to load the color grading {path:?}: {e}");
+            }
+        }
+
+        // The text and quads only live on the cpu, the brushes are built again for the fonts
+        recovered.fonts = mem::take(&mut self.fonts);
+        recovered.font_fallbacks = mem::take(&mut self.font_fallbacks);
+        (recovered.brush, recovered.world_brush) =
+            Self::create_brushes(&recovered.fonts, &recovered.device, &recovered.config);
+        recovered.texts = mem::take(&mut self.texts);
+        recovered.sdf_texts = mem::take(&mut self.sdf_texts);
+        recovered.quads = mem::take(&mut self.quads);
+        recovered.fps = mem::take(&mut self.fps);
+        recovered.stats = mem::take(&mut self.stats);
+        recovered.start_time = self.start_time;
+
+        if self.sprites.iter().any(Option::is_some) || self.decal_renderer.has_decals() {
+            warn!("Sprites and decals were lost with the gpu device and have to be added again");
+        }
+
+        // Every object is loaded into the same slot so the object indices stay valid
+        recovered.model_instances = mem::take(&mut self.model_instances);
+        recovered.model_instance_buffer =
+            Self::create_instance_buffer(&recovered.device, &recovered.model_instances);
+        recovered.morph_weights = MorphWeights::new(
+            &recovered.device,
+            &recovered.queue,
+            &recovered.model_instances,
+        );
+        recovered.records.objects = mem::take(&mut self.records.objects);
+        recovered.models = recovered
+            .records
+            .objects
+            .iter()
+            .enumerate()
+            .map(|(index, record)| {
+                let record = record.as_ref()?;
+                match record.load(&recovered.device, &recovered.queue) {
+                    Ok(model) => Some(model),
+                    Err(e) => {
+                        error!("Failed to load object {index} after the device was lost: {e}");
+                        None
+                    }
+                }
+            })
+            .collect();
+        recovered
+            .models
+            .resize_with(self.models.len(), Default::default);
+
+        for (index, old_model) in self.models.iter().enumerate() {
+            if let (Some(old_model), Some(model)) = (old_model, recovered.models[index].as_mut()) {
+                model.set_instances(old_model.get_instances());
+            }
+        }
+
+        // Objects that were still loading get the latest instances they were given
+        for pending in mem::take(&mut self.pending_objects) {
+            recovered.update_instances(pending.index, pending.instances);
+        }
+
+        for scatter in &self.scatters {
+            recovered.scatters.push(Scatter::new(
+                &recovered.device,
+                scatter.get_object_index(),
+                *scatter.get_settings(),
+            ));
+        }
+
+        for index in 0..recovered.records.objects.len() {
+            let Some(record) = recovered.records.objects[index].clone() else {
+                continue;
+            };
+
+            recovered.set_object_outline(index, record.outline);
+            recovered.set_object_reflection(index, record.reflection);
+        }
+
+        recovered.set_render_scale(self.render_scale);
+        recovered.dynamic_resolution = self.dynamic_resolution.take();
+        recovered.frustum_culling = self.frustum_culling;
+        recovered.set_indirect_drawing(self.indirect_draws.is_some());
+        recovered.set_color_audit(self.color_audit);
+        recovered.vertex_occlusion = self.vertex_occlusion;
+        recovered.set_sky(self.get_sky());
+
+        *self = recovered;
+
+        Ok(())
+    }
+
+    fn from_device(
+        surface: Option<Surface<'static>>,
+        device: Device,
+        queue: Queue,
+        config: SurfaceConfiguration,
+        capabilities: RendererCapabilities,
+    ) -> Self {
+        let headless_target = surface
+            .is_none()
+            .then(|| Self::create_headless_target(&device, &config));
+        let device_lost = recovery::watch_device_loss(&device);
+
+        let camera = Camera::create(
+            &device,
+            (5.0, 5.0, 5.0).into(),
+            (0.0, 0.0, 0.0).into(),
+            cgmath::Vector3::unit_y(),
+            config.width as f32 / config.height as f32,
+            45.0,
+            0.1,
+            100.0,
+        );
+
+        // The lights are bound even if there are none so the scene can always be drawn
+        let mut lights = Lights::default();
+        lights.adjust_buffer(&device);
+
+        let depth_texture = HeliumTexture::create_depth_texture(&device, &config);
+
+        // The default instance for all models will be at the world origin
+        // change the location by creating instances and adding them to this vector
+        let model_instances = vec![instance::Instance::default()];
+
+        let model_instance_buffer = Self::create_instance_buffer(&device, &model_instances);
+
+        // Shared layouts are created once and looked up by the pipelines that use them
+        let mut layouts = LayoutRegistry::new(&device);
+
+        let model_pipelines = ModelPipelines::new(&device, &mut layouts, HDR_FORMAT);
+
+        let obj_models = Vec::new();
+
+        let fonts = vec![FontArc::try_from_slice(include_bytes!("../../assets/font.ttf")).unwrap()];
+
+        let (brush, world_brush) = Self::create_brushes(&fonts, &device, &config);
+
+        // Everything on the screen is drawn through an srgb view so the linear output of the
+        // shaders is gamma encoded once at the end
+        let view_format = surface_view_format(config.format);
+
+        let overlay_renderer = OverlayRenderer::new(&device, view_format, None);
+        let world_overlay_renderer = OverlayRenderer::new(
+            &device,
+            view_format,
+            Some(Self::world_overlay_depth_stencil()),
+        );
+
+        let sprite_renderer = SpriteRenderer::new(&device, view_format);
+
+        let decal_renderer =
+            DecalRenderer::new(&device, HDR_FORMAT, &depth_texture.create_depth_only_view());
+        let sky_renderer = SkyRenderer::new(&device, HDR_FORMAT);
+        let depth_upscale = DepthUpscale::new(&device, &depth_texture.create_depth_only_view());
+
+        let outline_renderer = OutlineRenderer::new(&device, &layouts, HDR_FORMAT);
+        let uniform_ring = UniformRing::new(&device);
+
+        let debug_draw = DebugDraw::new(&device, &layouts, HDR_FORMAT);
+        let dynamic_meshes = DynamicMeshRenderer::new(&device, &layouts, HDR_FORMAT);
+        let sdf_text_renderer = SdfTextRenderer::new(
+            &device,
+            &layouts,
+            view_format,
+            Self::world_overlay_depth_stencil(),
+        );
+
+        let picking_renderer =
+            PickingRenderer::new(&device, &layouts, (config.width, config.height));
+
+        let post_stack = PostStack::new(
+            &device,
+            &queue,
+            view_format,
+            (config.width, config.height),
+            &depth_texture.create_depth_only_view(),
+        );
+
+        let reflection_renderer =
+            ReflectionRenderer::new(&device, &layouts, HDR_FORMAT, (config.width, config.height));
+
+        let morph_weights = MorphWeights::new(&device, &queue, &model_instances);
 
         Self {
             surface,
+            headless_target,
+            window: None,
             device,
+            device_lost,
             queue,
             config,
             camera,
             camera_active: false,
             lights,
             depth_texture,
-            render_pipeline,
+            screen_depth: None,
+            depth_upscale,
+            model_pipelines,
             models: obj_models,
+            pending_objects: Vec::new(),
+            scatters: Vec::new(),
+            morph_weights,
+            start_time: Instant::now(),
             model_instances,
             model_instance_buffer,
+            decal_renderer,
+            sky_renderer,
+            outline_renderer,
+            uniform_ring,
+            layouts,
+            bind_groups: BindGroupCache::default(),
+            capabilities,
+            debug_draw,
+            dynamic_meshes,
+            picking_renderer,
+            reflection_renderer,
+            post_stack,
+            render_graph: RenderGraph::default(),
             brush,
+            world_brush,
+            fonts,
+            font_fallbacks: HashMap::new(),
+            texts: Vec::new(),
+            sdf_texts: Vec::new(),
+            sdf_text_renderer,
+            overlay_renderer,
+            world_overlay_renderer,
+            quads: Vec::new(),
+            sprite_renderer,
+            sprite_textures: Vec::new(),
+            sprite_regions: Vec::new(),
+            sprites: Vec::new(),
+            text_icons: HashMap::new(),
             fps: String::new(),
+            stats: String::new(),
+            render_stats: RenderStats::default(),
+            scale_factor: 1.0,
+            render_scale: 1.0,
+            dynamic_resolution: None,
+            color_audit: false,
+            vertex_occlusion: None,
+            frustum_culling: true,
+            indirect_draws: None,
+            records: AssetRecords::default(),
         }
     }
 
@@ -433,17 +2337,24 @@ impl HeliumState {
         .unwrap()
     }
 
-    fn create_device(adapter: &Adapter) -> (Device, Queue) {
-        smol::block_on(adapter.request_device(
+    fn create_device(adapter: &Adapter) -> (Device, Queue, RendererCapabilities) {
+        // Optional features are only requested when the gpu supports them, the subsystems
+        // that use them fall back when they were not granted
+        let (required_features, required_limits) = RendererCapabilities::negotiate(adapter);
+
+        let (device, queue) = smol::block_on(adapter.request_device(
             &DeviceDescriptor {
-                required_features: Features::empty(),
-                required_limits: Limits::default(),
+                required_features,
+                required_limits,
                 label: None,
                 ..Default::default()
             },
             None,
         ))
-        .unwrap()
+        .unwrap();
+
+        let capabilities = RendererCapabilities::from_device(&device, adapter.get_info());
+        (device, queue, capabilities)
     }
 
     fn create_surface_config(
@@ -457,6 +2368,14 @@ impl HeliumState {
             .copied()
             .unwrap_or(surface_capabilities.formats[0]);
 
+        // Surfaces without an srgb format can still be viewed as srgb
+        let view_format = surface_view_format(surface_format);
+        let view_formats = if view_format != surface_format {
+            vec![view_format]
+        } else {
+            vec![]
+        };
+
         SurfaceConfiguration {
             usage: TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
@@ -464,28 +2383,203 @@ impl HeliumState {
             height: size.height,
             present_mode: PresentMode::AutoNoVsync,
             alpha_mode: surface_capabilities.alpha_modes[0],
-            view_formats: vec![],
+            view_formats,
             desired_maximum_frame_latency: 2,
         }
     }
 
     // Call this when resizing the window
+    /// Resizes the surface and every screen sized texture, the camera aspect ratio is
+    /// changed to match. Zero sizes, like when the window is minimized, are skipped
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        if new_size.width == 0 || new_size.height == 0 {
+            debug!("Skipping resize to: {:?}", new_size);
+            return;
+        }
+
         self.config.width = new_size.width;
         self.config.height = new_size.height;
 
-        self.surface.configure(&self.device, &self.config);
-        self.depth_texture = HeliumTexture::create_depth_texture(&self.device, &self.config);
+        match self.surface.as_ref() {
+            Some(surface) => surface.configure(&self.device, &self.config),
+            None => {
+                self.headless_target =
+                    Some(Self::create_headless_target(&self.device, &self.config))
+            }
+        }
+        self.resize_scene();
+        self.picking_renderer
+            .resize(&self.device, (self.config.width, self.config.height));
+        self.reflection_renderer
+            .resize(&self.device, (self.config.width, self.config.height));
+        self.brush.resize_view(
+            self.config.width as f32,
+            self.config.height as f32,
+            &self.queue,
+        );
+        self.world_brush.resize_view(
+            self.config.width as f32,
+            self.config.height as f32,
+            &self.queue,
+        );
+        self.render_graph
+            .resize(&self.device, (self.config.width, self.config.height));
+
+        self.camera.aspect = self.get_aspect_ratio();
+        self.camera.update_view_proj();
+        self.queue.write_buffer(
+            self.camera.get_buffer(),
+            0,
+            bytemuck::cast_slice(&[*self.camera.get_uniform()]),
+        );
 
         info!("Resized to: {:?}", new_size);
     }
 
+    // Creates the textures the scene is drawn to at the size of the scene
+    fn resize_scene(&mut self) {
+        let (width, height) = self.get_scene_size();
+        let scene_config = SurfaceConfiguration {
+            width,
+            height,
+            ..self.config.clone()
+        };
+
+        self.depth_texture = HeliumTexture::create_depth_texture(&self.device, &scene_config);
+        let scene_depth = self.depth_texture.create_depth_only_view();
+        self.decal_renderer
+            .set_depth_view(&self.device, &scene_depth);
+        self.depth_upscale
+            .set_scene_depth(&self.device, &scene_depth);
+        self.post_stack
+            .resize(&self.device, (width, height), &scene_depth);
+
+        self.screen_depth = ((width, height) != (self.config.width, self.config.height))
+            .then(|| HeliumTexture::create_depth_texture(&self.device, &self.config));
+    }
+
+    /// Gets the width and height in pixels the scene is drawn at before it is scaled onto
+    /// the screen
+    pub fn get_scene_size(&self) -> (u32, u32) {
+        (
+            ((self.config.width as f32 * self.render_scale).round() as u32).max(1),
+            ((self.config.height as f32 * self.render_scale).round() as u32).max(1),
+        )
+    }
+
+    /// Draws the scene at a different resolution than the screen, lower scales are faster
+    /// and blurrier and scales above 1 smooth the edges. The overlay is always drawn at the
+    /// resolution of the screen
+    ///
+    /// # Arguments
+    ///
+    /// * `scale` - The resolution of the scene compared to the screen, clamped to
+    ///   `RENDER_SCALE_RANGE`. The dynamic resolution changes it while it is on
+    pub fn set_render_scale(&mut self, scale: f32) {
+        let scale = scale.clamp(RENDER_SCALE_RANGE.0, RENDER_SCALE_RANGE.1);
+        if scale == self.render_scale {
+            return;
+        }
+
+        self.render_scale = scale;
+        self.resize_scene();
+        debug!("Render scale set to {scale}");
+    }
+
+    pub fn get_render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /// Bakes ambient occlusion into the vertices of the objects and static batches that are
+    /// loaded after it is set, crevices are darkened without any cost while drawing.
+    /// Objects that are already loaded keep their occlusion
+    ///
+    /// # Arguments
+    ///
+    /// * `occlusion` - How the occlusion is baked, `None` to stop baking
+    pub fn set_vertex_occlusion(&mut self, occlusion: Option<AmbientOcclusionBake>) {
+        self.vertex_occlusion = occlusion;
+    }
+
+    pub fn get_vertex_occlusion(&self) -> Option<AmbientOcclusionBake> {
+        self.vertex_occlusion
+    }
+
+    /// Changes the render scale every few frames to keep the frame time near a target
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The target and limits of the scale, `None` keeps the current scale
+    pub fn set_dynamic_resolution(&mut self, settings: Option<DynamicResolution>) {
+        self.dynamic_resolution = settings.map(ResolutionController::new);
+    }
+
+    pub fn get_dynamic_resolution(&self) -> Option<DynamicResolution> {
+        self.dynamic_resolution
+            .as_ref()
+            .map(ResolutionController::get_settings)
+    }
+
+    /// The width of the screen divided by the height
+    pub fn get_aspect_ratio(&self) -> f32 {
+        self.config.width as f32 / self.config.height as f32
+    }
+
+    /// Sets the ratio of physical pixels to logical pixels of the display the window is on
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+    }
+
+    pub fn get_scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    // The textures of the frame that custom passes can use
+    fn frame_resources<'a>(
+        &'a self,
+        surface: &'a TextureView,
+        scene_depth_sampled: &'a TextureView,
+    ) -> FrameResources<'a> {
+        FrameResources {
+            device: &self.device,
+            queue: &self.queue,
+            size: (self.config.width, self.config.height),
+            scene_size: self.get_scene_size(),
+            scene_color: self.post_stack.get_scene_view(),
+            scene_depth: self.depth_texture.get_view(),
+            scene_depth_sampled,
+            surface,
+            camera_bind_group: self.camera_active.then(|| self.camera.get_bind_group()),
+        }
+    }
+
     // Call this when requesting redraw
     pub fn render(&mut self) -> Result<(), SurfaceError> {
-        let output = self.surface.get_current_texture().unwrap();
-        let view = output
-            .texture
-            .create_view(&TextureViewDescriptor::default());
+        let _span = profile_span("render");
+        self.poll_pending_objects();
+
+        let render_scale = self.render_scale;
+        if let Some(scale) = self
+            .dynamic_resolution
+            .as_mut()
+            .and_then(|controller| controller.frame(render_scale))
+        {
+            self.set_render_scale(scale);
+        }
+
+        let output = self
+            .surface
+            .as_ref()
+            .map(|surface| surface.get_current_texture())
+            .transpose()?;
+        let target = match output.as_ref() {
+            Some(output) => &output.texture,
+            None => self.headless_target.as_ref().unwrap(),
+        };
+        let view = target.create_view(&TextureViewDescriptor {
+            format: Some(surface_view_format(self.config.format)),
+            ..Default::default()
+        });
 
         let mut encoder = self
             .device
@@ -493,12 +2587,124 @@ impl HeliumState {
                 label: Some("Render Encoder"),
             });
 
+        let mut stats = RenderStats::default();
+
+        // The small per draw uniforms of this frame are written to the ring in one upload
+        self.uniform_ring.begin_frame();
+        self.outline_renderer.prepare(&mut self.uniform_ring);
+        self.uniform_ring.upload(&self.device, &self.queue);
+
+        // Scattered objects are drawn from their own instance buffers
+        let scattered = self
+            .scatters
+            .iter()
+            .map(|scatter| scatter.get_object_index())
+            .collect::<Vec<_>>();
+
+        // Draws are sorted so bind groups and buffers are only set when they change
+        let frustum = self
+            .frustum_culling
+            .then(|| Frustum::from_view_proj(&self.camera.build_view_projection_matrix()));
+        let multi_draw = self
+            .indirect_draws
+            .as_ref()
+            .is_some_and(|indirect_draws| indirect_draws.supports_multi_draw());
+        let (draws, culled) = draw_list::build_draw_list(
+            &self.models,
+            &self.scatters,
+            &self.reflection_renderer,
+            &self.model_instances,
+            frustum.as_ref(),
+            self.camera.eye,
+            multi_draw,
+        );
+        stats.culled = culled;
+
+        // The pipelines of new face modes are created before the scene pass borrows them
+        for draw in draws.iter() {
+            self.model_pipelines
+                .prepare(&self.device, &mut self.layouts, draw.variant);
+            if draw.pipeline == DrawPipeline::Morph {
+                self.model_pipelines
+                    .prepare_morph(&self.device, &mut self.layouts, draw.variant);
+            }
+        }
+
+        // The morph targets are bound with the weight buffer, which is replaced as it grows
+        for mesh in self
+            .models
+            .iter_mut()
+            .flatten()
+            .flat_map(|model| model.get_meshes_mut().iter_mut())
+        {
+            if let Some(morph) = mesh.get_morph_targets_mut() {
+                morph.prepare(
+                    &self.device,
+                    self.layouts.get(LayoutKind::Morph),
+                    &self.morph_weights,
+                );
+            }
+        }
+
+        // The draw arguments have to be on the gpu before the scene is drawn
+        if let Some(indirect_draws) = self.indirect_draws.as_mut() {
+            indirect_draws.write(&self.device, &self.queue, &draws, &self.models);
+        }
+
+        // Shadow render passes, the depth from the lights is drawn before anything is lit
+        if self.camera_active {
+            let _span = profile_span("shadow_pass");
+            self.lights.draw_shadows(
+                &self.queue,
+                &mut encoder,
+                &self.camera,
+                &self.models,
+                &scattered,
+                &self.model_instance_buffer,
+            );
+        }
+
+        // Every material animates its texture with the same clock
+        if self.camera_active {
+            self.camera
+                .camera_uniform
+                .set_time(self.start_time.elapsed().as_secs_f32());
+            self.queue.write_buffer(
+                self.camera.get_buffer(),
+                0,
+                bytemuck::cast_slice(&[*self.camera.get_uniform()]),
+            );
+        }
+
+        // Reflection render passes, the mirrored scene is drawn before the surfaces sample it
+        if self.camera_active {
+            let _span = profile_span("reflection_pass");
+            self.reflection_renderer
+                .update_cameras(&self.queue, &self.camera);
+
+            self.reflection_renderer.draw(
+                &mut encoder,
+                &self.models,
+                &scattered,
+                &self.model_instance_buffer,
+                self.lights.get_bind_group(),
+            );
+        }
+
+        // The camera is jittered for temporal anti-aliasing before anything samples it
+        if self.camera_active {
+            self.post_stack.prepare_camera(&self.queue, &self.camera);
+            self.post_stack
+                .prepare_lens_flare(&self.queue, &self.camera, &self.lights);
+        }
+
         // Scene Render pass
         {
+            let _span = profile_span("scene_pass");
             let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
                 label: Some("Scene Render Pass"),
                 color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &view,
+                    view: self.post_stack.get_scene_view(),
                     resolve_target: None,
                     ops: Operations {
                         load: LoadOp::Clear(Color::BLACK),
@@ -517,39 +2723,484 @@ impl HeliumState {
                 timestamp_writes: None,
             });
 
+            // The sky is drawn first so the scene covers it
+            if self.camera_active {
+                self.sky_renderer
+                    .draw(&self.queue, &mut render_pass, &self.camera);
+            }
+
             // Only render the scene if the camera is active
             if self.camera_active {
-                // Set the render pipeline to the model render pipeline
-                render_pass.set_pipeline(&self.render_pipeline);
                 // Set this to the current held instance buffer that stores all the instance data for each mesh
                 render_pass.set_vertex_buffer(1, self.model_instance_buffer.slice(..));
 
-                // Lighting
-                render_pass.set_bind_group(2, self.lights.get_bind_group(), &[]);
-
-                // Sets each of the bind groups
-                use crate::model::draw_model::DrawModel;
-                for model in self.models.iter() {
-                    // Render each mesh in the model with its corresponding material
-                    for mesh in model.get_meshes().iter() {
-                        render_pass.draw_mesh(
-                            mesh,
-                            &model.get_materials()[*(mesh.get_material_index().unwrap())],
-                            self.camera.get_bind_group(),
+                let mut current_pipeline: Option<DrawPipeline> = None;
+                let mut current_variant = None;
+                let mut current_material = None;
+                let mut current_mesh = None;
+
+                // Draws of the same mesh with the same state are only split by culling
+                let mut draw_index = 0;
+                for batch in draws.chunk_by(|a, b| {
+                    a.pipeline == b.pipeline
+                        && a.variant == b.variant
+                        && a.material == b.material
+                        && a.mesh == b.mesh
+                }) {
+                    let first_draw = draw_index;
+                    draw_index += batch.len();
+                    let draw = &batch[0];
+
+                    let same_pipeline = current_pipeline.is_some_and(|pipeline| {
+                        pipeline.same_pipeline(&draw.pipeline)
+                            && current_variant == Some(draw.variant)
+                    });
+
+                    if !same_pipeline {
+                        render_pass.set_pipeline(match draw.pipeline {
+                            DrawPipeline::Model => self.model_pipelines.get_model(draw.variant),
+                            DrawPipeline::Morph => self.model_pipelines.get_morph(draw.variant),
+                            DrawPipeline::Scatter(_) => {
+                                self.model_pipelines.get_scatter(draw.variant)
+                            }
+                            DrawPipeline::Reflection(_) => {
+                                self.reflection_renderer.get_surface_pipeline()
+                            }
+                        });
+                        debug_assert!(
+                            !matches!(draw.pipeline, DrawPipeline::Model)
+                                || (self.layouts.matches("Model", 1, LayoutKind::Camera)
+                                    && self.layouts.matches("Model", 2, LayoutKind::Lights)),
+                            "The model pipeline does not take the camera and lights bind groups"
+                        );
+                        render_pass.set_bind_group(1, self.camera.get_bind_group(), &[]);
+                        // Lighting
+                        render_pass.set_bind_group(2, self.lights.get_bind_group(), &[]);
+                        stats.pipeline_changes += 1;
+                    }
+
+                    if current_pipeline != Some(draw.pipeline) {
+                        match draw.pipeline {
+                            DrawPipeline::Model | DrawPipeline::Morph => {}
+                            DrawPipeline::Scatter(scatter_index) => render_pass.set_bind_group(
+                                3,
+                                self.scatters[scatter_index].get_bind_group(),
+                                &[],
+                            ),
+                            DrawPipeline::Reflection(reflection_index) => render_pass
+                                .set_bind_group(
+                                    3,
+                                    self.reflection_renderer.get_bind_group(reflection_index),
+                                    &[],
+                                ),
+                        }
+                    }
+                    current_pipeline = Some(draw.pipeline);
+                    current_variant = Some(draw.variant);
+
+                    let (model_index, mesh_index) = draw.mesh;
+                    let Some(model) = self.models[model_index].as_ref() else {
+                        continue;
+                    };
+
+                    if current_material != Some(draw.material) {
+                        render_pass.set_bind_group(
+                            0,
+                            model.get_materials()[draw.material.1].get_bind_group(),
+                            &[],
+                        );
+                        current_material = Some(draw.material);
+                        stats.material_binds += 1;
+                    }
+
+                    let mesh = &model.get_meshes()[mesh_index];
+                    if current_mesh != Some(draw.mesh) {
+                        render_pass.set_vertex_buffer(0, mesh.get_vertex_buffer().slice(..));
+                        render_pass.set_index_buffer(
+                            mesh.get_index_buffer().slice(..),
+                            IndexFormat::Uint32,
                         );
+                        current_mesh = Some(draw.mesh);
+                        stats.mesh_binds += 1;
                     }
+
+                    // Every mesh with morph targets has its own deltas
+                    if draw.pipeline == DrawPipeline::Morph {
+                        if let Some(bind_group) = mesh
+                            .get_morph_targets()
+                            .and_then(|morph| morph.get_bind_group())
+                        {
+                            render_pass.set_bind_group(3, bind_group, &[]);
+                        }
+                    }
+
+                    match self.indirect_draws.as_ref() {
+                        Some(indirect_draws) if indirect_draws.supports_multi_draw() => {
+                            render_pass.multi_draw_indexed_indirect(
+                                indirect_draws.get_buffer(),
+                                IndirectDraws::offset(first_draw),
+                                batch.len() as u32,
+                            );
+                            stats.draw_calls += 1;
+                        }
+                        Some(indirect_draws) => {
+                            for index in first_draw..draw_index {
+                                render_pass.draw_indexed_indirect(
+                                    indirect_draws.get_buffer(),
+                                    IndirectDraws::offset(index),
+                                );
+                            }
+                            stats.draw_calls += batch.len() as u32;
+                        }
+                        None => {
+                            for draw in batch {
+                                let (start, end) = draw.instances;
+                                render_pass.draw_indexed(0..mesh.get_num_elements(), 0, start..end);
+                            }
+                            stats.draw_calls += batch.len() as u32;
+                        }
+                    }
+
+                    for draw in batch {
+                        let (start, end) = draw.instances;
+                        stats.instances += end - start;
+                        if mesh.get_topology().is_triangles() {
+                            stats.triangles += mesh.get_num_elements() / 3 * (end - start);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.render_stats = stats;
+
+        // Velocity render pass, the motion of the models is drawn for temporal anti-aliasing
+        if self.camera_active {
+            let _span = profile_span("velocity_pass");
+            self.post_stack.draw_velocity(
+                &self.device,
+                &mut encoder,
+                &self.models,
+                &scattered,
+                &self.model_instance_buffer,
+            );
+        }
+
+        // Pick render pass, only drawn when there are picks waiting for the next frame
+        if self.camera_active {
+            let _span = profile_span("pick_pass");
+            self.picking_renderer.draw(
+                &self.device,
+                &mut encoder,
+                &self.models,
+                &self.model_instance_buffer,
+                self.camera.get_bind_group(),
+            );
+        }
+
+        // Decal render pass, the decals read the depth of the scene instead of testing it
+        if self.camera_active && self.decal_renderer.has_decals() {
+            let _span = profile_span("decal_pass");
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Decal Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: self.post_stack.get_scene_view(),
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            self.decal_renderer
+                .draw(&mut render_pass, &self.queue, &self.camera);
+        }
+
+        // Dynamic mesh render pass, the meshes are blended over the scene
+        if self.camera_active && self.dynamic_meshes.has_meshes() {
+            let _span = profile_span("dynamic_mesh_pass");
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Dynamic Mesh Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: self.post_stack.get_scene_view(),
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: self.depth_texture.get_view(),
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    }),
+                    stencil_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    }),
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            self.dynamic_meshes.draw(
+                &self.device,
+                &self.queue,
+                &mut render_pass,
+                self.camera.get_bind_group(),
+            );
+        }
+
+        // Outline render pass, highlighted objects are masked in the stencil buffer
+        if self.camera_active && self.outline_renderer.has_outlines() {
+            let _span = profile_span("outline_pass");
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Outline Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: self.post_stack.get_scene_view(),
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: self.depth_texture.get_view(),
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    }),
+                    stencil_ops: Some(Operations {
+                        load: LoadOp::Clear(0),
+                        store: StoreOp::Discard,
+                    }),
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            self.outline_renderer.draw(
+                &mut render_pass,
+                &self.models,
+                &self.model_instance_buffer,
+                self.camera.get_bind_group(),
+                &self.uniform_ring,
+            );
+        }
+
+        // Debug render pass, the lines are hidden behind the scene
+        if self.camera_active && self.debug_draw.has_lines() {
+            let _span = profile_span("debug_pass");
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Debug Draw Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: self.post_stack.get_scene_view(),
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: self.depth_texture.get_view(),
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    }),
+                    stencil_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    }),
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            self.debug_draw.draw(
+                &self.device,
+                &self.queue,
+                &mut render_pass,
+                self.camera.get_bind_group(),
+                &self.lights,
+            );
+        }
+
+        // The graph is taken out of the state so the passes can borrow the frame resources
+        let mut render_graph = std::mem::take(&mut self.render_graph);
+        let scene_depth_sampled = self.depth_texture.create_depth_only_view();
+
+        render_graph.execute(
+            RenderStage::AfterScene,
+            &mut encoder,
+            &self.frame_resources(&view, &scene_depth_sampled),
+        );
+
+        // Post render pass, the hdr scene is exposed and tonemapped onto the screen
+        {
+            let _span = profile_span("post_pass");
+            self.post_stack.run(&mut encoder, &self.queue, &view);
+        }
+
+        render_graph.execute(
+            RenderStage::AfterPost,
+            &mut encoder,
+            &self.frame_resources(&view, &scene_depth_sampled),
+        );
+
+        // Text is laid out every frame so anchored text follows the size of the window
+        let screen_size = (self.config.width as f32, self.config.height as f32);
+
+        // The depth of a scene drawn at another resolution is copied to the size of the screen
+        if let Some(screen_depth) = self.screen_depth.as_ref() {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Depth Upscale Render Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: screen_depth.get_view(),
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Clear(1.0),
+                        store: StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            self.depth_upscale.draw(&mut render_pass);
+        }
+
+        // World overlay render pass, text and quads with a depth are hidden behind the scene
+        {
+            let _span = profile_span("world_overlay_pass");
+            let fonts = FontSet {
+                fonts: &self.fonts,
+                fallbacks: &self.font_fallbacks,
+                icons: &self.text_icons,
+                sprite_regions: &self.sprite_regions,
+            };
+            let mut sections = Vec::new();
+            for text in self.texts.iter().flatten() {
+                if text.depth.is_some() {
+                    sections.append(&mut text.sections(screen_size, &fonts));
                 }
             }
+
+            self.world_brush
+                .queue(&self.device, &self.queue, sections.iter())
+                .unwrap();
+
+            // Debug labels are placed on the screen so they are drawn over the scene
+            let debug_labels = if self.camera_active {
+                self.debug_draw.labels(&self.camera, screen_size)
+            } else {
+                Vec::new()
+            };
+
+            self.sdf_text_renderer.prepare(
+                &self.device,
+                &self.queue,
+                self.sdf_texts.iter().flatten().chain(debug_labels.iter()),
+                &self.fonts,
+                self.camera_active.then_some(&self.camera),
+                screen_size,
+            );
+
+            self.world_overlay_renderer.prepare(
+                &self.device,
+                &self.queue,
+                self.quads
+                    .iter()
+                    .flatten()
+                    .filter(|quad| quad.depth.is_some()),
+                screen_size,
+            );
+
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("World Overlay Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: self
+                        .screen_depth
+                        .as_ref()
+                        .unwrap_or(&self.depth_texture)
+                        .get_view(),
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            self.world_overlay_renderer.draw(&mut render_pass);
+            self.world_brush.draw(&mut render_pass);
+            self.sdf_text_renderer
+                .draw(&mut render_pass, self.camera.get_bind_group());
         }
 
         // Overlay render pass
         {
-            let section = TextSection::default()
+            let _span = profile_span("overlay_pass");
+            let stats = format!("\n{}", self.stats);
+            let mut stats_section = TextSection::default()
                 .add_text(Text::new(&self.fps).with_color([1.0, 1.0, 1.0, 1.0]));
+            if !self.stats.is_empty() {
+                stats_section =
+                    stats_section.add_text(Text::new(&stats).with_color([1.0, 1.0, 1.0, 1.0]));
+            }
+            let mut sections = vec![stats_section];
+
+            let fonts = FontSet {
+                fonts: &self.fonts,
+                fallbacks: &self.font_fallbacks,
+                icons: &self.text_icons,
+                sprite_regions: &self.sprite_regions,
+            };
+            let mut icons = Vec::new();
+            for text in self.texts.iter().flatten() {
+                if text.depth.is_none() {
+                    sections.append(&mut text.sections(screen_size, &fonts));
+                    icons.append(&mut text.icons(screen_size, &fonts));
+                }
+            }
+
             self.brush
-                .queue(&self.device, &self.queue, [&section])
+                .queue(&self.device, &self.queue, sections.iter())
                 .unwrap();
 
+            self.overlay_renderer.prepare(
+                &self.device,
+                &self.queue,
+                self.quads
+                    .iter()
+                    .flatten()
+                    .filter(|quad| quad.depth.is_none()),
+                screen_size,
+            );
+
+            self.sprite_renderer.prepare(
+                &self.device,
+                &self.queue,
+                self.sprites.iter().flatten().chain(&icons),
+                &self.sprite_regions,
+                screen_size,
+            );
+
             let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
                 label: Some("Overlay Render Pass"),
                 color_attachments: &[Some(RenderPassColorAttachment {
@@ -565,12 +3216,33 @@ impl HeliumState {
                 timestamp_writes: None,
             });
 
+            // Quads are drawn first so sprites and text can be placed on top of them
+            self.overlay_renderer.draw(&mut render_pass);
+            self.sprite_renderer
+                .draw(&mut render_pass, &self.sprite_textures);
             self.brush.draw(&mut render_pass);
         }
 
-        self.queue.submit(once(encoder.finish()));
-        output.present();
+        render_graph.execute(
+            RenderStage::AfterOverlay,
+            &mut encoder,
+            &self.frame_resources(&view, &scene_depth_sampled),
+        );
+        self.render_graph = render_graph;
+
+        {
+            let _span = profile_span("submit");
+            self.queue.submit(once(encoder.finish()));
+            if let Some(output) = output {
+                output.present();
+            }
+        }
+
+        // Read back the picks drawn this frame, the results arrive on a later poll
+        self.picking_renderer.map_copied();
+        self.device.poll(Maintain::Poll);
 
+        profile_frame("render");
         Ok(())
     }
 }
@@ -1,27 +1,87 @@
-use cgmath::{Vector3, Zero};
+use cgmath::{InnerSpace, Vector3, Zero};
 use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
     BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
-    BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType, BufferUsages, Device, Queue,
-    ShaderStages,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType, BufferUsages, CommandEncoder,
+    Device, Queue, ShaderStages,
 };
 
 #[allow(unused_imports)]
 use log::*;
 
-#[derive(Default)]
+use crate::{
+    camera::Camera,
+    color::{LinearRgba, Srgba},
+    environment::Environment,
+    model::Model,
+    shadow::{LightShadow, ShadowMaps, ShadowSettings},
+};
+
+// First binding of the environment in the lights bind group
+const ENVIRONMENT_BINDING: u32 = 1;
+
+// First binding of the shadow maps in the lights bind group
+const SHADOW_BINDING: u32 = 5;
+
+// Size of the header that comes before the lights in the light buffer
+const HEADER_SIZE: usize = std::mem::size_of::<LightsHeader>();
+
 pub struct Lights {
     lights: Vec<Light>,
     buffer: Option<Buffer>,
     bind_group: Option<BindGroup>,
+    // Image based lighting that is bound with the lights
+    environment: Option<Environment>,
+    // Depth maps of the lights that cast shadows
+    shadows: Option<ShadowMaps>,
+    // Scale of the lights with physical units, lights without units ignore it
+    exposure: f32,
     pub update_flag: bool,
 }
 
+impl Default for Lights {
+    fn default() -> Self {
+        Self {
+            lights: Vec::new(),
+            buffer: None,
+            bind_group: None,
+            environment: None,
+            shadows: None,
+            exposure: 1.0,
+            update_flag: false,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct LightsHeader {
+    exposure: f32,
+    _padding: [f32; 3],
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct LightRaw {
     pub position: [f32; 3],
     pub color: [f32; 3],
+    pub intensity: f32,
+    pub units: u32,
+    pub direction: [f32; 3],
+    pub kind: u32,
+}
+
+/// Physical exposure of a camera with the exposure value at ISO 100
+///
+/// # Arguments
+///
+/// * `ev100` - The exposure value, around 15 for a sunny day and around 5 for a lit room
+///
+/// # Returns
+///
+/// The multiplier that brings light in candela into the range of the scene
+pub fn exposure_from_ev100(ev100: f32) -> f32 {
+    1.0 / (1.2 * ev100.exp2())
 }
 
 impl Lights {
@@ -34,7 +94,12 @@ impl Lights {
     // HACK: This needs to be fixed in a much better way
     pub fn update_light(&mut self, light: &Light, queue: &Queue) {
         use std::mem;
-        let index = light.index * mem::size_of::<LightRaw>();
+        let index = HEADER_SIZE + light.index * mem::size_of::<LightRaw>();
+
+        // The shadows are drawn from the lights that are kept here
+        if let Some(stored) = self.lights.get_mut(light.index) {
+            *stored = *light;
+        }
 
         queue.write_buffer(
             self.buffer.as_ref().unwrap(),
@@ -44,21 +109,106 @@ impl Lights {
     }
 
     pub fn get_bind_group_layout(device: &Device) -> BindGroupLayout {
+        let mut entries = vec![BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }];
+        entries.extend(Environment::layout_entries(ENVIRONMENT_BINDING));
+        entries.extend(ShadowMaps::layout_entries(SHADOW_BINDING));
+
         device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: Some("Lights Bind Group"),
-            entries: &[BindGroupLayoutEntry {
-                binding: 0,
-                visibility: ShaderStages::FRAGMENT,
-                ty: BindingType::Buffer {
-                    ty: BufferBindingType::Storage { read_only: true },
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
+            entries: &entries,
         })
     }
 
+    /// Sets the environment map used for image based lighting
+    pub fn set_environment(&mut self, environment: Environment, device: &Device) {
+        self.environment = Some(environment);
+        self.adjust_buffer(device);
+    }
+
+    /// Sets how much the lights with physical units are scaled before they are added to
+    /// the scene, use `exposure_from_ev100` to get the exposure of a physical camera
+    pub fn set_exposure(&mut self, exposure: f32, queue: &Queue) {
+        self.exposure = exposure;
+
+        if let Some(buffer) = self.buffer.as_ref() {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&[self.header()]));
+        }
+    }
+
+    pub fn get_exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    fn header(&self) -> LightsHeader {
+        LightsHeader {
+            exposure: self.exposure,
+            _padding: [0.0; 3],
+        }
+    }
+
+    /// Changes the quality of the shadows, the shadow maps are recreated
+    pub fn set_shadow_settings(&mut self, settings: ShadowSettings, device: &Device) {
+        self.shadows = Some(ShadowMaps::new(device, settings));
+        self.adjust_buffer(device);
+    }
+
+    pub fn get_shadow_settings(&self) -> ShadowSettings {
+        self.shadows
+            .as_ref()
+            .map(|shadows| shadows.get_settings())
+            .unwrap_or_default()
+    }
+
+    /// Draws the shadow maps of the lights that cast shadows
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - The queue to write the cameras of the lights with
+    /// * `encoder` - The encoder to record the passes in
+    /// * `camera` - The camera the scene is drawn with, lights near it get shadows first and
+    ///   the cascades of directional lights are fit to it
+    /// * `models` - The models that cast shadows
+    /// * `scattered` - Models that are drawn from their own instance buffers and skipped
+    /// * `instance_buffer` - The instances of the models
+    pub fn draw_shadows(
+        &mut self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        camera: &Camera,
+        models: &[Option<Model>],
+        scattered: &[usize],
+        instance_buffer: &Buffer,
+    ) {
+        if let Some(shadows) = self.shadows.as_mut() {
+            shadows.draw(
+                queue,
+                encoder,
+                &self.lights,
+                camera,
+                models,
+                scattered,
+                instance_buffer,
+            );
+        }
+    }
+
+    pub fn get_lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    pub fn get_environment_mut(&mut self) -> Option<&mut Environment> {
+        self.environment.as_mut()
+    }
+
     pub fn get_bind_group(&self) -> &BindGroup {
         self.bind_group.as_ref().unwrap()
     }
@@ -71,40 +221,140 @@ impl Lights {
     /// On the GPU
     /// Only use when adding or removing lights because it reconstructs the buffer
     pub fn adjust_buffer(&mut self, device: &Device) {
-        let mut light_buffer = Vec::new();
+        let mut light_buffer: Vec<LightRaw> =
+            self.lights.iter().map(|light| light.to_raw()).collect();
 
-        for light in self.lights.iter() {
+        // The light array can not be empty so a black light is used when there are no lights
+        if light_buffer.is_empty() {
             light_buffer.push(LightRaw {
-                position: [light.position.x, light.position.y, light.position.z],
-                color: [light.color.0, light.color.1, light.color.2],
+                position: [0.0; 3],
+                color: [0.0; 3],
+                intensity: 0.0,
+                units: 0,
+                direction: [0.0, -1.0, 0.0],
+                kind: 0,
             });
         }
 
+        let mut contents = bytemuck::bytes_of(&self.header()).to_vec();
+        contents.extend_from_slice(bytemuck::cast_slice(&light_buffer));
+
         let buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("Lights Buffer"),
-            contents: bytemuck::cast_slice(&light_buffer),
+            contents: &contents,
             usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
         });
 
         self.buffer = Some(buffer);
 
+        let environment = self
+            .environment
+            .get_or_insert_with(|| Environment::empty(device));
+
+        let mut entries = vec![BindGroupEntry {
+            binding: 0,
+            resource: self.buffer.as_ref().unwrap().as_entire_binding(),
+        }];
+        entries.extend(environment.bind_group_entries(ENVIRONMENT_BINDING));
+
+        let shadows = self
+            .shadows
+            .get_or_insert_with(|| ShadowMaps::new(device, ShadowSettings::default()));
+        entries.extend(shadows.bind_group_entries(SHADOW_BINDING));
+
         let bind_group = device.create_bind_group(&BindGroupDescriptor {
             label: Some("Lights Bind Group"),
             layout: &Self::get_bind_group_layout(device),
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: self.buffer.as_ref().unwrap().as_entire_binding(),
-            }],
+            entries: &entries,
         });
 
         self.bind_group = Some(bind_group);
     }
 }
 
+/// How the intensity of a light is measured
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LightUnits {
+    /// The color times the intensity is added at every distance, the exposure is ignored
+    #[default]
+    Unitless,
+    /// The intensity is in candela, it falls off with the square of the distance and is
+    /// scaled by the exposure of the lights, directional lights use lux and do not fall off
+    Candela,
+}
+
+/// The shape of the light that is cast
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LightKind {
+    /// Shines in every direction from its position like a light bulb
+    #[default]
+    Point,
+    /// Shines in one direction from infinitely far away like the sun
+    Directional,
+}
+
+impl LightKind {
+    // Index of the kind in the shader
+    fn index(&self) -> u32 {
+        match self {
+            LightKind::Point => 0,
+            LightKind::Directional => 1,
+        }
+    }
+}
+
+impl LightUnits {
+    // Index of the units in the shader
+    fn index(&self) -> u32 {
+        match self {
+            LightUnits::Unitless => 0,
+            LightUnits::Candela => 1,
+        }
+    }
+}
+
+// Approximation of the color of a black body in srgb, valid from 1000K to 40000K
+fn temperature_to_srgb(kelvin: f32) -> Srgba {
+    let temperature = kelvin.clamp(1000.0, 40000.0) / 100.0;
+
+    let red = if temperature <= 66.0 {
+        255.0
+    } else {
+        329.69873 * (temperature - 60.0).powf(-0.13320476)
+    };
+
+    let green = if temperature <= 66.0 {
+        99.4708 * temperature.ln() - 161.11957
+    } else {
+        288.12216 * (temperature - 60.0).powf(-0.07551485)
+    };
+
+    let blue = if temperature >= 66.0 {
+        255.0
+    } else if temperature <= 19.0 {
+        0.0
+    } else {
+        138.51773 * (temperature - 10.0).ln() - 305.0448
+    };
+
+    Srgba::rgb(
+        (red / 255.0).clamp(0.0, 1.0),
+        (green / 255.0).clamp(0.0, 1.0),
+        (blue / 255.0).clamp(0.0, 1.0),
+    )
+}
+
 #[derive(Clone, Copy)]
 pub struct Light {
     position: Vector3<f32>,
     color: (f32, f32, f32),
+    intensity: f32,
+    units: LightUnits,
+    kind: LightKind,
+    // Direction the light shines in, only used by directional lights
+    direction: Vector3<f32>,
+    // `None` when the light does not cast shadows
+    shadows: Option<LightShadow>,
     pub index: usize,
 }
 
@@ -113,10 +363,65 @@ impl Light {
         Self {
             position: Vector3::zero(),
             color,
+            intensity: 1.0,
+            units: LightUnits::Unitless,
+            kind: LightKind::Point,
+            direction: -Vector3::unit_y(),
+            shadows: None,
             index: 0,
         }
     }
 
+    /// Creates a light that shines in one direction everywhere in the scene like the sun,
+    /// it casts cascaded shadows when shadows are turned on
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The color of the light
+    /// * `direction` - The direction the light shines in
+    pub fn directional(color: (f32, f32, f32), direction: Vector3<f32>) -> Self {
+        Self {
+            kind: LightKind::Directional,
+            direction: direction.normalize(),
+            ..Self::new(color)
+        }
+    }
+
+    /// Creates a light with the color of a black body at a temperature
+    ///
+    /// # Arguments
+    ///
+    /// * `kelvin` - The temperature, around 2700K for a light bulb and 6500K for daylight
+    /// * `candela` - The luminous intensity of the light
+    pub fn from_temperature(kelvin: f32, candela: f32) -> Self {
+        let LinearRgba { r, g, b, .. } = temperature_to_srgb(kelvin).to_linear();
+
+        Self {
+            color: (r, g, b),
+            intensity: candela,
+            units: LightUnits::Candela,
+            ..Self::new((1.0, 1.0, 1.0))
+        }
+    }
+
+    pub fn with_intensity(mut self, intensity: f32, units: LightUnits) -> Self {
+        self.intensity = intensity;
+        self.units = units;
+        self
+    }
+
+    /// Makes the light cast shadows with its own quality settings
+    pub fn with_shadows(mut self, shadows: LightShadow) -> Self {
+        self.shadows = Some(shadows);
+        self
+    }
+
+    /// Sets the intensity from the luminous flux of a light that shines in every direction,
+    /// like the lumens written on a light bulb
+    pub fn with_lumens(self, lumens: f32) -> Self {
+        self.with_intensity(lumens / (4.0 * std::f32::consts::PI), LightUnits::Candela)
+    }
+
     pub fn update_position(&mut self, position: &Vector3<f32>) -> &mut Self {
         self.position = *position;
         self
@@ -127,10 +432,76 @@ impl Light {
         self
     }
 
+    /// Changes the color to the color of a black body at a temperature in kelvin
+    pub fn update_temperature(&mut self, kelvin: f32) -> &mut Self {
+        let LinearRgba { r, g, b, .. } = temperature_to_srgb(kelvin).to_linear();
+        self.color = (r, g, b);
+        self
+    }
+
+    pub fn update_intensity(&mut self, intensity: f32) -> &mut Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn get_intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn get_units(&self) -> LightUnits {
+        self.units
+    }
+
+    /// Turns the shadows of the light on or off, `None` turns them off
+    pub fn update_shadows(&mut self, shadows: Option<LightShadow>) -> &mut Self {
+        self.shadows = shadows;
+        self
+    }
+
+    pub fn get_shadows(&self) -> Option<LightShadow> {
+        self.shadows
+    }
+
+    pub fn get_position(&self) -> Vector3<f32> {
+        self.position
+    }
+
+    /// Changes the direction of a directional light
+    pub fn update_direction(&mut self, direction: &Vector3<f32>) -> &mut Self {
+        self.direction = direction.normalize();
+        self
+    }
+
+    /// Points the light at a position in the world, directional lights shine from their
+    /// position towards it
+    pub fn look_at(&mut self, target: &Vector3<f32>) -> &mut Self {
+        let direction = target - self.position;
+        if direction.magnitude2() > f32::EPSILON {
+            self.direction = direction.normalize();
+        }
+        self
+    }
+
+    pub fn get_direction(&self) -> Vector3<f32> {
+        self.direction
+    }
+
+    pub fn get_kind(&self) -> LightKind {
+        self.kind
+    }
+
+    pub fn get_color(&self) -> (f32, f32, f32) {
+        self.color
+    }
+
     fn to_raw(self) -> LightRaw {
         LightRaw {
             position: [self.position.x, self.position.y, self.position.z],
             color: [self.color.0, self.color.1, self.color.2],
+            intensity: self.intensity,
+            units: self.units.index(),
+            direction: [self.direction.x, self.direction.y, self.direction.z],
+            kind: self.kind.index(),
         }
     }
 }
@@ -44,11 +44,7 @@ where
         self.set_vertex_buffer(0, mesh.get_vertex_buffer().slice(..));
         // self.set_vertex_buffer(1, mesh.get_instance_buffer().slice(..));
         self.set_index_buffer(mesh.get_index_buffer().slice(..), IndexFormat::Uint32);
-        self.set_bind_group(
-            0,
-            material.get_diffuse_texture().unwrap().get_bind_group(),
-            &[],
-        );
+        self.set_bind_group(0, material.get_bind_group(), &[]);
         self.set_bind_group(1, camera_bind_group, &[]);
         self.draw_indexed(0..mesh.get_num_elements(), 0, instances);
     }
@@ -0,0 +1,374 @@
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use cgmath::{InnerSpace, Matrix, Matrix3, Matrix4, SquareMatrix, Vector3, Zero};
+use gltf::{
+    buffer,
+    image::{self, Format},
+    material::AlphaMode as GltfAlphaMode,
+    mesh::Mode,
+    texture, Document, Node,
+};
+use log::*;
+use wgpu::{Device, Queue, TextureFormat};
+
+use super::{
+    material::{AlphaMode, FaceMode, Material, MaterialProperties, MaterialTextures},
+    mesh::MeshData,
+    model_vertex::ModelVertex,
+};
+use crate::helium_texture::HeliumTexture;
+
+// Sharpest specular exponent a smooth gltf material is given
+const MAX_SPECULAR_EXPONENT: f32 = 1000.0;
+
+/// Reads the meshes of a .gltf or .glb file with the transforms of their nodes applied,
+/// the materials and their textures are only loaded when there is a device to load them
+/// onto
+///
+/// # Arguments
+///
+/// * `file_path` - Filepath of the gltf or glb file, the buffers and images it points to
+///   are read from next to it
+/// * `gpu` - The device and queue to create the materials with
+///
+/// # Returns
+///
+/// The meshes and materials or an error if the file could not be read
+pub(super) fn load_gltf<P>(
+    file_path: P,
+    gpu: Option<(&Device, &Queue)>,
+) -> Result<(Vec<MeshData>, Vec<Material>), Error>
+where
+    P: AsRef<Path>,
+{
+    info!("Loading glTF: {:?}", file_path.as_ref());
+    let (document, buffers, images) = gltf::import(file_path.as_ref()).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Could not load {:?}: {}", file_path.as_ref(), e),
+        )
+    })?;
+
+    let mut materials = match gpu {
+        Some((device, queue)) => load_materials(&document, &images, device, queue),
+        None => Vec::new(),
+    };
+
+    let mut meshes = Vec::new();
+    match document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    {
+        Some(scene) => {
+            for node in scene.nodes() {
+                push_node(&node, Matrix4::identity(), &buffers, &mut meshes);
+            }
+        }
+        // Files without a scene are libraries of meshes that are loaded where they are
+        None => {
+            for mesh in document.meshes() {
+                push_mesh(&mesh, Matrix4::identity(), &buffers, &mut meshes);
+            }
+        }
+    }
+
+    // Any primitive without a material uses the engine default material
+    if let Some((device, queue)) = gpu.filter(|_| meshes.iter().any(|mesh| mesh.material.is_none()))
+    {
+        let default_index = materials.len();
+        materials.push(Material::default_material(device, queue));
+
+        for mesh in meshes.iter_mut().filter(|mesh| mesh.material.is_none()) {
+            mesh.material = Some(default_index);
+        }
+    }
+
+    Ok((meshes, materials))
+}
+
+fn push_node(
+    node: &Node,
+    parent: Matrix4<f32>,
+    buffers: &[buffer::Data],
+    meshes: &mut Vec<MeshData>,
+) {
+    let transform = parent * Matrix4::from(node.transform().matrix());
+
+    if let Some(mesh) = node.mesh() {
+        push_mesh(&mesh, transform, buffers, meshes);
+    }
+
+    for child in node.children() {
+        push_node(&child, transform, buffers, meshes);
+    }
+}
+
+// Adds every primitive of a mesh as a mesh of its own so each keeps its material
+fn push_mesh(
+    mesh: &gltf::Mesh,
+    transform: Matrix4<f32>,
+    buffers: &[buffer::Data],
+    meshes: &mut Vec<MeshData>,
+) {
+    let linear = Matrix3::from_cols(
+        transform.x.truncate(),
+        transform.y.truncate(),
+        transform.z.truncate(),
+    );
+    let normal_matrix = linear
+        .invert()
+        .map(|inverse| inverse.transpose())
+        .unwrap_or(linear);
+    // Mirroring nodes turn the triangles inside out
+    let mirrored = linear.determinant() < 0.0;
+
+    let mesh_name = mesh
+        .name()
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("Mesh {}", mesh.index()));
+    let primitive_count = mesh.primitives().len();
+
+    for primitive in mesh.primitives() {
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data[..]));
+
+        let Some(positions) = reader.read_positions() else {
+            warn!("Skipping a primitive of {mesh_name} without positions");
+            continue;
+        };
+        let positions = positions
+            .map(|position| (transform * Vector3::from(position).extend(1.0)).truncate())
+            .collect::<Vec<_>>();
+
+        let indices = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect::<Vec<_>>(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        let mut indices = match primitive.mode() {
+            Mode::Triangles => indices,
+            // Every other triangle of a strip is wound the other way
+            Mode::TriangleStrip => (0..indices.len().saturating_sub(2))
+                .flat_map(|i| match i % 2 {
+                    0 => [indices[i], indices[i + 1], indices[i + 2]],
+                    _ => [indices[i + 1], indices[i], indices[i + 2]],
+                })
+                .collect(),
+            Mode::TriangleFan => (1..indices.len().saturating_sub(1))
+                .flat_map(|i| [indices[0], indices[i], indices[i + 1]])
+                .collect(),
+            mode => {
+                warn!("Skipping a {mode:?} primitive of {mesh_name}, only triangles are loaded");
+                continue;
+            }
+        };
+        if mirrored {
+            for triangle in indices.chunks_exact_mut(3) {
+                triangle.swap(1, 2);
+            }
+        }
+
+        let normals = match reader.read_normals() {
+            Some(normals) => normals
+                .map(|normal| (normal_matrix * Vector3::from(normal)).normalize())
+                .collect(),
+            None => smooth_normals(&positions, &indices),
+        };
+        // The obj loader mirrors u and flips v from the bottom left origin of obj files, gltf
+        // already starts at the top left so only u is mirrored to match it
+        let uv_coords = reader
+            .read_tex_coords(0)
+            .map(|uv_coords| {
+                uv_coords
+                    .into_f32()
+                    .map(|[u, v]| [1.0 - u, v])
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let colors = reader
+            .read_colors(0)
+            .map(|colors| colors.into_rgb_f32().collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        let vertices = positions
+            .iter()
+            .enumerate()
+            .map(|(index, position)| {
+                ModelVertex::new(
+                    *position,
+                    uv_coords.get(index).copied().unwrap_or([0.0, 0.0]),
+                    normals.get(index).copied().unwrap_or(Vector3::unit_y()),
+                )
+                .with_color(colors.get(index).copied().unwrap_or([1.0, 1.0, 1.0]))
+            })
+            .collect();
+
+        meshes.push(MeshData {
+            name: if primitive_count > 1 {
+                format!("{mesh_name} {}", primitive.index())
+            } else {
+                mesh_name.clone()
+            },
+            vertices,
+            indices,
+            material: primitive.material().index(),
+        });
+    }
+}
+
+// The normals of a primitive without them, averaged from the triangles around each vertex
+fn smooth_normals(positions: &[Vector3<f32>], indices: &[u32]) -> Vec<Vector3<f32>> {
+    let mut normals = vec![Vector3::zero(); positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|corner| triangle[corner] as usize);
+        let (Some(pa), Some(pb), Some(pc)) = (positions.get(a), positions.get(b), positions.get(c))
+        else {
+            continue;
+        };
+
+        // Larger triangles count for more since the cross product is not normalized
+        let normal = (pb - pa).cross(pc - pa);
+        for corner in [a, b, c] {
+            normals[corner] += normal;
+        }
+    }
+
+    normals
+        .into_iter()
+        .map(|normal| {
+            if normal.magnitude2() > 0.0 {
+                normal.normalize()
+            } else {
+                Vector3::unit_y()
+            }
+        })
+        .collect()
+}
+
+// Creates the materials of the file in order so the primitives can use their indices
+fn load_materials(
+    document: &Document,
+    images: &[image::Data],
+    device: &Device,
+    queue: &Queue,
+) -> Vec<Material> {
+    let texture = |info: Option<texture::Texture>, format: TextureFormat| {
+        let image = images.get(info?.source().index())?;
+        Some(HeliumTexture::from_rgba_with_format(
+            device,
+            queue,
+            &to_rgba8(image),
+            (image.width, image.height),
+            format,
+        ))
+    };
+
+    document
+        .materials()
+        .map(|material| {
+            let name = material
+                .name()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("Material {}", material.index().unwrap_or_default()));
+            let pbr = material.pbr_metallic_roughness();
+            let [r, g, b, alpha] = pbr.base_color_factor();
+
+            if pbr.metallic_roughness_texture().is_some() || material.occlusion_texture().is_some()
+            {
+                info!("The metallic roughness and occlusion maps of {name} are not used");
+            }
+
+            // The blinn phong highlight that is about as wide as the roughness
+            let alpha_roughness = (pbr.roughness_factor() * pbr.roughness_factor()).max(1e-3);
+            let specular_exponent =
+                (2.0 / (alpha_roughness * alpha_roughness) - 2.0).clamp(1.0, MAX_SPECULAR_EXPONENT);
+
+            let properties = MaterialProperties {
+                diffuse_color: [r, g, b],
+                emissive_color: material.emissive_factor(),
+                emissive_intensity: material.emissive_strength().unwrap_or(1.0),
+                specular_exponent,
+                dissolve: match material.alpha_mode() {
+                    GltfAlphaMode::Opaque => 1.0,
+                    GltfAlphaMode::Mask | GltfAlphaMode::Blend => alpha,
+                },
+                alpha_mode: match material.alpha_mode() {
+                    GltfAlphaMode::Opaque => AlphaMode::Opaque,
+                    GltfAlphaMode::Mask => AlphaMode::Mask,
+                    GltfAlphaMode::Blend => AlphaMode::Blend,
+                },
+                alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+                illumination_model: if material.unlit() { 0 } else { 2 },
+                metallic: pbr.metallic_factor(),
+                roughness: pbr.roughness_factor(),
+                faces: FaceMode {
+                    double_sided: material.double_sided(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+
+            let textures = MaterialTextures {
+                diffuse: texture(
+                    pbr.base_color_texture().map(|info| info.texture()),
+                    TextureFormat::Rgba8UnormSrgb,
+                ),
+                normal: texture(
+                    material.normal_texture().map(|info| info.texture()),
+                    TextureFormat::Rgba8Unorm,
+                ),
+                emissive: texture(
+                    material.emissive_texture().map(|info| info.texture()),
+                    TextureFormat::Rgba8UnormSrgb,
+                ),
+                ..Default::default()
+            };
+
+            Material::new(name, properties, textures, device, queue)
+        })
+        .collect()
+}
+
+// Converts the pixels of an image to 8 bit RGBA, images with one or two channels are gray
+// with an alpha
+fn to_rgba8(image: &image::Data) -> Vec<u8> {
+    let (channels, channel_size) = match image.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 => (3, 1),
+        Format::R8G8B8A8 => (4, 1),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+        Format::R32G32B32FLOAT => (3, 4),
+        Format::R32G32B32A32FLOAT => (4, 4),
+    };
+
+    image
+        .pixels
+        .chunks_exact(channels * channel_size)
+        .flat_map(|pixel| {
+            let channel = |index: usize| {
+                let bytes = &pixel[index * channel_size..(index + 1) * channel_size];
+                // The channels are in the byte order of the machine
+                match *bytes {
+                    [value] => value,
+                    [a, b] => (u16::from_ne_bytes([a, b]) >> 8) as u8,
+                    [a, b, c, d] => {
+                        (f32::from_ne_bytes([a, b, c, d]).clamp(0.0, 1.0) * 255.0).round() as u8
+                    }
+                    _ => 0,
+                }
+            };
+
+            match channels {
+                1 => [channel(0), channel(0), channel(0), 255],
+                2 => [channel(0), channel(0), channel(0), channel(1)],
+                3 => [channel(0), channel(1), channel(2), 255],
+                _ => [channel(0), channel(1), channel(2), channel(3)],
+            }
+        })
+        .collect()
+}
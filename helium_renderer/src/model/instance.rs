@@ -1,12 +1,30 @@
-use cgmath::{Matrix3, Matrix4, One, Quaternion, Vector3};
+use cgmath::{Matrix3, Matrix4, One, Quaternion, SquareMatrix, Vector3};
 use wgpu::{BufferAddress, VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode};
 
-use super::vertex::Vertex;
+use crate::{bounds::BoundingSphere, color::LinearRgba};
 
-#[derive(Debug)]
+use super::{morph::MAX_MORPH_TARGETS, vertex::Vertex};
+
+pub const DEFAULT_INSTANCE_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+#[derive(Debug, Clone, Copy)]
 pub struct Instance {
     pub position: Vector3<f32>,
     pub rotation: Quaternion<f32>,
+    /// Size along each axis of the object before it is rotated
+    pub scale: Vector3<f32>,
+    /// RGBA tint multiplied into the material color
+    pub color: [f32; 4],
+    /// Extra data passed to the shaders for custom effects
+    pub custom_data: [f32; 4],
+    /// Layer of the texture array of a layered material
+    pub layer: u32,
+    /// Scales the emissive color of the material, used to make lights and screens pulse
+    pub emission: f32,
+    /// Hidden instances are scaled to nothing so they keep their place in the buffer
+    pub visible: bool,
+    /// How far the mesh is blended towards each of its morph targets, 0 is the base shape
+    pub morph_weights: [f32; MAX_MORPH_TARGETS],
 }
 
 impl Default for Instance {
@@ -18,6 +36,13 @@ impl Default for Instance {
                 z: 0.0,
             },
             rotation: Quaternion::one(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+            color: DEFAULT_INSTANCE_COLOR,
+            custom_data: [0.0; 4],
+            layer: 0,
+            emission: 1.0,
+            visible: true,
+            morph_weights: [0.0; MAX_MORPH_TARGETS],
         }
     }
 }
@@ -29,20 +54,137 @@ pub const INSTANCE_RAW_SIZE: usize = std::mem::size_of::<InstanceRaw>();
 pub struct InstanceRaw {
     model: [[f32; 4]; 4],
     normal: [[f32; 3]; 3],
+    color: [f32; 4],
+    custom_data: [f32; 4],
+    layer: u32,
+    emission: f32,
 }
 
 #[allow(unused)]
 impl Instance {
     pub fn new(position: Vector3<f32>, rotation: Quaternion<f32>) -> Self {
-        Self { position, rotation }
+        Self {
+            position,
+            rotation,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_scale(mut self, scale: Vector3<f32>) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_color<C>(mut self, color: C) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        self.color = color.into().to_array();
+        self
+    }
+
+    pub fn with_custom_data(mut self, custom_data: [f32; 4]) -> Self {
+        self.custom_data = custom_data;
+        self
+    }
+
+    pub fn with_layer(mut self, layer: u32) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    pub fn with_emission(mut self, emission: f32) -> Self {
+        self.emission = emission;
+        self
+    }
+
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    pub fn with_morph_weights(mut self, weights: &[f32]) -> Self {
+        self.set_morph_weights(weights);
+        self
+    }
+
+    pub fn set_scale(&mut self, scale: Vector3<f32>) -> &mut Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn set_color<C>(&mut self, color: C) -> &mut Self
+    where
+        C: Into<LinearRgba>,
+    {
+        self.color = color.into().to_array();
+        self
+    }
+
+    pub fn set_custom_data(&mut self, custom_data: [f32; 4]) -> &mut Self {
+        self.custom_data = custom_data;
+        self
+    }
+
+    pub fn set_layer(&mut self, layer: u32) -> &mut Self {
+        self.layer = layer;
+        self
+    }
+
+    pub fn set_emission(&mut self, emission: f32) -> &mut Self {
+        self.emission = emission;
+        self
+    }
+
+    pub fn set_visible(&mut self, visible: bool) -> &mut Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Sets the weights of the morph targets in the order they were added to the mesh,
+    /// targets without a weight are set to 0
+    pub fn set_morph_weights(&mut self, weights: &[f32]) -> &mut Self {
+        self.morph_weights = [0.0; MAX_MORPH_TARGETS];
+        for (weight, new_weight) in self.morph_weights.iter_mut().zip(weights) {
+            *weight = *new_weight;
+        }
+        self
+    }
+
+    /// The sphere around a sphere of the object after the instance moves it
+    pub fn transform_sphere(&self, sphere: &BoundingSphere) -> BoundingSphere {
+        sphere
+            .scaled(self.scale)
+            .transformed(self.position, self.rotation)
     }
 
     pub fn to_raw(&self) -> InstanceRaw {
-        let model =
-            (Matrix4::from_translation(self.position) * Matrix4::from(self.rotation)).into();
+        let scale = if self.visible {
+            self.scale
+        } else {
+            Vector3::new(0.0, 0.0, 0.0)
+        };
+        let model = (Matrix4::from_translation(self.position)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(scale.x, scale.y, scale.z))
+        .into();
+
+        // Normals are scaled by the inverse of the scale so they stay perpendicular to the
+        // stretched surface, a scale of zero has no inverse and keeps the rotation alone
+        let rotation = Matrix3::from(self.rotation);
+        let normal = if scale.x * scale.y * scale.z != 0.0 {
+            rotation * Matrix3::from_diagonal(scale.map(|s| 1.0 / s))
+        } else {
+            rotation
+        };
+
         InstanceRaw {
             model,
-            normal: Matrix3::from(self.rotation).into(),
+            normal: normal.into(),
+            color: self.color,
+            custom_data: self.custom_data,
+            layer: self.layer,
+            emission: self.emission,
         }
     }
 }
@@ -92,6 +234,30 @@ impl Vertex for InstanceRaw {
                     shader_location: 11,
                     format: VertexFormat::Float32x3,
                 },
+                // Instance color
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 25]>() as BufferAddress,
+                    shader_location: 12,
+                    format: VertexFormat::Float32x4,
+                },
+                // Instance custom data
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 29]>() as BufferAddress,
+                    shader_location: 13,
+                    format: VertexFormat::Float32x4,
+                },
+                // Instance texture layer
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 33]>() as BufferAddress,
+                    shader_location: 14,
+                    format: VertexFormat::Uint32,
+                },
+                // Instance emission
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 34]>() as BufferAddress,
+                    shader_location: 15,
+                    format: VertexFormat::Float32,
+                },
             ],
         }
     }
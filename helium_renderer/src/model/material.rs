@@ -1,22 +1,900 @@
 use helium_io::read_lines;
 use log::*;
-use std::{fs, io, path::Path};
-use wgpu::{Device, Queue};
+use std::{
+    cmp::Ordering,
+    fs,
+    hash::{Hash, Hasher},
+    io,
+    path::Path,
+};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBindingType, BufferUsages,
+    DepthBiasState, Device, Face, FrontFace, Queue, SamplerBindingType, ShaderStages,
+    TextureFormat, TextureSampleType, TextureViewDimension,
+};
 
-use crate::helium_texture::HeliumTexture;
+use crate::{
+    color::{audit_texture, LinearRgba},
+    helium_texture::HeliumTexture,
+    profile_span,
+};
+
+// Name given to the material used by meshes that don't specify one
+pub const DEFAULT_MATERIAL_NAME: &str = "Helium Default Material";
+
+// Color of the default material texture
+const DEFAULT_MATERIAL_COLOR: [u8; 4] = [255, 255, 255, 255];
+
+// Color of the normal map used when a material does not have one
+const FLAT_NORMAL_COLOR: [u8; 4] = [128, 128, 255, 255];
+
+// Color of the emissive map used when a material does not have one, the emissive color
+// is used as it is
+const DEFAULT_EMISSIVE_COLOR: [u8; 4] = [255, 255, 255, 255];
+
+// Weights of the splat map used when a material does not have one
+const DEFAULT_SPLAT_COLOR: [u8; 4] = [255, 0, 0, 0];
+
+// Flags for the material uniform to let the shader know what maps are present
+const MATERIAL_FLAG_NORMAL_MAP: u32 = 1;
+const MATERIAL_FLAG_LAYERS: u32 = 2;
+const MATERIAL_FLAG_SPLAT_MAP: u32 = 4;
+
+// Binding layout of the material
+// 0: diffuse texture (map_Kd)
+// 1: sampler shared by all the material textures
+// 2: material properties uniform
+// 3: specular texture (map_Ks)
+// 4: normal texture (map_Bump)
+// 5: dissolve texture (map_d)
+// 6: diffuse texture array (map_Kd_layers)
+// 7: splat map that blends the first four layers (map_splat)
+// 8: emissive texture (map_Ke)
+const fn material_texture_entry(binding: u32) -> BindGroupLayoutEntry {
+    material_texture_entry_with_dimension(binding, TextureViewDimension::D2)
+}
+
+const fn material_texture_entry_with_dimension(
+    binding: u32,
+    view_dimension: TextureViewDimension,
+) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::FRAGMENT,
+        ty: BindingType::Texture {
+            multisampled: false,
+            view_dimension,
+            sample_type: TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    }
+}
+
+const MATERIAL_BIND_GROUP_LAYOUT_DESCRIPTOR: BindGroupLayoutDescriptor =
+    BindGroupLayoutDescriptor {
+        label: Some("Material bind group layout"),
+        entries: &[
+            material_texture_entry(0),
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 2,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            material_texture_entry(3),
+            material_texture_entry(4),
+            material_texture_entry(5),
+            material_texture_entry_with_dimension(6, TextureViewDimension::D2Array),
+            material_texture_entry(7),
+            material_texture_entry(8),
+        ],
+    };
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct MaterialUniform {
+    ambient_color: [f32; 4],
+    diffuse_color: [f32; 4],
+    // The w component is the specular exponent
+    specular_color: [f32; 4],
+    // The w component is the emissive intensity
+    emissive_color: [f32; 4],
+    dissolve: f32,
+    illumination_model: u32,
+    flags: u32,
+    metallic: f32,
+    roughness: f32,
+    layer_count: u32,
+    // Fragments with less alpha are discarded, zero when the material is not masked
+    alpha_cutoff: f32,
+    _padding: u32,
+    // xy is how far the texture coordinates scroll every second and z how fast they turn
+    uv_animation: [f32; 4],
+    // The columns, rows, frames, and frames per second of the flipbook, no frames turns it off
+    flipbook: [f32; 4],
+}
+
+/// The order the corners of the front of a triangle are in when looking at it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Winding {
+    #[default]
+    CounterClockwise,
+    Clockwise,
+}
+
+/// Which faces of the triangles of a material are drawn, every combination is drawn with a
+/// pipeline of its own
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FaceMode {
+    /// Draws the back faces too, used for foliage and planes seen from both sides
+    pub double_sided: bool,
+    pub front_face: Winding,
+}
+
+impl FaceMode {
+    pub fn cull_mode(&self) -> Option<Face> {
+        (!self.double_sided).then_some(Face::Back)
+    }
+
+    pub fn front_face(&self) -> FrontFace {
+        match self.front_face {
+            Winding::CounterClockwise => FrontFace::Ccw,
+            Winding::Clockwise => FrontFace::Cw,
+        }
+    }
+}
+
+/// Moves the depth of a material so it wins or loses the depth test against surfaces it
+/// lies on. Negative values move it towards the camera, used for decals and coplanar
+/// overlays, and positive values move it away
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DepthBias {
+    /// Added to the depth in the smallest steps the depth buffer can hold
+    pub constant: i32,
+    /// Multiplies the slope of the triangle, surfaces seen at a steep angle are moved more
+    pub slope_scale: f32,
+    /// The largest change of the depth, 0 does not limit it
+    pub clamp: f32,
+}
+
+impl DepthBias {
+    pub fn new(constant: i32, slope_scale: f32, clamp: f32) -> Self {
+        Self {
+            constant,
+            slope_scale,
+            clamp,
+        }
+    }
+
+    // The bias as bits so it can key the pipeline variants
+    fn key(&self) -> (i32, u32, u32) {
+        (
+            self.constant,
+            self.slope_scale.to_bits(),
+            self.clamp.to_bits(),
+        )
+    }
+}
+
+impl PartialEq for DepthBias {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for DepthBias {}
+
+impl Hash for DepthBias {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl PartialOrd for DepthBias {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DepthBias {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl From<DepthBias> for DepthBiasState {
+    fn from(value: DepthBias) -> Self {
+        Self {
+            constant: value.constant,
+            slope_scale: value.slope_scale,
+            clamp: value.clamp,
+        }
+    }
+}
+
+/// How the alpha of a material covers the surfaces behind it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AlphaMode {
+    /// Covers everything behind it, the alpha is ignored
+    #[default]
+    Opaque,
+    /// Blended over the surfaces behind it, drawn after the opaque materials from the
+    /// farthest to the nearest without writing depth
+    Blend,
+    /// Cut out where the alpha is below the alpha cutoff and opaque everywhere else, like
+    /// the leaves of foliage
+    Mask,
+}
+
+/// Plays the cells of a sprite sheet texture one after another, the cells are read left
+/// to right and then top to bottom
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Flipbook {
+    pub columns: u32,
+    pub rows: u32,
+    /// How many cells are frames, a sheet with an unfilled last row has less than all of them
+    pub frames: u32,
+    pub frames_per_second: f32,
+}
+
+impl Flipbook {
+    /// Creates a flipbook that plays every cell of the sheet
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - The number of cells across the texture
+    /// * `rows` - The number of cells down the texture
+    /// * `frames_per_second` - How fast the frames change
+    pub fn new(columns: u32, rows: u32, frames_per_second: f32) -> Self {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        Self {
+            columns,
+            rows,
+            frames: columns * rows,
+            frames_per_second,
+        }
+    }
+
+    /// Plays only the first frames of the sheet
+    pub fn with_frames(mut self, frames: u32) -> Self {
+        self.frames = frames.clamp(1, self.columns * self.rows);
+        self
+    }
+}
+
+/// Moves the texture coordinates of a material over time for cheap animated surfaces like
+/// conveyor belts, flowing lava, and screens. The time is shared by every material
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextureAnimation {
+    /// How far the texture coordinates move every second
+    pub scroll: [f32; 2],
+    /// How fast the texture coordinates turn about the center of the texture in radians
+    /// per second
+    pub rotation: f32,
+    pub flipbook: Option<Flipbook>,
+}
+
+impl TextureAnimation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_scroll(mut self, u: f32, v: f32) -> Self {
+        self.scroll = [u, v];
+        self
+    }
+
+    pub fn with_rotation(mut self, radians_per_second: f32) -> Self {
+        self.rotation = radians_per_second;
+        self
+    }
+
+    pub fn with_flipbook(mut self, flipbook: Flipbook) -> Self {
+        self.flipbook = Some(flipbook);
+        self
+    }
+}
+
+/// The material properties described by an mtl file
+#[derive(Clone, Debug)]
+pub struct MaterialProperties {
+    /// Ka
+    pub ambient_color: [f32; 3],
+    /// Kd
+    pub diffuse_color: [f32; 3],
+    /// Ks
+    pub specular_color: [f32; 3],
+    /// Ke
+    pub emissive_color: [f32; 3],
+    /// Ke_intensity, multiplies the emissive color so it can glow brighter than white
+    /// in the hdr scene and be picked up by the bloom
+    pub emissive_intensity: f32,
+    /// Ns
+    pub specular_exponent: f32,
+    /// d (or 1 - Tr)
+    pub dissolve: f32,
+    /// alpha_mode, materials with a dissolve below 1 or a dissolve texture are blended
+    pub alpha_mode: AlphaMode,
+    /// alpha_cutoff, the alpha below which a masked material is cut out
+    pub alpha_cutoff: f32,
+    /// illum
+    pub illumination_model: u32,
+    /// Pm, how much the material reflects the environment like a metal
+    pub metallic: f32,
+    /// Pr, how blurry the reflections of the environment are
+    pub roughness: f32,
+    /// double_sided and front_face, which faces of the triangles are drawn
+    pub faces: FaceMode,
+    /// depth_bias, moves the depth of the material so it is drawn over coplanar surfaces
+    pub depth_bias: DepthBias,
+    /// uv_scroll, uv_rotate, and flipbook, moves the texture coordinates over time
+    pub texture_animation: TextureAnimation,
+}
+
+impl Default for MaterialProperties {
+    fn default() -> Self {
+        Self {
+            ambient_color: [1.0, 1.0, 1.0],
+            diffuse_color: [1.0, 1.0, 1.0],
+            specular_color: [1.0, 1.0, 1.0],
+            emissive_color: [0.0, 0.0, 0.0],
+            emissive_intensity: 1.0,
+            specular_exponent: 1000.0,
+            dissolve: 1.0,
+            alpha_mode: AlphaMode::Opaque,
+            alpha_cutoff: 0.5,
+            illumination_model: 2,
+            metallic: 0.0,
+            roughness: 1.0,
+            faces: FaceMode::default(),
+            depth_bias: DepthBias::default(),
+            texture_animation: TextureAnimation::default(),
+        }
+    }
+}
+
+impl MaterialProperties {
+    fn to_uniform(&self, flags: u32, layer_count: u32) -> MaterialUniform {
+        let [ar, ag, ab] = self.ambient_color;
+        let [dr, dg, db] = self.diffuse_color;
+        let [sr, sg, sb] = self.specular_color;
+        let [er, eg, eb] = self.emissive_color;
+        let [scroll_u, scroll_v] = self.texture_animation.scroll;
+        let flipbook = self
+            .texture_animation
+            .flipbook
+            .map_or([0.0; 4], |flipbook| {
+                [
+                    flipbook.columns as f32,
+                    flipbook.rows as f32,
+                    flipbook.frames as f32,
+                    flipbook.frames_per_second,
+                ]
+            });
+
+        MaterialUniform {
+            ambient_color: [ar, ag, ab, 1.0],
+            diffuse_color: [dr, dg, db, 1.0],
+            specular_color: [sr, sg, sb, self.specular_exponent],
+            emissive_color: [er, eg, eb, self.emissive_intensity],
+            dissolve: self.dissolve,
+            illumination_model: self.illumination_model,
+            flags,
+            metallic: self.metallic,
+            roughness: self.roughness,
+            layer_count,
+            alpha_cutoff: if self.alpha_mode == AlphaMode::Mask {
+                self.alpha_cutoff
+            } else {
+                0.0
+            },
+            _padding: 0,
+            uv_animation: [scroll_u, scroll_v, self.texture_animation.rotation, 0.0],
+            flipbook,
+        }
+    }
+}
+
+/// A flat colored material that does not need any textures
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorMaterial {
+    /// The RGBA color of the material, the alpha is used for transparency
+    pub albedo: [f32; 4],
+    /// Unlit materials ignore the lights in the scene and are drawn with their albedo color
+    pub unlit: bool,
+    /// The color the material glows with, it is added on top of the lighting
+    pub emissive: [f32; 3],
+    /// Multiplies the emissive color, values above 1 make the material bloom
+    pub emissive_intensity: f32,
+    /// Draws the back faces of the triangles too
+    pub double_sided: bool,
+    /// Moves the depth of the material so it is drawn over the surfaces it lies on
+    pub depth_bias: DepthBias,
+}
+
+impl Default for ColorMaterial {
+    fn default() -> Self {
+        Self {
+            albedo: [1.0, 1.0, 1.0, 1.0],
+            unlit: false,
+            emissive: [0.0, 0.0, 0.0],
+            emissive_intensity: 1.0,
+            double_sided: false,
+            depth_bias: DepthBias::default(),
+        }
+    }
+}
+
+impl ColorMaterial {
+    pub fn new<C>(albedo: C) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        Self {
+            albedo: albedo.into().to_array(),
+            ..Default::default()
+        }
+    }
+
+    pub fn unlit<C>(albedo: C) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        Self {
+            albedo: albedo.into().to_array(),
+            unlit: true,
+            ..Default::default()
+        }
+    }
+
+    /// Makes the material glow
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The color of the glow
+    /// * `intensity` - Multiplies the color, values above 1 make the material bloom
+    pub fn with_emissive<C>(mut self, color: C, intensity: f32) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        let color = color.into();
+        self.emissive = [color.r, color.g, color.b];
+        self.emissive_intensity = intensity;
+        self
+    }
+
+    /// Draws both sides of the triangles, used for planes and foliage
+    pub fn with_double_sided(mut self, double_sided: bool) -> Self {
+        self.double_sided = double_sided;
+        self
+    }
+
+    /// Moves the depth of the material, negative values draw it over the surfaces it lies
+    /// on like decals and overlays
+    pub fn with_depth_bias(mut self, depth_bias: DepthBias) -> Self {
+        self.depth_bias = depth_bias;
+        self
+    }
+}
+
+impl From<ColorMaterial> for MaterialProperties {
+    fn from(value: ColorMaterial) -> Self {
+        let [r, g, b, a] = value.albedo;
+        Self {
+            diffuse_color: [r, g, b],
+            emissive_color: value.emissive,
+            emissive_intensity: value.emissive_intensity,
+            dissolve: a,
+            alpha_mode: if a < 1.0 {
+                AlphaMode::Blend
+            } else {
+                AlphaMode::Opaque
+            },
+            // Illumination model 0 is drawn without any lighting
+            illumination_model: if value.unlit { 0 } else { 2 },
+            faces: FaceMode {
+                double_sided: value.double_sided,
+                ..Default::default()
+            },
+            depth_bias: value.depth_bias,
+            ..Default::default()
+        }
+    }
+}
 
 pub struct Material {
     name: String,
-    diffuse_texture: Option<HeliumTexture>,
+    properties: MaterialProperties,
+    diffuse_texture: HeliumTexture,
+    specular_texture: HeliumTexture,
+    normal_texture: HeliumTexture,
+    dissolve_texture: HeliumTexture,
+    layer_texture: HeliumTexture,
+    splat_texture: HeliumTexture,
+    emissive_texture: HeliumTexture,
+    flags: u32,
+    buffer: Buffer,
+    bind_group: BindGroup,
 }
 
 impl Material {
+    pub fn get_layout(device: &Device) -> BindGroupLayout {
+        device.create_bind_group_layout(&MATERIAL_BIND_GROUP_LAYOUT_DESCRIPTOR)
+    }
+
+    /// Creates the engine default material that is used for meshes without a material
+    pub fn default_material(device: &Device, queue: &Queue) -> Self {
+        Self::new(
+            DEFAULT_MATERIAL_NAME.to_string(),
+            MaterialProperties::default(),
+            MaterialTextures::default(),
+            device,
+            queue,
+        )
+    }
+
+    /// Creates a material from a flat color without any textures
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the material
+    /// * `color_material` - The color and lighting of the material
+    /// * `device` - The device to create the material on
+    /// * `queue` - The queue to write the material data with
+    pub fn from_color(
+        name: String,
+        color_material: ColorMaterial,
+        device: &Device,
+        queue: &Queue,
+    ) -> Self {
+        Self::new(
+            name,
+            color_material.into(),
+            MaterialTextures::default(),
+            device,
+            queue,
+        )
+    }
+
+    pub(super) fn new(
+        name: String,
+        properties: MaterialProperties,
+        textures: MaterialTextures,
+        device: &Device,
+        queue: &Queue,
+    ) -> Self {
+        let mut flags = 0;
+        if textures.normal.is_some() {
+            flags |= MATERIAL_FLAG_NORMAL_MAP;
+        }
+        if textures.layers.is_some() {
+            flags |= MATERIAL_FLAG_LAYERS;
+        }
+        if textures.splat.is_some() {
+            flags |= MATERIAL_FLAG_SPLAT_MAP;
+        }
+
+        let white = || HeliumTexture::from_color(device, queue, DEFAULT_MATERIAL_COLOR);
+        let diffuse_texture = textures.diffuse.unwrap_or_else(white);
+        let specular_texture = textures.specular.unwrap_or_else(white);
+        // Data textures are linear so the defaults are too
+        let linear = |color: &[u8; 4]| {
+            HeliumTexture::from_rgba_with_format(
+                device,
+                queue,
+                color,
+                (1, 1),
+                TextureFormat::Rgba8Unorm,
+            )
+        };
+        let dissolve_texture = textures
+            .dissolve
+            .unwrap_or_else(|| linear(&DEFAULT_MATERIAL_COLOR));
+        let normal_texture = textures
+            .normal
+            .unwrap_or_else(|| linear(&FLAT_NORMAL_COLOR));
+        let layer_texture = textures.layers.unwrap_or_else(|| {
+            HeliumTexture::array_from_rgba_with_format(
+                device,
+                queue,
+                &[&DEFAULT_MATERIAL_COLOR],
+                (1, 1),
+                TextureFormat::Rgba8UnormSrgb,
+            )
+        });
+        let splat_texture = textures
+            .splat
+            .unwrap_or_else(|| linear(&DEFAULT_SPLAT_COLOR));
+        let emissive_texture = textures
+            .emissive
+            .unwrap_or_else(|| HeliumTexture::from_color(device, queue, DEFAULT_EMISSIVE_COLOR));
+
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some(&(name.clone() + " Material Buffer")),
+            contents: bytemuck::cast_slice(&[
+                properties.to_uniform(flags, layer_texture.get_layer_count())
+            ]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some(&(name.clone() + " Material Bind Group")),
+            layout: &Self::get_layout(device),
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(diffuse_texture.get_view()),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(diffuse_texture.get_sampler()),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::TextureView(specular_texture.get_view()),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: BindingResource::TextureView(normal_texture.get_view()),
+                },
+                BindGroupEntry {
+                    binding: 5,
+                    resource: BindingResource::TextureView(dissolve_texture.get_view()),
+                },
+                BindGroupEntry {
+                    binding: 6,
+                    resource: BindingResource::TextureView(layer_texture.get_view()),
+                },
+                BindGroupEntry {
+                    binding: 7,
+                    resource: BindingResource::TextureView(splat_texture.get_view()),
+                },
+                BindGroupEntry {
+                    binding: 8,
+                    resource: BindingResource::TextureView(emissive_texture.get_view()),
+                },
+            ],
+        });
+
+        Self {
+            name,
+            properties,
+            diffuse_texture,
+            specular_texture,
+            normal_texture,
+            dissolve_texture,
+            layer_texture,
+            splat_texture,
+            emissive_texture,
+            flags,
+            buffer,
+            bind_group,
+        }
+    }
+
     pub fn get_name(&self) -> &String {
         &self.name
     }
 
-    pub fn get_diffuse_texture(&self) -> Option<&HeliumTexture> {
-        self.diffuse_texture.as_ref()
+    pub fn get_properties(&self) -> &MaterialProperties {
+        &self.properties
+    }
+
+    /// Which faces of the triangles are drawn, it picks the pipeline the material is drawn with
+    pub fn get_faces(&self) -> FaceMode {
+        self.properties.faces
+    }
+
+    pub fn get_depth_bias(&self) -> DepthBias {
+        self.properties.depth_bias
+    }
+
+    pub fn get_alpha_mode(&self) -> AlphaMode {
+        self.properties.alpha_mode
+    }
+
+    /// Changes how the texture coordinates of the material move over time
+    pub fn set_texture_animation(&mut self, texture_animation: TextureAnimation, queue: &Queue) {
+        let properties = MaterialProperties {
+            texture_animation,
+            ..self.properties.clone()
+        };
+        self.set_properties(properties, queue);
+    }
+
+    /// Changes the properties of the material and uploads them to the gpu
+    pub fn set_properties(&mut self, properties: MaterialProperties, queue: &Queue) {
+        self.properties = properties;
+        queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::cast_slice(&[self
+                .properties
+                .to_uniform(self.flags, self.layer_texture.get_layer_count())]),
+        );
+    }
+
+    pub fn get_bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+
+    pub fn get_diffuse_texture(&self) -> &HeliumTexture {
+        &self.diffuse_texture
+    }
+
+    pub fn get_specular_texture(&self) -> &HeliumTexture {
+        &self.specular_texture
+    }
+
+    pub fn get_normal_texture(&self) -> &HeliumTexture {
+        &self.normal_texture
+    }
+
+    pub fn get_dissolve_texture(&self) -> &HeliumTexture {
+        &self.dissolve_texture
+    }
+
+    pub fn get_layer_texture(&self) -> &HeliumTexture {
+        &self.layer_texture
+    }
+
+    pub fn get_splat_texture(&self) -> &HeliumTexture {
+        &self.splat_texture
+    }
+
+    pub fn get_emissive_texture(&self) -> &HeliumTexture {
+        &self.emissive_texture
+    }
+
+    /// Finds the textures of the material that are in the wrong color space
+    pub fn audit_color_spaces(&self) -> Vec<String> {
+        [
+            ("diffuse", &self.diffuse_texture, true),
+            ("specular", &self.specular_texture, true),
+            ("layers", &self.layer_texture, true),
+            ("emissive", &self.emissive_texture, true),
+            ("normal", &self.normal_texture, false),
+            ("dissolve", &self.dissolve_texture, false),
+            ("splat", &self.splat_texture, false),
+        ]
+        .into_iter()
+        .filter_map(|(name, texture, holds_color)| {
+            audit_texture(
+                &format!("The {name} texture of {}", self.name),
+                texture.get_format(),
+                holds_color,
+            )
+        })
+        .collect()
+    }
+}
+
+// Textures read from an mtl or gltf file before the material is built
+#[derive(Default)]
+pub(super) struct MaterialTextures {
+    pub diffuse: Option<HeliumTexture>,
+    pub specular: Option<HeliumTexture>,
+    pub normal: Option<HeliumTexture>,
+    pub dissolve: Option<HeliumTexture>,
+    pub layers: Option<HeliumTexture>,
+    pub splat: Option<HeliumTexture>,
+    pub emissive: Option<HeliumTexture>,
+}
+
+fn parse_color(line_split: &[&str]) -> Option<[f32; 3]> {
+    let r = line_split.get(1)?.parse::<f32>().ok()?;
+    // A single value means all the channels are the same
+    let g = line_split
+        .get(2)
+        .and_then(|g| g.parse::<f32>().ok())
+        .unwrap_or(r);
+    let b = line_split
+        .get(3)
+        .and_then(|b| b.parse::<f32>().ok())
+        .unwrap_or(r);
+
+    Some([r, g, b])
+}
+
+fn parse_scalar(line_split: &[&str]) -> Option<f32> {
+    line_split.get(1)?.parse::<f32>().ok()
+}
+
+fn load_texture<P>(
+    file_path: P,
+    line_split: &[&str],
+    format: TextureFormat,
+    device: &Device,
+    queue: &Queue,
+) -> Option<HeliumTexture>
+where
+    P: AsRef<Path>,
+{
+    let _span = profile_span("load_texture");
+    // Texture maps can have options before the file name so the file is the last argument
+    let new_path = file_path
+        .as_ref()
+        .parent()
+        .unwrap()
+        .join(line_split.last()?);
+    info!("Texture Path: {:?}", new_path);
+
+    let file_contents = match fs::read(&new_path) {
+        Ok(file_contents) => file_contents,
+        Err(e) => {
+            warn!("Could not read texture {:?}: {}", new_path, e);
+            return None;
+        }
+    };
+
+    match HeliumTexture::from_bytes_with_format(device, queue, &file_contents, format) {
+        Ok(texture) => Some(texture),
+        Err(e) => {
+            warn!("Could not decode texture {:?}: {}", new_path, e);
+            None
+        }
+    }
+}
+
+// Loads every file after the statement as a layer of a texture array
+fn load_texture_array<P>(
+    file_path: P,
+    line_split: &[&str],
+    device: &Device,
+    queue: &Queue,
+) -> Option<HeliumTexture>
+where
+    P: AsRef<Path>,
+{
+    let _span = profile_span("load_texture");
+    let directory = file_path.as_ref().parent().unwrap();
+
+    let mut layers = Vec::new();
+    for layer in line_split.iter().skip(1) {
+        let new_path = directory.join(layer);
+        info!("Texture Layer Path: {:?}", new_path);
+
+        match fs::read(&new_path) {
+            Ok(file_contents) => layers.push(file_contents),
+            Err(e) => {
+                warn!("Could not read texture layer {:?}: {}", new_path, e);
+                return None;
+            }
+        }
+    }
+
+    let layers = layers.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    match HeliumTexture::array_from_bytes_with_format(
+        device,
+        queue,
+        &layers,
+        TextureFormat::Rgba8UnormSrgb,
+    ) {
+        Ok(texture) => Some(texture),
+        Err(e) => {
+            warn!(
+                "Could not create texture array from {:?}: {}",
+                &line_split[1..],
+                e
+            );
+            None
+        }
     }
 }
 
@@ -31,7 +909,7 @@ where
     info!("Loading Material: {:?}", file_path.as_ref());
     let lines = read_lines(file_path.as_ref())?;
 
-    let mut current_material: Option<String> = None;
+    let mut current_material: Option<(String, MaterialProperties, MaterialTextures)> = None;
     let mut materials: Vec<Material> = Vec::new();
     for line in lines.map_while(Result::ok) {
         let line_split = line.split_whitespace().collect::<Vec<_>>();
@@ -39,24 +917,234 @@ where
             continue;
         }
 
+        if line_split[0] == "newmtl" {
+            if let Some((name, properties, textures)) = current_material.take() {
+                materials.push(Material::new(name, properties, textures, device, queue));
+            }
+
+            current_material = Some((
+                line_split[1].to_string(),
+                MaterialProperties::default(),
+                MaterialTextures::default(),
+            ));
+            continue;
+        }
+
+        // Every other statement describes the current material
+        let Some((_, properties, textures)) = current_material.as_mut() else {
+            continue;
+        };
+
         match line_split[0] {
-            "newmtl" => {
-                current_material = Some(line_split[1].to_string());
+            "Ka" => {
+                if let Some(color) = parse_color(&line_split) {
+                    properties.ambient_color = color;
+                }
+            }
+            "Kd" => {
+                if let Some(color) = parse_color(&line_split) {
+                    properties.diffuse_color = color;
+                }
+            }
+            "Ks" => {
+                if let Some(color) = parse_color(&line_split) {
+                    properties.specular_color = color;
+                }
+            }
+            "Ke" => {
+                if let Some(color) = parse_color(&line_split) {
+                    properties.emissive_color = color;
+                }
+            }
+            "Ke_intensity" => {
+                if let Some(intensity) = parse_scalar(&line_split) {
+                    properties.emissive_intensity = intensity;
+                }
+            }
+            "Ns" => {
+                if let Some(exponent) = parse_scalar(&line_split) {
+                    properties.specular_exponent = exponent;
+                }
+            }
+            "d" => {
+                if let Some(dissolve) = parse_scalar(&line_split) {
+                    properties.dissolve = dissolve;
+                    if dissolve < 1.0 {
+                        properties.alpha_mode = AlphaMode::Blend;
+                    }
+                }
+            }
+            "Tr" => {
+                if let Some(transparency) = parse_scalar(&line_split) {
+                    properties.dissolve = 1.0 - transparency;
+                    if transparency > 0.0 {
+                        properties.alpha_mode = AlphaMode::Blend;
+                    }
+                }
+            }
+            "Pm" => {
+                if let Some(metallic) = parse_scalar(&line_split) {
+                    properties.metallic = metallic;
+                }
+            }
+            "Pr" => {
+                if let Some(roughness) = parse_scalar(&line_split) {
+                    properties.roughness = roughness;
+                }
+            }
+            "alpha_mode" => match line_split.get(1).copied() {
+                Some("opaque") => properties.alpha_mode = AlphaMode::Opaque,
+                Some("blend") => properties.alpha_mode = AlphaMode::Blend,
+                Some("mask") => properties.alpha_mode = AlphaMode::Mask,
+                _ => warn!(
+                    "Unknown alpha mode {:?}, expected opaque, blend, or mask",
+                    line_split.get(1)
+                ),
+            },
+            "alpha_cutoff" => {
+                if let Some(cutoff) = parse_scalar(&line_split) {
+                    properties.alpha_cutoff = cutoff;
+                }
+            }
+            "double_sided" => {
+                // The statement alone turns it on
+                properties.faces.double_sided = line_split.get(1).is_none_or(|value| *value != "0");
+            }
+            "front_face" => match line_split.get(1).copied() {
+                Some("cw") => properties.faces.front_face = Winding::Clockwise,
+                Some("ccw") => properties.faces.front_face = Winding::CounterClockwise,
+                _ => warn!(
+                    "Unknown front face {:?}, expected cw or ccw",
+                    line_split.get(1)
+                ),
+            },
+            "depth_bias" => {
+                // depth_bias constant [slope_scale] [clamp]
+                let value = |index: usize| line_split.get(index).and_then(|v| v.parse().ok());
+                match line_split.get(1).and_then(|v| v.parse().ok()) {
+                    Some(constant) => {
+                        properties.depth_bias = DepthBias::new(
+                            constant,
+                            value(2).unwrap_or(0.0),
+                            value(3).unwrap_or(0.0),
+                        )
+                    }
+                    None => warn!(
+                        "Invalid depth bias {:?}, expected an integer constant",
+                        line_split.get(1)
+                    ),
+                }
+            }
+            "uv_scroll" => {
+                // uv_scroll u [v]
+                match parse_scalar(&line_split) {
+                    Some(u) => {
+                        let v = line_split
+                            .get(2)
+                            .and_then(|v| v.parse().ok())
+                            .unwrap_or(0.0);
+                        properties.texture_animation.scroll = [u, v];
+                    }
+                    None => warn!("Invalid uv scroll {:?}", line_split.get(1)),
+                }
+            }
+            "uv_rotate" => {
+                if let Some(rotation) = parse_scalar(&line_split) {
+                    properties.texture_animation.rotation = rotation;
+                }
+            }
+            "flipbook" => {
+                // flipbook columns rows frames_per_second [frames]
+                let value = |index: usize| line_split.get(index).and_then(|v| v.parse().ok());
+                match (
+                    value(1),
+                    value(2),
+                    line_split.get(3).and_then(|v| v.parse().ok()),
+                ) {
+                    (Some(columns), Some(rows), Some(frames_per_second)) => {
+                        let mut flipbook = Flipbook::new(columns, rows, frames_per_second);
+                        if let Some(frames) = value(4) {
+                            flipbook = flipbook.with_frames(frames);
+                        }
+                        properties.texture_animation.flipbook = Some(flipbook);
+                    }
+                    _ => warn!(
+                        "Invalid flipbook {:?}, expected columns, rows, and frames per second",
+                        &line_split[1..]
+                    ),
+                }
+            }
+            "illum" => {
+                if let Some(illumination_model) = line_split.get(1).and_then(|i| i.parse().ok()) {
+                    properties.illumination_model = illumination_model;
+                }
             }
             "map_Kd" => {
-                let new_path = file_path.as_ref().parent().unwrap().join(line_split[1]);
-                info!("Texture Path: {:?}", new_path);
-                let file_contents = fs::read(new_path).unwrap();
-                let texture = HeliumTexture::from_bytes(device, queue, &file_contents).unwrap();
-
-                materials.push(Material {
-                    name: current_material.take().unwrap(),
-                    diffuse_texture: Some(texture),
-                });
+                textures.diffuse = load_texture(
+                    file_path.as_ref(),
+                    &line_split,
+                    TextureFormat::Rgba8UnormSrgb,
+                    device,
+                    queue,
+                );
+            }
+            "map_Ks" => {
+                textures.specular = load_texture(
+                    file_path.as_ref(),
+                    &line_split,
+                    TextureFormat::Rgba8UnormSrgb,
+                    device,
+                    queue,
+                );
+            }
+            "map_Ke" => {
+                textures.emissive = load_texture(
+                    file_path.as_ref(),
+                    &line_split,
+                    TextureFormat::Rgba8UnormSrgb,
+                    device,
+                    queue,
+                );
+            }
+            "map_Bump" | "map_bump" | "bump" | "norm" => {
+                textures.normal = load_texture(
+                    file_path.as_ref(),
+                    &line_split,
+                    TextureFormat::Rgba8Unorm,
+                    device,
+                    queue,
+                );
+            }
+            "map_d" => {
+                properties.alpha_mode = AlphaMode::Blend;
+                textures.dissolve = load_texture(
+                    file_path.as_ref(),
+                    &line_split,
+                    TextureFormat::Rgba8Unorm,
+                    device,
+                    queue,
+                );
+            }
+            "map_Kd_layers" => {
+                textures.layers =
+                    load_texture_array(file_path.as_ref(), &line_split, device, queue);
+            }
+            "map_splat" => {
+                textures.splat = load_texture(
+                    file_path.as_ref(),
+                    &line_split,
+                    TextureFormat::Rgba8Unorm,
+                    device,
+                    queue,
+                );
             }
             _ => {}
         }
     }
 
+    if let Some((name, properties, textures)) = current_material.take() {
+        materials.push(Material::new(name, properties, textures, device, queue));
+    }
+
     Ok(materials)
 }
@@ -2,13 +2,54 @@ use std::ops::Range;
 
 use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
-    Buffer, BufferUsages, Device,
+    Buffer, BufferUsages, Device, PrimitiveTopology,
 };
 
 use super::{
     // instance::{Instance, InstanceRaw},
     model_vertex::ModelVertex,
+    morph::{MorphTarget, MorphTargets},
 };
+use crate::bounds::{Aabb, BoundingSphere};
+
+/// How the indices of a mesh are put together into the shapes that are drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Topology {
+    /// Every three indices are a triangle
+    #[default]
+    Triangles,
+    /// Every two indices are a separate line
+    Lines,
+    /// Every index is joined to the one before it by a line
+    LineStrip,
+    /// Every index is a point one pixel wide
+    Points,
+}
+
+impl Topology {
+    pub fn is_triangles(&self) -> bool {
+        *self == Self::Triangles
+    }
+}
+
+impl From<Topology> for PrimitiveTopology {
+    fn from(value: Topology) -> Self {
+        match value {
+            Topology::Triangles => Self::TriangleList,
+            Topology::Lines => Self::LineList,
+            Topology::LineStrip => Self::LineStrip,
+            Topology::Points => Self::PointList,
+        }
+    }
+}
+
+/// The vertices and indices of a mesh before they are uploaded to the gpu
+pub struct MeshData {
+    pub name: String,
+    pub vertices: Vec<ModelVertex>,
+    pub indices: Vec<u32>,
+    pub material: Option<usize>,
+}
 
 #[allow(unused)]
 pub struct Mesh {
@@ -20,6 +61,12 @@ pub struct Mesh {
     // num_instances: u32,
     instances: Range<u32>,
     material: Option<usize>,
+    topology: Topology,
+    // Bounds of the vertices in object space
+    aabb: Aabb,
+    bounding_sphere: BoundingSphere,
+    num_vertices: u32,
+    morph: Option<MorphTargets>,
 }
 
 impl Mesh {
@@ -59,6 +106,61 @@ impl Mesh {
         self.material.as_ref()
     }
 
+    pub fn get_topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Sets how the indices of the mesh are put together, only triangles are drawn into
+    /// the shadows, the picking buffer, outlines, and reflections
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    /// The box around the vertices of the mesh in object space, it is grown by how far the
+    /// morph targets can move the vertices
+    pub fn aabb(&self) -> Aabb {
+        let reach = self.get_morph_reach();
+        let reach = cgmath::Vector3::new(reach, reach, reach);
+        Aabb::new(self.aabb.min - reach, self.aabb.max + reach)
+    }
+
+    /// The sphere around the vertices of the mesh in object space, it is grown by how far
+    /// the morph targets can move the vertices
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        BoundingSphere::new(
+            self.bounding_sphere.center,
+            self.bounding_sphere.radius + self.get_morph_reach(),
+        )
+    }
+
+    pub fn get_num_vertices(&self) -> u32 {
+        self.num_vertices
+    }
+
+    /// Replaces the morph targets of the mesh, `None` removes them
+    pub(crate) fn set_morph_targets(&mut self, targets: Option<&[MorphTarget]>, device: &Device) {
+        self.morph = targets.map(|targets| {
+            MorphTargets::new(device, &self.name, self.num_vertices as usize, targets)
+        });
+    }
+
+    pub(crate) fn get_morph_targets(&self) -> Option<&MorphTargets> {
+        self.morph.as_ref()
+    }
+
+    pub(crate) fn get_morph_targets_mut(&mut self) -> Option<&mut MorphTargets> {
+        self.morph.as_mut()
+    }
+
+    pub fn has_morph_targets(&self) -> bool {
+        self.morph.is_some()
+    }
+
+    fn get_morph_reach(&self) -> f32 {
+        self.morph.as_ref().map_or(0.0, MorphTargets::get_reach)
+    }
+
     pub fn new(
         name: String,
         vertices: Vec<ModelVertex>,
@@ -77,6 +179,10 @@ impl Mesh {
             usage: BufferUsages::INDEX,
         });
 
+        let positions = vertices.iter().map(ModelVertex::get_position);
+        let aabb = Aabb::from_points(positions.clone()).unwrap_or_default();
+        let bounding_sphere = BoundingSphere::from_points(positions).unwrap_or_default();
+
         // let instance_buffer = device.create_buffer_init(&BufferInitDescriptor {
         //     label: Some(&(name.clone() + " Instance Buffer")),
         //     // Create an instance buffer with only 1 instance
@@ -93,9 +199,20 @@ impl Mesh {
             // num_instances: 1,
             instances: 0..1,
             material: None,
+            topology: Topology::Triangles,
+            aabb,
+            bounding_sphere,
+            num_vertices: vertices.len() as u32,
+            morph: None,
         }
     }
 
+    pub fn from_data(data: MeshData, device: &Device) -> Self {
+        let mut mesh = Self::new(data.name, data.vertices, data.indices, device);
+        mesh.set_material(data.material);
+        mesh
+    }
+
     pub fn set_material(&mut self, material_index: Option<usize>) {
         self.material = material_index;
     }
@@ -1,13 +1,21 @@
 pub mod draw_model;
+mod gltf_loader;
 pub mod instance;
 pub mod material;
 pub mod mesh;
 pub mod model_vertex;
+pub mod morph;
+pub mod occlusion;
 pub mod vertex;
 
 // Std
-use std::{io::Error, ops::Range, path::Path};
+use std::{
+    io::{Error, ErrorKind},
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
+use cgmath::{InnerSpace, Vector3};
 use model_vertex::ModelVertex;
 // wgpu imports
 use wgpu::{Device, Queue};
@@ -16,9 +24,27 @@ use wgpu::{Device, Queue};
 use log::*;
 
 // custom imports
+use crate::{
+    bounds::{Aabb, BoundingSphere},
+    color::Srgba,
+    profile_span,
+};
+use gltf_loader::load_gltf;
 use helium_io::read_lines;
-use material::{load_materials, Material};
-use mesh::Mesh;
+use instance::Instance;
+use material::{load_materials, ColorMaterial, Material, TextureAnimation};
+use mesh::{Mesh, MeshData, Topology};
+use morph::MorphTarget;
+use occlusion::{bake_occlusion, AmbientOcclusionBake};
+
+/// A non-moving object that is merged into a static batch
+#[derive(Clone)]
+pub struct StaticBatchObject {
+    pub path: PathBuf,
+    pub color_material: Option<ColorMaterial>,
+    /// Every copy of the object in the batch
+    pub instances: Vec<Instance>,
+}
 
 pub struct Model {
     meshes: Vec<Mesh>,
@@ -30,10 +56,58 @@ impl Model {
         &self.meshes
     }
 
+    pub(crate) fn get_meshes_mut(&mut self) -> &mut [Mesh] {
+        &mut self.meshes
+    }
+
     pub fn get_materials(&self) -> &[Material] {
         &self.materials
     }
 
+    /// The box around every mesh of the model in object space
+    pub fn aabb(&self) -> Aabb {
+        self.meshes
+            .iter()
+            .map(Mesh::aabb)
+            .reduce(|aabb, mesh_aabb| aabb.union(&mesh_aabb))
+            .unwrap_or_default()
+    }
+
+    /// The sphere around every mesh of the model in object space
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        self.meshes
+            .iter()
+            .map(Mesh::bounding_sphere)
+            .reduce(|sphere, mesh_sphere| sphere.union(&mesh_sphere))
+            .unwrap_or_default()
+    }
+
+    /// Moves the texture coordinates of every material of the model over time
+    pub fn set_texture_animation(&mut self, texture_animation: TextureAnimation, queue: &Queue) {
+        for material in self.materials.iter_mut() {
+            material.set_texture_animation(texture_animation, queue);
+        }
+    }
+
+    /// Replaces the materials of every mesh in the model with a single flat color material
+    pub fn set_color_material(
+        &mut self,
+        color_material: ColorMaterial,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        self.materials = vec![Material::from_color(
+            String::from("Color Material"),
+            color_material,
+            device,
+            queue,
+        )];
+
+        for mesh in self.meshes.iter_mut() {
+            mesh.set_material(Some(0));
+        }
+    }
+
     pub fn set_instances(&mut self, instances: Range<u32>) {
         for mesh in self.meshes.iter_mut() {
             mesh.set_instances(instances.clone());
@@ -56,20 +130,351 @@ impl Model {
         0
     }
 
-    pub fn from_obj<P>(file_path: P, device: &Device, queue: &Queue) -> Result<Self, Error>
+    /// Loads a model from an obj file and its materials
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - Filepath of the obj file
+    /// * `occlusion` - The ambient occlusion to bake into the vertices, `None` to skip the bake
+    ///
+    /// # Returns
+    ///
+    /// The model or an error if the file could not be read
+    pub fn from_obj<P>(
+        file_path: P,
+        occlusion: Option<&AmbientOcclusionBake>,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let _span = profile_span("load_model");
+        let (meshes, materials) = Self::load_obj(file_path, Some((device, queue)))?;
+
+        Ok(Self::from_mesh_data(meshes, materials, occlusion, device))
+    }
+
+    /// Loads a model from a .gltf or .glb file with its materials and embedded textures,
+    /// the base color, normal, and emissive maps of the materials are used
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - Filepath of the gltf or glb file
+    /// * `occlusion` - The ambient occlusion to bake into the vertices, `None` to skip the bake
+    ///
+    /// # Returns
+    ///
+    /// The model or an error if the file could not be read
+    pub fn from_gltf<P>(
+        file_path: P,
+        occlusion: Option<&AmbientOcclusionBake>,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let _span = profile_span("load_model");
+        let (meshes, materials) = load_gltf(file_path, Some((device, queue)))?;
+
+        Ok(Self::from_mesh_data(meshes, materials, occlusion, device))
+    }
+
+    /// Loads a model from an obj, gltf, or glb file depending on its extension
+    pub fn from_file<P>(
+        file_path: P,
+        occlusion: Option<&AmbientOcclusionBake>,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let _span = profile_span("load_model");
+        let (meshes, materials) = Self::load_file(file_path, Some((device, queue)))?;
+
+        Ok(Self::from_mesh_data(meshes, materials, occlusion, device))
+    }
+
+    fn from_mesh_data(
+        mut meshes: Vec<MeshData>,
+        materials: Vec<Material>,
+        occlusion: Option<&AmbientOcclusionBake>,
+        device: &Device,
+    ) -> Self {
+        if let Some(occlusion) = occlusion {
+            let _span = profile_span("bake_occlusion");
+            bake_occlusion(&mut meshes, occlusion);
+        }
+
+        Self {
+            meshes: meshes
+                .into_iter()
+                .map(|mesh| Mesh::from_data(mesh, device))
+                .collect(),
+            materials,
+        }
+    }
+
+    /// Loads morph targets for the meshes of a model from obj files of the same model in
+    /// other shapes, the files need the same meshes with the same faces as the base file
+    /// and only the positions and normals of the vertices can differ
+    ///
+    /// # Arguments
+    ///
+    /// * `base_path` - Filepath of the obj file the model was loaded from
+    /// * `targets` - The name and obj file of every target, in the order of their weights
+    ///
+    /// # Returns
+    ///
+    /// An error if a file could not be read or does not match the base model
+    pub fn load_morph_targets<P>(
+        &mut self,
+        base_path: P,
+        targets: &[(String, PathBuf)],
+        device: &Device,
+    ) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let _span = profile_span("load_morph_targets");
+        // Only the vertices are compared so the materials are not loaded again
+        let (base_meshes, _) = Self::load_obj(&base_path, None)?;
+        if base_meshes.len() != self.meshes.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "The base file does not have the meshes of the model",
+            ));
+        }
+
+        let mut mesh_targets = vec![Vec::new(); base_meshes.len()];
+        for (name, path) in targets {
+            let (target_meshes, _) = Self::load_obj(path, None)?;
+            if target_meshes.len() != base_meshes.len() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Morph target {name} has a different number of meshes"),
+                ));
+            }
+
+            for ((base, target), targets) in base_meshes
+                .iter()
+                .zip(target_meshes.iter())
+                .zip(mesh_targets.iter_mut())
+            {
+                if base.vertices.len() != target.vertices.len() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "Mesh {} of morph target {name} has a different number of vertices",
+                            base.name
+                        ),
+                    ));
+                }
+
+                let (positions, normals) = base
+                    .vertices
+                    .iter()
+                    .zip(target.vertices.iter())
+                    .map(|(base, target)| {
+                        (
+                            target.get_position() - base.get_position(),
+                            target.get_normal() - base.get_normal(),
+                        )
+                    })
+                    .unzip();
+                targets.push(MorphTarget::new(name, positions, normals));
+            }
+        }
+
+        for (mesh, targets) in self.meshes.iter_mut().zip(mesh_targets) {
+            mesh.set_morph_targets((!targets.is_empty()).then_some(&targets), device);
+        }
+
+        Ok(())
+    }
+
+    /// The names of the morph targets of the model in the order of their weights
+    pub fn get_morph_target_names(&self) -> Vec<String> {
+        self.meshes
+            .iter()
+            .find_map(Mesh::get_morph_targets)
+            .map(|morph| morph.get_names().to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Creates a model of lines or points, like a trajectory preview, a laser, a graph, or
+    /// an editor grid. It is drawn unlit in white until it is given a color material
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The points in object space in the order they are joined
+    /// * `topology` - How the points are joined, pairs of points for `Lines`, every point
+    ///   to the next for `LineStrip`, and not at all for `Points`
+    ///
+    /// # Returns
+    ///
+    /// The model with a single mesh of the points
+    pub fn from_lines(
+        points: &[[f32; 3]],
+        topology: Topology,
+        device: &Device,
+        queue: &Queue,
+    ) -> Self {
+        // The normal only matters for triangles, lines and points are drawn unlit
+        let vertices = points
+            .iter()
+            .map(|point| ModelVertex::new(*point, (0.0, 0.0), [0.0, 1.0, 0.0]))
+            .collect();
+        let indices = (0..points.len() as u32).collect();
+
+        let mut mesh =
+            Mesh::new(String::from("Lines"), vertices, indices, device).with_topology(topology);
+        mesh.set_material(Some(0));
+
+        Self {
+            meshes: vec![mesh],
+            materials: vec![Material::from_color(
+                String::from("Lines Material"),
+                ColorMaterial::unlit(Srgba::WHITE),
+                device,
+                queue,
+            )],
+        }
+    }
+
+    /// Merges many non-moving objects into a single model, every mesh that uses the same
+    /// material is combined into one mesh so all of the objects are drawn with one draw
+    /// call per material
+    ///
+    /// # Arguments
+    ///
+    /// * `objects` - The objects to merge with the transforms of each copy of the object
+    /// * `occlusion` - The ambient occlusion to bake into the vertices after they are moved
+    ///   into world space so the objects of the batch occlude each other
+    ///
+    /// # Returns
+    ///
+    /// The batched model with its vertices in world space
+    pub fn static_batch(
+        objects: &[StaticBatchObject],
+        occlusion: Option<&AmbientOcclusionBake>,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<Self, Error> {
+        let mut materials: Vec<Material> = Vec::new();
+        // Combined vertices and indices for every material
+        let mut batches: Vec<(Vec<ModelVertex>, Vec<u32>)> = Vec::new();
+
+        // Objects with the same model and material share their materials and meshes
+        let mut groups: Vec<(&Path, Option<ColorMaterial>, Vec<&Instance>)> = Vec::new();
+        for object in objects.iter() {
+            match groups.iter_mut().find(|(path, color_material, _)| {
+                *path == object.path.as_path() && *color_material == object.color_material
+            }) {
+                Some((_, _, instances)) => instances.extend(object.instances.iter()),
+                None => groups.push((
+                    object.path.as_path(),
+                    object.color_material,
+                    object.instances.iter().collect(),
+                )),
+            }
+        }
+
+        for (path, color_material, instances) in groups {
+            let (meshes, mut group_materials) = Self::load_file(path, Some((device, queue)))?;
+
+            let material_offset = materials.len();
+            if let Some(color_material) = color_material {
+                group_materials = vec![Material::from_color(
+                    String::from("Color Material"),
+                    color_material,
+                    device,
+                    queue,
+                )];
+            }
+            batches.resize_with(material_offset + group_materials.len(), Default::default);
+            materials.append(&mut group_materials);
+
+            for mesh in meshes.iter() {
+                let material_index = match color_material {
+                    Some(_) => material_offset,
+                    None => material_offset + mesh.material.unwrap_or_default(),
+                };
+                let (vertices, indices) = &mut batches[material_index];
+
+                for instance in instances.iter() {
+                    let index_offset = vertices.len() as u32;
+                    vertices.extend(mesh.vertices.iter().map(|v| v.transformed(instance)));
+                    indices.extend(mesh.indices.iter().map(|index| index + index_offset));
+                }
+            }
+        }
+
+        let mut mesh_data: Vec<MeshData> = batches
+            .into_iter()
+            .enumerate()
+            .filter(|(_, (_, indices))| !indices.is_empty())
+            .map(|(material_index, (vertices, indices))| MeshData {
+                name: format!("Static Batch {}", material_index),
+                vertices,
+                indices,
+                material: Some(material_index),
+            })
+            .collect();
+        if let Some(occlusion) = occlusion {
+            let _span = profile_span("bake_occlusion");
+            bake_occlusion(&mut mesh_data, occlusion);
+        }
+
+        let meshes = mesh_data
+            .into_iter()
+            .map(|mesh| Mesh::from_data(mesh, device))
+            .collect();
+
+        Ok(Self { meshes, materials })
+    }
+
+    // Reads the meshes of a model file with the loader of its extension
+    fn load_file<P>(
+        file_path: P,
+        gpu: Option<(&Device, &Queue)>,
+    ) -> Result<(Vec<MeshData>, Vec<Material>), Error>
+    where
+        P: AsRef<Path>,
+    {
+        match file_path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some(extension)
+                if extension.eq_ignore_ascii_case("gltf")
+                    || extension.eq_ignore_ascii_case("glb") =>
+            {
+                load_gltf(file_path, gpu)
+            }
+            _ => Self::load_obj(file_path, gpu),
+        }
+    }
+
+    // Reads the meshes of an obj file without creating their buffers, the materials are
+    // only loaded when there is a device to load them onto
+    fn load_obj<P>(
+        file_path: P,
+        gpu: Option<(&Device, &Queue)>,
+    ) -> Result<(Vec<MeshData>, Vec<Material>), Error>
     where
         P: AsRef<Path>,
     {
         info!("Loading Object: {:?}", file_path.as_ref());
         let mut mesh_name: Option<String> = None;
         let mut vertices: Vec<(f32, f32, f32)> = Vec::new();
+        let mut vertex_colors: Vec<(f32, f32, f32)> = Vec::new();
         let mut uv_coords: Vec<(f32, f32)> = Vec::new();
         let mut normals: Vec<(f32, f32, f32)> = Vec::new();
 
         let mut model_vertices: Vec<ModelVertex> = Vec::new();
         let mut indices: Vec<u32> = Vec::new();
 
-        let mut meshes: Vec<Mesh> = Vec::new();
+        let mut meshes: Vec<MeshData> = Vec::new();
         let mut materials: Vec<Material> = Vec::new();
 
         let mut material_index: Option<usize> = None;
@@ -87,9 +492,12 @@ impl Model {
                         // This is an object
                         "o" => {
                             if let Some(name) = mesh_name.take() {
-                                let mut new_mesh = Mesh::new(name, model_vertices, indices, device);
-                                new_mesh.set_material(material_index.take());
-                                meshes.push(new_mesh);
+                                meshes.push(MeshData {
+                                    name,
+                                    vertices: model_vertices,
+                                    indices,
+                                    material: material_index.take(),
+                                });
                                 model_vertices = Vec::new();
                                 indices = Vec::new();
                             }
@@ -105,6 +513,18 @@ impl Model {
                             );
 
                             vertices.push(vertex);
+
+                            // Some exporters add an rgb color after the position
+                            let color = match line_split.get(4..7) {
+                                Some([r, g, b]) => (
+                                    r.parse::<f32>().unwrap_or(1.0),
+                                    g.parse::<f32>().unwrap_or(1.0),
+                                    b.parse::<f32>().unwrap_or(1.0),
+                                ),
+                                _ => (1.0, 1.0, 1.0),
+                            };
+
+                            vertex_colors.push(color);
                         }
                         // This is a uv coordinate
                         "vt" => {
@@ -127,23 +547,49 @@ impl Model {
                         }
                         // This is a face
                         "f" => {
+                            let mut face_vertices = Vec::with_capacity(3);
                             for vertex_info in line_split[1..=3].iter() {
                                 let vertex_info_split =
                                     vertex_info.split('/').collect::<Vec<&str>>();
 
                                 // Get the index of each the vertex, uv, and normal, for each vertex of the face
-                                let (vertex_index, uv_index, normal_index) = (
-                                    vertex_info_split[0].parse::<usize>().unwrap() - 1,
-                                    vertex_info_split[1].parse::<usize>().unwrap() - 1,
-                                    vertex_info_split[2].parse::<usize>().unwrap() - 1,
+                                // The uv and normal are optional in the obj format
+                                let vertex_index =
+                                    vertex_info_split[0].parse::<usize>().unwrap() - 1;
+                                let uv_index = vertex_info_split
+                                    .get(1)
+                                    .and_then(|index| index.parse::<usize>().ok())
+                                    .map(|index| index - 1);
+                                let normal_index = vertex_info_split
+                                    .get(2)
+                                    .and_then(|index| index.parse::<usize>().ok())
+                                    .map(|index| index - 1);
+
+                                face_vertices.push((vertex_index, uv_index, normal_index));
+                            }
+
+                            // If the face doesn't specify normals then use the flat face normal
+                            let face_normal = {
+                                let (a, b, c) = (
+                                    Vector3::from(vertices[face_vertices[0].0]),
+                                    Vector3::from(vertices[face_vertices[1].0]),
+                                    Vector3::from(vertices[face_vertices[2].0]),
                                 );
 
+                                (b - a).cross(c - a).normalize()
+                            };
+
+                            for (vertex_index, uv_index, normal_index) in face_vertices {
                                 // Add a vertex to the current model based on the face information
-                                model_vertices.push(ModelVertex::new(
-                                    vertices[vertex_index],
-                                    uv_coords[uv_index],
-                                    normals[normal_index],
-                                ));
+                                model_vertices.push(
+                                    ModelVertex::new(
+                                        vertices[vertex_index],
+                                        uv_index.map_or((0.0, 0.0), |index| uv_coords[index]),
+                                        normal_index
+                                            .map_or(face_normal.into(), |index| normals[index]),
+                                    )
+                                    .with_color(vertex_colors[vertex_index]),
+                                );
 
                                 // WARN: This might be a problem
                                 indices.push(model_vertices.len() as u32 - 1);
@@ -151,11 +597,18 @@ impl Model {
                         }
                         // This is a mateiral
                         "mtllib" => {
+                            let Some((device, queue)) = gpu else {
+                                continue;
+                            };
                             let path_to_material =
                                 file_path.as_ref().parent().unwrap().join(line_split[1]);
-                            materials.append(
-                                &mut load_materials(path_to_material, device, queue).unwrap(),
-                            );
+                            match load_materials(&path_to_material, device, queue) {
+                                Ok(mut new_materials) => materials.append(&mut new_materials),
+                                Err(e) => warn!(
+                                    "Could not load material {:?}: {}, using the default material",
+                                    path_to_material, e
+                                ),
+                            }
                         }
                         // This is the object using the material
                         "usemtl" => {
@@ -176,13 +629,36 @@ impl Model {
                 }
 
                 // Add any remaining meshes in the object file
+                // Files without any objects are treated as a single mesh named after the file
+                if mesh_name.is_none() && !indices.is_empty() {
+                    mesh_name = file_path
+                        .as_ref()
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().to_string());
+                }
+
                 if let Some(name) = mesh_name.take() {
-                    let mut new_mesh = Mesh::new(name, model_vertices, indices, device);
-                    new_mesh.set_material(material_index.take());
-                    meshes.push(new_mesh);
+                    meshes.push(MeshData {
+                        name,
+                        vertices: model_vertices,
+                        indices,
+                        material: material_index.take(),
+                    });
+                }
+
+                // Any mesh that did not specify a material uses the engine default material
+                if let Some((device, queue)) =
+                    gpu.filter(|_| meshes.iter().any(|mesh| mesh.material.is_none()))
+                {
+                    let default_index = materials.len();
+                    materials.push(Material::default_material(device, queue));
+
+                    for mesh in meshes.iter_mut().filter(|mesh| mesh.material.is_none()) {
+                        mesh.material = Some(default_index);
+                    }
                 }
 
-                Ok(Self { meshes, materials })
+                Ok((meshes, materials))
             }
             Err(e) => {
                 error!("Error: {}", e);
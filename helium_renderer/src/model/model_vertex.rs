@@ -1,6 +1,7 @@
+use cgmath::{ElementWise, InnerSpace, Vector3};
 use wgpu::{BufferAddress, VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode};
 
-use super::vertex::Vertex;
+use super::{instance::Instance, vertex::Vertex};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -8,6 +9,9 @@ pub struct ModelVertex {
     position: [f32; 3],
     uv_coords: [f32; 2],
     normal_vec: [f32; 3],
+    color: [f32; 3],
+    // How much of the ambient light reaches the vertex, 1 is not occluded
+    occlusion: f32,
 }
 
 impl ModelVertex {
@@ -20,6 +24,53 @@ impl ModelVertex {
             position: position.into(),
             uv_coords: uv_coords.into(),
             normal_vec: normal_vec.into(),
+            color: [1.0, 1.0, 1.0],
+            occlusion: 1.0,
+        }
+    }
+
+    /// Sets the vertex color that is multiplied into the material color
+    pub fn with_color<C>(mut self, color: C) -> Self
+    where
+        C: Into<[f32; 3]>,
+    {
+        self.color = color.into();
+        self
+    }
+
+    /// Sets how much of the ambient and environment light reaches the vertex, from 0 for
+    /// fully occluded to 1
+    pub fn with_occlusion(mut self, occlusion: f32) -> Self {
+        self.occlusion = occlusion.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn get_position(&self) -> Vector3<f32> {
+        Vector3::from(self.position)
+    }
+
+    pub fn get_normal(&self) -> Vector3<f32> {
+        Vector3::from(self.normal_vec)
+    }
+
+    pub fn get_occlusion(&self) -> f32 {
+        self.occlusion
+    }
+
+    /// Moves the vertex by the transform of an instance, used to bake objects into world space
+    pub fn transformed(&self, instance: &Instance) -> Self {
+        let position = instance.rotation
+            * Vector3::from(self.position).mul_element_wise(instance.scale)
+            + instance.position;
+        let normal = instance.rotation
+            * Vector3::from(self.normal_vec)
+                .div_element_wise(instance.scale)
+                .normalize();
+
+        Self {
+            position: position.into(),
+            normal_vec: normal.into(),
+            ..*self
         }
     }
 }
@@ -49,6 +100,18 @@ impl Vertex for ModelVertex {
                     shader_location: 2,
                     format: VertexFormat::Float32x3,
                 },
+                // Vertex Color
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 8]>() as BufferAddress,
+                    shader_location: 3,
+                    format: VertexFormat::Float32x3,
+                },
+                // Ambient Occlusion
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 11]>() as BufferAddress,
+                    shader_location: 4,
+                    format: VertexFormat::Float32,
+                },
             ],
         }
     }
@@ -0,0 +1,263 @@
+use cgmath::{InnerSpace, Vector3};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType, BufferDescriptor, BufferUsages,
+    Device, Queue, ShaderStages,
+};
+
+use super::instance::Instance;
+
+/// The most morph targets a mesh can blend between, every instance has a weight for each
+/// of them. Must match MAX_MORPH_TARGETS in morph_vertex_shader.wgsl
+pub const MAX_MORPH_TARGETS: usize = 8;
+
+// Size of the weights of one instance on the gpu
+const WEIGHTS_SIZE: u64 = (MAX_MORPH_TARGETS * std::mem::size_of::<f32>()) as u64;
+
+/// A shape a mesh can be blended towards, like a smile or a blink, stored as how far each
+/// vertex of the mesh moves to reach the shape
+#[derive(Clone, Debug, PartialEq)]
+pub struct MorphTarget {
+    pub name: String,
+    /// How far each vertex moves, in the order of the vertices of the mesh
+    pub position_deltas: Vec<Vector3<f32>>,
+    /// How much the normal of each vertex changes
+    pub normal_deltas: Vec<Vector3<f32>>,
+}
+
+impl MorphTarget {
+    pub fn new(
+        name: &str,
+        position_deltas: Vec<Vector3<f32>>,
+        normal_deltas: Vec<Vector3<f32>>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            position_deltas,
+            normal_deltas,
+        }
+    }
+
+    // The furthest any vertex moves at a weight of 1
+    fn max_distance(&self) -> f32 {
+        self.position_deltas
+            .iter()
+            .map(|delta| delta.magnitude())
+            .fold(0.0, f32::max)
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct MorphInfo {
+    vertex_count: u32,
+    target_count: u32,
+    _padding: [u32; 2],
+}
+
+/// The morph targets of a mesh on the gpu, the deltas of every target are in one storage
+/// buffer that the vertex shader blends with the weights of the instance
+pub(crate) struct MorphTargets {
+    names: Vec<String>,
+    info: Buffer,
+    deltas: Buffer,
+    // How far the targets can move the vertices together, the bounds grow by it
+    reach: f32,
+    // The bind group and the generation of the weight buffer it was created with
+    bind_group: Option<(u64, BindGroup)>,
+}
+
+impl MorphTargets {
+    pub fn get_layout(device: &Device) -> BindGroupLayout {
+        let storage = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::VERTEX,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Morph Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage(1),
+                storage(2),
+            ],
+        })
+    }
+
+    /// Uploads the deltas of the targets of a mesh, targets past the most that can be
+    /// blended are dropped
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the mesh
+    /// * `vertex_count` - The number of vertices of the mesh, every target needs as many
+    /// * `targets` - The targets of the mesh
+    pub fn new(device: &Device, name: &str, vertex_count: usize, targets: &[MorphTarget]) -> Self {
+        let targets = &targets[..targets.len().min(MAX_MORPH_TARGETS)];
+
+        // The position and normal delta of every vertex of the first target, then the second
+        let mut deltas = Vec::with_capacity(targets.len() * vertex_count * 2);
+        for target in targets {
+            for vertex in 0..vertex_count {
+                let position = target.position_deltas.get(vertex).copied();
+                let normal = target.normal_deltas.get(vertex).copied();
+                deltas.push(position.map_or([0.0; 4], |delta| delta.extend(0.0).into()));
+                deltas.push(normal.map_or([0.0; 4], |delta| delta.extend(0.0).into()));
+            }
+        }
+        // Empty storage buffers can not be bound
+        if deltas.is_empty() {
+            deltas.push([0.0; 4]);
+        }
+
+        let info = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some(&format!("{name} Morph Info Buffer")),
+            contents: bytemuck::cast_slice(&[MorphInfo {
+                vertex_count: vertex_count as u32,
+                target_count: targets.len() as u32,
+                _padding: [0; 2],
+            }]),
+            usage: BufferUsages::UNIFORM,
+        });
+
+        let deltas = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some(&format!("{name} Morph Delta Buffer")),
+            contents: bytemuck::cast_slice(&deltas),
+            usage: BufferUsages::STORAGE,
+        });
+
+        Self {
+            names: targets.iter().map(|target| target.name.clone()).collect(),
+            info,
+            deltas,
+            reach: targets.iter().map(MorphTarget::max_distance).sum(),
+            bind_group: None,
+        }
+    }
+
+    /// The names of the targets in the order of their weights
+    pub fn get_names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn get_reach(&self) -> f32 {
+        self.reach
+    }
+
+    /// Creates the bind group again when the weight buffer was replaced
+    pub fn prepare(&mut self, device: &Device, layout: &BindGroupLayout, weights: &MorphWeights) {
+        if self
+            .bind_group
+            .as_ref()
+            .is_some_and(|(generation, _)| *generation == weights.generation)
+        {
+            return;
+        }
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Morph Bind Group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: self.info.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: self.deltas.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: weights.buffer.as_entire_binding(),
+                },
+            ],
+        });
+        self.bind_group = Some((weights.generation, bind_group));
+    }
+
+    /// The bind group of the targets, it has to be prepared first
+    pub fn get_bind_group(&self) -> Option<&BindGroup> {
+        self.bind_group.as_ref().map(|(_, bind_group)| bind_group)
+    }
+}
+
+/// The morph weights of every instance on the gpu, in the same order as the instance
+/// buffer so the vertex shader finds them by the index of the instance
+pub(crate) struct MorphWeights {
+    buffer: Buffer,
+    capacity: usize,
+    // Counts up every time the buffer is replaced so the bind groups using it are rebuilt
+    generation: u64,
+}
+
+impl MorphWeights {
+    pub fn new(device: &Device, queue: &Queue, instances: &[Instance]) -> Self {
+        let weights = Self {
+            buffer: Self::create_buffer(device, instances.len()),
+            capacity: instances.len().max(1),
+            generation: 0,
+        };
+        weights.write(queue, 0, instances);
+        weights
+    }
+
+    fn create_buffer(device: &Device, capacity: usize) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("Morph Weight Buffer"),
+            size: capacity.max(1) as u64 * WEIGHTS_SIZE,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Writes the weights of every instance, the buffer grows to fit them
+    pub fn write_all(&mut self, device: &Device, queue: &Queue, instances: &[Instance]) {
+        if instances.len() > self.capacity {
+            // Grows by more than is needed so adding a few instances at a time is cheap
+            self.capacity = instances.len().next_power_of_two();
+            self.buffer = Self::create_buffer(device, self.capacity);
+            self.generation += 1;
+        }
+
+        self.write(queue, 0, instances);
+    }
+
+    /// Writes the weights of a run of instances that fit in the buffer
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - The index of the first instance in the instance buffer
+    /// * `instances` - The instances from the first one on
+    pub fn write(&self, queue: &Queue, first: usize, instances: &[Instance]) {
+        let count = instances.len().min(self.capacity.saturating_sub(first));
+        if count == 0 {
+            return;
+        }
+
+        let weights = instances[..count]
+            .iter()
+            .map(|instance| instance.morph_weights)
+            .collect::<Vec<_>>();
+        queue.write_buffer(
+            &self.buffer,
+            first as u64 * WEIGHTS_SIZE,
+            bytemuck::cast_slice(&weights),
+        );
+    }
+}
@@ -0,0 +1,322 @@
+use std::{f32::consts::TAU, thread};
+
+use cgmath::{InnerSpace, Vector3};
+
+use super::{mesh::MeshData, model_vertex::ModelVertex};
+
+// Number of triangles a node of the tree is split past
+const LEAF_TRIANGLES: usize = 4;
+
+// Distance a hit has to be past the start of a ray so a vertex does not hit its own triangles
+const MIN_HIT_DISTANCE: f32 = 1e-4;
+
+/// Settings of the ambient occlusion that is baked into the vertices of a model when it
+/// is loaded. Rays are cast from every vertex over the half of a sphere around its normal
+/// and the vertex is darkened by how many of them hit the mesh
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AmbientOcclusionBake {
+    samples: u32,
+    distance: f32,
+    strength: f32,
+    bias: f32,
+}
+
+impl Default for AmbientOcclusionBake {
+    fn default() -> Self {
+        Self {
+            samples: 16,
+            distance: 1.0,
+            strength: 1.0,
+            bias: 0.001,
+        }
+    }
+}
+
+impl AmbientOcclusionBake {
+    /// Sets how many rays are cast from each vertex, more rays give smoother shading but
+    /// take longer to bake
+    pub fn with_samples(mut self, samples: u32) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    /// Sets how far away a surface can be to occlude a vertex in object space, or in world
+    /// space for static batches
+    pub fn with_distance(mut self, distance: f32) -> Self {
+        self.distance = distance.max(0.0);
+        self
+    }
+
+    /// Sets how dark a fully occluded vertex is, from 0 for no darkening to 1 for black
+    pub fn with_strength(mut self, strength: f32) -> Self {
+        self.strength = strength.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets how far rays start off of the surface to keep flat surfaces from occluding
+    /// themselves
+    pub fn with_bias(mut self, bias: f32) -> Self {
+        self.bias = bias.max(0.0);
+        self
+    }
+
+    pub fn get_samples(&self) -> u32 {
+        self.samples
+    }
+
+    pub fn get_distance(&self) -> f32 {
+        self.distance
+    }
+
+    pub fn get_strength(&self) -> f32 {
+        self.strength
+    }
+
+    pub fn get_bias(&self) -> f32 {
+        self.bias
+    }
+}
+
+type Triangle = [Vector3<f32>; 3];
+
+// A node of the bounding volume tree, leaves point to a range of the sorted triangles
+struct Node {
+    min: Vector3<f32>,
+    max: Vector3<f32>,
+    // The first triangle of a leaf or the index of the second child of a branch, the first
+    // child always comes right after its parent
+    start: usize,
+    // The number of triangles of a leaf, 0 for branches
+    count: usize,
+}
+
+struct TriangleTree {
+    triangles: Vec<Triangle>,
+    nodes: Vec<Node>,
+}
+
+impl TriangleTree {
+    fn new(mut triangles: Vec<Triangle>) -> Self {
+        let mut nodes = Vec::new();
+        let count = triangles.len();
+        if count > 0 {
+            Self::build(&mut triangles, 0, count, &mut nodes);
+        }
+
+        Self { triangles, nodes }
+    }
+
+    fn build(triangles: &mut [Triangle], start: usize, end: usize, nodes: &mut Vec<Node>) {
+        let mut min = Vector3::new(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = Vector3::new(f32::MIN, f32::MIN, f32::MIN);
+        for point in triangles[start..end].iter().flatten() {
+            min = Vector3::new(min.x.min(point.x), min.y.min(point.y), min.z.min(point.z));
+            max = Vector3::new(max.x.max(point.x), max.y.max(point.y), max.z.max(point.z));
+        }
+
+        let node_index = nodes.len();
+        nodes.push(Node {
+            min,
+            max,
+            start,
+            count: end - start,
+        });
+        if end - start <= LEAF_TRIANGLES {
+            return;
+        }
+
+        // Splits the triangles in half along the longest side of the box
+        let size = max - min;
+        let axis = if size.x >= size.y && size.x >= size.z {
+            0
+        } else if size.y >= size.z {
+            1
+        } else {
+            2
+        };
+        let centroid =
+            |triangle: &Triangle| triangle[0][axis] + triangle[1][axis] + triangle[2][axis];
+        let middle = (start + end) / 2;
+        triangles[start..end]
+            .select_nth_unstable_by(middle - start, |a, b| centroid(a).total_cmp(&centroid(b)));
+
+        Self::build(triangles, start, middle, nodes);
+        let second_child = nodes.len();
+        Self::build(triangles, middle, end, nodes);
+        nodes[node_index].start = second_child;
+        nodes[node_index].count = 0;
+    }
+
+    // Whether a ray hits any triangle closer than the distance
+    fn hits(&self, origin: Vector3<f32>, direction: Vector3<f32>, distance: f32) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+
+        let inverse = Vector3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if !ray_hits_box(origin, inverse, node.min, node.max, distance) {
+                continue;
+            }
+
+            if node.count == 0 {
+                stack.push(node.start);
+                stack.push(node_index + 1);
+                continue;
+            }
+
+            let leaf = &self.triangles[node.start..node.start + node.count];
+            if leaf
+                .iter()
+                .any(|triangle| ray_hits_triangle(origin, direction, triangle, distance))
+            {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+// Slab test of a ray against a box
+fn ray_hits_box(
+    origin: Vector3<f32>,
+    inverse: Vector3<f32>,
+    min: Vector3<f32>,
+    max: Vector3<f32>,
+    distance: f32,
+) -> bool {
+    let mut near = 0.0_f32;
+    let mut far = distance;
+    for axis in 0..3 {
+        let first = (min[axis] - origin[axis]) * inverse[axis];
+        let second = (max[axis] - origin[axis]) * inverse[axis];
+        near = near.max(first.min(second));
+        far = far.min(first.max(second));
+    }
+
+    near <= far
+}
+
+// Möller–Trumbore intersection of a ray with a triangle
+fn ray_hits_triangle(
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    triangle: &Triangle,
+    distance: f32,
+) -> bool {
+    let edge_1 = triangle[1] - triangle[0];
+    let edge_2 = triangle[2] - triangle[0];
+    let p = direction.cross(edge_2);
+    let determinant = edge_1.dot(p);
+    if determinant.abs() < 1e-12 {
+        return false;
+    }
+
+    let inverse_determinant = 1.0 / determinant;
+    let t_vec = origin - triangle[0];
+    let u = t_vec.dot(p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return false;
+    }
+
+    let q = t_vec.cross(edge_1);
+    let v = direction.dot(q) * inverse_determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return false;
+    }
+
+    let hit_distance = edge_2.dot(q) * inverse_determinant;
+    hit_distance > MIN_HIT_DISTANCE && hit_distance < distance
+}
+
+// Spreads the samples evenly over the unit square
+fn hammersley(index: u32, count: u32) -> (f32, f32) {
+    let radical_inverse = index.reverse_bits() as f32 / (u32::MAX as f32 + 1.0);
+    ((index as f32 + 0.5) / count as f32, radical_inverse)
+}
+
+// Directions over the half of a sphere around the z axis with more of them near the axis,
+// matching how much light from each direction reaches a surface
+fn hemisphere_directions(count: u32) -> Vec<Vector3<f32>> {
+    (0..count)
+        .map(|index| {
+            let (u, v) = hammersley(index, count);
+            let radius = u.sqrt();
+            let angle = TAU * v;
+            Vector3::new(
+                radius * angle.cos(),
+                radius * angle.sin(),
+                (1.0 - u).max(0.0).sqrt(),
+            )
+        })
+        .collect()
+}
+
+/// Bakes the ambient occlusion of every vertex of the meshes into their occlusion
+/// attribute, the meshes occlude each other so merged static batches are occluded by the
+/// rest of the batch
+///
+/// # Arguments
+///
+/// * `meshes` - The meshes to bake, only their triangles occlude the vertices
+/// * `settings` - How the occlusion is baked
+pub(crate) fn bake_occlusion(meshes: &mut [MeshData], settings: &AmbientOcclusionBake) {
+    let triangles = meshes
+        .iter()
+        .flat_map(|mesh| {
+            mesh.indices.chunks_exact(3).map(|face| {
+                [
+                    mesh.vertices[face[0] as usize].get_position(),
+                    mesh.vertices[face[1] as usize].get_position(),
+                    mesh.vertices[face[2] as usize].get_position(),
+                ]
+            })
+        })
+        .collect();
+    let tree = TriangleTree::new(triangles);
+    let directions = hemisphere_directions(settings.samples);
+
+    let bake_vertex = |vertex: &mut ModelVertex| {
+        let normal = vertex.get_normal();
+        if normal.magnitude2() <= f32::EPSILON {
+            return;
+        }
+
+        // Turns the directions around the z axis to be around the normal
+        let normal = normal.normalize();
+        let helper = if normal.x.abs() < 0.9 {
+            Vector3::unit_x()
+        } else {
+            Vector3::unit_y()
+        };
+        let tangent = normal.cross(helper).normalize();
+        let bitangent = normal.cross(tangent);
+
+        let origin = vertex.get_position() + normal * settings.bias;
+        let hits = directions
+            .iter()
+            .filter(|direction| {
+                let direction =
+                    tangent * direction.x + bitangent * direction.y + normal * direction.z;
+                tree.hits(origin, direction, settings.distance)
+            })
+            .count();
+
+        let occluded = hits as f32 / directions.len() as f32;
+        *vertex = vertex.with_occlusion(1.0 - settings.strength * occluded);
+    };
+
+    // Every vertex is baked on its own so they are split between the threads
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+    thread::scope(|scope| {
+        for mesh in meshes.iter_mut() {
+            let chunk_size = mesh.vertices.len().div_ceil(threads).max(1);
+            for chunk in mesh.vertices.chunks_mut(chunk_size) {
+                scope.spawn(move || chunk.iter_mut().for_each(bake_vertex));
+            }
+        }
+    });
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use wgpu::{include_wgsl, Device, Face, IndexFormat, RenderPipeline, TextureFormat};
+
+use crate::{
+    construct_render_pipline_from_layouts,
+    layouts::{LayoutKind, LayoutRegistry},
+    model::{
+        material::{AlphaMode, DepthBias, FaceMode, Material},
+        mesh::{Mesh, Topology},
+    },
+};
+
+/// The state of a mesh and its material that needs a pipeline of its own, draws with the
+/// same variant are drawn with the same pipeline
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct PipelineVariant {
+    pub faces: FaceMode,
+    pub depth_bias: DepthBias,
+    pub topology: Topology,
+    /// Blended materials are drawn without writing depth
+    pub blended: bool,
+}
+
+impl PipelineVariant {
+    pub fn new(mesh: &Mesh, material: &Material) -> Self {
+        Self {
+            faces: material.get_faces(),
+            depth_bias: material.get_depth_bias(),
+            topology: mesh.get_topology(),
+            blended: material.get_alpha_mode() == AlphaMode::Blend,
+        }
+    }
+
+    // Lines and points have no faces to cull
+    pub fn cull_mode(&self) -> Option<Face> {
+        if self.topology.is_triangles() {
+            self.faces.cull_mode()
+        } else {
+            None
+        }
+    }
+
+    // Strips have to be told the format of the indices
+    pub fn strip_index_format(&self) -> Option<IndexFormat> {
+        (self.topology == Topology::LineStrip).then_some(IndexFormat::Uint32)
+    }
+}
+
+/// The model, scatter, and morph pipelines for every variant that is drawn, a variant is
+/// created the first time a material needs it and kept after
+pub(crate) struct ModelPipelines {
+    format: TextureFormat,
+    model: HashMap<PipelineVariant, RenderPipeline>,
+    scatter: HashMap<PipelineVariant, RenderPipeline>,
+    // Morph pipelines are only created for the variants of meshes with morph targets
+    morph: HashMap<PipelineVariant, RenderPipeline>,
+}
+
+impl ModelPipelines {
+    /// Creates the pipelines of the default variant
+    pub fn new(device: &Device, layouts: &mut LayoutRegistry, format: TextureFormat) -> Self {
+        let mut pipelines = Self {
+            format,
+            model: HashMap::new(),
+            scatter: HashMap::new(),
+            morph: HashMap::new(),
+        };
+        pipelines.prepare(device, layouts, PipelineVariant::default());
+
+        pipelines
+    }
+
+    /// Creates the pipelines of a variant if they do not exist yet
+    pub fn prepare(
+        &mut self,
+        device: &Device,
+        layouts: &mut LayoutRegistry,
+        variant: PipelineVariant,
+    ) {
+        if !self.model.contains_key(&variant) {
+            let pipeline = construct_render_pipline_from_layouts(
+                layouts.register_pipeline(
+                    "Model",
+                    &[LayoutKind::Material, LayoutKind::Camera, LayoutKind::Lights],
+                ),
+                include_wgsl!("./shaders/vertex_shader.wgsl"),
+                device,
+                self.format,
+                String::from("Model"),
+                variant,
+            );
+            self.model.insert(variant, pipeline);
+        }
+
+        if !self.scatter.contains_key(&variant) {
+            let pipeline = construct_render_pipline_from_layouts(
+                layouts.register_pipeline(
+                    "Scatter",
+                    &[
+                        LayoutKind::Material,
+                        LayoutKind::Camera,
+                        LayoutKind::Lights,
+                        LayoutKind::Scatter,
+                    ],
+                ),
+                include_wgsl!("./shaders/scatter_vertex_shader.wgsl"),
+                device,
+                self.format,
+                String::from("Scatter"),
+                variant,
+            );
+            self.scatter.insert(variant, pipeline);
+        }
+    }
+
+    /// Creates the morph pipeline of a variant if it does not exist yet
+    pub fn prepare_morph(
+        &mut self,
+        device: &Device,
+        layouts: &mut LayoutRegistry,
+        variant: PipelineVariant,
+    ) {
+        if self.morph.contains_key(&variant) {
+            return;
+        }
+
+        let pipeline = construct_render_pipline_from_layouts(
+            layouts.register_pipeline(
+                "Morph",
+                &[
+                    LayoutKind::Material,
+                    LayoutKind::Camera,
+                    LayoutKind::Lights,
+                    LayoutKind::Morph,
+                ],
+            ),
+            include_wgsl!("./shaders/morph_vertex_shader.wgsl"),
+            device,
+            self.format,
+            String::from("Morph"),
+            variant,
+        );
+        self.morph.insert(variant, pipeline);
+    }
+
+    /// Gets the model pipeline of a variant, it has to be prepared first
+    pub fn get_model(&self, variant: PipelineVariant) -> &RenderPipeline {
+        &self.model[&variant]
+    }
+
+    /// Gets the scatter pipeline of a variant, it has to be prepared first
+    pub fn get_scatter(&self, variant: PipelineVariant) -> &RenderPipeline {
+        &self.scatter[&variant]
+    }
+
+    /// Gets the morph pipeline of a variant, it has to be prepared first
+    pub fn get_morph(&self, variant: PipelineVariant) -> &RenderPipeline {
+        &self.morph[&variant]
+    }
+}
@@ -0,0 +1,272 @@
+use wgpu::{
+    include_wgsl, BindGroup, BlendState, Buffer, ColorTargetState, ColorWrites, CompareFunction,
+    DepthBiasState, DepthStencilState, Device, Face, FragmentState, FrontFace, IndexFormat,
+    MultisampleState, PipelineCompilationOptions, PipelineLayout, PipelineLayoutDescriptor,
+    PolygonMode, PrimitiveState, PrimitiveTopology, RenderPass, RenderPipeline,
+    RenderPipelineDescriptor, ShaderModule, StencilFaceState, StencilOperation, StencilState,
+    TextureFormat, VertexState,
+};
+
+use crate::{
+    helium_texture,
+    instance::InstanceRaw,
+    layouts::{LayoutKind, LayoutRegistry},
+    model::{model_vertex::ModelVertex, vertex::Vertex, Model},
+    uniform_ring::UniformRing,
+};
+
+// Value written to the stencil buffer where a highlighted model is drawn
+const OUTLINE_STENCIL_REFERENCE: u32 = 1;
+
+/// A colored outline drawn around an object
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outline {
+    pub color: [f32; 4],
+    /// Thickness of the outline in world units
+    pub width: f32,
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 0.6, 0.0, 1.0],
+            width: 0.03,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct OutlineUniform {
+    color: [f32; 4],
+    width: f32,
+    _padding: [f32; 3],
+}
+
+impl From<Outline> for OutlineUniform {
+    fn from(outline: Outline) -> Self {
+        Self {
+            color: outline.color,
+            width: outline.width,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+// The outline of a single object in the renderer
+struct ObjectOutline {
+    object_index: usize,
+    outline: Outline,
+    // Offset of the outline uniform in the uniform ring this frame
+    ring_offset: u32,
+}
+
+/// Draws outlines around highlighted objects by marking the objects in the stencil buffer
+/// and drawing a slightly larger copy of them everywhere that is not marked
+pub struct OutlineRenderer {
+    mask_pipeline: RenderPipeline,
+    outline_pipeline: RenderPipeline,
+    outlines: Vec<ObjectOutline>,
+}
+
+impl OutlineRenderer {
+    pub fn new(device: &Device, layouts: &LayoutRegistry, format: TextureFormat) -> Self {
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Outline Render Pipeline Layout"),
+            bind_group_layouts: &[
+                layouts.get(LayoutKind::Camera),
+                layouts.get(LayoutKind::UniformRing),
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("./shaders/outline_shader.wgsl"));
+
+        // Marks every pixel of the object, even behind the scene, so the outline stays outside
+        let mask_pipeline = Self::create_pipeline(
+            device,
+            &pipeline_layout,
+            &shader,
+            format,
+            "Outline Mask",
+            "vs_mask",
+            ColorWrites::empty(),
+            DepthStencilState {
+                format: helium_texture::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::Always,
+                stencil: Self::stencil_state(CompareFunction::Always, StencilOperation::Replace),
+                bias: DepthBiasState::default(),
+            },
+        );
+
+        let outline_pipeline = Self::create_pipeline(
+            device,
+            &pipeline_layout,
+            &shader,
+            format,
+            "Outline",
+            "vs_outline",
+            ColorWrites::ALL,
+            DepthStencilState {
+                format: helium_texture::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::LessEqual,
+                stencil: Self::stencil_state(CompareFunction::NotEqual, StencilOperation::Keep),
+                bias: DepthBiasState::default(),
+            },
+        );
+
+        Self {
+            mask_pipeline,
+            outline_pipeline,
+            outlines: Vec::new(),
+        }
+    }
+
+    fn stencil_state(compare: CompareFunction, pass_op: StencilOperation) -> StencilState {
+        let face = StencilFaceState {
+            compare,
+            fail_op: StencilOperation::Keep,
+            depth_fail_op: StencilOperation::Keep,
+            pass_op,
+        };
+
+        StencilState {
+            front: face,
+            back: face,
+            read_mask: 0xff,
+            write_mask: 0xff,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_pipeline(
+        device: &Device,
+        layout: &PipelineLayout,
+        shader: &ShaderModule,
+        format: TextureFormat,
+        name: &str,
+        vertex_entry_point: &str,
+        write_mask: ColorWrites,
+        depth_stencil: DepthStencilState,
+    ) -> RenderPipeline {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some(&(name.to_string() + " Render Pipeline")),
+            layout: Some(layout),
+            vertex: VertexState {
+                module: shader,
+                entry_point: Some(vertex_entry_point),
+                buffers: &[ModelVertex::desc(), InstanceRaw::desc()],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                cull_mode: Some(Face::Back),
+                polygon_mode: PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(depth_stencil),
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    pub fn has_outlines(&self) -> bool {
+        !self.outlines.is_empty()
+    }
+
+    /// Adds, changes, or removes the outline of an object
+    ///
+    /// # Arguments
+    ///
+    /// * `object_index` - The index of the object in the renderer
+    /// * `outline` - The outline to draw around the object, `None` removes the outline
+    pub fn set_outline(&mut self, object_index: usize, outline: Option<Outline>) {
+        let position = self
+            .outlines
+            .iter()
+            .position(|outline| outline.object_index == object_index);
+
+        match (outline, position) {
+            (Some(outline), Some(position)) => self.outlines[position].outline = outline,
+            (Some(outline), None) => self.outlines.push(ObjectOutline {
+                object_index,
+                outline,
+                ring_offset: 0,
+            }),
+            (None, Some(position)) => {
+                self.outlines.remove(position);
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// Pushes the uniforms of the outlines into the uniform ring for this frame
+    pub fn prepare(&mut self, ring: &mut UniformRing) {
+        for outline in self.outlines.iter_mut() {
+            outline.ring_offset = ring.push(&OutlineUniform::from(outline.outline));
+        }
+    }
+
+    /// Draws the outlines, the render pass needs a depth stencil attachment with the
+    /// scene depth and a cleared stencil
+    pub fn draw(
+        &self,
+        render_pass: &mut RenderPass,
+        models: &[Option<Model>],
+        instance_buffer: &Buffer,
+        camera_bind_group: &BindGroup,
+        ring: &UniformRing,
+    ) {
+        if self.outlines.is_empty() {
+            return;
+        }
+
+        render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_stencil_reference(OUTLINE_STENCIL_REFERENCE);
+
+        // Every object is masked first so outlines are never drawn over a highlighted object
+        for pipeline in [&self.mask_pipeline, &self.outline_pipeline] {
+            render_pass.set_pipeline(pipeline);
+
+            for outline in self.outlines.iter() {
+                let Some(Some(model)) = models.get(outline.object_index) else {
+                    continue;
+                };
+
+                render_pass.set_bind_group(1, ring.get_bind_group(), &[outline.ring_offset]);
+                for mesh in model.get_meshes().iter() {
+                    // Lines and points have no silhouette to outline
+                    if !mesh.get_topology().is_triangles() {
+                        continue;
+                    }
+
+                    render_pass.set_vertex_buffer(0, mesh.get_vertex_buffer().slice(..));
+                    render_pass
+                        .set_index_buffer(mesh.get_index_buffer().slice(..), IndexFormat::Uint32);
+                    render_pass.draw_indexed(0..mesh.get_num_elements(), 0, mesh.get_instances());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use wgpu::{
+    include_wgsl,
+    util::{BufferInitDescriptor, DeviceExt},
+    BlendState, Buffer, BufferAddress, BufferUsages, ColorTargetState, ColorWrites,
+    DepthStencilState, Device, FragmentState, FrontFace, MultisampleState,
+    PipelineCompilationOptions, PipelineLayoutDescriptor, PolygonMode, PrimitiveState,
+    PrimitiveTopology, Queue, RenderPass, RenderPipeline, RenderPipelineDescriptor, TextureFormat,
+    VertexAttribute, VertexBufferLayout, VertexFormat, VertexState, VertexStepMode,
+};
+
+use crate::{color::LinearRgba, model::vertex::Vertex, ui::UiLayout};
+
+// Number of vertices used to draw a single quad
+const QUAD_VERTICES: usize = 6;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct OverlayVertex {
+    position: [f32; 3],
+    color: [f32; 4],
+}
+
+impl Vertex for OverlayVertex {
+    fn desc() -> VertexBufferLayout<'static> {
+        use std::mem;
+        VertexBufferLayout {
+            array_stride: mem::size_of::<OverlayVertex>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &[
+                // Position
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: VertexFormat::Float32x3,
+                },
+                // Color
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 3]>() as BufferAddress,
+                    shader_location: 1,
+                    format: VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// A flat colored rectangle drawn in the overlay below the text
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OverlayQuad {
+    /// Position of the top left of the quad in pixels
+    pub position: (f32, f32),
+    /// Width and height of the quad in pixels
+    pub size: (f32, f32),
+    pub color: [f32; 4],
+    /// Places the quad relative to the screen instead of the raw position
+    pub layout: Option<UiLayout>,
+    /// Depth of the quad in the scene, quads with a depth are hidden behind the scene
+    pub depth: Option<f32>,
+}
+
+impl OverlayQuad {
+    pub fn new<C>(position: (f32, f32), size: (f32, f32), color: C) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        Self {
+            position,
+            size,
+            color: color.into().to_array(),
+            layout: None,
+            depth: None,
+        }
+    }
+
+    pub fn with_layout(mut self, layout: Option<UiLayout>) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn with_depth(mut self, depth: Option<f32>) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Finds the top left corner of the quad in pixels on a screen of the given size
+    pub fn top_left(&self, screen_size: (f32, f32)) -> (f32, f32) {
+        match self.layout {
+            Some(layout) => layout.resolve_rect(screen_size, self.size),
+            None => self.position,
+        }
+    }
+
+    // Creates the two triangles of the quad in normalized device coordinates
+    fn vertices(&self, screen_size: (f32, f32)) -> [OverlayVertex; QUAD_VERTICES] {
+        let (x, y) = self.top_left(screen_size);
+
+        let left = x / screen_size.0 * 2.0 - 1.0;
+        let right = (x + self.size.0) / screen_size.0 * 2.0 - 1.0;
+        let top = 1.0 - y / screen_size.1 * 2.0;
+        let bottom = 1.0 - (y + self.size.1) / screen_size.1 * 2.0;
+
+        let depth = self.depth.unwrap_or(0.0);
+        let vertex = |x, y| OverlayVertex {
+            position: [x, y, depth],
+            color: self.color,
+        };
+
+        [
+            vertex(left, top),
+            vertex(left, bottom),
+            vertex(right, bottom),
+            vertex(left, top),
+            vertex(right, bottom),
+            vertex(right, top),
+        ]
+    }
+}
+
+/// Draws the overlay quads in a single draw call
+pub struct OverlayRenderer {
+    pipeline: RenderPipeline,
+    vertex_buffer: Buffer,
+    vertex_capacity: usize,
+    vertex_count: u32,
+}
+
+impl OverlayRenderer {
+    /// Creates the overlay renderer
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The format of the surface to draw to
+    /// * `depth_stencil` - The depth test to hide the quads behind the scene with, if any
+    pub fn new(
+        device: &Device,
+        format: TextureFormat,
+        depth_stencil: Option<DepthStencilState>,
+    ) -> Self {
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Overlay Render Pipeline Layout"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("./shaders/overlay_shader.wgsl"));
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Overlay Render Pipeline"),
+            layout: Some(&layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[OverlayVertex::desc()],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil,
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let vertex_capacity = QUAD_VERTICES;
+        let vertex_buffer = Self::create_vertex_buffer(device, vertex_capacity);
+
+        Self {
+            pipeline,
+            vertex_buffer,
+            vertex_capacity,
+            vertex_count: 0,
+        }
+    }
+
+    fn create_vertex_buffer(device: &Device, capacity: usize) -> Buffer {
+        device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Overlay vertex buffer"),
+            contents: bytemuck::cast_slice(&vec![
+                OverlayVertex {
+                    position: [0.0; 3],
+                    color: [0.0; 4],
+                };
+                capacity
+            ]),
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        })
+    }
+
+    /// Writes the vertices of the quads to the gpu, growing the buffer if needed
+    ///
+    /// # Arguments
+    ///
+    /// * `quads` - The quads to draw in order from back to front
+    /// * `screen_size` - The width and height of the screen in pixels
+    pub fn prepare<'a, I>(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        quads: I,
+        screen_size: (f32, f32),
+    ) where
+        I: IntoIterator<Item = &'a OverlayQuad>,
+    {
+        let vertices = quads
+            .into_iter()
+            .flat_map(|quad| quad.vertices(screen_size))
+            .collect::<Vec<_>>();
+
+        if vertices.len() > self.vertex_capacity {
+            self.vertex_capacity = vertices.len().next_power_of_two();
+            self.vertex_buffer = Self::create_vertex_buffer(device, self.vertex_capacity);
+        }
+
+        if !vertices.is_empty() {
+            queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+        }
+
+        self.vertex_count = vertices.len() as u32;
+    }
+
+    pub fn draw(&self, render_pass: &mut RenderPass) {
+        if self.vertex_count == 0 {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..self.vertex_count, 0..1);
+    }
+}
@@ -0,0 +1,311 @@
+use std::sync::{mpsc, Arc};
+
+use wgpu::{
+    include_wgsl, BindGroup, Buffer, BufferDescriptor, BufferUsages, Color, ColorTargetState,
+    ColorWrites, CommandEncoder, CompareFunction, DepthBiasState, DepthStencilState, Device,
+    Extent3d, Face, FragmentState, FrontFace, IndexFormat, LoadOp, MapMode, MultisampleState,
+    Operations, Origin3d, PipelineCompilationOptions, PipelineLayoutDescriptor, PolygonMode,
+    PrimitiveState, PrimitiveTopology, RenderPassColorAttachment, RenderPassDepthStencilAttachment,
+    RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, StencilState, StoreOp,
+    TexelCopyBufferInfo, TexelCopyBufferLayout, TexelCopyTextureInfo, Texture, TextureAspect,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
+    TextureViewDescriptor, VertexState, COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+
+use crate::{
+    helium_texture,
+    instance::InstanceRaw,
+    layouts::{LayoutKind, LayoutRegistry},
+    model::{model_vertex::ModelVertex, vertex::Vertex, Model},
+};
+
+// Format of the texture the instance ids are drawn to
+const PICK_FORMAT: TextureFormat = TextureFormat::R32Uint;
+
+/// A pick that is read back from the gpu after the next frame is drawn
+pub struct PickRequest {
+    receiver: mpsc::Receiver<Option<u32>>,
+}
+
+impl PickRequest {
+    /// Checks if the pick has been read back from the gpu
+    ///
+    /// # Returns
+    ///
+    /// `None` while the pick is in flight, otherwise the index of the picked instance
+    /// in the instance buffer or `None` if nothing was under the position
+    pub fn try_receive(&self) -> Option<Option<u32>> {
+        match self.receiver.try_recv() {
+            Ok(instance_index) => Some(instance_index),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(None),
+        }
+    }
+}
+
+// A pick that will be copied from the id texture in the next frame
+struct QueuedPick {
+    position: (u32, u32),
+    sender: mpsc::Sender<Option<u32>>,
+}
+
+// A pick that has been copied and is waiting for its buffer to be mapped
+struct CopiedPick {
+    buffer: Arc<Buffer>,
+    sender: mpsc::Sender<Option<u32>>,
+}
+
+/// Draws the instance index of every model into an offscreen texture so positions on
+/// the screen can be resolved to objects without testing rays against the meshes
+pub struct PickingRenderer {
+    pipeline: RenderPipeline,
+    id_texture: Texture,
+    id_view: TextureView,
+    depth_view: TextureView,
+    queued: Vec<QueuedPick>,
+    copied: Vec<CopiedPick>,
+}
+
+impl PickingRenderer {
+    pub fn new(device: &Device, layouts: &LayoutRegistry, size: (u32, u32)) -> Self {
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Pick Render Pipeline Layout"),
+            bind_group_layouts: &[layouts.get(LayoutKind::Camera)],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("./shaders/pick_shader.wgsl"));
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Pick Render Pipeline"),
+            layout: Some(&layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[ModelVertex::desc(), InstanceRaw::desc()],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format: PICK_FORMAT,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                cull_mode: Some(Face::Back),
+                polygon_mode: PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: helium_texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Less,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let (id_texture, id_view, depth_view) = Self::create_targets(device, size);
+
+        Self {
+            pipeline,
+            id_texture,
+            id_view,
+            depth_view,
+            queued: Vec::new(),
+            copied: Vec::new(),
+        }
+    }
+
+    fn create_targets(device: &Device, size: (u32, u32)) -> (Texture, TextureView, TextureView) {
+        let extent = Extent3d {
+            width: size.0.max(1),
+            height: size.1.max(1),
+            depth_or_array_layers: 1,
+        };
+
+        let create_texture = |label, format, usage| {
+            device.create_texture(&TextureDescriptor {
+                label: Some(label),
+                size: extent,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[],
+            })
+        };
+
+        let id_texture = create_texture(
+            "Pick Id Texture",
+            PICK_FORMAT,
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+        );
+        let depth_texture = create_texture(
+            "Pick Depth Texture",
+            helium_texture::DEPTH_FORMAT,
+            TextureUsages::RENDER_ATTACHMENT,
+        );
+
+        let id_view = id_texture.create_view(&TextureViewDescriptor::default());
+        let depth_view = depth_texture.create_view(&TextureViewDescriptor::default());
+
+        (id_texture, id_view, depth_view)
+    }
+
+    // Call this when resizing the window
+    pub fn resize(&mut self, device: &Device, size: (u32, u32)) {
+        (self.id_texture, self.id_view, self.depth_view) = Self::create_targets(device, size);
+    }
+
+    /// Queues a pick at a pixel on the screen
+    pub fn pick(&mut self, position: (u32, u32)) -> PickRequest {
+        let (sender, receiver) = mpsc::channel();
+        self.queued.push(QueuedPick { position, sender });
+        PickRequest { receiver }
+    }
+
+    /// Draws the ids of the models and copies the picked pixels when there are picks queued
+    pub fn draw(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        models: &[Option<Model>],
+        instance_buffer: &Buffer,
+        camera_bind_group: &BindGroup,
+    ) {
+        if self.queued.is_empty() {
+            return;
+        }
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Pick Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &self.id_view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::TRANSPARENT),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: &self.depth_view,
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Clear(1.0),
+                        store: StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+            render_pass.set_bind_group(0, camera_bind_group, &[]);
+
+            for model in models.iter().flatten() {
+                for mesh in model.get_meshes().iter() {
+                    // Lines and points are too thin to be picked
+                    if !mesh.get_topology().is_triangles() {
+                        continue;
+                    }
+
+                    render_pass.set_vertex_buffer(0, mesh.get_vertex_buffer().slice(..));
+                    render_pass
+                        .set_index_buffer(mesh.get_index_buffer().slice(..), IndexFormat::Uint32);
+                    render_pass.draw_indexed(0..mesh.get_num_elements(), 0, mesh.get_instances());
+                }
+            }
+        }
+
+        let (width, height) = (self.id_texture.width(), self.id_texture.height());
+
+        for pick in self.queued.drain(..) {
+            // Picks outside of the window never hit anything
+            if pick.position.0 >= width || pick.position.1 >= height {
+                _ = pick.sender.send(None);
+                continue;
+            }
+
+            let buffer = Arc::new(device.create_buffer(&BufferDescriptor {
+                label: Some("Pick buffer"),
+                size: COPY_BYTES_PER_ROW_ALIGNMENT as u64,
+                usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }));
+
+            encoder.copy_texture_to_buffer(
+                TexelCopyTextureInfo {
+                    texture: &self.id_texture,
+                    mip_level: 0,
+                    origin: Origin3d {
+                        x: pick.position.0,
+                        y: pick.position.1,
+                        z: 0,
+                    },
+                    aspect: TextureAspect::All,
+                },
+                TexelCopyBufferInfo {
+                    buffer: &buffer,
+                    layout: TexelCopyBufferLayout {
+                        offset: 0,
+                        bytes_per_row: Some(COPY_BYTES_PER_ROW_ALIGNMENT),
+                        rows_per_image: Some(1),
+                    },
+                },
+                Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+            );
+
+            self.copied.push(CopiedPick {
+                buffer,
+                sender: pick.sender,
+            });
+        }
+    }
+
+    /// Starts reading back the picks that were copied, call this after submitting the frame
+    pub fn map_copied(&mut self) {
+        for pick in self.copied.drain(..) {
+            let buffer = pick.buffer.clone();
+            let sender = pick.sender;
+
+            pick.buffer
+                .slice(..)
+                .map_async(MapMode::Read, move |result| {
+                    let id = result.ok().map(|_| {
+                        let id = {
+                            let data = buffer.slice(..).get_mapped_range();
+                            u32::from_ne_bytes([data[0], data[1], data[2], data[3]])
+                        };
+                        buffer.unmap();
+                        id
+                    });
+
+                    // An id of 0 means that nothing was drawn at the position
+                    _ = sender.send(id.and_then(|id| id.checked_sub(1)));
+                });
+        }
+    }
+}
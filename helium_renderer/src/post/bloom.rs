@@ -0,0 +1,377 @@
+use wgpu::{
+    include_wgsl,
+    util::{BufferInitDescriptor, DeviceExt},
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, BlendComponent,
+    BlendFactor, BlendOperation, BlendState, Buffer, BufferBindingType, BufferUsages, Color,
+    ColorTargetState, ColorWrites, CommandEncoder, Device, Extent3d, FilterMode, FragmentState,
+    LoadOp, MultisampleState, Operations, PipelineCompilationOptions, PipelineLayout,
+    PipelineLayoutDescriptor, PrimitiveState, Queue, RenderPassColorAttachment,
+    RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, Sampler, SamplerBindingType,
+    SamplerDescriptor, ShaderModule, ShaderStages, StoreOp, TextureDescriptor, TextureDimension,
+    TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
+    VertexState,
+};
+
+use super::HDR_FORMAT;
+
+// Most textures in the downsample chain, the first one is half the size of the screen
+const MAX_BLOOM_MIPS: u32 = 6;
+
+/// Makes the bright parts of the scene like emissive materials glow into their surroundings
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bloom {
+    /// How much of the glow is added to the scene
+    pub intensity: f32,
+    /// Brightness in the hdr scene where the glow starts, colors above 1 are brighter
+    /// than white
+    pub threshold: f32,
+    /// Width of the soft transition around the threshold
+    pub knee: f32,
+    /// Spread of the blur in texels of each step, larger values glow wider but blockier
+    pub radius: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self {
+            intensity: 0.1,
+            threshold: 1.0,
+            knee: 0.5,
+            radius: 1.0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct BloomUniform {
+    threshold: f32,
+    knee: f32,
+    intensity: f32,
+    radius: f32,
+}
+
+impl From<&Bloom> for BloomUniform {
+    fn from(value: &Bloom) -> Self {
+        Self {
+            threshold: value.threshold.max(0.0),
+            knee: value.knee.max(0.0),
+            intensity: value.intensity.max(0.0),
+            radius: value.radius.max(0.0),
+        }
+    }
+}
+
+/// Downsamples the bright parts of the scene into a mip chain, blurs them back up and
+/// adds them onto the scene
+pub(crate) struct BloomPass {
+    layout: BindGroupLayout,
+    prefilter_pipeline: RenderPipeline,
+    downsample_pipeline: RenderPipeline,
+    upsample_pipeline: RenderPipeline,
+    composite_pipeline: RenderPipeline,
+    buffer: Buffer,
+    sampler: Sampler,
+    mip_views: Vec<TextureView>,
+    scene_bind_group: BindGroup,
+    // Each bind group reads the mip at its index
+    mip_bind_groups: Vec<BindGroup>,
+}
+
+impl BloomPass {
+    pub fn new(device: &Device, size: (u32, u32), scene_view: &TextureView) -> Self {
+        let fragment_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty,
+            count: None,
+        };
+
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Bloom Bind Group Layout"),
+            entries: &[
+                fragment_entry(
+                    0,
+                    BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                ),
+                fragment_entry(
+                    1,
+                    BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: TextureViewDimension::D2,
+                        sample_type: TextureSampleType::Float { filterable: true },
+                    },
+                ),
+                fragment_entry(2, BindingType::Sampler(SamplerBindingType::Filtering)),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Bloom Render Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("../shaders/bloom.wgsl"));
+
+        // The upsampled mips are added on top of the larger ones, the alpha is kept
+        let additive = BlendState {
+            color: BlendComponent {
+                src_factor: BlendFactor::One,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+            alpha: BlendComponent {
+                src_factor: BlendFactor::Zero,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+        };
+
+        let create_pipeline = |entry_point, blend| {
+            Self::create_pipeline(device, &pipeline_layout, &shader, entry_point, blend)
+        };
+
+        let prefilter_pipeline = create_pipeline("fs_prefilter", None);
+        let downsample_pipeline = create_pipeline("fs_downsample", None);
+        let upsample_pipeline = create_pipeline("fs_upsample", Some(additive));
+        let composite_pipeline = create_pipeline("fs_composite", Some(additive));
+
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Bloom buffer"),
+            contents: bytemuck::cast_slice(&[BloomUniform::from(&Bloom::default())]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Bloom Sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let (mip_views, scene_bind_group, mip_bind_groups) =
+            Self::create_mips(device, &layout, &buffer, &sampler, size, scene_view);
+
+        Self {
+            layout,
+            prefilter_pipeline,
+            downsample_pipeline,
+            upsample_pipeline,
+            composite_pipeline,
+            buffer,
+            sampler,
+            mip_views,
+            scene_bind_group,
+            mip_bind_groups,
+        }
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        layout: &PipelineLayout,
+        shader: &ShaderModule,
+        entry_point: &str,
+        blend: Option<BlendState>,
+    ) -> RenderPipeline {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some(&format!("Bloom {entry_point} Render Pipeline")),
+            layout: Some(layout),
+            vertex: VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: shader,
+                entry_point: Some(entry_point),
+                targets: &[Some(ColorTargetState {
+                    format: HDR_FORMAT,
+                    blend,
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        buffer: &Buffer,
+        sampler: &Sampler,
+        source: &TextureView,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Bloom bind group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(source),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+
+    // Creates the downsample chain and the bind groups that read the scene and each mip
+    fn create_mips(
+        device: &Device,
+        layout: &BindGroupLayout,
+        buffer: &Buffer,
+        sampler: &Sampler,
+        size: (u32, u32),
+        scene_view: &TextureView,
+    ) -> (Vec<TextureView>, BindGroup, Vec<BindGroup>) {
+        let width = (size.0 / 2).max(1);
+        let height = (size.1 / 2).max(1);
+        // Stop before the smallest mip gets thinner than a couple of pixels
+        let mip_count = (width.min(height).max(1).ilog2()).clamp(1, MAX_BLOOM_MIPS);
+
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Bloom Texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: mip_count,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let mip_views: Vec<TextureView> = (0..mip_count)
+            .map(|mip| {
+                texture.create_view(&TextureViewDescriptor {
+                    label: Some("Bloom Mip View"),
+                    base_mip_level: mip,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        let create_bind_group =
+            |source| Self::create_bind_group(device, layout, buffer, sampler, source);
+        let scene_bind_group = create_bind_group(scene_view);
+        let mip_bind_groups = mip_views.iter().map(create_bind_group).collect();
+
+        (mip_views, scene_bind_group, mip_bind_groups)
+    }
+
+    /// Call this when the scene texture is recreated
+    pub fn resize(&mut self, device: &Device, size: (u32, u32), scene_view: &TextureView) {
+        (self.mip_views, self.scene_bind_group, self.mip_bind_groups) = Self::create_mips(
+            device,
+            &self.layout,
+            &self.buffer,
+            &self.sampler,
+            size,
+            scene_view,
+        );
+    }
+
+    pub fn set_settings(&self, queue: &Queue, bloom: &Bloom) {
+        queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::cast_slice(&[BloomUniform::from(bloom)]),
+        );
+    }
+
+    fn draw_fullscreen(
+        encoder: &mut CommandEncoder,
+        label: &str,
+        target: &TextureView,
+        load: LoadOp<Color>,
+        pipeline: &RenderPipeline,
+        bind_group: &BindGroup,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: Operations {
+                    load,
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    /// Blurs the bright parts of the scene and adds them back onto the scene
+    pub fn draw(&self, encoder: &mut CommandEncoder, scene_view: &TextureView) {
+        // The first mip keeps the bright parts of the scene and every other one halves
+        // the previous mip
+        for (mip, target) in self.mip_views.iter().enumerate() {
+            let (pipeline, bind_group) = match mip {
+                0 => (&self.prefilter_pipeline, &self.scene_bind_group),
+                _ => (&self.downsample_pipeline, &self.mip_bind_groups[mip - 1]),
+            };
+
+            Self::draw_fullscreen(
+                encoder,
+                "Bloom Downsample Render Pass",
+                target,
+                LoadOp::Clear(Color::BLACK),
+                pipeline,
+                bind_group,
+            );
+        }
+
+        // Each mip is blurred onto the next larger one from the smallest up
+        for mip in (1..self.mip_views.len()).rev() {
+            Self::draw_fullscreen(
+                encoder,
+                "Bloom Upsample Render Pass",
+                &self.mip_views[mip - 1],
+                LoadOp::Load,
+                &self.upsample_pipeline,
+                &self.mip_bind_groups[mip],
+            );
+        }
+
+        Self::draw_fullscreen(
+            encoder,
+            "Bloom Composite Render Pass",
+            scene_view,
+            LoadOp::Load,
+            &self.composite_pipeline,
+            &self.mip_bind_groups[0],
+        );
+    }
+}
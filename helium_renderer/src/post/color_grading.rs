@@ -0,0 +1,156 @@
+use std::{fs, io, path::Path};
+
+use wgpu::{
+    util::{DeviceExt, TextureDataOrder},
+    Device, Extent3d, Queue, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    TextureView, TextureViewDescriptor,
+};
+
+/// A 3d lookup table that remaps the colors of the screen, loaded from a `.cube` file
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorGradingLut {
+    size: u32,
+    /// RGB colors with red changing the fastest, then green, then blue
+    colors: Vec<[f32; 3]>,
+}
+
+impl ColorGradingLut {
+    /// Loads a lookup table from a `.cube` file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Filepath to the `.cube` file
+    pub fn from_cube<P>(path: P) -> Result<Self, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        Self::parse_cube(&fs::read_to_string(path)?)
+    }
+
+    /// Parses the contents of a `.cube` file, only 3d tables are supported
+    pub fn parse_cube(contents: &str) -> Result<Self, io::Error> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut size = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut colors = Vec::new();
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let keyword = parts.next().unwrap_or_default();
+
+            let parse_triplet = |parts: std::str::SplitWhitespace| -> Result<[f32; 3], io::Error> {
+                let values = parts
+                    .map(|value| value.parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| invalid(format!("Invalid value in line '{line}': {e}")))?;
+
+                <[f32; 3]>::try_from(values)
+                    .map_err(|_| invalid(format!("Expected three values in line '{line}'")))
+            };
+
+            match keyword {
+                "TITLE" => {}
+                "LUT_1D_SIZE" => {
+                    return Err(invalid(String::from("1D lookup tables are not supported")))
+                }
+                "LUT_3D_SIZE" => {
+                    size = Some(
+                        parts
+                            .next()
+                            .and_then(|size| size.parse::<u32>().ok())
+                            .filter(|size| *size >= 2)
+                            .ok_or_else(|| {
+                                invalid(format!("Invalid lookup table size '{line}'"))
+                            })?,
+                    );
+                }
+                "DOMAIN_MIN" => domain_min = parse_triplet(parts)?,
+                "DOMAIN_MAX" => domain_max = parse_triplet(parts)?,
+                _ => {
+                    let color = parse_triplet(line.split_whitespace())?;
+
+                    // Colors are stored in the 0 to 1 range of the texture
+                    colors.push([0, 1, 2].map(|channel| {
+                        let range = domain_max[channel] - domain_min[channel];
+                        (color[channel] - domain_min[channel]) / range
+                    }));
+                }
+            }
+        }
+
+        let size = size.ok_or_else(|| invalid(String::from("Missing LUT_3D_SIZE")))?;
+        if colors.len() != (size * size * size) as usize {
+            return Err(invalid(format!(
+                "Expected {} colors but found {}",
+                size * size * size,
+                colors.len()
+            )));
+        }
+
+        Ok(Self { size, colors })
+    }
+
+    /// A table that leaves every color the same
+    pub fn identity(size: u32) -> Self {
+        let size = size.max(2);
+        let max = (size - 1) as f32;
+
+        let mut colors = Vec::with_capacity((size * size * size) as usize);
+        for blue in 0..size {
+            for green in 0..size {
+                for red in 0..size {
+                    colors.push([red as f32 / max, green as f32 / max, blue as f32 / max]);
+                }
+            }
+        }
+
+        Self { size, colors }
+    }
+
+    /// The number of entries along each side of the table
+    pub fn get_size(&self) -> u32 {
+        self.size
+    }
+
+    // Uploads the table to a 3d texture that can be filtered
+    pub(crate) fn create_view(&self, device: &Device, queue: &Queue) -> TextureView {
+        let data = self
+            .colors
+            .iter()
+            .flat_map(|color| {
+                let [red, green, blue] =
+                    color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+                [red, green, blue, 255]
+            })
+            .collect::<Vec<_>>();
+
+        device
+            .create_texture_with_data(
+                queue,
+                &TextureDescriptor {
+                    label: Some("Color Grading Texture"),
+                    size: Extent3d {
+                        width: self.size,
+                        height: self.size,
+                        depth_or_array_layers: self.size,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D3,
+                    format: TextureFormat::Rgba8Unorm,
+                    usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                    view_formats: &[],
+                },
+                TextureDataOrder::LayerMajor,
+                &data,
+            )
+            .create_view(&TextureViewDescriptor::default())
+    }
+}
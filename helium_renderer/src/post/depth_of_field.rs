@@ -0,0 +1,313 @@
+use wgpu::{
+    include_wgsl,
+    util::{BufferInitDescriptor, DeviceExt},
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
+    BufferBindingType, BufferUsages, Color, ColorTargetState, ColorWrites, CommandEncoder, Device,
+    Extent3d, FilterMode, FragmentState, LoadOp, MultisampleState, Operations,
+    PipelineCompilationOptions, PipelineLayoutDescriptor, PrimitiveState, Queue,
+    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor,
+    Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages, StoreOp, Texture,
+    TextureDescriptor, TextureDimension, TextureSampleType, TextureUsages, TextureView,
+    TextureViewDescriptor, TextureViewDimension, VertexState,
+};
+
+use crate::camera::Camera;
+
+use super::HDR_FORMAT;
+
+/// Where the depth of field is focused
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepthOfFieldFocus {
+    /// A fixed distance from the camera in world units
+    Distance(f32),
+    /// Focuses on whatever is under the center of the screen
+    Auto,
+}
+
+/// Blurs the parts of the scene that are nearer or farther than the focus
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthOfField {
+    pub focus: DepthOfFieldFocus,
+    /// How quickly the scene blurs away from the focus, larger is blurrier
+    pub aperture: f32,
+    /// Radius of the largest blur in pixels
+    pub max_blur: f32,
+}
+
+impl Default for DepthOfField {
+    fn default() -> Self {
+        Self {
+            focus: DepthOfFieldFocus::Distance(10.0),
+            aperture: 0.5,
+            max_blur: 12.0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct DepthOfFieldUniform {
+    znear: f32,
+    zfar: f32,
+    focus_distance: f32,
+    aperture: f32,
+    max_blur: f32,
+    autofocus: u32,
+    _padding: [f32; 2],
+}
+
+impl DepthOfFieldUniform {
+    fn new(depth_of_field: &DepthOfField, znear: f32, zfar: f32) -> Self {
+        let (focus_distance, autofocus) = match depth_of_field.focus {
+            DepthOfFieldFocus::Distance(distance) => (distance, 0),
+            DepthOfFieldFocus::Auto => (0.0, 1),
+        };
+
+        Self {
+            znear,
+            zfar,
+            focus_distance,
+            aperture: depth_of_field.aperture.max(0.0),
+            max_blur: depth_of_field.max_blur.max(0.0),
+            autofocus,
+            _padding: [0.0; 2],
+        }
+    }
+}
+
+/// Draws the blurred scene into a texture and copies it back into the scene
+pub(crate) struct DepthOfFieldPass {
+    pipeline: RenderPipeline,
+    layout: BindGroupLayout,
+    buffer: Buffer,
+    sampler: Sampler,
+    output: (Texture, TextureView),
+    bind_group: BindGroup,
+}
+
+impl DepthOfFieldPass {
+    pub fn new(
+        device: &Device,
+        size: (u32, u32),
+        scene_view: &TextureView,
+        depth_view: &TextureView,
+    ) -> Self {
+        let fragment_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty,
+            count: None,
+        };
+
+        let texture_entry = |binding, sample_type| {
+            fragment_entry(
+                binding,
+                BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: TextureViewDimension::D2,
+                    sample_type,
+                },
+            )
+        };
+
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Depth Of Field Bind Group Layout"),
+            entries: &[
+                fragment_entry(
+                    0,
+                    BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                ),
+                texture_entry(1, TextureSampleType::Float { filterable: true }),
+                fragment_entry(2, BindingType::Sampler(SamplerBindingType::Filtering)),
+                texture_entry(3, TextureSampleType::Depth),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Depth Of Field Render Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("../shaders/depth_of_field.wgsl"));
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Depth Of Field Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format: HDR_FORMAT,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Depth Of Field buffer"),
+            contents: bytemuck::cast_slice(&[DepthOfFieldUniform::new(
+                &DepthOfField::default(),
+                0.1,
+                100.0,
+            )]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Depth Of Field Sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let output = Self::create_output(device, size);
+        let bind_group =
+            Self::create_bind_group(device, &layout, &buffer, &sampler, scene_view, depth_view);
+
+        Self {
+            pipeline,
+            layout,
+            buffer,
+            sampler,
+            output,
+            bind_group,
+        }
+    }
+
+    fn create_output(device: &Device, size: (u32, u32)) -> (Texture, TextureView) {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Depth Of Field Texture"),
+            size: Extent3d {
+                width: size.0.max(1),
+                height: size.1.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        (texture, view)
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        buffer: &Buffer,
+        sampler: &Sampler,
+        scene_view: &TextureView,
+        depth_view: &TextureView,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Depth Of Field bind group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(scene_view),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::Sampler(sampler),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::TextureView(depth_view),
+                },
+            ],
+        })
+    }
+
+    /// Call this when the scene or depth textures are recreated
+    pub fn resize(
+        &mut self,
+        device: &Device,
+        size: (u32, u32),
+        scene_view: &TextureView,
+        depth_view: &TextureView,
+    ) {
+        self.output = Self::create_output(device, size);
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.layout,
+            &self.buffer,
+            &self.sampler,
+            scene_view,
+            depth_view,
+        );
+    }
+
+    /// Writes the settings with the clip planes of the camera the scene was drawn with
+    pub fn update(&self, queue: &Queue, depth_of_field: &DepthOfField, camera: &Camera) {
+        queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::cast_slice(&[DepthOfFieldUniform::new(
+                depth_of_field,
+                camera.znear,
+                camera.zfar,
+            )]),
+        );
+    }
+
+    /// Blurs the scene and copies the result back into the scene
+    pub fn draw(&self, encoder: &mut CommandEncoder, scene_texture: &Texture) {
+        {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Depth Of Field Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &self.output.1,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        encoder.copy_texture_to_texture(
+            self.output.0.as_image_copy(),
+            scene_texture.as_image_copy(),
+            scene_texture.size(),
+        );
+    }
+}
@@ -0,0 +1,285 @@
+use wgpu::{
+    include_wgsl,
+    util::{BufferInitDescriptor, DeviceExt},
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBindingType,
+    BufferDescriptor, BufferUsages, CommandEncoder, ComputePassDescriptor, ComputePipeline,
+    ComputePipelineDescriptor, Device, PipelineCompilationOptions, PipelineLayoutDescriptor, Queue,
+    ShaderStages, TextureSampleType, TextureView, TextureViewDimension,
+};
+
+// Number of luminance bins in the histogram
+const HISTOGRAM_BINS: u64 = 256;
+
+// Size of the workgroups that build the histogram
+const HISTOGRAM_WORKGROUP_SIZE: u32 = 16;
+
+/// How bright the scene is drawn before it is tonemapped
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Exposure {
+    /// A fixed exposure in stops, 0 leaves the scene as it is and each stop doubles it
+    Manual(f32),
+    /// Adapts to the average brightness of the scene like an eye or a camera
+    Auto {
+        /// Darkest luminance that is adapted to
+        min_luminance: f32,
+        /// Brightest luminance that is adapted to
+        max_luminance: f32,
+        /// How quickly the exposure adapts, higher is faster
+        adaptation_speed: f32,
+        /// Stops added to the adapted exposure
+        compensation: f32,
+    },
+}
+
+impl Default for Exposure {
+    fn default() -> Self {
+        Self::Manual(0.0)
+    }
+}
+
+impl Exposure {
+    /// Auto exposure with the default range and speed
+    pub fn auto() -> Self {
+        Self::Auto {
+            min_luminance: 0.005,
+            max_luminance: 50.0,
+            adaptation_speed: 1.5,
+            compensation: 0.0,
+        }
+    }
+
+    // Multiplier of the scene color, or of the adapted exposure when the exposure is auto
+    pub(crate) fn multiplier(&self) -> f32 {
+        match self {
+            Exposure::Manual(stops) => stops.exp2(),
+            Exposure::Auto { compensation, .. } => compensation.exp2(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct HistogramParams {
+    min_log_luminance: f32,
+    log_luminance_range: f32,
+    adaptation: f32,
+    _padding: f32,
+}
+
+/// Measures the average luminance of the scene with a histogram on the gpu
+pub(crate) struct AutoExposure {
+    histogram_pipeline: ComputePipeline,
+    average_pipeline: ComputePipeline,
+    layout: BindGroupLayout,
+    histogram_buffer: Buffer,
+    params_buffer: Buffer,
+    luminance_buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+impl AutoExposure {
+    pub fn new(device: &Device, scene_view: &TextureView) -> Self {
+        let storage_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Auto Exposure Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: TextureViewDimension::D2,
+                        sample_type: TextureSampleType::Float { filterable: false },
+                    },
+                    count: None,
+                },
+                storage_entry(1),
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(3),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Auto Exposure Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader =
+            device.create_shader_module(include_wgsl!("../shaders/luminance_histogram.wgsl"));
+
+        let create_pipeline = |entry_point| {
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some("Auto Exposure Pipeline"),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: PipelineCompilationOptions::default(),
+                cache: None,
+            })
+        };
+
+        let histogram_pipeline = create_pipeline("build_histogram");
+        let average_pipeline = create_pipeline("average_luminance");
+
+        let histogram_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Luminance histogram buffer"),
+            size: HISTOGRAM_BINS * std::mem::size_of::<u32>() as u64,
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let params_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Luminance histogram params buffer"),
+            size: std::mem::size_of::<HistogramParams>() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Starts adapted to middle gray so the first frames are not flashed
+        let luminance_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Adapted luminance buffer"),
+            contents: bytemuck::cast_slice(&[0.18_f32]),
+            usage: BufferUsages::STORAGE,
+        });
+
+        let bind_group = Self::create_bind_group(
+            device,
+            &layout,
+            scene_view,
+            &histogram_buffer,
+            &params_buffer,
+            &luminance_buffer,
+        );
+
+        Self {
+            histogram_pipeline,
+            average_pipeline,
+            layout,
+            histogram_buffer,
+            params_buffer,
+            luminance_buffer,
+            bind_group,
+        }
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        scene_view: &TextureView,
+        histogram_buffer: &Buffer,
+        params_buffer: &Buffer,
+        luminance_buffer: &Buffer,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Auto Exposure bind group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(scene_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: histogram_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: luminance_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    /// Call this when the scene texture is recreated
+    pub fn set_scene_view(&mut self, device: &Device, scene_view: &TextureView) {
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.layout,
+            scene_view,
+            &self.histogram_buffer,
+            &self.params_buffer,
+            &self.luminance_buffer,
+        );
+    }
+
+    /// Buffer holding the luminance that the exposure is adapted to
+    pub fn get_luminance_buffer(&self) -> &Buffer {
+        &self.luminance_buffer
+    }
+
+    /// Measures the scene and moves the adapted luminance towards its average
+    pub fn dispatch(
+        &self,
+        encoder: &mut CommandEncoder,
+        queue: &Queue,
+        exposure: &Exposure,
+        size: (u32, u32),
+        delta_time: f32,
+    ) {
+        let Exposure::Auto {
+            min_luminance,
+            max_luminance,
+            adaptation_speed,
+            ..
+        } = *exposure
+        else {
+            return;
+        };
+
+        let min_log_luminance = min_luminance.max(1e-5).log2();
+        let max_log_luminance = max_luminance.max(min_luminance * 2.0).log2();
+
+        queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::cast_slice(&[HistogramParams {
+                min_log_luminance,
+                log_luminance_range: max_log_luminance - min_log_luminance,
+                adaptation: 1.0 - (-delta_time * adaptation_speed).exp(),
+                _padding: 0.0,
+            }]),
+        );
+
+        let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("Auto Exposure Compute Pass"),
+            timestamp_writes: None,
+        });
+
+        compute_pass.set_bind_group(0, &self.bind_group, &[]);
+
+        compute_pass.set_pipeline(&self.histogram_pipeline);
+        compute_pass.dispatch_workgroups(
+            size.0.div_ceil(HISTOGRAM_WORKGROUP_SIZE),
+            size.1.div_ceil(HISTOGRAM_WORKGROUP_SIZE),
+            1,
+        );
+
+        compute_pass.set_pipeline(&self.average_pipeline);
+        compute_pass.dispatch_workgroups(1, 1, 1);
+    }
+}
@@ -0,0 +1,41 @@
+use super::screen_overlay::{ScreenOverlayUniform, ScreenOverlays, MAX_SCREEN_OVERLAYS};
+
+/// Effects that imitate the lens and film of a camera, each effect is off at 0
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LensEffects {
+    /// How much the corners of the screen are darkened, 1 darkens them to black
+    pub vignette: f32,
+    /// How far the red and blue channels are split apart towards the edges of the screen
+    pub chromatic_aberration: f32,
+    /// Strength of the animated noise added to the screen
+    pub grain: f32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct LensUniform {
+    vignette: f32,
+    chromatic_aberration: f32,
+    grain: f32,
+    // Seconds since the post stack was created, animates the grain and the distortions
+    time: f32,
+    // The screen overlays are drawn in the same pass as the lens effects
+    overlay_count: u32,
+    _padding: [u32; 3],
+    overlays: [ScreenOverlayUniform; MAX_SCREEN_OVERLAYS],
+}
+
+impl LensUniform {
+    pub fn new(lens: &LensEffects, overlays: &ScreenOverlays, time: f32) -> Self {
+        let (overlays, overlay_count) = overlays.to_uniform();
+        Self {
+            vignette: lens.vignette.clamp(0.0, 1.0),
+            chromatic_aberration: lens.chromatic_aberration.max(0.0),
+            grain: lens.grain.max(0.0),
+            time,
+            overlay_count,
+            _padding: [0; 3],
+            overlays,
+        }
+    }
+}
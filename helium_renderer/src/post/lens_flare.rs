@@ -0,0 +1,399 @@
+use cgmath::{InnerSpace, Vector4};
+use wgpu::{
+    include_wgsl,
+    util::{BufferInitDescriptor, DeviceExt},
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, BlendComponent, BlendFactor,
+    BlendOperation, BlendState, Buffer, BufferBindingType, BufferUsages, ColorTargetState,
+    ColorWrites, CommandEncoder, Device, FragmentState, LoadOp, MultisampleState, Operations,
+    PipelineCompilationOptions, PipelineLayoutDescriptor, PrimitiveState, Queue,
+    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor,
+    ShaderStages, StoreOp, TextureSampleType, TextureView, TextureViewDimension, VertexState,
+};
+
+use crate::{
+    camera::Camera,
+    light::{LightKind, LightUnits, Lights},
+};
+
+use super::HDR_FORMAT;
+
+/// Most lights that flare at once, the brightest lights are picked
+pub const MAX_FLARE_SOURCES: usize = 8;
+
+/// Most elements in the chain of a flare
+pub const MAX_FLARE_ELEMENTS: usize = 16;
+
+/// The shape of an element of a lens flare
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FlareShape {
+    /// A soft filled circle, like the ghosts of the lens
+    #[default]
+    Disk,
+    /// A thin circle
+    Ring,
+    /// A glow with streaks, used at the light for the glare of the sun
+    Glare,
+}
+
+/// One element of a lens flare that is drawn along the line from the light through the
+/// center of the screen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FlareElement {
+    /// Where the element is on the line, 0 at the light, 1 at the center of the screen,
+    /// and 2 at the light mirrored across the center
+    pub position: f32,
+    /// Radius of the element as a fraction of the height of the screen
+    pub size: f32,
+    /// Linear color the light is multiplied by
+    pub color: [f32; 3],
+    pub shape: FlareShape,
+}
+
+impl FlareElement {
+    pub fn new(position: f32, size: f32, color: [f32; 3], shape: FlareShape) -> Self {
+        Self {
+            position,
+            size,
+            color,
+            shape,
+        }
+    }
+}
+
+/// Flares and glare drawn over the sun and bright lights, they fade out when the light
+/// is hidden behind the scene or leaves the screen
+#[derive(Clone, Debug, PartialEq)]
+pub struct LensFlare {
+    /// The elements drawn for every light, only the first `MAX_FLARE_ELEMENTS` are drawn
+    pub elements: Vec<FlareElement>,
+    /// How bright the flares are compared to the light
+    pub intensity: f32,
+    /// Whether point lights flare, directional lights like the sun always do
+    pub point_lights: bool,
+    /// Brightness a light needs to flare, the brightness of point lights falls off with
+    /// their distance from the camera
+    pub min_brightness: f32,
+    /// Radius in pixels around the light that is tested against the depth of the scene,
+    /// larger radiuses fade the flares more smoothly as the light is covered
+    pub occlusion_radius: f32,
+}
+
+impl Default for LensFlare {
+    /// A glare at the light with a chain of ghosts through the center of the screen
+    fn default() -> Self {
+        Self {
+            elements: vec![
+                FlareElement::new(0.0, 0.35, [1.0, 0.95, 0.85], FlareShape::Glare),
+                FlareElement::new(0.45, 0.04, [0.4, 0.5, 1.0], FlareShape::Disk),
+                FlareElement::new(0.7, 0.08, [0.3, 1.0, 0.4], FlareShape::Ring),
+                FlareElement::new(1.2, 0.06, [1.0, 0.6, 0.3], FlareShape::Disk),
+                FlareElement::new(1.5, 0.12, [0.5, 0.4, 1.0], FlareShape::Disk),
+                FlareElement::new(2.0, 0.2, [0.3, 0.6, 1.0], FlareShape::Ring),
+            ],
+            intensity: 0.2,
+            point_lights: false,
+            min_brightness: 0.1,
+            occlusion_radius: 8.0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct FlareSourceRaw {
+    position: [f32; 4],
+    color: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct FlareElementRaw {
+    position: f32,
+    size: f32,
+    shape: u32,
+    _padding: f32,
+    color: [f32; 4],
+}
+
+impl From<&FlareElement> for FlareElementRaw {
+    fn from(element: &FlareElement) -> Self {
+        let [r, g, b] = element.color;
+        Self {
+            position: element.position,
+            size: element.size.max(0.0),
+            shape: match element.shape {
+                FlareShape::Disk => 0,
+                FlareShape::Ring => 1,
+                FlareShape::Glare => 2,
+            },
+            _padding: 0.0,
+            color: [r, g, b, 1.0],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct FlareUniform {
+    sources: [FlareSourceRaw; MAX_FLARE_SOURCES],
+    elements: [FlareElementRaw; MAX_FLARE_ELEMENTS],
+    source_count: u32,
+    element_count: u32,
+    occlusion_radius: f32,
+    intensity: f32,
+}
+
+// Brightness of a linear color as the eye sees it
+fn luminance(color: (f32, f32, f32)) -> f32 {
+    0.2126 * color.0 + 0.7152 * color.1 + 0.0722 * color.2
+}
+
+/// Draws the lens flares of the brightest lights onto the hdr scene, the elements are
+/// tested against the depth buffer on the gpu so there is no readback
+pub(crate) struct LensFlarePass {
+    pipeline: RenderPipeline,
+    layout: BindGroupLayout,
+    buffer: Buffer,
+    bind_group: BindGroup,
+    // Number of instances drawn this frame, a flare for every element of every source
+    instances: u32,
+}
+
+impl LensFlarePass {
+    pub fn new(device: &Device, depth_view: &TextureView) -> Self {
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Lens Flare Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: TextureViewDimension::D2,
+                        sample_type: TextureSampleType::Depth,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Lens Flare Render Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("../shaders/lens_flare.wgsl"));
+
+        // The flares are light added onto the scene
+        let additive = BlendState {
+            color: BlendComponent {
+                src_factor: BlendFactor::One,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+            alpha: BlendComponent {
+                src_factor: BlendFactor::Zero,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+        };
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Lens Flare Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format: HDR_FORMAT,
+                    blend: Some(additive),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Lens Flare buffer"),
+            contents: bytemuck::cast_slice(&[FlareUniform {
+                sources: [FlareSourceRaw::default(); MAX_FLARE_SOURCES],
+                elements: [FlareElementRaw::default(); MAX_FLARE_ELEMENTS],
+                source_count: 0,
+                element_count: 0,
+                occlusion_radius: 0.0,
+                intensity: 0.0,
+            }]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let bind_group = Self::create_bind_group(device, &layout, &buffer, depth_view);
+
+        Self {
+            pipeline,
+            layout,
+            buffer,
+            bind_group,
+            instances: 0,
+        }
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        buffer: &Buffer,
+        depth_view: &TextureView,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Lens Flare bind group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(depth_view),
+                },
+            ],
+        })
+    }
+
+    /// Call this when the depth texture is recreated
+    pub fn resize(&mut self, device: &Device, depth_view: &TextureView) {
+        self.bind_group = Self::create_bind_group(device, &self.layout, &self.buffer, depth_view);
+    }
+
+    /// Finds the brightest lights in front of the camera and sends them to the gpu
+    pub fn update(&mut self, queue: &Queue, flare: &LensFlare, camera: &Camera, lights: &Lights) {
+        let view_proj = camera.build_view_projection_matrix();
+        let eye = camera.eye;
+
+        let mut sources: Vec<(f32, FlareSourceRaw)> = lights
+            .get_lights()
+            .iter()
+            .filter_map(|light| {
+                let exposure = match light.get_units() {
+                    LightUnits::Candela => lights.get_exposure(),
+                    LightUnits::Unitless => 1.0,
+                };
+                let color = light.get_color();
+
+                // Directional lights are points at infinity against the direction they shine
+                let (clip, brightness) = match light.get_kind() {
+                    LightKind::Directional => (
+                        view_proj * (-light.get_direction()).extend(0.0),
+                        light.get_intensity() * exposure,
+                    ),
+                    LightKind::Point if flare.point_lights => {
+                        let position = light.get_position();
+                        let distance = (position - eye.to_homogeneous().truncate()).magnitude2();
+                        (
+                            view_proj * position.extend(1.0),
+                            light.get_intensity() * exposure / distance.max(1.0),
+                        )
+                    }
+                    LightKind::Point => return None,
+                };
+
+                let brightness = brightness * luminance(color);
+                if clip.w <= 0.0 || brightness < flare.min_brightness {
+                    return None;
+                }
+
+                // The sun is behind everything in the depth buffer
+                let depth = match light.get_kind() {
+                    LightKind::Directional => 1.0,
+                    LightKind::Point => clip.z / clip.w,
+                };
+                let position = Vector4::new(clip.x / clip.w, clip.y / clip.w, depth, 0.0);
+                // Lights far off of the screen have faded out already
+                if position.x.abs() > 1.5 || position.y.abs() > 1.5 {
+                    return None;
+                }
+
+                Some((
+                    brightness,
+                    FlareSourceRaw {
+                        position: position.into(),
+                        color: [
+                            color.0 * brightness,
+                            color.1 * brightness,
+                            color.2 * brightness,
+                            1.0,
+                        ],
+                    },
+                ))
+            })
+            .collect();
+        sources.sort_by(|a, b| b.0.total_cmp(&a.0));
+        sources.truncate(MAX_FLARE_SOURCES);
+
+        let mut uniform = FlareUniform {
+            sources: [FlareSourceRaw::default(); MAX_FLARE_SOURCES],
+            elements: [FlareElementRaw::default(); MAX_FLARE_ELEMENTS],
+            source_count: sources.len() as u32,
+            element_count: flare.elements.len().min(MAX_FLARE_ELEMENTS) as u32,
+            occlusion_radius: flare.occlusion_radius.max(0.0),
+            intensity: flare.intensity.max(0.0),
+        };
+        for (raw, (_, source)) in uniform.sources.iter_mut().zip(sources) {
+            *raw = source;
+        }
+        for (raw, element) in uniform.elements.iter_mut().zip(flare.elements.iter()) {
+            *raw = element.into();
+        }
+
+        self.instances = uniform.source_count * uniform.element_count;
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    /// Adds the flares of the lights found in the last update onto the scene
+    pub fn draw(&self, encoder: &mut CommandEncoder, scene_view: &TextureView) {
+        if self.instances == 0 {
+            return;
+        }
+
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Lens Flare Render Pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: scene_view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Load,
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..6, 0..self.instances);
+    }
+}
@@ -0,0 +1,444 @@
+pub mod bloom;
+pub mod color_grading;
+pub mod depth_of_field;
+pub mod exposure;
+pub mod fxaa;
+pub mod lens;
+pub mod lens_flare;
+pub mod motion_blur;
+pub mod screen_overlay;
+pub mod taa;
+pub mod tonemap;
+pub mod velocity;
+
+use std::time::Instant;
+
+use cgmath::{Vector2, Zero};
+
+use wgpu::{
+    Buffer, Color, CommandEncoder, Device, Extent3d, LoadOp, Operations, Queue,
+    RenderPassColorAttachment, RenderPassDescriptor, StoreOp, Texture, TextureDescriptor,
+    TextureDimension, TextureFormat, TextureUsages, TextureView, TextureViewDescriptor,
+};
+
+use crate::{camera::Camera, light::Lights, model::Model};
+
+use bloom::{Bloom, BloomPass};
+use color_grading::ColorGradingLut;
+use depth_of_field::{DepthOfField, DepthOfFieldPass};
+use exposure::{AutoExposure, Exposure};
+use fxaa::FxaaPass;
+use lens::LensEffects;
+use lens_flare::{LensFlare, LensFlarePass};
+use motion_blur::{MotionBlur, MotionBlurPass};
+use screen_overlay::{ScreenOverlay, ScreenOverlayHandle, ScreenOverlays};
+use taa::TaaPass;
+use tonemap::{TonemapPass, Tonemapper};
+use velocity::VelocityPass;
+
+/// Format of the texture the scene is drawn to before the post stack runs
+pub const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// How the edges of the scene are smoothed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AntiAliasing {
+    #[default]
+    None,
+    /// Temporal anti-aliasing, the camera is jittered every frame and the frames are
+    /// blended together
+    Taa,
+    /// Fast approximate anti-aliasing, the edges of the tonemapped screen are blurred
+    /// which is cheap enough for low end hardware
+    Fxaa,
+}
+
+/// Settings of the effects that are applied to the scene after it is drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PostSettings {
+    pub tonemapper: Tonemapper,
+    pub exposure: Exposure,
+    pub anti_aliasing: AntiAliasing,
+    /// `None` turns the motion blur off
+    pub motion_blur: Option<MotionBlur>,
+    /// `None` turns the depth of field off
+    pub depth_of_field: Option<DepthOfField>,
+    /// `None` turns the bloom off
+    pub bloom: Option<Bloom>,
+    pub lens: LensEffects,
+}
+
+/// The scene is drawn into an hdr texture that the post stack processes and draws
+/// onto the screen
+pub struct PostStack {
+    settings: PostSettings,
+    size: (u32, u32),
+    scene_texture: Texture,
+    scene_view: TextureView,
+    auto_exposure: AutoExposure,
+    tonemap: TonemapPass,
+    velocity: VelocityPass,
+    taa: TaaPass,
+    motion_blur: MotionBlurPass,
+    depth_of_field: DepthOfFieldPass,
+    bloom: BloomPass,
+    fxaa: FxaaPass,
+    // Kept out of the settings since its chain of elements can not be copied
+    lens_flare: Option<LensFlare>,
+    lens_flare_pass: LensFlarePass,
+    screen_overlays: ScreenOverlays,
+    // Set when the camera was prepared so the passes that depend on it can run
+    camera_prepared: bool,
+    last_frame: Instant,
+    // Seconds since the post stack was created, wrapped every hour
+    elapsed: f32,
+}
+
+impl PostStack {
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        format: TextureFormat,
+        size: (u32, u32),
+        depth_view: &TextureView,
+    ) -> Self {
+        let (scene_texture, scene_view) = Self::create_scene_texture(device, size);
+        let auto_exposure = AutoExposure::new(device, &scene_view);
+        let tonemap = TonemapPass::new(
+            device,
+            queue,
+            format,
+            &scene_view,
+            auto_exposure.get_luminance_buffer(),
+        );
+        let velocity = VelocityPass::new(device, size);
+        let taa = TaaPass::new(device, size, &scene_view, depth_view, &velocity);
+        let motion_blur = MotionBlurPass::new(device, size, &scene_view, depth_view, &velocity);
+        let depth_of_field = DepthOfFieldPass::new(device, size, &scene_view, depth_view);
+        let bloom = BloomPass::new(device, size, &scene_view);
+        let fxaa = FxaaPass::new(device, format, size);
+        let lens_flare_pass = LensFlarePass::new(device, depth_view);
+
+        Self {
+            settings: PostSettings::default(),
+            size,
+            scene_texture,
+            scene_view,
+            auto_exposure,
+            tonemap,
+            velocity,
+            taa,
+            motion_blur,
+            depth_of_field,
+            bloom,
+            fxaa,
+            lens_flare: None,
+            lens_flare_pass,
+            screen_overlays: ScreenOverlays::default(),
+            camera_prepared: false,
+            last_frame: Instant::now(),
+            elapsed: 0.0,
+        }
+    }
+
+    fn create_scene_texture(device: &Device, size: (u32, u32)) -> (Texture, TextureView) {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Hdr Scene Texture"),
+            size: Extent3d {
+                width: size.0.max(1),
+                height: size.1.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: HDR_FORMAT,
+            // The anti-aliased scene is copied back into the texture
+            usage: TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        (texture, view)
+    }
+
+    // Recreates the bind groups that read the scene texture
+    fn bind_scene_view(&mut self, device: &Device) {
+        self.auto_exposure.set_scene_view(device, &self.scene_view);
+        self.tonemap.set_scene_view(
+            device,
+            &self.scene_view,
+            self.auto_exposure.get_luminance_buffer(),
+        );
+    }
+
+    // Call this when resizing the window
+    pub fn resize(&mut self, device: &Device, size: (u32, u32), depth_view: &TextureView) {
+        self.size = size;
+        (self.scene_texture, self.scene_view) = Self::create_scene_texture(device, size);
+        self.bind_scene_view(device);
+        self.velocity.resize(device, size);
+        self.taa
+            .resize(device, size, &self.scene_view, depth_view, &self.velocity);
+        self.motion_blur
+            .resize(device, size, &self.scene_view, depth_view, &self.velocity);
+        self.depth_of_field
+            .resize(device, size, &self.scene_view, depth_view);
+        self.bloom.resize(device, size, &self.scene_view);
+        self.fxaa.resize(device, size);
+        self.lens_flare_pass.resize(device, depth_view);
+    }
+
+    /// The texture the scene is drawn to
+    pub fn get_scene_view(&self) -> &TextureView {
+        &self.scene_view
+    }
+
+    pub fn get_settings(&self) -> PostSettings {
+        self.settings
+    }
+
+    pub fn set_settings(&mut self, queue: &Queue, settings: PostSettings) {
+        // The history is stale when the anti-aliasing is turned back on
+        if settings.anti_aliasing != self.settings.anti_aliasing {
+            self.taa.reset_history();
+        }
+
+        // The last frame is stale when the motion has not been drawn for a while
+        if !self.uses_velocity() {
+            self.velocity.reset();
+        }
+
+        self.settings = settings;
+        self.tonemap
+            .set_settings(queue, &settings.tonemapper, &settings.exposure);
+
+        if let Some(motion_blur) = settings.motion_blur.as_ref() {
+            self.motion_blur.set_settings(queue, motion_blur);
+        }
+
+        if let Some(bloom) = settings.bloom.as_ref() {
+            self.bloom.set_settings(queue, bloom);
+        }
+    }
+
+    /// Draws flares over the sun and bright lights, `None` turns them off
+    pub fn set_lens_flare(&mut self, lens_flare: Option<LensFlare>) {
+        self.lens_flare = lens_flare;
+    }
+
+    pub fn get_lens_flare(&self) -> Option<&LensFlare> {
+        self.lens_flare.as_ref()
+    }
+
+    /// Adds an overlay over the screen that fades in, it is drawn over the overlays that
+    /// were added before it
+    pub fn add_screen_overlay(&mut self, overlay: ScreenOverlay) -> ScreenOverlayHandle {
+        self.screen_overlays.add(overlay)
+    }
+
+    pub fn update_screen_overlay(&mut self, handle: ScreenOverlayHandle, overlay: ScreenOverlay) {
+        self.screen_overlays.update(handle, overlay);
+    }
+
+    /// Fades an overlay out and removes it
+    pub fn remove_screen_overlay(&mut self, handle: ScreenOverlayHandle) {
+        self.screen_overlays.remove(handle);
+    }
+
+    pub fn has_screen_overlay(&self, handle: ScreenOverlayHandle) -> bool {
+        self.screen_overlays.contains(handle)
+    }
+
+    /// Removes every overlay at once without fading them out
+    pub fn clear_screen_overlays(&mut self) {
+        self.screen_overlays.clear();
+    }
+
+    // Moves the overlays to another post stack, used when the device is recreated
+    pub(crate) fn take_screen_overlays(&mut self) -> ScreenOverlays {
+        std::mem::take(&mut self.screen_overlays)
+    }
+
+    pub(crate) fn set_screen_overlays(&mut self, screen_overlays: ScreenOverlays) {
+        self.screen_overlays = screen_overlays;
+    }
+
+    /// Finds the lights that flare from where the camera is, call this before the scene
+    /// is drawn with the camera that is not jittered
+    pub fn prepare_lens_flare(&mut self, queue: &Queue, camera: &Camera, lights: &Lights) {
+        if let Some(lens_flare) = self.lens_flare.as_ref() {
+            self.lens_flare_pass
+                .update(queue, lens_flare, camera, lights);
+        }
+    }
+
+    // The velocity of the models is only drawn when an effect follows the motion
+    fn uses_velocity(&self) -> bool {
+        self.settings.anti_aliasing == AntiAliasing::Taa || self.settings.motion_blur.is_some()
+    }
+
+    /// Grades the colors of the screen with a lookup table, `None` removes the grading
+    pub fn set_color_grading(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        lut: Option<&ColorGradingLut>,
+    ) {
+        self.tonemap.set_color_grading(device, queue, lut);
+        self.bind_scene_view(device);
+        self.set_settings(queue, self.settings);
+    }
+
+    /// Updates the effects that depend on the camera and jitters it when temporal
+    /// anti-aliasing is on, call this before the scene is drawn
+    pub fn prepare_camera(&mut self, queue: &Queue, camera: &Camera) {
+        self.camera_prepared = true;
+
+        if let Some(depth_of_field) = self.settings.depth_of_field.as_ref() {
+            self.depth_of_field.update(queue, depth_of_field, camera);
+        }
+
+        if !self.uses_velocity() {
+            return;
+        }
+
+        let taa = self.settings.anti_aliasing == AntiAliasing::Taa;
+        let jitter = if taa {
+            self.taa.next_jitter(queue)
+        } else {
+            Vector2::zero()
+        };
+
+        let jittered_view_proj = self.velocity.update_camera(queue, camera, jitter);
+        if taa {
+            let mut camera_uniform = *camera.get_uniform();
+            camera_uniform.update_view_proj_with_matrix(camera.eye, jittered_view_proj);
+            queue.write_buffer(
+                camera.get_buffer(),
+                0,
+                bytemuck::cast_slice(&[camera_uniform]),
+            );
+        }
+    }
+
+    /// Draws the motion of the models for temporal anti-aliasing and motion blur, call
+    /// this after the scene is drawn
+    ///
+    /// # Arguments
+    ///
+    /// * `models` - The models of the scene
+    /// * `excluded` - Indices of models whose instances are not in the instance buffer
+    /// * `instance_buffer` - The instance buffer the models are drawn with
+    pub fn draw_velocity(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        models: &[Option<Model>],
+        excluded: &[usize],
+        instance_buffer: &Buffer,
+    ) {
+        if self.camera_prepared && self.uses_velocity() {
+            self.velocity
+                .draw(device, encoder, models, excluded, instance_buffer);
+        }
+    }
+
+    /// Applies the post effects to the scene and draws it to the output
+    pub fn run(&mut self, encoder: &mut CommandEncoder, queue: &Queue, output: &TextureView) {
+        let now = Instant::now();
+        let delta_time = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+        // Wrapped so the grain keeps its precision in long sessions
+        self.elapsed = (self.elapsed + delta_time) % 3600.0;
+
+        // The effects that blend the scene run before the exposure so they stay in hdr,
+        // they only run on frames that were drawn with a camera
+        if std::mem::take(&mut self.camera_prepared) {
+            if self.settings.anti_aliasing == AntiAliasing::Taa {
+                self.taa.resolve(encoder, &self.scene_texture);
+            }
+
+            if self.settings.depth_of_field.is_some() {
+                self.depth_of_field.draw(encoder, &self.scene_texture);
+            }
+
+            if self.settings.motion_blur.is_some() {
+                self.motion_blur.draw(encoder, &self.scene_texture);
+            }
+
+            // The flares are added before the bloom so they glow like the lights do
+            if self.lens_flare.is_some() {
+                self.lens_flare_pass.draw(encoder, &self.scene_view);
+            }
+        }
+
+        // The glow is added before the exposure so it is measured with the rest of the scene
+        if self.settings.bloom.is_some() {
+            self.bloom.draw(encoder, &self.scene_view);
+        }
+
+        self.auto_exposure.dispatch(
+            encoder,
+            queue,
+            &self.settings.exposure,
+            self.size,
+            delta_time,
+        );
+
+        self.screen_overlays.advance(delta_time);
+        self.tonemap.set_lens(
+            queue,
+            &self.settings.lens,
+            &self.screen_overlays,
+            self.elapsed,
+        );
+
+        // Fxaa smooths the tonemapped screen so the scene is tonemapped into its texture
+        let fxaa = self.settings.anti_aliasing == AntiAliasing::Fxaa;
+        let tonemap_output = if fxaa {
+            self.fxaa.get_screen_view()
+        } else {
+            output
+        };
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Tonemap Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: tonemap_output,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            self.tonemap.draw(&mut render_pass);
+        }
+
+        if fxaa {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Fxaa Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: output,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            self.fxaa.draw(&mut render_pass);
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use wgpu::{
+    include_wgsl,
+    util::{BufferInitDescriptor, DeviceExt},
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
+    BufferBindingType, BufferUsages, Color, ColorTargetState, ColorWrites, CommandEncoder, Device,
+    Extent3d, FilterMode, FragmentState, LoadOp, MultisampleState, Operations,
+    PipelineCompilationOptions, PipelineLayoutDescriptor, PrimitiveState, Queue,
+    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor,
+    Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages, StoreOp, Texture,
+    TextureDescriptor, TextureDimension, TextureSampleType, TextureUsages, TextureView,
+    TextureViewDescriptor, TextureViewDimension, VertexState,
+};
+
+use super::{velocity::VelocityPass, HDR_FORMAT};
+
+// Longest blur as a fraction of the screen so fast motion does not smear the whole screen
+const MAX_BLUR_LENGTH: f32 = 0.05;
+
+/// Blurs the scene along the motion of the camera and the models
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MotionBlur {
+    /// Fraction of the motion of a frame that is blurred, like the shutter of a camera
+    pub intensity: f32,
+    /// Number of times the scene is sampled along the motion, more is smoother
+    pub samples: u32,
+}
+
+impl Default for MotionBlur {
+    fn default() -> Self {
+        Self {
+            intensity: 0.5,
+            samples: 8,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct MotionBlurUniform {
+    intensity: f32,
+    samples: u32,
+    max_length: f32,
+    _padding: f32,
+}
+
+impl From<&MotionBlur> for MotionBlurUniform {
+    fn from(motion_blur: &MotionBlur) -> Self {
+        Self {
+            intensity: motion_blur.intensity.max(0.0),
+            samples: motion_blur.samples,
+            max_length: MAX_BLUR_LENGTH,
+            _padding: 0.0,
+        }
+    }
+}
+
+/// Draws the blurred scene into a texture and copies it back into the scene
+pub(crate) struct MotionBlurPass {
+    pipeline: RenderPipeline,
+    layout: BindGroupLayout,
+    buffer: Buffer,
+    sampler: Sampler,
+    output: (Texture, TextureView),
+    bind_group: BindGroup,
+}
+
+impl MotionBlurPass {
+    pub fn new(
+        device: &Device,
+        size: (u32, u32),
+        scene_view: &TextureView,
+        depth_view: &TextureView,
+        velocity: &VelocityPass,
+    ) -> Self {
+        let fragment_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty,
+            count: None,
+        };
+
+        let uniform_entry = |binding| {
+            fragment_entry(
+                binding,
+                BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+            )
+        };
+
+        let texture_entry = |binding, sample_type| {
+            fragment_entry(
+                binding,
+                BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: TextureViewDimension::D2,
+                    sample_type,
+                },
+            )
+        };
+
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Motion Blur Bind Group Layout"),
+            entries: &[
+                uniform_entry(0),
+                texture_entry(1, TextureSampleType::Float { filterable: true }),
+                fragment_entry(2, BindingType::Sampler(SamplerBindingType::Filtering)),
+                texture_entry(3, TextureSampleType::Float { filterable: true }),
+                texture_entry(4, TextureSampleType::Depth),
+                uniform_entry(5),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Motion Blur Render Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("../shaders/motion_blur.wgsl"));
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Motion Blur Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format: HDR_FORMAT,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Motion Blur buffer"),
+            contents: bytemuck::cast_slice(&[MotionBlurUniform::from(&MotionBlur::default())]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Motion Blur Sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let output = Self::create_output(device, size);
+        let bind_group = Self::create_bind_group(
+            device, &layout, &buffer, &sampler, scene_view, depth_view, velocity,
+        );
+
+        Self {
+            pipeline,
+            layout,
+            buffer,
+            sampler,
+            output,
+            bind_group,
+        }
+    }
+
+    fn create_output(device: &Device, size: (u32, u32)) -> (Texture, TextureView) {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Motion Blur Texture"),
+            size: Extent3d {
+                width: size.0.max(1),
+                height: size.1.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        (texture, view)
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        buffer: &Buffer,
+        sampler: &Sampler,
+        scene_view: &TextureView,
+        depth_view: &TextureView,
+        velocity: &VelocityPass,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Motion Blur bind group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: velocity.get_buffer().as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(scene_view),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::Sampler(sampler),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::TextureView(velocity.get_velocity_view()),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: BindingResource::TextureView(depth_view),
+                },
+                BindGroupEntry {
+                    binding: 5,
+                    resource: buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    /// Call this when the scene, depth, or velocity textures are recreated
+    pub fn resize(
+        &mut self,
+        device: &Device,
+        size: (u32, u32),
+        scene_view: &TextureView,
+        depth_view: &TextureView,
+        velocity: &VelocityPass,
+    ) {
+        self.output = Self::create_output(device, size);
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.layout,
+            &self.buffer,
+            &self.sampler,
+            scene_view,
+            depth_view,
+            velocity,
+        );
+    }
+
+    pub fn set_settings(&self, queue: &Queue, motion_blur: &MotionBlur) {
+        queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::cast_slice(&[MotionBlurUniform::from(motion_blur)]),
+        );
+    }
+
+    /// Blurs the scene and copies the result back into the scene
+    pub fn draw(&self, encoder: &mut CommandEncoder, scene_texture: &Texture) {
+        {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Motion Blur Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &self.output.1,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        encoder.copy_texture_to_texture(
+            self.output.0.as_image_copy(),
+            scene_texture.as_image_copy(),
+            scene_texture.size(),
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use crate::color::LinearRgba;
+
+/// The most overlays that are drawn at once, the newest ones are kept. Must match the
+/// size of the overlay array in tonemap_shader.wgsl
+pub const MAX_SCREEN_OVERLAYS: usize = 8;
+
+/// How an overlay changes the screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScreenOverlayKind {
+    /// Colors the edges of the screen, like the red of taking damage
+    Vignette,
+    /// Colors the whole screen, like the green of poison
+    Tint,
+    /// Bends the screen with slow waves, like looking through water
+    Distortion,
+}
+
+impl ScreenOverlayKind {
+    fn index(&self) -> u32 {
+        match self {
+            ScreenOverlayKind::Vignette => 0,
+            ScreenOverlayKind::Tint => 1,
+            ScreenOverlayKind::Distortion => 2,
+        }
+    }
+}
+
+/// A full screen effect that is drawn over the scene after the tonemapping, overlays are
+/// stacked in the order they were added and fade in and out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenOverlay {
+    pub kind: ScreenOverlayKind,
+    /// The linear RGBA color of the overlay, the alpha is how much it covers the scene.
+    /// Distortions ignore it
+    pub color: [f32; 4],
+    /// How far a vignette reaches into the screen from 0 to 1, or how far a distortion
+    /// bends the screen. Tints ignore it
+    pub strength: f32,
+    /// Seconds the overlay takes to appear
+    pub fade_in: f32,
+    /// Seconds the overlay takes to disappear after it is removed
+    pub fade_out: f32,
+    /// Removes the overlay by itself after this many seconds, `None` keeps it until it is
+    /// removed
+    pub duration: Option<f32>,
+}
+
+impl ScreenOverlay {
+    fn with_kind(kind: ScreenOverlayKind, color: [f32; 4], strength: f32) -> Self {
+        Self {
+            kind,
+            color,
+            strength,
+            fade_in: 0.1,
+            fade_out: 0.3,
+            duration: None,
+        }
+    }
+
+    /// Colors the edges of the screen
+    pub fn vignette<C>(color: C) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        Self::with_kind(ScreenOverlayKind::Vignette, color.into().to_array(), 0.6)
+    }
+
+    /// Colors the whole screen, the alpha of the color is how strong the tint is
+    pub fn tint<C>(color: C) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        Self::with_kind(ScreenOverlayKind::Tint, color.into().to_array(), 1.0)
+    }
+
+    /// Bends the screen with waves
+    ///
+    /// # Arguments
+    ///
+    /// * `strength` - How far the screen is bent, 1 moves it by about a percent of its size
+    pub fn distortion(strength: f32) -> Self {
+        Self::with_kind(ScreenOverlayKind::Distortion, [1.0; 4], strength)
+    }
+
+    pub fn with_strength(mut self, strength: f32) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    /// Sets how many seconds the overlay takes to appear and to disappear
+    pub fn with_fade(mut self, fade_in: f32, fade_out: f32) -> Self {
+        self.fade_in = fade_in.max(0.0);
+        self.fade_out = fade_out.max(0.0);
+        self
+    }
+
+    /// Removes the overlay by itself after a while, used for flashes like taking damage
+    pub fn with_duration(mut self, seconds: f32) -> Self {
+        self.duration = Some(seconds.max(0.0));
+        self
+    }
+}
+
+/// Handle to an overlay that has been added to the screen
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ScreenOverlayHandle(pub usize);
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct ScreenOverlayUniform {
+    color: [f32; 4],
+    kind: u32,
+    // How far the overlay has faded in
+    weight: f32,
+    strength: f32,
+    _padding: f32,
+}
+
+struct ActiveOverlay {
+    handle: ScreenOverlayHandle,
+    overlay: ScreenOverlay,
+    age: f32,
+    weight: f32,
+    removing: bool,
+}
+
+/// The overlays on the screen in the order they are drawn
+#[derive(Default)]
+pub(crate) struct ScreenOverlays {
+    overlays: Vec<ActiveOverlay>,
+    next_handle: usize,
+}
+
+impl ScreenOverlays {
+    pub fn add(&mut self, overlay: ScreenOverlay) -> ScreenOverlayHandle {
+        let handle = ScreenOverlayHandle(self.next_handle);
+        self.next_handle += 1;
+
+        self.overlays.push(ActiveOverlay {
+            handle,
+            overlay,
+            age: 0.0,
+            weight: 0.0,
+            removing: false,
+        });
+
+        handle
+    }
+
+    /// Changes an overlay without fading it in again
+    pub fn update(&mut self, handle: ScreenOverlayHandle, overlay: ScreenOverlay) {
+        if let Some(active) = self.find(handle) {
+            active.overlay = overlay;
+        }
+    }
+
+    /// Fades an overlay out and removes it
+    pub fn remove(&mut self, handle: ScreenOverlayHandle) {
+        if let Some(active) = self.find(handle) {
+            active.removing = true;
+        }
+    }
+
+    /// Whether an overlay is still on the screen, overlays that are fading out still are
+    pub fn contains(&self, handle: ScreenOverlayHandle) -> bool {
+        self.overlays.iter().any(|active| active.handle == handle)
+    }
+
+    pub fn clear(&mut self) {
+        self.overlays.clear();
+    }
+
+    fn find(&mut self, handle: ScreenOverlayHandle) -> Option<&mut ActiveOverlay> {
+        self.overlays
+            .iter_mut()
+            .find(|active| active.handle == handle)
+    }
+
+    /// Fades the overlays in and out and removes the ones that have disappeared
+    pub fn advance(&mut self, delta_time: f32) {
+        for active in self.overlays.iter_mut() {
+            active.age += delta_time;
+            if active
+                .overlay
+                .duration
+                .is_some_and(|duration| active.age >= duration)
+            {
+                active.removing = true;
+            }
+
+            let (target, fade) = if active.removing {
+                (0.0, active.overlay.fade_out)
+            } else {
+                (1.0, active.overlay.fade_in)
+            };
+            let step = if fade > 0.0 { delta_time / fade } else { 1.0 };
+            active.weight = if active.weight < target {
+                (active.weight + step).min(target)
+            } else {
+                (active.weight - step).max(target)
+            };
+        }
+
+        self.overlays
+            .retain(|active| !(active.removing && active.weight <= 0.0));
+    }
+
+    /// The newest overlays that fit in the uniform and how many of them there are
+    pub fn to_uniform(&self) -> ([ScreenOverlayUniform; MAX_SCREEN_OVERLAYS], u32) {
+        let mut uniforms = [ScreenOverlayUniform::default(); MAX_SCREEN_OVERLAYS];
+        let skipped = self.overlays.len().saturating_sub(MAX_SCREEN_OVERLAYS);
+        for (uniform, active) in uniforms.iter_mut().zip(&self.overlays[skipped..]) {
+            *uniform = ScreenOverlayUniform {
+                color: active.overlay.color,
+                kind: active.overlay.kind.index(),
+                weight: active.weight,
+                strength: active.overlay.strength,
+                _padding: 0.0,
+            };
+        }
+
+        (uniforms, (self.overlays.len() - skipped) as u32)
+    }
+}
@@ -0,0 +1,347 @@
+use cgmath::Vector2;
+use wgpu::{
+    include_wgsl,
+    util::{BufferInitDescriptor, DeviceExt},
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
+    BufferBindingType, BufferUsages, Color, ColorTargetState, ColorWrites, CommandEncoder, Device,
+    Extent3d, FilterMode, FragmentState, LoadOp, MultisampleState, Operations,
+    PipelineCompilationOptions, PipelineLayoutDescriptor, PrimitiveState, Queue,
+    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor,
+    Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages, StoreOp, Texture,
+    TextureDescriptor, TextureDimension, TextureSampleType, TextureUsages, TextureView,
+    TextureViewDescriptor, TextureViewDimension, VertexState,
+};
+
+use super::{velocity::VelocityPass, HDR_FORMAT};
+
+// Number of jitter positions before the sequence repeats
+const JITTER_SAMPLES: u32 = 8;
+
+// How much of the current frame is blended into the history
+const HISTORY_BLEND: f32 = 0.1;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct TaaUniform {
+    blend: f32,
+    reset: u32,
+    _padding: [f32; 2],
+}
+
+// Point of the low discrepancy Halton sequence in the 0 to 1 range
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut fraction = 1.0;
+    let mut result = 0.0;
+
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+
+    result
+}
+
+/// Jitters the camera and blends the scene with the reprojected history of the last frames
+pub(crate) struct TaaPass {
+    pipeline: RenderPipeline,
+    layout: BindGroupLayout,
+    buffer: Buffer,
+    sampler: Sampler,
+    size: (u32, u32),
+    history: [(Texture, TextureView); 2],
+    // Index of the history texture that holds the last frame
+    history_index: usize,
+    history_valid: bool,
+    bind_groups: [BindGroup; 2],
+    frame: u32,
+}
+
+impl TaaPass {
+    pub fn new(
+        device: &Device,
+        size: (u32, u32),
+        scene_view: &TextureView,
+        depth_view: &TextureView,
+        velocity: &VelocityPass,
+    ) -> Self {
+        let fragment_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty,
+            count: None,
+        };
+
+        let uniform_entry = |binding| {
+            fragment_entry(
+                binding,
+                BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+            )
+        };
+
+        let texture_entry = |binding, sample_type| {
+            fragment_entry(
+                binding,
+                BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: TextureViewDimension::D2,
+                    sample_type,
+                },
+            )
+        };
+
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Taa Resolve Bind Group Layout"),
+            entries: &[
+                uniform_entry(0),
+                texture_entry(1, TextureSampleType::Float { filterable: true }),
+                texture_entry(2, TextureSampleType::Float { filterable: true }),
+                fragment_entry(3, BindingType::Sampler(SamplerBindingType::Filtering)),
+                texture_entry(4, TextureSampleType::Float { filterable: true }),
+                texture_entry(5, TextureSampleType::Depth),
+                uniform_entry(6),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Taa Resolve Render Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("../shaders/taa_resolve.wgsl"));
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Taa Resolve Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format: HDR_FORMAT,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Taa buffer"),
+            contents: bytemuck::cast_slice(&[TaaUniform {
+                blend: HISTORY_BLEND,
+                reset: 1,
+                _padding: [0.0; 2],
+            }]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Taa History Sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let history = Self::create_history(device, size);
+
+        let bind_groups = Self::create_bind_groups(
+            device, &layout, &buffer, &sampler, scene_view, depth_view, velocity, &history,
+        );
+
+        Self {
+            pipeline,
+            layout,
+            buffer,
+            sampler,
+            size,
+            history,
+            history_index: 0,
+            history_valid: false,
+            bind_groups,
+            frame: 0,
+        }
+    }
+
+    fn create_history(device: &Device, size: (u32, u32)) -> [(Texture, TextureView); 2] {
+        [0, 1].map(|_| {
+            let texture = device.create_texture(&TextureDescriptor {
+                label: Some("Taa History Texture"),
+                size: Extent3d {
+                    width: size.0.max(1),
+                    height: size.1.max(1),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: HDR_FORMAT,
+                usage: TextureUsages::RENDER_ATTACHMENT
+                    | TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&TextureViewDescriptor::default());
+            (texture, view)
+        })
+    }
+
+    // One bind group for each history texture that can be read from
+    #[allow(clippy::too_many_arguments)]
+    fn create_bind_groups(
+        device: &Device,
+        layout: &BindGroupLayout,
+        buffer: &Buffer,
+        sampler: &Sampler,
+        scene_view: &TextureView,
+        depth_view: &TextureView,
+        velocity: &VelocityPass,
+        history: &[(Texture, TextureView); 2],
+    ) -> [BindGroup; 2] {
+        history.each_ref().map(|(_, history_view)| {
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("Taa Resolve bind group"),
+                layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: velocity.get_buffer().as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::TextureView(scene_view),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: BindingResource::TextureView(history_view),
+                    },
+                    BindGroupEntry {
+                        binding: 3,
+                        resource: BindingResource::Sampler(sampler),
+                    },
+                    BindGroupEntry {
+                        binding: 4,
+                        resource: BindingResource::TextureView(velocity.get_velocity_view()),
+                    },
+                    BindGroupEntry {
+                        binding: 5,
+                        resource: BindingResource::TextureView(depth_view),
+                    },
+                    BindGroupEntry {
+                        binding: 6,
+                        resource: buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        })
+    }
+
+    /// Call this when the scene, depth, or velocity textures are recreated
+    pub fn resize(
+        &mut self,
+        device: &Device,
+        size: (u32, u32),
+        scene_view: &TextureView,
+        depth_view: &TextureView,
+        velocity: &VelocityPass,
+    ) {
+        self.size = size;
+        self.history = Self::create_history(device, size);
+        self.bind_groups = Self::create_bind_groups(
+            device,
+            &self.layout,
+            &self.buffer,
+            &self.sampler,
+            scene_view,
+            depth_view,
+            velocity,
+            &self.history,
+        );
+        self.reset_history();
+    }
+
+    /// Throws away the history so the next frame is not blended with old frames
+    pub fn reset_history(&mut self) {
+        self.history_valid = false;
+    }
+
+    /// Moves to the next sub pixel offset of the camera, call this once per frame
+    ///
+    /// # Returns
+    ///
+    /// The offset of the camera in clip space
+    pub fn next_jitter(&mut self, queue: &Queue) -> Vector2<f32> {
+        self.frame = (self.frame + 1) % JITTER_SAMPLES;
+
+        queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::cast_slice(&[TaaUniform {
+                blend: HISTORY_BLEND,
+                reset: !self.history_valid as u32,
+                _padding: [0.0; 2],
+            }]),
+        );
+
+        // The jitter is up to half a pixel in each direction
+        Vector2::new(
+            (halton(self.frame + 1, 2) - 0.5) * 2.0 / self.size.0.max(1) as f32,
+            (halton(self.frame + 1, 3) - 0.5) * 2.0 / self.size.1.max(1) as f32,
+        )
+    }
+
+    /// Blends the scene with the history and copies the result back into the scene
+    pub fn resolve(&mut self, encoder: &mut CommandEncoder, scene_texture: &Texture) {
+        let output_index = 1 - self.history_index;
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Taa Resolve Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &self.history[output_index].1,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.bind_groups[self.history_index], &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        encoder.copy_texture_to_texture(
+            self.history[output_index].0.as_image_copy(),
+            scene_texture.as_image_copy(),
+            scene_texture.size(),
+        );
+
+        self.history_index = output_index;
+        self.history_valid = true;
+    }
+}
@@ -0,0 +1,367 @@
+use wgpu::{
+    include_wgsl,
+    util::{BufferInitDescriptor, DeviceExt},
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
+    BufferBindingType, BufferUsages, ColorTargetState, ColorWrites, Device, FilterMode,
+    FragmentState, MultisampleState, PipelineCompilationOptions, PipelineLayoutDescriptor,
+    PrimitiveState, Queue, RenderPass, RenderPipeline, RenderPipelineDescriptor, Sampler,
+    SamplerBindingType, SamplerDescriptor, ShaderStages, TextureFormat, TextureSampleType,
+    TextureView, TextureViewDimension, VertexState,
+};
+
+use crate::color::needs_shader_encode;
+
+use super::{
+    color_grading::ColorGradingLut,
+    exposure::Exposure,
+    lens::{LensEffects, LensUniform},
+    screen_overlay::ScreenOverlays,
+};
+
+/// The curve that maps the hdr colors of the scene to the colors of the screen
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tonemapper {
+    /// Colors brighter than white are clipped
+    #[default]
+    None,
+    Reinhard,
+    /// Filmic curve with more contrast than Reinhard
+    Aces,
+}
+
+impl Tonemapper {
+    // Index of the tonemapper in the shader
+    fn index(&self) -> u32 {
+        match self {
+            Tonemapper::None => 0,
+            Tonemapper::Reinhard => 1,
+            Tonemapper::Aces => 2,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct TonemapUniform {
+    tonemapper: u32,
+    auto_exposure: u32,
+    exposure: f32,
+    lut_enabled: u32,
+    lut_size: f32,
+    encode_srgb: u32,
+    _padding: [f32; 2],
+}
+
+/// Draws the hdr scene onto the screen with the exposure, tonemapper, color grading, and
+/// lens effects
+pub(crate) struct TonemapPass {
+    pipeline: RenderPipeline,
+    layout: BindGroupLayout,
+    scene_sampler: Sampler,
+    lut_sampler: Sampler,
+    buffer: Buffer,
+    lens_buffer: Buffer,
+    // The identity table is bound when there is no color grading
+    lut_view: TextureView,
+    lut_size: Option<u32>,
+    // Set when the screen has no srgb view so the shader has to gamma encode the output
+    encode_srgb: bool,
+    bind_group: BindGroup,
+}
+
+impl TonemapPass {
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        format: TextureFormat,
+        scene_view: &TextureView,
+        luminance_buffer: &Buffer,
+    ) -> Self {
+        let fragment_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty,
+            count: None,
+        };
+
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Tonemap Bind Group Layout"),
+            entries: &[
+                fragment_entry(
+                    0,
+                    BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: TextureViewDimension::D2,
+                        sample_type: TextureSampleType::Float { filterable: true },
+                    },
+                ),
+                fragment_entry(1, BindingType::Sampler(SamplerBindingType::Filtering)),
+                fragment_entry(
+                    2,
+                    BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                ),
+                fragment_entry(
+                    3,
+                    BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                ),
+                fragment_entry(
+                    4,
+                    BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: TextureViewDimension::D3,
+                        sample_type: TextureSampleType::Float { filterable: true },
+                    },
+                ),
+                fragment_entry(5, BindingType::Sampler(SamplerBindingType::Filtering)),
+                fragment_entry(
+                    6,
+                    BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                ),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Tonemap Render Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("../shaders/tonemap_shader.wgsl"));
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Tonemap Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let create_sampler = |label| {
+            device.create_sampler(&SamplerDescriptor {
+                label: Some(label),
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                mipmap_filter: FilterMode::Nearest,
+                ..Default::default()
+            })
+        };
+
+        let scene_sampler = create_sampler("Tonemap Scene Sampler");
+        let lut_sampler = create_sampler("Color Grading Sampler");
+
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Tonemap buffer"),
+            contents: bytemuck::cast_slice(&[Self::uniform(
+                &Tonemapper::default(),
+                &Exposure::default(),
+                None,
+                needs_shader_encode(format),
+            )]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let lens_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Lens buffer"),
+            contents: bytemuck::cast_slice(&[LensUniform::new(
+                &LensEffects::default(),
+                &ScreenOverlays::default(),
+                0.0,
+            )]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let lut_view = ColorGradingLut::identity(2).create_view(device, queue);
+
+        let bind_group = Self::create_bind_group(
+            device,
+            &layout,
+            scene_view,
+            &scene_sampler,
+            &buffer,
+            luminance_buffer,
+            &lut_view,
+            &lut_sampler,
+            &lens_buffer,
+        );
+
+        Self {
+            pipeline,
+            layout,
+            scene_sampler,
+            lut_sampler,
+            buffer,
+            lens_buffer,
+            lut_view,
+            lut_size: None,
+            encode_srgb: needs_shader_encode(format),
+            bind_group,
+        }
+    }
+
+    fn uniform(
+        tonemapper: &Tonemapper,
+        exposure: &Exposure,
+        lut_size: Option<u32>,
+        encode_srgb: bool,
+    ) -> TonemapUniform {
+        TonemapUniform {
+            tonemapper: tonemapper.index(),
+            auto_exposure: matches!(exposure, Exposure::Auto { .. }) as u32,
+            exposure: exposure.multiplier(),
+            lut_enabled: lut_size.is_some() as u32,
+            lut_size: lut_size.unwrap_or(2) as f32,
+            encode_srgb: encode_srgb as u32,
+            _padding: [0.0; 2],
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        scene_view: &TextureView,
+        scene_sampler: &Sampler,
+        buffer: &Buffer,
+        luminance_buffer: &Buffer,
+        lut_view: &TextureView,
+        lut_sampler: &Sampler,
+        lens_buffer: &Buffer,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Tonemap bind group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(scene_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(scene_sampler),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: luminance_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: BindingResource::TextureView(lut_view),
+                },
+                BindGroupEntry {
+                    binding: 5,
+                    resource: BindingResource::Sampler(lut_sampler),
+                },
+                BindGroupEntry {
+                    binding: 6,
+                    resource: lens_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    /// Call this when the scene texture is recreated
+    pub fn set_scene_view(
+        &mut self,
+        device: &Device,
+        scene_view: &TextureView,
+        luminance_buffer: &Buffer,
+    ) {
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.layout,
+            scene_view,
+            &self.scene_sampler,
+            &self.buffer,
+            luminance_buffer,
+            &self.lut_view,
+            &self.lut_sampler,
+            &self.lens_buffer,
+        );
+    }
+
+    pub fn set_settings(&self, queue: &Queue, tonemapper: &Tonemapper, exposure: &Exposure) {
+        queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::cast_slice(&[Self::uniform(
+                tonemapper,
+                exposure,
+                self.lut_size,
+                self.encode_srgb,
+            )]),
+        );
+    }
+
+    /// Writes the lens effects and screen overlays, call this every frame so the grain
+    /// and the overlays are animated
+    pub fn set_lens(
+        &self,
+        queue: &Queue,
+        lens: &LensEffects,
+        overlays: &ScreenOverlays,
+        time: f32,
+    ) {
+        queue.write_buffer(
+            &self.lens_buffer,
+            0,
+            bytemuck::cast_slice(&[LensUniform::new(lens, overlays, time)]),
+        );
+    }
+
+    /// Changes the color grading, the bind group has to be recreated with `set_scene_view`
+    pub fn set_color_grading(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        lut: Option<&ColorGradingLut>,
+    ) {
+        self.lut_view = lut
+            .unwrap_or(&ColorGradingLut::identity(2))
+            .create_view(device, queue);
+        self.lut_size = lut.map(|lut| lut.get_size());
+    }
+
+    pub fn draw(&self, render_pass: &mut RenderPass) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,372 @@
+use cgmath::{Matrix4, SquareMatrix, Vector2, Vector3};
+use wgpu::{
+    include_wgsl,
+    util::{BufferInitDescriptor, DeviceExt},
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferAddress, BufferBindingType, BufferDescriptor,
+    BufferUsages, Color, ColorTargetState, ColorWrites, CommandEncoder, CompareFunction,
+    DepthBiasState, DepthStencilState, Device, Extent3d, Face, FragmentState, FrontFace,
+    IndexFormat, LoadOp, MultisampleState, Operations, PipelineCompilationOptions,
+    PipelineLayoutDescriptor, PolygonMode, PrimitiveState, PrimitiveTopology, Queue,
+    RenderPassColorAttachment, RenderPassDepthStencilAttachment, RenderPassDescriptor,
+    RenderPipeline, RenderPipelineDescriptor, ShaderStages, StencilState, StoreOp,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
+    TextureViewDescriptor, VertexAttribute, VertexBufferLayout, VertexFormat, VertexState,
+    VertexStepMode,
+};
+
+use crate::{
+    camera::Camera,
+    helium_texture,
+    instance::INSTANCE_RAW_SIZE,
+    model::{model_vertex::ModelVertex, Model},
+};
+
+// Format of the texture the screen space motion is drawn to
+const VELOCITY_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct MotionUniform {
+    jittered_view_proj: [[f32; 4]; 4],
+    view_proj: [[f32; 4]; 4],
+    previous_view_proj: [[f32; 4]; 4],
+    inverse_view_proj: [[f32; 4]; 4],
+}
+
+/// Draws how far every model moved on the screen since the last frame for the effects
+/// that follow the motion of the scene
+///
+/// The model matrices of the last frame are kept in a copy of the instance buffer since
+/// the model pipelines have no vertex attributes left for them
+pub(crate) struct VelocityPass {
+    pipeline: RenderPipeline,
+    buffer: Buffer,
+    bind_group: BindGroup,
+    velocity_view: TextureView,
+    depth_view: TextureView,
+    previous_instance_buffer: Option<Buffer>,
+    previous_view_proj: Option<Matrix4<f32>>,
+}
+
+impl VelocityPass {
+    pub fn new(device: &Device, size: (u32, u32)) -> Self {
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Velocity Bind Group Layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Velocity Render Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("../shaders/velocity_shader.wgsl"));
+
+        // Only the position of the vertices and the model matrices are read
+        let vertex_attributes = [VertexAttribute {
+            offset: 0,
+            shader_location: 0,
+            format: VertexFormat::Float32x3,
+        }];
+
+        let matrix_attributes = |first_location: u32| {
+            [0, 1, 2, 3].map(|column: u32| VertexAttribute {
+                offset: (column as usize * std::mem::size_of::<[f32; 4]>()) as BufferAddress,
+                shader_location: first_location + column,
+                format: VertexFormat::Float32x4,
+            })
+        };
+
+        let current_attributes = matrix_attributes(5);
+        let previous_attributes = matrix_attributes(9);
+
+        let instance_layout = |attributes| VertexBufferLayout {
+            array_stride: INSTANCE_RAW_SIZE as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes,
+        };
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Velocity Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[
+                    VertexBufferLayout {
+                        array_stride: std::mem::size_of::<ModelVertex>() as BufferAddress,
+                        step_mode: VertexStepMode::Vertex,
+                        attributes: &vertex_attributes,
+                    },
+                    instance_layout(&current_attributes),
+                    instance_layout(&previous_attributes),
+                ],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format: VELOCITY_FORMAT,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                cull_mode: Some(Face::Back),
+                polygon_mode: PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: helium_texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Less,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Motion buffer"),
+            contents: bytemuck::cast_slice(&[MotionUniform {
+                jittered_view_proj: Matrix4::identity().into(),
+                view_proj: Matrix4::identity().into(),
+                previous_view_proj: Matrix4::identity().into(),
+                inverse_view_proj: Matrix4::identity().into(),
+            }]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Velocity bind group"),
+            layout: &layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        let (velocity_view, depth_view) = Self::create_targets(device, size);
+
+        Self {
+            pipeline,
+            buffer,
+            bind_group,
+            velocity_view,
+            depth_view,
+            previous_instance_buffer: None,
+            previous_view_proj: None,
+        }
+    }
+
+    fn create_targets(device: &Device, size: (u32, u32)) -> (TextureView, TextureView) {
+        let extent = Extent3d {
+            width: size.0.max(1),
+            height: size.1.max(1),
+            depth_or_array_layers: 1,
+        };
+
+        let create_view = |label, format, usage| {
+            device
+                .create_texture(&TextureDescriptor {
+                    label: Some(label),
+                    size: extent,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage,
+                    view_formats: &[],
+                })
+                .create_view(&TextureViewDescriptor::default())
+        };
+
+        let velocity_view = create_view(
+            "Velocity Texture",
+            VELOCITY_FORMAT,
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        );
+        let depth_view = create_view(
+            "Velocity Depth Texture",
+            helium_texture::DEPTH_FORMAT,
+            TextureUsages::RENDER_ATTACHMENT,
+        );
+
+        (velocity_view, depth_view)
+    }
+
+    // Call this when resizing the window
+    pub fn resize(&mut self, device: &Device, size: (u32, u32)) {
+        (self.velocity_view, self.depth_view) = Self::create_targets(device, size);
+        self.reset();
+    }
+
+    /// Forgets the last frame so the next frame has no motion
+    pub fn reset(&mut self) {
+        self.previous_instance_buffer = None;
+        self.previous_view_proj = None;
+    }
+
+    /// Buffer holding the current and last view projection of the camera
+    pub fn get_buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Texture holding the motion in uv coordinates in rg, alpha is 1 where a model was drawn
+    pub fn get_velocity_view(&self) -> &TextureView {
+        &self.velocity_view
+    }
+
+    /// Writes the view projection of the camera for this frame
+    ///
+    /// # Arguments
+    ///
+    /// * `camera` - The camera the scene is drawn with
+    /// * `jitter` - Offset of the camera in clip space
+    ///
+    /// # Returns
+    ///
+    /// The view projection of the camera with the jitter applied
+    pub fn update_camera(
+        &mut self,
+        queue: &Queue,
+        camera: &Camera,
+        jitter: Vector2<f32>,
+    ) -> Matrix4<f32> {
+        let view_proj = camera.build_view_projection_matrix();
+        let jittered_view_proj =
+            Matrix4::from_translation(Vector3::new(jitter.x, jitter.y, 0.0)) * view_proj;
+
+        queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::cast_slice(&[MotionUniform {
+                jittered_view_proj: jittered_view_proj.into(),
+                view_proj: view_proj.into(),
+                previous_view_proj: self.previous_view_proj.unwrap_or(view_proj).into(),
+                inverse_view_proj: jittered_view_proj
+                    .invert()
+                    .unwrap_or(Matrix4::identity())
+                    .into(),
+            }]),
+        );
+
+        self.previous_view_proj = Some(view_proj);
+        jittered_view_proj
+    }
+
+    /// Draws how far the models moved on the screen since the last frame
+    ///
+    /// # Arguments
+    ///
+    /// * `encoder` - The encoder of the frame
+    /// * `models` - The models of the scene
+    /// * `excluded` - Indices of models whose instances are not in the instance buffer
+    /// * `instance_buffer` - The instance buffer the models are drawn with
+    pub fn draw(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        models: &[Option<Model>],
+        excluded: &[usize],
+        instance_buffer: &Buffer,
+    ) {
+        // The previous instances start as a copy of the current ones when the buffer changes
+        if self
+            .previous_instance_buffer
+            .as_ref()
+            .is_none_or(|buffer| buffer.size() != instance_buffer.size())
+        {
+            let buffer = device.create_buffer(&BufferDescriptor {
+                label: Some("Previous instance buffer"),
+                size: instance_buffer.size(),
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            encoder.copy_buffer_to_buffer(instance_buffer, 0, &buffer, 0, instance_buffer.size());
+            self.previous_instance_buffer = Some(buffer);
+        }
+
+        let Some(previous_instance_buffer) = self.previous_instance_buffer.as_ref() else {
+            return;
+        };
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Velocity Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &self.velocity_view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::TRANSPARENT),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: &self.depth_view,
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Clear(1.0),
+                        store: StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+            render_pass.set_vertex_buffer(2, previous_instance_buffer.slice(..));
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+
+            for (model_index, model) in models.iter().enumerate() {
+                let Some(model) = model else {
+                    continue;
+                };
+
+                if excluded.contains(&model_index) {
+                    continue;
+                }
+
+                for mesh in model.get_meshes().iter() {
+                    // Lines and points are treated as not moving
+                    if !mesh.get_topology().is_triangles() {
+                        continue;
+                    }
+
+                    render_pass.set_vertex_buffer(0, mesh.get_vertex_buffer().slice(..));
+                    render_pass
+                        .set_index_buffer(mesh.get_index_buffer().slice(..), IndexFormat::Uint32);
+                    render_pass.draw_indexed(0..mesh.get_num_elements(), 0, mesh.get_instances());
+                }
+            }
+        }
+
+        encoder.copy_buffer_to_buffer(
+            instance_buffer,
+            0,
+            previous_instance_buffer,
+            0,
+            instance_buffer.size(),
+        );
+    }
+}
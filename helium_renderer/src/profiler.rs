@@ -0,0 +1,292 @@
+use std::{
+    cell::Cell,
+    collections::VecDeque,
+    fmt::Write as _,
+    fs, io,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+// Number of spans that are kept when the capacity is not set
+const DEFAULT_CAPACITY: usize = 100_000;
+
+// Number of the newest spans that are searched for the last frame of each thread
+const LAST_FRAME_SEARCH: usize = 4096;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static NEXT_THREAD: AtomicU32 = AtomicU32::new(0);
+
+// The time every span start is measured from
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+// The finished spans of every thread with the names of the threads
+struct ProfilerState {
+    spans: VecDeque<ProfileRecord>,
+    capacity: usize,
+    threads: Vec<(u32, String)>,
+}
+
+static PROFILER: Mutex<ProfilerState> = Mutex::new(ProfilerState {
+    spans: VecDeque::new(),
+    capacity: DEFAULT_CAPACITY,
+    threads: Vec::new(),
+});
+
+thread_local! {
+    // The id of the thread in the profile, given out the first time the thread opens a span
+    static THREAD_ID: Cell<Option<u32>> = const { Cell::new(None) };
+    // How many spans are open on the thread
+    static DEPTH: Cell<u32> = const { Cell::new(0) };
+}
+
+// Runs a change on the state, a poisoned state is still used since the spans are plain data
+fn with_state<F, R>(change: F) -> R
+where
+    F: FnOnce(&mut ProfilerState) -> R,
+{
+    let mut state = PROFILER
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    change(&mut state)
+}
+
+fn thread_id() -> u32 {
+    THREAD_ID.with(|id| {
+        if let Some(id) = id.get() {
+            return id;
+        }
+
+        let new_id = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+        let name = thread::current()
+            .name()
+            .map_or_else(|| format!("thread {}", new_id), str::to_string);
+        with_state(|state| state.threads.push((new_id, name)));
+        id.set(Some(new_id));
+        new_id
+    })
+}
+
+/// A span of time that was measured on a thread
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProfileRecord {
+    pub name: &'static str,
+    /// The id of the thread the span ran on
+    pub thread: u32,
+    /// When the span started since the profiler was first used
+    pub start: Duration,
+    pub duration: Duration,
+    /// How many spans the span is inside of, spans at depth 0 are frames or other work
+    /// that is not inside anything
+    pub depth: u32,
+}
+
+impl ProfileRecord {
+    pub fn end(&self) -> Duration {
+        self.start + self.duration
+    }
+}
+
+/// Measures the time until it is dropped when profiling is turned on, spans opened while
+/// it is alive are nested inside of it. With the `profiling` feature the span is also
+/// entered as a `tracing` span named `helium` with the name in its `name` field
+#[must_use = "the span ends as soon as it is dropped"]
+pub struct ProfileSpan {
+    name: &'static str,
+    // `None` when profiling was off when the span was opened
+    start: Option<Instant>,
+    #[cfg(feature = "profiling")]
+    _tracing: tracing::span::EnteredSpan,
+}
+
+impl Drop for ProfileSpan {
+    fn drop(&mut self) {
+        let Some(start) = self.start else {
+            return;
+        };
+
+        let duration = start.elapsed();
+        let depth = DEPTH.with(|depth| {
+            let parent_depth = depth.get().saturating_sub(1);
+            depth.set(parent_depth);
+            parent_depth
+        });
+        let epoch = *EPOCH.get_or_init(|| start);
+        let record = ProfileRecord {
+            name: self.name,
+            thread: thread_id(),
+            start: start.saturating_duration_since(epoch),
+            duration,
+            depth,
+        };
+
+        with_state(|state| {
+            if state.spans.len() >= state.capacity {
+                state.spans.pop_front();
+            }
+            state.spans.push_back(record);
+        });
+    }
+}
+
+/// Opens a span that measures the time until it is dropped, it does nothing while
+/// profiling is off
+///
+/// # Arguments
+///
+/// * `name` - The name of the work the span measures, like the name of a system or pass
+///
+/// # Returns
+///
+/// The span to keep alive for as long as the work runs
+pub fn profile_span(name: &'static str) -> ProfileSpan {
+    // The subscriber decides if the tracing span is recorded, it does not need profiling on
+    #[cfg(feature = "profiling")]
+    let _tracing = tracing::info_span!("helium", name).entered();
+
+    let start = ENABLED.load(Ordering::Relaxed).then(|| {
+        DEPTH.with(|depth| depth.set(depth.get() + 1));
+        let start = Instant::now();
+        EPOCH.get_or_init(|| start);
+        start
+    });
+
+    ProfileSpan {
+        name,
+        start,
+        #[cfg(feature = "profiling")]
+        _tracing,
+    }
+}
+
+/// Marks the end of a frame of a thread, like an update or a render. With the `profiling`
+/// feature it is sent as a `tracing` event with the `helium::frame` target so tools can
+/// split the trace into frames, it does nothing without the feature
+///
+/// # Arguments
+///
+/// * `name` - The name of the frame, like `"update"` or `"render"`
+#[cfg_attr(not(feature = "profiling"), allow(unused_variables))]
+pub fn profile_frame(name: &'static str) {
+    #[cfg(feature = "profiling")]
+    tracing::info!(target: "helium::frame", frame = name);
+}
+
+/// Turns the recording of spans on every thread on or off, it is off by default
+pub fn set_profiling(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_profiling() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Sets how many spans are kept, the oldest spans are forgotten past it
+pub fn set_profile_capacity(capacity: usize) {
+    with_state(|state| {
+        state.capacity = capacity.max(1);
+        let excess = state.spans.len().saturating_sub(state.capacity);
+        state.spans.drain(..excess);
+    });
+}
+
+/// Forgets every recorded span
+pub fn clear_profile() {
+    with_state(|state| state.spans.clear());
+}
+
+/// Gives the recorded spans in the order they ended, a span ends after the spans inside
+/// of it
+pub fn get_profile_spans() -> Vec<ProfileRecord> {
+    with_state(|state| state.spans.iter().copied().collect())
+}
+
+/// Gives the last span at depth 0 of each thread with the spans inside of it, like the
+/// last update and the last render
+pub fn get_last_frame_spans() -> Vec<ProfileRecord> {
+    with_state(|state| {
+        // The last span at depth 0 of each thread that has been found
+        let mut frames: Vec<ProfileRecord> = Vec::new();
+        let mut spans = Vec::new();
+
+        // Spans end after the spans inside of them so the frame comes before its children
+        for span in state.spans.iter().rev().take(LAST_FRAME_SEARCH) {
+            let frame = frames.iter().find(|frame| frame.thread == span.thread);
+            match frame {
+                None if span.depth == 0 => {
+                    frames.push(*span);
+                    spans.push(*span);
+                }
+                Some(frame) if span.depth > 0 && span.start >= frame.start => spans.push(*span),
+                _ => {}
+            }
+        }
+
+        spans.reverse();
+        spans
+    })
+}
+
+/// Gives the ids of the threads that recorded spans with their names
+pub fn get_profile_threads() -> Vec<(u32, String)> {
+    with_state(|state| state.threads.clone())
+}
+
+// Escapes the characters that can not be in a JSON string
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            character if character.is_control() => {
+                _ = write!(escaped, "\\u{:04x}", character as u32);
+            }
+            character => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Writes the recorded spans as a Chrome trace that can be opened in `chrome://tracing`
+/// or Perfetto
+///
+/// # Arguments
+///
+/// * `path` - Filepath to write the trace to
+pub fn export_chrome_trace<P>(path: P) -> Result<(), io::Error>
+where
+    P: AsRef<Path>,
+{
+    let (spans, threads) = with_state(|state| (state.spans.clone(), state.threads.clone()));
+
+    let mut events = Vec::with_capacity(spans.len() + threads.len());
+    for (thread, name) in threads {
+        events.push(format!(
+            "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{},\"args\":{{\"name\":\"{}\"}}}}",
+            thread,
+            escape_json(&name),
+        ));
+    }
+    for span in spans {
+        events.push(format!(
+            "{{\"name\":\"{}\",\"cat\":\"helium\",\"ph\":\"X\",\"pid\":1,\"tid\":{},\"ts\":{:.3},\"dur\":{:.3}}}",
+            escape_json(span.name),
+            span.thread,
+            span.start.as_secs_f64() * 1_000_000.0,
+            span.duration.as_secs_f64() * 1_000_000.0,
+        ));
+    }
+
+    fs::write(
+        path,
+        format!(
+            "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[\n{}\n]}}\n",
+            events.join(",\n")
+        ),
+    )
+}
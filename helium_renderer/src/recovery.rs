@@ -0,0 +1,131 @@
+use std::{
+    io,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use log::*;
+use wgpu::{Device, DeviceLostReason, Queue};
+
+use crate::{
+    model::{
+        material::{ColorMaterial, TextureAnimation},
+        mesh::Topology,
+        occlusion::AmbientOcclusionBake,
+        Model, StaticBatchObject,
+    },
+    outline::Outline,
+    reflection::Reflection,
+};
+
+/// Where the meshes and textures of an object were loaded from
+#[derive(Clone)]
+pub(crate) enum ObjectSource {
+    // An obj, gltf, or glb file
+    File(PathBuf),
+    StaticBatch(Vec<StaticBatchObject>),
+    Lines(Vec<[f32; 3]>, Topology),
+}
+
+/// What is needed to load an object onto a new device after the old one was lost
+#[derive(Clone)]
+pub(crate) struct ObjectRecord {
+    pub source: ObjectSource,
+    // The ambient occlusion that was baked into the vertices when it was loaded
+    pub occlusion: Option<AmbientOcclusionBake>,
+    pub color_material: Option<ColorMaterial>,
+    // The name and file of every morph target of an obj object
+    pub morph_targets: Vec<(String, PathBuf)>,
+    pub texture_animation: Option<TextureAnimation>,
+    pub outline: Option<Outline>,
+    pub reflection: Option<Reflection>,
+}
+
+impl ObjectRecord {
+    pub fn new(source: ObjectSource, occlusion: Option<AmbientOcclusionBake>) -> Self {
+        Self {
+            source,
+            occlusion,
+            color_material: None,
+            morph_targets: Vec::new(),
+            texture_animation: None,
+            outline: None,
+            reflection: None,
+        }
+    }
+
+    /// Loads the model again from its files with the color material it was given
+    pub fn load(&self, device: &Device, queue: &Queue) -> Result<Model, io::Error> {
+        let mut model = match &self.source {
+            ObjectSource::File(path) => {
+                Model::from_file(path, self.occlusion.as_ref(), device, queue)?
+            }
+            ObjectSource::StaticBatch(objects) => {
+                Model::static_batch(objects, self.occlusion.as_ref(), device, queue)?
+            }
+            ObjectSource::Lines(points, topology) => {
+                Model::from_lines(points, *topology, device, queue)
+            }
+        };
+
+        if let Some(color_material) = self.color_material {
+            model.set_color_material(color_material, device, queue);
+        }
+
+        if let Some(texture_animation) = self.texture_animation {
+            model.set_texture_animation(texture_animation, queue);
+        }
+
+        if let ObjectSource::File(path) = &self.source {
+            if !self.morph_targets.is_empty() {
+                model.load_morph_targets(path, &self.morph_targets, device)?;
+            }
+        }
+
+        Ok(model)
+    }
+}
+
+/// The files that the gpu resources of the scene were created from, the gpu copies
+/// are gone when the device is lost so they are loaded from here again
+#[derive(Default)]
+pub(crate) struct AssetRecords {
+    // The objects by their index, removed objects have no record
+    pub objects: Vec<Option<ObjectRecord>>,
+    pub environment: Option<(PathBuf, f32)>,
+    pub color_grading: Option<PathBuf>,
+}
+
+impl AssetRecords {
+    /// Keeps the record of an object at its index
+    pub fn set_object(&mut self, object_index: usize, record: Option<ObjectRecord>) {
+        if self.objects.len() <= object_index {
+            self.objects.resize(object_index + 1, None);
+        }
+
+        self.objects[object_index] = record;
+    }
+
+    pub fn get_object_mut(&mut self, object_index: usize) -> Option<&mut ObjectRecord> {
+        self.objects.get_mut(object_index)?.as_mut()
+    }
+}
+
+/// Sets a flag when the driver loses the device, like after a driver reset or when the
+/// gpu is removed. Devices destroyed by the renderer do not set it
+pub(crate) fn watch_device_loss(device: &Device) -> Arc<AtomicBool> {
+    let device_lost = Arc::new(AtomicBool::new(false));
+    let flag = device_lost.clone();
+
+    device.set_device_lost_callback(move |reason, message| {
+        if reason == DeviceLostReason::Unknown {
+            error!("The gpu device was lost: {message}");
+            flag.store(true, Ordering::Release);
+        }
+    });
+
+    device_lost
+}
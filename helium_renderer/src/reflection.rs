@@ -0,0 +1,509 @@
+use cgmath::{InnerSpace, Matrix4, Transform, Vector3, Vector4};
+use wgpu::{
+    include_wgsl,
+    util::{BufferInitDescriptor, DeviceExt},
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, BlendState,
+    Buffer, BufferBindingType, BufferUsages, Color, ColorTargetState, ColorWrites, CommandEncoder,
+    CompareFunction, DepthBiasState, DepthStencilState, Device, Extent3d, Face, FilterMode,
+    FragmentState, FrontFace, IndexFormat, LoadOp, MultisampleState, Operations,
+    PipelineCompilationOptions, PipelineLayoutDescriptor, PolygonMode, PrimitiveState,
+    PrimitiveTopology, Queue, RenderPassColorAttachment, RenderPassDepthStencilAttachment,
+    RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, Sampler, SamplerBindingType,
+    SamplerDescriptor, ShaderModuleDescriptor, ShaderStages, StencilState, StoreOp,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
+    TextureView, TextureViewDescriptor, TextureViewDimension, VertexState,
+};
+
+use crate::{
+    camera::{Camera, CameraUniform},
+    helium_texture,
+    instance::InstanceRaw,
+    layouts::{BindGroupCache, CachedResource, LayoutKind, LayoutRegistry},
+    model::{model_vertex::ModelVertex, vertex::Vertex, Model},
+};
+
+/// A flat reflective surface like water or a mirror, the scene is mirrored about the plane
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reflection {
+    /// Any point on the plane of the surface
+    pub point: Vector3<f32>,
+    /// Direction the surface faces, only objects on this side of the plane are reflected
+    pub normal: Vector3<f32>,
+    /// How much of the reflection is mixed into the color of the surface from 0 to 1
+    pub reflectivity: f32,
+}
+
+impl Default for Reflection {
+    fn default() -> Self {
+        Self {
+            point: Vector3::new(0.0, 0.0, 0.0),
+            normal: Vector3::unit_y(),
+            reflectivity: 0.8,
+        }
+    }
+}
+
+impl Reflection {
+    // Normal of the plane in xyz and the distance from the origin in w
+    fn plane(&self) -> Vector4<f32> {
+        let normal = self.normal.normalize();
+        normal.extend(-normal.dot(self.point))
+    }
+
+    // Matrix that mirrors a point about the plane
+    fn mirror_matrix(&self) -> Matrix4<f32> {
+        let plane = self.plane();
+        let (a, b, c, d) = (plane.x, plane.y, plane.z, plane.w);
+
+        #[rustfmt::skip]
+        let matrix = Matrix4::new(
+            1.0 - 2.0 * a * a, -2.0 * a * b,       -2.0 * a * c,       0.0,
+            -2.0 * a * b,       1.0 - 2.0 * b * b, -2.0 * b * c,       0.0,
+            -2.0 * a * c,       -2.0 * b * c,       1.0 - 2.0 * c * c, 0.0,
+            -2.0 * a * d,       -2.0 * b * d,       -2.0 * c * d,       1.0,
+        );
+
+        matrix
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ReflectionUniform {
+    reflectivity: f32,
+    _padding: [f32; 3],
+}
+
+impl From<Reflection> for ReflectionUniform {
+    fn from(reflection: Reflection) -> Self {
+        Self {
+            reflectivity: reflection.reflectivity,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+// The offscreen targets and camera of a single reflective object
+struct ReflectionPlane {
+    object_index: usize,
+    reflection: Reflection,
+    camera_buffer: Buffer,
+    camera_bind_group: BindGroup,
+    buffer: Buffer,
+    color_view: TextureView,
+    depth_view: TextureView,
+    bind_group: BindGroup,
+}
+
+/// Draws the scene mirrored about reflective surfaces into offscreen textures that the
+/// surfaces sample when they are drawn
+pub struct ReflectionRenderer {
+    scene_pipeline: RenderPipeline,
+    surface_pipeline: RenderPipeline,
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    format: TextureFormat,
+    size: (u32, u32),
+    planes: Vec<ReflectionPlane>,
+}
+
+impl ReflectionRenderer {
+    pub fn new(
+        device: &Device,
+        layouts: &LayoutRegistry,
+        format: TextureFormat,
+        size: (u32, u32),
+    ) -> Self {
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Reflection Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: TextureViewDimension::D2,
+                        sample_type: TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let camera_layout = layouts.get(LayoutKind::Camera);
+        let material_layout = layouts.get(LayoutKind::Material);
+        let lights_layout = layouts.get(LayoutKind::Lights);
+
+        // Mirroring the scene flips the winding of the triangles
+        let scene_pipeline = Self::create_pipeline(
+            device,
+            &[material_layout, camera_layout, lights_layout],
+            include_wgsl!("./shaders/fragment_shader.wgsl"),
+            format,
+            FrontFace::Cw,
+            "Reflected Scene",
+        );
+
+        let surface_pipeline = Self::create_pipeline(
+            device,
+            &[material_layout, camera_layout, lights_layout, &layout],
+            include_wgsl!("./shaders/reflection_shader.wgsl"),
+            format,
+            FrontFace::Ccw,
+            "Reflective Surface",
+        );
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Reflection Sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            scene_pipeline,
+            surface_pipeline,
+            layout,
+            sampler,
+            format,
+            size,
+            planes: Vec::new(),
+        }
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        layouts: &[&BindGroupLayout],
+        fragment_shader: ShaderModuleDescriptor,
+        format: TextureFormat,
+        front_face: FrontFace,
+        name: &str,
+    ) -> RenderPipeline {
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some(&(name.to_string() + " Render Pipeline Layout")),
+            bind_group_layouts: layouts,
+            push_constant_ranges: &[],
+        });
+
+        let vertex_shader =
+            device.create_shader_module(include_wgsl!("./shaders/vertex_shader.wgsl"));
+        let fragment_shader = device.create_shader_module(fragment_shader);
+
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some(&(name.to_string() + " Render Pipeline")),
+            layout: Some(&layout),
+            vertex: VertexState {
+                module: &vertex_shader,
+                entry_point: Some("main"),
+                buffers: &[ModelVertex::desc(), InstanceRaw::desc()],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &fragment_shader,
+                entry_point: Some("main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face,
+                cull_mode: Some(Face::Back),
+                polygon_mode: PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: helium_texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Less,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    // Creates the screen sized color and depth textures that the mirrored scene is drawn to
+    fn create_targets(
+        device: &Device,
+        format: TextureFormat,
+        size: (u32, u32),
+    ) -> (TextureView, TextureView) {
+        let extent = Extent3d {
+            width: size.0.max(1),
+            height: size.1.max(1),
+            depth_or_array_layers: 1,
+        };
+
+        let create_view = |label, format, usage| {
+            device
+                .create_texture(&TextureDescriptor {
+                    label: Some(label),
+                    size: extent,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage,
+                    view_formats: &[],
+                })
+                .create_view(&TextureViewDescriptor::default())
+        };
+
+        let color_view = create_view(
+            "Reflection Texture",
+            format,
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        );
+        let depth_view = create_view(
+            "Reflection Depth Texture",
+            helium_texture::DEPTH_FORMAT,
+            TextureUsages::RENDER_ATTACHMENT,
+        );
+
+        (color_view, depth_view)
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &Device,
+        color_view: &TextureView,
+        buffer: &Buffer,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Reflection bind group"),
+            layout: &self.layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(color_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&self.sampler),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    // Call this when resizing the window
+    pub fn resize(&mut self, device: &Device, size: (u32, u32)) {
+        self.size = size;
+
+        for index in 0..self.planes.len() {
+            let (color_view, depth_view) = Self::create_targets(device, self.format, size);
+            let bind_group =
+                self.create_bind_group(device, &color_view, &self.planes[index].buffer);
+
+            let plane = &mut self.planes[index];
+            plane.color_view = color_view;
+            plane.depth_view = depth_view;
+            plane.bind_group = bind_group;
+        }
+    }
+
+    pub fn get_surface_pipeline(&self) -> &RenderPipeline {
+        &self.surface_pipeline
+    }
+
+    /// Gets the index of the reflection of an object, `None` if the object is not reflective
+    pub fn get_reflection_index(&self, object_index: usize) -> Option<usize> {
+        self.planes
+            .iter()
+            .position(|plane| plane.object_index == object_index)
+    }
+
+    pub fn get_bind_group(&self, reflection_index: usize) -> &BindGroup {
+        &self.planes[reflection_index].bind_group
+    }
+
+    /// Adds, changes, or removes the reflection of an object
+    ///
+    /// # Arguments
+    ///
+    /// * `object_index` - The index of the object in the renderer
+    /// * `reflection` - The plane and reflectivity of the surface, `None` removes the reflection
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_reflection(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        layouts: &LayoutRegistry,
+        bind_groups: &mut BindGroupCache,
+        object_index: usize,
+        reflection: Option<Reflection>,
+    ) {
+        match (reflection, self.get_reflection_index(object_index)) {
+            (Some(reflection), Some(position)) => {
+                let plane = &mut self.planes[position];
+                if plane.reflection != reflection {
+                    plane.reflection = reflection;
+                    queue.write_buffer(
+                        &plane.buffer,
+                        0,
+                        bytemuck::cast_slice(&[ReflectionUniform::from(reflection)]),
+                    );
+                }
+            }
+            (Some(reflection), None) => {
+                let buffer = device.create_buffer_init(&BufferInitDescriptor {
+                    label: Some("Reflection buffer"),
+                    contents: bytemuck::cast_slice(&[ReflectionUniform::from(reflection)]),
+                    usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                });
+
+                let camera_buffer = device.create_buffer_init(&BufferInitDescriptor {
+                    label: Some("Reflection camera buffer"),
+                    contents: bytemuck::cast_slice(&[CameraUniform::default()]),
+                    usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                });
+
+                let camera_bind_group = bind_groups.get_or_create(
+                    device,
+                    layouts,
+                    LayoutKind::Camera,
+                    &[CachedResource::Buffer(camera_buffer.clone())],
+                );
+
+                let (color_view, depth_view) = Self::create_targets(device, self.format, self.size);
+                let bind_group = self.create_bind_group(device, &color_view, &buffer);
+
+                self.planes.push(ReflectionPlane {
+                    object_index,
+                    reflection,
+                    camera_buffer,
+                    camera_bind_group,
+                    buffer,
+                    color_view,
+                    depth_view,
+                    bind_group,
+                });
+            }
+            (None, Some(position)) => {
+                let plane = self.planes.remove(position);
+                bind_groups.remove(&CachedResource::Buffer(plane.camera_buffer));
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// Mirrors the camera about every reflective surface, call this before drawing
+    pub fn update_cameras(&self, queue: &Queue, camera: &Camera) {
+        let view_proj = camera.build_view_projection_matrix();
+
+        for plane in self.planes.iter() {
+            let mirror = plane.reflection.mirror_matrix();
+            let eye = mirror.transform_point(camera.eye);
+
+            // Keeps the time of the camera so animated materials match in the reflection
+            let mut uniform = *camera.get_uniform();
+            uniform.update_view_proj_with_matrix(eye, view_proj * mirror);
+            // Objects below the surface would be mirrored above it
+            uniform.set_clip_plane(plane.reflection.plane().into());
+
+            queue.write_buffer(&plane.camera_buffer, 0, bytemuck::cast_slice(&[uniform]));
+        }
+    }
+
+    /// Draws the mirrored scene for every reflective surface, reflective surfaces themselves
+    /// and scattered objects are left out of the reflections
+    pub fn draw(
+        &self,
+        encoder: &mut CommandEncoder,
+        models: &[Option<Model>],
+        excluded: &[usize],
+        instance_buffer: &Buffer,
+        lights_bind_group: &BindGroup,
+    ) {
+        for plane in self.planes.iter() {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Reflection Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &plane.color_view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: &plane.depth_view,
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Clear(1.0),
+                        store: StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(&self.scene_pipeline);
+            render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+            render_pass.set_bind_group(1, &plane.camera_bind_group, &[]);
+            render_pass.set_bind_group(2, lights_bind_group, &[]);
+
+            for (model_index, model) in models.iter().enumerate() {
+                let Some(model) = model else {
+                    continue;
+                };
+
+                if excluded.contains(&model_index)
+                    || self.get_reflection_index(model_index).is_some()
+                {
+                    continue;
+                }
+
+                for mesh in model.get_meshes().iter() {
+                    // Lines and points are left out of the mirrored scene
+                    if !mesh.get_topology().is_triangles() {
+                        continue;
+                    }
+
+                    let material = &model.get_materials()[*mesh.get_material_index().unwrap()];
+                    render_pass.set_bind_group(0, material.get_bind_group(), &[]);
+                    render_pass.set_vertex_buffer(0, mesh.get_vertex_buffer().slice(..));
+                    render_pass
+                        .set_index_buffer(mesh.get_index_buffer().slice(..), IndexFormat::Uint32);
+                    render_pass.draw_indexed(0..mesh.get_num_elements(), 0, mesh.get_instances());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use std::io;
+
+use wgpu::{BindGroup, CommandEncoder, Device, Queue, TextureView};
+
+/// Points in the frame where custom passes are run, passes in the same stage run in the
+/// order they were added
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RenderStage {
+    /// After the scene, decals, and outlines are drawn into the hdr scene texture and
+    /// before the post stack runs
+    AfterScene,
+    /// After the post stack drew the scene onto the screen and before the overlays
+    AfterPost,
+    /// After everything else has been drawn onto the screen
+    AfterOverlay,
+}
+
+/// Textures of the frame that custom passes can read or write
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RenderResource {
+    /// The hdr texture the scene is drawn to
+    SceneColor,
+    /// The depth buffer of the scene
+    SceneDepth,
+    /// The screen, it can only be written to
+    Surface,
+}
+
+/// Handle to a custom pass that has been added to the render graph
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RenderPassHandle(pub usize);
+
+/// A pass that users can insert into the frame without changing the renderer
+pub trait CustomRenderPass: Send {
+    /// Resources the pass samples from, they are bound as textures
+    fn inputs(&self) -> Vec<RenderResource> {
+        Vec::new()
+    }
+
+    /// Resources the pass draws to, they are used as attachments
+    fn outputs(&self) -> Vec<RenderResource>;
+
+    /// Called when the window is resized so the pass can recreate its own textures
+    fn resize(&mut self, _device: &Device, _size: (u32, u32)) {}
+
+    /// Records the commands of the pass
+    fn execute(&mut self, encoder: &mut CommandEncoder, context: &PassContext);
+}
+
+// Every texture of the frame, the passes only get the ones they declared
+pub(crate) struct FrameResources<'a> {
+    pub device: &'a Device,
+    pub queue: &'a Queue,
+    pub size: (u32, u32),
+    pub scene_size: (u32, u32),
+    pub scene_color: &'a TextureView,
+    pub scene_depth: &'a TextureView,
+    pub scene_depth_sampled: &'a TextureView,
+    pub surface: &'a TextureView,
+    pub camera_bind_group: Option<&'a BindGroup>,
+}
+
+/// What a custom pass can access while it is executed
+pub struct PassContext<'a> {
+    resources: &'a FrameResources<'a>,
+    inputs: &'a [RenderResource],
+    outputs: &'a [RenderResource],
+}
+
+impl PassContext<'_> {
+    pub fn get_device(&self) -> &Device {
+        self.resources.device
+    }
+
+    pub fn get_queue(&self) -> &Queue {
+        self.resources.queue
+    }
+
+    /// The width and height of the frame in pixels
+    pub fn get_size(&self) -> (u32, u32) {
+        self.resources.size
+    }
+
+    /// The width and height of the scene textures in pixels, they are smaller or larger
+    /// than the frame when the renderer has a render scale
+    pub fn get_scene_size(&self) -> (u32, u32) {
+        self.resources.scene_size
+    }
+
+    /// The bind group of the active camera, `None` when there is no camera
+    pub fn get_camera_bind_group(&self) -> Option<&BindGroup> {
+        self.resources.camera_bind_group
+    }
+
+    /// Gets a view of a resource to sample from
+    ///
+    /// # Returns
+    ///
+    /// The view or `None` if the resource was not declared as an input, the depth is a
+    /// depth only view
+    pub fn get_input(&self, resource: RenderResource) -> Option<&TextureView> {
+        if !self.inputs.contains(&resource) {
+            return None;
+        }
+
+        match resource {
+            RenderResource::SceneColor => Some(self.resources.scene_color),
+            RenderResource::SceneDepth => Some(self.resources.scene_depth_sampled),
+            RenderResource::Surface => None,
+        }
+    }
+
+    /// Gets a view of a resource to draw to
+    ///
+    /// # Returns
+    ///
+    /// The view or `None` if the resource was not declared as an output
+    pub fn get_output(&self, resource: RenderResource) -> Option<&TextureView> {
+        if !self.outputs.contains(&resource) {
+            return None;
+        }
+
+        match resource {
+            RenderResource::SceneColor => Some(self.resources.scene_color),
+            RenderResource::SceneDepth => Some(self.resources.scene_depth),
+            RenderResource::Surface => Some(self.resources.surface),
+        }
+    }
+}
+
+struct GraphPass {
+    stage: RenderStage,
+    inputs: Vec<RenderResource>,
+    outputs: Vec<RenderResource>,
+    pass: Box<dyn CustomRenderPass>,
+}
+
+/// The custom passes that are inserted into the frame
+#[derive(Default)]
+pub struct RenderGraph {
+    passes: Vec<Option<GraphPass>>,
+}
+
+impl RenderGraph {
+    // Checks that the resources of a pass can be used at its stage
+    fn validate(
+        stage: RenderStage,
+        inputs: &[RenderResource],
+        outputs: &[RenderResource],
+    ) -> Result<(), io::Error> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+
+        if outputs.is_empty() {
+            return Err(invalid(String::from("A pass needs at least one output")));
+        }
+
+        if inputs.contains(&RenderResource::Surface) {
+            return Err(invalid(String::from("The surface can not be sampled")));
+        }
+
+        if let Some(resource) = inputs.iter().find(|input| outputs.contains(input)) {
+            return Err(invalid(format!(
+                "{resource:?} can not be both an input and an output of a pass"
+            )));
+        }
+
+        for output in outputs {
+            let writable = match output {
+                // The scene has already been drawn onto the screen after the post stack
+                RenderResource::SceneColor | RenderResource::SceneDepth => {
+                    stage == RenderStage::AfterScene
+                }
+                // The post stack clears the screen when it runs
+                RenderResource::Surface => stage != RenderStage::AfterScene,
+            };
+
+            if !writable {
+                return Err(invalid(format!(
+                    "{output:?} can not be written to at {stage:?}"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds a pass to the frame
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The point in the frame where the pass runs
+    /// * `pass` - The pass, its inputs and outputs are read once when it is added
+    ///
+    /// # Returns
+    ///
+    /// A `RenderPassHandle` to remove the pass with or an error if the resources of the
+    /// pass can not be used at the stage
+    pub fn add_pass(
+        &mut self,
+        stage: RenderStage,
+        pass: Box<dyn CustomRenderPass>,
+    ) -> Result<RenderPassHandle, io::Error> {
+        let inputs = pass.inputs();
+        let outputs = pass.outputs();
+        Self::validate(stage, &inputs, &outputs)?;
+
+        let graph_pass = GraphPass {
+            stage,
+            inputs,
+            outputs,
+            pass,
+        };
+
+        let index = match self.passes.iter().position(|pass| pass.is_none()) {
+            Some(index) => {
+                self.passes[index] = Some(graph_pass);
+                index
+            }
+            None => {
+                self.passes.push(Some(graph_pass));
+                self.passes.len() - 1
+            }
+        };
+
+        Ok(RenderPassHandle(index))
+    }
+
+    /// Removes a pass from the frame
+    ///
+    /// # Returns
+    ///
+    /// The pass or `None` if the handle was already removed
+    pub fn remove_pass(&mut self, handle: RenderPassHandle) -> Option<Box<dyn CustomRenderPass>> {
+        self.passes
+            .get_mut(handle.0)
+            .and_then(|pass| pass.take())
+            .map(|pass| pass.pass)
+    }
+
+    // Call this when resizing the window
+    pub fn resize(&mut self, device: &Device, size: (u32, u32)) {
+        for graph_pass in self.passes.iter_mut().flatten() {
+            graph_pass.pass.resize(device, size);
+        }
+    }
+
+    // Runs the passes of a stage
+    pub(crate) fn execute(
+        &mut self,
+        stage: RenderStage,
+        encoder: &mut CommandEncoder,
+        resources: &FrameResources,
+    ) {
+        for graph_pass in self.passes.iter_mut().flatten() {
+            if graph_pass.stage != stage {
+                continue;
+            }
+
+            let context = PassContext {
+                resources,
+                inputs: &graph_pass.inputs,
+                outputs: &graph_pass.outputs,
+            };
+
+            graph_pass.pass.execute(encoder, &context);
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use std::time::{Duration, Instant};
+
+use wgpu::{
+    include_wgsl, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, CompareFunction,
+    DepthBiasState, DepthStencilState, Device, FragmentState, MultisampleState,
+    PipelineCompilationOptions, PipelineLayoutDescriptor, PrimitiveState, RenderPass,
+    RenderPipeline, RenderPipelineDescriptor, ShaderStages, StencilState, TextureSampleType,
+    TextureView, TextureViewDimension, VertexState,
+};
+
+use crate::helium_texture::DEPTH_FORMAT;
+
+/// The smallest and largest render scale
+pub const RENDER_SCALE_RANGE: (f32, f32) = (0.25, 2.0);
+
+/// Settings of the automatic render scale that lowers the resolution of the scene when
+/// frames take longer than the target and raises it again when they are faster
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DynamicResolution {
+    target_frame_time: Duration,
+    min_scale: f32,
+    max_scale: f32,
+    step: f32,
+    hysteresis: f32,
+    frames: u32,
+}
+
+impl Default for DynamicResolution {
+    /// Aims for 60 frames a second between half and full resolution
+    fn default() -> Self {
+        Self {
+            target_frame_time: Duration::from_secs_f32(1.0 / 60.0),
+            min_scale: 0.5,
+            max_scale: 1.0,
+            step: 0.05,
+            hysteresis: 0.1,
+            frames: 30,
+        }
+    }
+}
+
+impl DynamicResolution {
+    /// Sets the frame time to aim for, with vsync it should not be shorter than the
+    /// refresh interval of the display or the scale only ever goes down
+    pub fn with_target_frame_time(mut self, target_frame_time: Duration) -> Self {
+        self.target_frame_time = target_frame_time;
+        self
+    }
+
+    /// Sets the smallest and largest scale the resolution is changed between
+    pub fn with_scale_range(mut self, min_scale: f32, max_scale: f32) -> Self {
+        self.min_scale = min_scale.clamp(RENDER_SCALE_RANGE.0, RENDER_SCALE_RANGE.1);
+        self.max_scale = max_scale.clamp(self.min_scale, RENDER_SCALE_RANGE.1);
+        self
+    }
+
+    /// Sets how much the scale changes at a time
+    pub fn with_step(mut self, step: f32) -> Self {
+        self.step = step.max(0.01);
+        self
+    }
+
+    /// Sets how far from the target the frame time has to be before the scale changes, as
+    /// a fraction of the target. Frame times inside the band keep the scale so it does not
+    /// flip between two scales every few frames
+    pub fn with_hysteresis(mut self, hysteresis: f32) -> Self {
+        self.hysteresis = hysteresis.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets how many frames are averaged before the scale is changed
+    pub fn with_frames(mut self, frames: u32) -> Self {
+        self.frames = frames.max(1);
+        self
+    }
+
+    pub fn get_target_frame_time(&self) -> Duration {
+        self.target_frame_time
+    }
+
+    pub fn get_scale_range(&self) -> (f32, f32) {
+        (self.min_scale, self.max_scale)
+    }
+
+    pub fn get_step(&self) -> f32 {
+        self.step
+    }
+
+    pub fn get_hysteresis(&self) -> f32 {
+        self.hysteresis
+    }
+
+    pub fn get_frames(&self) -> u32 {
+        self.frames
+    }
+}
+
+/// Measures the frame times and picks the render scale of the dynamic resolution
+#[derive(Debug)]
+pub(crate) struct ResolutionController {
+    settings: DynamicResolution,
+    last_frame: Option<Instant>,
+    elapsed: Duration,
+    frames: u32,
+}
+
+impl ResolutionController {
+    pub fn new(settings: DynamicResolution) -> Self {
+        Self {
+            settings,
+            last_frame: None,
+            elapsed: Duration::ZERO,
+            frames: 0,
+        }
+    }
+
+    pub fn get_settings(&self) -> DynamicResolution {
+        self.settings
+    }
+
+    /// Counts a frame that is about to be drawn
+    ///
+    /// # Arguments
+    ///
+    /// * `scale` - The render scale of the last frames
+    ///
+    /// # Returns
+    ///
+    /// The new render scale when it should change
+    pub fn frame(&mut self, scale: f32) -> Option<f32> {
+        let now = Instant::now();
+        let last_frame = self.last_frame.replace(now)?;
+        self.elapsed += now - last_frame;
+        self.frames += 1;
+
+        if self.frames < self.settings.frames {
+            return None;
+        }
+
+        let average = self.elapsed.as_secs_f32() / self.frames as f32;
+        self.elapsed = Duration::ZERO;
+        self.frames = 0;
+
+        let target = self.settings.target_frame_time.as_secs_f32();
+        let new_scale = if average > target * (1.0 + self.settings.hysteresis) {
+            scale - self.settings.step
+        } else if average < target * (1.0 - self.settings.hysteresis) {
+            scale + self.settings.step
+        } else {
+            scale
+        }
+        .clamp(self.settings.min_scale, self.settings.max_scale);
+
+        (new_scale != scale).then_some(new_scale)
+    }
+}
+
+/// Copies the depth of the scene into a depth texture the size of the screen when the
+/// scene is drawn at a different resolution
+pub(crate) struct DepthUpscale {
+    pipeline: RenderPipeline,
+    layout: BindGroupLayout,
+    bind_group: BindGroup,
+}
+
+impl DepthUpscale {
+    pub fn new(device: &Device, scene_depth: &TextureView) -> Self {
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Depth Upscale Bind Group Layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: TextureViewDimension::D2,
+                    sample_type: TextureSampleType::Depth,
+                },
+                count: None,
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Depth Upscale Render Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("./shaders/depth_upscale.wgsl"));
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Depth Upscale Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: Some(DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Always,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let bind_group = Self::create_bind_group(device, &layout, scene_depth);
+
+        Self {
+            pipeline,
+            layout,
+            bind_group,
+        }
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        scene_depth: &TextureView,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Depth Upscale bind group"),
+            layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(scene_depth),
+            }],
+        })
+    }
+
+    // Call this when the scene depth texture is created again
+    pub fn set_scene_depth(&mut self, device: &Device, scene_depth: &TextureView) {
+        self.bind_group = Self::create_bind_group(device, &self.layout, scene_depth);
+    }
+
+    pub fn draw(&self, render_pass: &mut RenderPass) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
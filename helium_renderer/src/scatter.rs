@@ -0,0 +1,195 @@
+use cgmath::{Deg, Quaternion, Rotation3, Vector3};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType, BufferUsages, Device, Queue,
+    ShaderStages,
+};
+
+use crate::instance::Instance;
+
+/// The area of the world that instances are scattered in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScatterRegion {
+    /// Corner of the region with the smallest coordinates
+    pub min: Vector3<f32>,
+    /// Corner of the region with the largest coordinates
+    pub max: Vector3<f32>,
+}
+
+impl ScatterRegion {
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    /// Area of the region on the ground (xz) plane
+    pub fn area(&self) -> f32 {
+        ((self.max.x - self.min.x) * (self.max.z - self.min.z)).abs()
+    }
+}
+
+/// How the scattered instances are culled on the gpu
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScatterSettings {
+    /// Radius of a sphere around the origin of the model that contains the whole model,
+    /// used to cull instances outside of the view of the camera
+    pub bounding_radius: f32,
+    /// Distance from the camera that instances start thinning out
+    pub falloff_start: f32,
+    /// Distance from the camera that all instances are hidden,
+    /// the density does not fall off if this is not past the start
+    pub falloff_end: f32,
+}
+
+impl Default for ScatterSettings {
+    fn default() -> Self {
+        Self {
+            bounding_radius: 1.0,
+            falloff_start: 30.0,
+            falloff_end: 80.0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ScatterUniform {
+    bounding_radius: f32,
+    falloff_start: f32,
+    falloff_end: f32,
+    _padding: f32,
+}
+
+impl From<ScatterSettings> for ScatterUniform {
+    fn from(settings: ScatterSettings) -> Self {
+        Self {
+            bounding_radius: settings.bounding_radius,
+            falloff_start: settings.falloff_start,
+            falloff_end: settings.falloff_end,
+            _padding: 0.0,
+        }
+    }
+}
+
+// Small deterministic generator so the same seed always scatters the same instances
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Random value in [0, 1)
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+/// Creates randomly placed instances with a random rotation around the y axis
+///
+/// # Arguments
+///
+/// * `region` - The region to place the instances in
+/// * `density` - The number of instances per square unit of the region
+/// * `seed` - The seed of the random placement, the same seed gives the same instances
+///
+/// # Returns
+///
+/// The instances, with a random value used to thin them out with distance in `custom_data[3]`
+pub fn generate_scatter_instances(region: ScatterRegion, density: f32, seed: u64) -> Vec<Instance> {
+    let count = (region.area() * density.max(0.0)).round() as usize;
+    let mut random = SplitMix64(seed);
+
+    (0..count)
+        .map(|_| {
+            let position = Vector3::new(
+                random.range(region.min.x, region.max.x),
+                random.range(region.min.y, region.max.y),
+                random.range(region.min.z, region.max.z),
+            );
+            let rotation = Quaternion::from_angle_y(Deg(random.range(0.0, 360.0)));
+
+            Instance::new(position, rotation).with_custom_data([0.0, 0.0, 0.0, random.next_f32()])
+        })
+        .collect()
+}
+
+/// An object whose instances are culled and thinned out on the gpu
+pub struct Scatter {
+    object_index: usize,
+    settings: ScatterSettings,
+    buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+impl Scatter {
+    pub fn get_layout(device: &Device) -> BindGroupLayout {
+        device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Scatter Bind Group Layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    }
+
+    pub fn new(device: &Device, object_index: usize, settings: ScatterSettings) -> Self {
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Scatter buffer"),
+            contents: bytemuck::cast_slice(&[ScatterUniform::from(settings)]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Scatter bind group"),
+            layout: &Self::get_layout(device),
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            object_index,
+            settings,
+            buffer,
+            bind_group,
+        }
+    }
+
+    pub fn get_object_index(&self) -> usize {
+        self.object_index
+    }
+
+    pub fn get_settings(&self) -> &ScatterSettings {
+        &self.settings
+    }
+
+    pub fn get_bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+
+    /// Writes new culling settings to the gpu
+    pub fn set_settings(&mut self, settings: ScatterSettings, queue: &Queue) {
+        self.settings = settings;
+        queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::cast_slice(&[ScatterUniform::from(settings)]),
+        );
+    }
+}
@@ -0,0 +1,685 @@
+use std::{collections::HashMap, mem};
+
+use cgmath::{InnerSpace, Quaternion, Rotation, Vector3};
+use log::warn;
+use wgpu::{
+    include_wgsl, AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindingResource,
+    BlendState, Buffer, BufferAddress, BufferDescriptor, BufferUsages, ColorTargetState,
+    ColorWrites, DepthStencilState, Device, Extent3d, FilterMode, FragmentState, MultisampleState,
+    Origin3d, PipelineCompilationOptions, PipelineLayoutDescriptor, PrimitiveState,
+    PrimitiveTopology, Queue, RenderPass, RenderPipeline, RenderPipelineDescriptor,
+    SamplerDescriptor, TexelCopyBufferLayout, TexelCopyTextureInfo, Texture, TextureAspect,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureViewDescriptor,
+    VertexAttribute, VertexBufferLayout, VertexFormat, VertexState, VertexStepMode,
+};
+use wgpu_text::glyph_brush::ab_glyph::{point, Font, FontArc, GlyphId, ScaleFont};
+
+use crate::{
+    camera::Camera,
+    color::LinearRgba,
+    layouts::{LayoutKind, LayoutRegistry},
+    text::FontHandle,
+};
+
+// Size in pixels the glyphs are drawn into the atlas at, larger text is scaled up from it
+const SDF_GLYPH_SIZE: f32 = 48.0;
+// How many pixels the distance field reaches past the edge of a glyph, outlines can be at
+// most this wide at the glyph size
+const SDF_SPREAD: usize = 6;
+// Width and height of the atlas in pixels
+const ATLAS_SIZE: usize = 1024;
+// Vertices of the two triangles of a glyph
+const GLYPH_VERTICES: usize = 6;
+
+/// Where sdf text is drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SdfPlacement {
+    /// At a point of the world, always facing the camera
+    Billboard { position: Vector3<f32> },
+    /// At a point of the world, the text is written along x and up along y of the rotation
+    World {
+        position: Vector3<f32>,
+        rotation: Quaternion<f32>,
+    },
+    /// On the screen at a position in pixels from the top left
+    Screen { position: (f32, f32) },
+}
+
+/// An outline drawn around sdf text
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SdfOutline {
+    pub color: [f32; 4],
+    /// Width of the outline as a fraction of the size of the text, at most about 0.12
+    pub width: f32,
+}
+
+/// A blurred copy of sdf text drawn behind it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SdfShadow {
+    pub color: [f32; 4],
+    /// Offset of the shadow as a fraction of the size of the text, y is down
+    pub offset: (f32, f32),
+    /// How blurred the edge of the shadow is from 0 to 1
+    pub softness: f32,
+}
+
+/// Text drawn from a signed distance field of its glyphs so it stays sharp when it is
+/// scaled up, used for labels in the world and large titles
+#[derive(Clone, Debug, PartialEq)]
+pub struct SdfText {
+    pub text: String,
+    pub placement: SdfPlacement,
+    /// Size of the text in world units, or in pixels on the screen
+    pub size: f32,
+    pub font: FontHandle,
+    pub color: [f32; 4],
+    pub outline: Option<SdfOutline>,
+    pub shadow: Option<SdfShadow>,
+    /// The point of the text that is placed at the position as a fraction of its width and
+    /// height, `(0.5, 0.5)` centers the text
+    pub anchor: (f32, f32),
+}
+
+impl SdfText {
+    fn new(text: String, placement: SdfPlacement, size: f32, anchor: (f32, f32)) -> Self {
+        Self {
+            text,
+            placement,
+            size,
+            font: FontHandle::default(),
+            color: [1.0; 4],
+            outline: None,
+            shadow: None,
+            anchor,
+        }
+    }
+
+    /// Creates text centered on a point in the world that always faces the camera
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to draw
+    /// * `position` - The point in the world
+    /// * `size` - The height of a line of the text in world units
+    pub fn billboard(text: String, position: Vector3<f32>, size: f32) -> Self {
+        Self::new(text, SdfPlacement::Billboard { position }, size, (0.5, 0.5))
+    }
+
+    /// Creates text centered on a point in the world with a fixed rotation, like a sign
+    pub fn world(
+        text: String,
+        position: Vector3<f32>,
+        rotation: Quaternion<f32>,
+        size: f32,
+    ) -> Self {
+        Self::new(
+            text,
+            SdfPlacement::World { position, rotation },
+            size,
+            (0.5, 0.5),
+        )
+    }
+
+    /// Creates text on the screen with its top left at a position in pixels
+    pub fn screen(text: String, position: (f32, f32), size: f32) -> Self {
+        Self::new(text, SdfPlacement::Screen { position }, size, (0.0, 0.0))
+    }
+
+    pub fn with_font(mut self, font: FontHandle) -> Self {
+        self.font = font;
+        self
+    }
+
+    pub fn with_color<C>(mut self, color: C) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        self.color = color.into().to_array();
+        self
+    }
+
+    pub fn with_outline<C>(mut self, color: C, width: f32) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        self.outline = Some(SdfOutline {
+            color: color.into().to_array(),
+            width,
+        });
+        self
+    }
+
+    pub fn with_shadow<C>(mut self, color: C, offset: (f32, f32), softness: f32) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        self.shadow = Some(SdfShadow {
+            color: color.into().to_array(),
+            offset,
+            softness,
+        });
+        self
+    }
+
+    pub fn with_anchor(mut self, anchor: (f32, f32)) -> Self {
+        self.anchor = anchor;
+        self
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct SdfVertex {
+    position: [f32; 4],
+    tex_coords: [f32; 2],
+    color: [f32; 4],
+    outline_color: [f32; 4],
+    params: [f32; 4],
+}
+
+impl SdfVertex {
+    fn desc() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: mem::size_of::<SdfVertex>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &[
+                // Position
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: VertexFormat::Float32x4,
+                },
+                // UV coordinates
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 1,
+                    format: VertexFormat::Float32x2,
+                },
+                // Color
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 6]>() as BufferAddress,
+                    shader_location: 2,
+                    format: VertexFormat::Float32x4,
+                },
+                // Outline color
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 10]>() as BufferAddress,
+                    shader_location: 3,
+                    format: VertexFormat::Float32x4,
+                },
+                // Outline edge and softness
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 14]>() as BufferAddress,
+                    shader_location: 4,
+                    format: VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+// A glyph that has been drawn into the atlas
+#[derive(Clone, Copy, Debug)]
+struct AtlasGlyph {
+    // Top left and bottom right in texture coordinates
+    uv_min: [f32; 2],
+    uv_max: [f32; 2],
+    // Offset of the top left from the pen on the baseline and the size, in pixels at the
+    // glyph size
+    offset: (f32, f32),
+    size: (f32, f32),
+}
+
+// The distance field of a glyph before it is copied into the atlas
+struct DistanceField {
+    pixels: Vec<u8>,
+    width: usize,
+    height: usize,
+    // Offset of the top left from the pen on the baseline
+    offset: (f32, f32),
+}
+
+// Draws the signed distance field of a glyph, 0.5 is the edge and the values reach 0 and 1
+// at the spread outside and inside of it
+fn glyph_distance_field(font: &FontArc, glyph: GlyphId) -> Option<DistanceField> {
+    let outline =
+        font.outline_glyph(glyph.with_scale_and_position(SDF_GLYPH_SIZE, point(0.0, 0.0)))?;
+    let bounds = outline.px_bounds();
+    let width = bounds.width().ceil() as usize + SDF_SPREAD * 2;
+    let height = bounds.height().ceil() as usize + SDF_SPREAD * 2;
+
+    let mut inside = vec![false; width * height];
+    outline.draw(|x, y, coverage| {
+        let (x, y) = (x as usize + SDF_SPREAD, y as usize + SDF_SPREAD);
+        if x < width && y < height {
+            inside[y * width + x] = coverage >= 0.5;
+        }
+    });
+
+    // The distance to the nearest pixel on the other side of the edge, searched within the
+    // spread since that is as far as the field reaches
+    let spread = SDF_SPREAD as isize;
+    let mut field = vec![0; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let state = inside[y * width + x];
+            let mut nearest = SDF_SPREAD as f32;
+            for dy in -spread..=spread {
+                for dx in -spread..=spread {
+                    let (sx, sy) = (x as isize + dx, y as isize + dy);
+                    let other = if sx < 0 || sy < 0 || sx >= width as isize || sy >= height as isize
+                    {
+                        false
+                    } else {
+                        inside[sy as usize * width + sx as usize]
+                    };
+                    if other != state {
+                        nearest = nearest.min(((dx * dx + dy * dy) as f32).sqrt() - 0.5);
+                    }
+                }
+            }
+
+            let signed = if state { nearest } else { -nearest };
+            let value = 0.5 + signed / (SDF_SPREAD as f32 * 2.0);
+            field[y * width + x] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+    }
+
+    let offset = (
+        bounds.min.x - SDF_SPREAD as f32,
+        bounds.min.y - SDF_SPREAD as f32,
+    );
+    Some(DistanceField {
+        pixels: field,
+        width,
+        height,
+        offset,
+    })
+}
+
+/// Draws sdf text in the world and on the screen, glyphs are added to the atlas the first
+/// time they are drawn
+pub(crate) struct SdfTextRenderer {
+    pipeline: RenderPipeline,
+    atlas_texture: Texture,
+    atlas_bind_group: BindGroup,
+    // The distance field of every glyph in one channel
+    atlas: Vec<u8>,
+    // Whether glyphs were added since the atlas was written to the gpu
+    atlas_dirty: bool,
+    // Where the next glyph is placed, glyphs are packed in rows
+    cursor: (usize, usize),
+    row_height: usize,
+    // Glyphs without an outline like spaces are kept as `None`
+    glyphs: HashMap<(FontHandle, GlyphId), Option<AtlasGlyph>>,
+    vertex_buffer: Buffer,
+    vertex_capacity: usize,
+    vertex_count: u32,
+}
+
+impl SdfTextRenderer {
+    pub fn new(
+        device: &Device,
+        layouts: &LayoutRegistry,
+        format: TextureFormat,
+        depth_stencil: DepthStencilState,
+    ) -> Self {
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("SDF Text Render Pipeline Layout"),
+            bind_group_layouts: &[
+                layouts.get(LayoutKind::Camera),
+                layouts.get(LayoutKind::Texture),
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("./shaders/sdf_text_shader.wgsl"));
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("SDF Text Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[SdfVertex::desc()],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            // Text in the world can be read from behind
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(depth_stencil),
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let atlas_texture = device.create_texture(&TextureDescriptor {
+            label: Some("SDF Text Atlas"),
+            size: Extent3d {
+                width: ATLAS_SIZE as u32,
+                height: ATLAS_SIZE as u32,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::R8Unorm,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        // The distance is blended between the pixels, that is what keeps the edges smooth
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("SDF Text Atlas Sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let atlas_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("SDF Text Atlas Bind Group"),
+            layout: layouts.get(LayoutKind::Texture),
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(
+                        &atlas_texture.create_view(&TextureViewDescriptor::default()),
+                    ),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        Self {
+            pipeline,
+            atlas_texture,
+            atlas_bind_group,
+            atlas: vec![0; ATLAS_SIZE * ATLAS_SIZE],
+            atlas_dirty: false,
+            cursor: (0, 0),
+            row_height: 0,
+            glyphs: HashMap::new(),
+            vertex_buffer: Self::create_vertex_buffer(device, GLYPH_VERTICES * 64),
+            vertex_capacity: GLYPH_VERTICES * 64,
+            vertex_count: 0,
+        }
+    }
+
+    fn create_vertex_buffer(device: &Device, capacity: usize) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("SDF Text Vertex Buffer"),
+            size: (capacity * mem::size_of::<SdfVertex>()) as BufferAddress,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    // Finds a glyph in the atlas and draws it there the first time it is used
+    fn glyph(&mut self, fonts: &[FontArc], font: FontHandle, id: GlyphId) -> Option<AtlasGlyph> {
+        if let Some(glyph) = self.glyphs.get(&(font, id)) {
+            return *glyph;
+        }
+
+        let field = fonts
+            .get(font.0)
+            .and_then(|face| glyph_distance_field(face, id));
+        let glyph = field.and_then(|field| {
+            let (width, height) = (field.width, field.height);
+            // Starts a new row when the glyph does not fit at the end of this one
+            if self.cursor.0 + width > ATLAS_SIZE {
+                self.cursor = (0, self.cursor.1 + self.row_height);
+                self.row_height = 0;
+            }
+            if self.cursor.1 + height > ATLAS_SIZE {
+                warn!("The sdf text atlas is full, new glyphs are not drawn");
+                return None;
+            }
+
+            let (x, y) = self.cursor;
+            for row in 0..height {
+                let start = (y + row) * ATLAS_SIZE + x;
+                self.atlas[start..start + width]
+                    .copy_from_slice(&field.pixels[row * width..(row + 1) * width]);
+            }
+            self.cursor.0 += width;
+            self.row_height = self.row_height.max(height);
+            self.atlas_dirty = true;
+
+            let size = ATLAS_SIZE as f32;
+            Some(AtlasGlyph {
+                uv_min: [x as f32 / size, y as f32 / size],
+                uv_max: [(x + width) as f32 / size, (y + height) as f32 / size],
+                offset: field.offset,
+                size: (width as f32, height as f32),
+            })
+        });
+
+        self.glyphs.insert((font, id), glyph);
+        glyph
+    }
+
+    // Lays out the glyphs of the text in pixels at the glyph size with y down, the anchor
+    // of the text is at the origin
+    fn layout(&mut self, text: &SdfText, fonts: &[FontArc]) -> Vec<(AtlasGlyph, (f32, f32))> {
+        let Some(face) = fonts.get(text.font.0) else {
+            return Vec::new();
+        };
+        let scaled = face.as_scaled(SDF_GLYPH_SIZE);
+        let line_height = scaled.height() + scaled.line_gap();
+
+        let mut placed = Vec::new();
+        let mut width: f32 = 0.0;
+        let mut lines = 0;
+        for (line_index, line) in text.text.lines().enumerate() {
+            let baseline = line_index as f32 * line_height + scaled.ascent();
+            let mut pen = 0.0;
+            let mut previous: Option<GlyphId> = None;
+
+            for character in line.chars() {
+                let id = face.glyph_id(character);
+                if let Some(previous) = previous {
+                    pen += scaled.kern(previous, id);
+                }
+                if let Some(glyph) = self.glyph(fonts, text.font, id) {
+                    placed.push((glyph, (pen + glyph.offset.0, baseline + glyph.offset.1)));
+                }
+                pen += scaled.h_advance(id);
+                previous = Some(id);
+            }
+
+            width = width.max(pen);
+            lines = line_index + 1;
+        }
+
+        let height = lines as f32 * line_height;
+        let anchor = (text.anchor.0 * width, text.anchor.1 * height);
+        for (_, position) in placed.iter_mut() {
+            position.0 -= anchor.0;
+            position.1 -= anchor.1;
+        }
+
+        placed
+    }
+
+    /// Lays out the texts and writes their vertices, glyphs that are new are drawn into the
+    /// atlas
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` - The texts to draw
+    /// * `fonts` - The fonts of the renderer
+    /// * `camera` - The camera the texts in the world face, `None` only draws the screen texts
+    /// * `screen_size` - The width and height of the screen in pixels
+    pub fn prepare<'a, I>(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        texts: I,
+        fonts: &[FontArc],
+        camera: Option<&Camera>,
+        screen_size: (f32, f32),
+    ) where
+        I: IntoIterator<Item = &'a SdfText>,
+    {
+        let mut vertices = Vec::new();
+
+        for text in texts {
+            // The origin of the text and the directions of its x and y axes, screen text is
+            // placed in normalized device coordinates
+            let (origin, right, up, w) = match text.placement {
+                SdfPlacement::Screen { position } => (
+                    Vector3::new(
+                        position.0 / screen_size.0 * 2.0 - 1.0,
+                        1.0 - position.1 / screen_size.1 * 2.0,
+                        0.0,
+                    ),
+                    Vector3::new(2.0 / screen_size.0, 0.0, 0.0),
+                    Vector3::new(0.0, 2.0 / screen_size.1, 0.0),
+                    0.0,
+                ),
+                SdfPlacement::Billboard { position } => {
+                    let Some(camera) = camera else {
+                        continue;
+                    };
+                    let right = camera.target.cross(camera.up).normalize();
+                    (position, right, right.cross(camera.target).normalize(), 1.0)
+                }
+                SdfPlacement::World { position, rotation } => {
+                    if camera.is_none() {
+                        continue;
+                    }
+                    (
+                        position,
+                        rotation.rotate_vector(Vector3::unit_x()),
+                        rotation.rotate_vector(Vector3::unit_y()),
+                        1.0,
+                    )
+                }
+            };
+            let scale = text.size / SDF_GLYPH_SIZE;
+            let project = |(x, y): (f32, f32)| -> [f32; 4] {
+                (origin + right * (x * scale) - up * (y * scale))
+                    .extend(w)
+                    .into()
+            };
+
+            let glyphs = self.layout(text, fonts);
+
+            // The distance field covers the spread on both sides of the edge
+            let distance_per_em = SDF_GLYPH_SIZE / (SDF_SPREAD as f32 * 2.0);
+            let mut quads = Vec::new();
+            if let Some(shadow) = text.shadow {
+                let offset = (
+                    shadow.offset.0 * SDF_GLYPH_SIZE,
+                    shadow.offset.1 * SDF_GLYPH_SIZE,
+                );
+                let params = [0.5, shadow.softness.clamp(0.0, 1.0) * 0.5, 0.0, 0.0];
+                quads.push((offset, shadow.color, shadow.color, params));
+            }
+            let (outline_color, outline_edge) = match text.outline {
+                Some(outline) => (
+                    outline.color,
+                    (0.5 - outline.width * distance_per_em).max(0.0),
+                ),
+                None => (text.color, 0.5),
+            };
+            quads.push((
+                (0.0, 0.0),
+                text.color,
+                outline_color,
+                [outline_edge, 0.0, 0.0, 0.0],
+            ));
+
+            for (offset, color, outline_color, params) in quads {
+                for (glyph, (x, y)) in glyphs.iter() {
+                    let (x, y) = (x + offset.0, y + offset.1);
+                    let corner = |cx: f32, cy: f32, u: f32, v: f32| SdfVertex {
+                        position: project((x + cx * glyph.size.0, y + cy * glyph.size.1)),
+                        tex_coords: [u, v],
+                        color,
+                        outline_color,
+                        params,
+                    };
+
+                    let [u0, v0] = glyph.uv_min;
+                    let [u1, v1] = glyph.uv_max;
+                    let top_left = corner(0.0, 0.0, u0, v0);
+                    let top_right = corner(1.0, 0.0, u1, v0);
+                    let bottom_left = corner(0.0, 1.0, u0, v1);
+                    let bottom_right = corner(1.0, 1.0, u1, v1);
+                    vertices.extend([
+                        top_left,
+                        bottom_left,
+                        top_right,
+                        top_right,
+                        bottom_left,
+                        bottom_right,
+                    ]);
+                }
+            }
+        }
+
+        if self.atlas_dirty {
+            queue.write_texture(
+                TexelCopyTextureInfo {
+                    texture: &self.atlas_texture,
+                    mip_level: 0,
+                    origin: Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                &self.atlas,
+                TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(ATLAS_SIZE as u32),
+                    rows_per_image: Some(ATLAS_SIZE as u32),
+                },
+                Extent3d {
+                    width: ATLAS_SIZE as u32,
+                    height: ATLAS_SIZE as u32,
+                    depth_or_array_layers: 1,
+                },
+            );
+            self.atlas_dirty = false;
+        }
+
+        if vertices.len() > self.vertex_capacity {
+            self.vertex_capacity = vertices.len().next_power_of_two();
+            self.vertex_buffer = Self::create_vertex_buffer(device, self.vertex_capacity);
+        }
+        if !vertices.is_empty() {
+            queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+        }
+        self.vertex_count = vertices.len() as u32;
+    }
+
+    pub fn draw(&self, render_pass: &mut RenderPass, camera_bind_group: &BindGroup) {
+        if self.vertex_count == 0 {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.atlas_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..self.vertex_count, 0..1);
+    }
+}
@@ -0,0 +1,102 @@
+// Spreads the bright parts of the scene into their surroundings, the scene is downsampled
+// into a chain of smaller textures that are blurred back up on top of each other
+
+struct BloomSettings {
+    // Brightness where the bloom starts
+    threshold: f32,
+    // Width of the soft transition around the threshold
+    knee: f32,
+    // How much of the bloom is added to the scene
+    intensity: f32,
+    // Spread of the upsampling filter in texels
+    radius: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> settings: BloomSettings;
+
+@group(0) @binding(1)
+var t_source: texture_2d<f32>;
+
+@group(0) @binding(2)
+var s_source: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+// A single triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.tex_coords = uv;
+    return out;
+}
+
+fn sample_source(uv: vec2<f32>) -> vec3<f32> {
+    return textureSampleLevel(t_source, s_source, uv, 0.0).rgb;
+}
+
+// Averages a 4x4 block of the source with four bilinear samples
+fn downsample(uv: vec2<f32>) -> vec3<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_source));
+
+    var color = sample_source(uv + vec2<f32>(-texel.x, -texel.y));
+    color += sample_source(uv + vec2<f32>(texel.x, -texel.y));
+    color += sample_source(uv + vec2<f32>(-texel.x, texel.y));
+    color += sample_source(uv + vec2<f32>(texel.x, texel.y));
+    return color * 0.25;
+}
+
+// Blurs the smaller texture with a 3x3 tent filter while it is upsampled
+fn upsample(uv: vec2<f32>) -> vec3<f32> {
+    let offset = settings.radius / vec2<f32>(textureDimensions(t_source));
+
+    var color = sample_source(uv) * 4.0;
+    color += sample_source(uv + vec2<f32>(-offset.x, 0.0)) * 2.0;
+    color += sample_source(uv + vec2<f32>(offset.x, 0.0)) * 2.0;
+    color += sample_source(uv + vec2<f32>(0.0, -offset.y)) * 2.0;
+    color += sample_source(uv + vec2<f32>(0.0, offset.y)) * 2.0;
+    color += sample_source(uv + vec2<f32>(-offset.x, -offset.y));
+    color += sample_source(uv + vec2<f32>(offset.x, -offset.y));
+    color += sample_source(uv + vec2<f32>(-offset.x, offset.y));
+    color += sample_source(uv + vec2<f32>(offset.x, offset.y));
+    return color / 16.0;
+}
+
+// Keeps the parts of the scene above the threshold with a soft knee so the bloom does
+// not pop in
+@fragment
+fn fs_prefilter(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Clamped so single very bright pixels do not flicker as they move
+    let color = min(downsample(in.tex_coords), vec3<f32>(1000.0));
+    let brightness = max(color.r, max(color.g, color.b));
+
+    let knee = max(settings.knee, 1e-4);
+    var soft = clamp(brightness - settings.threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee);
+
+    let contribution = max(soft, brightness - settings.threshold) / max(brightness, 1e-4);
+    return vec4<f32>(color * contribution, 1.0);
+}
+
+@fragment
+fn fs_downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(downsample(in.tex_coords), 1.0);
+}
+
+// Added on top of the larger texture with additive blending
+@fragment
+fn fs_upsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(upsample(in.tex_coords), 1.0);
+}
+
+// Added on top of the scene with additive blending
+@fragment
+fn fs_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(upsample(in.tex_coords) * settings.intensity, 1.0);
+}
@@ -0,0 +1,87 @@
+struct DecalGlobals {
+    view_proj: mat4x4<f32>,
+    inverse_view_proj: mat4x4<f32>,
+};
+
+struct DecalUniform {
+    model: mat4x4<f32>,
+    inverse_model: mat4x4<f32>,
+    color: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> globals: DecalGlobals;
+
+// Depth of the scene that the decals are projected onto
+@group(0) @binding(1)
+var t_depth: texture_depth_2d;
+
+@group(1) @binding(0)
+var<uniform> decal: DecalUniform;
+
+@group(2) @binding(0)
+var t_decal: texture_2d<f32>;
+
+@group(2) @binding(1)
+var s_decal: sampler;
+
+// Corners of the unit box the decal is projected through
+const BOX_CORNERS = array<vec3<f32>, 8>(
+    vec3<f32>(-0.5, -0.5, -0.5),
+    vec3<f32>(0.5, -0.5, -0.5),
+    vec3<f32>(0.5, 0.5, -0.5),
+    vec3<f32>(-0.5, 0.5, -0.5),
+    vec3<f32>(-0.5, -0.5, 0.5),
+    vec3<f32>(0.5, -0.5, 0.5),
+    vec3<f32>(0.5, 0.5, 0.5),
+    vec3<f32>(-0.5, 0.5, 0.5),
+);
+
+// Two triangles for each face of the box with counter clockwise winding from outside
+const BOX_INDICES = array<u32, 36>(
+    0u, 2u, 1u, 0u, 3u, 2u,
+    4u, 5u, 6u, 4u, 6u, 7u,
+    0u, 1u, 5u, 0u, 5u, 4u,
+    3u, 6u, 2u, 3u, 7u, 6u,
+    0u, 4u, 7u, 0u, 7u, 3u,
+    1u, 2u, 6u, 1u, 6u, 5u,
+);
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let corner = BOX_CORNERS[BOX_INDICES[vertex_index]];
+
+    var out: VertexOutput;
+    out.clip_position = globals.view_proj * decal.model * vec4<f32>(corner, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(in.clip_position.xy);
+    let depth = textureLoad(t_depth, pixel, 0);
+
+    // Rebuild the position of the scene at this pixel from the depth
+    let screen_uv = in.clip_position.xy / vec2<f32>(textureDimensions(t_depth));
+    let ndc = vec4<f32>(screen_uv.x * 2.0 - 1.0, 1.0 - screen_uv.y * 2.0, depth, 1.0);
+    let world = globals.inverse_view_proj * ndc;
+    let world_position = world.xyz / world.w;
+
+    // The decal is projected down the local y axis of the box
+    let local_position = (decal.inverse_model * vec4<f32>(world_position, 1.0)).xyz;
+    let inside = all(abs(local_position) <= vec3<f32>(0.5));
+
+    // Fade out on surfaces that face away from the projection so the decal does not stretch
+    let surface_normal = normalize(cross(dpdy(world_position), dpdx(world_position)));
+    let projection_dir = normalize((decal.model * vec4<f32>(0.0, 1.0, 0.0, 0.0)).xyz);
+    let facing = smoothstep(0.2, 0.5, dot(surface_normal, projection_dir));
+
+    let uv = vec2<f32>(local_position.x + 0.5, local_position.z + 0.5);
+    let color = textureSampleLevel(t_decal, s_decal, uv, 0.0) * decal.color;
+
+    return vec4<f32>(color.rgb, color.a * facing * select(0.0, 1.0, inside));
+}
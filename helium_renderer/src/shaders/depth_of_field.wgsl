@@ -0,0 +1,104 @@
+// Blurs the scene by how far it is from the focus distance, the samples are gathered on a
+// spiral so bright spots spread into round bokeh shapes
+
+// Angle between the samples of the spiral
+const GOLDEN_ANGLE: f32 = 2.39996323;
+// How quickly the spiral grows, lower is smoother but slower
+const RADIUS_SCALE: f32 = 1.0;
+
+struct DepthOfFieldSettings {
+    znear: f32,
+    zfar: f32,
+    focus_distance: f32,
+    aperture: f32,
+    // Largest circle of confusion in pixels
+    max_blur: f32,
+    // Focuses on the depth under the center of the screen
+    autofocus: u32,
+    _padding_0: f32,
+    _padding_1: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> settings: DepthOfFieldSettings;
+
+@group(0) @binding(1)
+var t_scene: texture_2d<f32>;
+
+@group(0) @binding(2)
+var s_scene: sampler;
+
+@group(0) @binding(3)
+var t_depth: texture_depth_2d;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+// A single triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.tex_coords = uv;
+    return out;
+}
+
+// Distance from the camera of a pixel in the depth buffer
+fn linear_depth(pixel: vec2<i32>) -> f32 {
+    let depth = textureLoad(t_depth, pixel, 0);
+    return settings.znear * settings.zfar / (settings.zfar - depth * (settings.zfar - settings.znear));
+}
+
+// Radius of the blur in pixels, it grows with the distance from the focus
+fn circle_of_confusion(distance: f32, focus_distance: f32) -> f32 {
+    let coc = settings.aperture * abs(distance - focus_distance) / max(distance, 1e-4);
+    return clamp(coc * settings.max_blur, 0.0, settings.max_blur);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let dimensions = vec2<i32>(textureDimensions(t_scene));
+    let texel = 1.0 / vec2<f32>(dimensions);
+    let pixel = vec2<i32>(in.clip_position.xy);
+
+    var focus_distance = settings.focus_distance;
+    if (settings.autofocus != 0u) {
+        focus_distance = linear_depth(dimensions / 2);
+    }
+
+    let center_distance = linear_depth(pixel);
+    let center_size = circle_of_confusion(center_distance, focus_distance);
+    let center = textureLoad(t_scene, pixel, 0);
+
+    var color = center.rgb;
+    var total = 1.0;
+    var radius = RADIUS_SCALE;
+    var angle = 0.0;
+    while (radius < settings.max_blur) {
+        let offset = vec2<f32>(cos(angle), sin(angle)) * radius;
+        let sample_pixel = clamp(pixel + vec2<i32>(offset), vec2<i32>(0), dimensions - 1);
+
+        let sample_color = textureSampleLevel(t_scene, s_scene, in.tex_coords + offset * texel, 0.0).rgb;
+        let sample_distance = linear_depth(sample_pixel);
+        var sample_size = circle_of_confusion(sample_distance, focus_distance);
+
+        // Blurry backgrounds do not bleed over sharper things in front of them
+        if (sample_distance > center_distance) {
+            sample_size = clamp(sample_size, 0.0, center_size * 2.0);
+        }
+
+        // Samples only count when their blur reaches the pixel
+        let weight = smoothstep(radius - 0.5, radius + 0.5, sample_size);
+        color += mix(color / total, sample_color, weight);
+        total += 1.0;
+
+        radius += RADIUS_SCALE / radius;
+        angle += GOLDEN_ANGLE;
+    }
+
+    return vec4<f32>(color / total, center.a);
+}
@@ -0,0 +1,28 @@
+// Copies the depth of the scaled scene into a depth texture the size of the screen so the
+// world overlay can be hidden behind the scene
+
+@group(0) @binding(0)
+var t_depth: texture_depth_2d;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+// A single triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.tex_coords = uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @builtin(frag_depth) f32 {
+    let size = vec2<i32>(textureDimensions(t_depth));
+    let coords = min(vec2<i32>(in.tex_coords * vec2<f32>(size)), size - 1);
+    return textureLoad(t_depth, coords, 0);
+}
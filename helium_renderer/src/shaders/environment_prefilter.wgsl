@@ -0,0 +1,130 @@
+struct PrefilterParams {
+    roughness: f32,
+    sample_count: u32,
+    _padding_0: u32,
+    _padding_1: u32,
+};
+
+@group(0) @binding(0)
+var environment: texture_2d<f32>;
+
+@group(0) @binding(1)
+var output: texture_storage_2d_array<rgba16float, write>;
+
+@group(0) @binding(2)
+var<uniform> params: PrefilterParams;
+
+const PI: f32 = 3.14159265359;
+
+// Direction through a texel of a cube face, uv is from -1 to 1
+fn cube_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    switch face {
+        case 0u: { return vec3<f32>(1.0, -uv.y, -uv.x); }
+        case 1u: { return vec3<f32>(-1.0, -uv.y, uv.x); }
+        case 2u: { return vec3<f32>(uv.x, 1.0, uv.y); }
+        case 3u: { return vec3<f32>(uv.x, -1.0, -uv.y); }
+        case 4u: { return vec3<f32>(uv.x, -uv.y, 1.0); }
+        default: { return vec3<f32>(-uv.x, -uv.y, -1.0); }
+    }
+}
+
+// Reads the equirectangular environment in a direction
+fn sample_environment(direction: vec3<f32>) -> vec3<f32> {
+    let size = textureDimensions(environment);
+    let uv = vec2<f32>(
+        atan2(direction.z, direction.x) / (2.0 * PI) + 0.5,
+        acos(clamp(direction.y, -1.0, 1.0)) / PI,
+    );
+
+    let texel = min(vec2<u32>(uv * vec2<f32>(size)), size - vec2<u32>(1u));
+    return textureLoad(environment, texel, 0).rgb;
+}
+
+fn tangent_basis(normal: vec3<f32>) -> mat3x3<f32> {
+    let up = select(vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(1.0, 0.0, 0.0), abs(normal.y) > 0.999);
+    let tangent = normalize(cross(up, normal));
+    let bitangent = cross(normal, tangent);
+    return mat3x3<f32>(tangent, bitangent, normal);
+}
+
+// Direction of the output texel, returns false when the invocation is outside of the texture
+fn texel_direction(id: vec3<u32>, direction: ptr<function, vec3<f32>>) -> bool {
+    let size = textureDimensions(output);
+    if (id.x >= size.x || id.y >= size.y) {
+        return false;
+    }
+
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size) * 2.0 - 1.0;
+    *direction = normalize(cube_direction(id.z, uv));
+    return true;
+}
+
+// Cosine weighted sum of the light around each direction for the diffuse lighting
+@compute @workgroup_size(8, 8, 1)
+fn irradiance(@builtin(global_invocation_id) id: vec3<u32>) {
+    var normal: vec3<f32>;
+    if (!texel_direction(id, &normal)) {
+        return;
+    }
+
+    let basis = tangent_basis(normal);
+    let steps = max(params.sample_count, 1u);
+
+    var sum = vec3<f32>(0.0);
+    for (var i = 0u; i < steps; i++) {
+        let phi = 2.0 * PI * (f32(i) + 0.5) / f32(steps);
+        for (var j = 0u; j < steps; j++) {
+            let theta = 0.5 * PI * (f32(j) + 0.5) / f32(steps);
+            let local = vec3<f32>(sin(theta) * cos(phi), sin(theta) * sin(phi), cos(theta));
+            sum += sample_environment(basis * local) * cos(theta) * sin(theta);
+        }
+    }
+
+    let irradiance = PI * sum / f32(steps * steps);
+    textureStore(output, id.xy, id.z, vec4<f32>(irradiance, 1.0));
+}
+
+fn hammersley(i: u32, count: u32) -> vec2<f32> {
+    return vec2<f32>(f32(i) / f32(count), f32(reverseBits(i)) * 2.3283064365386963e-10);
+}
+
+fn importance_sample_ggx(xi: vec2<f32>, roughness: f32) -> vec3<f32> {
+    let a = roughness * roughness;
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+    let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+    return vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+}
+
+// Blurs the environment by the roughness of a mip level for the specular reflections
+@compute @workgroup_size(8, 8, 1)
+fn specular(@builtin(global_invocation_id) id: vec3<u32>) {
+    var normal: vec3<f32>;
+    if (!texel_direction(id, &normal)) {
+        return;
+    }
+
+    if (params.roughness <= 0.0) {
+        textureStore(output, id.xy, id.z, vec4<f32>(sample_environment(normal), 1.0));
+        return;
+    }
+
+    // The view direction is assumed to be the same as the normal
+    let basis = tangent_basis(normal);
+    let count = max(params.sample_count, 1u);
+
+    var sum = vec3<f32>(0.0);
+    var weight = 0.0;
+    for (var i = 0u; i < count; i++) {
+        let half_vector = basis * importance_sample_ggx(hammersley(i, count), params.roughness);
+        let light_dir = normalize(2.0 * dot(normal, half_vector) * half_vector - normal);
+        let n_dot_l = dot(normal, light_dir);
+
+        if (n_dot_l > 0.0) {
+            sum += sample_environment(light_dir) * n_dot_l;
+            weight += n_dot_l;
+        }
+    }
+
+    textureStore(output, id.xy, id.z, vec4<f32>(sum / max(weight, 1e-4), 1.0));
+}
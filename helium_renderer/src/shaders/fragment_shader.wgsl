@@ -3,65 +3,451 @@ struct VertexOutput {
     @location(0) tex_coords: vec2<f32>,
     @location(1) world_normal: vec3<f32>,
     @location(2) world_position: vec3<f32>,
+    @location(3) color: vec4<f32>,
+    @location(4) custom_data: vec4<f32>,
+    // Layer of the material texture array
+    @location(5) @interpolate(flat) layer: u32,
+    // Scales the emissive color of the material
+    @location(6) emission: f32,
+    // Ambient occlusion baked into the vertices, 1 is not occluded
+    @location(7) occlusion: f32,
 }
 
 // Fagment Shader
 
+struct MaterialUniform {
+    ambient_color: vec4<f32>,
+    diffuse_color: vec4<f32>,
+    // w is the specular exponent
+    specular_color: vec4<f32>,
+    // w is the emissive intensity
+    emissive_color: vec4<f32>,
+    dissolve: f32,
+    illumination_model: u32,
+    flags: u32,
+    metallic: f32,
+    roughness: f32,
+    layer_count: u32,
+    // Fragments with less alpha are discarded, zero when the material is not masked
+    alpha_cutoff: f32,
+    _padding: u32,
+    // xy is how far the texture coordinates scroll every second and z how fast they turn
+    uv_animation: vec4<f32>,
+    // The columns, rows, frames, and frames per second of the flipbook, no frames turns it off
+    flipbook: vec4<f32>,
+};
+
+const MATERIAL_FLAG_NORMAL_MAP: u32 = 1u;
+const MATERIAL_FLAG_LAYERS: u32 = 2u;
+const MATERIAL_FLAG_SPLAT_MAP: u32 = 4u;
+
 @group(0) @binding(0)
 var t_diffuse: texture_2d<f32>;
 
 @group(0) @binding(1)
 var s_diffuse: sampler;
 
+@group(0) @binding(2)
+var<uniform> material: MaterialUniform;
+
+@group(0) @binding(3)
+var t_specular: texture_2d<f32>;
+
+@group(0) @binding(4)
+var t_normal: texture_2d<f32>;
+
+@group(0) @binding(5)
+var t_dissolve: texture_2d<f32>;
+
+@group(0) @binding(6)
+var t_layers: texture_2d_array<f32>;
+
+@group(0) @binding(7)
+var t_splat: texture_2d<f32>;
+
+@group(0) @binding(8)
+var t_emissive: texture_2d<f32>;
 
 
 struct CameraUniform {
     view_position: vec4<f32>,
     view_proj: mat4x4<f32>,
+    // Fragments behind the plane are discarded when drawing reflections
+    clip_plane: vec4<f32>,
+    // x is the seconds since the renderer started
+    time: vec4<f32>,
 };
 
+const LIGHT_UNITS_CANDELA: u32 = 1u;
+const LIGHT_KIND_DIRECTIONAL: u32 = 1u;
+
 struct Light {
     position: array<f32, 3>,
     color: array<f32, 3>,
+    intensity: f32,
+    units: u32,
+    direction: array<f32, 3>,
+    kind: u32,
+};
+
+struct Lights {
+    // Scale of the lights in candela so they fit in the range of the scene
+    exposure: f32,
+    _padding_0: f32,
+    _padding_1: f32,
+    _padding_2: f32,
+    lights: array<Light>,
 };
 
 @group(1) @binding(0)
 var<uniform> camera: CameraUniform;
 
 @group(2) @binding(0)
-var<storage, read> lights: array<Light>;
-// var<storage, read> lights: array<f32>;
+var<storage, read> light_buffer: Lights;
+
+struct EnvironmentUniform {
+    intensity: f32,
+    specular_mips: f32,
+    enabled: u32,
+    _padding: u32,
+};
+
+@group(2) @binding(1)
+var t_irradiance: texture_cube<f32>;
+
+@group(2) @binding(2)
+var t_environment_specular: texture_cube<f32>;
+
+@group(2) @binding(3)
+var s_environment: sampler;
+
+@group(2) @binding(4)
+var<uniform> environment: EnvironmentUniform;
+
+const SHADOW_KIND_CASCADES: u32 = 1u;
+
+struct Shadow {
+    // Cameras of the faces of a point light in the order +x, -x, +y, -y, +z, -z, or of
+    // the cascades of a directional light from the nearest to the farthest
+    view_proj: array<mat4x4<f32>, 6>,
+    light_index: u32,
+    first_layer: u32,
+    pcf_kernel: u32,
+    bias: f32,
+    // Part of the layer that is used by lights with a lower resolution
+    uv_scale: f32,
+    texel_size: f32,
+    kind: u32,
+    cascade_count: u32,
+    // Depth along the view where each cascade ends
+    cascade_splits: vec4<f32>,
+    // Radius of each cascade, farther cascades have bigger texels and need more bias
+    cascade_radii: vec4<f32>,
+    // Part of each cascade that is blended into the next one
+    cascade_blend: f32,
+    _padding_0: f32,
+    _padding_1: f32,
+    _padding_2: f32,
+};
+
+struct Shadows {
+    count: u32,
+    _padding_0: u32,
+    _padding_1: u32,
+    _padding_2: u32,
+    shadows: array<Shadow>,
+};
+
+@group(2) @binding(5)
+var t_shadow: texture_depth_2d_array;
+
+@group(2) @binding(6)
+var s_shadow: sampler_comparison;
+
+@group(2) @binding(7)
+var<storage, read> shadow_buffer: Shadows;
+
+// The face of the cube around a light that a direction points through
+fn cube_face(direction: vec3<f32>) -> u32 {
+    let magnitude = abs(direction);
+    if (magnitude.x >= magnitude.y && magnitude.x >= magnitude.z) {
+        return select(1u, 0u, direction.x > 0.0);
+    }
+    if (magnitude.y >= magnitude.z) {
+        return select(3u, 2u, direction.y > 0.0);
+    }
+    return select(5u, 4u, direction.z > 0.0);
+}
+
+// Compares a position against one layer of a shadow, 1 is fully lit and 0 is fully shadowed
+fn sample_shadow(shadow: Shadow, index: u32, position: vec3<f32>) -> f32 {
+    let clip = shadow.view_proj[index] * vec4<f32>(position, 1.0);
+    let ndc = clip.xyz / clip.w;
+
+    // Positions past the range of the shadows are lit
+    if (ndc.z > 1.0) {
+        return 1.0;
+    }
+
+    let uv = (ndc.xy * vec2<f32>(0.5, -0.5) + vec2<f32>(0.5)) * shadow.uv_scale;
+    let uv_min = vec2<f32>(shadow.texel_size * 0.5);
+    let uv_max = vec2<f32>(shadow.uv_scale - shadow.texel_size * 0.5);
+    let layer = i32(shadow.first_layer + index);
+
+    // Percentage closer filtering averages a square of comparisons around the position
+    let radius = i32(shadow.pcf_kernel / 2u);
+    var lit = 0.0;
+    var samples = 0.0;
+    for (var x = -radius; x <= radius; x = x + 1) {
+        for (var y = -radius; y <= radius; y = y + 1) {
+            let offset = vec2<f32>(f32(x), f32(y)) * shadow.texel_size;
+            let sample_uv = clamp(uv + offset, uv_min, uv_max);
+            lit += textureSampleCompareLevel(t_shadow, s_shadow, sample_uv, layer, ndc.z);
+            samples += 1.0;
+        }
+    }
+
+    return lit / samples;
+}
+
+// Picks the cascade by the depth of the position and blends it into the next cascade
+// near its end so the seams between the cascades are not visible
+fn sample_cascades(shadow: Shadow, world_position: vec3<f32>, normal: vec3<f32>) -> f32 {
+    // The splits are depths along the view like the w of the clip position
+    let distance = (camera.view_proj * vec4<f32>(world_position, 1.0)).w;
+
+    var cascade = shadow.cascade_count;
+    for (var index: u32 = 0u; index < shadow.cascade_count; index = index + 1u) {
+        if (distance < shadow.cascade_splits[index]) {
+            cascade = index;
+            break;
+        }
+    }
+
+    // Positions past the last cascade are lit
+    if (cascade >= shadow.cascade_count) {
+        return 1.0;
+    }
+
+    let bias_scale = shadow.cascade_radii[cascade] / shadow.cascade_radii[0];
+    var lit = sample_shadow(shadow, cascade, world_position + normal * shadow.bias * bias_scale);
+
+    let split = shadow.cascade_splits[cascade];
+    let previous_split = select(0.0, shadow.cascade_splits[max(cascade, 1u) - 1u], cascade > 0u);
+    let blend_start = split - (split - previous_split) * shadow.cascade_blend;
+    if (cascade + 1u < shadow.cascade_count && distance > blend_start) {
+        let next_bias_scale = shadow.cascade_radii[cascade + 1u] / shadow.cascade_radii[0];
+        let next = sample_shadow(shadow, cascade + 1u, world_position + normal * shadow.bias * next_bias_scale);
+        lit = mix(lit, next, smoothstep(blend_start, split, distance));
+    }
+
+    return lit;
+}
+
+// How much of a light reaches a position, 1 is fully lit and 0 is fully shadowed
+fn shadow_factor(light_index: u32, light_position: vec3<f32>, world_position: vec3<f32>, normal: vec3<f32>) -> f32 {
+    for (var shadow_index: u32 = 0u; shadow_index < shadow_buffer.count; shadow_index = shadow_index + 1u) {
+        let shadow = shadow_buffer.shadows[shadow_index];
+        if (shadow.light_index != light_index) {
+            continue;
+        }
+
+        if (shadow.kind == SHADOW_KIND_CASCADES) {
+            return sample_cascades(shadow, world_position, normal);
+        }
+
+        // Moving the position off the surface keeps it from shadowing itself
+        let position = world_position + normal * shadow.bias;
+        return sample_shadow(shadow, cube_face(position - light_position), position);
+    }
+
+    return 1.0;
+}
+
+// Analytic fit of the split sum brdf so a lookup texture is not needed
+fn environment_brdf(f0: vec3<f32>, roughness: f32, n_dot_v: f32) -> vec3<f32> {
+    let c0 = vec4<f32>(-1.0, -0.0275, -0.572, 0.022);
+    let c1 = vec4<f32>(1.0, 0.0425, 1.04, -0.04);
+    let r = roughness * c0 + c1;
+    let a004 = min(r.x * r.x, exp2(-9.28 * n_dot_v)) * r.x + r.y;
+    let ab = vec2<f32>(-1.04, 1.04) * a004 + r.zw;
+    return f0 * ab.x + ab.y;
+}
+
+// Diffuse and specular lighting from the environment map
+fn environment_lighting(object_color: vec3<f32>, normal: vec3<f32>, view_dir: vec3<f32>) -> vec3<f32> {
+    let roughness = clamp(material.roughness, 0.0, 1.0);
+    let metallic = clamp(material.metallic, 0.0, 1.0);
+    let n_dot_v = max(dot(normal, view_dir), 1e-4);
+
+    let f0 = mix(vec3<f32>(0.04), object_color, metallic);
+    let irradiance = textureSample(t_irradiance, s_environment, normal).rgb;
+    let prefiltered = textureSampleLevel(
+        t_environment_specular,
+        s_environment,
+        reflect(-view_dir, normal),
+        roughness * (environment.specular_mips - 1.0),
+    ).rgb;
+
+    let diffuse = irradiance * object_color * (1.0 - metallic);
+    let specular = prefiltered * environment_brdf(f0, roughness, n_dot_v);
+    return (diffuse + specular) * environment.intensity;
+}
+
+// Perturbs the normal with the normal map using the screen space derivatives
+// so the meshes do not need tangents
+fn apply_normal_map(normal: vec3<f32>, position: vec3<f32>, uv: vec2<f32>, sampled: vec3<f32>) -> vec3<f32> {
+    let dp1 = dpdx(position);
+    let dp2 = dpdy(position);
+    let duv1 = dpdx(uv);
+    let duv2 = dpdy(uv);
+
+    let dp2perp = cross(dp2, normal);
+    let dp1perp = cross(normal, dp1);
+    let tangent = dp2perp * duv1.x + dp1perp * duv2.x;
+    let bitangent = dp2perp * duv1.y + dp1perp * duv2.y;
+
+    let inverse_max = inverseSqrt(max(max(dot(tangent, tangent), dot(bitangent, bitangent)), 1e-8));
+    let tbn = mat3x3<f32>(tangent * inverse_max, bitangent * inverse_max, normal);
+
+    return normalize(tbn * (sampled * 2.0 - 1.0));
+}
+
+// The diffuse color, layered materials use the layer of the instance
+// or blend the first four layers with the weights of the splat map
+fn diffuse_color(uv: vec2<f32>, layer: u32) -> vec4<f32> {
+    if ((material.flags & MATERIAL_FLAG_LAYERS) == 0u) {
+        return textureSample(t_diffuse, s_diffuse, uv);
+    }
+
+    let last_layer = max(material.layer_count, 1u) - 1u;
+    if ((material.flags & MATERIAL_FLAG_SPLAT_MAP) == 0u) {
+        return textureSample(t_layers, s_diffuse, uv, min(layer, last_layer));
+    }
+
+    let weights = textureSample(t_splat, s_diffuse, uv);
+    let total = max(weights.r + weights.g + weights.b + weights.a, 1e-4);
+
+    var color = vec4<f32>(0.0);
+    for (var i: u32 = 0u; i < 4u; i = i + 1u) {
+        color += weights[i] / total * textureSample(t_layers, s_diffuse, uv, min(i, last_layer));
+    }
+    return color;
+}
+
+// Scrolls and turns the texture coordinates and moves them into the current cell of the
+// flipbook
+fn animate_uv(uv: vec2<f32>) -> vec2<f32> {
+    let time = camera.time.x;
+
+    // Turns about the center of the texture
+    let angle = material.uv_animation.z * time;
+    let centered = uv - vec2<f32>(0.5);
+    var animated = vec2<f32>(
+        cos(angle) * centered.x - sin(angle) * centered.y,
+        sin(angle) * centered.x + cos(angle) * centered.y,
+    ) + vec2<f32>(0.5);
+    // Only the fraction of the scroll is kept so the coordinates stay precise
+    animated += fract(material.uv_animation.xy * time);
+
+    let frames = material.flipbook.z;
+    if (frames < 1.0) {
+        return animated;
+    }
+
+    let cells = max(material.flipbook.xy, vec2<f32>(1.0));
+    let frame = floor(time * material.flipbook.w) % frames;
+    let cell = vec2<f32>(frame % cells.x, floor(frame / cells.x));
+    return (fract(animated) + cell) / cells;
+}
 
 @fragment
-fn main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let object_color: vec4<f32> = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+fn main(in: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    let uv = animate_uv(in.tex_coords);
+    let texture_color: vec4<f32> = diffuse_color(uv, in.layer);
+    let specular_map: vec4<f32> = textureSample(t_specular, s_diffuse, uv);
+    let normal_map: vec4<f32> = textureSample(t_normal, s_diffuse, uv);
+    let dissolve_map: vec4<f32> = textureSample(t_dissolve, s_diffuse, uv);
+    let emissive_map: vec4<f32> = textureSample(t_emissive, s_diffuse, uv);
+
+    if (dot(vec4<f32>(in.world_position, 1.0), camera.clip_plane) < 0.0) {
+        discard;
+    }
+
+    let object_color = material.diffuse_color.rgb * texture_color.rgb * in.color.rgb;
+    // Emission can be brighter than white so the bloom picks it up
+    let emission = material.emissive_color.rgb * material.emissive_color.w * emissive_map.rgb * in.emission;
+    let alpha = material.dissolve * texture_color.a * dissolve_map.r * in.color.a;
+    if (alpha < material.alpha_cutoff) {
+        discard;
+    }
+
+    // The back faces of double sided materials are lit from their own side
+    let geometry_normal = normalize(select(-in.world_normal, in.world_normal, front_facing));
+    let mapped_normal = apply_normal_map(geometry_normal, in.world_position, uv, normal_map.rgb);
+    let has_normal_map = (material.flags & MATERIAL_FLAG_NORMAL_MAP) != 0u;
+    let normal = select(geometry_normal, mapped_normal, has_normal_map);
+
+    // Illumination model 0 is a constant color with no lighting
+    if (material.illumination_model == 0u) {
+        return vec4<f32>(object_color + emission, alpha);
+    }
+
+    let specular_exponent = max(material.specular_color.w, 1.0);
+    let specular_enabled = material.illumination_model >= 2u;
+
     var result: vec3<f32> = vec3<f32>(0.0, 0.0, 0.0);
-    for (var light_index: u32 = 0; light_index < arrayLength(&lights); light_index = light_index + 1) {
-        let position = vec3<f32>(lights[light_index].position[0], lights[light_index].position[1], lights[light_index].position[2]);
-        let color = vec3<f32>(lights[light_index].color[0], lights[light_index].color[1], lights[light_index].color[2]);
-        
+    for (var light_index: u32 = 0; light_index < arrayLength(&light_buffer.lights); light_index = light_index + 1) {
+        let light = light_buffer.lights[light_index];
+        let position = vec3<f32>(light.position[0], light.position[1], light.position[2]);
+
+        let direction = vec3<f32>(light.direction[0], light.direction[1], light.direction[2]);
+        let directional = light.kind == LIGHT_KIND_DIRECTIONAL;
+
+        // Lights in candela fall off with the square of the distance and are exposed like
+        // a camera would, directional lights are in lux and do not fall off, lights without
+        // units are used as they are
+        let to_light = position - in.world_position;
+        let physical = light.units == LIGHT_UNITS_CANDELA;
+        let distance_falloff = select(1.0 / max(dot(to_light, to_light), 1e-4), 1.0, directional);
+        let falloff = select(1.0, light_buffer.exposure * distance_falloff, physical);
+        let color = vec3<f32>(light.color[0], light.color[1], light.color[2]) * light.intensity * falloff;
+
         // Ambient lighting
         let ambient_strength = 0.01;
-        let ambient_color = color.rgb * ambient_strength;
+        let ambient_color = color.rgb * ambient_strength * material.ambient_color.rgb * in.occlusion;
 
 
         // Diffuse lighting
-        let light_dir = normalize(position.xyz - in.world_position);
+        let light_dir = select(normalize(to_light), -direction, directional);
+
+        let shadow = shadow_factor(light_index, position, in.world_position, geometry_normal);
 
-        let diffuse_strength = max(dot(in.world_normal, light_dir), 0.0);
-        let diffuse_color = color.rgb * diffuse_strength;
+        let diffuse_strength = max(dot(normal, light_dir), 0.0);
+        let diffuse_color = color.rgb * diffuse_strength * shadow;
 
         // Specular lighting
         let view_dir = normalize(camera.view_position.xyz - in.world_position);
-        let reflect_dir = reflect(-light_dir, in.world_normal);
+        let reflect_dir = reflect(-light_dir, normal);
         // let half_dir = normalize(view_dir + light_dir);
-        let specular_strength = pow(max(dot(view_dir, reflect_dir), 0.0), 1000.0);
+        let specular_strength = pow(max(dot(view_dir, reflect_dir), 0.0), specular_exponent);
         // let specular_strength = pow(max(dot(view_dir, half_dir), 0.0), 100.0);
-        let specular_color = specular_strength * color.rgb;
+        let specular_color = specular_strength * color.rgb * material.specular_color.rgb * specular_map.rgb * shadow;
+
 
+        result += (ambient_color + diffuse_color) * object_color;
+        if (specular_enabled) {
+            result += specular_color;
+        }
+    }
 
-        result += (ambient_color + diffuse_color + specular_color) * object_color.rgb;
+    if (environment.enabled != 0u) {
+        let view_dir = normalize(camera.view_position.xyz - in.world_position);
+        // The light from the environment is the light that crevices block
+        result += environment_lighting(object_color, normal, view_dir) * in.occlusion;
     }
 
-    return vec4<f32>(result, object_color.a);
+    result += emission;
+
+    return vec4<f32>(result, alpha);
 }
@@ -0,0 +1,87 @@
+// Smooths the edges of the tonemapped screen by blurring along the direction of the edges
+
+// Smallest amount the edge direction is reduced by
+const REDUCE_MIN: f32 = 1.0 / 128.0;
+// How much the edge direction is reduced by the brightness around the pixel
+const REDUCE_MUL: f32 = 1.0 / 8.0;
+// Farthest distance in pixels that is sampled along an edge
+const SPAN_MAX: f32 = 8.0;
+
+@group(0) @binding(0)
+var t_screen: texture_2d<f32>;
+
+@group(0) @binding(1)
+var s_screen: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+// A single triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.tex_coords = uv;
+    return out;
+}
+
+// Perceptual brightness of a linear color
+fn luma(color: vec3<f32>) -> f32 {
+    return sqrt(dot(color, vec3<f32>(0.299, 0.587, 0.114)));
+}
+
+fn sample_screen(uv: vec2<f32>) -> vec3<f32> {
+    return textureSampleLevel(t_screen, s_screen, uv, 0.0).rgb;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_screen));
+    let uv = in.tex_coords;
+
+    let luma_nw = luma(sample_screen(uv + vec2<f32>(-1.0, -1.0) * texel));
+    let luma_ne = luma(sample_screen(uv + vec2<f32>(1.0, -1.0) * texel));
+    let luma_sw = luma(sample_screen(uv + vec2<f32>(-1.0, 1.0) * texel));
+    let luma_se = luma(sample_screen(uv + vec2<f32>(1.0, 1.0) * texel));
+    let color_m = sample_screen(uv);
+    let luma_m = luma(color_m);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // The edge runs across the largest change in brightness
+    var direction = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+
+    let direction_reduce = max(
+        (luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL,
+        REDUCE_MIN,
+    );
+    let inverse_direction_min = 1.0 / (min(abs(direction.x), abs(direction.y)) + direction_reduce);
+    direction = clamp(
+        direction * inverse_direction_min,
+        vec2<f32>(-SPAN_MAX),
+        vec2<f32>(SPAN_MAX),
+    ) * texel;
+
+    let color_a = 0.5 * (
+        sample_screen(uv + direction * (1.0 / 3.0 - 0.5))
+        + sample_screen(uv + direction * (2.0 / 3.0 - 0.5))
+    );
+    let color_b = color_a * 0.5 + 0.25 * (
+        sample_screen(uv - direction * 0.5)
+        + sample_screen(uv + direction * 0.5)
+    );
+
+    // The wider blur is only used when it did not cross into another edge
+    let luma_b = luma(color_b);
+    let color = select(color_b, color_a, luma_b < luma_min || luma_b > luma_max);
+
+    return vec4<f32>(color, 1.0);
+}
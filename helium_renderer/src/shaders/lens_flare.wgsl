@@ -0,0 +1,141 @@
+// Must match MAX_FLARE_SOURCES and MAX_FLARE_ELEMENTS in lens_flare.rs
+const MAX_SOURCES: u32 = 8u;
+const MAX_ELEMENTS: u32 = 16u;
+
+// Shapes of the elements
+const SHAPE_DISK: u32 = 0u;
+const SHAPE_RING: u32 = 1u;
+const SHAPE_GLARE: u32 = 2u;
+
+struct FlareSource {
+    // Position on the screen in ndc with the depth in z
+    position: vec4<f32>,
+    // Color times the brightness of the light
+    color: vec4<f32>,
+}
+
+struct FlareElement {
+    // Position along the line through the center of the screen, its size as a fraction
+    // of the height of the screen, and its shape
+    position: f32,
+    size: f32,
+    shape: u32,
+    _padding: f32,
+    color: vec4<f32>,
+}
+
+struct FlareUniform {
+    sources: array<FlareSource, MAX_SOURCES>,
+    elements: array<FlareElement, MAX_ELEMENTS>,
+    source_count: u32,
+    element_count: u32,
+    // Radius in pixels of the area around a source that is tested against the depth
+    occlusion_radius: f32,
+    intensity: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> flare: FlareUniform;
+
+@group(0) @binding(1)
+var t_depth: texture_depth_2d;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // -1 to 1 across the element
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) @interpolate(flat) shape: u32,
+}
+
+// Samples on each side of the center of the occlusion test
+const OCCLUSION_TAPS: i32 = 2;
+
+// Fraction of the depth samples around the source that are not in front of it
+fn visibility(source: vec4<f32>) -> f32 {
+    let size = vec2<i32>(textureDimensions(t_depth));
+    let center = vec2<i32>((source.xy * vec2<f32>(0.5, -0.5) + 0.5) * vec2<f32>(size));
+    let step = flare.occlusion_radius / f32(OCCLUSION_TAPS);
+
+    var visible = 0.0;
+    var total = 0.0;
+    for (var y = -OCCLUSION_TAPS; y <= OCCLUSION_TAPS; y++) {
+        for (var x = -OCCLUSION_TAPS; x <= OCCLUSION_TAPS; x++) {
+            let offset = vec2<i32>(vec2<f32>(f32(x), f32(y)) * step);
+            let pixel = center + offset;
+            total += 1.0;
+            // Samples off of the screen can not hide the source
+            if any(pixel < vec2<i32>(0)) || any(pixel >= size) {
+                continue;
+            }
+            if textureLoad(t_depth, pixel, 0) >= source.z {
+                visible += 1.0;
+            }
+        }
+    }
+
+    return visible / total;
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) instance_index: u32,
+) -> VertexOutput {
+    let source = flare.sources[instance_index / flare.element_count];
+    let element = flare.elements[instance_index % flare.element_count];
+
+    // Two triangles of a quad
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    let corner = corners[vertex_index];
+
+    // Flares fade out as their source leaves the screen
+    let edge = max(abs(source.position.x), abs(source.position.y));
+    let fade = 1.0 - smoothstep(0.8, 1.2, edge);
+    let strength = visibility(source.position) * fade * flare.intensity;
+
+    let size = vec2<f32>(textureDimensions(t_depth));
+    let aspect = size.y / size.x;
+    // Hidden flares collapse to nothing so they do not cover any pixels
+    let radius = element.size * 2.0 * select(0.0, 1.0, strength > 0.0);
+    let center = source.position.xy * (1.0 - element.position);
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(center + corner * radius * vec2<f32>(aspect, 1.0), 0.0, 1.0);
+    out.uv = corner;
+    out.color = element.color.rgb * source.color.rgb * strength;
+    out.shape = element.shape;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let distance = length(in.uv);
+
+    var shape = 0.0;
+    switch in.shape {
+        case SHAPE_RING: {
+            let ring = (distance - 0.8) * 8.0;
+            shape = exp(-ring * ring);
+        }
+        case SHAPE_GLARE: {
+            // A soft glow with streaks along the axes of the screen
+            let glow = pow(max(1.0 - distance, 0.0), 3.0);
+            let streaks = (exp(-abs(in.uv.y) * 40.0) + exp(-abs(in.uv.x) * 40.0))
+                * max(1.0 - distance, 0.0);
+            shape = glow + streaks * 0.5;
+        }
+        default: {
+            shape = 1.0 - smoothstep(0.6, 1.0, distance);
+        }
+    }
+
+    return vec4<f32>(in.color * shape, 0.0);
+}
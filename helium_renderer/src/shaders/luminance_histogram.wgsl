@@ -0,0 +1,90 @@
+// Builds a histogram of the log luminance of the scene and adapts the exposure to its average
+
+const HISTOGRAM_BINS: u32 = 256u;
+
+struct HistogramParams {
+    min_log_luminance: f32,
+    log_luminance_range: f32,
+    // Fraction of the way the exposure moves to the average luminance this frame
+    adaptation: f32,
+    _padding: f32,
+};
+
+@group(0) @binding(0)
+var t_scene: texture_2d<f32>;
+
+@group(0) @binding(1)
+var<storage, read_write> histogram: array<atomic<u32>, HISTOGRAM_BINS>;
+
+@group(0) @binding(2)
+var<uniform> params: HistogramParams;
+
+// The adapted luminance that the tonemapper exposes for
+@group(0) @binding(3)
+var<storage, read_write> adapted_luminance: f32;
+
+var<workgroup> local_histogram: array<atomic<u32>, HISTOGRAM_BINS>;
+var<workgroup> weighted_bins: array<f32, HISTOGRAM_BINS>;
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// Bin 0 holds pixels that are too dark to count
+fn luminance_bin(color: vec3<f32>) -> u32 {
+    let value = luminance(color);
+    if (value < 1e-5) {
+        return 0u;
+    }
+
+    let log_luminance = clamp(
+        (log2(value) - params.min_log_luminance) / params.log_luminance_range,
+        0.0,
+        1.0,
+    );
+    return u32(log_luminance * 254.0 + 1.0);
+}
+
+@compute @workgroup_size(16, 16)
+fn build_histogram(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    atomicStore(&local_histogram[local_index], 0u);
+    workgroupBarrier();
+
+    let dimensions = textureDimensions(t_scene);
+    if (global_id.x < dimensions.x && global_id.y < dimensions.y) {
+        let color = textureLoad(t_scene, vec2<i32>(global_id.xy), 0).rgb;
+        atomicAdd(&local_histogram[luminance_bin(color)], 1u);
+    }
+
+    workgroupBarrier();
+    atomicAdd(&histogram[local_index], atomicLoad(&local_histogram[local_index]));
+}
+
+@compute @workgroup_size(256)
+fn average_luminance(@builtin(local_invocation_index) local_index: u32) {
+    // The histogram is cleared for the next frame while it is read
+    let count = atomicExchange(&histogram[local_index], 0u);
+    weighted_bins[local_index] = f32(count * local_index);
+    workgroupBarrier();
+
+    for (var stride = HISTOGRAM_BINS / 2u; stride > 0u; stride = stride / 2u) {
+        if (local_index < stride) {
+            weighted_bins[local_index] += weighted_bins[local_index + stride];
+        }
+        workgroupBarrier();
+    }
+
+    if (local_index == 0u) {
+        let dimensions = textureDimensions(t_scene);
+        let lit_pixels = max(f32(dimensions.x * dimensions.y) - f32(count), 1.0);
+        let average_bin = weighted_bins[0] / lit_pixels - 1.0;
+        let average_log_luminance = average_bin / 254.0 * params.log_luminance_range
+            + params.min_log_luminance;
+        let average = exp2(average_log_luminance);
+
+        adapted_luminance = adapted_luminance + (average - adapted_luminance) * params.adaptation;
+    }
+}
@@ -0,0 +1,122 @@
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) world_position: vec3<f32>,
+    @location(3) color: vec4<f32>,
+    @location(4) custom_data: vec4<f32>,
+    // Layer of the material texture array
+    @location(5) @interpolate(flat) layer: u32,
+    // Scales the emissive color of the material
+    @location(6) emission: f32,
+    // Ambient occlusion baked into the vertices, 1 is not occluded
+    @location(7) occlusion: f32,
+}
+
+struct InstanceInput {
+    @location(5) model_matrix_0: vec4<f32>,
+    @location(6) model_matrix_1: vec4<f32>,
+    @location(7) model_matrix_2: vec4<f32>,
+    @location(8) model_matrix_3: vec4<f32>,
+
+    @location(9) normal_matrix_0: vec3<f32>,
+    @location(10) normal_matrix_1: vec3<f32>,
+    @location(11) normal_matrix_2: vec3<f32>,
+
+    @location(12) color: vec4<f32>,
+    @location(13) custom_data: vec4<f32>,
+    @location(14) layer: u32,
+    @location(15) emission: f32,
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) color: vec3<f32>,
+    @location(4) occlusion: f32,
+};
+
+
+struct CameraUniform {
+    view_position: vec4<f32>,
+    view_proj: mat4x4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// Must match MAX_MORPH_TARGETS in morph.rs
+const MAX_MORPH_TARGETS: u32 = 8u;
+
+struct MorphInfo {
+    vertex_count: u32,
+    target_count: u32,
+};
+
+// The weights of one instance for each target
+struct MorphWeights {
+    weights: array<vec4<f32>, 2>,
+};
+
+@group(3) @binding(0)
+var<uniform> morph: MorphInfo;
+
+// The position and normal delta of every vertex of every target
+@group(3) @binding(1)
+var<storage, read> deltas: array<vec4<f32>>;
+
+@group(3) @binding(2)
+var<storage, read> morph_weights: array<MorphWeights>;
+
+
+// Vertex Shader
+
+@vertex
+fn main(
+    model: VertexInput,
+    instance: InstanceInput,
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) instance_index: u32,
+) -> VertexOutput {
+    let model_matrix = mat4x4<f32> (
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+
+    let normal_matrix = mat3x3<f32> (
+        instance.normal_matrix_0,
+        instance.normal_matrix_1,
+        instance.normal_matrix_2,
+    );
+    
+    // The vertex is moved towards every target by the weight of the instance for it
+    var position = model.position;
+    var normal = model.normal;
+    let weights = morph_weights[instance_index].weights;
+    for (var target_index = 0u; target_index < min(morph.target_count, MAX_MORPH_TARGETS); target_index++) {
+        let weight = weights[target_index / 4u][target_index % 4u];
+        if weight == 0.0 {
+            continue;
+        }
+
+        let delta = (target_index * morph.vertex_count + vertex_index) * 2u;
+        position += deltas[delta].xyz * weight;
+        normal += deltas[delta + 1u].xyz * weight;
+    }
+
+    var out: VertexOutput;
+    out.tex_coords = model.tex_coords;
+    out.color = instance.color * vec4<f32>(model.color, 1.0);
+    out.custom_data = instance.custom_data;
+    out.layer = instance.layer;
+    out.emission = instance.emission;
+    out.occlusion = model.occlusion;
+    out.world_normal = normal_matrix * normalize(normal);
+    var world_position: vec4<f32> = model_matrix * vec4<f32>(position, 1.0);
+    out.world_position = world_position.xyz;
+    out.clip_position = camera.view_proj * model_matrix * vec4<f32>(position, 1.0);
+    return out;
+}
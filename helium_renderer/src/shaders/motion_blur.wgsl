@@ -0,0 +1,89 @@
+// Blurs the scene along the motion of the camera and the models
+
+struct MotionUniform {
+    jittered_view_proj: mat4x4<f32>,
+    view_proj: mat4x4<f32>,
+    previous_view_proj: mat4x4<f32>,
+    inverse_view_proj: mat4x4<f32>,
+};
+
+struct MotionBlurSettings {
+    // Fraction of the motion of a frame that is blurred, like the shutter of a camera
+    intensity: f32,
+    samples: u32,
+    // Longest blur in uv coordinates
+    max_length: f32,
+    _padding: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> motion: MotionUniform;
+
+@group(0) @binding(1)
+var t_scene: texture_2d<f32>;
+
+@group(0) @binding(2)
+var s_scene: sampler;
+
+@group(0) @binding(3)
+var t_velocity: texture_2d<f32>;
+
+@group(0) @binding(4)
+var t_depth: texture_depth_2d;
+
+@group(0) @binding(5)
+var<uniform> settings: MotionBlurSettings;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+// A single triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.tex_coords = uv;
+    return out;
+}
+
+// How far the pixel moved on the screen since the last frame
+fn pixel_velocity(pixel: vec2<i32>, uv: vec2<f32>) -> vec2<f32> {
+    let velocity = textureLoad(t_velocity, pixel, 0);
+    if (velocity.a > 0.0) {
+        return velocity.xy;
+    }
+
+    // Pixels without a model, like the sky, only moved with the camera
+    let depth = textureLoad(t_depth, pixel, 0);
+    let ndc = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, depth, 1.0);
+    let world = motion.inverse_view_proj * ndc;
+    let previous_clip = motion.previous_view_proj * vec4<f32>(world.xyz / world.w, 1.0);
+    let previous_ndc = previous_clip.xy / previous_clip.w;
+    return uv - vec2<f32>(previous_ndc.x * 0.5 + 0.5, 0.5 - previous_ndc.y * 0.5);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(in.clip_position.xy);
+    let current = textureLoad(t_scene, pixel, 0);
+
+    var blur = pixel_velocity(pixel, in.tex_coords) * settings.intensity;
+    let blur_length = length(blur);
+    if (blur_length > settings.max_length) {
+        blur = blur * (settings.max_length / blur_length);
+    }
+
+    let samples = max(settings.samples, 2u);
+    var color = vec3<f32>(0.0);
+    for (var i = 0u; i < samples; i++) {
+        // Samples are spread along the motion centered on the pixel
+        let t = f32(i) / f32(samples - 1u) - 0.5;
+        color += textureSampleLevel(t_scene, s_scene, in.tex_coords - blur * t, 0.0).rgb;
+    }
+
+    return vec4<f32>(color / f32(samples), current.a);
+}
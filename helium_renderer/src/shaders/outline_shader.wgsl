@@ -0,0 +1,71 @@
+struct InstanceInput {
+    @location(5) model_matrix_0: vec4<f32>,
+    @location(6) model_matrix_1: vec4<f32>,
+    @location(7) model_matrix_2: vec4<f32>,
+    @location(8) model_matrix_3: vec4<f32>,
+
+    @location(9) normal_matrix_0: vec3<f32>,
+    @location(10) normal_matrix_1: vec3<f32>,
+    @location(11) normal_matrix_2: vec3<f32>,
+
+    @location(12) color: vec4<f32>,
+    @location(13) custom_data: vec4<f32>,
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) color: vec3<f32>,
+};
+
+struct CameraUniform {
+    view_position: vec4<f32>,
+    view_proj: mat4x4<f32>,
+};
+
+struct OutlineUniform {
+    color: vec4<f32>,
+    width: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+@group(1) @binding(0)
+var<uniform> outline: OutlineUniform;
+
+fn world_position(model: VertexInput, instance: InstanceInput, extrude: f32) -> vec4<f32> {
+    let model_matrix = mat4x4<f32> (
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+
+    let normal_matrix = mat3x3<f32> (
+        instance.normal_matrix_0,
+        instance.normal_matrix_1,
+        instance.normal_matrix_2,
+    );
+
+    let world_normal = normalize(normal_matrix * model.normal);
+    return model_matrix * vec4<f32>(model.position, 1.0) + vec4<f32>(world_normal * extrude, 0.0);
+}
+
+// Draws the model at its normal size to mark its pixels in the stencil buffer
+@vertex
+fn vs_mask(model: VertexInput, instance: InstanceInput) -> @builtin(position) vec4<f32> {
+    return camera.view_proj * world_position(model, instance, 0.0);
+}
+
+// Draws the model pushed out along its normals, only outside of the marked pixels
+@vertex
+fn vs_outline(model: VertexInput, instance: InstanceInput) -> @builtin(position) vec4<f32> {
+    return camera.view_proj * world_position(model, instance, outline.width);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return outline.color;
+}
@@ -0,0 +1,87 @@
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) world_position: vec3<f32>,
+    @location(3) color: vec4<f32>,
+    @location(4) custom_data: vec4<f32>,
+}
+
+// Fagment Shader for reflective surfaces
+
+struct MaterialUniform {
+    ambient_color: vec4<f32>,
+    diffuse_color: vec4<f32>,
+    // w is the specular exponent
+    specular_color: vec4<f32>,
+    emissive_color: vec4<f32>,
+    dissolve: f32,
+    illumination_model: u32,
+    flags: u32,
+    metallic: f32,
+    roughness: f32,
+    layer_count: u32,
+    // Fragments with less alpha are discarded, zero when the material is not masked
+    alpha_cutoff: f32,
+    _padding: u32,
+};
+
+@group(0) @binding(0)
+var t_diffuse: texture_2d<f32>;
+
+@group(0) @binding(1)
+var s_diffuse: sampler;
+
+@group(0) @binding(2)
+var<uniform> material: MaterialUniform;
+
+struct CameraUniform {
+    view_position: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    clip_plane: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct ReflectionUniform {
+    reflectivity: f32,
+    _padding_0: f32,
+    _padding_1: f32,
+    _padding_2: f32,
+};
+
+// The scene drawn from the mirrored camera, the same size as the screen
+@group(3) @binding(0)
+var t_reflection: texture_2d<f32>;
+
+@group(3) @binding(1)
+var s_reflection: sampler;
+
+@group(3) @binding(2)
+var<uniform> reflection: ReflectionUniform;
+
+@fragment
+fn main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texture_color: vec4<f32> = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    let surface_color = material.diffuse_color.rgb * texture_color.rgb * in.color.rgb;
+    let alpha = material.dissolve * texture_color.a * in.color.a;
+    if (alpha < material.alpha_cutoff) {
+        discard;
+    }
+
+    // The mirrored scene lines up with the surface at the same pixel on the screen
+    let screen_uv = in.clip_position.xy / vec2<f32>(textureDimensions(t_reflection));
+    let reflected_color = textureSample(t_reflection, s_reflection, screen_uv).rgb;
+
+    // Surfaces reflect more when they are viewed at a grazing angle, a reflectivity
+    // of zero never reflects
+    let view_dir = normalize(camera.view_position.xyz - in.world_position);
+    let n_dot_v = clamp(dot(normalize(in.world_normal), view_dir), 0.0, 1.0);
+    let reflectivity = clamp(reflection.reflectivity, 0.0, 1.0);
+    let fresnel = reflectivity * (1.0 + (1.0 - reflectivity) * pow(1.0 - n_dot_v, 5.0));
+
+    let result = mix(surface_color, reflected_color, fresnel) + material.emissive_color.rgb * material.emissive_color.w;
+
+    return vec4<f32>(result, alpha);
+}
@@ -0,0 +1,136 @@
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) world_position: vec3<f32>,
+    @location(3) color: vec4<f32>,
+    @location(4) custom_data: vec4<f32>,
+    // Layer of the material texture array
+    @location(5) @interpolate(flat) layer: u32,
+    // Scales the emissive color of the material
+    @location(6) emission: f32,
+    // Ambient occlusion baked into the vertices, 1 is not occluded
+    @location(7) occlusion: f32,
+}
+
+struct InstanceInput {
+    @location(5) model_matrix_0: vec4<f32>,
+    @location(6) model_matrix_1: vec4<f32>,
+    @location(7) model_matrix_2: vec4<f32>,
+    @location(8) model_matrix_3: vec4<f32>,
+
+    @location(9) normal_matrix_0: vec3<f32>,
+    @location(10) normal_matrix_1: vec3<f32>,
+    @location(11) normal_matrix_2: vec3<f32>,
+
+    @location(12) color: vec4<f32>,
+    @location(13) custom_data: vec4<f32>,
+    @location(14) layer: u32,
+    @location(15) emission: f32,
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) color: vec3<f32>,
+    @location(4) occlusion: f32,
+};
+
+
+struct CameraUniform {
+    view_position: vec4<f32>,
+    view_proj: mat4x4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct ScatterUniform {
+    bounding_radius: f32,
+    falloff_start: f32,
+    falloff_end: f32,
+    _padding: f32,
+};
+
+@group(3) @binding(0)
+var<uniform> scatter: ScatterUniform;
+
+// Position outside of the clip volume so every triangle of a culled instance is clipped
+const CULLED_POSITION: vec4<f32> = vec4<f32>(0.0, 0.0, -2.0, 1.0);
+
+fn matrix_row(matrix: mat4x4<f32>, index: u32) -> vec4<f32> {
+    return vec4<f32>(matrix[0][index], matrix[1][index], matrix[2][index], matrix[3][index]);
+}
+
+// Signed distance from a frustum plane in the form of a row combination of the view projection
+fn plane_distance(plane: vec4<f32>, point: vec3<f32>) -> f32 {
+    return (dot(plane.xyz, point) + plane.w) / length(plane.xyz);
+}
+
+fn outside_frustum(center: vec3<f32>, radius: f32) -> bool {
+    let row_0 = matrix_row(camera.view_proj, 0u);
+    let row_1 = matrix_row(camera.view_proj, 1u);
+    let row_2 = matrix_row(camera.view_proj, 2u);
+    let row_3 = matrix_row(camera.view_proj, 3u);
+
+    return plane_distance(row_3 + row_0, center) < -radius
+        || plane_distance(row_3 - row_0, center) < -radius
+        || plane_distance(row_3 + row_1, center) < -radius
+        || plane_distance(row_3 - row_1, center) < -radius
+        || plane_distance(row_2, center) < -radius
+        || plane_distance(row_3 - row_2, center) < -radius;
+}
+
+// Instances are thinned out with distance using the random value stored in custom_data.w
+fn thinned_out(center: vec3<f32>, keep_value: f32) -> bool {
+    if (scatter.falloff_end <= scatter.falloff_start) {
+        return false;
+    }
+
+    let distance = length(center - camera.view_position.xyz);
+    let density = 1.0 - smoothstep(scatter.falloff_start, scatter.falloff_end, distance);
+    return keep_value > density;
+}
+
+
+// Scatter Vertex Shader
+
+@vertex
+fn main(
+    model: VertexInput,
+    instance: InstanceInput
+) -> VertexOutput {
+    let model_matrix = mat4x4<f32> (
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+
+    let normal_matrix = mat3x3<f32> (
+        instance.normal_matrix_0,
+        instance.normal_matrix_1,
+        instance.normal_matrix_2,
+    );
+    
+    var out: VertexOutput;
+
+    let center = instance.model_matrix_3.xyz;
+    if (outside_frustum(center, scatter.bounding_radius) || thinned_out(center, instance.custom_data.w)) {
+        out.clip_position = CULLED_POSITION;
+        return out;
+    }
+
+    out.tex_coords = model.tex_coords;
+    out.color = instance.color * vec4<f32>(model.color, 1.0);
+    out.custom_data = instance.custom_data;
+    out.layer = instance.layer;
+    out.emission = instance.emission;
+    out.occlusion = model.occlusion;
+    out.world_normal = normal_matrix * model.normal;
+    var world_position: vec4<f32> = model_matrix * vec4<f32>(model.position, 1.0);
+    out.world_position = world_position.xyz;
+    out.clip_position = camera.view_proj * model_matrix * vec4<f32>(model.position, 1.0);
+    return out;
+}
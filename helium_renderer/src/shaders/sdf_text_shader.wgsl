@@ -0,0 +1,63 @@
+// Draws text from a signed distance field atlas so its edges stay sharp at any size
+
+struct CameraUniform {
+    view_position: vec4<f32>,
+    view_proj: mat4x4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+@group(1) @binding(0)
+var t_atlas: texture_2d<f32>;
+
+@group(1) @binding(1)
+var s_atlas: sampler;
+
+struct VertexInput {
+    // xyz is the position in the world when w is 1, xy is the position on the screen in
+    // normalized device coordinates when w is 0
+    @location(0) position: vec4<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) outline_color: vec4<f32>,
+    // x is the distance the outline starts at and y is how soft the edges are
+    @location(4) params: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) outline_color: vec4<f32>,
+    @location(3) params: vec4<f32>,
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    if (in.position.w > 0.5) {
+        out.clip_position = camera.view_proj * vec4<f32>(in.position.xyz, 1.0);
+    } else {
+        out.clip_position = vec4<f32>(in.position.xy, 0.0, 1.0);
+    }
+    out.tex_coords = in.tex_coords;
+    out.color = in.color;
+    out.outline_color = in.outline_color;
+    out.params = in.params;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The edge of the glyph is at 0.5, inside is above it
+    let distance = textureSample(t_atlas, s_atlas, in.tex_coords).r;
+
+    // The edge is smoothed over about a pixel on the screen however large the text is
+    let width = max(fwidth(distance) * 0.7, in.params.y);
+    let fill = smoothstep(0.5 - width, 0.5 + width, distance);
+    let outer = smoothstep(in.params.x - width, in.params.x + width, distance);
+
+    let color = mix(in.outline_color, in.color, fill);
+    return vec4<f32>(color.rgb, color.a * outer);
+}
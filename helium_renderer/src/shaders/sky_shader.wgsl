@@ -0,0 +1,83 @@
+struct SkyUniform {
+    // Turns points on the screen into directions, the camera is at the origin
+    inverse_view_proj: mat4x4<f32>,
+    // Direction towards the sun with the cosine of the radius of the sun disk in w
+    sun_direction: vec4<f32>,
+    // Color of the sun disk and glow with the brightness of the stars in w
+    sun_color: vec4<f32>,
+    // Color straight up with the brightness of the whole sky in w
+    zenith: vec4<f32>,
+    horizon: vec4<f32>,
+    ground: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> sky: SkyUniform;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+};
+
+// Number of star cells around the sky, more cells give smaller and more stars
+const STAR_CELLS: f32 = 150.0;
+// Fraction of the cells that do not have a star
+const STAR_THRESHOLD: f32 = 0.995;
+// Brightness of the sun disk compared to the rest of the sky
+const SUN_DISK_BRIGHTNESS: f32 = 20.0;
+
+// One triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    let ndc = uv * 2.0 - 1.0;
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 1.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+fn hash(cell: vec3<f32>) -> f32 {
+    let p = fract(cell * 0.3183099 + 0.1) * 17.0;
+    return fract(p.x * p.y * p.z * (p.x + p.y + p.z));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let near = sky.inverse_view_proj * vec4<f32>(in.ndc, 0.0, 1.0);
+    let far = sky.inverse_view_proj * vec4<f32>(in.ndc, 1.0, 1.0);
+    let direction = normalize(far.xyz / far.w - near.xyz / near.w);
+    let up = direction.y;
+
+    // Gradient from the horizon to the zenith and down to the ground
+    var color: vec3<f32>;
+    if up >= 0.0 {
+        color = mix(sky.horizon.rgb, sky.zenith.rgb, sqrt(up));
+    } else {
+        color = mix(sky.horizon.rgb, sky.ground.rgb, sqrt(min(-up * 4.0, 1.0)));
+    }
+
+    // The sun is hidden by the ground once it sets
+    let above_ground = smoothstep(-0.01, 0.0, up);
+    let sun_cos = dot(direction, sky.sun_direction.xyz);
+    let sun_edge = sky.sun_direction.w;
+    color += sky.sun_color.rgb * pow(max(sun_cos, 0.0), 32.0) * 0.4;
+    color += sky.sun_color.rgb * smoothstep(sun_edge, mix(sun_edge, 1.0, 0.1), sun_cos)
+        * SUN_DISK_BRIGHTNESS * above_ground;
+
+    // Stars are points in a few of the cells of a grid around the camera
+    let stars = sky.sun_color.w;
+    if stars > 0.0 && up > 0.0 {
+        let cell_position = direction * STAR_CELLS;
+        let star = hash(floor(cell_position));
+        if star > STAR_THRESHOLD {
+            let offset = length(fract(cell_position) - 0.5);
+            let brightness = (star - STAR_THRESHOLD) / (1.0 - STAR_THRESHOLD);
+            color += vec3<f32>(1.0 - smoothstep(0.0, 0.25, offset)) * brightness * stars
+                * smoothstep(0.0, 0.2, up);
+        }
+    }
+
+    return vec4<f32>(color * sky.zenith.w, 1.0);
+}
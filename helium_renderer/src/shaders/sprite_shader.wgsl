@@ -0,0 +1,33 @@
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) opacity: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) opacity: f32,
+}
+
+@group(0) @binding(0)
+var t_sprite: texture_2d<f32>;
+
+@group(0) @binding(1)
+var s_sprite: sampler;
+
+// Sprites are already in normalized device coordinates
+@vertex
+fn vs_main(model: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(model.position, 0.0, 1.0);
+    out.tex_coords = model.tex_coords;
+    out.opacity = model.opacity;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(t_sprite, s_sprite, in.tex_coords);
+    return vec4<f32>(color.rgb, color.a * in.opacity);
+}
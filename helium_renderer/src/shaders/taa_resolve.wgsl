@@ -0,0 +1,98 @@
+// Blends the current frame with the reprojected history of the last frames
+
+struct MotionUniform {
+    jittered_view_proj: mat4x4<f32>,
+    view_proj: mat4x4<f32>,
+    previous_view_proj: mat4x4<f32>,
+    inverse_view_proj: mat4x4<f32>,
+};
+
+struct TaaUniform {
+    // How much of the current frame is blended into the history
+    blend: f32,
+    // The history is thrown away when it is not valid, like after resizing
+    reset: u32,
+    _padding_0: f32,
+    _padding_1: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> motion: MotionUniform;
+
+@group(0) @binding(1)
+var t_scene: texture_2d<f32>;
+
+@group(0) @binding(2)
+var t_history: texture_2d<f32>;
+
+@group(0) @binding(3)
+var s_history: sampler;
+
+@group(0) @binding(4)
+var t_velocity: texture_2d<f32>;
+
+@group(0) @binding(5)
+var t_depth: texture_depth_2d;
+
+@group(0) @binding(6)
+var<uniform> taa: TaaUniform;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+// A single triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.tex_coords = uv;
+    return out;
+}
+
+// Where the pixel was on the screen in the last frame
+fn previous_uv(pixel: vec2<i32>, uv: vec2<f32>) -> vec2<f32> {
+    let velocity = textureLoad(t_velocity, pixel, 0);
+    if (velocity.a > 0.0) {
+        return uv - velocity.xy;
+    }
+
+    // Pixels without a model, like the sky, only moved with the camera
+    let depth = textureLoad(t_depth, pixel, 0);
+    let ndc = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, depth, 1.0);
+    let world = motion.inverse_view_proj * ndc;
+    let previous_clip = motion.previous_view_proj * vec4<f32>(world.xyz / world.w, 1.0);
+    let previous_ndc = previous_clip.xy / previous_clip.w;
+    return vec2<f32>(previous_ndc.x * 0.5 + 0.5, 0.5 - previous_ndc.y * 0.5);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(in.clip_position.xy);
+    let dimensions = vec2<i32>(textureDimensions(t_scene));
+    let current = textureLoad(t_scene, pixel, 0);
+
+    // The history is clamped to the colors around the pixel so it can not ghost
+    var neighborhood_min = current.rgb;
+    var neighborhood_max = current.rgb;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let neighbor_pixel = clamp(pixel + vec2<i32>(x, y), vec2<i32>(0), dimensions - 1);
+            let neighbor = textureLoad(t_scene, neighbor_pixel, 0).rgb;
+            neighborhood_min = min(neighborhood_min, neighbor);
+            neighborhood_max = max(neighborhood_max, neighbor);
+        }
+    }
+
+    let history_uv = previous_uv(pixel, in.tex_coords);
+    let history = textureSampleLevel(t_history, s_history, history_uv, 0.0).rgb;
+    let clamped_history = clamp(history, neighborhood_min, neighborhood_max);
+
+    let off_screen = any(history_uv < vec2<f32>(0.0)) || any(history_uv > vec2<f32>(1.0));
+    let blend = select(taa.blend, 1.0, off_screen || taa.reset != 0u);
+
+    return vec4<f32>(mix(clamped_history, current.rgb, blend), current.a);
+}
@@ -0,0 +1,206 @@
+// Exposes, tonemaps, and color grades the hdr scene onto the screen
+
+const TONEMAPPER_NONE: u32 = 0u;
+const TONEMAPPER_REINHARD: u32 = 1u;
+const TONEMAPPER_ACES: u32 = 2u;
+
+struct TonemapSettings {
+    tonemapper: u32,
+    auto_exposure: u32,
+    // Multiplier of the scene color when the exposure is manual, otherwise the
+    // compensation applied to the auto exposure
+    exposure: f32,
+    lut_enabled: u32,
+    lut_size: f32,
+    // The screen has no srgb view so the output is gamma encoded here
+    encode_srgb: u32,
+    _padding_0: f32,
+    _padding_1: f32,
+};
+
+@group(0) @binding(0)
+var t_scene: texture_2d<f32>;
+
+@group(0) @binding(1)
+var s_scene: sampler;
+
+@group(0) @binding(2)
+var<uniform> settings: TonemapSettings;
+
+@group(0) @binding(3)
+var<storage, read> adapted_luminance: f32;
+
+@group(0) @binding(4)
+var t_lut: texture_3d<f32>;
+
+@group(0) @binding(5)
+var s_lut: sampler;
+
+const OVERLAY_VIGNETTE: u32 = 0u;
+const OVERLAY_TINT: u32 = 1u;
+const OVERLAY_DISTORTION: u32 = 2u;
+
+// Must match MAX_SCREEN_OVERLAYS in screen_overlay.rs
+const MAX_SCREEN_OVERLAYS: u32 = 8u;
+
+struct ScreenOverlay {
+    color: vec4<f32>,
+    kind: u32,
+    // How far the overlay has faded in
+    weight: f32,
+    strength: f32,
+    _padding: f32,
+};
+
+struct LensSettings {
+    vignette: f32,
+    chromatic_aberration: f32,
+    grain: f32,
+    time: f32,
+    overlay_count: u32,
+    _padding_0: u32,
+    _padding_1: u32,
+    _padding_2: u32,
+    overlays: array<ScreenOverlay, MAX_SCREEN_OVERLAYS>,
+};
+
+@group(0) @binding(6)
+var<uniform> lens: LensSettings;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+// A single triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.tex_coords = uv;
+    return out;
+}
+
+fn reinhard(color: vec3<f32>) -> vec3<f32> {
+    return color / (vec3<f32>(1.0) + color);
+}
+
+// Narkowicz fit of the ACES filmic curve
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+    return clamp((color * (a * color + b)) / (color * (c * color + d) + e), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+// Cheap hash that turns a position into noise in the 0 to 1 range
+fn hash(position: vec2<f32>) -> f32 {
+    return fract(sin(dot(position, vec2<f32>(12.9898, 78.233))) * 43758.5453);
+}
+
+// Bends the screen coordinates with the waves of every distortion overlay
+fn distort(uv: vec2<f32>) -> vec2<f32> {
+    var distorted = uv;
+    for (var i: u32 = 0u; i < min(lens.overlay_count, MAX_SCREEN_OVERLAYS); i = i + 1u) {
+        let overlay = lens.overlays[i];
+        if (overlay.kind == OVERLAY_DISTORTION) {
+            let waves = vec2<f32>(
+                sin(uv.y * 25.0 + lens.time * 2.0),
+                cos(uv.x * 25.0 + lens.time * 1.7),
+            );
+            distorted += waves * overlay.strength * overlay.weight * 0.01;
+        }
+    }
+    return distorted;
+}
+
+// Blends the colors of the vignette and tint overlays over the screen in order
+fn apply_overlays(color: vec3<f32>, from_center: vec2<f32>) -> vec3<f32> {
+    var result = color;
+    for (var i: u32 = 0u; i < min(lens.overlay_count, MAX_SCREEN_OVERLAYS); i = i + 1u) {
+        let overlay = lens.overlays[i];
+        let amount = overlay.color.a * overlay.weight;
+        switch overlay.kind {
+            case OVERLAY_VIGNETTE: {
+                let edge = smoothstep(1.0 - overlay.strength, 1.0, length(from_center) * 1.41421356);
+                result = mix(result, overlay.color.rgb, edge * amount);
+            }
+            case OVERLAY_TINT: {
+                result = mix(result, overlay.color.rgb, amount);
+            }
+            default: {}
+        }
+    }
+    return result;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = distort(in.tex_coords);
+
+    // Chromatic aberration splits the channels more towards the edges like a cheap lens
+    let from_center = in.tex_coords - vec2<f32>(0.5);
+    let aberration = from_center * lens.chromatic_aberration * 0.02;
+    let scene = textureSample(t_scene, s_scene, uv);
+    let red = textureSample(t_scene, s_scene, uv + aberration).r;
+    let blue = textureSample(t_scene, s_scene, uv - aberration).b;
+
+    // Auto exposure maps the average luminance of the scene to middle gray
+    var exposure = settings.exposure;
+    if (settings.auto_exposure != 0u) {
+        exposure = settings.exposure * 0.18 / max(adapted_luminance, 1e-4);
+    }
+
+    let exposed = vec3<f32>(red, scene.g, blue) * exposure;
+
+    var color: vec3<f32>;
+    switch settings.tonemapper {
+        case TONEMAPPER_REINHARD: {
+            color = reinhard(exposed);
+        }
+        case TONEMAPPER_ACES: {
+            color = aces(exposed);
+        }
+        case TONEMAPPER_NONE, default: {
+            color = clamp(exposed, vec3<f32>(0.0), vec3<f32>(1.0));
+        }
+    }
+
+    // Lookup tables are made for display colors so the grading is done in srgb
+    let lut_coords = linear_to_srgb(color) * ((settings.lut_size - 1.0) / settings.lut_size)
+        + 0.5 / settings.lut_size;
+    let graded = srgb_to_linear(textureSampleLevel(t_lut, s_lut, lut_coords, 0.0).rgb);
+    color = select(color, graded, settings.lut_enabled != 0u);
+
+    // The vignette darkens the corners after the grading so it is not graded away
+    let vignette = 1.0 - lens.vignette * smoothstep(0.2, 0.8, length(from_center) * 1.41421356);
+    color *= vignette;
+
+    // The overlays are drawn over the graded screen so their colors are the ones asked for
+    color = apply_overlays(color, from_center);
+
+    // Grain changes every frame and is stronger in the darker parts of the screen
+    let noise = hash(in.clip_position.xy + fract(lens.time) * 1000.0) - 0.5;
+    color = max(color + noise * lens.grain * 0.1 * (1.0 - color), vec3<f32>(0.0));
+
+    // Everything above is linear, the gamma is only applied once at the very end
+    color = select(color, linear_to_srgb(color), settings.encode_srgb != 0u);
+
+    return vec4<f32>(color, 1.0);
+}
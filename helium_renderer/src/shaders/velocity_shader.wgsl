@@ -0,0 +1,79 @@
+// Draws how far every pixel of the scene moved on the screen since the last frame
+
+struct MotionUniform {
+    jittered_view_proj: mat4x4<f32>,
+    view_proj: mat4x4<f32>,
+    previous_view_proj: mat4x4<f32>,
+    inverse_view_proj: mat4x4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> motion: MotionUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+}
+
+struct InstanceInput {
+    @location(5) model_matrix_0: vec4<f32>,
+    @location(6) model_matrix_1: vec4<f32>,
+    @location(7) model_matrix_2: vec4<f32>,
+    @location(8) model_matrix_3: vec4<f32>,
+}
+
+// The instance as it was drawn in the last frame
+struct PreviousInstanceInput {
+    @location(9) model_matrix_0: vec4<f32>,
+    @location(10) model_matrix_1: vec4<f32>,
+    @location(11) model_matrix_2: vec4<f32>,
+    @location(12) model_matrix_3: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) current_position: vec4<f32>,
+    @location(1) previous_position: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+    previous: PreviousInstanceInput,
+) -> VertexOutput {
+    let model_matrix = mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+
+    let previous_model_matrix = mat4x4<f32>(
+        previous.model_matrix_0,
+        previous.model_matrix_1,
+        previous.model_matrix_2,
+        previous.model_matrix_3,
+    );
+
+    let world_position = model_matrix * vec4<f32>(model.position, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = motion.jittered_view_proj * world_position;
+    // The velocity is measured without the jitter so still objects do not move
+    out.current_position = motion.view_proj * world_position;
+    out.previous_position = motion.previous_view_proj * previous_model_matrix * vec4<f32>(model.position, 1.0);
+    return out;
+}
+
+fn clip_to_uv(clip: vec4<f32>) -> vec2<f32> {
+    let ndc = clip.xy / clip.w;
+    return vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let velocity = clip_to_uv(in.current_position) - clip_to_uv(in.previous_position);
+
+    // Alpha marks the pixels that were covered by a model
+    return vec4<f32>(velocity, 0.0, 1.0);
+}
@@ -3,6 +3,14 @@ struct VertexOutput {
     @location(0) tex_coords: vec2<f32>,
     @location(1) world_normal: vec3<f32>,
     @location(2) world_position: vec3<f32>,
+    @location(3) color: vec4<f32>,
+    @location(4) custom_data: vec4<f32>,
+    // Layer of the material texture array
+    @location(5) @interpolate(flat) layer: u32,
+    // Scales the emissive color of the material
+    @location(6) emission: f32,
+    // Ambient occlusion baked into the vertices, 1 is not occluded
+    @location(7) occlusion: f32,
 }
 
 struct InstanceInput {
@@ -14,12 +22,19 @@ struct InstanceInput {
     @location(9) normal_matrix_0: vec3<f32>,
     @location(10) normal_matrix_1: vec3<f32>,
     @location(11) normal_matrix_2: vec3<f32>,
+
+    @location(12) color: vec4<f32>,
+    @location(13) custom_data: vec4<f32>,
+    @location(14) layer: u32,
+    @location(15) emission: f32,
 }
 
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) tex_coords: vec2<f32>,
     @location(2) normal: vec3<f32>,
+    @location(3) color: vec3<f32>,
+    @location(4) occlusion: f32,
 };
 
 
@@ -54,6 +69,11 @@ fn main(
     
     var out: VertexOutput;
     out.tex_coords = model.tex_coords;
+    out.color = instance.color * vec4<f32>(model.color, 1.0);
+    out.custom_data = instance.custom_data;
+    out.layer = instance.layer;
+    out.emission = instance.emission;
+    out.occlusion = model.occlusion;
     out.world_normal = normal_matrix * model.normal;
     var world_position: vec4<f32> = model_matrix * vec4<f32>(model.position, 1.0);
     out.world_position = world_position.xyz;
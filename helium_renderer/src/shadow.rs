@@ -0,0 +1,698 @@
+use std::{cmp::Ordering, num::NonZeroU64};
+
+use cgmath::{
+    ortho, perspective, Deg, EuclideanSpace, InnerSpace, Matrix4, Point3, SquareMatrix, Vector3,
+};
+use wgpu::{
+    include_wgsl, AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
+    BufferBinding, BufferBindingType, BufferDescriptor, BufferUsages, CommandEncoder,
+    CompareFunction, DepthBiasState, DepthStencilState, Device, Extent3d, Face, FilterMode,
+    FrontFace, IndexFormat, LoadOp, MultisampleState, Operations, PipelineCompilationOptions,
+    PipelineLayoutDescriptor, PolygonMode, PrimitiveState, PrimitiveTopology, Queue,
+    RenderPassDepthStencilAttachment, RenderPassDescriptor, RenderPipeline,
+    RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages,
+    StencilState, StoreOp, Texture, TextureDescriptor, TextureDimension, TextureFormat,
+    TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
+    VertexState,
+};
+
+#[allow(unused_imports)]
+use log::*;
+
+use crate::{
+    camera::Camera,
+    instance::InstanceRaw,
+    light::{Light, LightKind},
+    model::{model_vertex::ModelVertex, vertex::Vertex, Model},
+    resources::OPENGL_TO_WGPU_MATIX,
+};
+
+const SHADOW_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+
+// A point light draws its shadows into one layer for each side of a cube, the cascades of
+// a directional light use the same layers
+const POINT_LIGHT_FACES: u32 = 6;
+
+// Most cascades a directional light can split the view into
+const MAX_CASCADES: usize = 4;
+
+// How much the cascade splits lean towards logarithmic instead of uniform splits
+const CASCADE_SPLIT_LAMBDA: f32 = 0.75;
+
+// How the shader reads the layers of a shadow
+const SHADOW_KIND_FACES: u32 = 0;
+const SHADOW_KIND_CASCADES: u32 = 1;
+
+// Closest distance to a light that casts shadows
+const SHADOW_NEAR: f32 = 0.05;
+
+// Smallest shadow map a light can ask for
+const MIN_RESOLUTION: u32 = 16;
+
+/// Quality of the shadows of every light
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShadowSettings {
+    pub enabled: bool,
+    /// Width and height of each shadow map in pixels, lights can ask for less but not more
+    pub resolution: u32,
+    /// How far surfaces are moved along their normal before they are tested in world
+    /// units, larger values remove acne but detach the shadows from the objects
+    pub bias: f32,
+    /// Width of the square of samples that are averaged to soften the edges, 1 is hard
+    pub pcf_kernel: u32,
+    /// The most lights that cast shadows at once, when more lights cast shadows the ones
+    /// with the most influence near the camera are picked and the rest are unshadowed
+    pub max_shadow_lights: u32,
+    /// How far from the lights shadows are cast in world units
+    pub distance: f32,
+    /// Number of cascades the view is split into for directional lights, from 1 to 4
+    pub cascade_count: u32,
+    /// How far from the camera directional lights cast shadows in world units
+    pub cascade_distance: f32,
+    /// Part of each cascade that is blended into the next one to hide the seams
+    pub cascade_blend: f32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            resolution: 1024,
+            bias: 0.05,
+            pcf_kernel: 3,
+            max_shadow_lights: 4,
+            distance: 50.0,
+            cascade_count: 4,
+            cascade_distance: 100.0,
+            cascade_blend: 0.1,
+        }
+    }
+}
+
+/// Shadow settings of a single light, `None` uses the value of the `ShadowSettings`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LightShadow {
+    pub resolution: Option<u32>,
+    pub bias: Option<f32>,
+    pub pcf_kernel: Option<u32>,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ShadowsHeader {
+    count: u32,
+    _padding: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ShadowRaw {
+    view_proj: [[[f32; 4]; 4]; POINT_LIGHT_FACES as usize],
+    light_index: u32,
+    first_layer: u32,
+    pcf_kernel: u32,
+    bias: f32,
+    // Part of the layer that is used by lights with a lower resolution than the maps
+    uv_scale: f32,
+    texel_size: f32,
+    kind: u32,
+    cascade_count: u32,
+    // Depth along the view where each cascade ends
+    cascade_splits: [f32; MAX_CASCADES],
+    cascade_radii: [f32; MAX_CASCADES],
+    cascade_blend: f32,
+    _padding: [f32; 3],
+}
+
+// Cameras looking down each axis in the order the shader picks the faces
+fn face_view_projs(position: Vector3<f32>, far: f32) -> [Matrix4<f32>; 6] {
+    let eye = Point3::from_vec(position);
+    let proj = OPENGL_TO_WGPU_MATIX * perspective(Deg(90.0), 1.0, SHADOW_NEAR, far);
+
+    [
+        (Vector3::unit_x(), Vector3::unit_y()),
+        (-Vector3::unit_x(), Vector3::unit_y()),
+        (Vector3::unit_y(), Vector3::unit_z()),
+        (-Vector3::unit_y(), Vector3::unit_z()),
+        (Vector3::unit_z(), Vector3::unit_y()),
+        (-Vector3::unit_z(), Vector3::unit_y()),
+    ]
+    .map(|(direction, up)| proj * Matrix4::look_to_rh(eye, direction, up))
+}
+
+// Depths along the view where the cascades end, a mix of logarithmic splits that keep
+// the near cascades sharp and uniform splits that keep the far ones from being too thin
+fn cascade_splits(near: f32, far: f32, count: usize) -> [f32; MAX_CASCADES] {
+    let mut splits = [far; MAX_CASCADES];
+
+    for (index, split) in splits.iter_mut().enumerate().take(count) {
+        let fraction = (index + 1) as f32 / count as f32;
+        let logarithmic = near * (far / near).powf(fraction);
+        let uniform = near + (far - near) * fraction;
+        *split = CASCADE_SPLIT_LAMBDA * logarithmic + (1.0 - CASCADE_SPLIT_LAMBDA) * uniform;
+    }
+
+    splits
+}
+
+// Fits an orthographic camera looking down the light around a slice of the view
+//
+// Returns the camera and the radius of the cascade
+fn fit_cascade(
+    camera: &Camera,
+    direction: Vector3<f32>,
+    slice: (f32, f32),
+    resolution: u32,
+    caster_distance: f32,
+) -> (Matrix4<f32>, f32) {
+    let forward = camera.target.normalize();
+    let right = forward.cross(camera.up).normalize();
+    let up = right.cross(forward);
+    let tan_half_fovy = (camera.fovy.to_radians() / 2.0).tan();
+
+    let corners = [slice.0, slice.1].map(|depth| {
+        let half_height = depth * tan_half_fovy;
+        let half_width = half_height * camera.aspect;
+        let center = camera.eye.to_vec() + forward * depth;
+
+        [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)]
+            .map(|(x, y)| center + right * half_width * x + up * half_height * y)
+    });
+    let corners = corners.as_flattened();
+
+    // A bounding sphere keeps the size of the cascade the same when the camera turns
+    let center = corners
+        .iter()
+        .fold(Vector3::new(0.0, 0.0, 0.0), |sum, corner| sum + corner)
+        / corners.len() as f32;
+    let radius = corners
+        .iter()
+        .map(|corner| (corner - center).magnitude())
+        .fold(0.0, f32::max)
+        .max(1e-3);
+
+    let light_up = if direction.y.abs() > 0.99 {
+        Vector3::unit_z()
+    } else {
+        Vector3::unit_y()
+    };
+
+    // Snapping the center to the texels keeps the edges from shimmering as the camera moves
+    let texel = 2.0 * radius / resolution as f32;
+    let light_view = Matrix4::look_to_rh(Point3::origin(), direction, light_up);
+    let light_center = light_view * center.extend(1.0);
+    let snapped = Vector3::new(
+        (light_center.x / texel).floor() * texel,
+        (light_center.y / texel).floor() * texel,
+        light_center.z,
+    );
+    let center = light_view
+        .invert()
+        .map(|inverse| (inverse * snapped.extend(1.0)).truncate())
+        .unwrap_or(center);
+
+    // Objects behind the cascade up to the shadow distance still cast shadows into it
+    let eye = Point3::from_vec(center - direction * (radius + caster_distance));
+    let view = Matrix4::look_to_rh(eye, direction, light_up);
+    let proj = OPENGL_TO_WGPU_MATIX
+        * ortho(
+            -radius,
+            radius,
+            -radius,
+            radius,
+            0.0,
+            2.0 * radius + caster_distance,
+        );
+
+    (proj * view, radius)
+}
+
+// How much a light is likely to be noticed from the camera, directional lights reach
+// everywhere so they are not divided by the distance
+fn influence(light: &Light, eye: Point3<f32>) -> f32 {
+    let (r, g, b) = light.get_color();
+    let brightness = light.get_intensity() * r.max(g).max(b);
+
+    match light.get_kind() {
+        LightKind::Point => {
+            brightness / (light.get_position() - eye.to_vec()).magnitude2().max(1.0)
+        }
+        LightKind::Directional => brightness,
+    }
+}
+
+/// Depth maps drawn from the lights that cast shadows, every light uses six layers of
+/// one texture array for the faces of a point light or the cascades of a directional light
+pub(crate) struct ShadowMaps {
+    settings: ShadowSettings,
+    // Lights that fit in the texture array
+    max_lights: u32,
+    pipeline: RenderPipeline,
+    face_buffer: Buffer,
+    // Distance between the cameras of the faces in the face buffer
+    face_stride: u64,
+    face_bind_group: BindGroup,
+    #[allow(unused)]
+    texture: Texture,
+    array_view: TextureView,
+    layer_views: Vec<TextureView>,
+    sampler: Sampler,
+    shadow_buffer: Buffer,
+    // Number of lights that did not fit in the budget the last time they were drawn
+    skipped: usize,
+}
+
+impl ShadowMaps {
+    pub fn new(device: &Device, settings: ShadowSettings) -> Self {
+        let limits = device.limits();
+        let max_lights = settings
+            .max_shadow_lights
+            .min(limits.max_texture_array_layers / POINT_LIGHT_FACES);
+        let resolution = settings
+            .resolution
+            .clamp(MIN_RESOLUTION, limits.max_texture_dimension_2d);
+        let settings = ShadowSettings {
+            resolution,
+            max_shadow_lights: max_lights,
+            ..settings
+        };
+
+        let face_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Shadow Face Bind Group Layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Shadow Render Pipeline Layout"),
+            bind_group_layouts: &[&face_layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("./shaders/shadow_shader.wgsl"));
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Shadow Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[ModelVertex::desc(), InstanceRaw::desc()],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: None,
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                cull_mode: Some(Face::Back),
+                polygon_mode: PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: SHADOW_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Less,
+                stencil: StencilState::default(),
+                bias: DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let layer_count = max_lights.max(1) * POINT_LIGHT_FACES;
+
+        // Every face has its own camera at an offset that the uniform can be bound at
+        let face_size = std::mem::size_of::<[[f32; 4]; 4]>() as u64;
+        let face_stride =
+            face_size.next_multiple_of(limits.min_uniform_buffer_offset_alignment as u64);
+        let face_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Shadow Face Buffer"),
+            size: face_stride * layer_count as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let face_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Shadow Face Bind Group"),
+            layout: &face_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::Buffer(BufferBinding {
+                    buffer: &face_buffer,
+                    offset: 0,
+                    size: NonZeroU64::new(face_size),
+                }),
+            }],
+        });
+
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Shadow Map Texture"),
+            size: Extent3d {
+                width: resolution,
+                height: resolution,
+                depth_or_array_layers: layer_count,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: SHADOW_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let array_view = texture.create_view(&TextureViewDescriptor {
+            label: Some("Shadow Map Array View"),
+            dimension: Some(TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let layer_views = (0..layer_count)
+            .map(|layer| {
+                texture.create_view(&TextureViewDescriptor {
+                    label: Some("Shadow Map Layer View"),
+                    dimension: Some(TextureViewDimension::D2),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        // Linear filtering compares the four nearest texels so the edges are smoothed
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Shadow Map Sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Nearest,
+            compare: Some(CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        let shadow_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Shadow Buffer"),
+            size: (std::mem::size_of::<ShadowsHeader>()
+                + std::mem::size_of::<ShadowRaw>() * max_lights.max(1) as usize)
+                as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            settings,
+            max_lights,
+            pipeline,
+            face_buffer,
+            face_stride,
+            face_bind_group,
+            texture,
+            array_view,
+            layer_views,
+            sampler,
+            shadow_buffer,
+            skipped: 0,
+        }
+    }
+
+    /// The settings after they were clamped to the limits of the device
+    pub fn get_settings(&self) -> ShadowSettings {
+        self.settings
+    }
+
+    /// Layout entries of the shadow maps in the lights bind group
+    pub fn layout_entries(first_binding: u32) -> [BindGroupLayoutEntry; 3] {
+        [
+            BindGroupLayoutEntry {
+                binding: first_binding,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: TextureViewDimension::D2Array,
+                    sample_type: TextureSampleType::Depth,
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: first_binding + 1,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Comparison),
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: first_binding + 2,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ]
+    }
+
+    /// Bind group entries of the shadow maps matching `layout_entries`
+    pub fn bind_group_entries(&self, first_binding: u32) -> [BindGroupEntry<'_>; 3] {
+        [
+            BindGroupEntry {
+                binding: first_binding,
+                resource: BindingResource::TextureView(&self.array_view),
+            },
+            BindGroupEntry {
+                binding: first_binding + 1,
+                resource: BindingResource::Sampler(&self.sampler),
+            },
+            BindGroupEntry {
+                binding: first_binding + 2,
+                resource: self.shadow_buffer.as_entire_binding(),
+            },
+        ]
+    }
+
+    /// Picks the lights that cast shadows this frame and draws the depth of the models
+    /// from each of them
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - The queue to write the cameras of the lights with
+    /// * `encoder` - The encoder to record the passes in
+    /// * `lights` - Every light in the scene in the order of the light buffer
+    /// * `camera` - The camera the scene is drawn with, lights near it are picked first and
+    ///   the cascades of directional lights are fit to it
+    /// * `models` - The models that cast shadows
+    /// * `scattered` - Models that are drawn from their own instance buffers and skipped
+    /// * `instance_buffer` - The instances of the models
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &mut self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        lights: &[Light],
+        camera: &Camera,
+        models: &[Option<Model>],
+        scattered: &[usize],
+        instance_buffer: &Buffer,
+    ) {
+        let mut casters = lights
+            .iter()
+            .enumerate()
+            .filter(|_| self.settings.enabled)
+            .filter_map(|(index, light)| light.get_shadows().map(|shadow| (index, light, shadow)))
+            .collect::<Vec<_>>();
+
+        casters.sort_by(|(_, a, _), (_, b, _)| {
+            influence(b, camera.eye)
+                .partial_cmp(&influence(a, camera.eye))
+                .unwrap_or(Ordering::Equal)
+        });
+
+        // Lights over the budget are drawn without shadows instead of failing
+        let skipped = casters.len().saturating_sub(self.max_lights as usize);
+        if skipped != self.skipped {
+            if skipped > 0 {
+                warn!(
+                    "{} shadow casting lights are over the budget of {}, they are drawn without shadows",
+                    skipped, self.max_lights
+                );
+            }
+            self.skipped = skipped;
+        }
+        casters.truncate(self.max_lights as usize);
+
+        let cascade_count = (self.settings.cascade_count as usize).clamp(1, MAX_CASCADES);
+        let splits = cascade_splits(
+            camera.znear,
+            camera.zfar.min(self.settings.cascade_distance),
+            cascade_count,
+        );
+
+        let mut shadows = Vec::with_capacity(casters.len());
+        for (slot, (light_index, light, shadow)) in casters.iter().enumerate() {
+            let resolution = shadow
+                .resolution
+                .unwrap_or(self.settings.resolution)
+                .clamp(MIN_RESOLUTION, self.settings.resolution);
+            let first_layer = slot as u32 * POINT_LIGHT_FACES;
+
+            let mut raw = ShadowRaw {
+                view_proj: [Matrix4::identity().into(); POINT_LIGHT_FACES as usize],
+                light_index: *light_index as u32,
+                first_layer,
+                pcf_kernel: shadow.pcf_kernel.unwrap_or(self.settings.pcf_kernel).max(1),
+                bias: shadow.bias.unwrap_or(self.settings.bias),
+                uv_scale: resolution as f32 / self.settings.resolution as f32,
+                texel_size: 1.0 / self.settings.resolution as f32,
+                kind: SHADOW_KIND_FACES,
+                cascade_count: 0,
+                cascade_splits: splits,
+                cascade_radii: [1.0; MAX_CASCADES],
+                cascade_blend: self.settings.cascade_blend.clamp(0.0, 1.0),
+                _padding: [0.0; 3],
+            };
+
+            let view_projs = match light.get_kind() {
+                LightKind::Point => {
+                    face_view_projs(light.get_position(), self.settings.distance).to_vec()
+                }
+                LightKind::Directional => {
+                    raw.kind = SHADOW_KIND_CASCADES;
+                    raw.cascade_count = cascade_count as u32;
+
+                    (0..cascade_count)
+                        .map(|cascade| {
+                            let near = if cascade == 0 {
+                                camera.znear
+                            } else {
+                                splits[cascade - 1]
+                            };
+                            let (view_proj, radius) = fit_cascade(
+                                camera,
+                                light.get_direction(),
+                                (near, splits[cascade]),
+                                resolution,
+                                self.settings.distance,
+                            );
+                            raw.cascade_radii[cascade] = radius;
+                            view_proj
+                        })
+                        .collect()
+                }
+            };
+
+            for (index, view_proj) in view_projs.iter().enumerate() {
+                raw.view_proj[index] = (*view_proj).into();
+                self.draw_layer(
+                    queue,
+                    encoder,
+                    first_layer + index as u32,
+                    resolution,
+                    view_proj,
+                    models,
+                    scattered,
+                    instance_buffer,
+                );
+            }
+
+            shadows.push(raw);
+        }
+
+        let header = ShadowsHeader {
+            count: shadows.len() as u32,
+            _padding: [0; 3],
+        };
+        queue.write_buffer(&self.shadow_buffer, 0, bytemuck::bytes_of(&header));
+        if !shadows.is_empty() {
+            queue.write_buffer(
+                &self.shadow_buffer,
+                std::mem::size_of::<ShadowsHeader>() as u64,
+                bytemuck::cast_slice(&shadows),
+            );
+        }
+    }
+
+    // Draws the depth of the models into one layer of the shadow maps
+    #[allow(clippy::too_many_arguments)]
+    fn draw_layer(
+        &self,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        layer: u32,
+        resolution: u32,
+        view_proj: &Matrix4<f32>,
+        models: &[Option<Model>],
+        scattered: &[usize],
+        instance_buffer: &Buffer,
+    ) {
+        let matrix: [[f32; 4]; 4] = (*view_proj).into();
+        queue.write_buffer(
+            &self.face_buffer,
+            layer as u64 * self.face_stride,
+            bytemuck::cast_slice(&[matrix]),
+        );
+
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Shadow Render Pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: &self.layer_views[layer as usize],
+                depth_ops: Some(Operations {
+                    load: LoadOp::Clear(1.0),
+                    store: StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        // Lights with a lower resolution only draw into the corner of the layer
+        render_pass.set_viewport(0.0, 0.0, resolution as f32, resolution as f32, 0.0, 1.0);
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(
+            0,
+            &self.face_bind_group,
+            &[(layer as u64 * self.face_stride) as u32],
+        );
+        render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+
+        for (model_index, model) in models.iter().enumerate() {
+            let Some(model) = model.as_ref() else {
+                continue;
+            };
+
+            if scattered.contains(&model_index) {
+                continue;
+            }
+
+            for mesh in model.get_meshes().iter() {
+                // Lines and points cast no shadows
+                if !mesh.get_topology().is_triangles() {
+                    continue;
+                }
+
+                render_pass.set_vertex_buffer(0, mesh.get_vertex_buffer().slice(..));
+                render_pass
+                    .set_index_buffer(mesh.get_index_buffer().slice(..), IndexFormat::Uint32);
+                render_pass.draw_indexed(0..mesh.get_num_elements(), 0, mesh.get_instances());
+            }
+        }
+    }
+}
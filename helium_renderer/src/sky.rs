@@ -0,0 +1,295 @@
+use cgmath::{InnerSpace, Matrix4, Point3, SquareMatrix, Vector3};
+use wgpu::{
+    include_wgsl,
+    util::{BufferInitDescriptor, DeviceExt},
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType, BufferUsages, ColorTargetState,
+    ColorWrites, CompareFunction, DepthBiasState, DepthStencilState, Device, FragmentState,
+    MultisampleState, PipelineCompilationOptions, PipelineLayoutDescriptor, PrimitiveState,
+    PrimitiveTopology, Queue, RenderPass, RenderPipeline, RenderPipelineDescriptor, ShaderStages,
+    StencilState, TextureFormat, VertexState,
+};
+
+use crate::{camera::Camera, helium_texture};
+
+// Colors of the sky in linear space at noon, at night, and at sunset
+const DAY_ZENITH: Vector3<f32> = Vector3::new(0.12, 0.3, 0.75);
+const DAY_HORIZON: Vector3<f32> = Vector3::new(0.55, 0.7, 0.9);
+const NIGHT_ZENITH: Vector3<f32> = Vector3::new(0.002, 0.004, 0.012);
+const NIGHT_HORIZON: Vector3<f32> = Vector3::new(0.01, 0.015, 0.03);
+const SUNSET_HORIZON: Vector3<f32> = Vector3::new(0.9, 0.45, 0.2);
+
+// Color of the sun high in the sky and at the horizon
+const NOON_SUN: Vector3<f32> = Vector3::new(1.0, 0.95, 0.88);
+const SUNSET_SUN: Vector3<f32> = Vector3::new(1.0, 0.35, 0.1);
+
+// How much of the horizon color the ground below the horizon reflects
+const GROUND_REFLECTANCE: f32 = 0.35;
+
+fn smoothstep(edge_0: f32, edge_1: f32, x: f32) -> f32 {
+    let t = ((x - edge_0) / (edge_1 - edge_0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: Vector3<f32>, b: Vector3<f32>, t: f32) -> Vector3<f32> {
+    a + (b - a) * t
+}
+
+/// A sky that is computed from the position of the sun and drawn behind the scene, with a
+/// gradient from the horizon, a glowing sun disk, and stars at night
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProceduralSky {
+    sun_direction: Vector3<f32>,
+    sun_size: f32,
+    intensity: f32,
+    star_brightness: f32,
+}
+
+impl Default for ProceduralSky {
+    fn default() -> Self {
+        Self {
+            sun_direction: Vector3::new(0.0, 1.0, 0.0),
+            sun_size: 0.5,
+            intensity: 1.0,
+            star_brightness: 1.0,
+        }
+    }
+}
+
+impl ProceduralSky {
+    /// Sets the direction from the camera towards the sun, the sky is dark while it points
+    /// below the horizon
+    pub fn with_sun_direction(mut self, direction: Vector3<f32>) -> Self {
+        self.set_sun_direction(direction);
+        self
+    }
+
+    /// Sets the radius of the sun disk in degrees
+    pub fn with_sun_size(mut self, degrees: f32) -> Self {
+        self.sun_size = degrees.max(0.0);
+        self
+    }
+
+    /// Sets the brightness the whole sky is multiplied by
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity.max(0.0);
+        self
+    }
+
+    /// Sets the brightness of the stars at night, 0 hides them
+    pub fn with_star_brightness(mut self, brightness: f32) -> Self {
+        self.star_brightness = brightness.max(0.0);
+        self
+    }
+
+    pub fn set_sun_direction(&mut self, direction: Vector3<f32>) {
+        if direction.magnitude2() > f32::EPSILON {
+            self.sun_direction = direction.normalize();
+        }
+    }
+
+    pub fn get_sun_direction(&self) -> Vector3<f32> {
+        self.sun_direction
+    }
+
+    pub fn get_sun_size(&self) -> f32 {
+        self.sun_size
+    }
+
+    pub fn get_intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn get_star_brightness(&self) -> f32 {
+        self.star_brightness
+    }
+
+    /// How much sunlight reaches the ground, from 0 at night to 1 once the sun is well
+    /// above the horizon
+    pub fn get_daylight(&self) -> f32 {
+        smoothstep(-0.05, 0.15, self.sun_direction.y)
+    }
+
+    /// The color of the sun, white at noon and red near the horizon
+    pub fn get_sun_color(&self) -> (f32, f32, f32) {
+        let color = lerp(
+            SUNSET_SUN,
+            NOON_SUN,
+            smoothstep(0.0, 0.4, self.sun_direction.y),
+        );
+        (color.x, color.y, color.z)
+    }
+
+    fn uniform(&self, inverse_view_proj: Matrix4<f32>) -> SkyUniform {
+        let elevation = self.sun_direction.y;
+        let day = smoothstep(-0.15, 0.25, elevation);
+        // The horizon glows while the sun is near it
+        let sunset = (1.0 - (elevation - 0.02).abs() / 0.25).clamp(0.0, 1.0);
+
+        let zenith = lerp(NIGHT_ZENITH, DAY_ZENITH, day);
+        let horizon = lerp(
+            lerp(NIGHT_HORIZON, DAY_HORIZON, day),
+            SUNSET_HORIZON,
+            sunset * 0.7,
+        );
+        let ground = horizon * GROUND_REFLECTANCE;
+        let (r, g, b) = self.get_sun_color();
+        let sun = Vector3::new(r, g, b) * smoothstep(-0.1, 0.0, elevation);
+        let stars = self.star_brightness * (1.0 - smoothstep(-0.2, 0.05, elevation));
+
+        SkyUniform {
+            inverse_view_proj: inverse_view_proj.into(),
+            sun_direction: self
+                .sun_direction
+                .extend(self.sun_size.to_radians().cos())
+                .into(),
+            sun_color: sun.extend(stars).into(),
+            zenith: zenith.extend(self.intensity).into(),
+            horizon: horizon.extend(1.0).into(),
+            ground: ground.extend(1.0).into(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct SkyUniform {
+    inverse_view_proj: [[f32; 4]; 4],
+    sun_direction: [f32; 4],
+    sun_color: [f32; 4],
+    zenith: [f32; 4],
+    horizon: [f32; 4],
+    ground: [f32; 4],
+}
+
+/// Draws the procedural sky as the background of the scene
+pub struct SkyRenderer {
+    pipeline: RenderPipeline,
+    buffer: Buffer,
+    bind_group: BindGroup,
+    sky: Option<ProceduralSky>,
+}
+
+impl SkyRenderer {
+    pub fn new(device: &Device, format: TextureFormat) -> Self {
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Sky Bind Group Layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Sky Render Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("./shaders/sky_shader.wgsl"));
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Sky Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            // The sky is drawn first and the scene is drawn over it
+            depth_stencil: Some(DepthStencilState {
+                format: helium_texture::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::Always,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Sky Uniform Buffer"),
+            contents: bytemuck::cast_slice(
+                &[ProceduralSky::default().uniform(Matrix4::identity())],
+            ),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Sky Bind Group"),
+            layout: &layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            pipeline,
+            buffer,
+            bind_group,
+            sky: None,
+        }
+    }
+
+    pub fn set_sky(&mut self, sky: Option<ProceduralSky>) {
+        self.sky = sky;
+    }
+
+    pub fn get_sky(&self) -> Option<ProceduralSky> {
+        self.sky
+    }
+
+    /// Draws the sky over the whole screen, nothing is drawn when there is no sky
+    pub(crate) fn draw(&self, queue: &Queue, render_pass: &mut RenderPass, camera: &Camera) {
+        let Some(sky) = self.sky.as_ref() else {
+            return;
+        };
+
+        // The sky is infinitely far away so only the direction of the camera matters
+        let view_proj = Camera::build_view_projection_matrix_parts(
+            Point3::new(0.0, 0.0, 0.0),
+            camera.target,
+            camera.up,
+            camera.aspect,
+            camera.fovy,
+            camera.znear,
+            camera.zfar,
+        );
+        let Some(inverse_view_proj) = view_proj.invert() else {
+            return;
+        };
+        queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::cast_slice(&[sky.uniform(inverse_view_proj)]),
+        );
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,334 @@
+use std::ops::Range;
+
+use wgpu::{
+    include_wgsl,
+    util::{BufferInitDescriptor, DeviceExt},
+    BlendState, Buffer, BufferAddress, BufferUsages, ColorTargetState, ColorWrites, Device,
+    FragmentState, FrontFace, MultisampleState, PipelineCompilationOptions,
+    PipelineLayoutDescriptor, PolygonMode, PrimitiveState, PrimitiveTopology, Queue, RenderPass,
+    RenderPipeline, RenderPipelineDescriptor, TextureFormat, VertexAttribute, VertexBufferLayout,
+    VertexFormat, VertexState, VertexStepMode,
+};
+
+use crate::{helium_texture::HeliumTexture, model::vertex::Vertex, ui::UiLayout};
+
+// Number of vertices used to draw a single sprite
+const SPRITE_VERTICES: usize = 6;
+
+/// Handle to a texture that has been loaded for drawing sprites
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SpriteHandle(pub usize);
+
+/// The part of a sprite texture that a handle draws, sprites packed into an atlas share the
+/// texture
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SpriteRegion {
+    /// Index of the texture in the sprite textures
+    pub texture: usize,
+    /// Top left and bottom right of the region in texture coordinates
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+    /// The width and height of the region in pixels
+    pub dimensions: (u32, u32),
+}
+
+impl SpriteRegion {
+    /// A region that covers the whole texture
+    pub fn full(texture: usize, dimensions: (u32, u32)) -> Self {
+        Self {
+            texture,
+            uv_min: [0.0, 0.0],
+            uv_max: [1.0, 1.0],
+            dimensions,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SpriteVertex {
+    position: [f32; 2],
+    tex_coords: [f32; 2],
+    opacity: f32,
+}
+
+impl Vertex for SpriteVertex {
+    fn desc() -> VertexBufferLayout<'static> {
+        use std::mem;
+        VertexBufferLayout {
+            array_stride: mem::size_of::<SpriteVertex>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &[
+                // Position
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: VertexFormat::Float32x2,
+                },
+                // UV coordinates
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 2]>() as BufferAddress,
+                    shader_location: 1,
+                    format: VertexFormat::Float32x2,
+                },
+                // Opacity
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 2,
+                    format: VertexFormat::Float32,
+                },
+            ],
+        }
+    }
+}
+
+/// A textured image drawn in screen space in the overlay
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OverlaySprite {
+    pub texture: SpriteHandle,
+    /// Position of the top left of the sprite in pixels before rotating
+    pub position: (f32, f32),
+    /// Width and height of the sprite in pixels before scaling
+    pub size: (f32, f32),
+    pub scale: f32,
+    /// Rotation around the center of the sprite in radians
+    pub rotation: f32,
+    pub opacity: f32,
+    /// Places the sprite relative to the screen instead of the raw position
+    pub layout: Option<UiLayout>,
+}
+
+impl OverlaySprite {
+    pub fn new(texture: SpriteHandle, position: (f32, f32), size: (f32, f32)) -> Self {
+        Self {
+            texture,
+            position,
+            size,
+            scale: 1.0,
+            rotation: 0.0,
+            opacity: 1.0,
+            layout: None,
+        }
+    }
+
+    pub fn with_layout(mut self, layout: Option<UiLayout>) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    // Creates the two rotated triangles of the sprite in normalized device coordinates
+    fn vertices(
+        &self,
+        region: &SpriteRegion,
+        screen_size: (f32, f32),
+    ) -> [SpriteVertex; SPRITE_VERTICES] {
+        let size = (self.size.0 * self.scale, self.size.1 * self.scale);
+        let (x, y) = match self.layout {
+            Some(layout) => layout.resolve_rect(screen_size, size),
+            None => self.position,
+        };
+
+        let center = (x + size.0 / 2.0, y + size.1 / 2.0);
+        let (sin, cos) = self.rotation.sin_cos();
+
+        let vertex = |corner: (f32, f32), [u, v]: [f32; 2]| {
+            // Rotate in pixels so the sprite is not stretched by the aspect ratio
+            let offset = (corner.0 * size.0 / 2.0, corner.1 * size.1 / 2.0);
+            let rotated = (
+                center.0 + offset.0 * cos - offset.1 * sin,
+                center.1 + offset.0 * sin + offset.1 * cos,
+            );
+
+            SpriteVertex {
+                position: [
+                    rotated.0 / screen_size.0 * 2.0 - 1.0,
+                    1.0 - rotated.1 / screen_size.1 * 2.0,
+                ],
+                tex_coords: [
+                    region.uv_min[0] + (region.uv_max[0] - region.uv_min[0]) * u,
+                    region.uv_min[1] + (region.uv_max[1] - region.uv_min[1]) * v,
+                ],
+                opacity: self.opacity,
+            }
+        };
+
+        let top_left = vertex((-1.0, -1.0), [0.0, 0.0]);
+        let bottom_left = vertex((-1.0, 1.0), [0.0, 1.0]);
+        let bottom_right = vertex((1.0, 1.0), [1.0, 1.0]);
+        let top_right = vertex((1.0, -1.0), [1.0, 0.0]);
+
+        [
+            top_left,
+            bottom_left,
+            bottom_right,
+            top_left,
+            bottom_right,
+            top_right,
+        ]
+    }
+}
+
+/// Draws the overlay sprites with one draw call for each run of sprites that share a texture
+pub struct SpriteRenderer {
+    pipeline: RenderPipeline,
+    vertex_buffer: Buffer,
+    vertex_capacity: usize,
+    // The index of the texture and the vertices drawn with it
+    draws: Vec<(usize, Range<u32>)>,
+}
+
+impl SpriteRenderer {
+    pub fn new(device: &Device, format: TextureFormat) -> Self {
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Sprite Render Pipeline Layout"),
+            bind_group_layouts: &[&HeliumTexture::get_layout(device)],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("./shaders/sprite_shader.wgsl"));
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Sprite Render Pipeline"),
+            layout: Some(&layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[SpriteVertex::desc()],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let vertex_capacity = SPRITE_VERTICES;
+        let vertex_buffer = Self::create_vertex_buffer(device, vertex_capacity);
+
+        Self {
+            pipeline,
+            vertex_buffer,
+            vertex_capacity,
+            draws: Vec::new(),
+        }
+    }
+
+    fn create_vertex_buffer(device: &Device, capacity: usize) -> Buffer {
+        device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Sprite vertex buffer"),
+            contents: bytemuck::cast_slice(&vec![
+                SpriteVertex {
+                    position: [0.0; 2],
+                    tex_coords: [0.0; 2],
+                    opacity: 0.0,
+                };
+                capacity
+            ]),
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        })
+    }
+
+    /// Writes the vertices of the sprites to the gpu, growing the buffer if needed
+    ///
+    /// # Arguments
+    ///
+    /// * `sprites` - The sprites to draw in order from back to front
+    /// * `regions` - The regions of the sprite textures that the handles point to
+    /// * `screen_size` - The width and height of the screen in pixels
+    pub(crate) fn prepare<'a, I>(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        sprites: I,
+        regions: &[SpriteRegion],
+        screen_size: (f32, f32),
+    ) where
+        I: IntoIterator<Item = &'a OverlaySprite>,
+    {
+        let mut vertices = Vec::new();
+        self.draws.clear();
+
+        for sprite in sprites {
+            let Some(region) = regions.get(sprite.texture.0) else {
+                continue;
+            };
+
+            let start = vertices.len() as u32;
+            vertices.extend_from_slice(&sprite.vertices(region, screen_size));
+            let end = vertices.len() as u32;
+
+            // Sprites from the same atlas are drawn together
+            match self.draws.last_mut() {
+                Some((texture, range)) if *texture == region.texture => range.end = end,
+                _ => self.draws.push((region.texture, start..end)),
+            }
+        }
+
+        if vertices.len() > self.vertex_capacity {
+            self.vertex_capacity = vertices.len().next_power_of_two();
+            self.vertex_buffer = Self::create_vertex_buffer(device, self.vertex_capacity);
+        }
+
+        if !vertices.is_empty() {
+            queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+        }
+    }
+
+    /// Draws the prepared sprites with their textures
+    pub fn draw(&self, render_pass: &mut RenderPass, textures: &[HeliumTexture]) {
+        if self.draws.is_empty() {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+
+        for (texture, vertices) in self.draws.iter() {
+            if let Some(bind_group) = textures
+                .get(*texture)
+                .and_then(|texture| texture.get_bind_group())
+            {
+                render_pass.set_bind_group(0, bind_group, &[]);
+                render_pass.draw(vertices.clone(), 0..1);
+            }
+        }
+    }
+}
@@ -0,0 +1,589 @@
+use std::collections::HashMap;
+
+use wgpu_text::glyph_brush::{
+    ab_glyph::{Font, FontArc, PxScale, ScaleFont},
+    BuiltInLineBreaker, FontId, GlyphPositioner, Layout, Section, SectionGeometry, Text,
+};
+
+use crate::{
+    color::{LinearRgba, Srgba},
+    sprite::{OverlaySprite, SpriteHandle, SpriteRegion},
+    text_layout::{BoundedText, TextBounds},
+    ui::UiLayout,
+};
+
+// Size in pixels of text that does not specify a size
+pub const DEFAULT_TEXT_SIZE: f32 = 16.0;
+
+// Directions to offset the outline copies of the text in
+pub(crate) const OUTLINE_DIRECTIONS: [(f32, f32); 8] = [
+    (-1.0, -1.0),
+    (0.0, -1.0),
+    (1.0, -1.0),
+    (-1.0, 0.0),
+    (1.0, 0.0),
+    (-1.0, 1.0),
+    (0.0, 1.0),
+    (1.0, 1.0),
+];
+
+/// Handle to a font that has been registered with the renderer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FontHandle(pub usize);
+
+impl From<FontHandle> for FontId {
+    fn from(value: FontHandle) -> Self {
+        FontId(value.0)
+    }
+}
+
+/// The fonts of the renderer and the fonts each of them falls back to for characters it
+/// does not have, like a latin font falling back to a japanese one. The icons that rich
+/// text can show inline are looked up here too
+pub(crate) struct FontSet<'a> {
+    pub fonts: &'a [FontArc],
+    pub fallbacks: &'a HashMap<FontHandle, Vec<FontHandle>>,
+    pub icons: &'a HashMap<String, SpriteHandle>,
+    pub sprite_regions: &'a [SpriteRegion],
+}
+
+impl FontSet<'_> {
+    // The first font of the chain that has a glyph for the character
+    fn font_for(&self, character: char, font: FontHandle, chain: &[FontHandle]) -> FontHandle {
+        std::iter::once(&font)
+            .chain(chain)
+            .copied()
+            .find(|handle| {
+                self.fonts
+                    .get(handle.0)
+                    .is_some_and(|f| f.glyph_id(character).0 != 0)
+            })
+            .unwrap_or(font)
+    }
+
+    /// Splits text into runs that are each drawn with one font of the fallback chain
+    pub fn runs<'t>(&self, text: &'t str, font: FontHandle) -> Vec<(&'t str, FontHandle)> {
+        let Some(chain) = self.fallbacks.get(&font).filter(|chain| !chain.is_empty()) else {
+            return vec![(text, font)];
+        };
+
+        let mut runs = Vec::new();
+        let (mut start, mut current) = (0, font);
+        for (index, character) in text.char_indices() {
+            // Spaces stay with the run they are in so the runs are not split at every word
+            let wanted = if character.is_whitespace() {
+                current
+            } else {
+                self.font_for(character, font, chain)
+            };
+
+            if wanted != current {
+                if index > start {
+                    runs.push((&text[start..index], current));
+                }
+                (start, current) = (index, wanted);
+            }
+        }
+        runs.push((&text[start..], current));
+
+        runs
+    }
+
+    /// How far the pen moves after a character of a font
+    pub fn advance(&self, font: FontHandle, character: char, size: f32) -> f32 {
+        self.fonts.get(font.0).map_or(0.0, |face| {
+            face.as_scaled(size).h_advance(face.glyph_id(character))
+        })
+    }
+
+    /// The distance between the lines of text of a font
+    pub fn line_height(&self, font: FontHandle, size: f32) -> f32 {
+        self.fonts.get(font.0).map_or(size, |face| {
+            let face = face.as_scaled(size);
+            face.height() + face.line_gap()
+        })
+    }
+
+    /// The sprite of an icon and its width when it is as tall as the text
+    pub fn icon(&self, name: &str, size: f32) -> Option<(SpriteHandle, f32)> {
+        let handle = *self.icons.get(name)?;
+        let (width, height) = self.sprite_regions.get(handle.0)?.dimensions;
+        Some((handle, size * width as f32 / height.max(1) as f32))
+    }
+
+    /// An invisible character and the scale that stretches it to take up the space of an
+    /// icon. The no-break space keeps the icon on the line of the word next to it
+    pub fn icon_placeholder(
+        &self,
+        font: FontHandle,
+        size: f32,
+        width: f32,
+    ) -> Option<(&'static str, PxScale)> {
+        let face = self.fonts.get(font.0)?;
+        let (placeholder, glyph) = match face.glyph_id('\u{a0}') {
+            glyph if glyph.0 != 0 => ("\u{a0}", glyph),
+            _ => (" ", face.glyph_id(' ')),
+        };
+
+        let advance = face.as_scaled(size).h_advance(glyph);
+        if advance <= 0.0 {
+            return None;
+        }
+
+        Some((
+            placeholder,
+            PxScale {
+                x: size * width / advance,
+                y: size,
+            },
+        ))
+    }
+}
+
+/// A piece of rich text with the look its markup gave it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum MarkupSpan<'a> {
+    Text {
+        text: &'a str,
+        /// The color of the text, `None` uses the color of the style
+        color: Option<[f32; 4]>,
+        bold: bool,
+    },
+    /// A sprite drawn inline with the text by the name it was registered with
+    Icon(&'a str),
+}
+
+/// Splits text with markup into the spans it draws. `[b]` and `[/b]` draw text with the
+/// bold font, `[color=#ff8800]` and `[/color]` color it, `[icon=name]` draws a sprite
+/// inline, and `[[` draws a bracket. Anything else in brackets is drawn as it is written
+pub(crate) fn parse_markup(text: &str) -> Vec<MarkupSpan<'_>> {
+    let mut spans = Vec::new();
+    // Tags can be nested, closing a color goes back to the one before it
+    let mut colors: Vec<[f32; 4]> = Vec::new();
+    let mut bold = 0_usize;
+
+    fn push_text<'a>(
+        spans: &mut Vec<MarkupSpan<'a>>,
+        text: &'a str,
+        colors: &[[f32; 4]],
+        bold: bool,
+    ) {
+        if !text.is_empty() {
+            spans.push(MarkupSpan::Text {
+                text,
+                color: colors.last().copied(),
+                bold,
+            });
+        }
+    }
+
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        push_text(&mut spans, &rest[..open], &colors, bold > 0);
+        let after = &rest[open + 1..];
+
+        if let Some(escaped) = after.strip_prefix('[') {
+            push_text(&mut spans, &rest[open..=open], &colors, bold > 0);
+            rest = escaped;
+            continue;
+        }
+
+        let Some(close) = after.find(']') else {
+            push_text(&mut spans, &rest[open..], &colors, bold > 0);
+            return spans;
+        };
+
+        let handled = match &after[..close] {
+            "b" => {
+                bold += 1;
+                true
+            }
+            "/b" => {
+                bold = bold.saturating_sub(1);
+                true
+            }
+            "/color" => {
+                colors.pop();
+                true
+            }
+            tag => {
+                if let Some(code) = tag.strip_prefix("color=") {
+                    Srgba::hex(code)
+                        .map(|color| colors.push(color.to_linear().to_array()))
+                        .is_some()
+                } else if let Some(name) = tag.strip_prefix("icon=") {
+                    spans.push(MarkupSpan::Icon(name));
+                    true
+                } else {
+                    false
+                }
+            }
+        };
+
+        if handled {
+            rest = &after[close + 1..];
+        } else {
+            push_text(&mut spans, &rest[open..=open], &colors, bold > 0);
+            rest = after;
+        }
+    }
+    push_text(&mut spans, rest, &colors, bold > 0);
+
+    spans
+}
+
+// Where an icon is in a section, found again after the section is laid out
+struct IconPlacement {
+    // The index of the invisible text that holds the place of the icon
+    text_index: usize,
+    sprite: SpriteHandle,
+    width: f32,
+}
+
+/// Outline drawn around text to keep it readable on any background
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextOutline {
+    pub color: [f32; 4],
+    /// Thickness of the outline in pixels
+    pub thickness: f32,
+}
+
+/// How a piece of text should look
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextStyle {
+    pub font: FontHandle,
+    /// The font of text in `[b]` tags of rich text, `None` draws it with the regular font
+    pub bold_font: Option<FontHandle>,
+    /// Size of the text in pixels
+    pub size: f32,
+    pub color: [f32; 4],
+    pub outline: Option<TextOutline>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font: FontHandle::default(),
+            bold_font: None,
+            size: DEFAULT_TEXT_SIZE,
+            color: [1.0, 1.0, 1.0, 1.0],
+            outline: None,
+        }
+    }
+}
+
+impl TextStyle {
+    pub fn with_font(mut self, font: FontHandle) -> Self {
+        self.font = font;
+        self
+    }
+
+    pub fn with_bold_font(mut self, font: FontHandle) -> Self {
+        self.bold_font = Some(font);
+        self
+    }
+
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_color<C>(mut self, color: C) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        self.color = color.into().to_array();
+        self
+    }
+
+    pub fn with_outline<C>(mut self, color: C, thickness: f32) -> Self
+    where
+        C: Into<LinearRgba>,
+    {
+        self.outline = Some(TextOutline {
+            color: color.into().to_array(),
+            thickness,
+        });
+        self
+    }
+
+    /// Applies the font, size and color of the style to a piece of text
+    pub fn apply<'a>(&self, text: Text<'a>) -> Text<'a> {
+        text.with_font_id(self.font)
+            .with_scale(self.size)
+            .with_color(self.color)
+    }
+
+    /// The font that text is drawn with inside and outside of bold tags
+    pub(crate) fn span_font(&self, bold: bool) -> FontHandle {
+        match self.bold_font {
+            Some(bold_font) if bold => bold_font,
+            _ => self.font,
+        }
+    }
+
+    // Builds one section of the spans, the outline passes a color that replaces the colors
+    // of the markup
+    fn section<'a>(
+        &self,
+        spans: &[MarkupSpan<'a>],
+        position: (f32, f32),
+        layout: Layout<BuiltInLineBreaker>,
+        fonts: &FontSet,
+        color: Option<[f32; 4]>,
+    ) -> (Section<'a>, Vec<IconPlacement>) {
+        let mut section = Section::default()
+            .with_screen_position(position)
+            .with_layout(layout);
+        let mut icons = Vec::new();
+
+        for span in spans {
+            match *span {
+                MarkupSpan::Text {
+                    text,
+                    color: span_color,
+                    bold,
+                } => {
+                    let font = self.span_font(bold);
+                    let color = color.or(span_color).unwrap_or(self.color);
+
+                    // Characters the font does not have are drawn with its fallbacks
+                    for (run, font) in fonts.runs(text, font) {
+                        section = section.add_text(
+                            Text::new(run)
+                                .with_font_id(font)
+                                .with_scale(self.size)
+                                .with_color(color),
+                        );
+                    }
+                }
+                MarkupSpan::Icon(name) => {
+                    let Some((sprite, width)) = fonts.icon(name, self.size) else {
+                        continue;
+                    };
+                    if let Some((placeholder, scale)) =
+                        fonts.icon_placeholder(self.font, self.size, width)
+                    {
+                        icons.push(IconPlacement {
+                            text_index: section.text.len(),
+                            sprite,
+                            width,
+                        });
+                        section = section.add_text(
+                            Text::new(placeholder)
+                                .with_font_id(self.font)
+                                .with_scale(scale)
+                                .with_color([0.0; 4]),
+                        );
+                    }
+                }
+            }
+        }
+
+        (section, icons)
+    }
+
+    /// Creates the sections needed to draw the spans with this style
+    /// The outline sections come first so they are drawn below the text
+    pub(crate) fn sections<'a>(
+        &self,
+        spans: &[MarkupSpan<'a>],
+        position: (f32, f32),
+        layout: Layout<BuiltInLineBreaker>,
+        fonts: &FontSet,
+    ) -> Vec<Section<'a>> {
+        let mut sections = Vec::new();
+
+        if let Some(outline) = self.outline {
+            for (x, y) in OUTLINE_DIRECTIONS {
+                let position = (
+                    position.0 + x * outline.thickness,
+                    position.1 + y * outline.thickness,
+                );
+                let (section, _) =
+                    self.section(spans, position, layout, fonts, Some(outline.color));
+                sections.push(section);
+            }
+        }
+
+        sections.push(self.section(spans, position, layout, fonts, None).0);
+
+        sections
+    }
+
+    /// Lays out the spans to find where their icons are drawn
+    pub(crate) fn icons(
+        &self,
+        spans: &[MarkupSpan],
+        position: (f32, f32),
+        layout: Layout<BuiltInLineBreaker>,
+        fonts: &FontSet,
+    ) -> Vec<OverlaySprite> {
+        let (section, placements) = self.section(spans, position, layout, fonts, None);
+        if placements.is_empty() {
+            return Vec::new();
+        }
+        let Some(font) = fonts
+            .fonts
+            .get(self.font.0)
+            .map(|font| font.as_scaled(self.size))
+        else {
+            return Vec::new();
+        };
+
+        let glyphs =
+            layout.calculate_glyphs(fonts.fonts, &SectionGeometry::from(&section), &section.text);
+
+        // The icons are centered on the line the glyph is on
+        let line_height = font.ascent() - font.descent();
+        placements
+            .iter()
+            .filter_map(|placement| {
+                let glyph = glyphs
+                    .iter()
+                    .find(|glyph| glyph.section_index == placement.text_index)?;
+                let baseline = glyph.glyph.position;
+                let top = baseline.y - font.ascent() + (line_height - self.size) * 0.5;
+
+                Some(OverlaySprite::new(
+                    placement.sprite,
+                    (baseline.x, top),
+                    (placement.width, self.size),
+                ))
+            })
+            .collect()
+    }
+}
+
+/// A piece of text drawn in the overlay pass
+#[derive(Clone, Debug, PartialEq)]
+pub struct OverlayText {
+    pub text: String,
+    /// Position of the top left of the text in pixels
+    pub position: (f32, f32),
+    pub style: TextStyle,
+    /// Places the text relative to the screen instead of the raw position
+    pub layout: Option<UiLayout>,
+    /// Depth of the text in the scene, text with a depth is hidden behind the scene
+    pub depth: Option<f32>,
+    /// Reads the text as rich text with colors, bold, and icons, see `with_markup`
+    pub markup: bool,
+    /// Wraps and aligns the text inside of a box, the layout places the box
+    pub bounds: Option<TextBounds>,
+}
+
+impl OverlayText {
+    pub fn new(text: String, position: (f32, f32), style: TextStyle) -> Self {
+        Self {
+            text,
+            position,
+            style,
+            layout: None,
+            depth: None,
+            markup: false,
+            bounds: None,
+        }
+    }
+
+    pub fn with_layout(mut self, layout: Option<UiLayout>) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn with_depth(mut self, depth: Option<f32>) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Reads tags in the text, `[b]bold[/b]`, `[color=#ff0000]red[/color]`, and
+    /// `[icon=coin]` draw bold text, colored text, and inline icons. Icons are registered
+    /// with the renderer by name and are only drawn on text without a depth
+    pub fn with_markup(mut self, markup: bool) -> Self {
+        self.markup = markup;
+        self
+    }
+
+    pub fn with_bounds(mut self, bounds: Option<TextBounds>) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    // The spans of the text, plain text is one span
+    fn spans(&self) -> Vec<MarkupSpan<'_>> {
+        if self.markup {
+            parse_markup(&self.text)
+        } else {
+            vec![MarkupSpan::Text {
+                text: &self.text,
+                color: None,
+                bold: false,
+            }]
+        }
+    }
+
+    // Where the text is drawn and how it is aligned there
+    fn placement(&self, screen_size: (f32, f32)) -> ((f32, f32), Layout<BuiltInLineBreaker>) {
+        match self.layout {
+            Some(layout) => (layout.resolve(screen_size), layout.anchor.text_layout()),
+            None => (self.position, Layout::default()),
+        }
+    }
+
+    // Breaks the text into the lines of its bounds and finds the top left of the box
+    fn bounded(
+        &self,
+        bounds: &TextBounds,
+        screen_size: (f32, f32),
+        fonts: &FontSet,
+    ) -> (BoundedText<'_>, (f32, f32)) {
+        let text = BoundedText::new(&self.style, &self.spans(), bounds, screen_size, fonts);
+        let position = match self.layout {
+            Some(layout) => layout.resolve_rect(screen_size, text.size),
+            None => self.position,
+        };
+
+        (text, position)
+    }
+
+    /// Creates the sections to draw the text on a screen of the given size
+    pub(crate) fn sections(&self, screen_size: (f32, f32), fonts: &FontSet) -> Vec<Section<'_>> {
+        let mut sections = match &self.bounds {
+            Some(bounds) => {
+                let (text, position) = self.bounded(bounds, screen_size, fonts);
+                text.sections(position)
+            }
+            None => {
+                let (position, layout) = self.placement(screen_size);
+                self.style.sections(&self.spans(), position, layout, fonts)
+            }
+        };
+
+        if let Some(depth) = self.depth {
+            for section in sections.iter_mut() {
+                for text in section.text.iter_mut() {
+                    text.extra.z = depth;
+                }
+            }
+        }
+
+        sections
+    }
+
+    /// The sprites of the inline icons of the text on a screen of the given size
+    pub(crate) fn icons(&self, screen_size: (f32, f32), fonts: &FontSet) -> Vec<OverlaySprite> {
+        if !self.markup || self.depth.is_some() {
+            return Vec::new();
+        }
+
+        match &self.bounds {
+            Some(bounds) => {
+                let (text, position) = self.bounded(bounds, screen_size, fonts);
+                text.icons(position)
+            }
+            None => {
+                let (position, layout) = self.placement(screen_size);
+                self.style.icons(&self.spans(), position, layout, fonts)
+            }
+        }
+    }
+}
@@ -0,0 +1,663 @@
+use std::mem;
+
+use wgpu_text::glyph_brush::{ab_glyph::PxScale, Layout, Section, Text};
+
+use crate::{
+    sprite::{OverlaySprite, SpriteHandle},
+    text::{FontHandle, FontSet, MarkupSpan, TextStyle, OUTLINE_DIRECTIONS},
+};
+
+/// How the lines of bounded text are placed across the width of their box
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretches the spaces so every line but the last of a paragraph fills the width
+    Justify,
+}
+
+/// What happens to the lines of bounded text that do not fit in the height of the box
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextOverflow {
+    /// The lines are drawn below the box
+    #[default]
+    Visible,
+    /// The text is cut off at the edges of the box
+    Clip,
+    /// The last line that fits ends with an ellipsis, lines that are too wide without
+    /// wrapping end with one too
+    Ellipsis,
+    /// Only the lines that fit are drawn, starting from the scrolled line
+    Scroll,
+}
+
+/// A box that text is wrapped and aligned inside of. The size is recomputed from the
+/// screen every frame so the text wraps again when the window is resized
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextBounds {
+    /// Width and height of the box in pixels, an infinite height lets the text grow down
+    pub size: (f32, f32),
+    /// Added to the size as a fraction of the screen size
+    pub size_percent: (f32, f32),
+    /// Whether lines are broken between words to fit the width, otherwise lines only
+    /// break at newlines
+    pub wrap: bool,
+    pub align: TextAlign,
+    pub overflow: TextOverflow,
+    /// The first line that is drawn when the overflow scrolls, lines past the end are
+    /// clamped so `usize::MAX` shows the last lines
+    pub scroll: usize,
+}
+
+impl TextBounds {
+    /// Creates a box of the given size in pixels
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            size: (width, height),
+            size_percent: (0.0, 0.0),
+            wrap: true,
+            align: TextAlign::default(),
+            overflow: TextOverflow::default(),
+            scroll: 0,
+        }
+    }
+
+    /// Creates a box that wraps at a width and grows down with the text
+    pub fn with_max_width(width: f32) -> Self {
+        Self::new(width, f32::INFINITY)
+    }
+
+    /// Grows the box with the screen, like `(0.5, 0.0)` for half of the screen width
+    pub fn with_size_percent(mut self, size_percent: (f32, f32)) -> Self {
+        self.size_percent = size_percent;
+        self
+    }
+
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: TextOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn with_scroll(mut self, scroll: usize) -> Self {
+        self.scroll = scroll;
+        self
+    }
+
+    /// The width and height of the box in pixels on a screen of the given size
+    pub fn resolve(&self, screen_size: (f32, f32)) -> (f32, f32) {
+        (
+            (self.size.0 + self.size_percent.0 * screen_size.0).max(0.0),
+            (self.size.1 + self.size_percent.1 * screen_size.1).max(0.0),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum GlyphKind {
+    Character,
+    Space,
+    Newline,
+    Icon(SpriteHandle),
+}
+
+// A character of bounded text with the look of its span
+#[derive(Clone, Copy, Debug)]
+struct BoxGlyph<'a> {
+    // The text the character is in, neighbouring characters of the same text are drawn
+    // together
+    source: &'a str,
+    start: usize,
+    end: usize,
+    font: FontHandle,
+    scale: PxScale,
+    color: Option<[f32; 4]>,
+    advance: f32,
+    kind: GlyphKind,
+}
+
+impl BoxGlyph<'_> {
+    // Whether the glyph can be drawn in the same text as the one before it
+    fn continues(&self, previous: &BoxGlyph) -> bool {
+        std::ptr::eq(self.source, previous.source)
+            && self.start == previous.end
+            && self.font == previous.font
+            && self.scale == previous.scale
+            && self.color == previous.color
+    }
+}
+
+struct BoxLine<'a> {
+    glyphs: Vec<BoxGlyph<'a>>,
+    // The last line of a paragraph is not stretched when justifying
+    ends_paragraph: bool,
+}
+
+impl<'a> BoxLine<'a> {
+    fn new(mut glyphs: Vec<BoxGlyph<'a>>, ends_paragraph: bool) -> Self {
+        // Spaces at the end of a line would push it away from the right edge
+        while glyphs
+            .last()
+            .is_some_and(|glyph| glyph.kind == GlyphKind::Space)
+        {
+            glyphs.pop();
+        }
+
+        Self {
+            glyphs,
+            ends_paragraph,
+        }
+    }
+
+    fn width(&self) -> f32 {
+        self.glyphs.iter().map(|glyph| glyph.advance).sum()
+    }
+
+    // Drops characters from the end until the ellipsis fits after them
+    fn truncate(&mut self, width: f32, ellipsis: BoxGlyph<'a>) {
+        while !self.glyphs.is_empty() && self.width() + ellipsis.advance > width {
+            self.glyphs.pop();
+        }
+        while self
+            .glyphs
+            .last()
+            .is_some_and(|glyph| glyph.kind == GlyphKind::Space)
+        {
+            self.glyphs.pop();
+        }
+
+        let color = self.glyphs.last().and_then(|glyph| glyph.color);
+        self.glyphs.push(BoxGlyph { color, ..ellipsis });
+        self.ends_paragraph = true;
+    }
+}
+
+// Splits the spans into characters and measures them
+fn box_glyphs<'a>(
+    style: &TextStyle,
+    spans: &[MarkupSpan<'a>],
+    fonts: &FontSet,
+) -> Vec<BoxGlyph<'a>> {
+    let scale = PxScale::from(style.size);
+    let mut glyphs = Vec::new();
+
+    for span in spans {
+        match *span {
+            MarkupSpan::Text { text, color, bold } => {
+                for (run, font) in fonts.runs(text, style.span_font(bold)) {
+                    for (start, character) in run.char_indices() {
+                        let kind = match character {
+                            '\n' => GlyphKind::Newline,
+                            c if c.is_whitespace() => GlyphKind::Space,
+                            _ => GlyphKind::Character,
+                        };
+
+                        glyphs.push(BoxGlyph {
+                            source: run,
+                            start,
+                            end: start + character.len_utf8(),
+                            font,
+                            scale,
+                            color,
+                            advance: fonts.advance(font, character, style.size),
+                            kind,
+                        });
+                    }
+                }
+            }
+            MarkupSpan::Icon(name) => {
+                let Some((sprite, width)) = fonts.icon(name, style.size) else {
+                    continue;
+                };
+                if let Some((placeholder, scale)) =
+                    fonts.icon_placeholder(style.font, style.size, width)
+                {
+                    glyphs.push(BoxGlyph {
+                        source: placeholder,
+                        start: 0,
+                        end: placeholder.len(),
+                        font: style.font,
+                        scale,
+                        color: Some([0.0; 4]),
+                        advance: width,
+                        kind: GlyphKind::Icon(sprite),
+                    });
+                }
+            }
+        }
+    }
+
+    glyphs
+}
+
+// Breaks the characters into lines at newlines and, when wrapping, between the words that
+// do not fit in the width
+fn break_lines(glyphs: Vec<BoxGlyph>, width: f32, wrap: bool) -> Vec<BoxLine> {
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+    let mut line_width = 0.0;
+    // Where the line can be broken, after its last space
+    let mut break_at = None;
+
+    for glyph in glyphs {
+        match glyph.kind {
+            GlyphKind::Newline => {
+                lines.push(BoxLine::new(mem::take(&mut line), true));
+                line_width = 0.0;
+                break_at = None;
+                continue;
+            }
+            GlyphKind::Space => {
+                line.push(glyph);
+                line_width += glyph.advance;
+                break_at = Some(line.len());
+                continue;
+            }
+            _ => (),
+        }
+
+        if wrap && !line.is_empty() && line_width + glyph.advance > width {
+            // Words that are wider than the box are broken where they reach its edge
+            let rest = line.split_off(break_at.unwrap_or(line.len()));
+            lines.push(BoxLine::new(mem::replace(&mut line, rest), false));
+            line_width = line.iter().map(|glyph| glyph.advance).sum();
+            break_at = None;
+        }
+
+        line.push(glyph);
+        line_width += glyph.advance;
+    }
+    lines.push(BoxLine::new(line, true));
+
+    lines
+}
+
+/// Text that has been broken into the lines of its bounds, only the lines that are drawn
+/// are kept
+pub(crate) struct BoundedText<'a> {
+    style: TextStyle,
+    lines: Vec<BoxLine<'a>>,
+    line_height: f32,
+    align: TextAlign,
+    clip: bool,
+    /// The width and height of the box the text is drawn in
+    pub size: (f32, f32),
+}
+
+impl<'a> BoundedText<'a> {
+    pub fn new(
+        style: &TextStyle,
+        spans: &[MarkupSpan<'a>],
+        bounds: &TextBounds,
+        screen_size: (f32, f32),
+        fonts: &FontSet,
+    ) -> Self {
+        let (ellipsis, advance) = match fonts.advance(style.font, '\u{2026}', style.size) {
+            advance if advance > 0.0 => ("\u{2026}", advance),
+            _ => ("...", fonts.advance(style.font, '.', style.size) * 3.0),
+        };
+        let ellipsis = BoxGlyph {
+            source: ellipsis,
+            start: 0,
+            end: ellipsis.len(),
+            font: style.font,
+            scale: PxScale::from(style.size),
+            color: None,
+            advance,
+            kind: GlyphKind::Character,
+        };
+
+        Self::from_glyphs(
+            style,
+            box_glyphs(style, spans, fonts),
+            bounds,
+            screen_size,
+            fonts.line_height(style.font, style.size),
+            ellipsis,
+        )
+    }
+
+    // Lays out characters that have already been measured
+    fn from_glyphs(
+        style: &TextStyle,
+        glyphs: Vec<BoxGlyph<'a>>,
+        bounds: &TextBounds,
+        screen_size: (f32, f32),
+        line_height: f32,
+        ellipsis: BoxGlyph<'a>,
+    ) -> Self {
+        let (width, height) = bounds.resolve(screen_size);
+        let mut lines = break_lines(glyphs, width, bounds.wrap);
+
+        // The number of whole lines that fit in the height
+        let fitting = if height.is_finite() {
+            ((height / line_height).floor() as usize).max(1)
+        } else {
+            usize::MAX
+        };
+
+        match bounds.overflow {
+            TextOverflow::Visible => (),
+            // The line that is cut in half by the bottom is still drawn
+            TextOverflow::Clip => lines.truncate(fitting.saturating_add(1)),
+            TextOverflow::Ellipsis => {
+                let cut = lines.len() > fitting;
+                lines.truncate(fitting);
+                let last = lines.len() - 1;
+                for (index, line) in lines.iter_mut().enumerate() {
+                    if line.width() > width || (cut && index == last) {
+                        line.truncate(width, ellipsis);
+                    }
+                }
+            }
+            TextOverflow::Scroll => {
+                let first = bounds.scroll.min(lines.len().saturating_sub(fitting));
+                lines.drain(..first);
+                lines.truncate(fitting);
+            }
+        }
+
+        let size = (
+            if width.is_finite() {
+                width
+            } else {
+                lines.iter().map(BoxLine::width).fold(0.0, f32::max)
+            },
+            if height.is_finite() {
+                height
+            } else {
+                lines.len() as f32 * line_height
+            },
+        );
+
+        Self {
+            style: *style,
+            lines,
+            line_height,
+            align: bounds.align,
+            clip: bounds.overflow != TextOverflow::Visible,
+            size,
+        }
+    }
+
+    // Splits the lines into pieces that are drawn from a position, lines are one piece
+    // unless justifying moves the words apart
+    fn chunks(&self, position: (f32, f32)) -> Vec<((f32, f32), &[BoxGlyph<'a>])> {
+        let mut chunks = Vec::new();
+
+        for (index, line) in self.lines.iter().enumerate() {
+            let y = position.1 + index as f32 * self.line_height;
+            let free = (self.size.0 - line.width()).max(0.0);
+            let spaces = line
+                .glyphs
+                .iter()
+                .filter(|glyph| glyph.kind == GlyphKind::Space)
+                .count();
+
+            let (offset, stretch) = match self.align {
+                TextAlign::Left => (0.0, 0.0),
+                TextAlign::Center => (free * 0.5, 0.0),
+                TextAlign::Right => (free, 0.0),
+                TextAlign::Justify if !line.ends_paragraph && spaces > 0 => {
+                    (0.0, free / spaces as f32)
+                }
+                TextAlign::Justify => (0.0, 0.0),
+            };
+
+            let mut x = position.0 + offset;
+            let (mut start, mut start_x) = (0, x);
+            for (glyph_index, glyph) in line.glyphs.iter().enumerate() {
+                x += glyph.advance;
+                if stretch > 0.0 && glyph.kind == GlyphKind::Space {
+                    chunks.push(((start_x, y), &line.glyphs[start..=glyph_index]));
+                    x += stretch;
+                    (start, start_x) = (glyph_index + 1, x);
+                }
+            }
+            if start < line.glyphs.len() {
+                chunks.push(((start_x, y), &line.glyphs[start..]));
+            }
+        }
+
+        chunks
+    }
+
+    // Builds a section for every chunk, the outline passes a color that replaces the
+    // colors of the markup
+    fn chunk_sections(&self, position: (f32, f32), color: Option<[f32; 4]>) -> Vec<Section<'a>> {
+        let right = position.0 + self.size.0;
+        let bottom = position.1 + self.size.1;
+
+        self.chunks(position)
+            .into_iter()
+            .map(|((x, y), glyphs)| {
+                let mut section = Section::default()
+                    .with_screen_position((x, y))
+                    .with_layout(Layout::default_single_line());
+                if self.clip {
+                    section = section.with_bounds(((right - x).max(0.0), (bottom - y).max(0.0)));
+                }
+
+                // Neighbouring characters with the same look are drawn as one text
+                let mut start = 0;
+                for end in 1..=glyphs.len() {
+                    if end < glyphs.len() && glyphs[end].continues(&glyphs[end - 1]) {
+                        continue;
+                    }
+
+                    let (first, last) = (&glyphs[start], &glyphs[end - 1]);
+                    let color = match (first.kind, color) {
+                        (GlyphKind::Icon(_), _) => [0.0; 4],
+                        (_, Some(color)) => color,
+                        _ => first.color.unwrap_or(self.style.color),
+                    };
+                    section = section.add_text(
+                        Text::new(&first.source[first.start..last.end])
+                            .with_font_id(first.font)
+                            .with_scale(first.scale)
+                            .with_color(color),
+                    );
+                    start = end;
+                }
+
+                section
+            })
+            .collect()
+    }
+
+    /// Creates the sections to draw the text with the top left of its box at a position
+    /// The outline sections come first so they are drawn below the text
+    pub fn sections(&self, position: (f32, f32)) -> Vec<Section<'a>> {
+        let mut sections = Vec::new();
+
+        if let Some(outline) = self.style.outline {
+            for (x, y) in OUTLINE_DIRECTIONS {
+                sections.append(&mut self.chunk_sections(
+                    (
+                        position.0 + x * outline.thickness,
+                        position.1 + y * outline.thickness,
+                    ),
+                    Some(outline.color),
+                ));
+            }
+        }
+
+        sections.append(&mut self.chunk_sections(position, None));
+
+        sections
+    }
+
+    /// The sprites of the inline icons with the top left of the box at a position
+    pub fn icons(&self, position: (f32, f32)) -> Vec<OverlaySprite> {
+        let bottom = position.1 + self.size.1;
+        let size = self.style.size;
+        let mut icons = Vec::new();
+
+        for ((mut x, y), glyphs) in self.chunks(position) {
+            for glyph in glyphs {
+                // Icons are centered on their line
+                let top = y + (self.line_height - size) * 0.5;
+                if let GlyphKind::Icon(sprite) = glyph.kind {
+                    if !self.clip || top + size <= bottom {
+                        icons.push(OverlaySprite::new(sprite, (x, top), (glyph.advance, size)));
+                    }
+                }
+                x += glyph.advance;
+            }
+        }
+
+        icons
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADVANCE: f32 = 10.0;
+    const LINE_HEIGHT: f32 = 20.0;
+
+    // Measures every character of the text as the same width
+    fn measured(text: &str) -> Vec<BoxGlyph<'_>> {
+        text.char_indices()
+            .map(|(start, character)| BoxGlyph {
+                source: text,
+                start,
+                end: start + character.len_utf8(),
+                font: FontHandle::default(),
+                scale: PxScale::from(16.0),
+                color: None,
+                advance: ADVANCE,
+                kind: match character {
+                    '\n' => GlyphKind::Newline,
+                    ' ' => GlyphKind::Space,
+                    _ => GlyphKind::Character,
+                },
+            })
+            .collect()
+    }
+
+    fn ellipsis() -> BoxGlyph<'static> {
+        BoxGlyph {
+            end: 3,
+            advance: ADVANCE * 3.0,
+            ..measured("...")[0]
+        }
+    }
+
+    fn glyph_text(glyphs: &[BoxGlyph]) -> String {
+        glyphs
+            .iter()
+            .map(|glyph| &glyph.source[glyph.start..glyph.end])
+            .collect()
+    }
+
+    fn line_texts(lines: &[BoxLine]) -> Vec<String> {
+        lines.iter().map(|line| glyph_text(&line.glyphs)).collect()
+    }
+
+    fn bounded(text: &str, bounds: TextBounds) -> BoundedText<'_> {
+        BoundedText::from_glyphs(
+            &TextStyle::default(),
+            measured(text),
+            &bounds,
+            (0.0, 0.0),
+            LINE_HEIGHT,
+            ellipsis(),
+        )
+    }
+
+    #[test]
+    fn test_wrap_between_words() {
+        let lines = break_lines(measured("hello big world"), 100.0, true);
+
+        assert_eq!(line_texts(&lines), ["hello big", "world"]);
+        assert!(!lines[0].ends_paragraph);
+        assert!(lines[1].ends_paragraph);
+        assert_eq!(lines[0].width(), 90.0);
+    }
+
+    #[test]
+    fn test_newlines_without_wrap() {
+        let lines = break_lines(measured("hello big world\nagain"), 50.0, false);
+
+        assert_eq!(line_texts(&lines), ["hello big world", "again"]);
+        assert!(lines.iter().all(|line| line.ends_paragraph));
+    }
+
+    #[test]
+    fn test_split_long_word() {
+        let lines = break_lines(measured("ab abcdefgh"), 30.0, true);
+
+        assert_eq!(line_texts(&lines), ["ab", "abc", "def", "gh"]);
+    }
+
+    #[test]
+    fn test_justify_stretch() {
+        let text = bounded(
+            "aa bb cc dd",
+            TextBounds::with_max_width(100.0).with_align(TextAlign::Justify),
+        );
+        let chunks = text
+            .chunks((0.0, 0.0))
+            .into_iter()
+            .map(|(position, glyphs)| (position, glyph_text(glyphs)))
+            .collect::<Vec<_>>();
+
+        // The 20 free pixels of the first line are shared by its two spaces, the last
+        // line of the paragraph is left alone
+        assert_eq!(
+            chunks,
+            [
+                ((0.0, 0.0), "aa ".to_string()),
+                ((40.0, 0.0), "bb ".to_string()),
+                ((80.0, 0.0), "cc".to_string()),
+                ((0.0, LINE_HEIGHT), "dd".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ellipsis_truncation() {
+        let text = bounded(
+            "aaaa bbbb cccc",
+            TextBounds::new(50.0, 2.0 * LINE_HEIGHT).with_overflow(TextOverflow::Ellipsis),
+        );
+        assert_eq!(line_texts(&text.lines), ["aaaa", "bb..."]);
+        assert!(text.lines.iter().all(|line| line.width() <= 50.0));
+
+        let text = bounded(
+            "abcdefgh",
+            TextBounds::with_max_width(50.0)
+                .with_wrap(false)
+                .with_overflow(TextOverflow::Ellipsis),
+        );
+        assert_eq!(line_texts(&text.lines), ["ab..."]);
+        assert_eq!(text.size, (50.0, LINE_HEIGHT));
+    }
+
+    #[test]
+    fn test_scroll_clamp() {
+        let bounds = TextBounds::new(50.0, 2.0 * LINE_HEIGHT).with_overflow(TextOverflow::Scroll);
+
+        let text = bounded("a\nb\nc\nd", bounds.with_scroll(1));
+        assert_eq!(line_texts(&text.lines), ["b", "c"]);
+
+        let text = bounded("a\nb\nc\nd", bounds.with_scroll(usize::MAX));
+        assert_eq!(line_texts(&text.lines), ["c", "d"]);
+
+        let text = bounded("a", bounds.with_scroll(3));
+        assert_eq!(line_texts(&text.lines), ["a"]);
+    }
+}
@@ -0,0 +1,182 @@
+use wgpu_text::glyph_brush::{BuiltInLineBreaker, HorizontalAlign, Layout, VerticalAlign};
+
+/// The point of the screen that a ui element is attached to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    TopCenter,
+    TopRight,
+    CenterLeft,
+    Center,
+    CenterRight,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+}
+
+impl Anchor {
+    /// The position of the anchor as a fraction of the screen or element size
+    pub fn factors(&self) -> (f32, f32) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::TopCenter => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::CenterLeft => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::CenterRight => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::BottomCenter => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+
+    /// The text layout that places the text on the same side of the position as the anchor
+    pub fn text_layout(&self) -> Layout<BuiltInLineBreaker> {
+        let (x, y) = self.factors();
+
+        let h_align = if x == 0.0 {
+            HorizontalAlign::Left
+        } else if x == 0.5 {
+            HorizontalAlign::Center
+        } else {
+            HorizontalAlign::Right
+        };
+
+        let v_align = if y == 0.0 {
+            VerticalAlign::Top
+        } else if y == 0.5 {
+            VerticalAlign::Center
+        } else {
+            VerticalAlign::Bottom
+        };
+
+        Layout::default().h_align(h_align).v_align(v_align)
+    }
+}
+
+/// Places a ui element relative to an anchor of the screen so it stays in place when resizing
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UiLayout {
+    pub anchor: Anchor,
+    /// Offset from the anchor as a fraction of the screen size
+    pub offset_percent: (f32, f32),
+    /// Offset from the anchor in pixels
+    pub offset_pixels: (f32, f32),
+}
+
+impl UiLayout {
+    pub fn new(anchor: Anchor) -> Self {
+        Self {
+            anchor,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a layout that centers an element on a position in pixels
+    pub fn centered_at(position: (f32, f32)) -> Self {
+        Self {
+            anchor: Anchor::Center,
+            offset_percent: (-0.5, -0.5),
+            offset_pixels: position,
+        }
+    }
+
+    pub fn with_offset_percent(mut self, offset_percent: (f32, f32)) -> Self {
+        self.offset_percent = offset_percent;
+        self
+    }
+
+    pub fn with_offset_pixels(mut self, offset_pixels: (f32, f32)) -> Self {
+        self.offset_pixels = offset_pixels;
+        self
+    }
+
+    /// Finds the position on the screen of the layout
+    ///
+    /// # Arguments
+    ///
+    /// * `screen_size` - The width and height of the screen in pixels
+    ///
+    /// # Returns
+    ///
+    /// The position in pixels of the anchor point of the element
+    pub fn resolve(&self, screen_size: (f32, f32)) -> (f32, f32) {
+        let (anchor_x, anchor_y) = self.anchor.factors();
+
+        (
+            anchor_x * screen_size.0 + self.offset_percent.0 * screen_size.0 + self.offset_pixels.0,
+            anchor_y * screen_size.1 + self.offset_percent.1 * screen_size.1 + self.offset_pixels.1,
+        )
+    }
+
+    /// Finds the top left corner of an element of the given size placed with the layout
+    /// The element is aligned to the same side as the anchor
+    pub fn resolve_rect(&self, screen_size: (f32, f32), element_size: (f32, f32)) -> (f32, f32) {
+        let (x, y) = self.resolve(screen_size);
+        let (anchor_x, anchor_y) = self.anchor.factors();
+
+        (x - anchor_x * element_size.0, y - anchor_y * element_size.1)
+    }
+
+    /// Checks if a point on the screen is inside an element placed with the layout
+    pub fn contains(
+        &self,
+        screen_size: (f32, f32),
+        element_size: (f32, f32),
+        point: (f32, f32),
+    ) -> bool {
+        let (x, y) = self.resolve_rect(screen_size, element_size);
+
+        point.0 >= x
+            && point.0 <= x + element_size.0
+            && point.1 >= y
+            && point.1 <= y + element_size.1
+    }
+
+    /// Creates a layout for an element placed inside of an element placed with this layout
+    ///
+    /// # Arguments
+    ///
+    /// * `element_size` - The size of the outer element in pixels
+    /// * `offset` - Offset in pixels from the top left of the outer element to the inner element
+    /// * `inner_size` - The size of the inner element in pixels
+    ///
+    /// # Returns
+    ///
+    /// A layout with the same anchor that places the inner element
+    pub fn inner(
+        &self,
+        element_size: (f32, f32),
+        offset: (f32, f32),
+        inner_size: (f32, f32),
+    ) -> Self {
+        let (anchor_x, anchor_y) = self.anchor.factors();
+
+        Self {
+            offset_pixels: (
+                self.offset_pixels.0 + anchor_x * (inner_size.0 - element_size.0) + offset.0,
+                self.offset_pixels.1 + anchor_y * (inner_size.1 - element_size.1) + offset.1,
+            ),
+            ..*self
+        }
+    }
+
+    /// Creates a layout anchored to the center of an element placed with this layout
+    /// Used to center text inside of ui elements
+    pub fn centered_in(&self, element_size: (f32, f32)) -> Self {
+        let (anchor_x, anchor_y) = self.anchor.factors();
+
+        Self {
+            anchor: Anchor::Center,
+            offset_percent: (
+                self.offset_percent.0 + anchor_x - 0.5,
+                self.offset_percent.1 + anchor_y - 0.5,
+            ),
+            offset_pixels: (
+                self.offset_pixels.0 + (0.5 - anchor_x) * element_size.0,
+                self.offset_pixels.1 + (0.5 - anchor_y) * element_size.1,
+            ),
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::num::NonZeroU64;
+
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBinding, BufferBindingType,
+    BufferDescriptor, BufferUsages, Device, Queue, ShaderStages,
+};
+
+/// The largest uniform that can be pushed into the ring
+pub const MAX_RING_UNIFORM_SIZE: u64 = 256;
+
+// Number of uniforms the ring has room for when it is created, it grows when a frame
+// pushes more
+const INITIAL_CAPACITY: u64 = 64;
+
+/// A buffer that the small uniforms of a frame are packed into, every uniform is bound
+/// with a dynamic offset into the same bind group instead of owning a buffer and a bind
+/// group of its own. The uniforms are collected on the cpu and written with a single
+/// upload every frame
+pub struct UniformRing {
+    buffer: Buffer,
+    bind_group: BindGroup,
+    // Distance between uniforms so every offset is aligned for the device
+    stride: u64,
+    capacity: u64,
+    // The uniforms pushed this frame
+    staging: Vec<u8>,
+}
+
+impl UniformRing {
+    pub fn new(device: &Device) -> Self {
+        let stride = MAX_RING_UNIFORM_SIZE
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment as u64);
+        let (buffer, bind_group) = Self::create_buffer(device, stride * INITIAL_CAPACITY);
+
+        Self {
+            buffer,
+            bind_group,
+            stride,
+            capacity: INITIAL_CAPACITY,
+            staging: Vec::new(),
+        }
+    }
+
+    /// The layout of the ring bind group, pipelines that read uniforms from the ring
+    /// use it for the group they bind the ring to
+    pub fn get_layout(device: &Device) -> BindGroupLayout {
+        device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Uniform Ring Bind Group Layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    }
+
+    fn create_buffer(device: &Device, size: u64) -> (Buffer, BindGroup) {
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Uniform Ring Buffer"),
+            size,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Uniform Ring Bind Group"),
+            layout: &Self::get_layout(device),
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::Buffer(BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: NonZeroU64::new(MAX_RING_UNIFORM_SIZE),
+                }),
+            }],
+        });
+
+        (buffer, bind_group)
+    }
+
+    /// Drops the uniforms of the last frame, called before anything is pushed for the
+    /// next one
+    pub fn begin_frame(&mut self) {
+        self.staging.clear();
+    }
+
+    /// Adds a uniform for this frame
+    ///
+    /// # Arguments
+    ///
+    /// * `uniform` - The data to bind, at most `MAX_RING_UNIFORM_SIZE` bytes
+    ///
+    /// # Returns
+    ///
+    /// The dynamic offset to bind the ring bind group with to read the uniform
+    pub fn push<T: bytemuck::Pod>(&mut self, uniform: &T) -> u32 {
+        let bytes = bytemuck::bytes_of(uniform);
+        assert!(
+            bytes.len() as u64 <= MAX_RING_UNIFORM_SIZE,
+            "Uniforms in the ring can be at most {MAX_RING_UNIFORM_SIZE} bytes"
+        );
+
+        let offset = self.staging.len();
+        self.staging.extend_from_slice(bytes);
+        self.staging.resize(offset + self.stride as usize, 0);
+        offset as u32
+    }
+
+    /// Number of uniforms pushed this frame
+    pub fn len(&self) -> usize {
+        self.staging.len() / self.stride as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.staging.is_empty()
+    }
+
+    /// Writes the uniforms of this frame to the gpu, the buffer is recreated at double
+    /// the size when they do not fit so the bind group has to be read after this
+    pub fn upload(&mut self, device: &Device, queue: &Queue) {
+        if self.staging.is_empty() {
+            return;
+        }
+
+        let needed = self.len() as u64;
+        if needed > self.capacity {
+            self.capacity = needed.next_power_of_two();
+            (self.buffer, self.bind_group) =
+                Self::create_buffer(device, self.stride * self.capacity);
+        }
+
+        queue.write_buffer(&self.buffer, 0, &self.staging);
+    }
+
+    pub fn get_bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+}